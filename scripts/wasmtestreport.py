@@ -24,6 +24,7 @@ import shutil
 import logging
 import tempfile
 import sys
+import shlex
 import time
 
 # Configure logger
@@ -56,6 +57,7 @@ TESTFILES_DST = LINDFS_ROOT / "testfiles"
 DETERMINISTIC_PARENT_NAME = "deterministic"
 FAIL_PARENT_NAME = "fail"
 EXPECTED_DIRECTORY = Path("./expected")
+RUNFLAGS_DIRECTORY = Path("./runflags")
 SKIP_TESTS_FILE = "skip_test_cases.txt"
 GLOBAL_COMPILE_FLAGS = []
 DIR_FLAGS = []
@@ -439,6 +441,26 @@ def get_expected_output(source_file):
     return success, output, f"Native execution: {output}" if not success else None, error_type, timing_info
 
 
+# ----------------------------------------------------------------------
+# Function: get_run_flags
+#
+# Purpose:
+#   Get the extra lind-boot options a test needs (e.g. `--tmpfs /tmp`)
+#
+# Variables:
+# - Input: source_file - path to the .c file
+# - Output: list of options read from runflags/<stem>.flags, empty if there is none
+# ----------------------------------------------------------------------
+def get_run_flags(source_file):
+    """Get extra lind-boot options from runflags/<stem>.flags"""
+    source_file = Path(source_file)
+    flags_file = source_file.parent / RUNFLAGS_DIRECTORY / f"{source_file.stem}.flags"
+    if not flags_file.is_file():
+        return []
+    with open(flags_file, 'r') as f:
+        return shlex.split(f.read(), comments=True)
+
+
 # ----------------------------------------------------------------------
 # Function: compile_c_to_wasm
 #
@@ -495,6 +517,7 @@ def compile_c_to_wasm(source_file, allow_precompiled=False):
 # - Input:
 #    wasm_file (Path): path to the .wasm 
 #    timeout_sec (int): time limit in seconds for the run
+#    run_flags (list): lind-boot options passed before the wasm file
 # - Output:
 #   A tuple (returncode, output_string). Returncode can be an integer,
 #   "timeout" for timeouts, or "unknown_error" for exceptions.
@@ -507,9 +530,9 @@ def compile_c_to_wasm(source_file, allow_precompiled=False):
 #   Since the script outputs the command being run, we ignore 
 #   the first line in stdout by the script which is the command itself
 # ----------------------------------------------------------------------
-def run_compiled_wasm(wasm_file, timeout_sec=DEFAULT_TIMEOUT):
+def run_compiled_wasm(wasm_file, timeout_sec=DEFAULT_TIMEOUT, run_flags=()):
     wasm_file = Path(wasm_file)
-    run_cmd = [os.path.join(LIND_TOOL_PATH, "lind_run"), wasm_file.name]
+    run_cmd = [os.path.join(LIND_TOOL_PATH, "lind_run"), *run_flags, wasm_file.name]
     
     logger.debug(f"Running command: {' '.join(map(str, run_cmd))}") 
     if os.path.isfile(os.path.join(LIND_TOOL_PATH, "lind_run")):
@@ -570,7 +593,7 @@ def test_single_file_unified(source_file, result, timeout_sec=DEFAULT_TIMEOUT, t
             return
         
         try:
-            wasm_retcode, wasm_output, wasm_run_time = run_compiled_wasm(wasm_file, timeout_sec)
+            wasm_retcode, wasm_output, wasm_run_time = run_compiled_wasm(wasm_file, timeout_sec, get_run_flags(source_file))
             wasm_timing["wasm_run_time_sec"] = wasm_run_time
             combined_timing = merge_timing_info(native_timing, wasm_timing)
             
@@ -629,7 +652,7 @@ def test_single_file_unified(source_file, result, timeout_sec=DEFAULT_TIMEOUT, t
         return
     
     try:
-        retcode, wasm_output, wasm_run_time = run_compiled_wasm(wasm_file, timeout_sec)
+        retcode, wasm_output, wasm_run_time = run_compiled_wasm(wasm_file, timeout_sec, get_run_flags(source_file))
         timing_info["wasm_run_time_sec"] = wasm_run_time
         
         # Handle WASM execution result
@@ -1339,6 +1362,13 @@ def setup_test_file_in_artifacts(original_source, artifacts_root):
         expected_dir_dst = dest_dir / EXPECTED_DIRECTORY
         if not expected_dir_dst.exists():
             shutil.copytree(expected_dir_src, expected_dir_dst)
+
+    # Copy lind-boot run flags directory if present
+    runflags_dir_src = original_source.parent / RUNFLAGS_DIRECTORY
+    if runflags_dir_src.is_dir():
+        runflags_dir_dst = dest_dir / RUNFLAGS_DIRECTORY
+        if not runflags_dir_dst.exists():
+            shutil.copytree(runflags_dir_src, runflags_dir_dst)
             
     return dest_source
 
@@ -1,23 +1,72 @@
 //! This file contains all the implementation related to Cage structure. Including structure
 //! definitions, a global variables that handles cage management, and cage initialization and
 //! finialization required by wasmtime
+use crate::cputime::{CpuAccount, CpuTimes};
 use crate::memory::vmmap::*;
-use crate::timer::*;
+use crate::timer::CageTimers;
 use dashmap::DashMap;
 pub use once_cell::sync::Lazy;
 /// Uses spinlocks first (for short waits) and parks threads when blocking to reduce kernel
 /// interaction and increases efficiency.
 pub use parking_lot::{Mutex, RwLock};
 pub use std::path::{Path, PathBuf};
-pub use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+pub use std::sync::atomic::{AtomicI32, AtomicI64, AtomicU64, Ordering};
 pub use std::sync::Arc;
 use sysdefs::constants::lind_platform_const::MAX_CAGEID;
-use sysdefs::data::fs_struct::SigactionStruct;
+use sysdefs::constants::sys_const::SS_DISABLE;
+use sysdefs::data::fs_struct::{SigactionStruct, StackStruct};
+use sysdefs::data::sys_struct::UtsNameStruct;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Zombie {
     pub cageid: u64,
+    // Wait status reported to the parent, in the encoding of Linux (exit code or signal)
     pub exit_code: i32,
+    // Peak resident set size of the exited cage in kilobytes, reported through
+    // the `ru_maxrss` field of wait4()
+    pub maxrss: i64,
+    // User and system time of the exited cage and of the children it waited for, added to the
+    // parent's children time once it is waited for
+    pub cpu: CpuTimes,
+}
+
+// Signal state of one thread of a cage
+#[derive(Debug)]
+pub struct ThreadSignals {
+    // address of the epoch of the wasm thread
+    pub epoch: RwLock<*mut u64>,
+    // sigset is an atomic signal set representing the signals currently blocked for the thread.
+    // Interacts with sigprocmask_syscall() to block / unblock / replace the signal mask.
+    pub sigset: AtomicU64,
+    // saved_sigset is the mask to go back to once the signal that interrupted a ppoll(),
+    // pselect6() or epoll_pwait() has been handled. Those calls wait with a mask of their own,
+    // which has to stay in place until the handler of the signal it let through has run.
+    pub saved_sigset: Mutex<Option<u64>>,
+    // pending_signals are the signals sent to this thread with tgkill() / tkill(), which no
+    // other thread may handle
+    pub pending_signals: RwLock<Vec<i32>>,
+    // altstack is the alternate signal stack of the thread, set with sigaltstack(). Handlers
+    // installed with SA_ONSTACK run on it.
+    pub altstack: Mutex<StackStruct>,
+}
+
+// the alternate signal stack of a thread that has none
+pub const ALTSTACK_DISABLED: StackStruct = StackStruct {
+    ss_sp: 0,
+    ss_flags: SS_DISABLE,
+    ss_size: 0,
+};
+
+impl ThreadSignals {
+    pub fn new(epoch: *mut u64, sigset: u64, altstack: StackStruct) -> Self {
+        Self {
+            epoch: RwLock::new(epoch),
+            sigset: AtomicU64::new(sigset),
+            saved_sigset: Mutex::new(None),
+            pending_signals: RwLock::new(vec![]),
+            altstack: Mutex::new(altstack),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -39,26 +88,28 @@ pub struct Cage {
     // defines how the cage should handle a specific signal. Interacts with sigaction_syscall() to register or
     // retrieve the handler for a specific signal.
     pub signalhandler: DashMap<i32, SigactionStruct>,
-    // sigset is an atomic signal sets representing the signals
-    // currently blocked for the cage. Interacts with sigprocmask_syscall() to
-    // block / unblock / replace the signal mask for a the cage.
-    pub sigset: AtomicU64,
-    // pending_signals are signals that are pending to be handled
+    // pending_signals are the process-directed signals that are pending to be handled. Any
+    // thread of the cage that does not block one of them may handle it.
     pub pending_signals: RwLock<Vec<i32>>,
-    // epoch_handler is a hash map where key is the thread id of the cage, and the value is the epoch
-    // address of the wasm thread. The epoch is a u64 value that guest thread is frequently checking for
-    // and just to host once the value is changed
-    pub epoch_handler: DashMap<i32, RwLock<*mut u64>>,
-    // The kernel thread id of the main thread of current cage, used because when we want to send signals,
-    // we want to send to the main thread
+    // epoch_handler is a hash map where key is the thread id of the cage, and the value is the
+    // signal state of the thread: the epoch address of the wasm thread, its signal mask and the
+    // signals sent to it alone. The epoch is a u64 value that guest thread is frequently checking
+    // for and just to host once the value is changed
+    pub epoch_handler: DashMap<i32, ThreadSignals>,
+    // The kernel thread id of the main thread of current cage. Process-directed signals are handed
+    // to it first, unless it blocks them
     pub main_threadid: RwLock<i32>,
-    // The interval_timer can serve as a source for triggering signals and works together with signalhandler
-    // and sigset to manage and handle signals. The design of the interval_timer supports periodic triggering,
-    // simulating operations in Linux that need to run at regular intervals. It assists in implementing setitimer()
-    // in RawPOSIX, and by triggering lind_kill_from_id when the interval_timer expires
-    // (implemented in src/interface/timer.rs), it facilitates the implementation of signal handling in rawposix
-    // for the corresponding Cage.
-    pub interval_timer: IntervalTimer,
+    // timers are the interval timers (setitimer(), alarm()) and the POSIX timers (timer_create()) of
+    // the cage, which raise their signals in it when they expire. They are served by the timer
+    // thread of `signal::timer`.
+    pub timers: CageTimers,
+    // cpu accounts the CPU time the threads of the cage use, which ITIMER_VIRTUAL, ITIMER_PROF and
+    // CPU-time clocks count
+    pub cpu: CpuAccount,
+    // realtime_offset is the time-namespace offset of the cage's CLOCK_REALTIME from the host's,
+    // in nanoseconds. Only a grate interposing on clock_settime() for the cage can move it, and
+    // fork() hands it to the child.
+    pub realtime_offset: AtomicI64,
     // The zombies field in the Cage struct is used to manage information about child cages that have
     // exited, but whose exit status has not yet been retrieved by their parent using wait() / waitpid().
     // When a cage exits, shared memory segments are detached, file descriptors are removed from fdtable,
@@ -73,6 +124,11 @@ pub struct Cage {
     pub child_num: AtomicU64,
     // vmmap represents the virtual memory mapping for this cage. More details on `memory::vmmap`
     pub vmmap: RwLock<Vmmap>,
+    // uts is the system identity the cage sees through uname(), gethostname() and
+    // /proc/sys/kernel. Every cage has a copy of its own: fork() hands the parent's to the child,
+    // and sethostname() / setdomainname() only change the calling cage (and the children it
+    // forks afterwards), like a process that unshared its UTS namespace.
+    pub uts: RwLock<UtsNameStruct>,
 }
 
 /// We achieve an O(1) complexity for our cage map implementation through the following three approaches:
@@ -152,6 +208,25 @@ pub fn cagetable_clear() -> Vec<usize> {
     exitvec
 }
 
+/// Returns the ids of all cages currently present in the cage table.
+///
+/// This takes a snapshot: cages may be added or removed concurrently, so callers must
+/// still handle `get_cage` returning `None` for an id in the result.
+#[allow(static_mut_refs)]
+pub fn cagetable_ids() -> Vec<u64> {
+    let mut ids = Vec::new();
+
+    unsafe {
+        for (cageid, cage) in CAGE_MAP.iter().enumerate() {
+            if cage.is_some() {
+                ids.push(cageid as u64);
+            }
+        }
+    }
+
+    ids
+}
+
 /// Global cage ID allocator shared across all cages and subsystems.
 ///
 /// This allocator exists because cage IDs cannot be derived from the
@@ -187,6 +262,7 @@ pub fn alloc_cage_id() -> Option<u64> {
 
 mod tests {
     use super::*;
+    use sysdefs::constants::sys_const::UTSNAME_LENGTH;
 
     #[test]
     fn test_get_cage_out_of_range() {
@@ -217,14 +293,23 @@ mod tests {
             cwd: RwLock::new(Arc::new(PathBuf::from("/"))),
             rev_shm: Mutex::new(Vec::new()),
             signalhandler: DashMap::new(),
-            sigset: AtomicU64::new(0),
             pending_signals: RwLock::new(vec![]),
             epoch_handler: DashMap::new(),
             main_threadid: RwLock::new(0),
-            interval_timer: crate::timer::IntervalTimer::new(2),
+            timers: crate::timer::CageTimers::new(2),
+            cpu: crate::cputime::CpuAccount::default(),
+            realtime_offset: AtomicI64::new(0),
             zombies: RwLock::new(vec![]),
             child_num: AtomicU64::new(0),
             vmmap: RwLock::new(crate::memory::vmmap::Vmmap::new()),
+            uts: RwLock::new(UtsNameStruct {
+                sysname: [0; UTSNAME_LENGTH],
+                nodename: [0; UTSNAME_LENGTH],
+                release: [0; UTSNAME_LENGTH],
+                version: [0; UTSNAME_LENGTH],
+                machine: [0; UTSNAME_LENGTH],
+                domainname: [0; UTSNAME_LENGTH],
+            }),
         };
 
         add_cage(2, test_cage);
@@ -0,0 +1,294 @@
+//! Deterministic execution
+//!
+//! With `lind-boot --deterministic`, the sources of nondeterminism a cage can observe through
+//! its syscalls are replaced, so that the same binary run on the same input makes the same
+//! syscalls with the same results:
+//!
+//! - Time comes from a virtual clock instead of the host's. It stands still while a cage
+//!   computes, and moves forward by a fixed `tick` on every syscall, in place of the
+//!   instructions run since the last one, and by the full length of every sleep, which returns
+//!   at once. The realtime clock reads `REALTIME_START` plus the monotonic clock.
+//! - The CPU time of a cage is the ticks of its own syscalls, all of it user time.
+//! - The timers of the cages expire against the virtual clock, while the clock is moved forward.
+//! - Random bytes come from a ChaCha20 stream per cage, keyed by the seed and the cage id.
+//!
+//! Cage ids, which the cages see as pids, are handed out in the order of the `fork()` calls
+//! already, and directory listings are sorted by RawPOSIX.
+//!
+//! The clock is shared by all cages. Cages that run concurrently still interleave as the host
+//! schedules them, so a run is only reproducible as far as their syscalls do not race.
+use crate::cage::get_cage;
+use crate::timer::{virtual_expire, virtual_next_wakeup};
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use sysdefs::constants::err_const::Errno;
+
+/// What the virtual realtime clock reads at boot: 2000-01-01 00:00:00 UTC
+pub const REALTIME_START: Duration = Duration::from_secs(946_684_800);
+
+/// What the virtual monotonic clock reads at boot. Not zero, which programs take for a
+/// timestamp that was never set.
+const MONOTONIC_START: Duration = Duration::from_secs(1);
+
+/// The settings of deterministic execution
+#[derive(Clone, Debug)]
+pub struct DeterministicConfig {
+    /// Seed of the random streams of the cages
+    pub seed: u64,
+    /// Virtual time each syscall moves the clock forward by
+    pub tick: Duration,
+}
+
+impl Default for DeterministicConfig {
+    fn default() -> Self {
+        DeterministicConfig {
+            seed: 0,
+            tick: Duration::from_micros(1),
+        }
+    }
+}
+
+struct Deterministic {
+    config: DeterministicConfig,
+    /// Virtual monotonic time, in nanoseconds
+    now: AtomicU64,
+    /// Random stream of each cage, created on its first use
+    streams: DashMap<u64, ChaCha20>,
+}
+
+static DETERMINISTIC: OnceLock<Deterministic> = OnceLock::new();
+
+/// Turn on deterministic execution. Has to be called before `rawposix_start`.
+///
+/// ## Returns:
+/// - `Ok(())` on success
+/// - `Err(EBUSY)` if it was already turned on
+pub fn deterministic_init(config: DeterministicConfig) -> Result<(), Errno> {
+    DETERMINISTIC
+        .set(Deterministic {
+            config,
+            now: AtomicU64::new(MONOTONIC_START.as_nanos() as u64),
+            streams: DashMap::new(),
+        })
+        .map_err(|_| Errno::EBUSY)
+}
+
+pub fn is_deterministic() -> bool {
+    DETERMINISTIC.get().is_some()
+}
+
+/// The virtual monotonic clock. Only meaningful in deterministic mode.
+pub fn virtual_now() -> Duration {
+    DETERMINISTIC.get().map_or(Duration::ZERO, |det| {
+        Duration::from_nanos(det.now.load(Ordering::Relaxed))
+    })
+}
+
+/// A reading of clock `clockid` in deterministic mode, `None` outside of it and for the CPU
+/// time clocks, which count per cage
+pub fn virtual_clock(clockid: libc::clockid_t) -> Option<Duration> {
+    DETERMINISTIC.get()?;
+    match clockid {
+        libc::CLOCK_REALTIME
+        | libc::CLOCK_REALTIME_COARSE
+        | libc::CLOCK_REALTIME_ALARM
+        | libc::CLOCK_TAI => Some(REALTIME_START + virtual_now()),
+        libc::CLOCK_MONOTONIC
+        | libc::CLOCK_MONOTONIC_RAW
+        | libc::CLOCK_MONOTONIC_COARSE
+        | libc::CLOCK_BOOTTIME
+        | libc::CLOCK_BOOTTIME_ALARM => Some(virtual_now()),
+        _ => None,
+    }
+}
+
+/// Move the virtual clock forward to `to`, expiring the timers that come due on the way
+fn advance_to(det: &Deterministic, to: Duration) {
+    let to = to.as_nanos().min(u64::MAX as u128) as u64;
+    det.now.fetch_max(to, Ordering::Relaxed);
+    virtual_expire(virtual_now());
+}
+
+/// Account for a syscall of cage `cageid`: the clock moves forward by one tick, which the cage
+/// is charged for as CPU time. A no-op outside of deterministic mode.
+pub fn syscall_tick(cageid: u64) {
+    let Some(det) = DETERMINISTIC.get() else {
+        return;
+    };
+    let tick = det.config.tick;
+    if let Some(cage) = get_cage(cageid) {
+        cage.cpu.charge(tick);
+    }
+    let now = det.now.fetch_add(tick.as_nanos() as u64, Ordering::Relaxed);
+    advance_to(det, Duration::from_nanos(now) + tick);
+}
+
+/// Sleep until the virtual monotonic clock reads `deadline`, which takes no time on the host.
+/// The timers that come due on the way expire in order, and the sleep ends early once
+/// `interrupted` reports a signal for the sleeper.
+///
+/// ## Returns:
+/// - `Ok(())` once the deadline is reached
+/// - `Err(remaining)` if the sleep was interrupted
+pub fn virtual_sleep_until(
+    deadline: Duration,
+    interrupted: impl Fn() -> bool,
+) -> Result<(), Duration> {
+    let Some(det) = DETERMINISTIC.get() else {
+        return Ok(());
+    };
+    loop {
+        let now = virtual_now();
+        if now >= deadline {
+            return Ok(());
+        }
+        let next = virtual_next_wakeup().map_or(deadline, |at| at.clamp(now, deadline));
+        advance_to(det, next);
+        if interrupted() {
+            return Err(deadline.saturating_sub(virtual_now()));
+        }
+    }
+}
+
+/// Fill `buf` from the random stream of cage `cageid`.
+///
+/// ## Returns:
+/// - `false` outside of deterministic mode, leaving `buf` untouched
+pub fn virtual_random(cageid: u64, buf: &mut [u8]) -> bool {
+    let Some(det) = DETERMINISTIC.get() else {
+        return false;
+    };
+    det.streams
+        .entry(cageid)
+        .or_insert_with(|| {
+            let mut key = [0u8; 32];
+            key[..8].copy_from_slice(&det.config.seed.to_le_bytes());
+            ChaCha20::new(&key, cageid)
+        })
+        .fill(buf);
+    true
+}
+
+/// The ChaCha20 keystream, in its original form with a 64-bit nonce and block counter
+struct ChaCha20 {
+    state: [u32; 16],
+    block: [u8; 64],
+    /// Bytes of `block` handed out already
+    used: usize,
+}
+
+impl ChaCha20 {
+    fn new(key: &[u8; 32], nonce: u64) -> Self {
+        let mut state = [0u32; 16];
+        // "expand 32-byte k"
+        state[..4].copy_from_slice(&[0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574]);
+        for (word, bytes) in state[4..12].iter_mut().zip(key.chunks_exact(4)) {
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+        state[14] = nonce as u32;
+        state[15] = (nonce >> 32) as u32;
+        ChaCha20 {
+            state,
+            block: [0; 64],
+            used: 64,
+        }
+    }
+
+    fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+        x[a] = x[a].wrapping_add(x[b]);
+        x[d] = (x[d] ^ x[a]).rotate_left(16);
+        x[c] = x[c].wrapping_add(x[d]);
+        x[b] = (x[b] ^ x[c]).rotate_left(12);
+        x[a] = x[a].wrapping_add(x[b]);
+        x[d] = (x[d] ^ x[a]).rotate_left(8);
+        x[c] = x[c].wrapping_add(x[d]);
+        x[b] = (x[b] ^ x[c]).rotate_left(7);
+    }
+
+    /// Compute the next block of the keystream
+    fn refill(&mut self) {
+        let mut x = self.state;
+        for _ in 0..10 {
+            Self::quarter_round(&mut x, 0, 4, 8, 12);
+            Self::quarter_round(&mut x, 1, 5, 9, 13);
+            Self::quarter_round(&mut x, 2, 6, 10, 14);
+            Self::quarter_round(&mut x, 3, 7, 11, 15);
+            Self::quarter_round(&mut x, 0, 5, 10, 15);
+            Self::quarter_round(&mut x, 1, 6, 11, 12);
+            Self::quarter_round(&mut x, 2, 7, 8, 13);
+            Self::quarter_round(&mut x, 3, 4, 9, 14);
+        }
+        for (i, bytes) in self.block.chunks_exact_mut(4).enumerate() {
+            bytes.copy_from_slice(&x[i].wrapping_add(self.state[i]).to_le_bytes());
+        }
+        self.used = 0;
+
+        let counter = ((self.state[13] as u64) << 32 | self.state[12] as u64).wrapping_add(1);
+        self.state[12] = counter as u32;
+        self.state[13] = (counter >> 32) as u32;
+    }
+
+    fn fill(&mut self, buf: &mut [u8]) {
+        let mut filled = 0;
+        while filled < buf.len() {
+            if self.used == self.block.len() {
+                self.refill();
+            }
+            let n = (buf.len() - filled).min(self.block.len() - self.used);
+            buf[filled..filled + n].copy_from_slice(&self.block[self.used..self.used + n]);
+            self.used += n;
+            filled += n;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chacha20_block() {
+        // RFC 8439, section 2.3.2. Its 32-bit counter and 96-bit nonce map onto the 64-bit
+        // counter and nonce words of the original layout.
+        let key: [u8; 32] = std::array::from_fn(|i| i as u8);
+        let mut stream = ChaCha20::new(&key, 0x4a00_0000);
+        stream.state[12] = 1;
+        stream.state[13] = 0x0900_0000;
+        let mut block = [0u8; 64];
+        stream.fill(&mut block);
+        assert_eq!(
+            block[..16],
+            [
+                0x10, 0xf1, 0xe7, 0xe4, 0xd1, 0x3b, 0x59, 0x15, 0x50, 0x0f, 0xdd, 0x1f, 0xa3, 0x20,
+                0x71, 0xc4
+            ]
+        );
+        assert_eq!(
+            block[48..],
+            [
+                0xb5, 0x12, 0x9c, 0xd1, 0xde, 0x16, 0x4e, 0xb9, 0xcb, 0xd0, 0x83, 0xe8, 0xa2, 0x50,
+                0x3c, 0x4e
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chacha20_fill_is_contiguous() {
+        let key = [7u8; 32];
+        let mut whole = [0u8; 200];
+        ChaCha20::new(&key, 3).fill(&mut whole);
+
+        let mut pieces = [0u8; 200];
+        let mut stream = ChaCha20::new(&key, 3);
+        for chunk in pieces.chunks_mut(13) {
+            stream.fill(chunk);
+        }
+        assert_eq!(whole, pieces);
+
+        let mut other = [0u8; 200];
+        ChaCha20::new(&key, 4).fill(&mut other);
+        assert_ne!(whole, other);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod cage;
+pub mod deterministic;
 pub mod memory;
 pub mod signal;
 
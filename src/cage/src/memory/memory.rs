@@ -143,6 +143,12 @@ pub fn fork_vmmap(parent_cageid: u64, child_cageid: u64) {
     drop(child_vmmap);
     let mut child_vmmap = child_cage.vmmap.write();
     child_vmmap.set_program_break(parent_vmmap.program_break);
+
+    // the child inherited the parent's counters along with its entries; its high-water
+    // marks start from what it holds right after the copy
+    child_vmmap.peak_committed_pages = child_vmmap.committed_pages;
+    child_vmmap.peak_resident_pages = 0;
+    child_vmmap.sample_resident_pages();
 }
 
 // set the wasm linear memory base address to vmmap
@@ -31,13 +31,19 @@ const DEFAULT_VMMAP_SIZE: u32 = 1 << (32 - PAGESHIFT);
 /// - Anonymous: Memory not backed by any file (e.g. heap allocations)
 /// - SharedMemory: Memory backed by a shared memory segment, identified by shmid
 /// - FileDescriptor: Memory backed by a file, identified by file descriptor
+/// - SharedAnonymous: MAP_SHARED anonymous memory, identified by an id unique to the mmap() that
+///   made it and the user address it starts at, which the cages forked from its own share
+/// - SharedFile: MAP_SHARED file mapping, by its file descriptor, and identified by the host
+///   device and inode of the file and the user address offset 0 of the file would be at
 #[allow(dead_code)]
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum MemoryBackingType {
     None, // just a dummy value for places where it needs to be passed, but you dont have the value
     Anonymous,
-    SharedMemory(u64),   // stores shmid
-    FileDescriptor(u64), // stores file descriptor addr
+    SharedMemory(u64),              // stores shmid
+    FileDescriptor(u64),            // stores file descriptor addr
+    SharedAnonymous(u64, u32),      // stores mapping id and start address
+    SharedFile(u64, u64, u64, i64), // stores file descriptor, device, inode and address of offset 0
 }
 
 /// An entry in the virtual memory map that contains fields such as page number, number of pages,
@@ -204,6 +210,13 @@ pub trait VmmapOps {
         page_num: u32,
     ) -> impl DoubleEndedIterator<Item = (&Interval<u32>, &mut VmmapEntry)>;
 
+    // Method to iterate over the entries overlapping a page range
+    fn overlapping_entries(
+        &self,
+        page_num: u32,
+        npages: u32,
+    ) -> impl DoubleEndedIterator<Item = (&Interval<u32>, &VmmapEntry)>;
+
     // Method to get the first entry in the memory map
     fn first_entry(&self) -> Option<(&Interval<u32>, &VmmapEntry)>;
 
@@ -256,6 +269,15 @@ pub struct Vmmap {
     pub start_address: u32, // start address of valid vmmap address range
     pub end_address: u32,   // end address of valid vmmap address range
     pub program_break: u32, // program break (i.e. heap bottom) of the memory
+
+    // Memory accounting, in pages. "Committed" counts every page the cage can currently
+    // touch (any mapping whose prot is not PROT_NONE) and is maintained by the vmmap
+    // operations themselves. "Resident" is only known to the host kernel, so it is
+    // sampled with `mincore` on demand (see `sample_resident_pages`).
+    pub committed_pages: u64,
+    pub peak_committed_pages: u64,
+    pub resident_pages: u64,
+    pub peak_resident_pages: u64,
 }
 
 #[allow(dead_code)]
@@ -270,6 +292,10 @@ impl Vmmap {
             start_address: 0,
             end_address: DEFAULT_VMMAP_SIZE,
             program_break: 0,
+            committed_pages: 0,
+            peak_committed_pages: 0,
+            resident_pages: 0,
+            peak_resident_pages: 0,
         }
     }
 
@@ -285,6 +311,72 @@ impl Vmmap {
         self.start_address = 0;
         self.end_address = DEFAULT_VMMAP_SIZE;
         self.program_break = 0;
+        // exec starts a fresh image, but the high-water marks describe the process
+        // (as with Linux `VmHWM`/`ru_maxrss`), so only the current usage is reset
+        self.committed_pages = 0;
+        self.resident_pages = 0;
+    }
+
+    /// Counts the committed (non-`PROT_NONE`) pages that currently lie in `[start, end)`
+    fn committed_pages_in(&self, start: u32, end: u32) -> u64 {
+        self.entries
+            .overlapping(ie(start, end))
+            .filter(|(_, entry)| entry.prot != PROT_NONE)
+            .map(|(interval, _)| {
+                // Clamp split entries to the queried range; interval ends are inclusive
+                let lo = interval.start().max(start);
+                let hi = (interval.end() + 1).min(end);
+                (hi - lo) as u64
+            })
+            .sum()
+    }
+
+    /// Applies a change in committed pages and keeps the peak up to date
+    fn account_committed(&mut self, before: u64, after: u64) {
+        self.committed_pages = (self.committed_pages + after).saturating_sub(before);
+        self.peak_committed_pages = self.peak_committed_pages.max(self.committed_pages);
+    }
+
+    /// Samples how many committed pages are resident in host memory
+    ///
+    /// Residency is owned by the host kernel, so this walks every committed mapping and asks
+    /// `mincore` about it. The result is stored in `resident_pages` (and folded into
+    /// `peak_resident_pages`) so that callers that only need a recent value, such as
+    /// `wait4`'s `ru_maxrss`, do not have to sample again.
+    ///
+    /// Returns the number of resident pages, or the previous sample if the base address is
+    /// not set yet.
+    pub fn sample_resident_pages(&mut self) -> u64 {
+        let base = match self.base_address {
+            Some(base) => base,
+            None => return self.resident_pages,
+        };
+
+        let mut resident = 0u64;
+        let mut vec = Vec::new();
+        for (interval, entry) in self.entries.iter() {
+            if entry.prot == PROT_NONE {
+                continue;
+            }
+            let npages = (interval.end() + 1 - interval.start()) as usize;
+            vec.clear();
+            vec.resize(npages, 0u8);
+            let addr = base + ((interval.start() as usize) << PAGESHIFT);
+            let ret = unsafe {
+                libc::mincore(
+                    addr as *mut libc::c_void,
+                    npages << PAGESHIFT,
+                    vec.as_mut_ptr(),
+                )
+            };
+            if ret == 0 {
+                resident += vec.iter().filter(|v| **v & 1 != 0).count() as u64;
+            }
+        }
+
+        self.resident_pages = resident;
+        self.peak_resident_pages = self.peak_resident_pages.max(resident);
+        resident
     }
 
     /// Rounds up a page number to the nearest multiple of pages_per_map
@@ -490,8 +582,13 @@ impl VmmapOps for Vmmap {
     /// - Start: vmmap_entry_ref.page_num
     /// - End: vmmap_entry_ref.page_num + vmmap_entry_ref.npages (inclusive)
     fn add_entry(&mut self, vmmap_entry_ref: VmmapEntry) {
+        let committed = if vmmap_entry_ref.prot != PROT_NONE {
+            vmmap_entry_ref.npages as u64
+        } else {
+            0
+        };
         // Create interval from page range and insert entry with strict bounds checking
-        let _ = self.entries.insert_strict(
+        let inserted = self.entries.insert_strict(
             // pages x to y, y included
             ie(
                 vmmap_entry_ref.page_num,
@@ -499,6 +596,9 @@ impl VmmapOps for Vmmap {
             ),
             vmmap_entry_ref,
         );
+        if inserted.is_ok() {
+            self.account_committed(0, committed);
+        }
     }
 
     /// Adds a new entry to the virtual memory map with overwrite capability
@@ -638,6 +738,15 @@ impl VmmapOps for Vmmap {
             cage_id,
         };
 
+        // Pages in the range that were committed before this update; whatever replaces
+        // them is accounted for below
+        let committed_before = self.committed_pages_in(new_region_start_page, new_region_end_page);
+        let committed_after = if remove || prot == PROT_NONE {
+            0
+        } else {
+            npages as u64
+        };
+
         // Insert new entry, overwriting any existing entries in the range
         let _ = self
             .entries
@@ -651,6 +760,8 @@ impl VmmapOps for Vmmap {
                 .remove_overlapping(ie(new_region_start_page, new_region_end_page));
         }
 
+        self.account_committed(committed_before, committed_after);
+
         Ok(())
     }
 
@@ -672,6 +783,7 @@ impl VmmapOps for Vmmap {
         // Calculate page range
         let new_region_end_page = page_num + npages;
         let new_region_start_page = page_num;
+        let committed_before = self.committed_pages_in(new_region_start_page, new_region_end_page);
 
         // Collect information about overlapping entries that need to be modified
         let mut entries_to_modify = Vec::new();
@@ -756,6 +868,9 @@ impl VmmapOps for Vmmap {
                 }
             }
         }
+
+        let committed_after = self.committed_pages_in(new_region_start_page, new_region_end_page);
+        self.account_committed(committed_before, committed_after);
     }
 
     /// Checks if a memory mapping exists with specified protection
@@ -991,6 +1106,24 @@ impl VmmapOps for Vmmap {
         }
     }
 
+    /// Creates an iterator over the entries overlapping a page range
+    ///
+    /// Arguments:
+    /// - page_num: First page of the range
+    /// - npages: Number of pages in the range, must be non-zero
+    ///
+    /// Returns:
+    /// - Iterator over the overlapping entries in address order. Entries that were split by
+    ///   an overwrite keep their original `page_num` / `npages`, so callers should take the
+    ///   bounds from the interval.
+    fn overlapping_entries(
+        &self,
+        page_num: u32,
+        npages: u32,
+    ) -> impl DoubleEndedIterator<Item = (&Interval<u32>, &VmmapEntry)> {
+        self.entries.overlapping(ie(page_num, page_num + npages))
+    }
+
     /// Finds available space in the memory map for a new mapping
     ///
     /// Searches for a gap between existing mappings that can accommodate
@@ -2444,4 +2577,229 @@ mod tests {
         let result = vmmap.calculate_page_range(0, 1);
         assert_eq!(result, Some((0, 1)), "1 byte should still be 1 page");
     }
+
+    /// Test: committed page accounting across map, protect and unmap
+    /// Expected: PROT_NONE pages are not committed, the peak never decreases
+    #[test]
+    fn test_committed_pages_accounting() {
+        let mut vmmap = Vmmap::new();
+
+        vmmap
+            .add_entry_with_overwrite(
+                10,
+                20,
+                PROT_READ | PROT_WRITE,
+                PROT_READ | PROT_WRITE,
+                0,
+                MemoryBackingType::Anonymous,
+                0,
+                0,
+                0,
+            )
+            .unwrap();
+        assert_eq!(vmmap.committed_pages, 20);
+
+        // Remapping part of the region over itself must not double count
+        vmmap
+            .add_entry_with_overwrite(
+                15,
+                10,
+                PROT_READ,
+                PROT_READ | PROT_WRITE,
+                0,
+                MemoryBackingType::Anonymous,
+                0,
+                0,
+                0,
+            )
+            .unwrap();
+        assert_eq!(vmmap.committed_pages, 20);
+
+        // Dropping pages to PROT_NONE releases them
+        vmmap.change_prot(10, 5, PROT_NONE);
+        assert_eq!(vmmap.committed_pages, 15);
+        vmmap.change_prot(10, 2, PROT_READ);
+        assert_eq!(vmmap.committed_pages, 17);
+
+        vmmap.remove_entry(20, 10).unwrap();
+        assert_eq!(vmmap.committed_pages, 7);
+        assert_eq!(vmmap.peak_committed_pages, 20);
+
+        vmmap.clear();
+        assert_eq!(vmmap.committed_pages, 0);
+        assert_eq!(vmmap.peak_committed_pages, 20);
+    }
+
+    /// Test: a PROT_NONE reservation commits nothing until it is made accessible
+    /// Expected: only accessible pages count, and the peak survives exec's clear()
+    #[test]
+    fn test_committed_pages_reservation() {
+        let mut vmmap = Vmmap::new();
+
+        vmmap
+            .add_entry_with_overwrite(
+                100,
+                50,
+                PROT_NONE,
+                PROT_READ | PROT_WRITE,
+                0,
+                MemoryBackingType::Anonymous,
+                0,
+                0,
+                0,
+            )
+            .unwrap();
+        assert_eq!(vmmap.committed_pages, 0);
+        assert_eq!(vmmap.peak_committed_pages, 0);
+
+        vmmap.change_prot(110, 8, PROT_READ | PROT_WRITE);
+        assert_eq!(vmmap.committed_pages, 8);
+        // Changing between accessible protections doesn't commit anything new
+        vmmap.change_prot(110, 8, PROT_READ);
+        assert_eq!(vmmap.committed_pages, 8);
+
+        // Unmapping the uncommitted part of the reservation changes nothing
+        vmmap.remove_entry(130, 20).unwrap();
+        assert_eq!(vmmap.committed_pages, 8);
+
+        vmmap.clear();
+        assert_eq!(vmmap.committed_pages, 0);
+        assert_eq!(vmmap.peak_committed_pages, 8);
+
+        // The peak keeps growing across exec
+        vmmap
+            .add_entry_with_overwrite(
+                10,
+                12,
+                PROT_READ,
+                PROT_READ,
+                0,
+                MemoryBackingType::Anonymous,
+                0,
+                0,
+                0,
+            )
+            .unwrap();
+        assert_eq!(vmmap.committed_pages, 12);
+        assert_eq!(vmmap.peak_committed_pages, 12);
+    }
+
+    /// Test: resident page sampling against real host memory
+    /// Expected: only touched, accessible pages are resident; the peak never decreases
+    #[test]
+    fn test_resident_pages_sampling() {
+        let mut vmmap = Vmmap::new();
+        let len = 16 << PAGESHIFT;
+
+        // Without a base address there is nothing to sample
+        assert_eq!(vmmap.sample_resident_pages(), 0);
+
+        let base = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        assert_ne!(base, libc::MAP_FAILED);
+        vmmap.set_base_address(base as usize);
+
+        vmmap
+            .add_entry_with_overwrite(
+                0,
+                16,
+                PROT_READ | PROT_WRITE,
+                PROT_READ | PROT_WRITE,
+                0,
+                MemoryBackingType::Anonymous,
+                0,
+                0,
+                0,
+            )
+            .unwrap();
+        assert_eq!(vmmap.sample_resident_pages(), 0);
+
+        // Touch pages 2, 3 and 9
+        for page in [2usize, 3, 9] {
+            unsafe { *(base as *mut u8).add(page << PAGESHIFT) = 1 };
+        }
+        assert_eq!(vmmap.sample_resident_pages(), 3);
+        assert_eq!(vmmap.resident_pages, 3);
+
+        // PROT_NONE pages are not committed, so they are not counted either
+        vmmap.change_prot(0, 4, PROT_NONE);
+        assert_eq!(vmmap.sample_resident_pages(), 1);
+        assert_eq!(vmmap.peak_resident_pages, 3);
+
+        unsafe { libc::munmap(base, len) };
+    }
+
+    /// Test: overlapping_entries over mapped ranges, gaps and split entries
+    /// Expected: exactly the entries intersecting the range, in address order
+    #[test]
+    fn test_overlapping_entries() {
+        let mut vmmap = Vmmap::new();
+
+        for start in [10, 30, 50] {
+            vmmap
+                .add_entry_with_overwrite(
+                    start,
+                    10,
+                    PROT_READ | PROT_WRITE,
+                    PROT_READ | PROT_WRITE,
+                    0,
+                    MemoryBackingType::Anonymous,
+                    0,
+                    0,
+                    0,
+                )
+                .unwrap();
+        }
+
+        let bounds = |vmmap: &Vmmap, page_num, npages| -> Vec<(u32, u32)> {
+            vmmap
+                .overlapping_entries(page_num, npages)
+                .map(|(interval, _)| (interval.start(), interval.end()))
+                .collect()
+        };
+
+        // A range reaching into two entries, including a single page of the second
+        assert_eq!(bounds(&vmmap, 15, 16), vec![(10, 19), (30, 39)]);
+        // Ranges in the gaps, or just past an entry, overlap nothing
+        assert!(bounds(&vmmap, 20, 10).is_empty());
+        assert!(bounds(&vmmap, 60, 1).is_empty());
+        // A range covering everything
+        assert_eq!(bounds(&vmmap, 0, 100), vec![(10, 19), (30, 39), (50, 59)]);
+
+        // Overwriting the middle of an entry splits it into three
+        vmmap
+            .add_entry_with_overwrite(
+                33,
+                4,
+                PROT_READ,
+                PROT_READ | PROT_WRITE,
+                0,
+                MemoryBackingType::Anonymous,
+                0,
+                0,
+                0,
+            )
+            .unwrap();
+        assert_eq!(bounds(&vmmap, 30, 10), vec![(30, 32), (33, 36), (37, 39)]);
+        let prots: Vec<i32> = vmmap
+            .overlapping_entries(30, 10)
+            .map(|(_, entry)| entry.prot)
+            .collect();
+        assert_eq!(
+            prots,
+            vec![PROT_READ | PROT_WRITE, PROT_READ, PROT_READ | PROT_WRITE]
+        );
+
+        // The iterator also runs backwards
+        let last = vmmap.overlapping_entries(0, 100).next_back().unwrap();
+        assert_eq!(last.0.start(), 50);
+    }
 }
@@ -0,0 +1,245 @@
+//! Per-cage CPU accounting
+//!
+//! Every cage runs on host threads of the one lind process, so the host's process CPU clock
+//! mixes all cages together. Instead, each thread of a cage is registered here when it starts
+//! (`lind_signal_init`) and its time is folded into the cage's total when it exits
+//! (`lind_thread_exit`). What a thread used before it became a cage thread, like compiling the
+//! module, is not counted.
+//!
+//! The total comes from the thread's CPU clock and is exact. How it divides into user and
+//! system time is only known at tick granularity, from `utime` and `stime` of
+//! `/proc/self/task/<tid>/stat`, so the total is split in the proportion of those.
+//!
+//! In deterministic mode the host's clocks are not consulted at all: a cage is charged the
+//! virtual ticks of its syscalls instead, see `crate::deterministic`.
+//!
+//! The time of the children a cage waited for is kept apart, for `RUSAGE_CHILDREN` and the
+//! `tms_cutime` / `tms_cstime` of `times()`.
+use crate::deterministic::is_deterministic;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// CPU time, split like `getrusage()` does
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub user: Duration,
+    pub system: Duration,
+}
+
+impl CpuTimes {
+    pub fn total(&self) -> Duration {
+        self.user + self.system
+    }
+}
+
+#[derive(Debug)]
+struct CpuThread {
+    /// CPU clock of the host thread
+    clockid: libc::clockid_t,
+    /// Kernel thread id of the host thread, for its `/proc` entry
+    tid: libc::pid_t,
+    /// Readings of the clock and of the `utime` / `stime` ticks when it was registered
+    base: Duration,
+    base_ticks: (u64, u64),
+    /// Virtual time charged to the thread in deterministic mode
+    charged: Duration,
+}
+
+#[derive(Debug, Default)]
+struct CpuAccountInner {
+    threads: HashMap<i32, CpuThread>,
+    /// Time of the threads that are gone
+    exited: CpuTimes,
+    /// Virtual time charged in deterministic mode
+    charged: Duration,
+    /// Time of the children that were waited for
+    children: CpuTimes,
+}
+
+/// CPU time used by the threads of a cage
+#[derive(Debug, Default)]
+pub struct CpuAccount {
+    inner: Mutex<CpuAccountInner>,
+}
+
+/// Read a host clock, `None` if it is the CPU clock of a thread that is gone
+pub fn clock_read(clockid: libc::clockid_t) -> Option<Duration> {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    if unsafe { libc::clock_gettime(clockid, &mut ts) } < 0 {
+        return None;
+    }
+    Some(Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+}
+
+/// CPU clock of the calling host thread
+pub fn cpu_clock_self() -> libc::clockid_t {
+    let mut clockid: libc::clockid_t = 0;
+    unsafe { libc::pthread_getcpuclockid(libc::pthread_self(), &mut clockid) };
+    clockid
+}
+
+/// `utime` and `stime` of a host thread, in clock ticks
+fn thread_ticks(tid: libc::pid_t) -> Option<(u64, u64)> {
+    let stat = std::fs::read_to_string(format!("/proc/self/task/{}/stat", tid)).ok()?;
+    // The command name may contain anything, the fields that follow start after its ')'
+    let mut fields = stat[stat.rfind(')')? + 1..].split_whitespace();
+    // `state` is field 3, `utime` and `stime` are fields 14 and 15
+    let utime = fields.nth(11)?.parse().ok()?;
+    let stime = fields.next()?.parse().ok()?;
+    Some((utime, stime))
+}
+
+impl CpuThread {
+    fn total(&self) -> Option<Duration> {
+        Some(clock_read(self.clockid)?.saturating_sub(self.base))
+    }
+
+    fn times(&self) -> Option<CpuTimes> {
+        let total = self.total()?;
+        let (utime, stime) = thread_ticks(self.tid).unwrap_or(self.base_ticks);
+        let user_ticks = utime.saturating_sub(self.base_ticks.0);
+        let ticks = user_ticks + stime.saturating_sub(self.base_ticks.1);
+        // Too short a run to have been sampled by a tick counts as user time
+        let user = if ticks == 0 {
+            total
+        } else {
+            total.mul_f64(user_ticks as f64 / ticks as f64)
+        };
+        Some(CpuTimes {
+            user,
+            system: total - user,
+        })
+    }
+}
+
+impl CpuAccount {
+    /// Start counting the calling host thread as thread `threadid` of the cage. Registering a
+    /// thread again, as `exec` does, starts over from its current reading.
+    pub fn thread_start(&self, threadid: i32) {
+        let clockid = cpu_clock_self();
+        let tid = unsafe { libc::gettid() };
+        let thread = CpuThread {
+            clockid,
+            tid,
+            base: clock_read(clockid).unwrap_or_default(),
+            base_ticks: thread_ticks(tid).unwrap_or_default(),
+            charged: Duration::ZERO,
+        };
+        self.inner.lock().threads.insert(threadid, thread);
+    }
+
+    /// Fold the time of thread `threadid` into the cage's, before the thread is gone
+    pub fn thread_exit(&self, threadid: i32) {
+        let mut inner = self.inner.lock();
+        if let Some(times) = inner.threads.remove(&threadid).and_then(|t| t.times()) {
+            inner.exited.user += times.user;
+            inner.exited.system += times.system;
+        }
+    }
+
+    /// Fold the time of every thread into the cage's, for `exec`, which ends all of them
+    /// but the one that registers again in the new program
+    pub fn threads_exit(&self) {
+        let mut inner = self.inner.lock();
+        for (_, thread) in std::mem::take(&mut inner.threads) {
+            if let Some(times) = thread.times() {
+                inner.exited.user += times.user;
+                inner.exited.system += times.system;
+            }
+        }
+    }
+
+    /// The number of threads the cage has running, which bounds how fast its CPU time can grow
+    pub fn threads(&self) -> usize {
+        self.inner.lock().threads.len()
+    }
+
+    /// Charge the cage `time` of virtual CPU time, in deterministic mode. The calling host thread
+    /// is charged as well, if it is one of the cage's.
+    pub fn charge(&self, time: Duration) {
+        let mut inner = self.inner.lock();
+        inner.charged = inner.charged.saturating_add(time);
+        let tid = unsafe { libc::gettid() };
+        if let Some(thread) = inner.threads.values_mut().find(|t| t.tid == tid) {
+            thread.charged = thread.charged.saturating_add(time);
+        }
+    }
+
+    /// User plus system time of the calling host thread since it was registered, for
+    /// `CLOCK_THREAD_CPUTIME_ID`. Zero if it is not a thread of the cage.
+    pub fn thread_total(&self) -> Duration {
+        let inner = self.inner.lock();
+        let tid = unsafe { libc::gettid() };
+        let Some(thread) = inner.threads.values().find(|t| t.tid == tid) else {
+            return Duration::ZERO;
+        };
+        if is_deterministic() {
+            return thread.charged;
+        }
+        thread.total().unwrap_or_default()
+    }
+
+    /// User and system time of the calling host thread, for `RUSAGE_THREAD`
+    pub fn thread_times(&self) -> CpuTimes {
+        let inner = self.inner.lock();
+        let tid = unsafe { libc::gettid() };
+        let Some(thread) = inner.threads.values().find(|t| t.tid == tid) else {
+            return CpuTimes::default();
+        };
+        if is_deterministic() {
+            return CpuTimes {
+                user: thread.charged,
+                system: Duration::ZERO,
+            };
+        }
+        thread.times().unwrap_or_default()
+    }
+
+    /// Add the time of a child that was waited for
+    pub fn add_children(&self, times: CpuTimes) {
+        let mut inner = self.inner.lock();
+        inner.children.user += times.user;
+        inner.children.system += times.system;
+    }
+
+    /// User and system time of the children that were waited for, and of theirs
+    pub fn children(&self) -> CpuTimes {
+        self.inner.lock().children
+    }
+
+    /// User plus system time of the cage. Cheaper than `times()`, as it needs no `/proc` reads.
+    pub fn total(&self) -> Duration {
+        let inner = self.inner.lock();
+        if is_deterministic() {
+            return inner.charged;
+        }
+        inner
+            .threads
+            .values()
+            .filter_map(CpuThread::total)
+            .fold(inner.exited.total(), |sum, t| sum + t)
+    }
+
+    /// User and system time of the cage
+    pub fn times(&self) -> CpuTimes {
+        let inner = self.inner.lock();
+        if is_deterministic() {
+            return CpuTimes {
+                user: inner.charged,
+                system: Duration::ZERO,
+            };
+        }
+        inner
+            .threads
+            .values()
+            .filter_map(CpuThread::times)
+            .fold(inner.exited, |sum, t| CpuTimes {
+                user: sum.user + t.user,
+                system: sum.system + t.system,
+            })
+    }
+}
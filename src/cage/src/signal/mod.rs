@@ -1,5 +1,7 @@
+pub mod cputime;
 pub mod signal;
 pub mod timer;
 
+pub use cputime::*;
 pub use signal::*;
 pub use timer::*;
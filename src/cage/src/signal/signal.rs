@@ -1,29 +1,63 @@
-use crate::cage::get_cage;
-use parking_lot::RwLock;
+use crate::cage::{get_cage, Cage, ThreadSignals, ALTSTACK_DISABLED};
+use std::cell::Cell;
 use std::sync::atomic::Ordering;
-use sysdefs::constants::{SA_NODEFER, SA_RESETHAND, SIG_DFL};
+use std::sync::OnceLock;
+use sysdefs::constants::err_const::Errno;
+use sysdefs::constants::{
+    SA_NODEFER, SA_ONSTACK, SA_RESETHAND, SA_RESTART, SIGKILL, SIGSTOP, SIG_DFL, SIG_IGN,
+    SS_DISABLE,
+};
+use sysdefs::data::fs_struct::StackStruct;
 
 const EPOCH_NORMAL: u64 = 0;
 const EPOCH_SIGNAL: u64 = 0xc0ffee;
 const EPOCH_KILLED: u64 = 0xdead;
 
-// switch the epoch of the main thread of the cage to "signal" state
-// thread safety: this function could possibly be invoked by multiple threads of the same cage
-pub fn signal_epoch_trigger(cageid: u64) {
-    #[cfg(feature = "disable_signals")]
-    return;
+// called with the cage id whenever the epoch of a cage is triggered or its threads are killed,
+// so that waits RawPOSIX keeps on the host (futexes) can end early
+static SIGNAL_WAKEUP: OnceLock<fn(u64)> = OnceLock::new();
+
+// register the hook that wakes up the waits of a signalled cage, once at startup
+pub fn register_signal_wakeup(wakeup: fn(u64)) {
+    let _ = SIGNAL_WAKEUP.set(wakeup);
+}
+
+fn signal_wakeup(cageid: u64) {
+    if let Some(wakeup) = SIGNAL_WAKEUP.get() {
+        wakeup(cageid);
+    }
+}
+
+thread_local! {
+    // the cage and thread id of the cage thread the host thread runs, set by lind_signal_init
+    static CURRENT_THREAD: Cell<Option<(u64, i32)>> = const { Cell::new(None) };
+}
+
+// the thread of the cage the calling host thread runs, if it runs one of them
+pub fn current_cage_thread(cageid: u64) -> Option<i32> {
+    match CURRENT_THREAD.get() {
+        Some((cage, threadid)) if cage == cageid => Some(threadid),
+        _ => None,
+    }
+}
+
+// the thread of the cage the calling host thread runs, or the main thread of the cage if it runs
+// none of them, like a grate or RawPOSIX itself acting on behalf of the cage. ESRCH if the cage
+// is gone
+pub fn current_threadid(cageid: u64) -> Result<i32, Errno> {
+    if let Some(threadid) = current_cage_thread(cageid) {
+        return Ok(threadid);
+    }
+    let cage = get_cage(cageid).ok_or(Errno::ESRCH)?;
+    let threadid = *cage.main_threadid.read();
+    Ok(threadid)
+}
 
+// switch the epoch of a thread to "signal" state, unless it is killed already
+fn thread_epoch_signal(thread: &ThreadSignals) {
     #[cfg(not(feature = "disable_signals"))]
     {
-        let cage = get_cage(cageid).unwrap();
-
-        let threadid_guard = cage.main_threadid.read();
-        let main_threadid = *threadid_guard;
-        let epoch_handler = cage
-            .epoch_handler
-            .get(&main_threadid)
-            .expect("main threadid does not exist");
-        let guard = epoch_handler.write();
+        let guard = thread.epoch.write();
         let epoch = *guard;
         // SAFETY: the pointer is locked with write access so no one is able to modify it concurrently
         // However, Potential BUG (TODO): We still need to verify the lifetime of the pointer. This pointer
@@ -31,14 +65,34 @@ pub fn signal_epoch_trigger(cageid: u64) {
         // we still need to figure out when is the destroy happening and make sure it is destroyed after the
         // information in rawposix is updated
         unsafe {
-            *epoch = EPOCH_SIGNAL;
+            if *epoch != EPOCH_KILLED {
+                *epoch = EPOCH_SIGNAL;
+            }
         }
     }
 }
 
-// switch the epoch of all threads of the cage to "killed" state
-// thread safety: this function will only be invoked by main thread of the cage
-pub fn epoch_kill_all(cageid: u64) {
+// switch the epoch of thread `threadid` of the cage to "signal" state
+// thread safety: this function could possibly be invoked by multiple threads of the same cage
+pub fn signal_epoch_trigger(cageid: u64, threadid: i32) {
+    #[cfg(feature = "disable_signals")]
+    return;
+
+    #[cfg(not(feature = "disable_signals"))]
+    {
+        let cage = get_cage(cageid).unwrap();
+        let Some(thread) = cage.epoch_handler.get(&threadid) else {
+            return;
+        };
+        thread_epoch_signal(&thread);
+        drop(thread);
+        signal_wakeup(cageid);
+    }
+}
+
+// switch the epoch of all threads of the cage but the calling one to "killed" state
+// thread safety: this function could possibly be invoked by multiple threads of the same cage
+pub fn epoch_kill_all(cageid: u64, self_threadid: i32) {
     #[cfg(feature = "disable_signals")]
     return;
 
@@ -46,42 +100,33 @@ pub fn epoch_kill_all(cageid: u64) {
     {
         let cage = get_cage(cageid).unwrap();
 
-        let threadid_guard = cage.main_threadid.read();
-        let main_threadid = *threadid_guard;
         // we iterate through the epoch handler of each thread in the cage
         for entry in cage.epoch_handler.iter() {
-            if entry.key() == &main_threadid {
-                // main thread should be the one invoking this method
-                // so main thread could kill itself and we do not need to notify it again
+            if *entry.key() == self_threadid {
+                // the calling thread kills itself, we do not need to notify it again
                 continue;
             }
-            let epoch_handler = entry.value();
-            let guard = epoch_handler.write();
+            let guard = entry.value().epoch.write();
             let epoch = *guard;
-            // SAFETY: see comment at `signal_epoch_trigger`
+            // SAFETY: see comment at `thread_epoch_signal`
             unsafe {
                 *epoch = EPOCH_KILLED;
             }
         }
+        signal_wakeup(cageid);
     }
 }
 
 // get the current epoch state of the thread
-// thread safety: this function will only be invoked by main thread of the cage
-fn get_epoch_state(cageid: u64, thread_id: u64) -> u64 {
+fn get_epoch_state(thread: &ThreadSignals) -> u64 {
     #[cfg(feature = "disable_signals")]
     return EPOCH_NORMAL;
 
     #[cfg(not(feature = "disable_signals"))]
     {
-        let cage = get_cage(cageid).unwrap();
-        let epoch_handler = cage
-            .epoch_handler
-            .get(&(thread_id as i32))
-            .expect("threadid does not exist");
-        let guard = epoch_handler.read();
+        let guard = thread.epoch.read();
         let epoch = *guard;
-        // SAFETY: see comment at `signal_epoch_trigger`
+        // SAFETY: see comment at `thread_epoch_signal`
         unsafe { *epoch }
     }
 }
@@ -96,70 +141,227 @@ pub fn thread_check_killed(cageid: u64, thread_id: u64) -> bool {
     {
         let cage = get_cage(cageid).unwrap();
         // this method should not be invoked if the thread is already killed (i.e. thread is removed from epoch_handler)
-        let epoch_handler = cage.epoch_handler.get(&(thread_id as i32)).unwrap();
-        let guard = epoch_handler.write();
-        let epoch = *guard;
-        // SAFETY: see comment at `signal_epoch_trigger`
-        unsafe { *epoch == EPOCH_KILLED }
+        let thread = cage.epoch_handler.get(&(thread_id as i32)).unwrap();
+        get_epoch_state(&thread) == EPOCH_KILLED
     }
 }
 
-// reset the epoch of the main thread of the cage to "normal" state
-// usually invoked when all the pending signals are handled for the cage
-// thread safety: this function will only be invoked by main thread of the cage
-pub fn signal_epoch_reset(cageid: u64) {
+// like `thread_check_killed`, but for any thread making a syscall: one whose signals are not set
+// up yet (or no longer, across exec), or one of a cage that a harsh exit tore down before the
+// thread reached its next epoch check
+// a thread the cage does not know of does not count as killed
+pub fn thread_is_killed(cageid: u64, thread_id: u64) -> bool {
+    #[cfg(feature = "disable_signals")]
+    return false;
+
+    #[cfg(not(feature = "disable_signals"))]
+    {
+        let Some(cage) = get_cage(cageid) else {
+            return false;
+        };
+        cage.epoch_handler
+            .get(&(thread_id as i32))
+            .is_some_and(|thread| get_epoch_state(&thread) == EPOCH_KILLED)
+    }
+}
+
+// reset the epoch of the thread to "normal" state, unless it was killed meanwhile
+// usually invoked when all the pending signals the thread may handle are handled
+// thread safety: this function will only be invoked by the thread itself
+pub fn signal_epoch_reset(cageid: u64, threadid: i32) {
     #[cfg(feature = "disable_signals")]
     return;
 
     #[cfg(not(feature = "disable_signals"))]
     {
         let cage = get_cage(cageid).unwrap();
-
-        let threadid_guard = cage.main_threadid.read();
-        let main_threadid = *threadid_guard;
-        let epoch_handler = cage.epoch_handler.get(&main_threadid).unwrap();
-        let guard = epoch_handler.write();
+        let thread = cage.epoch_handler.get(&threadid).unwrap();
+        let guard = thread.epoch.write();
         let epoch = *guard;
-        // SAFETY: see comment at `signal_epoch_trigger`
+        // SAFETY: see comment at `thread_epoch_signal`
         unsafe {
-            *epoch = EPOCH_NORMAL;
+            if *epoch != EPOCH_KILLED {
+                *epoch = EPOCH_NORMAL;
+            }
         }
     }
 }
 
-// manually check if the epoch is not in "normal" state
+// manually check if the epoch of the calling thread is not in "normal" state
 // useful if we want to do our own epoch check in host
-// thread safety: this function will only be invoked by main thread of the cage
+// thread safety: this function could possibly be invoked by multiple threads of the same cage
 pub fn signal_check_trigger(cageid: u64) -> bool {
     #[cfg(feature = "disable_signals")]
     return false;
 
     #[cfg(not(feature = "disable_signals"))]
     {
-        let cage = get_cage(cageid).unwrap();
+        let Ok(threadid) = current_threadid(cageid) else {
+            return false;
+        };
+        let Some(cage) = get_cage(cageid) else {
+            return false;
+        };
+        let Some(thread) = cage.epoch_handler.get(&threadid) else {
+            return false;
+        };
+        get_epoch_state(&thread) > EPOCH_NORMAL
+    }
+}
 
-        let threadid_guard = cage.main_threadid.read();
-        let main_threadid = *threadid_guard;
+// check if the epoch of thread `threadid` of the cage is not in "normal" state, i.e. the thread
+// was picked to handle a signal or was killed. False if the cage or the thread is gone
+// thread safety: this function could possibly be invoked by multiple threads of the same cage
+pub fn thread_check_trigger(cageid: u64, threadid: i32) -> bool {
+    #[cfg(feature = "disable_signals")]
+    return false;
 
-        let epoch_handler = cage.epoch_handler.get(&main_threadid).unwrap();
-        let guard = epoch_handler.write();
-        let epoch = *guard;
-        // SAFETY: see comment at `signal_epoch_trigger`
-        unsafe { *epoch > EPOCH_NORMAL }
+    #[cfg(not(feature = "disable_signals"))]
+    {
+        let Some(cage) = get_cage(cageid) else {
+            return false;
+        };
+        let Some(thread) = cage.epoch_handler.get(&threadid) else {
+            return false;
+        };
+        get_epoch_state(&thread) > EPOCH_NORMAL
     }
 }
 
-// check if the signal of the cage is in blocked state
-// thread safety: this function will only be invoked by main thread of the cage
-//                but should still work fine if accessed by multiple threads
-pub fn signal_check_block(cageid: u64, signo: i32) -> bool {
+// whether a blocking call of the calling thread that a signal interrupted is to be restarted
+// once the signal is handled, as Linux does for -ERESTARTSYS: the signal the thread handles
+// next has a handler installed with SA_RESTART, or the default disposition, which either ends
+// the cage or lets the call carry on. A killed thread restarts nothing
+// thread safety: this function will only be invoked by the thread itself
+pub fn signal_restart_check(cageid: u64) -> bool {
+    let Ok(threadid) = current_threadid(cageid) else {
+        return false;
+    };
+    let Some(cage) = get_cage(cageid) else {
+        return false;
+    };
+    let Some(thread) = cage.epoch_handler.get(&threadid) else {
+        return false;
+    };
+    if get_epoch_state(&thread) == EPOCH_KILLED {
+        return false;
+    }
+    let sigset = thread.sigset.load(Ordering::Relaxed);
+    let unblocked = |signo: &&i32| (sigset & convert_signal_mask(**signo)) == 0;
+    // the same order as lind_get_first_signal, the thread's own signals first
+    let own = thread
+        .pending_signals
+        .read()
+        .iter()
+        .find(unblocked)
+        .copied();
+    let signo = own.or_else(|| cage.pending_signals.read().iter().find(unblocked).copied());
+    let Some(signo) = signo else {
+        return false;
+    };
+    let restart = match cage.signalhandler.get(&signo) {
+        Some(act) => act.sa_handler == SIG_DFL as u32 || act.sa_flags as u32 & SA_RESTART != 0,
+        None => true,
+    };
+    restart
+}
+
+// check if the signal is blocked by thread `threadid` of the cage
+// thread safety: this function could possibly be invoked by multiple threads of the same cage
+pub fn signal_check_block(cageid: u64, threadid: i32, signo: i32) -> bool {
     let cage = get_cage(cageid).unwrap();
-    let sigset = cage.sigset.load(Ordering::Relaxed);
+    let Some(thread) = cage.epoch_handler.get(&threadid) else {
+        return false;
+    };
+    let sigset = thread.sigset.load(Ordering::Relaxed);
 
     // check if the corresponding signal bit is set in sigset
     (sigset & convert_signal_mask(signo)) > 0
 }
 
+// the signal mask of the calling thread, empty if the cage is gone
+// thread safety: this function could possibly be invoked by multiple threads of the same cage
+pub fn signal_mask_get(cageid: u64) -> u64 {
+    let Ok(threadid) = current_threadid(cageid) else {
+        return 0;
+    };
+    signal_mask_of(cageid, threadid)
+}
+
+// the signal mask of thread `threadid` of the cage, empty if there is no such thread or cage
+pub fn signal_mask_of(cageid: u64, threadid: i32) -> u64 {
+    let Some(cage) = get_cage(cageid) else {
+        return 0;
+    };
+    let Some(thread) = cage.epoch_handler.get(&threadid) else {
+        return 0;
+    };
+    thread.sigset.load(Ordering::Relaxed)
+}
+
+// the alternate signal stack of thread `threadid` of the cage, disabled if there is no such
+// thread
+pub fn signal_altstack_get(cageid: u64, threadid: i32) -> StackStruct {
+    let cage = get_cage(cageid).unwrap();
+    let Some(thread) = cage.epoch_handler.get(&threadid) else {
+        return ALTSTACK_DISABLED;
+    };
+    let altstack = *thread.altstack.lock();
+    altstack
+}
+
+// replace the alternate signal stack of thread `threadid` of the cage
+pub fn signal_altstack_set(cageid: u64, threadid: i32, altstack: StackStruct) {
+    let cage = get_cage(cageid).unwrap();
+    if let Some(thread) = cage.epoch_handler.get(&threadid) {
+        *thread.altstack.lock() = altstack;
+    };
+}
+
+// whether shadow stack pointer `sp` is on alternate signal stack `altstack`, which grows down
+// from `ss_sp + ss_size` like the shadow stack does
+pub fn altstack_contains(altstack: &StackStruct, sp: u32) -> bool {
+    altstack.ss_flags & SS_DISABLE == 0
+        && sp > altstack.ss_sp
+        && sp - altstack.ss_sp <= altstack.ss_size
+}
+
+// where the handler of a signal, installed with `sa_flags`, starts its shadow stack on thread
+// `threadid` of the cage, whose stack pointer is `sp`
+// returns the top of the alternate signal stack if the handler has SA_ONSTACK and the thread an
+// alternate stack that it is not on already, and None if the handler runs on the current stack
+pub fn signal_altstack_top(cageid: u64, threadid: i32, sa_flags: i32, sp: u32) -> Option<u32> {
+    if sa_flags as u32 & SA_ONSTACK == 0 {
+        return None;
+    }
+    let altstack = signal_altstack_get(cageid, threadid);
+    if altstack.ss_flags & SS_DISABLE != 0 || altstack_contains(&altstack, sp) {
+        return None;
+    }
+    // the top of the stack is kept 16-byte aligned, as the shadow stack ABI wants it
+    Some((altstack.ss_sp + altstack.ss_size) & !15)
+}
+
+// the thread that is to handle process-directed signal `signo`: a thread that is handling
+// signals already, else the main thread, else any other thread, as long as it does not block it
+fn process_signal_target(cage: &Cage, signo: i32) -> Option<i32> {
+    let mask = convert_signal_mask(signo);
+    let main_threadid = *cage.main_threadid.read();
+    let mut target = None;
+    for entry in cage.epoch_handler.iter() {
+        if entry.sigset.load(Ordering::Relaxed) & mask != 0 {
+            continue;
+        }
+        if get_epoch_state(&entry) == EPOCH_SIGNAL {
+            return Some(*entry.key());
+        }
+        if target.is_none() || *entry.key() == main_threadid {
+            target = Some(*entry.key());
+        }
+    }
+    target
+}
+
 // retrieve the signal handler for the specified signal of the cage
 // if the signal handler does not exist, then return SIG_DFL
 // thread safety: this function will only be invoked by main thread of the cage
@@ -174,7 +376,16 @@ pub fn signal_get_handler(cageid: u64, signo: i32) -> u32 {
     handler
 }
 
+// whether the signal would just be dropped: it has the default disposition, and by default
+// it is ignored
+fn signal_ignored(cageid: u64, signo: i32) -> bool {
+    signal_get_handler(cageid, signo) == SIG_DFL.try_into().unwrap()
+        && sysdefs::constants::signal_default_handler_dispatcher(signo)
+            == sysdefs::constants::SignalDefaultHandler::Ignore
+}
+
 // send specified signal to the cage, return value indicates whether the cage exists
+// the signal is handled by whichever thread of the cage does not block it, see `signal_may_trigger`
 // thread safety: this function could possibly be invoked by multiple threads of the same cage
 // NOTE: signo MUST be checked to make sure it's valid before passing to this function,
 //       otherwise would cause undefined behavior in release build
@@ -191,25 +402,19 @@ pub fn lind_send_signal(cageid: u64, signo: i32) -> bool {
         if signo > 0 {
             // if the sent signal has the default disposition and its default behavior is SIG_DFL
             // let's just ignore the signal
-            if signal_get_handler(cageid, signo) == SIG_DFL.try_into().unwrap()
-                && sysdefs::constants::signal_default_handler_dispatcher(signo)
-                    == sysdefs::constants::SignalDefaultHandler::Ignore
-            {
+            if signal_ignored(cageid, signo) {
                 return true;
             }
 
-            let mut pending_signals = cage.pending_signals.write();
             // TODO: currently we are queuing the same signals instead of merging the same signal
             // this is different from linux which always merge the same signal if they havn't been handled yet
             // we queue the signals for now because our epoch based signal implementation could have much longer
             // gap for signal checkings than linux. We need to finally decide whether do the queuing or merging
             // in the future, probably based on some experimental data
-            pending_signals.push(signo);
+            cage.pending_signals.write().push(signo);
 
-            // we only trigger epoch if the signal is not blocked
-            if !signal_check_block(cageid, signo) {
-                signal_epoch_trigger(cageid);
-            }
+            // hand the signal to a thread that does not block it, if there is one
+            signal_may_trigger(cageid);
         }
 
         true
@@ -218,93 +423,248 @@ pub fn lind_send_signal(cageid: u64, signo: i32) -> bool {
     }
 }
 
+// send specified signal to thread `threadid` of the cage alone, like tgkill()
+// return value indicates whether the thread exists
+// thread safety: this function could possibly be invoked by multiple threads of the same cage
+// NOTE: signo MUST be checked to make sure it's valid before passing to this function
+pub fn lind_send_thread_signal(cageid: u64, threadid: i32, signo: i32) -> bool {
+    debug_assert!(
+        (0..32).contains(&signo),
+        "invalid signal number passed to lind_send_thread_signal"
+    );
+
+    let Some(cage) = get_cage(cageid) else {
+        return false;
+    };
+    let Some(thread) = cage.epoch_handler.get(&threadid) else {
+        return false;
+    };
+    // as for kill(), signal 0 only checks that the thread exists
+    if signo == 0 || signal_ignored(cageid, signo) {
+        return true;
+    }
+
+    thread.pending_signals.write().push(signo);
+    // we only trigger epoch if the thread does not block the signal
+    if thread.sigset.load(Ordering::Relaxed) & convert_signal_mask(signo) == 0 {
+        thread_epoch_signal(&thread);
+        drop(thread);
+        signal_wakeup(cageid);
+    }
+    true
+}
+
 pub fn convert_signal_mask(signo: i32) -> u64 {
     (1 << (signo - 1)) as u64
 }
 
-// retrieve the first unblocked signal in the pending signal list
+// whether `thread` has a signal pending, sent to it or to the whole cage, that `mask` does not
+// block
+fn thread_has_unblocked(cage: &Cage, thread: &ThreadSignals, mask: u64) -> bool {
+    let unblocked = |&signo: &i32| (mask & convert_signal_mask(signo)) == 0;
+    // one list locked at a time, `lind_get_first_signal` takes the cage's first
+    if thread.pending_signals.read().iter().any(unblocked) {
+        return true;
+    }
+    cage.pending_signals.read().iter().any(unblocked)
+}
+
+// install a new signal mask for the calling thread and return the mask it replaced, for
+// sigprocmask() and for the temporary mask of a wait (ppoll, pselect6, epoll_pwait). SIGKILL and
+// SIGSTOP can never be blocked. Without a thread to install it on, nothing changes and the mask
+// returned is empty. Signals already pending that the new mask lets through trigger
+// the epoch of the thread right away, so a wait ends with EINTR instead of missing them, and the
+// process-directed signals it now blocks are handed to another thread
+// thread safety: this function could possibly be invoked by multiple threads of the same cage
+pub fn signal_mask_swap(cageid: u64, mask: u64) -> u64 {
+    let Ok(threadid) = current_threadid(cageid) else {
+        return 0;
+    };
+    let Some(cage) = get_cage(cageid) else {
+        return 0;
+    };
+    let mask = mask & !(convert_signal_mask(SIGKILL) | convert_signal_mask(SIGSTOP));
+    let Some(thread) = cage.epoch_handler.get(&threadid) else {
+        return 0;
+    };
+    let oldmask = thread.sigset.swap(mask, Ordering::Relaxed);
+
+    let triggered = thread_has_unblocked(&cage, &thread, mask);
+    if triggered {
+        thread_epoch_signal(&thread);
+    }
+    drop(thread);
+    if mask & !oldmask != 0 {
+        signal_may_trigger(cageid);
+    } else if triggered {
+        signal_wakeup(cageid);
+    }
+    oldmask
+}
+
+// undo signal_mask_swap at the end of the wait. If the wait was interrupted, the temporary
+// mask stays until the pending signal has been handled, and `oldmask` is restored when its
+// handler returns (see lind_get_first_signal), like the kernel does on sigreturn
+// thread safety: this function could possibly be invoked by multiple threads of the same cage
+pub fn signal_mask_restore(cageid: u64, oldmask: u64, interrupted: bool) {
+    let Ok(threadid) = current_threadid(cageid) else {
+        return;
+    };
+    let Some(cage) = get_cage(cageid) else {
+        return;
+    };
+    let Some(thread) = cage.epoch_handler.get(&threadid) else {
+        return;
+    };
+    if interrupted {
+        *thread.saved_sigset.lock() = Some(oldmask);
+    } else {
+        thread.sigset.store(oldmask, Ordering::Relaxed);
+        drop(thread);
+        signal_may_trigger(cageid);
+    }
+}
+
+// what a thread needs to run a signal handler: the handler, its sa_flags, and the callback that
+// restores the signal mask once the handler returns
+pub type SignalDispatch = (u32, i32, Box<dyn Fn(u64)>);
+
+// retrieve the first signal thread `threadid` of the cage does not block, among the signals sent
+// to it and then the ones sent to the whole cage
 // returns an optional tuple where the first element is the signal number
-// the second element is the signal handler
-// and the third element is the signal mask restore callback function
-// thread safety: this function will only be invoked by main thread of the cage
-pub fn lind_get_first_signal(cageid: u64) -> Option<(i32, u32, Box<dyn Fn(u64)>)> {
+// and the second element is the handler, its sa_flags and the signal mask restore callback
+// function, see `signal_dispatch`
+// thread safety: this function will only be invoked by the thread itself
+pub fn lind_get_first_signal(cageid: u64, threadid: i32) -> Option<(i32, SignalDispatch)> {
     let cage = get_cage(cageid).unwrap();
     let mut pending_signals = cage.pending_signals.write();
-    let sigset = cage.sigset.load(Ordering::Relaxed);
-
-    // we iterate through signal and retrieve the first unblocked signals in the pending list
-    if let Some(index) = pending_signals.iter().position(
-        |&signo| (sigset & convert_signal_mask(signo)) == 0, // check if signal is blocked
-    ) {
-        // retrieve the signal number
-        let signo = pending_signals.remove(index);
-        // retrieve the corresponding signal handler
-        match cage.signalhandler.get_mut(&signo) {
-            Some(mut sigaction) => {
-                // if sigprocmask is called during the execution of the signal handler
-                // the signal mask will not be perseved once handler is finished
-
-                // by default, we block the same signal during its execution
-                let mut mask_self = convert_signal_mask(signo);
-                let signal_handler = sigaction.sa_handler;
-                // if SA_RESETHAND is set, we reset the signal handler to default for this signal
-                if sigaction.sa_flags as u32 & SA_RESETHAND > 0 {
-                    sigaction.sa_handler = SIG_DFL as u32;
-                }
-
-                // if SA_NODEFER is set, we allow the same signal to interrupt itself
-                if sigaction.sa_flags as u32 & SA_NODEFER > 0 {
-                    mask_self = 0;
-                }
-                // temporily update the signal mask
-                cage.sigset
-                    .fetch_or(sigaction.sa_mask | mask_self, Ordering::Relaxed);
-
-                // restorer is called when the signal handler finishes. It should restore the signal mask
-                let restorer = Box::new(move |cageid| {
-                    let cage = get_cage(cageid).unwrap();
-                    cage.sigset.store(sigset, Ordering::Relaxed);
-                });
-                Some((signo, signal_handler, restorer))
+    let thread = cage.epoch_handler.get(&threadid)?;
+    let mut thread_pending = thread.pending_signals.write();
+    let sigset = thread.sigset.load(Ordering::Relaxed);
+    // the mask a ppoll(), pselect6() or epoll_pwait() interrupted by this signal replaced is the
+    // one to go back to once the handler is finished
+    let saved_sigset = thread.saved_sigset.lock().take();
+    let restore_sigset = saved_sigset.unwrap_or(sigset);
+
+    // check if signal is blocked
+    let unblocked = |&signo: &i32| (sigset & convert_signal_mask(signo)) == 0;
+    // signals sent to the thread itself come first, as on Linux
+    let signo = if let Some(index) = thread_pending.iter().position(unblocked) {
+        thread_pending.remove(index)
+    } else if let Some(index) = pending_signals.iter().position(unblocked) {
+        pending_signals.remove(index)
+    } else {
+        // if there is no pending unblocked signal, we return None
+        if let Some(saved_sigset) = saved_sigset {
+            thread.sigset.store(saved_sigset, Ordering::Relaxed);
+        }
+        return None;
+    };
+    drop(thread_pending);
+
+    Some((
+        signo,
+        signal_dispatch(&cage, &thread, threadid, signo, restore_sigset),
+    ))
+}
+
+// retrieve the handler for a signal raised synchronously by thread `threadid` itself, i.e. a
+// Wasm trap turned into SIGSEGV, SIGBUS, SIGFPE or SIGILL
+// like the kernel's force_sig(), a blocked or ignored fault signal cannot be put off, so in
+// that case, as well as when the signal has the default disposition, None is returned and the
+// caller terminates the cage with the signal
+// thread safety: this function will only be invoked by the thread itself
+pub fn lind_get_fault_signal(cageid: u64, threadid: i32, signo: i32) -> Option<SignalDispatch> {
+    let cage = get_cage(cageid).unwrap();
+    let thread = cage.epoch_handler.get(&threadid)?;
+    let sigset = thread.sigset.load(Ordering::Relaxed);
+    if sigset & convert_signal_mask(signo) != 0 {
+        return None;
+    }
+    let signal_handler = signal_get_handler(cageid, signo);
+    if signal_handler == SIG_DFL as u32 || signal_handler == SIG_IGN as u32 {
+        return None;
+    }
+
+    Some(signal_dispatch(&cage, &thread, threadid, signo, sigset))
+}
+
+// set thread `threadid` up to run the handler of `signo`, wherever the signal came from
+// the handler's sa_mask and, unless SA_NODEFER is set, the signal itself are blocked while it
+// runs, SA_RESETHAND puts the default disposition back, and the returned restorer sets the
+// thread's mask to `restore_sigset` once the handler is finished
+fn signal_dispatch(
+    cage: &Cage,
+    thread: &ThreadSignals,
+    threadid: i32,
+    signo: i32,
+    restore_sigset: u64,
+) -> SignalDispatch {
+    // restorer is called when the signal handler finishes. It should restore the signal mask
+    let restorer: Box<dyn Fn(u64)> = Box::new(move |cageid| {
+        let cage = get_cage(cageid).unwrap();
+        if let Some(thread) = cage.epoch_handler.get(&threadid) {
+            thread.sigset.store(restore_sigset, Ordering::Relaxed);
+        };
+    });
+
+    // retrieve the corresponding signal handler
+    let sigaction = cage.signalhandler.get_mut(&signo);
+    match sigaction {
+        Some(mut sigaction) => {
+            // if sigprocmask is called during the execution of the signal handler
+            // the signal mask will not be perseved once handler is finished
+
+            // by default, we block the same signal during its execution
+            let mut mask_self = convert_signal_mask(signo);
+            let signal_handler = sigaction.sa_handler;
+            let sa_flags = sigaction.sa_flags;
+            // if SA_RESETHAND is set, we reset the signal handler to default for this signal
+            if sa_flags as u32 & SA_RESETHAND > 0 {
+                sigaction.sa_handler = SIG_DFL as u32;
             }
-            None => {
-                // retrieve the signal handler
-                // if no signal handler is found, SIG_DFL will be returned
-                let signal_handler = signal_get_handler(cageid, signo);
-                let restorer = Box::new(move |cageid| {
-                    let cage = get_cage(cageid).unwrap();
-                    cage.sigset.store(sigset, Ordering::Relaxed);
-                });
-                Some((signo, signal_handler, restorer))
+
+            // if SA_NODEFER is set, we allow the same signal to interrupt itself
+            if sa_flags as u32 & SA_NODEFER > 0 {
+                mask_self = 0;
             }
+            // temporily update the signal mask of the thread
+            thread
+                .sigset
+                .fetch_or(sigaction.sa_mask | mask_self, Ordering::Relaxed);
+            (signal_handler, sa_flags, restorer)
         }
-    } else {
-        // if there is no pending unblocked signal, we return None
-        None
+        // if no signal handler is found, the signal has the default disposition
+        None => (SIG_DFL as u32, 0, restorer),
     }
 }
 
-// check if there is any pending unblocked signals
+// check if there is any pending signal thread `threadid` of the cage does not block
 // return true if no pending unblocked signals are found
-// thread safety: this function will only be invoked by main thread of the cage
-pub fn lind_check_no_pending_signal(cageid: u64) -> bool {
+// thread safety: this function will only be invoked by the thread itself
+pub fn lind_check_no_pending_signal(cageid: u64, threadid: i32) -> bool {
     let cage = get_cage(cageid).unwrap();
-    let pending_signals = cage.pending_signals.read();
-
-    // iterate through each pending signal
-    if let Some(_index) = pending_signals.iter().position(
-        // check if the signal is blocked
-        |&signo| !signal_check_block(cageid, signo),
-    ) {
-        false
-    } else {
-        true
-    }
+    let Some(thread) = cage.epoch_handler.get(&threadid) else {
+        return true;
+    };
+    let sigset = thread.sigset.load(Ordering::Relaxed);
+    !thread_has_unblocked(&cage, &thread, sigset)
 }
 
-// initialize the signal for a new thread
+// initialize the signal for a new thread, and start accounting for its CPU time
+// the thread starts with signal mask `sigset` and alternate signal stack `altstack`, which its
+// creator read from its own thread: the mask for a new thread of the cage, both for the first
+// thread of a forked cage
+// this runs on the new thread itself, which from then on is known as thread `threadid` of the cage
 // thread safety: this function could possibly be invoked by multiple threads of the same cage
-pub fn lind_signal_init(cageid: u64, epoch_handler: *mut u64, threadid: i32, is_mainthread: bool) {
+pub fn lind_signal_init(
+    cageid: u64,
+    epoch_handler: *mut u64,
+    threadid: i32,
+    is_mainthread: bool,
+    sigset: u64,
+    altstack: StackStruct,
+) {
     let cage = get_cage(cageid).unwrap();
 
     // if this is specified as the main thread, then replace the main_threadid field in cage
@@ -312,11 +672,18 @@ pub fn lind_signal_init(cageid: u64, epoch_handler: *mut u64, threadid: i32, is_
         let mut threadid_guard = cage.main_threadid.write();
         *threadid_guard = threadid;
     }
-    let epoch_handler = RwLock::new(epoch_handler);
-    cage.epoch_handler.insert(threadid, epoch_handler);
+    cage.epoch_handler.insert(
+        threadid,
+        ThreadSignals::new(epoch_handler, sigset, altstack),
+    );
+    CURRENT_THREAD.set(Some((cageid, threadid)));
+    // this runs on the new thread itself, which is how its CPU time is found
+    cage.cpu.thread_start(threadid);
 }
 
 // clean up signal stuff for an exited thread
+// the signals sent to the thread alone are lost with it, the process-directed ones it was to
+// handle are handed to another thread
 // return true if this is the last thread in the cage, otherwise return false
 pub fn lind_thread_exit(cageid: u64, thread_id: u64) -> bool {
     let cage = get_cage(cageid).unwrap();
@@ -334,45 +701,32 @@ pub fn lind_thread_exit(cageid: u64, thread_id: u64) -> bool {
             .iter()
             .find(|entry| *entry.key() as u64 != thread_id)
         {
-            let id = *entry.key();
-            *threadid_guard = id;
-
-            // Migrate epoch state to the new main thread. Skipped entirely when
-            // signals are disabled since the epoch pointer is null.
-            #[cfg(not(feature = "disable_signals"))]
-            {
-                let state = get_epoch_state(cageid, thread_id);
-
-                // if the exiting thread has pending signals, migrate to the newly assigned main-thread
-                // NOTE: below implementation of signal migration between threads is based on the assumption
-                // that EPOCH_KILLED state will only occur when all the threads is in EPOCH_KILLED state
-                // which holds true for now since EPOCH_KILLED is currently only used in epoch_kill_all
-                if state == EPOCH_SIGNAL {
-                    let new_thread_epoch_handler = entry.value().write();
-                    let new_thread_epoch = *new_thread_epoch_handler;
-                    unsafe {
-                        // make sure not to overwrite EPOCH_KILLED
-                        if *new_thread_epoch != EPOCH_KILLED {
-                            *new_thread_epoch = state;
-                        }
-                    };
-                }
-            }
+            *threadid_guard = *entry.key();
         } else {
             // we just exited the last thread in the cage
             last_thread = true;
         }
     }
+    // the CPU time of the thread stays with the cage
+    cage.cpu.thread_exit(thread_id as i32);
     // remove the epoch handler of the thread
     cage.epoch_handler
         .remove(&(thread_id as i32))
         .expect("thread id does not exist!");
+    drop(threadid_guard);
+    if current_cage_thread(cageid) == Some(thread_id as i32) {
+        CURRENT_THREAD.set(None);
+    }
 
+    if !last_thread {
+        signal_may_trigger(cageid);
+    }
     last_thread
 }
 
-// trigger the epoch if pending signal list is not empty
-// This function is invoked only by a newly exec-ed cage
+// trigger the epoch of every thread of the cage that has a pending signal it does not block.
+// Each process-directed signal goes to one thread, see `process_signal_target`.
+// Besides delivering newly sent signals, this is invoked by a newly exec-ed cage
 // immediately after it completes its initialization.
 // Its purpose is to handle the scenario where Linux resets
 // the signal mask but preserves pending signals after exec.
@@ -380,8 +734,33 @@ pub fn lind_thread_exit(cageid: u64, thread_id: u64) -> bool {
 // pending in the previous process right after it starts.
 pub fn signal_may_trigger(cageid: u64) {
     let cage = get_cage(cageid).unwrap();
-    let pending_signals = cage.pending_signals.read();
-    if !pending_signals.is_empty() {
-        signal_epoch_trigger(cageid);
+    let mut targets = vec![];
+    for entry in cage.epoch_handler.iter() {
+        let sigset = entry.sigset.load(Ordering::Relaxed);
+        if entry
+            .pending_signals
+            .read()
+            .iter()
+            .any(|&signo| (sigset & convert_signal_mask(signo)) == 0)
+        {
+            targets.push(*entry.key());
+        }
+    }
+    let pending_signals = cage.pending_signals.read().clone();
+    targets.extend(
+        pending_signals
+            .iter()
+            .filter_map(|&signo| process_signal_target(&cage, signo)),
+    );
+    if targets.is_empty() {
+        return;
+    }
+    targets.sort_unstable();
+    targets.dedup();
+    for threadid in targets {
+        if let Some(thread) = cage.epoch_handler.get(&threadid) {
+            thread_epoch_signal(&thread);
+        }
     }
+    signal_wakeup(cageid);
 }
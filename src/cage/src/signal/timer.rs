@@ -1,130 +1,539 @@
-//! Interval timer implementation for `itimer` and `SIGALRM`
+//! Timers of the cages: `setitimer()` / `alarm()` and POSIX timers (`timer_create()`)
 //!
-//! This file emulates a per-Cage `ITIMER_REAL` that counts down wall-clock time and
-//! delivers `SIGALRM` when it expires. We chose to implement `itimer` / `SIGALRM` entirely
-//! in user space rather than relying on the host kernel because of how our runtime manages
-//! signals:
+//! Timers are implemented entirely in user space rather than relying on the host kernel
+//! because of how our runtime manages signals:
 //! Host timers (e.g., `setitimer`) deliver signals to host processes or threads. Our runtime,
 //! however, models Cages as logical processes inside a Wasm environment. The kernel cannot
 //! deliver a timer interrupt specifically to a Cage or its designated “main thread.” A
-//! user-space timer gives us precise control over which Cage receives `SIGALRM`.
+//! user-space timer gives us precise control over which Cage receives the signal.
 //! All signal delivery in our system is mediated through the epoch-based mechanism.
 //! (See our online design doc for more details.)
+//!
+//! The timers of all cages are served by a single thread, `lind-timer`. It keeps the wakeups
+//! of the armed timers in a heap and sleeps on a condition variable until the earliest is due,
+//! so a timer fires within tens of microseconds of its deadline. Arming a timer that is due
+//! before all the others wakes the thread to sleep for less.
+//!
+//! A timer counts one of the clocks of `TimerClock`. A CPU time clock advances no faster than
+//! wall-clock time on each of the threads it counts, so its timer is checked again after the
+//! remaining time divided by the number of threads, but no more often than every
+//! `CPU_TIMER_GRANULARITY`.
+//!
+//! An expired timer raises its signal in the cage with `lind_send_signal`. As with the
+//! standard signals of Linux, an expiry while the timer's signal is still pending does not
+//! raise it again but counts as an overrun, which `timer_getoverrun()` reports.
+//!
+//! The realtime clocks read as the cage sees them, moved by its `realtime_offset`, see
+//! `cage_clock_now`. An absolute timer on one of them expires when the cage's clock reaches it.
+//!
+//! In deterministic mode the timers count the virtual clocks instead. Their wakeups are kept
+//! in a heap of their own, which is served by whichever thread moves the virtual clock forward
+//! rather than by `lind-timer`.
+use super::cputime::clock_read;
 use super::lind_send_signal;
-use std::sync::{Arc, Mutex, MutexGuard};
+use crate::cage::get_cage;
+use crate::deterministic::{is_deterministic, virtual_clock, virtual_now};
+use once_cell::sync::Lazy;
+use parking_lot::{Condvar, Mutex, MutexGuard};
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::thread;
 pub use std::time::Duration;
 pub use std::time::Instant;
 use std::time::SystemTime;
-use sysdefs::constants::SIGALRM;
+use sysdefs::constants::{SIGALRM, SIGPROF, SIGVTALRM};
 
-#[derive(Debug)]
-struct _IntervalTimer {
-    pub cageid: u64,
-    pub init_instant: Instant, // The instant this process is created
+/// How often a CPU time timer is checked at most
+const CPU_TIMER_GRANULARITY: Duration = Duration::from_millis(1);
 
-    pub start_instant: Instant,
-    pub curr_duration: Duration,
-    pub next_duration: Duration,
+/// Stale wakeups the heap may hold before they are pruned
+const STALE_WAKEUPS_MAX: usize = 1024;
 
-    pub is_ticking: bool,
+/// The clock a timer counts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimerClock {
+    /// A host clock that advances with wall-clock time, like `CLOCK_MONOTONIC` for
+    /// `ITIMER_REAL`
+    Host(libc::clockid_t),
+    /// User plus system time of the cage (`ITIMER_PROF`, `CLOCK_PROCESS_CPUTIME_ID`)
+    CageCpu,
+    /// User time of the cage (`ITIMER_VIRTUAL`)
+    CageUser,
+    /// CPU time of one host thread, by its CPU clock (`CLOCK_THREAD_CPUTIME_ID`)
+    Thread(libc::clockid_t),
 }
 
-#[derive(Clone, Debug)]
-pub struct IntervalTimer {
-    _ac: Arc<Mutex<_IntervalTimer>>,
+#[derive(Debug, Default)]
+struct TimerState {
+    /// Expiry, as a reading of the timer's clock. `None` while disarmed.
+    deadline: Option<Duration>,
+    interval: Duration,
+    /// Overruns of the signal raised last
+    overrun: i32,
+    /// Overruns of the signal raised before, reported while the last one is pending
+    overrun_last: i32,
 }
 
-impl IntervalTimer {
-    pub fn new(cageid: u64) -> Self {
-        Self {
-            _ac: Arc::new(Mutex::new(_IntervalTimer {
-                cageid: cageid,
-                init_instant: Instant::now(),
-                start_instant: Instant::now(),
-                curr_duration: Duration::ZERO,
-                next_duration: Duration::ZERO,
-                is_ticking: false,
-            })),
+/// A timer of a cage
+#[derive(Debug)]
+pub struct Timer {
+    cageid: u64,
+    clock: TimerClock,
+    /// The signal raised on expiry, `None` for `SIGEV_NONE`
+    signo: Option<i32>,
+    /// Bumped whenever the timer is set, so that the wakeups of earlier settings are dropped
+    generation: AtomicU64,
+    state: Mutex<TimerState>,
+}
+
+impl Timer {
+    pub fn new(cageid: u64, clock: TimerClock, signo: Option<i32>) -> Arc<Self> {
+        Arc::new(Self {
+            cageid,
+            clock,
+            signo,
+            generation: AtomicU64::new(0),
+            state: Mutex::new(TimerState::default()),
+        })
+    }
+
+    /// The current reading of the timer's clock, `None` if the cage or thread whose time it
+    /// counts is gone
+    pub fn clock_now(&self) -> Option<Duration> {
+        match self.clock {
+            TimerClock::Host(clockid) => Some(realtime_shift(
+                self.cageid,
+                clockid,
+                virtual_clock(clockid).or_else(|| clock_read(clockid))?,
+            )),
+            TimerClock::Thread(clockid) => clock_read(clockid),
+            TimerClock::CageCpu => Some(get_cage(self.cageid)?.cpu.total()),
+            TimerClock::CageUser => Some(get_cage(self.cageid)?.cpu.times().user),
+        }
+    }
+
+    /// How long to wait before checking on a timer `remaining` away from its deadline
+    fn wake_after(&self, remaining: Duration) -> Duration {
+        // The virtual CPU time of a cage never advances faster than the virtual clock
+        if is_deterministic() {
+            return remaining;
+        }
+        match self.clock {
+            TimerClock::Host(_) => remaining,
+            TimerClock::CageCpu | TimerClock::CageUser => {
+                let threads = get_cage(self.cageid).map_or(1, |cage| cage.cpu.threads());
+                (remaining / threads.max(1) as u32).max(CPU_TIMER_GRANULARITY)
+            }
+            TimerClock::Thread(_) => remaining.max(CPU_TIMER_GRANULARITY),
         }
     }
 
-    // Similar to getitimer. Returns (current value, next value)
-    pub fn get_itimer(&self) -> (Duration, Duration) {
-        let guard = self._ac.lock().unwrap();
+    fn remaining(&self, state: &TimerState) -> Duration {
+        let Some(deadline) = state.deadline else {
+            return Duration::ZERO;
+        };
+        match self.clock_now() {
+            // A timer that is due but has not been served yet is still armed, like on Linux
+            Some(now) => deadline.saturating_sub(now).max(Duration::from_micros(1)),
+            None => Duration::ZERO,
+        }
+    }
 
-        (guard.curr_duration, guard.next_duration)
+    /// The time until the timer expires, zero if it is disarmed, and its interval, like
+    /// `getitimer()` and `timer_gettime()` report them
+    pub fn get(&self) -> (Duration, Duration) {
+        let state = self.state.lock();
+        (self.remaining(&state), state.interval)
     }
 
-    fn _set_itimer(
-        &self,
-        guard: &mut MutexGuard<_IntervalTimer>,
-        curr_duration: Duration,
-        next_duration: Duration,
-    ) {
-        if curr_duration.is_zero() {
-            guard.is_ticking = false;
-        } else {
-            guard.start_instant = Instant::now();
-            guard.curr_duration = curr_duration;
-            guard.next_duration = next_duration;
-
-            if !guard.is_ticking {
-                guard.is_ticking = true;
-
-                let self_dup = self.clone();
-                thread::spawn(move || {
-                    // There is a chance that there'll be two ticking threads running
-                    // at the same time
-                    self_dup.tick();
-                });
+    /// Arm the timer to expire at `value` and then every `interval`. `value` is a reading of
+    /// the timer's clock if `absolute`, like with `TIMER_ABSTIME`, and relative to now
+    /// otherwise. A zero `value` disarms the timer.
+    ///
+    /// ## Returns:
+    /// - the previous setting, like `get()`
+    pub fn set(
+        self: &Arc<Self>,
+        value: Duration,
+        interval: Duration,
+        absolute: bool,
+    ) -> (Duration, Duration) {
+        let mut state = self.state.lock();
+        let old = (self.remaining(&state), state.interval);
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        state.interval = interval;
+        state.overrun = 0;
+        state.overrun_last = 0;
+        state.deadline = None;
+
+        if !value.is_zero() {
+            if let Some(now) = self.clock_now() {
+                let deadline = if absolute {
+                    value
+                } else {
+                    now.saturating_add(value)
+                };
+                state.deadline = Some(deadline);
+                schedule(
+                    self,
+                    generation,
+                    self.wake_after(deadline.saturating_sub(now)),
+                );
             }
         }
+        old
+    }
+
+    /// Disarm the timer
+    pub fn disarm(&self) {
+        let mut state = self.state.lock();
+        self.generation.fetch_add(1, Ordering::Relaxed);
+        state.deadline = None;
+    }
+
+    /// The overrun count of the last signal the timer raised that was delivered, like
+    /// `timer_getoverrun()`
+    pub fn overrun(&self) -> i32 {
+        let state = self.state.lock();
+        match self.signo {
+            Some(signo) if signal_pending(self.cageid, signo) => state.overrun_last,
+            _ => state.overrun,
+        }
+    }
+
+    /// Serve a wakeup of the timer: raise its signal if it expired, schedule the next one
+    fn expire(self: &Arc<Self>, generation: u64) {
+        let mut state = self.state.lock();
+        if self.generation.load(Ordering::Relaxed) != generation {
+            return;
+        }
+        let Some(deadline) = state.deadline else {
+            return;
+        };
+        let Some(now) = self.clock_now() else {
+            state.deadline = None;
+            return;
+        };
+        if now < deadline {
+            schedule(self, generation, self.wake_after(deadline - now));
+            return;
+        }
+
+        // Whole periods that went by since the deadline count as overruns
+        let mut missed = 0;
+        if state.interval.is_zero() {
+            state.deadline = None;
+        } else {
+            let periods = (now - deadline).as_nanos() / state.interval.as_nanos();
+            missed = periods.min(i32::MAX as u128) as i32;
+            let next = deadline.saturating_add(Duration::from_nanos(
+                ((periods + 1) * state.interval.as_nanos()).min(u64::MAX as u128) as u64,
+            ));
+            state.deadline = Some(next);
+            schedule(self, generation, self.wake_after(next - now));
+        }
+
+        let Some(signo) = self.signo else {
+            return;
+        };
+        if signal_pending(self.cageid, signo) {
+            state.overrun = state.overrun.saturating_add(missed).saturating_add(1);
+        } else if lind_send_signal(self.cageid, signo) {
+            state.overrun_last = state.overrun;
+            state.overrun = missed;
+        } else {
+            // The cage is gone
+            state.deadline = None;
+        }
+    }
+}
+
+fn signal_pending(cageid: u64, signo: i32) -> bool {
+    get_cage(cageid).is_some_and(|cage| cage.pending_signals.read().contains(&signo))
+}
+
+/// A point in time the timer service has to check on a timer
+#[derive(Debug)]
+struct Wakeup {
+    /// Time since `SERVICE_START`, or a reading of the virtual clock in deterministic mode
+    at: Duration,
+    generation: u64,
+    timer: Arc<Timer>,
+}
+
+impl PartialEq for Wakeup {
+    fn eq(&self, other: &Self) -> bool {
+        self.at == other.at
+    }
+}
+
+impl Eq for Wakeup {}
+
+impl PartialOrd for Wakeup {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
     }
+}
 
-    pub fn set_itimer(&self, curr_duration: Duration, next_duration: Duration) {
-        let mut guard = self._ac.lock().unwrap();
-        self._set_itimer(&mut guard, curr_duration, next_duration);
+impl Ord for Wakeup {
+    // Reversed, so that the heap pops the earliest wakeup first
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other.at.cmp(&self.at)
     }
+}
 
-    pub fn tick(&self) {
-        loop {
-            {
-                let mut guard = self._ac.lock().unwrap();
+struct TimerService {
+    queue: Mutex<BinaryHeap<Wakeup>>,
+    wakeup: Condvar,
+}
 
-                if guard.is_ticking {
-                    let remaining_seconds = guard
-                        .curr_duration
-                        .saturating_sub(guard.start_instant.elapsed());
+static SERVICE_START: Lazy<Instant> = Lazy::new(Instant::now);
 
-                    if remaining_seconds == Duration::ZERO {
-                        // Sends a SIGALRM signal to the cage when the timer expires.
-                        // This struct/method is used exclusively by the setitimer and alarm syscall,
-                        // which is expected to send a SIGALRM signal upon expiration.
-                        lind_send_signal(guard.cageid, SIGALRM);
+/// The wakeups due on the virtual clock, in deterministic mode
+static VIRTUAL_QUEUE: Mutex<BinaryHeap<Wakeup>> = Mutex::new(BinaryHeap::new());
 
-                        let new_curr_duration = guard.next_duration;
-                        // Repeat the intervals until user cancel it
-                        let new_next_duration = guard.next_duration;
+static SERVICE: Lazy<TimerService> = Lazy::new(|| {
+    thread::Builder::new()
+        .name("lind-timer".to_string())
+        .spawn(serve)
+        .expect("Failed to start the timer thread");
+    TimerService {
+        queue: Mutex::new(BinaryHeap::new()),
+        wakeup: Condvar::new(),
+    }
+});
 
-                        self._set_itimer(&mut guard, new_curr_duration, new_next_duration);
-                        // Calling self.set_itimer will automatically turn of the timer if
-                        // next_duration is ZERO
-                    }
-                } else {
-                    break;
-                }
+/// Add a wakeup to `queue`, return whether it is the earliest
+fn push(queue: &mut BinaryHeap<Wakeup>, wakeup: Wakeup) -> bool {
+    if queue.len() >= STALE_WAKEUPS_MAX {
+        // Timers set over and over again leave the wakeups of their earlier settings behind
+        queue.retain(|w| w.generation == w.timer.generation.load(Ordering::Relaxed));
+    }
+    let earliest = queue.peek().is_none_or(|first| wakeup.at < first.at);
+    queue.push(wakeup);
+    earliest
+}
+
+/// Have the timer service check on `timer` after `after`
+fn schedule(timer: &Arc<Timer>, generation: u64, after: Duration) {
+    if is_deterministic() {
+        let wakeup = Wakeup {
+            at: virtual_now().saturating_add(after),
+            generation,
+            timer: timer.clone(),
+        };
+        push(&mut VIRTUAL_QUEUE.lock(), wakeup);
+        return;
+    }
+
+    let at = SERVICE_START.elapsed().saturating_add(after);
+    // Too far out to ever be reached
+    if SERVICE_START.checked_add(at).is_none() {
+        return;
+    }
+    let wakeup = Wakeup {
+        at,
+        generation,
+        timer: timer.clone(),
+    };
+    if push(&mut SERVICE.queue.lock(), wakeup) {
+        SERVICE.wakeup.notify_one();
+    }
+}
+
+/// When the earliest timer on the virtual clock is due
+pub(crate) fn virtual_next_wakeup() -> Option<Duration> {
+    VIRTUAL_QUEUE.lock().peek().map(|w| w.at)
+}
+
+/// Serve the wakeups on the virtual clock that are due by `now`
+pub(crate) fn virtual_expire(now: Duration) {
+    loop {
+        let wakeup = {
+            let mut queue = VIRTUAL_QUEUE.lock();
+            match queue.peek() {
+                Some(first) if first.at <= now => queue.pop().unwrap(),
+                _ => return,
+            }
+        };
+        wakeup.timer.expire(wakeup.generation);
+    }
+}
+
+/// Body of the `lind-timer` thread
+fn serve() {
+    let mut queue = SERVICE.queue.lock();
+    loop {
+        match queue.peek() {
+            None => SERVICE.wakeup.wait(&mut queue),
+            Some(first) if first.at > SERVICE_START.elapsed() => {
+                let at = *SERVICE_START + first.at;
+                SERVICE.wakeup.wait_until(&mut queue, at);
+            }
+            Some(_) => {
+                let wakeup = queue.pop().unwrap();
+                // `expire` schedules the timer again and raises signals, which needs the
+                // queue unlocked
+                MutexGuard::unlocked(&mut queue, || wakeup.timer.expire(wakeup.generation));
             }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PosixTimers {
+    next_id: i32,
+    timers: HashMap<i32, Arc<Timer>>,
+}
+
+/// The timers of a cage: its interval timers and the POSIX timers it created
+#[derive(Debug)]
+pub struct CageTimers {
+    cageid: u64,
+    /// `ITIMER_REAL`, `ITIMER_VIRTUAL` and `ITIMER_PROF`, indexed by `which`
+    itimers: [Arc<Timer>; 3],
+    posix: Mutex<PosixTimers>,
+}
 
-            thread::sleep(Duration::from_millis(20)); // One jiffy
+impl CageTimers {
+    pub fn new(cageid: u64) -> Self {
+        Self {
+            cageid,
+            itimers: [
+                Timer::new(
+                    cageid,
+                    TimerClock::Host(libc::CLOCK_MONOTONIC),
+                    Some(SIGALRM),
+                ),
+                Timer::new(cageid, TimerClock::CageUser, Some(SIGVTALRM)),
+                Timer::new(cageid, TimerClock::CageCpu, Some(SIGPROF)),
+            ],
+            posix: Mutex::new(PosixTimers::default()),
+        }
+    }
+
+    /// The interval timer `which`, `None` if there is no such timer
+    pub fn itimer(&self, which: i32) -> Option<&Arc<Timer>> {
+        usize::try_from(which)
+            .ok()
+            .and_then(|i| self.itimers.get(i))
+    }
+
+    /// Create a disarmed POSIX timer and return its id
+    pub fn create(&self, clock: TimerClock, signo: Option<i32>) -> i32 {
+        let mut posix = self.posix.lock();
+        let mut id = posix.next_id;
+        while posix.timers.contains_key(&id) {
+            id = id.checked_add(1).unwrap_or(0);
+        }
+        posix.next_id = id.checked_add(1).unwrap_or(0);
+        posix
+            .timers
+            .insert(id, Timer::new(self.cageid, clock, signo));
+        id
+    }
+
+    /// The POSIX timer `id`
+    pub fn get(&self, id: i32) -> Option<Arc<Timer>> {
+        self.posix.lock().timers.get(&id).cloned()
+    }
+
+    /// Delete the POSIX timer `id`, return whether it existed
+    pub fn delete(&self, id: i32) -> bool {
+        match self.posix.lock().timers.remove(&id) {
+            Some(timer) => {
+                timer.disarm();
+                true
+            }
+            None => false,
         }
     }
 
-    pub fn clone_with_new_cageid(&self, cageid: u64) -> Self {
-        let mut guard = self._ac.lock().unwrap();
-        guard.cageid = cageid;
+    /// Delete the POSIX timers, which do not survive `exec`. The interval timers do.
+    pub fn exec(&self) {
+        let mut posix = self.posix.lock();
+        for (_, timer) in posix.timers.drain() {
+            timer.disarm();
+        }
+        posix.next_id = 0;
+    }
 
-        self.clone()
+    /// Disarm every timer of an exiting cage
+    pub fn clear(&self) {
+        self.exec();
+        for timer in &self.itimers {
+            timer.disarm();
+        }
+    }
+}
+
+/// Whether `clockid` tells the time of day, which the `realtime_offset` of a cage moves
+pub fn is_realtime_clock(clockid: libc::clockid_t) -> bool {
+    matches!(
+        clockid,
+        libc::CLOCK_REALTIME
+            | libc::CLOCK_REALTIME_COARSE
+            | libc::CLOCK_REALTIME_ALARM
+            | libc::CLOCK_TAI
+    )
+}
+
+/// `time` moved by `offset` nanoseconds, which may be negative
+pub fn offset_time(time: Duration, offset: i64) -> Duration {
+    let by = Duration::from_nanos(offset.unsigned_abs());
+    if offset < 0 {
+        time.saturating_sub(by)
+    } else {
+        time.saturating_add(by)
+    }
+}
+
+/// A reading `now` of the host's (or the virtual) clock `clockid` as cage `cageid` sees it
+fn realtime_shift(cageid: u64, clockid: libc::clockid_t, now: Duration) -> Duration {
+    if !is_realtime_clock(clockid) {
+        return now;
+    }
+    let offset = get_cage(cageid).map_or(0, |cage| cage.realtime_offset.load(Ordering::Relaxed));
+    offset_time(now, offset)
+}
+
+/// A reading of clock `clockid` as cage `cageid` sees it. The CPU time clocks count the time of
+/// the cage, and of the calling thread for `CLOCK_THREAD_CPUTIME_ID`; the realtime clocks are
+/// moved by the cage's `realtime_offset`.
+///
+/// ## Returns:
+/// - `None` for a clock that does not exist, the clock of another thread, or the CPU clock of
+///   a process that is not a cage
+pub fn cage_clock_now(cageid: u64, clockid: libc::clockid_t) -> Option<Duration> {
+    let cage = get_cage(cageid)?;
+    match clockid {
+        libc::CLOCK_PROCESS_CPUTIME_ID => Some(cage.cpu.total()),
+        libc::CLOCK_THREAD_CPUTIME_ID => Some(cage.cpu.thread_total()),
+        // clock_getcpuclockid() makes the clock of process `pid` as `~pid << 3`, with the kind of
+        // time in the low bits: 0 for user plus system, 1 for user, 2 for scheduled, and 4 set
+        // for the clock of a thread rather than a process
+        clockid if clockid < 0 => {
+            if clockid & 4 != 0 {
+                return None;
+            }
+            let pid = !(clockid >> 3);
+            let target = if pid == 0 {
+                cage
+            } else {
+                get_cage(pid as u64)?
+            };
+            match clockid & 3 {
+                0 | 2 => Some(target.cpu.total()),
+                1 => Some(target.cpu.times().user),
+                _ => None,
+            }
+        }
+        clockid => Some(realtime_shift(
+            cageid,
+            clockid,
+            virtual_clock(clockid).or_else(|| clock_read(clockid))?,
+        )),
     }
 }
 
@@ -168,3 +577,74 @@ pub fn timeout_setup_ms(timeout_ms: i32) -> (Duration, i32) {
 
     (end_time, timeout)
 }
+
+// Like timeout_setup_ms, for the nanosecond timeouts of ppoll, pselect6 and epoll_pwait2.
+// `None` waits forever. The chunk is rounded up to a whole millisecond so that a timeout
+// shorter than one still blocks instead of spinning.
+pub fn timeout_setup(timeout: Option<Duration>) -> (Duration, i32) {
+    match timeout {
+        Some(end_time) => {
+            let chunk = end_time.as_nanos().div_ceil(1_000_000).min(100);
+            (end_time, chunk as i32)
+        }
+        None => (Duration::MAX, 100),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_expires_and_disarms() {
+        // SIGEV_NONE needs no cage to raise a signal in
+        let timer = Timer::new(0, TimerClock::Host(libc::CLOCK_MONOTONIC), None);
+        timer.set(Duration::from_millis(20), Duration::ZERO, false);
+        let (remaining, interval) = timer.get();
+        assert!(remaining > Duration::from_millis(10) && remaining <= Duration::from_millis(20));
+        assert_eq!(interval, Duration::ZERO);
+
+        thread::sleep(Duration::from_millis(40));
+        assert_eq!(timer.get(), (Duration::ZERO, Duration::ZERO));
+    }
+
+    #[test]
+    fn test_timer_set_returns_previous_setting() {
+        let timer = Timer::new(0, TimerClock::Host(libc::CLOCK_MONOTONIC), None);
+        timer.set(Duration::from_secs(10), Duration::from_secs(1), false);
+        let (remaining, interval) = timer.set(Duration::ZERO, Duration::ZERO, false);
+        assert!(remaining > Duration::from_secs(9));
+        assert_eq!(interval, Duration::from_secs(1));
+        assert_eq!(timer.get(), (Duration::ZERO, Duration::ZERO));
+    }
+
+    #[test]
+    fn test_posix_timer_ids() {
+        let timers = CageTimers::new(0);
+        let clock = TimerClock::Host(libc::CLOCK_REALTIME);
+        assert_eq!(timers.create(clock, None), 0);
+        assert_eq!(timers.create(clock, None), 1);
+        assert!(timers.delete(0));
+        assert!(!timers.delete(0));
+        assert!(timers.get(1).is_some());
+
+        // exec deletes them all and starts over
+        timers.exec();
+        assert!(timers.get(1).is_none());
+        assert_eq!(timers.create(clock, None), 0);
+        assert!(timers.itimer(2).is_some());
+        assert!(timers.itimer(3).is_none());
+    }
+
+    #[test]
+    fn test_offset_time() {
+        let time = Duration::from_secs(100);
+        assert_eq!(offset_time(time, 0), time);
+        assert_eq!(
+            offset_time(time, 1_500_000_000),
+            Duration::from_millis(101_500)
+        );
+        assert_eq!(offset_time(time, -1_000_000_000), Duration::from_secs(99));
+        assert_eq!(offset_time(time, i64::MIN), Duration::ZERO);
+    }
+}
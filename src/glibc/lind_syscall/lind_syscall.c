@@ -18,6 +18,18 @@ int __lind_make_syscall_trampoline(unsigned int callnumber,
     __import_name__("make-syscall")
 ));
 
+// Runs the handlers of the pending signals of the calling thread, the same
+// callback the epoch checks wasmtime inserts into the guest code call
+void __lind_epoch_callback(void) __attribute__((
+    __import_module__("lind"),
+    __import_name__("epoch_callback")
+));
+
+// Kernel internal errno of a blocking call that a signal interrupted and
+// that is to be made again once the handler has run, see rawposix's
+// interrupt module. It never reaches the application.
+#define LIND_ERESTARTSYS 512
+
 /*
  * make_threei_call:
  *
@@ -65,6 +77,11 @@ int __lind_make_syscall_trampoline(unsigned int callnumber,
  * trampoline to return raw -errno value and must not receive additional errno 
  * post-processing at this layer. Other syscalls, however, rely on the standard 
  * POSIX errno translation implemented here.
+ *
+ * Either way, a blocking call that a signal with an SA_RESTART handler
+ * interrupted returns -LIND_ERESTARTSYS. The handler is run right away and
+ * the call is made again, as the Linux kernel does on the way back to user
+ * space.
  */
 int make_threei_call (unsigned int callnumber, 
     uint64_t callname, 
@@ -77,15 +94,22 @@ int make_threei_call (unsigned int callnumber,
     uint64_t arg6, uint64_t arg6cageid,
     int translate_errno)
 {
-    int ret = __lind_make_syscall_trampoline(callnumber, 
-        callname, 
-        self_cageid, target_cageid,
-        arg1, arg1cageid,
-        arg2, arg2cageid,
-        arg3, arg3cageid,
-        arg4, arg4cageid,
-        arg5, arg5cageid,
-        arg6, arg6cageid);
+    int ret;
+    do
+    {
+        ret = __lind_make_syscall_trampoline(callnumber, 
+            callname, 
+            self_cageid, target_cageid,
+            arg1, arg1cageid,
+            arg2, arg2cageid,
+            arg3, arg3cageid,
+            arg4, arg4cageid,
+            arg5, arg5cageid,
+            arg6, arg6cageid);
+        if (ret != -LIND_ERESTARTSYS)
+            break;
+        __lind_epoch_callback();
+    } while (1);
     // if translate_errno is not enabled, we do not do any further process to errno handling and directly return the result
     if(translate_errno == 0) return ret;
     // handle the errno
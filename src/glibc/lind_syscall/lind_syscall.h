@@ -14,6 +14,8 @@
  *   - Invoke threei style syscalls via make_threei_call().
  *   - Register or deregister grate-level syscall handlers via register_handler().
  *   - Copy data between cages in a controlled way via copy_data_between_cages().
+ *
+ * It also defines the socket options only lind has.
  */
 
 #include <stdint.h> // For uint64_t definition
@@ -39,4 +41,10 @@ int copy_data_between_cages(uint64_t thiscage, uint64_t targetcage,
     uint64_t destaddr, uint64_t destcage, 
     uint64_t len, uint64_t copytype);
 
+/*
+ * SOL_SOCKET option: no SIGPIPE on writes to the socket, like SO_NOSIGPIPE on the
+ * BSDs.  Linux has no such option, MSG_NOSIGNAL is the portable way per send.
+ */
+#define SO_LIND_NOSIGPIPE 0x1022
+
 #endif // _LIND_SYSCALL_H
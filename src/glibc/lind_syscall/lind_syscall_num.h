@@ -31,6 +31,7 @@
 #define IOCTL_SYSCALL 16
 #define PREAD_SYSCALL 17
 #define PWRITE_SYSCALL 18
+#define READV_SYSCALL 19
 
 #define WRITEV_SYSCALL 20
 #define ACCESS_SYSCALL 21
@@ -38,6 +39,10 @@
 #define SELECT_SYSCALL 23
 
 #define SCHED_YIELD_SYSCALL 24
+#define MREMAP_SYSCALL 25
+#define MSYNC_SYSCALL 26
+#define MINCORE_SYSCALL 27
+#define MADVISE_SYSCALL 28
 
 #define SHMGET_SYSCALL 29
 #define SHMAT_SYSCALL 30
@@ -47,8 +52,11 @@
 
 #define NANOSLEEP_TIME64_SYSCALL 35
 
+#define GETITIMER_SYSCALL 36
+#define ALARM_SYSCALL 37
 #define SETITIMER_SYSCALL 38
 #define GETPID_SYSCALL 39
+#define SENDFILE_SYSCALL 40
 
 #define SOCKET_SYSCALL 41
 #define CONNECT_SYSCALL 42
@@ -72,6 +80,7 @@
 #define EXIT_SYSCALL 60
 #define WAITPID_SYSCALL 61
 #define KILL_SYSCALL 62
+#define UNAME_SYSCALL 63
 
 #define SHMDT_SYSCALL 67
 
@@ -91,36 +100,69 @@
 
 #define LINK_SYSCALL 86
 #define UNLINK_SYSCALL 87
-
+#define SYMLINK_SYSCALL 88
 #define READLINK_SYSCALL 89
 #define CHMOD_SYSCALL 90
 #define FCHMOD_SYSCALL 91
 
+#define GETTIMEOFDAY_SYSCALL 96
+#define GETRUSAGE_SYSCALL 98
+#define TIMES_SYSCALL 100
+
 #define GETUID_SYSCALL 102
 #define GETGID_SYSCALL 104
 #define GETEUID_SYSCALL 107
 #define GETEGID_SYSCALL 108
 #define GETPPID_SYSCALL 110
+#define SIGALTSTACK_SYSCALL 131
 #define STATFS_SYSCALL 137
 #define FSTATFS_SYSCALL 138
 #define GETHOSTNAME_SYSCALL 170
+#define SETDOMAINNAME_SYSCALL 171
+#define GETTID_SYSCALL 186
+#define TKILL_SYSCALL 200
+#define TIME_SYSCALL 201
 #define FUTEX_SYSCALL 202
 #define EPOLL_CREATE_SYSCALL 213
+#define TIMER_CREATE_SYSCALL 222
+#define TIMER_SETTIME_SYSCALL 223
+#define TIMER_GETTIME_SYSCALL 224
+#define TIMER_GETOVERRUN_SYSCALL 225
+#define TIMER_DELETE_SYSCALL 226
+#define CLOCK_SETTIME_SYSCALL 227
 #define CLOCK_GETTIME_SYSCALL 228
+#define CLOCK_GETRES_SYSCALL 229
+#define CLOCK_NANOSLEEP_SYSCALL 230
 #define EPOLL_WAIT_SYSCALL 232
 #define EPOLL_CTL_SYSCALL 233
+#define TGKILL_SYSCALL 234
 #define UNLINKAT_SYSCALL 263
 #define READLINKAT_SYSCALL 267
+#define PSELECT6_SYSCALL 270
+#define PPOLL_SYSCALL 271
+#define SPLICE_SYSCALL 275
+#define TEE_SYSCALL 276
 #define SYNC_FILE_RANGE 277
+#define EPOLL_PWAIT_SYSCALL 281
+#define FALLOCATE_SYSCALL 285
+#define ACCEPT4_SYSCALL 288
 #define EPOLL_CREATE1_SYSCALL 291
 #define DUP3_SYSCALL 292
 #define PIPE2_SYSCALL 293
+#define PREADV_SYSCALL 295
+#define PWRITEV_SYSCALL 296
+#define RECVMMSG_SYSCALL 299
+#define SENDMMSG_SYSCALL 307
 #define GETRANDOM_SYSCALL 318
+#define COPY_FILE_RANGE_SYSCALL 326
+#define EPOLL_PWAIT2_SYSCALL 441
 
 /* Lind-specific syscalls (not part of the Linux syscall table) */
 #define REGISTER_HANDLER_SYSCALL 1001
 #define COPY_DATA_BETWEEN_CAGES_SYSCALL 1002
 #define COPY_HANDLER_TABLE_TO_CAGE_SYSCALL 1003
+/* sethostname is 170 on Linux, which GETHOSTNAME_SYSCALL uses */
+#define SETHOSTNAME_SYSCALL 1004
 
 #endif /* _LIND_SYSCALL_NUM_H */
  
@@ -20,6 +20,20 @@
 #include <unistd.h>
 #include <pthreadP.h>
 #include <shlib-compat.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+
+/* Lind: RawPOSIX keeps the thread ids of a cage, which are the ones
+   pthread_create stores in pd->tid.  Returns 0 or an error number.  */
+static int
+lind_tgkill (pid_t tid, int signo)
+{
+  int ret = MAKE_LEGACY_SYSCALL (TGKILL_SYSCALL, "syscall|tgkill",
+				 (uint64_t) __getpid (), (uint64_t) tid,
+				 (uint64_t) signo, NOTUSED, NOTUSED, NOTUSED,
+				 TRANSLATE_ERRNO_OFF);
+  return ret < 0 ? -ret : 0;
+}
 
 /* Sends SIGNO to THREADID.  If the thread is about to exit or has
    already exited on the kernel side, return NO_TID.  Otherwise return
@@ -39,9 +53,10 @@ __pthread_kill_implementation (pthread_t threadid, int signo, int no_tid)
          delivery of all pending signals after unblocking in the code
          below.  POSIX only guarantees delivery of a single signal,
          which may not be the right one.)  */
-      pid_t tid = INTERNAL_SYSCALL_CALL (gettid);
-      int ret = INTERNAL_SYSCALL_CALL (tgkill, __getpid (), tid, signo);
-      return INTERNAL_SYSCALL_ERROR_P (ret) ? INTERNAL_SYSCALL_ERRNO (ret) : 0;
+      pid_t tid = MAKE_LEGACY_SYSCALL (GETTID_SYSCALL, "syscall|gettid",
+				       NOTUSED, NOTUSED, NOTUSED, NOTUSED,
+				       NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
+      return lind_tgkill (tid, signo);
     }
 
   /* Block all signals, as required by pd->exit_lock.  */
@@ -59,8 +74,7 @@ __pthread_kill_implementation (pthread_t threadid, int signo, int no_tid)
     ret = no_tid;
   else
     {
-      ret = INTERNAL_SYSCALL_CALL (tgkill, __getpid (), pd->tid, signo);
-      ret = INTERNAL_SYSCALL_ERROR_P (ret) ? INTERNAL_SYSCALL_ERRNO (ret) : 0;
+      ret = lind_tgkill (pd->tid, signo);
     }
 
   __libc_lock_unlock (pd->exit_lock);
@@ -19,6 +19,9 @@
 #include <pthreadP.h>
 #include <sysdep.h>
 #include <shlib-compat.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 int
 __pthread_sigmask (int how, const sigset_t *newmask, sigset_t *oldmask)
@@ -36,13 +39,26 @@ __pthread_sigmask (int how, const sigset_t *newmask, sigset_t *oldmask)
       newmask = &local_newmask;
     }
 
-  /* We know that realtime signals are available if NPTL is used.  */
-  int result = INTERNAL_SYSCALL_CALL (rt_sigprocmask, how, newmask,
-				      oldmask, __NSIG_BYTES);
+  /* Lind: the mask is the calling thread's own in RawPOSIX as well.  The
+     sigset is converted like in sigprocmask, and the error number is
+     returned instead of setting errno.  */
+  unsigned long long rawposix_set, rawposix_oset;
+  if (newmask != NULL)
+    rawposix_set = newmask->__val[0];
+  int result = MAKE_LEGACY_SYSCALL (
+      SIGPROCMASK_SYSCALL, "syscall|pthread_sigmask", (uint64_t) how,
+      (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (newmask != NULL ? &rawposix_set : NULL),
+      (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (oldmask != NULL ? &rawposix_oset : NULL),
+      NOTUSED, NOTUSED, NOTUSED, TRANSLATE_ERRNO_OFF);
+  if (result < 0)
+    return -result;
+  if (oldmask != NULL)
+    oldmask->__val[0] = (unsigned long int) rawposix_oset;
+  return 0;
 
-  return (INTERNAL_SYSCALL_ERROR_P (result)
-	  ? INTERNAL_SYSCALL_ERRNO (result)
-	  : 0);
+  // Lind-Wasm: Original glibc code removed for compatibility
+  // to find original source code refer to (2.39.9000) at
+  // (/home/lind-wasm/glibc/nptl/pthread_sigmask.c):(39-44)
 }
 libc_hidden_def (__pthread_sigmask)
 
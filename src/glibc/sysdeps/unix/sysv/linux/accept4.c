@@ -18,18 +18,19 @@
 #include <errno.h>
 #include <signal.h>
 #include <sys/socket.h>
-
 #include <sysdep-cancel.h>
-#include <sys/syscall.h>
-#include <socketcall.h>
-#include <kernel-features.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 int
 accept4 (int fd, __SOCKADDR_ARG addr, socklen_t *addr_len, int flags)
 {
-#ifdef __ASSUME_ACCEPT4_SYSCALL
-  return SYSCALL_CANCEL (accept4, fd, addr.__sockaddr__, addr_len, flags);
-#else
-  return SOCKETCALL_CANCEL (accept4, fd, addr.__sockaddr__, addr_len, flags);
-#endif
+  /* addr and addr_len may be NULL, as for accept.  */
+  uint64_t host_addr = TRANSLATE_GUEST_POINTER_TO_HOST (addr.__sockaddr__);
+  uint64_t host_len = TRANSLATE_GUEST_POINTER_TO_HOST (addr_len);
+
+  return MAKE_LEGACY_SYSCALL (ACCEPT4_SYSCALL, "syscall|accept4", (uint64_t) fd,
+			      host_addr, host_len, (uint64_t) flags,
+			      NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
 }
@@ -0,0 +1,34 @@
+/* alarm -- Schedule an alarm.  Linux version.
+   Copyright (C) 1991-2024 Free Software Foundation, Inc.
+   This file is part of the GNU C Library.
+
+   The GNU C Library is free software; you can redistribute it and/or
+   modify it under the terms of the GNU Lesser General Public
+   License as published by the Free Software Foundation; either
+   version 2.1 of the License, or (at your option) any later version.
+
+   The GNU C Library is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+   Lesser General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public
+   License along with the GNU C Library; if not, see
+   <https://www.gnu.org/licenses/>.  */
+
+#include <unistd.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+
+/* Schedule an alarm.  In SECONDS seconds, the process will get a SIGALRM.
+   If SECONDS is zero, any currently scheduled alarm will be cancelled.
+   The function returns the number of seconds remaining until the last
+   alarm scheduled would have signaled, or zero if there wasn't one.  */
+unsigned int
+alarm (unsigned int seconds)
+{
+  return MAKE_LEGACY_SYSCALL (ALARM_SYSCALL, "syscall|alarm",
+			      (uint64_t) seconds, NOTUSED, NOTUSED, NOTUSED,
+			      NOTUSED, NOTUSED, TRANSLATE_ERRNO_OFF);
+}
+libc_hidden_def (alarm)
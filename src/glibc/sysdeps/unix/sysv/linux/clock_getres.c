@@ -23,38 +23,22 @@
 #include <sysdep-vdso.h>
 #include <shlib-compat.h>
 #include <kernel-features.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 /* Get resolution of clock.  */
 int
 __clock_getres64 (clockid_t clock_id, struct __timespec64 *res)
 {
-  int r;
+  uint64_t host_res = TRANSLATE_GUEST_POINTER_TO_HOST (res);
+  return MAKE_LEGACY_SYSCALL (CLOCK_GETRES_SYSCALL, "syscall|clock_getres",
+			      (uint64_t) clock_id, host_res, NOTUSED, NOTUSED,
+			      NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
 
-#ifndef __NR_clock_getres_time64
-# define __NR_clock_getres_time64 __NR_clock_getres
-#endif
-
-#ifdef HAVE_CLOCK_GETRES64_VSYSCALL
-  r = INLINE_VSYSCALL (clock_getres_time64, 2, clock_id, res);
-#else
-  r = INLINE_SYSCALL_CALL (clock_getres_time64, clock_id, res);
-#endif
-  if (r == 0 || errno != ENOSYS)
-    return r;
-
-#ifndef __ASSUME_TIME64_SYSCALLS
-  /* Fallback code that uses 32-bit support.  */
-  struct timespec ts32;
-# ifdef HAVE_CLOCK_GETRES_VSYSCALL
-  r = INLINE_VSYSCALL (clock_getres, 2, clock_id, &ts32);
-# else
-  r = INLINE_SYSCALL_CALL (clock_getres, clock_id, &ts32);
-# endif
-  if (r == 0 && res != NULL)
-    *res = valid_timespec_to_timespec64 (ts32);
-#endif
-
-  return r;
+  // Lind-Wasm: Original glibc code removed for compatibility
+  // to find original source code refer to (2.39.9000) at
+  // (/home/lind-wasm/glibc/sysdeps/unix/sysv/linux/clock_getres.c):(31-57)
 }
 
 #if __TIMESIZE != 64
@@ -27,17 +27,58 @@
 #include <syscall-template.h>
 #include <lind_syscall_num.h>
 #include <addr_translation.h>
+#include <stdint.h>
 
-/* We can simply use the syscall.  The CPU clocks are not supported
-   with this function.  */
+/* We can simply use the syscall.  Like the kernel, RawPOSIX sleeps on
+   the CPU clock of a process but not on that of a thread.  */
 int
 __clock_nanosleep_time64 (clockid_t clock_id, int flags,
 			  const struct __timespec64 *req,
 			  struct __timespec64 *rem)
 {
-  uint64_t host_req = TRANSLATE_GUEST_POINTER_TO_HOST (req);
-  uint64_t host_rem = TRANSLATE_GUEST_POINTER_TO_HOST (rem);
-  return MAKE_LEGACY_SYSCALL (
-      NANOSLEEP_TIME64_SYSCALL, "syscall|nanosleep", (uint64_t) clock_id,
-      (uint64_t) flags, host_req, host_rem, NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
+  /* Lind: the timespecs are handed over in the host layout, where tv_nsec
+     is 64 bits wide and the padding of __timespec64 may hold garbage.  */
+  struct
+  {
+    int64_t tv_sec;
+    int64_t tv_nsec;
+  } host_req = { req->tv_sec, req->tv_nsec }, host_rem = { 0, 0 };
+
+  /* clock_nanosleep returns the error number instead of setting errno.  */
+  int ret = MAKE_LEGACY_SYSCALL (
+      CLOCK_NANOSLEEP_SYSCALL, "syscall|clock_nanosleep", (uint64_t) clock_id,
+      (uint64_t) flags, TRANSLATE_GUEST_POINTER_TO_HOST (&host_req),
+      rem != NULL ? TRANSLATE_GUEST_POINTER_TO_HOST (&host_rem) : 0, NOTUSED,
+      NOTUSED, TRANSLATE_ERRNO_OFF);
+  if (ret == -EINTR && rem != NULL)
+    {
+      rem->tv_sec = host_rem.tv_sec;
+      rem->tv_nsec = host_rem.tv_nsec;
+    }
+  return -ret;
+}
+
+#if __TIMESIZE != 64
+libc_hidden_def (__clock_nanosleep_time64)
+
+int
+__clock_nanosleep (clockid_t clock_id, int flags, const struct timespec *req,
+		   struct timespec *rem)
+{
+  struct __timespec64 treq64 = valid_timespec_to_timespec64 (*req);
+  struct __timespec64 trem64 = { 0, 0 };
+  int ret = __clock_nanosleep_time64 (clock_id, flags, &treq64,
+				      rem != NULL ? &trem64 : NULL);
+  if (ret == EINTR && rem != NULL && (flags & TIMER_ABSTIME) == 0)
+    *rem = valid_timespec64_to_timespec (trem64);
+  return ret;
 }
+#endif
+libc_hidden_def (__clock_nanosleep)
+versioned_symbol (libc, __clock_nanosleep, clock_nanosleep, GLIBC_2_17);
+/* clock_nanosleep moved to libc in version 2.17;
+   old binaries may expect the symbol version it had in librt.  */
+#if SHLIB_COMPAT (libc, GLIBC_2_2, GLIBC_2_17)
+strong_alias (__clock_nanosleep, __clock_nanosleep_2);
+compat_symbol (libc, __clock_nanosleep_2, clock_nanosleep, GLIBC_2_2);
+#endif
@@ -20,6 +20,10 @@
 #include <time.h>
 #include <shlib-compat.h>
 #include <kernel-features.h>
+#include <stdint.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 /* Set CLOCK to value TP.  */
 int
@@ -32,26 +36,23 @@ __clock_settime64 (clockid_t clock_id, const struct __timespec64 *tp)
       return -1;
     }
 
-#ifndef __NR_clock_settime64
-# define __NR_clock_settime64 __NR_clock_settime
-#endif
-  int ret = INLINE_SYSCALL_CALL (clock_settime64, clock_id, tp);
-
-#ifndef __ASSUME_TIME64_SYSCALLS
-  if (ret == 0 || errno != ENOSYS)
-    return ret;
+  /* Lind: the timespec is handed over in the host layout, where tv_nsec
+     is 64 bits wide and the padding of __timespec64 may hold garbage.  */
+  struct
+  {
+    int64_t tv_sec;
+    int64_t tv_nsec;
+  } host_tp = { tp->tv_sec, tp->tv_nsec };
 
-  if (! in_int32_t_range (tp->tv_sec))
-    {
-      __set_errno (EOVERFLOW);
-      return -1;
-    }
-
-  struct timespec ts32 = valid_timespec64_to_timespec (*tp);
-  ret = INLINE_SYSCALL_CALL (clock_settime, clock_id, &ts32);
-#endif
+  return MAKE_LEGACY_SYSCALL (CLOCK_SETTIME_SYSCALL, "syscall|clock_settime",
+			      (uint64_t) clock_id,
+			      TRANSLATE_GUEST_POINTER_TO_HOST (&host_tp),
+			      NOTUSED, NOTUSED, NOTUSED, NOTUSED,
+			      TRANSLATE_ERRNO_ON);
 
-  return ret;
+  // Lind-Wasm: Original glibc code removed for compatibility
+  // to find original source code refer to (2.39.9000) at
+  // (/home/lind-wasm/glibc/sysdeps/unix/sysv/linux/clock_settime.c):(35-56)
 }
 
 #if __TIMESIZE != 64
@@ -22,6 +22,9 @@
 
 #include <sysdep-cancel.h>
 #include <sys/syscall.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 /* Wait for events on an epoll instance "epfd". Returns the number of
    triggered events returned in "events" buffer. Or -1 in case of
@@ -37,7 +40,16 @@ int epoll_pwait (int epfd, struct epoll_event *events,
 		 int maxevents, int timeout,
 		 const sigset_t *set)
 {
-  return SYSCALL_CANCEL (epoll_pwait, epfd, events, maxevents,
-			 timeout, set, __NSIG_BYTES);
+  /* Lind: the signal mask is handed over as the 64-bit set RawPOSIX keeps.  */
+  unsigned long long rawposix_set;
+  if (set != NULL)
+    rawposix_set = set->__val[0];
+
+  return MAKE_LEGACY_SYSCALL (
+      EPOLL_PWAIT_SYSCALL, "syscall|epoll_pwait", (uint64_t) epfd,
+      TRANSLATE_GUEST_POINTER_TO_HOST (events), (uint64_t) maxevents,
+      (uint64_t) timeout,
+      set != NULL ? TRANSLATE_GUEST_POINTER_TO_HOST (&rawposix_set) : 0,
+      (uint64_t) sizeof (rawposix_set), TRANSLATE_ERRNO_ON);
 }
 libc_hidden_def (epoll_pwait)
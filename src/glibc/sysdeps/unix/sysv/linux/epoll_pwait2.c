@@ -18,13 +18,37 @@
 
 #include <sys/epoll.h>
 #include <sysdep.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 int
 __epoll_pwait2_time64 (int fd, struct epoll_event *ev, int maxev,
 		       const struct __timespec64 *tmo, const sigset_t *s)
 {
-  /* The syscall only supports 64-bit time_t.  */
-  return SYSCALL_CANCEL (epoll_pwait2, fd, ev, maxev, tmo, s, __NSIG_BYTES);
+  /* Lind: the timeout is handed over as a host struct timespec and the
+     signal mask as the 64-bit set RawPOSIX keeps, as for ppoll.  */
+  struct
+  {
+    int64_t tv_sec;
+    int64_t tv_nsec;
+  } host_tmo;
+  unsigned long long rawposix_set;
+
+  if (tmo != NULL)
+    {
+      host_tmo.tv_sec = tmo->tv_sec;
+      host_tmo.tv_nsec = tmo->tv_nsec;
+    }
+  if (s != NULL)
+    rawposix_set = s->__val[0];
+
+  return MAKE_LEGACY_SYSCALL (
+      EPOLL_PWAIT2_SYSCALL, "syscall|epoll_pwait2", (uint64_t) fd,
+      TRANSLATE_GUEST_POINTER_TO_HOST (ev), (uint64_t) maxev,
+      tmo != NULL ? TRANSLATE_GUEST_POINTER_TO_HOST (&host_tmo) : 0,
+      s != NULL ? TRANSLATE_GUEST_POINTER_TO_HOST (&rawposix_set) : 0,
+      (uint64_t) sizeof (rawposix_set), TRANSLATE_ERRNO_ON);
 }
 #if __TIMESIZE != 64
 libc_hidden_def (__epoll_pwait2_time64)
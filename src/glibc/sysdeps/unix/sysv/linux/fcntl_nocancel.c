@@ -78,6 +78,12 @@ __fcntl64_nocancel_adjusted (int fd, int cmd, void *arg)
       ptr_arg = (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (arg);
       int_arg = 0; /* Unused for pointer commands */
     }
+  else if (cmd == F_GETOWN_EX || cmd == F_SETOWN_EX)
+    {
+      /* arg is a struct f_owner_ex pointer */
+      ptr_arg = (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (arg);
+      int_arg = 0;
+    }
   else
     {
       /* Integer argument (flags, fd numbers, etc.) - no translation */
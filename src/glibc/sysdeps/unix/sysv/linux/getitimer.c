@@ -21,24 +21,17 @@
 #include <sys/types.h>
 #include <sysdep.h>
 #include <tv32-compat.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 int
 __getitimer64 (__itimer_which_t which, struct __itimerval64 *curr_value)
 {
-#if __KERNEL_OLD_TIMEVAL_MATCHES_TIMEVAL64
-  return INLINE_SYSCALL_CALL (getitimer, which, curr_value);
-#else
-  struct __itimerval32 curr_value_32;
-
-  if (INLINE_SYSCALL_CALL (getitimer, which, &curr_value_32) == -1)
-    return -1;
-
-  curr_value->it_interval
-    = valid_timeval32_to_timeval64 (curr_value_32.it_interval);
-  curr_value->it_value
-    = valid_timeval32_to_timeval64 (curr_value_32.it_value);
-  return 0;
-#endif
+  uint64_t host_curr = TRANSLATE_GUEST_POINTER_TO_HOST (curr_value);
+  return MAKE_LEGACY_SYSCALL (GETITIMER_SYSCALL, "syscall|getitimer",
+			      (uint64_t) which, host_curr, NOTUSED, NOTUSED,
+			      NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
 }
 
 #if __TIMESIZE != 64
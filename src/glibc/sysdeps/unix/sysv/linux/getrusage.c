@@ -21,20 +21,55 @@
 #include <sys/types.h>
 #include <sysdep.h>
 #include <tv32-compat.h>
+#include <stdint.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
+
+/* RawPOSIX fills the rusage buffer in the x86_64 kernel layout, where every
+   field is 64 bits wide, as for wait4.  */
+struct lind_rusage
+  {
+    int64_t ru_utime_sec, ru_utime_usec;
+    int64_t ru_stime_sec, ru_stime_usec;
+    int64_t ru_long[14]; /* ru_maxrss ... ru_nivcsw */
+  };
 
 int
 __getrusage64 (enum __rusage_who who, struct __rusage64 *usage)
 {
-#if __KERNEL_OLD_TIMEVAL_MATCHES_TIMEVAL64
-  return INLINE_SYSCALL_CALL (getrusage, who, usage);
-#else
-  struct __rusage32 usage32;
-  if (INLINE_SYSCALL_CALL (getrusage, who, &usage32) == -1)
-    return -1;
-
-  rusage32_to_rusage64 (&usage32, usage);
+  struct lind_rusage lind_usage;
+  int ret = MAKE_LEGACY_SYSCALL (GETRUSAGE_SYSCALL, "syscall|getrusage",
+				 (uint64_t) who,
+				 TRANSLATE_GUEST_POINTER_TO_HOST (&lind_usage),
+				 NOTUSED, NOTUSED, NOTUSED, NOTUSED,
+				 TRANSLATE_ERRNO_ON);
+  if (ret != 0)
+    return ret;
+
+  usage->ru_utime.tv_sec = lind_usage.ru_utime_sec;
+  usage->ru_utime.tv_usec = lind_usage.ru_utime_usec;
+  usage->ru_stime.tv_sec = lind_usage.ru_stime_sec;
+  usage->ru_stime.tv_usec = lind_usage.ru_stime_usec;
+  usage->ru_maxrss = lind_usage.ru_long[0];
+  usage->ru_ixrss = lind_usage.ru_long[1];
+  usage->ru_idrss = lind_usage.ru_long[2];
+  usage->ru_isrss = lind_usage.ru_long[3];
+  usage->ru_minflt = lind_usage.ru_long[4];
+  usage->ru_majflt = lind_usage.ru_long[5];
+  usage->ru_nswap = lind_usage.ru_long[6];
+  usage->ru_inblock = lind_usage.ru_long[7];
+  usage->ru_oublock = lind_usage.ru_long[8];
+  usage->ru_msgsnd = lind_usage.ru_long[9];
+  usage->ru_msgrcv = lind_usage.ru_long[10];
+  usage->ru_nsignals = lind_usage.ru_long[11];
+  usage->ru_nvcsw = lind_usage.ru_long[12];
+  usage->ru_nivcsw = lind_usage.ru_long[13];
   return 0;
-#endif
+
+  // Lind-Wasm: Original glibc code removed for compatibility
+  // to find original source code refer to (2.39.9000) at
+  // (/home/lind-wasm/glibc/sysdeps/unix/sysv/linux/getrusage.c):(28-37)
 }
 
 #if __TIMESIZE != 64
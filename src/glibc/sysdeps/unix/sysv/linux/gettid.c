@@ -0,0 +1,29 @@
+/* Get the thread id of the calling thread.  Linux/lind version.
+   Copyright (C) 2019-2024 Free Software Foundation, Inc.
+   This file is part of the GNU C Library.
+
+   The GNU C Library is free software; you can redistribute it and/or
+   modify it under the terms of the GNU Lesser General Public
+   License as published by the Free Software Foundation; either
+   version 2.1 of the License, or (at your option) any later version.
+
+   The GNU C Library is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+   Lesser General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public
+   License along with the GNU C Library.  If not, see
+   <https://www.gnu.org/licenses/>.  */
+
+#include <unistd.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+
+/* RawPOSIX hands out the thread ids of a cage, the main thread is 1.  */
+__pid_t
+__gettid (void)
+{
+   return MAKE_LEGACY_SYSCALL(GETTID_SYSCALL, "syscall|gettid", NOTUSED, NOTUSED, NOTUSED, NOTUSED, NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
+}
+weak_alias (__gettid, gettid)
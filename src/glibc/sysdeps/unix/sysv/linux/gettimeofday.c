@@ -58,19 +58,19 @@ weak_alias (__gettimeofday, gettimeofday)
 /* Conversion of gettimeofday function to support 64 bit time on archs
    with __WORDSIZE == 32 and __TIMESIZE == 32/64  */
 #include <errno.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 int
 __gettimeofday64 (struct __timeval64 *restrict tv, void *restrict tz)
 {
-  if (__glibc_unlikely (tz != 0))
-    memset (tz, 0, sizeof (struct timezone));
-
-  struct __timespec64 ts64;
-  if (__clock_gettime64 (CLOCK_REALTIME, &ts64))
-	  return -1;
-
-  *tv = timespec64_to_timeval64 (ts64);
-  return 0;
+  /* struct __timeval64 has the host layout already.  RawPOSIX zeroes the
+     timezone, which has been obsolete for decades.  */
+  return MAKE_LEGACY_SYSCALL (GETTIMEOFDAY_SYSCALL, "syscall|gettimeofday",
+			      TRANSLATE_GUEST_POINTER_TO_HOST (tv),
+			      TRANSLATE_GUEST_POINTER_TO_HOST (tz), NOTUSED,
+			      NOTUSED, NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
 }
 
 # if __TIMESIZE != 64
@@ -17,6 +17,7 @@
    <https://www.gnu.org/licenses/>.  */
 
 #include <signal.h>
+#include <string.h>
 #include <ldsodefs.h>
 #include <syscall-template.h>
 #include <lind_syscall_num.h>
@@ -86,10 +87,28 @@ extern void restore (void) {
 // RESTORE (restore, __NR_sigreturn)
 
 // entry point of epoch callback in glibc, invoked by wasmtime
+// sa_flags are the flags the handler was installed with; si_code and si_addr
+// fill in the siginfo_t handed to SA_SIGINFO handlers (si_addr is the faulting
+// address of a trap turned into SIGSEGV, SIGBUS, SIGFPE or SIGILL)
 __attribute__((export_name("signal_callback")))
-void signal_callback(__sighandler_t callback, int signal) {
+void signal_callback(__sighandler_t callback, int signal, int sa_flags,
+		     int si_code, void *si_addr) {
+  if(callback == 0)
+    return;
+
   // directly call into user's custom signal handler
-  if(callback != 0)
+  // a Wasm indirect call traps unless the callee takes exactly the arguments
+  // passed, so SA_SIGINFO handlers have to be called with all three of them
+  if (sa_flags & SA_SIGINFO)
+  {
+    siginfo_t info;
+    memset (&info, 0, sizeof (info));
+    info.si_signo = signal;
+    info.si_code = si_code;
+    info.si_addr = si_addr;
+    ((void (*) (int, siginfo_t *, void *)) callback) (signal, &info, NULL);
+  }
+  else
     callback(signal);
 }
 
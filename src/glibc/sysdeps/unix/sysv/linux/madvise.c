@@ -1,9 +1,18 @@
 #include <unistd.h>
+#include <sys/mman.h>
+#include <stdint.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 int
 __GI___madvise (void *addr, size_t len, int advice)
 {
-  return 0;
+  return MAKE_LEGACY_SYSCALL (MADVISE_SYSCALL, "syscall|madvise",
+		       (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (addr),
+		       (uint64_t) len, (uint64_t) advice, NOTUSED, NOTUSED,
+		       NOTUSED, TRANSLATE_ERRNO_ON);
 }
 
 weak_alias(__GI___madvise, __madvise)
+weak_alias(__GI___madvise, madvise)
@@ -0,0 +1,15 @@
+#include <unistd.h>
+#include <sys/mman.h>
+#include <stdint.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
+
+int
+mincore (void *addr, size_t len, unsigned char *vec)
+{
+  return MAKE_LEGACY_SYSCALL (MINCORE_SYSCALL, "syscall|mincore",
+		       (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (addr),
+		       (uint64_t) len, (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (vec),
+		       NOTUSED, NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
+}
@@ -20,6 +20,10 @@
 #include <sysdep.h>
 #include <stdarg.h>
 #include <stddef.h>
+#include <stdint.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 void *
 __mremap (void *addr, size_t old_len, size_t new_len, int flags, ...)
@@ -34,8 +38,11 @@ __mremap (void *addr, size_t old_len, size_t new_len, int flags, ...)
       va_end (va);
     }
 
-  return (void *) INLINE_SYSCALL_CALL (mremap, addr, old_len, new_len, flags,
-				       new_addr);
+  return (void *) (intptr_t) MAKE_LEGACY_SYSCALL (MREMAP_SYSCALL, "syscall|mremap",
+		       (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (addr),
+		       (uint64_t) old_len, (uint64_t) new_len, (uint64_t) flags,
+		       (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (new_addr),
+		       NOTUSED, TRANSLATE_ERRNO_ON);
 }
 libc_hidden_def (__mremap)
 weak_alias (__mremap, mremap)
@@ -18,9 +18,16 @@
 
 #include <sys/mman.h>
 #include <sysdep-cancel.h>
+#include <stdint.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 int
 msync (void *addr, size_t length, int flags)
 {
-  return SYSCALL_CANCEL (msync, addr, length, flags);
+  return MAKE_LEGACY_SYSCALL (MSYNC_SYSCALL, "syscall|msync",
+		       (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (addr),
+		       (uint64_t) length, (uint64_t) flags, NOTUSED, NOTUSED,
+		       NOTUSED, TRANSLATE_ERRNO_ON);
 }
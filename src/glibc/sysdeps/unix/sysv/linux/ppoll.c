@@ -20,47 +20,47 @@
 #include <time.h>
 #include <sys/poll.h>
 #include <sysdep-cancel.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
+/* Lind: the timeout is handed over as a host struct timespec, whose tv_nsec
+   is 64 bits wide where ours is padded, and the signal mask as the 64-bit
+   set RawPOSIX keeps, as for sigprocmask.  */
 int
 __ppoll64 (struct pollfd *fds, nfds_t nfds, const struct __timespec64 *timeout,
            const sigset_t *sigmask)
 {
-  /* The Linux kernel can in some situations update the timeout value.
-     We do not want that so use a local variable.  */
-  struct __timespec64 tval;
-  if (timeout != NULL)
-    {
-      tval = *timeout;
-      timeout = &tval;
-    }
-
-#ifndef __NR_ppoll_time64
-# define __NR_ppoll_time64 __NR_ppoll
-#endif
+  struct
+  {
+    int64_t tv_sec;
+    int64_t tv_nsec;
+  } host_timeout;
+  unsigned long long rawposix_set;
 
-#ifdef __ASSUME_TIME64_SYSCALLS
-  return SYSCALL_CANCEL (ppoll_time64, fds, nfds, timeout, sigmask,
-			 __NSIG_BYTES);
-#else
-  int ret;
-  bool need_time64 = timeout != NULL && !in_int32_t_range (timeout->tv_sec);
-  if (need_time64)
+  if (timeout != NULL)
     {
-      ret = SYSCALL_CANCEL (ppoll_time64, fds, nfds, timeout, sigmask,
-			    __NSIG_BYTES);
-      if (ret == 0 || errno != ENOSYS)
-	return ret;
-      __set_errno (EOVERFLOW);
-      return -1;
+      host_timeout.tv_sec = timeout->tv_sec;
+      host_timeout.tv_nsec = timeout->tv_nsec;
     }
+  if (sigmask != NULL)
+    rawposix_set = sigmask->__val[0];
 
-  struct timespec ts32;
-  if (timeout != NULL)
-    ts32 = valid_timespec64_to_timespec (*timeout);
+  return MAKE_LEGACY_SYSCALL (PPOLL_SYSCALL, "syscall|ppoll",
+			      TRANSLATE_GUEST_POINTER_TO_HOST (fds),
+			      (uint64_t) nfds,
+			      timeout != NULL
+			      ? TRANSLATE_GUEST_POINTER_TO_HOST (&host_timeout)
+			      : 0,
+			      sigmask != NULL
+			      ? TRANSLATE_GUEST_POINTER_TO_HOST (&rawposix_set)
+			      : 0,
+			      (uint64_t) sizeof (rawposix_set), NOTUSED,
+			      TRANSLATE_ERRNO_ON);
 
-  return SYSCALL_CANCEL (ppoll, fds, nfds, timeout ? &ts32 : NULL, sigmask,
-			 __NSIG_BYTES);
-#endif
+  // Lind-Wasm: Original glibc code removed for compatibility
+  // to find original source code refer to (2.39.9000) at
+  // (/home/lind-wasm/glibc/sysdeps/unix/sysv/linux/ppoll.c):(28-62)
 }
 
 #if __TIMESIZE != 64
@@ -17,58 +17,49 @@
 
 #include <sys/select.h>
 #include <sysdep-cancel.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
-static int
-pselect64_syscall (int nfds, fd_set *readfds, fd_set *writefds,
-		   fd_set *exceptfds, const struct __timespec64 *timeout,
-		   const sigset_t *sigmask)
-{
-#ifndef __NR_pselect6_time64
-# define __NR_pselect6_time64 __NR_pselect6
-#endif
-  /* NB: This is required by ARGIFY used in x32 internal_syscallN.  */
-  __syscall_ulong_t data[2] =
-    {
-      (uintptr_t) sigmask, __NSIG_BYTES
-    };
-  return SYSCALL_CANCEL (pselect6_time64, nfds, readfds, writefds, exceptfds,
-			 timeout, data);
-}
-
+/* Lind: the timeout and the signal mask are handed over like for ppoll.  The
+   kernel takes a pointer to a { sigmask, size } pair as the last argument;
+   RawPOSIX takes the signal mask itself, so that no pointer inside the pair
+   needs translating.  */
 int
 __pselect64 (int nfds, fd_set *readfds, fd_set *writefds, fd_set *exceptfds,
 	     const struct __timespec64 *timeout, const sigset_t *sigmask)
 {
-  /* The Linux kernel can in some situations update the timeout value.
-     We do not want that so use a local variable.  */
-  struct __timespec64 tval;
+  struct
+  {
+    int64_t tv_sec;
+    int64_t tv_nsec;
+  } host_timeout;
+  unsigned long long rawposix_set;
+
   if (timeout != NULL)
     {
-      tval = *timeout;
-      timeout = &tval;
+      host_timeout.tv_sec = timeout->tv_sec;
+      host_timeout.tv_nsec = timeout->tv_nsec;
     }
+  if (sigmask != NULL)
+    rawposix_set = sigmask->__val[0];
 
-  /* Note: the system call expects 7 values but on most architectures
-     we can only pass in 6 directly.  If there is an architecture with
-     support for more parameters a new version of this file needs to
-     be created.  */
-#ifdef __ASSUME_TIME64_SYSCALLS
-  return pselect64_syscall (nfds, readfds, writefds, exceptfds, timeout,
-			    sigmask);
-#else
-  bool need_time64 = timeout != NULL && !in_int32_t_range (timeout->tv_sec);
-  if (need_time64)
-    {
-      int r = pselect64_syscall (nfds, readfds, writefds, exceptfds, timeout,
-				 sigmask);
-      if (r == 0 || errno != ENOSYS)
-	return r;
-      __set_errno (EOVERFLOW);
-      return -1;
-    }
+  return MAKE_LEGACY_SYSCALL (PSELECT6_SYSCALL, "syscall|pselect6",
+			      (uint64_t) nfds,
+			      (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (readfds),
+			      (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (writefds),
+			      (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (exceptfds),
+			      timeout != NULL
+			      ? TRANSLATE_GUEST_POINTER_TO_HOST (&host_timeout)
+			      : 0,
+			      sigmask != NULL
+			      ? TRANSLATE_GUEST_POINTER_TO_HOST (&rawposix_set)
+			      : 0,
+			      TRANSLATE_ERRNO_ON);
 
-  return __pselect32 (nfds, readfds, writefds, exceptfds, timeout, sigmask);
-#endif
+  // Lind-Wasm: Original glibc code removed for compatibility
+  // to find original source code refer to (2.39.9000) at
+  // (/home/lind-wasm/glibc/sysdeps/unix/sysv/linux/pselect.c):(21-75)
 }
 
 #if __TIMESIZE != 64
@@ -17,33 +17,18 @@
 
 #include <sys/uio.h>
 #include <sysdep-cancel.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 #ifndef __OFF_T_MATCHES_OFF64_T
-
-# ifdef __ASSUME_PREADV
-
 ssize_t
 pwritev (int fd, const struct iovec *vector, int count, off_t offset)
 {
-  return SYSCALL_CANCEL (pwritev, fd, vector, count, LO_HI_LONG (offset));
+  return MAKE_LEGACY_SYSCALL (PWRITEV_SYSCALL, "syscall|pwritev", (uint64_t) fd,
+			      TRANSLATE_GUEST_POINTER_TO_HOST (vector),
+			      (uint64_t) count, (uint64_t) (int64_t) offset,
+			      NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
 }
-# else
-static ssize_t __atomic_pwritev_replacement (int, const struct iovec *,
-					     int, off_t);
-ssize_t
-pwritev (int fd, const struct iovec *vector, int count, off_t offset)
-{
-  ssize_t result = SYSCALL_CANCEL (pwritev, fd, vector, count,
-				   LO_HI_LONG (offset));
-  if (result >= 0 || errno != ENOSYS)
-    return result;
-  return __atomic_pwritev_replacement (fd, vector, count, offset);
-}
-#  define PWRITEV static __atomic_pwritev_replacement
-#  define PWRITE __pwrite
-#  define OFF_T off_t
-#  include <sysdeps/posix/pwritev_common.c>
-# endif /* __ASSUME_PREADV  */
-
 libc_hidden_def (pwritev)
 #endif
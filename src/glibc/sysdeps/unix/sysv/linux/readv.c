@@ -19,11 +19,19 @@
 #include <unistd.h>
 #include <sys/uio.h>
 #include <sysdep-cancel.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
+/* Lind: unlike writev, the iovec array is passed untranslated; rawposix
+   rebases the iov_base pointers itself.  */
 ssize_t
 __readv (int fd, const struct iovec *iov, int iovcnt)
 {
-  return SYSCALL_CANCEL (readv, fd, iov, iovcnt);
+  return MAKE_LEGACY_SYSCALL (READV_SYSCALL, "syscall|readv", (uint64_t) fd,
+			      TRANSLATE_GUEST_POINTER_TO_HOST (iov),
+			      (uint64_t) iovcnt, NOTUSED, NOTUSED, NOTUSED,
+			      TRANSLATE_ERRNO_ON);
 }
 libc_hidden_def (__readv)
 weak_alias (__readv, readv)
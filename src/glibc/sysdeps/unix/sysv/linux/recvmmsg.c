@@ -18,43 +18,43 @@
 #include <sys/socket.h>
 #include <sysdep.h>
 #include <socketcall.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
+/* Lind: the mmsghdr array is passed untranslated, as for sendmmsg.  The
+   timeout is handed over as a host struct timespec, whose tv_nsec is 64 bits
+   wide where ours is padded.  */
 static int
 recvmmsg_syscall (int fd, struct mmsghdr *vmessages, unsigned int vlen,
 		  int flags, struct __timespec64 *timeout)
 {
-#ifndef __NR_recvmmsg_time64
-# define __NR_recvmmsg_time64 __NR_recvmmsg
-#endif
-  int r = SYSCALL_CANCEL (recvmmsg_time64, fd, vmessages, vlen, flags,
-			  timeout);
-#ifndef __ASSUME_TIME64_SYSCALLS
-  if (r >= 0 || errno != ENOSYS)
-    return r;
+  struct
+  {
+    int64_t tv_sec;
+    int64_t tv_nsec;
+  } host_timeout;
 
-  struct timespec ts32, *pts32 = NULL;
   if (timeout != NULL)
     {
-      if (! in_int32_t_range (timeout->tv_sec))
-	{
-	  __set_errno (EINVAL);
-	  return -1;
-	}
-      ts32 = valid_timespec64_to_timespec (*timeout);
-      pts32 = &ts32;
+      host_timeout.tv_sec = timeout->tv_sec;
+      host_timeout.tv_nsec = timeout->tv_nsec;
     }
 
-# ifdef __ASSUME_RECVMMSG_SYSCALL
-  r = SYSCALL_CANCEL (recvmmsg, fd, vmessages, vlen, flags, pts32);
-# else
-  r = SOCKETCALL_CANCEL (recvmmsg, fd, vmessages, vlen, flags, pts32);
-# endif
-  if (r >= 0)
+  int r = MAKE_LEGACY_SYSCALL (RECVMMSG_SYSCALL, "syscall|recvmmsg",
+			       (uint64_t) fd,
+			       TRANSLATE_GUEST_POINTER_TO_HOST (vmessages),
+			       (uint64_t) vlen, (uint64_t) flags,
+			       timeout != NULL
+			       ? TRANSLATE_GUEST_POINTER_TO_HOST (&host_timeout)
+			       : 0,
+			       NOTUSED, TRANSLATE_ERRNO_ON);
+
+  if (r >= 0 && timeout != NULL)
     {
-      if (timeout != NULL)
-        *timeout = valid_timespec_to_timespec64 (ts32);
+      timeout->tv_sec = host_timeout.tv_sec;
+      timeout->tv_nsec = host_timeout.tv_nsec;
     }
-#endif
   return r;
 }
 
@@ -20,18 +20,21 @@
 #include <stddef.h>
 #include <errno.h>
 #include <sysdep.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 /* Send COUNT bytes from file associated with IN_FD starting at OFFSET to
    descriptor OUT_FD.  */
 ssize_t
 sendfile64 (int out_fd, int in_fd, off64_t *offset, size_t count)
 {
-#ifndef __NR_sendfile64
-# define __NR_sendfile64 __NR_sendfile
-#endif
-  return INLINE_SYSCALL_CALL (sendfile64, out_fd, in_fd, offset, count);
+  return MAKE_LEGACY_SYSCALL (SENDFILE_SYSCALL, "syscall|sendfile",
+			      (uint64_t) out_fd, (uint64_t) in_fd,
+			      TRANSLATE_GUEST_POINTER_TO_HOST (offset),
+			      (uint64_t) count, NOTUSED, NOTUSED,
+			      TRANSLATE_ERRNO_ON);
 }
-
 #ifdef __OFF_T_MATCHES_OFF64_T
 strong_alias (sendfile64, sendfile)
 #endif
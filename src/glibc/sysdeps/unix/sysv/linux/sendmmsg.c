@@ -17,20 +17,21 @@
 
 #include <errno.h>
 #include <sys/socket.h>
-
 #include <sysdep-cancel.h>
-#include <sys/syscall.h>
-#include <socketcall.h>
-#include <kernel-features.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
+/* Lind: the mmsghdr array is passed untranslated; rawposix translates the
+   msghdrs, their iovecs and their control buffers.  */
 int
 __sendmmsg (int fd, struct mmsghdr *vmessages, unsigned int vlen, int flags)
 {
-#ifdef __ASSUME_SENDMMSG_SYSCALL
-  return SYSCALL_CANCEL (sendmmsg, fd, vmessages, vlen, flags);
-#else
-  return SOCKETCALL_CANCEL (sendmmsg, fd, vmessages, vlen, flags);
-#endif
+  return MAKE_LEGACY_SYSCALL (SENDMMSG_SYSCALL, "syscall|sendmmsg",
+			      (uint64_t) fd,
+			      TRANSLATE_GUEST_POINTER_TO_HOST (vmessages),
+			      (uint64_t) vlen, (uint64_t) flags,
+			      NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
 }
 libc_hidden_def (__sendmmsg)
 weak_alias (__sendmmsg, sendmmsg)
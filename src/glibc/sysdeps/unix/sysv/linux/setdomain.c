@@ -0,0 +1,32 @@
+/* Set the NIS domain name of the current cage.
+   Copyright (C) 1994-2024 Free Software Foundation, Inc.
+   This file is part of the GNU C Library.
+
+   The GNU C Library is free software; you can redistribute it and/or
+   modify it under the terms of the GNU Lesser General Public
+   License as published by the Free Software Foundation; either
+   version 2.1 of the License, or (at your option) any later version.
+
+   The GNU C Library is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+   Lesser General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public
+   License along with the GNU C Library; if not, see
+
+#include <unistd.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
+
+/* Set the name of the current YP domain to NAME, which is LEN bytes long.
+   lind-wasm: this changes the UTS record of the calling cage only.  */
+int
+setdomainname (const char *name, size_t len)
+{
+  return MAKE_LEGACY_SYSCALL (SETDOMAINNAME_SYSCALL, "syscall|setdomainname",
+			      (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (name),
+			      (uint64_t) len, NOTUSED, NOTUSED, NOTUSED, NOTUSED,
+			      TRANSLATE_ERRNO_ON);
+}
@@ -0,0 +1,44 @@
+/* Set or get the alternate signal stack.  Linux/lind version.
+   Copyright (C) 2024 Free Software Foundation, Inc.
+   This file is part of the GNU C Library.
+
+   The GNU C Library is free software; you can redistribute it and/or
+   modify it under the terms of the GNU Lesser General Public
+   License as published by the Free Software Foundation; either
+   version 2.1 of the License, or (at your option) any later version.
+
+   The GNU C Library is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+   Lesser General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public
+   License along with the GNU C Library.  If not, see
+   <https://www.gnu.org/licenses/>.  */
+
+#include <signal.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
+
+/* Whether the caller runs on the alternate stack is told by its stack
+   pointer, here the shadow stack pointer __stack_pointer, which only the
+   guest can read, so it goes along with the call.  ss_sp stays a guest
+   address.  */
+int
+__sigaltstack (const stack_t *ss, stack_t *oss)
+{
+  uintptr_t sp;
+  __asm__ (".globaltype __stack_pointer, i32\n"
+	   "global.get __stack_pointer\n"
+	   "local.set %0\n"
+	   : "=r"(sp));
+  uint64_t host_ss = TRANSLATE_GUEST_POINTER_TO_HOST (ss);
+  uint64_t host_oss = TRANSLATE_GUEST_POINTER_TO_HOST (oss);
+
+  return MAKE_LEGACY_SYSCALL (SIGALTSTACK_SYSCALL, "syscall|sigaltstack",
+			      host_ss, host_oss, (uint64_t) sp,
+			      NOTUSED, NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
+}
+libc_hidden_def (__sigaltstack)
+weak_alias (__sigaltstack, sigaltstack)
@@ -18,10 +18,19 @@
 
 #include <fcntl.h>
 #include <sysdep-cancel.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 ssize_t
 splice (int fd_in, loff_t *off_in, int fd_out, loff_t *off_out, size_t len,
 	unsigned int flags)
 {
-  return SYSCALL_CANCEL (splice, fd_in, off_in, fd_out, off_out, len, flags);
+  return MAKE_LEGACY_SYSCALL (SPLICE_SYSCALL, "syscall|splice",
+			      (uint64_t) fd_in,
+			      TRANSLATE_GUEST_POINTER_TO_HOST (off_in),
+			      (uint64_t) fd_out,
+			      TRANSLATE_GUEST_POINTER_TO_HOST (off_out),
+			      (uint64_t) len, (uint64_t) flags,
+			      TRANSLATE_ERRNO_ON);
 }
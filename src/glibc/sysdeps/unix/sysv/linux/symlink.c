@@ -19,15 +19,17 @@
 #include <unistd.h>
 #include <fcntl.h>
 #include <sysdep.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 /* Make a link to FROM called TO.  */
 int
 __symlink (const char *from, const char *to)
 {
-#ifdef __NR_symlink
-  return INLINE_SYSCALL_CALL (symlink, from, to);
-#else
-  return INLINE_SYSCALL_CALL (symlinkat, from, AT_FDCWD, to);
-#endif
+  return MAKE_LEGACY_SYSCALL (SYMLINK_SYSCALL, "syscall|symlink",
+		       (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (from),
+		       (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (to),
+		       NOTUSED, NOTUSED, NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
 }
 weak_alias (__symlink, symlink)
@@ -0,0 +1,29 @@
+/* Send a signal to one thread of a process.  Linux/lind version.
+   Copyright (C) 2019-2024 Free Software Foundation, Inc.
+   This file is part of the GNU C Library.
+
+   The GNU C Library is free software; you can redistribute it and/or
+   modify it under the terms of the GNU Lesser General Public
+   License as published by the Free Software Foundation; either
+   version 2.1 of the License, or (at your option) any later version.
+
+   The GNU C Library is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+   Lesser General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public
+   License along with the GNU C Library.  If not, see
+   <https://www.gnu.org/licenses/>.  */
+
+#include <signal.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+
+/* The thread group id is the cage id, as getpid returns it.  */
+int
+__tgkill (__pid_t tgid, __pid_t tid, int sig)
+{
+   return MAKE_LEGACY_SYSCALL(TGKILL_SYSCALL, "syscall|tgkill", (uint64_t) tgid, (uint64_t) tid, (uint64_t) sig, NOTUSED, NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
+}
+weak_alias (__tgkill, tgkill)
@@ -50,18 +50,25 @@ time (time_t *t)
 # include <time.h>
 # include <time-clockid.h>
 # include <errno.h>
+# include <syscall-template.h>
+# include <lind_syscall_num.h>
+# include <addr_translation.h>
 
 /* Return the time now, and store it in *TIMER if not NULL.  */
 
 __time64_t
 __time64 (__time64_t *timer)
 {
-  struct __timespec64 ts;
-  __clock_gettime64 (TIME_CLOCK_GETTIME_CLOCKID, &ts);
+  /* The return value of a syscall is only 32 bits wide, the full time is
+     read from the buffer RawPOSIX stores it in.  */
+  __time64_t t;
+  MAKE_LEGACY_SYSCALL (TIME_SYSCALL, "syscall|time",
+		       TRANSLATE_GUEST_POINTER_TO_HOST (&t), NOTUSED, NOTUSED,
+		       NOTUSED, NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
 
   if (timer != NULL)
-    *timer = ts.tv_sec;
-  return ts.tv_sec;
+    *timer = t;
+  return t;
 }
 
 # if __TIMESIZE != 64
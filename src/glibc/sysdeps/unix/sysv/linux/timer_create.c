@@ -25,19 +25,36 @@
 #include <internaltypes.h>
 #include <pthreadP.h>
 #include "kernel-posix-timers.h"
-#include "kernel-posix-cpu-timers.h"
 #include <shlib-compat.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
+
+/* Lind: RawPOSIX takes the CPU-time clocks by their public ids, and reads
+   the fields of the sigevent it uses from a struct of its own, as our sigval
+   is narrower than the host's.  */
+static int
+lind_timer_create (clockid_t clock_id, const struct sigevent *evp,
+		   kernel_timer_t *ktimerid)
+{
+  struct
+  {
+    int32_t sigev_signo;
+    int32_t sigev_notify;
+    int32_t sigev_notify_thread_id;
+  } host_evp = { evp->sigev_signo, evp->sigev_notify, evp->_sigev_un._tid };
+
+  return MAKE_LEGACY_SYSCALL (TIMER_CREATE_SYSCALL, "syscall|timer_create",
+			      (uint64_t) clock_id,
+			      TRANSLATE_GUEST_POINTER_TO_HOST (&host_evp),
+			      TRANSLATE_GUEST_POINTER_TO_HOST (ktimerid),
+			      NOTUSED, NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
+}
 
 int
 ___timer_create (clockid_t clock_id, struct sigevent *evp, timer_t *timerid)
 {
   {
-    clockid_t syscall_clockid = (clock_id == CLOCK_PROCESS_CPUTIME_ID
-				 ? PROCESS_CLOCK
-				 : clock_id == CLOCK_THREAD_CPUTIME_ID
-				 ? THREAD_CLOCK
-				 : clock_id);
-
     /* If the user wants notification via a thread we need to handle
        this special.  */
     if (evp == NULL
@@ -58,8 +75,7 @@ ___timer_create (clockid_t clock_id, struct sigevent *evp, timer_t *timerid)
 	  }
 
 	kernel_timer_t ktimerid;
-	if (INLINE_SYSCALL_CALL (timer_create, syscall_clockid, evp,
-				 &ktimerid) == -1)
+	if (lind_timer_create (clock_id, evp, &ktimerid) == -1)
 	  return -1;
 
 	*timerid = kernel_timer_to_timerid (ktimerid);
@@ -114,13 +130,9 @@ ___timer_create (clockid_t clock_id, struct sigevent *evp, timer_t *timerid)
 	    ._sigev_un = { ._pad = { [0] = __timer_helper_tid } } };
 
 	/* Create the timer.  */
-	int res;
-	res = INTERNAL_SYSCALL_CALL (timer_create, syscall_clockid, &sev,
-				     &newp->ktimerid);
-	if (INTERNAL_SYSCALL_ERROR_P (res))
+	if (lind_timer_create (clock_id, &sev, &newp->ktimerid) == -1)
 	  {
 	    free (newp);
-	    __set_errno (INTERNAL_SYSCALL_ERRNO (res));
 	    return -1;
 	  }
 
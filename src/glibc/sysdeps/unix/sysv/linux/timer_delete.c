@@ -22,12 +22,16 @@
 #include "kernel-posix-timers.h"
 #include <pthreadP.h>
 #include <shlib-compat.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
 
 int
 ___timer_delete (timer_t timerid)
 {
   kernel_timer_t ktimerid = timerid_to_kernel_timer (timerid);
-  int res = INLINE_SYSCALL_CALL (timer_delete, ktimerid);
+  int res = MAKE_LEGACY_SYSCALL (TIMER_DELETE_SYSCALL, "syscall|timer_delete",
+			      (uint64_t) ktimerid, NOTUSED, NOTUSED, NOTUSED,
+			      NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
 
   if (res == 0)
     {
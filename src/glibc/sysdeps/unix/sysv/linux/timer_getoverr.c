@@ -20,12 +20,16 @@
 #include <sysdep.h>
 #include "kernel-posix-timers.h"
 #include <shlib-compat.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
 
 int
 ___timer_getoverrun (timer_t timerid)
 {
   kernel_timer_t ktimerid = timerid_to_kernel_timer (timerid);
-  return INLINE_SYSCALL_CALL (timer_getoverrun, ktimerid);
+  return MAKE_LEGACY_SYSCALL (TIMER_GETOVERRUN_SYSCALL, "syscall|timer_getoverrun",
+			      (uint64_t) ktimerid, NOTUSED, NOTUSED, NOTUSED,
+			      NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
 }
 versioned_symbol (libc, ___timer_getoverrun, timer_getoverrun, GLIBC_2_34);
 libc_hidden_ver (___timer_getoverrun, __timer_getoverrun)
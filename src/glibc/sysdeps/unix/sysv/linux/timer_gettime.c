@@ -22,6 +22,9 @@
 #include <kernel-features.h>
 #include "kernel-posix-timers.h"
 #include <shlib-compat.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 #if !TIMER_T_WAS_INT_COMPAT
 int
@@ -29,23 +32,35 @@ ___timer_gettime64 (timer_t timerid, struct __itimerspec64 *value)
 {
   kernel_timer_t ktimerid = timerid_to_kernel_timer (timerid);
 
-# ifndef __NR_timer_gettime64
-#  define __NR_timer_gettime64 __NR_timer_gettime
-# endif
-  int ret = INLINE_SYSCALL_CALL (timer_gettime64, ktimerid, value);
-# ifndef __ASSUME_TIME64_SYSCALLS
-  if (ret == 0 || errno != ENOSYS)
-    return ret;
-
-  struct itimerspec its32;
-  ret = INLINE_SYSCALL_CALL (timer_gettime, ktimerid, &its32);
+  /* Lind: the itimerspec is handed over in the host layout, as for
+     timer_settime.  */
+  struct
+  {
+    int64_t it_interval_sec;
+    int64_t it_interval_nsec;
+    int64_t it_value_sec;
+    int64_t it_value_nsec;
+  } host_value;
+
+  int ret = MAKE_LEGACY_SYSCALL (TIMER_GETTIME_SYSCALL, "syscall|timer_gettime",
+				 (uint64_t) ktimerid,
+				 value != NULL
+				 ? TRANSLATE_GUEST_POINTER_TO_HOST (&host_value)
+				 : 0,
+				 NOTUSED, NOTUSED, NOTUSED, NOTUSED,
+				 TRANSLATE_ERRNO_ON);
   if (ret == 0)
     {
-      value->it_interval = valid_timespec_to_timespec64 (its32.it_interval);
-      value->it_value = valid_timespec_to_timespec64 (its32.it_value);
+      value->it_interval.tv_sec = host_value.it_interval_sec;
+      value->it_interval.tv_nsec = host_value.it_interval_nsec;
+      value->it_value.tv_sec = host_value.it_value_sec;
+      value->it_value.tv_nsec = host_value.it_value_nsec;
     }
-# endif
   return ret;
+
+  // Lind-Wasm: Original glibc code removed for compatibility
+  // to find original source code refer to (2.39.9000) at
+  // (/home/lind-wasm/glibc/sysdeps/unix/sysv/linux/timer_gettime.c):(32-48)
 }
 
 # if __TIMESIZE == 64
@@ -22,6 +22,9 @@
 #include <kernel-features.h>
 #include "kernel-posix-timers.h"
 #include <shlib-compat.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 #if !TIMER_T_WAS_INT_COMPAT
 int
@@ -31,41 +34,45 @@ ___timer_settime64 (timer_t timerid, int flags,
 {
   kernel_timer_t ktimerid = timerid_to_kernel_timer (timerid);
 
-# ifdef __ASSUME_TIME64_SYSCALLS
-#  ifndef __NR_timer_settime64
-#   define __NR_timer_settime64 __NR_timer_settime
-#  endif
-  return INLINE_SYSCALL_CALL (timer_settime64, ktimerid, flags, value,
-                              ovalue);
-# else
-#  ifdef __NR_timer_settime64
-  int ret = INLINE_SYSCALL_CALL (timer_settime64, ktimerid, flags, value,
-                                 ovalue);
-  if (ret == 0 || errno != ENOSYS)
-    return ret;
-#  endif
-  struct itimerspec its32, oits32;
-
-  if (! in_int32_t_range ((value->it_value).tv_sec)
-      || ! in_int32_t_range ((value->it_interval).tv_sec))
+  /* Lind: the itimerspecs are handed over in the host layout, whose tv_nsec
+     is 64 bits wide where ours is padded, as for ppoll.  */
+  struct
+  {
+    int64_t it_interval_sec;
+    int64_t it_interval_nsec;
+    int64_t it_value_sec;
+    int64_t it_value_nsec;
+  } host_value, host_ovalue;
+
+  if (value == NULL)
     {
-      __set_errno (EOVERFLOW);
+      __set_errno (EINVAL);
       return -1;
     }
-
-  its32.it_interval = valid_timespec64_to_timespec (value->it_interval);
-  its32.it_value = valid_timespec64_to_timespec (value->it_value);
-
-  int retval = INLINE_SYSCALL_CALL (timer_settime, ktimerid, flags,
-                                    &its32, ovalue ? &oits32 : NULL);
-  if (retval == 0 && ovalue)
+  host_value.it_interval_sec = value->it_interval.tv_sec;
+  host_value.it_interval_nsec = value->it_interval.tv_nsec;
+  host_value.it_value_sec = value->it_value.tv_sec;
+  host_value.it_value_nsec = value->it_value.tv_nsec;
+
+  int ret = MAKE_LEGACY_SYSCALL (TIMER_SETTIME_SYSCALL, "syscall|timer_settime",
+				 (uint64_t) ktimerid, (uint64_t) flags,
+				 TRANSLATE_GUEST_POINTER_TO_HOST (&host_value),
+				 ovalue != NULL
+				 ? TRANSLATE_GUEST_POINTER_TO_HOST (&host_ovalue)
+				 : 0,
+				 NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
+  if (ret == 0 && ovalue != NULL)
     {
-      ovalue->it_interval = valid_timespec_to_timespec64 (oits32.it_interval);
-      ovalue->it_value = valid_timespec_to_timespec64 (oits32.it_value);
+      ovalue->it_interval.tv_sec = host_ovalue.it_interval_sec;
+      ovalue->it_interval.tv_nsec = host_ovalue.it_interval_nsec;
+      ovalue->it_value.tv_sec = host_ovalue.it_value_sec;
+      ovalue->it_value.tv_nsec = host_ovalue.it_value_nsec;
     }
+  return ret;
 
-  return retval;
-# endif
+  // Lind-Wasm: Original glibc code removed for compatibility
+  // to find original source code refer to (2.39.9000) at
+  // (/home/lind-wasm/glibc/sysdeps/unix/sysv/linux/timer_settime.c):(32-66)
 }
 
 # if __TIMESIZE == 64
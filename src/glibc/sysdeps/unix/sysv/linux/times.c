@@ -18,36 +18,36 @@
 #include <errno.h>
 #include <sys/times.h>
 #include <sysdep.h>
-
+#include <stdint.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
 
 clock_t
 __times (struct tms *buf)
 {
-  clock_t ret = INTERNAL_SYSCALL_CALL (times, buf);
-  if (INTERNAL_SYSCALL_ERROR_P (ret)
-      && __glibc_unlikely (INTERNAL_SYSCALL_ERRNO (ret) == EFAULT)
-      && buf)
-    {
-      /* This might be an error or not.  For architectures which have no
-	 separate return value and error indicators we cannot
-	 distinguish a return value of e.g. (clock_t) -14 from -EFAULT.
-	 Therefore the only course of action is to dereference the user
-	 -supplied structure on a return of (clock_t) -14.  This will crash
-	 applications which pass in an invalid non-NULL BUF pointer.
-	 Note that Linux allows BUF to be NULL in which case we skip this.  */
-#define touch(v) \
-      do {								      \
-	clock_t temp = v;						      \
-	v = temp;							      \
-      } while (0)
-      touch (buf->tms_utime);
-      touch (buf->tms_stime);
-      touch (buf->tms_cutime);
-      touch (buf->tms_cstime);
+  /* Lind: the tms is handed over in the host layout, where clock_t is 64
+     bits wide.  */
+  struct
+  {
+    int64_t tms_utime;
+    int64_t tms_stime;
+    int64_t tms_cutime;
+    int64_t tms_cstime;
+  } host_buf;
 
-      /* If we come here the memory is valid and the kernel did not
-	 return an EFAULT error, but rather e.g. (clock_t) -14.
-	 Return the value given by the kernel.  */
+  /* times() cannot fail, the return value is a count of ticks that may
+     well look like an error code.  */
+  clock_t ret = MAKE_LEGACY_SYSCALL (
+      TIMES_SYSCALL, "syscall|times",
+      buf != NULL ? TRANSLATE_GUEST_POINTER_TO_HOST (&host_buf) : 0, NOTUSED,
+      NOTUSED, NOTUSED, NOTUSED, NOTUSED, TRANSLATE_ERRNO_OFF);
+  if (buf != NULL)
+    {
+      buf->tms_utime = host_buf.tms_utime;
+      buf->tms_stime = host_buf.tms_stime;
+      buf->tms_cutime = host_buf.tms_cutime;
+      buf->tms_cstime = host_buf.tms_cstime;
     }
 
   /* On Linux this function never fails except with EFAULT.
@@ -61,5 +61,9 @@ __times (struct tms *buf)
     return (clock_t) 0;
 
   return ret;
+
+  // Lind-Wasm: Original glibc code removed for compatibility
+  // to find original source code refer to (2.39.9000) at
+  // (/home/lind-wasm/glibc/sysdeps/unix/sysv/linux/times.c):(26-51)
 }
 weak_alias (__times, times)
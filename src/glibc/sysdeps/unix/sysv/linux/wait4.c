@@ -21,92 +21,57 @@
 #include <sys/types.h>
 #include <sysdep-cancel.h>
 #include <tv32-compat.h>
+#include <stdint.h>
+#include <syscall-template.h>
+#include <lind_syscall_num.h>
+#include <addr_translation.h>
+
+/* RawPOSIX fills the rusage buffer in the x86_64 kernel layout, where every
+   field is 64 bits wide.  */
+struct lind_rusage
+  {
+    int64_t ru_utime_sec, ru_utime_usec;
+    int64_t ru_stime_sec, ru_stime_usec;
+    int64_t ru_long[14]; /* ru_maxrss ... ru_nivcsw */
+  };
 
 pid_t
 __wait4_time64 (pid_t pid, int *stat_loc, int options, struct __rusage64 *usage)
 {
-#ifdef __NR_wait4
-# if __KERNEL_OLD_TIMEVAL_MATCHES_TIMEVAL64
-  return SYSCALL_CANCEL (wait4, pid, stat_loc, options, usage);
-# else
+  struct lind_rusage lind_usage;
   pid_t ret;
-  struct __rusage32 usage32;
 
-  ret = SYSCALL_CANCEL (wait4, pid, stat_loc, options,
-                        usage != NULL ? &usage32 : NULL);
+  ret = MAKE_LEGACY_SYSCALL (WAITPID_SYSCALL, "syscall|wait4", (uint64_t) pid,
+			     (uint64_t) TRANSLATE_GUEST_POINTER_TO_HOST (stat_loc),
+			     (uint64_t) options,
+			     (uint64_t) (usage != NULL
+					 ? TRANSLATE_GUEST_POINTER_TO_HOST (&lind_usage)
+					 : 0),
+			     NOTUSED, NOTUSED, TRANSLATE_ERRNO_ON);
 
   if (ret > 0 && usage != NULL)
-    rusage32_to_rusage64 (&usage32, usage);
-
-  return ret;
-# endif
-#elif defined (__ASSUME_WAITID_PID0_P_PGID)
-  idtype_t idtype = P_PID;
-
-  if (pid < -1)
     {
-      idtype = P_PGID;
-      pid *= -1;
+      usage->ru_utime.tv_sec = lind_usage.ru_utime_sec;
+      usage->ru_utime.tv_usec = lind_usage.ru_utime_usec;
+      usage->ru_stime.tv_sec = lind_usage.ru_stime_sec;
+      usage->ru_stime.tv_usec = lind_usage.ru_stime_usec;
+      usage->ru_maxrss = lind_usage.ru_long[0];
+      usage->ru_ixrss = lind_usage.ru_long[1];
+      usage->ru_idrss = lind_usage.ru_long[2];
+      usage->ru_isrss = lind_usage.ru_long[3];
+      usage->ru_minflt = lind_usage.ru_long[4];
+      usage->ru_majflt = lind_usage.ru_long[5];
+      usage->ru_nswap = lind_usage.ru_long[6];
+      usage->ru_inblock = lind_usage.ru_long[7];
+      usage->ru_oublock = lind_usage.ru_long[8];
+      usage->ru_msgsnd = lind_usage.ru_long[9];
+      usage->ru_msgrcv = lind_usage.ru_long[10];
+      usage->ru_nsignals = lind_usage.ru_long[11];
+      usage->ru_nvcsw = lind_usage.ru_long[12];
+      usage->ru_nivcsw = lind_usage.ru_long[13];
     }
-  else if (pid == -1)
-    idtype = P_ALL;
-  else if (pid == 0)
-    idtype = P_PGID;
 
-  options |= WEXITED;
-
-  siginfo_t infop;
-
-# if __KERNEL_OLD_TIMEVAL_MATCHES_TIMEVAL64
-  if (SYSCALL_CANCEL (waitid, idtype, pid, &infop, options, usage) < 0)
-    return -1;
-# else
-  {
-    struct __rusage32 usage32;
-    if (SYSCALL_CANCEL (waitid, idtype, pid, &infop, options, &usage32) < 0)
-      return -1;
-    if (usage != NULL)
-      rusage32_to_rusage64 (&usage32, usage);
-  }
-# endif
-
-  if (stat_loc)
-    {
-      switch (infop.si_code)
-        {
-        case CLD_EXITED:
-          *stat_loc = W_EXITCODE (infop.si_status, 0);
-          break;
-        case CLD_DUMPED:
-          *stat_loc = WCOREFLAG | infop.si_status;
-	  break;
-        case CLD_KILLED:
-          *stat_loc = infop.si_status;
-          break;
-        case CLD_TRAPPED:
-        case CLD_STOPPED:
-          *stat_loc = W_STOPCODE (infop.si_status);
-          break;
-        case CLD_CONTINUED:
-          *stat_loc = __W_CONTINUED;
-          break;
-	default:
-	  *stat_loc = 0;
-	  break;
-        }
-    }
-
-  return infop.si_pid;
-#else
-/* Linux waitid prior kernel 5.4 does not support waiting for the current
-   process.  It is possible to emulate wait4 it by calling getpgid for
-   PID 0, however, it would require an additional syscall and it is inherent
-   racy: after the current process group is received and before it is passed
-   to waitid a signal could arrive causing the current process group to
-   change.  */
-// # error "The kernel ABI does not provide a way to implement wait4"
-return 0;
-#endif
+  return ret;
 }
 
 #if __TIMESIZE != 64
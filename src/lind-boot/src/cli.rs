@@ -1,4 +1,10 @@
+use cage::deterministic::DeterministicConfig;
 use clap::*;
+use rawposix::netpolicy::{DeniedSocket, NetPolicy, NetRule, PortRange};
+use rawposix::resolver::{HostEntry, ResolverConfig};
+use rawposix::uts::UtsConfig;
+use std::time::Duration;
+use sysdefs::constants::sys_const::HOST_NAME_MAX;
 
 #[derive(Debug, Parser, Clone)]
 #[command(name = "lind-boot")]
@@ -39,6 +45,173 @@ pub struct CliOptions {
     /// cause the environment variable `FOO` to be inherited.
     #[arg(long = "env", number_of_values = 1, value_name = "NAME[=VAL]", value_parser = parse_env_var)]
     pub vars: Vec<(String, Option<String>)>,
+
+    /// Mount an in-memory tmpfs at a guest path.
+    ///
+    /// `--tmpfs /tmp` mounts an unlimited tmpfs at `/tmp`. An optional size
+    /// such as `--tmpfs /tmp:64M` caps the bytes it may hold; writes beyond
+    /// that fail with `ENOSPC`. The suffixes `K`, `M` and `G` are accepted.
+    /// May be given multiple times.
+    #[arg(long = "tmpfs", number_of_values = 1, value_name = "PATH[:SIZE]", value_parser = parse_tmpfs_mount)]
+    pub tmpfs: Vec<(String, Option<usize>)>,
+
+    /// Use the lindfs image read-only and send all modifications to DIR.
+    ///
+    /// Reads fall through to the image, files are copied into DIR before
+    /// they are modified, and deletions are recorded as whiteout files, so
+    /// the image can be shared by many runs. DIR must not be inside the image.
+    #[arg(long = "overlay-upper", value_name = "DIR")]
+    pub overlay_upper: Option<String>,
+
+    /// Give every cage its own upper directory inside the `--overlay-upper`
+    /// directory. A forked child starts from its parent's view, but the
+    /// changes it makes are not visible to the parent.
+    #[arg(long = "overlay-per-cage", requires = "overlay_upper")]
+    pub overlay_per_cage: bool,
+
+    /// Cap the memory committed by all cages together.
+    ///
+    /// When the cages map more than SIZE bytes in total, the cage with the
+    /// largest footprint is sent `SIGKILL`. The suffixes `K`, `M` and `G`
+    /// are accepted.
+    #[arg(long = "memory-limit", value_name = "SIZE", value_parser = parse_memory_size)]
+    pub memory_limit: Option<u64>,
+
+    /// Cap the memory committed by each cage.
+    ///
+    /// A cage that maps more than SIZE bytes is sent `SIGKILL`, and the call
+    /// that crossed the limit fails with `ENOMEM`. The suffixes `K`, `M` and
+    /// `G` are accepted.
+    #[arg(long = "cage-memory-limit", value_name = "SIZE", value_parser = parse_memory_size)]
+    pub cage_memory_limit: Option<u64>,
+
+    /// Give the cages a private network instead of the host's.
+    ///
+    /// TCP and UDP sockets only reach the loopback addresses of the
+    /// namespace, whose ports are not shared with the host or with other
+    /// runs. Connecting anywhere else fails with `ENETUNREACH`.
+    #[arg(long)]
+    pub netns: bool,
+
+    /// Expose a TCP port of the private network on the host.
+    ///
+    /// `--netns-forward 8080:18080` lets host programs reach a cage
+    /// listening on port 8080 at `127.0.0.1:18080`. May be given multiple
+    /// times.
+    #[arg(long = "netns-forward", number_of_values = 1, value_name = "GUEST_PORT:HOST_PORT", requires = "netns", value_parser = parse_port_forward)]
+    pub netns_forward: Vec<(u16, u16)>,
+
+    /// Only let the cages connect and send to addresses matching RULE.
+    ///
+    /// RULE is a CIDR block with an optional port range, such as
+    /// `10.0.0.0/8`, `192.0.2.1:443`, `[2001:db8::]/32:8000-8100` or
+    /// `*:53`. Other destinations fail with `ENETUNREACH`. May be given
+    /// multiple times.
+    #[arg(long = "net-allow", number_of_values = 1, value_name = "RULE", value_parser = parse_net_rule)]
+    pub net_allow: Vec<NetRule>,
+
+    /// Never let the cages connect or send to addresses matching RULE,
+    /// even if `--net-allow` permits them. May be given multiple times.
+    #[arg(long = "net-deny", number_of_values = 1, value_name = "RULE", value_parser = parse_net_rule)]
+    pub net_deny: Vec<NetRule>,
+
+    /// Only let the cages bind to local addresses matching RULE. Other
+    /// addresses fail with `EACCES`. May be given multiple times.
+    #[arg(long = "net-bind-allow", number_of_values = 1, value_name = "RULE", value_parser = parse_net_rule)]
+    pub net_bind_allow: Vec<NetRule>,
+
+    /// Never let the cages bind to local addresses matching RULE. May be
+    /// given multiple times.
+    #[arg(long = "net-bind-deny", number_of_values = 1, value_name = "RULE", value_parser = parse_net_rule)]
+    pub net_bind_deny: Vec<NetRule>,
+
+    /// Only let the cages listen on PORTS, a port or a range such as
+    /// `8000-8100`. Other ports fail with `EACCES`. May be given multiple
+    /// times.
+    #[arg(long = "net-listen-port", number_of_values = 1, value_name = "PORTS", value_parser = parse_port_range)]
+    pub net_listen_ports: Vec<PortRange>,
+
+    /// Refuse to create sockets of KIND: `unix`, `inet`, `inet6`,
+    /// `netlink`, `packet` or `raw`. `socket` fails with `EACCES`. May be
+    /// given multiple times.
+    #[arg(long = "net-deny-socket", number_of_values = 1, value_name = "KIND", value_parser = parse_denied_socket)]
+    pub net_deny_sockets: Vec<DeniedSocket>,
+
+    /// Append every network policy decision to FILE.
+    #[arg(long = "net-audit", value_name = "FILE")]
+    pub net_audit: Option<String>,
+
+    /// Host name the cages see through `uname`, `gethostname` and
+    /// `/proc/sys/kernel/hostname` (default: `lind`). A cage can change its
+    /// own with `sethostname`, which its children then inherit.
+    #[arg(long, value_name = "NAME", value_parser = parse_uts_name)]
+    pub hostname: Option<String>,
+
+    /// NIS domain name the cages see (default: `(none)`).
+    #[arg(long, value_name = "NAME", value_parser = parse_uts_name)]
+    pub domainname: Option<String>,
+
+    /// Kernel release reported by `uname` (default: `6.16.0-lind`).
+    #[arg(long = "uts-release", value_name = "RELEASE", value_parser = parse_uts_name)]
+    pub uts_release: Option<String>,
+
+    /// Kernel version reported by `uname`.
+    #[arg(long = "uts-version", value_name = "VERSION", value_parser = parse_uts_name)]
+    pub uts_version: Option<String>,
+
+    /// Machine reported by `uname` (default: `x86_64`).
+    #[arg(long = "uts-machine", value_name = "MACHINE", value_parser = parse_uts_name)]
+    pub uts_machine: Option<String>,
+
+    /// Serve generated `/etc/hosts`, `/etc/resolv.conf` and
+    /// `/etc/nsswitch.conf` to the cages instead of those of the image.
+    /// Implied by `--add-host` and `--dns-stub`.
+    #[arg(long = "resolver-files")]
+    pub resolver_files: bool,
+
+    /// Add `NAME:ADDR` to the generated `/etc/hosts`, such as
+    /// `db.test:10.0.0.5` or `db.test:[fd00::5]`. May be given multiple
+    /// times.
+    #[arg(long = "add-host", number_of_values = 1, value_name = "NAME:ADDR", value_parser = parse_host_entry)]
+    pub add_hosts: Vec<HostEntry>,
+
+    /// Answer DNS queries of the cages on `127.0.0.1:53` of the private
+    /// network, from the same table as `/etc/hosts`. Every other name fails
+    /// to resolve, so no query leaves the run.
+    #[arg(long = "dns-stub", requires = "netns")]
+    pub dns_stub: bool,
+
+    /// Run the cages deterministically, so that the same binary and input
+    /// make the same syscalls with the same results.
+    ///
+    /// The clocks start at 2000-01-01 and only move forward by a tick on
+    /// every syscall and by the length of every sleep, which returns at once.
+    /// Random bytes come from a stream seeded by `--seed`, and directories
+    /// are listed in name order.
+    #[arg(long)]
+    pub deterministic: bool,
+
+    /// Seed of the random bytes in deterministic mode (default: 0).
+    #[arg(long, value_name = "SEED", requires = "deterministic")]
+    pub seed: Option<u64>,
+
+    /// Virtual time a syscall takes in deterministic mode, standing in for
+    /// the instructions run since the last one, such as `1us` or `250ns`
+    /// (default: `1us`). The suffixes `ns`, `us`, `ms` and `s` are accepted.
+    #[arg(long, value_name = "DURATION", requires = "deterministic", value_parser = parse_tick)]
+    pub tick: Option<Duration>,
+
+    /// Log the syscalls of the cages to FILE, with their results and the
+    /// bytes they wrote into the memory of the cage, along with the signals
+    /// delivered, for `--replay`.
+    #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+    pub record: Option<String>,
+
+    /// Run the cages against a log written by `--record`: their syscalls
+    /// return the logged results instead of reaching the host, and the
+    /// logged signals are raised again after the same syscalls.
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<String>,
 }
 
 pub fn parse_env_var(s: &str) -> Result<(String, Option<String>), String> {
@@ -49,8 +222,146 @@ pub fn parse_env_var(s: &str) -> Result<(String, Option<String>), String> {
     ))
 }
 
+pub fn parse_tmpfs_mount(s: &str) -> Result<(String, Option<usize>), String> {
+    let (path, size) = match s.split_once(':') {
+        Some((path, size)) => (path, Some(size)),
+        None => (s, None),
+    };
+    if !path.starts_with('/') {
+        return Err(format!(
+            "tmpfs mount point must be an absolute path: {}",
+            path
+        ));
+    }
+    let quota = match size {
+        None => None,
+        Some(size) => Some(parse_size(size).map_err(|e| format!("tmpfs {}", e))? as usize),
+    };
+    Ok((path.to_string(), quota))
+}
+
+pub fn parse_memory_size(s: &str) -> Result<u64, String> {
+    parse_size(s).map_err(|e| format!("memory {}", e))
+}
+
+pub fn parse_port_forward(s: &str) -> Result<(u16, u16), String> {
+    let parse_port = |port: &str| match port.parse::<u16>() {
+        Ok(port) if port != 0 => Ok(port),
+        _ => Err(format!("port forward has an invalid port: {}", s)),
+    };
+    match s.split_once(':') {
+        Some((guest, host)) => Ok((parse_port(guest)?, parse_port(host)?)),
+        None => Err(format!("port forward must be GUEST_PORT:HOST_PORT: {}", s)),
+    }
+}
+
+pub fn parse_net_rule(s: &str) -> Result<NetRule, String> {
+    s.parse()
+}
+
+pub fn parse_port_range(s: &str) -> Result<PortRange, String> {
+    s.parse()
+}
+
+pub fn parse_denied_socket(s: &str) -> Result<DeniedSocket, String> {
+    s.parse()
+}
+
+pub fn parse_uts_name(s: &str) -> Result<String, String> {
+    if s.len() > HOST_NAME_MAX {
+        return Err(format!("name is longer than {} bytes: {}", HOST_NAME_MAX, s));
+    }
+    Ok(s.to_string())
+}
+
+pub fn parse_host_entry(s: &str) -> Result<HostEntry, String> {
+    s.parse()
+}
+
+pub fn parse_tick(s: &str) -> Result<Duration, String> {
+    let (digits, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(pos) => s.split_at(pos),
+        None => (s, "ns"),
+    };
+    let n: u64 = digits
+        .parse()
+        .map_err(|_| format!("tick is invalid: {}", s))?;
+    match unit {
+        "ns" => Ok(Duration::from_nanos(n)),
+        "us" => Ok(Duration::from_micros(n)),
+        "ms" => Ok(Duration::from_millis(n)),
+        "s" => Ok(Duration::from_secs(n)),
+        _ => Err(format!("tick has an unknown unit: {}", s)),
+    }
+}
+
+/// Parse a byte count with an optional `K`, `M` or `G` suffix
+fn parse_size(size: &str) -> Result<u64, String> {
+    let (digits, shift) = match size.as_bytes().last() {
+        Some(b'K' | b'k') => (&size[..size.len() - 1], 10),
+        Some(b'M' | b'm') => (&size[..size.len() - 1], 20),
+        Some(b'G' | b'g') => (&size[..size.len() - 1], 30),
+        _ => (size, 0),
+    };
+    let n: u64 = digits
+        .parse()
+        .map_err(|_| format!("size is invalid: {}", size))?;
+    n.checked_shl(shift)
+        .filter(|v| v >> shift == n && *v <= usize::MAX as u64)
+        .ok_or_else(|| format!("size is too large: {}", size))
+}
+
 impl CliOptions {
     pub fn wasm_file(&self) -> &str {
         &self.args[0]
     }
+
+    /// The network policy requested on the command line, if any
+    pub fn net_policy(&self) -> Option<NetPolicy> {
+        let policy = NetPolicy {
+            allow: self.net_allow.clone(),
+            deny: self.net_deny.clone(),
+            bind_allow: self.net_bind_allow.clone(),
+            bind_deny: self.net_bind_deny.clone(),
+            listen_ports: self.net_listen_ports.clone(),
+            denied_sockets: self.net_deny_sockets.clone(),
+        };
+        let restricted = !(policy.allow.is_empty()
+            && policy.deny.is_empty()
+            && policy.bind_allow.is_empty()
+            && policy.bind_deny.is_empty()
+            && policy.listen_ports.is_empty()
+            && policy.denied_sockets.is_empty());
+        (restricted || self.net_audit.is_some()).then_some(policy)
+    }
+
+    /// The names of the init cage, the defaults overridden by the command line
+    pub fn uts_config(&self) -> UtsConfig {
+        let default = UtsConfig::default();
+        UtsConfig {
+            nodename: self.hostname.clone().unwrap_or(default.nodename),
+            domainname: self.domainname.clone().unwrap_or(default.domainname),
+            release: self.uts_release.clone().unwrap_or(default.release),
+            version: self.uts_version.clone().unwrap_or(default.version),
+            machine: self.uts_machine.clone().unwrap_or(default.machine),
+        }
+    }
+
+    /// The deterministic execution requested on the command line, if any
+    pub fn deterministic_config(&self) -> Option<DeterministicConfig> {
+        let default = DeterministicConfig::default();
+        self.deterministic.then(|| DeterministicConfig {
+            seed: self.seed.unwrap_or(default.seed),
+            tick: self.tick.unwrap_or(default.tick),
+        })
+    }
+
+    /// The resolver setup requested on the command line, if any
+    pub fn resolver_config(&self) -> Option<ResolverConfig> {
+        let requested = self.resolver_files || !self.add_hosts.is_empty() || self.dns_stub;
+        requested.then(|| ResolverConfig {
+            hosts: self.add_hosts.clone(),
+            dns_stub: self.dns_stub,
+        })
+    }
 }
@@ -1,5 +1,6 @@
 use crate::{cli::CliOptions, lind_wasmtime::host::HostCtx, lind_wasmtime::trampoline::*};
 use anyhow::{Context, Result, anyhow, bail};
+use cage::ALTSTACK_DISABLED;
 use cage::signal::{lind_signal_init, signal_may_trigger};
 use cfg_if::cfg_if;
 use std::ffi::c_void;
@@ -14,6 +15,7 @@ use wasmtime::{
     WasmBacktraceDetails,
 };
 use wasmtime_lind_3i::{VmCtxWrapper, init_vmctx_pool, rm_vmctx, set_vmctx, set_vmctx_thread};
+use wasmtime_lind_multi_process::signal::trap_signal;
 use wasmtime_lind_multi_process::{CAGE_START_ID, LindCtx, THREAD_START_ID};
 use wasmtime_lind_utils::LindCageManager;
 use wasmtime_wasi_threads::WasiThreadsCtx;
@@ -97,6 +99,7 @@ pub fn execute_wasmtime(lindboot_cli: CliOptions) -> anyhow::Result<Vec<Val>> {
             &module,
             CAGE_START_ID as u64,
             &args,
+            &lind_manager,
         )
         .with_context(|| format!("failed to run main module"))
     });
@@ -160,8 +163,15 @@ pub fn execute_with_lind(
 
     // -- Run the module in the cage --
     let result = wasmtime_wasi::runtime::with_ambient_tokio_runtime(|| {
-        load_main_module(&mut wstore, &mut linker, &module, cageid as u64, &args)
-            .with_context(|| format!("failed to run main module"))
+        load_main_module(
+            &mut wstore,
+            &mut linker,
+            &module,
+            cageid as u64,
+            &args,
+            &lind_manager,
+        )
+        .with_context(|| format!("failed to run main module"))
     });
 
     result
@@ -375,6 +385,7 @@ fn load_main_module(
     module: &Module,
     cageid: u64,
     args: &[String],
+    lind_manager: &LindCageManager,
 ) -> Result<Vec<Val>> {
     // todo:
     // I don't setup `epoch_handler` since it seems not being used by our previous implementation.
@@ -422,12 +433,15 @@ fn load_main_module(
         }
     }
 
-    // initialize the signal for the main thread of the cage
+    // initialize the signal for the main thread of the cage, which starts (or after exec starts
+    // over) with an empty signal mask and no alternate signal stack
     lind_signal_init(
         cageid,
         pointer as *mut u64,
         THREAD_START_ID,
         true, /* this is the main thread */
+        0,
+        ALTSTACK_DISABLED,
     );
 
     // see comments at signal_may_trigger for more details
@@ -476,8 +490,13 @@ fn load_main_module(
         set_vmctx(cageid, backup_vmctx_wrapper);
     }
 
+    // a fault of the guest code is raised as a signal, see `trap_signal`. If it ended the cage,
+    // its status went to the parent with the signal and the return value of `_start` is moot
     let ret = match func {
-        Some(func) => invoke_func(store, func, &args),
+        Some(func) => invoke_func(store, func, &args).or_else(|err| {
+            trap_signal(store, &instance, cageid, THREAD_START_ID, lind_manager, err)
+                .map(|()| vec![Val::I32(0)])
+        }),
         None => Ok(vec![]),
     };
 
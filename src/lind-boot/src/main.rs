@@ -5,8 +5,17 @@ use crate::{
     cli::CliOptions,
     lind_wasmtime::{execute_wasmtime, precompile_module},
 };
+use cage::deterministic::deterministic_init;
 use clap::Parser;
 use rawposix::init::{rawposix_shutdown, rawposix_start};
+use rawposix::netns::netns_init;
+use rawposix::netpolicy::netpolicy_init;
+use rawposix::oom::memory_budget_init;
+use rawposix::overlay::overlay_init;
+use rawposix::resolver::resolver_init;
+use rawposix::tmpfs::tmpfs_mount;
+use rawposix::uts::uts_init;
+use threei::record::{record_init, replay_init};
 
 /// Entry point of the lind-boot executable.
 ///
@@ -27,9 +36,58 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
+    // The upper directory lives outside the image, so it has to be opened before
+    // RawPOSIX chroots into it
+    if let Some(upper) = &lindboot_cli.overlay_upper {
+        overlay_init(upper, lindboot_cli.overlay_per_cage)
+            .map_err(|e| format!("failed to set up overlay in {}: {:?}", upper, e))?;
+    }
+
+    if lindboot_cli.memory_limit.is_some() || lindboot_cli.cage_memory_limit.is_some() {
+        memory_budget_init(lindboot_cli.cage_memory_limit, lindboot_cli.memory_limit)
+            .map_err(|e| format!("invalid memory limit: {:?}", e))?;
+    }
+
+    // The audit log lives outside the image as well
+    if let Some(policy) = lindboot_cli.net_policy() {
+        netpolicy_init(policy, lindboot_cli.net_audit.as_deref())
+            .map_err(|e| format!("failed to set up network policy: {:?}", e))?;
+    }
+
+    if lindboot_cli.netns {
+        netns_init(&lindboot_cli.netns_forward)
+            .map_err(|e| format!("invalid network namespace setup: {:?}", e))?;
+    }
+
+    // The DNS stub binds in the namespace, so it comes after it
+    if let Some(config) = lindboot_cli.resolver_config() {
+        resolver_init(config).map_err(|e| format!("failed to set up the resolver: {:?}", e))?;
+    }
+
+    uts_init(&lindboot_cli.uts_config())
+        .map_err(|e| format!("invalid system identity: {:?}", e))?;
+
+    if let Some(config) = lindboot_cli.deterministic_config() {
+        deterministic_init(config)
+            .map_err(|e| format!("failed to set up deterministic mode: {:?}", e))?;
+    }
+
+    if let Some(path) = &lindboot_cli.record {
+        record_init(path).map_err(|e| format!("failed to record to {}: {:?}", path, e))?;
+    }
+    if let Some(path) = &lindboot_cli.replay {
+        replay_init(path).map_err(|e| format!("failed to replay {}: {:?}", path, e))?;
+    }
+
     // Initialize RawPOSIX and register RawPOSIX syscalls with 3i
     rawposix_start(0);
 
+    // Mount the requested in-memory filesystems before the first cage runs
+    for (path, quota) in &lindboot_cli.tmpfs {
+        tmpfs_mount(path, *quota)
+            .map_err(|e| format!("failed to mount tmpfs at {}: {:?}", path, e))?;
+    }
+
     // Execute with user-selected runtime. Can be switched to other runtime implementation
     // in the future (e.g.: MPK).
     execute_wasmtime(lindboot_cli)?;
@@ -0,0 +1,96 @@
+//! The RawPOSIX side of deterministic execution, see `cage::deterministic`
+//!
+//! - Directories read from the host are listed in name order rather than in the order of the
+//!   host filesystem, which depends on how the files were created. The listing of a directory
+//!   is read in full and sorted when reading starts at position 0, and then handed out from
+//!   memory, with `lseek()` moving through it like through an overlay listing.
+//! - A wait with a finite timeout in `poll()`, `select()` or `epoll_wait()` only samples the fds
+//!   on the host. If none is ready, the timeout is slept on the virtual clock, after which the
+//!   call reports that nothing became ready. An infinite wait still blocks on the host.
+
+use crate::overlay::{fill_dirents, last_errno, read_dir, DirEntry};
+use cage::deterministic::{is_deterministic, virtual_now, virtual_sleep_until};
+use cage::signal_check_trigger;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::os::fd::RawFd;
+use std::time::Duration;
+use sysdefs::constants::err_const::{syscall_error, Errno};
+
+/// The sorted listing and read position of each host directory fd read in deterministic mode
+static SORTED_DIRS: Lazy<DashMap<RawFd, (Vec<DirEntry>, usize)>> = Lazy::new(DashMap::new);
+
+/// `getdents64()` on a host directory in deterministic mode.
+///
+/// ## Returns:
+/// `None` outside of deterministic mode, otherwise the syscall result
+pub fn sorted_getdents(kernel_fd: RawFd, dirp: *mut u8, count: usize) -> Option<i32> {
+    if !is_deterministic() {
+        return None;
+    }
+    let mut entry = SORTED_DIRS.entry(kernel_fd).or_default();
+    let (listing, pos) = &mut *entry;
+    if *pos == 0 {
+        if unsafe { libc::lseek(kernel_fd, 0, libc::SEEK_SET) } < 0 {
+            return Some(syscall_error(
+                last_errno(),
+                "getdents",
+                "cannot rewind directory",
+            ));
+        }
+        *listing = match read_dir(kernel_fd) {
+            Ok(entries) => entries,
+            Err(e) => return Some(syscall_error(e, "getdents", "cannot list directory")),
+        };
+        listing.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    Some(fill_dirents(listing, pos, dirp, count))
+}
+
+/// `lseek()` on a host directory listed by `sorted_getdents`. The position counts entries of
+/// the sorted listing.
+///
+/// ## Returns:
+/// `None` if `kernel_fd` isn't such a directory, otherwise the syscall result
+pub fn sorted_lseek(kernel_fd: RawFd, offset: i64, whence: i32) -> Option<i32> {
+    let mut entry = SORTED_DIRS.get_mut(&kernel_fd)?;
+    let (_, pos) = &mut *entry;
+    let newpos = match whence {
+        libc::SEEK_SET => offset,
+        libc::SEEK_CUR => *pos as i64 + offset,
+        _ => -1,
+    };
+    if newpos < 0 {
+        return Some(syscall_error(Errno::EINVAL, "lseek", "Invalid argument"));
+    }
+    *pos = newpos as usize;
+    Some(newpos as i32)
+}
+
+/// Forget the listing of a host fd. Called when it is closed.
+pub fn sorted_forget_fd(kernel_fd: RawFd) {
+    SORTED_DIRS.remove(&kernel_fd);
+}
+
+/// The timeout to wait for on the host: a finite one is only sampled in deterministic mode
+pub fn host_timeout(timeout: Option<Duration>) -> Option<Duration> {
+    match timeout {
+        Some(_) if is_deterministic() => Some(Duration::ZERO),
+        timeout => timeout,
+    }
+}
+
+/// Once a wait sampled by `host_timeout` found nothing ready, sleep out its `timeout` on the
+/// virtual clock. A no-op outside of deterministic mode.
+///
+/// ## Returns:
+/// - `Err(EINTR)` if a signal for the cage arrived before the timeout was over
+pub fn virtual_timeout(cageid: u64, timeout: Option<Duration>) -> Result<(), Errno> {
+    let Some(timeout) = timeout.filter(|_| is_deterministic()) else {
+        return Ok(());
+    };
+    virtual_sleep_until(virtual_now().saturating_add(timeout), || {
+        signal_check_trigger(cageid)
+    })
+    .map_err(|_| Errno::EINTR)
+}
@@ -0,0 +1,328 @@
+//! Built-in virtual device nodes
+//!
+//! Cages run chrooted into lindfs, which does not (and should not) contain a populated `/dev`.
+//! Instead of leaking host device files into the sandbox, RawPOSIX serves the handful of
+//! character devices that ordinary programs expect directly from this module. Opening one of
+//! the paths below never touches the host kernel: the resulting virtual fd has kind
+//! `FDKIND_DEV` and its `underfd` identifies the open file description, which records the
+//! device it refers to. As for regular files, every `open()` creates a new description, so
+//! that epoll registrations and `O_ASYNC` state of two opens of the same device are separate.
+//!
+//! The `perfdinfo` of a device fd holds the file status flags it was opened with, so that
+//! `fcntl(F_GETFL)` / `fcntl(F_SETFL)` work without a kernel fd.
+//!
+//! `/dev/stdin`, `/dev/stdout` and `/dev/stderr` are aliases: opening them duplicates the
+//! cage's current fd 0 / 1 / 2, whatever kind that fd happens to be.
+use crate::epoll::epoll_forget;
+use crate::fs_calls::getrandom_syscall;
+use crate::interrupt::interruptible;
+use crate::sigio::sigio_forget;
+use dashmap::DashMap;
+use fdtables;
+use lazy_static::lazy_static;
+use libc::c_void;
+use std::sync::atomic::{AtomicU64, Ordering};
+use sysdefs::constants::err_const::{get_errno, handle_errno, syscall_error, Errno};
+use sysdefs::constants::fs_const::{
+    FIONBIO, F_GETFL, F_SETFL, O_ACCMODE, O_CLOEXEC, O_NONBLOCK, STDERR_FILENO, STDIN_FILENO,
+    STDOUT_FILENO, S_IFCHR, TIOCGWINSZ,
+};
+use sysdefs::constants::lind_platform_const::{FDKIND_DEV, UNUSED_ARG, UNUSED_ID};
+use sysdefs::constants::net_const::{POLLIN, POLLOUT};
+use sysdefs::data::fs_struct::StatData;
+
+/// Device ids, recorded in the open file description of `FDKIND_DEV` fds
+pub const DEV_NULL: u64 = 0;
+pub const DEV_ZERO: u64 = 1;
+pub const DEV_FULL: u64 = 2;
+pub const DEV_RANDOM: u64 = 3;
+pub const DEV_URANDOM: u64 = 4;
+pub const DEV_TTY: u64 = 5;
+
+/// Largest chunk requested from `getrandom_syscall` at a time. Linux caps a single
+/// `getrandom()` call at 32 MiB - 1, so we stay well below it.
+const DEV_RANDOM_CHUNK: usize = 1 << 20;
+
+lazy_static! {
+    // Device of each open file description, keyed by the `underfd` of the FDKIND_DEV fds
+    // referring to it
+    static ref DEV_DESCRIPTIONS: DashMap<u64, u64> = DashMap::new();
+}
+
+static NEXT_DESCRIPTION_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A node that can be resolved from a path under `/dev`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DevNode {
+    /// One of the devices served by this module, identified by its device id
+    Device(u64),
+    /// `/dev/stdin`, `/dev/stdout`, `/dev/stderr`: an alias of the given virtual fd
+    StdAlias(u64),
+}
+
+/// Resolve a normalized absolute path (as produced by `sc_convert_path_to_host`) to a
+/// virtual device node.
+///
+/// ## Returns:
+/// - `Some(node)` if the path names one of the built-in devices
+/// - `None` otherwise, in which case the caller should fall back to the host filesystem
+pub fn devfs_lookup(path: &[u8]) -> Option<DevNode> {
+    match path {
+        b"/dev/null" => Some(DevNode::Device(DEV_NULL)),
+        b"/dev/zero" => Some(DevNode::Device(DEV_ZERO)),
+        b"/dev/full" => Some(DevNode::Device(DEV_FULL)),
+        b"/dev/random" => Some(DevNode::Device(DEV_RANDOM)),
+        b"/dev/urandom" => Some(DevNode::Device(DEV_URANDOM)),
+        b"/dev/tty" => Some(DevNode::Device(DEV_TTY)),
+        b"/dev/stdin" | b"/dev/fd/0" => Some(DevNode::StdAlias(STDIN_FILENO as u64)),
+        b"/dev/stdout" | b"/dev/fd/1" => Some(DevNode::StdAlias(STDOUT_FILENO as u64)),
+        b"/dev/stderr" | b"/dev/fd/2" => Some(DevNode::StdAlias(STDERR_FILENO as u64)),
+        _ => None,
+    }
+}
+
+/// Create a new open file description of device `dev`, returning the `underfd` to store in
+/// the `FDKIND_DEV` fd that refers to it.
+pub fn devfs_new_description(dev: u64) -> u64 {
+    let id = NEXT_DESCRIPTION_ID.fetch_add(1, Ordering::Relaxed);
+    DEV_DESCRIPTIONS.insert(id, dev);
+    id
+}
+
+/// Return the device id of the open file description `underfd` of an `FDKIND_DEV` fd.
+pub fn devfs_device(underfd: u64) -> Option<u64> {
+    DEV_DESCRIPTIONS.get(&underfd).map(|dev| *dev)
+}
+
+/// Look up a virtual fd and return its device id if it refers to a built-in device.
+///
+/// Returns `None` for unknown fds as well; the caller's regular path will report `EBADF`.
+pub fn devfs_fd_device(cageid: u64, vfd: u64) -> Option<u64> {
+    match fdtables::translate_virtual_fd(cageid, vfd) {
+        Ok(entry) if entry.fdkind == FDKIND_DEV => devfs_device(entry.underfd),
+        _ => None,
+    }
+}
+
+/// Open a device node for `cageid`, returning the new virtual fd or a negative errno.
+///
+/// Device opens never fail for permission reasons: every device here is world
+/// readable and writable, matching the default modes on Linux.
+pub fn devfs_open(cageid: u64, node: DevNode, oflag: i32) -> i32 {
+    let should_cloexec = (oflag & O_CLOEXEC) != 0;
+
+    let (fdkind, underfd, perfdinfo) = match node {
+        DevNode::Device(dev) => (
+            FDKIND_DEV,
+            devfs_new_description(dev),
+            (oflag & !O_CLOEXEC) as u64,
+        ),
+        DevNode::StdAlias(stdfd) => {
+            // Same as reopening /proc/self/fd/N: share whatever fd N currently is
+            let entry = match fdtables::translate_virtual_fd(cageid, stdfd) {
+                Ok(entry) => entry,
+                Err(_) => return syscall_error(Errno::ENXIO, "open", "no such device"),
+            };
+            (entry.fdkind, entry.underfd, entry.perfdinfo)
+        }
+    };
+
+    match fdtables::get_unused_virtual_fd(cageid, fdkind, underfd, should_cloexec, perfdinfo) {
+        Ok(vfd) => vfd as i32,
+        Err(_) => {
+            if let DevNode::Device(_) = node {
+                DEV_DESCRIPTIONS.remove(&underfd);
+            }
+            syscall_error(Errno::EMFILE, "open", "Too many files opened")
+        }
+    }
+}
+
+/// Read from a device into `buf`.
+///
+/// `/dev/random` and `/dev/urandom` are both filled through `getrandom_syscall` so that
+/// every source of randomness in a cage goes through the same syscall path.
+pub fn devfs_read(cageid: u64, dev: u64, buf: *mut u8, count: usize) -> i32 {
+    match dev {
+        DEV_NULL => 0,
+        DEV_ZERO | DEV_FULL => {
+            unsafe { std::ptr::write_bytes(buf, 0, count) };
+            count as i32
+        }
+        DEV_RANDOM | DEV_URANDOM => {
+            let mut filled = 0usize;
+            while filled < count {
+                let chunk = (count - filled).min(DEV_RANDOM_CHUNK);
+                let ret = getrandom_syscall(
+                    cageid,
+                    buf as u64 + filled as u64,
+                    cageid,
+                    chunk as u64,
+                    cageid,
+                    0,
+                    cageid,
+                    UNUSED_ARG,
+                    UNUSED_ID,
+                    UNUSED_ARG,
+                    UNUSED_ID,
+                    UNUSED_ARG,
+                    UNUSED_ID,
+                );
+                if ret < 0 {
+                    // Report a partial read if we already produced some bytes
+                    return if filled > 0 { filled as i32 } else { ret };
+                }
+                filled += ret as usize;
+            }
+            filled as i32
+        }
+        DEV_TTY => {
+            // The controlling terminal of a cage is the terminal lind-boot runs in
+            let ret = interruptible(cageid, true, || unsafe {
+                libc::read(STDIN_FILENO, buf as *mut c_void, count)
+            }) as i32;
+            if ret < 0 {
+                return handle_errno(get_errno(), "read");
+            }
+            ret
+        }
+        _ => syscall_error(Errno::ENXIO, "read", "no such device"),
+    }
+}
+
+/// Write `buf` to a device.
+pub fn devfs_write(dev: u64, buf: *const u8, count: usize) -> i32 {
+    match dev {
+        // Writes to the random devices only "mix entropy" on Linux; accept and drop them
+        DEV_NULL | DEV_ZERO | DEV_RANDOM | DEV_URANDOM => count as i32,
+        DEV_FULL => syscall_error(Errno::ENOSPC, "write", "No space left on device"),
+        DEV_TTY => {
+            let ret = unsafe { libc::write(STDOUT_FILENO, buf as *const c_void, count) as i32 };
+            if ret < 0 {
+                return handle_errno(get_errno(), "write");
+            }
+            ret
+        }
+        _ => syscall_error(Errno::ENXIO, "write", "no such device"),
+    }
+}
+
+/// Device files have no file position; Linux accepts any seek on them and reports offset 0.
+pub fn devfs_lseek(dev: u64) -> i32 {
+    match dev {
+        DEV_TTY => syscall_error(Errno::ESPIPE, "lseek", "Illegal seek"),
+        _ => 0,
+    }
+}
+
+/// Fill `statbuf` for a device node.
+///
+/// Major/minor numbers follow Linux (`1:3` for null, `1:5` for zero, ... and `5:0` for tty)
+/// so that programs which compare `st_rdev` keep working.
+pub fn devfs_stat(dev: u64, statbuf: &mut StatData) {
+    let (major, minor): (u64, u64) = match dev {
+        DEV_NULL => (1, 3),
+        DEV_ZERO => (1, 5),
+        DEV_FULL => (1, 7),
+        DEV_RANDOM => (1, 8),
+        DEV_URANDOM => (1, 9),
+        _ => (5, 0),
+    };
+
+    *statbuf = StatData::default();
+    statbuf.st_dev = 5; // devtmpfs on a typical Linux host
+    statbuf.st_ino = (dev + 1) as usize;
+    statbuf.st_mode = (S_IFCHR | 0o666) as u32;
+    statbuf.st_nlink = 1;
+    statbuf.st_rdev = libc::makedev(major as u32, minor as u32) as u64;
+    statbuf.st_blksize = 4096;
+}
+
+/// `fcntl` commands that reach a device fd (descriptor flags and duplication are handled
+/// generically by `fcntl_syscall`). Only the file status flags are meaningful here.
+pub fn devfs_fcntl(cageid: u64, vfd: u64, perfdinfo: u64, cmd: i32, arg: i32) -> i32 {
+    match cmd {
+        F_GETFL => perfdinfo as i32,
+        F_SETFL => {
+            // The access mode can't be changed after open
+            let newflags = (perfdinfo as i32 & O_ACCMODE) | (arg & !O_ACCMODE);
+            match fdtables::set_perfdinfo(cageid, vfd, newflags as u64) {
+                Ok(()) => 0,
+                Err(_) => syscall_error(Errno::EBADF, "fcntl", "Bad File Descriptor"),
+            }
+        }
+        _ => syscall_error(Errno::EINVAL, "fcntl", "Invalid command for device"),
+    }
+}
+
+/// Handle `ioctl()` on a device fd. `FIONBIO` toggles `O_NONBLOCK` in the status flags kept
+/// in `perfdinfo`, like `F_SETFL` does. Only the tty is a terminal: its window size comes from
+/// the host terminal. The other devices refuse terminal requests the way their Linux drivers
+/// do, with `EINVAL` for the random devices and `ENOTTY` for the memory devices.
+pub fn devfs_ioctl(cageid: u64, vfd: u64, perfdinfo: u64, dev: u64, req: u32, arg: *mut u8) -> i32 {
+    match req {
+        FIONBIO => {
+            if arg.is_null() {
+                return syscall_error(Errno::EFAULT, "ioctl", "Invalid address");
+            }
+            let newflags = if unsafe { *(arg as *const i32) } != 0 {
+                perfdinfo as i32 | O_NONBLOCK
+            } else {
+                perfdinfo as i32 & !O_NONBLOCK
+            };
+            match fdtables::set_perfdinfo(cageid, vfd, newflags as u64) {
+                Ok(()) => 0,
+                Err(_) => syscall_error(Errno::EBADF, "ioctl", "Bad File Descriptor"),
+            }
+        }
+        TIOCGWINSZ if dev == DEV_TTY => {
+            let ret = unsafe { libc::ioctl(STDOUT_FILENO, req as u64, arg as *mut c_void) };
+            if ret < 0 {
+                return handle_errno(get_errno(), "ioctl");
+            }
+            ret
+        }
+        _ if dev == DEV_TTY => syscall_error(Errno::EINVAL, "ioctl", "Unsupported tty request"),
+        _ if dev == DEV_RANDOM || dev == DEV_URANDOM => {
+            syscall_error(Errno::EINVAL, "ioctl", "Invalid request for random device")
+        }
+        _ => syscall_error(Errno::ENOTTY, "ioctl", "Inappropriate ioctl for device"),
+    }
+}
+
+/// Check an access mode against a device node. All devices are `rw-rw-rw-`.
+pub fn devfs_access(amode: i32) -> i32 {
+    if amode & libc::X_OK != 0 {
+        return syscall_error(Errno::EACCES, "access", "Permission denied");
+    }
+    0
+}
+
+/// Compute poll readiness for a device. None of the devices ever block for writing, and
+/// all but the tty always have data to read, so those report ready immediately.
+pub fn devfs_poll_revents(dev: u64, events: i16) -> i16 {
+    let ready = if dev == DEV_TTY {
+        let mut hostfd = libc::pollfd {
+            fd: STDIN_FILENO,
+            events: POLLIN,
+            revents: 0,
+        };
+        let readable = unsafe { libc::poll(&mut hostfd, 1, 0) } > 0;
+        if readable {
+            POLLIN | POLLOUT
+        } else {
+            POLLOUT
+        }
+    } else {
+        POLLIN | POLLOUT
+    };
+    events & ready
+}
+
+/// Close handler registered for `FDKIND_DEV`, called once the last fd referring to an open
+/// file description goes away. Devices hold no host resources, only the description is freed.
+pub fn devfs_close(fdentry: fdtables::FDTableEntry, _count: u64) {
+    epoll_forget(FDKIND_DEV, fdentry.underfd);
+    sigio_forget(FDKIND_DEV, fdentry.underfd);
+    DEV_DESCRIPTIONS.remove(&fdentry.underfd);
+}
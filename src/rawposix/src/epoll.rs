@@ -0,0 +1,305 @@
+//! Epoll interest lists
+//!
+//! An epoll fd of a cage is an fdtables `FDT_KINDEPOLL` entry with a kernel epoll instance
+//! behind it. Files the kernel knows, kernel fds, namespace sockets and other epoll instances,
+//! are registered with that instance, so `EPOLLET`, `EPOLLONESHOT` and `EPOLLEXCLUSIVE` work for
+//! them as they do on Linux. Device nodes and tmpfs files have no host fd to register. Their
+//! registrations are kept here and evaluated on every `epoll_wait`, with the state Linux keeps
+//! for each registration:
+//! - the events it is still armed for: a one-shot registration is disarmed once it was
+//!   reported, until `EPOLL_CTL_MOD` arms it again
+//! - the readiness it was last reported with: an edge-triggered registration is reported again
+//!   only once it becomes ready for something it was not ready for before
+//!
+//! Either way a ready registration reports the `data` it was registered with.
+//!
+//! The interest list belongs to the epoll instance, not to the fd. Instances are keyed by their
+//! fdtables entry, which `dup` and `fork` share between the fds they create, so all of those
+//! see the same registrations and the same one-shot and edge state. The instance, kernel epoll
+//! fd included, goes away with the last fd referring to it.
+
+use crate::devfs::{devfs_device, devfs_poll_revents};
+use fdtables::{FDTableEntry, FDT_KINDEPOLL};
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use sysdefs::constants::err_const::{get_errno, Errno};
+use sysdefs::constants::net_const::{
+    EPOLLERR, EPOLLET, EPOLLEXCLUSIVE, EPOLLHUP, EPOLLIN, EPOLLONESHOT, EPOLLOUT, EPOLLWAKEUP,
+    EPOLL_CTL_ADD, EPOLL_CTL_DEL, EPOLL_CTL_MOD, POLLIN, POLLOUT,
+};
+use sysdefs::constants::{FDKIND_DEV, FDKIND_KERNEL, FDKIND_NETNS, FDKIND_TMPFS};
+
+/// Flags of a registration, as opposed to the events it waits for (`EP_PRIVATE_BITS`)
+const PRIVATE_BITS: u32 = (EPOLLWAKEUP | EPOLLONESHOT | EPOLLET | EPOLLEXCLUSIVE) as u32;
+
+/// What `EPOLLEXCLUSIVE` may be combined with (`EPOLLEXCLUSIVE_OK_BITS`)
+const EXCLUSIVE_OK_BITS: u32 =
+    (EPOLLIN | EPOLLOUT | EPOLLERR | EPOLLHUP | EPOLLWAKEUP | EPOLLET | EPOLLEXCLUSIVE) as u32;
+
+/// The file a registration watches, as the `(fdkind, underfd)` of its fdtables entry
+type Target = (u32, u64);
+
+struct Registration {
+    /// Events and flags as registered, plus `EPOLLERR | EPOLLHUP`, which are always waited
+    /// for. A disarmed one-shot registration has the flags left only.
+    events: u32,
+    data: u64,
+    /// The host fd it is registered under in the kernel instance, `None` if it is evaluated
+    /// here
+    hostfd: Option<i32>,
+    /// The readiness it was last reported with, for `EPOLLET`
+    reported: u32,
+}
+
+struct EpollInstance {
+    kernel_epfd: i32,
+    interest: HashMap<Target, Registration>,
+    /// The registrations handed to the kernel, by the host fd it reports them with
+    by_hostfd: HashMap<i32, Target>,
+}
+
+lazy_static! {
+    /// Epoll instances, by their fdtables entry
+    static ref EPOLL_INSTANCES: Mutex<HashMap<u64, EpollInstance>> = Mutex::new(HashMap::new());
+}
+
+fn last_errno() -> Errno {
+    Errno::from_discriminant(get_errno()).unwrap_or(Errno::EIO)
+}
+
+/// The fdtables entry of the epoll fd `epfd` of `cageid`
+fn instance_of(cageid: u64, epfd: u64) -> Result<u64, Errno> {
+    let entry = fdtables::translate_virtual_fd(cageid, epfd).map_err(|_| Errno::EBADF)?;
+    if entry.fdkind != FDT_KINDEPOLL {
+        return Err(Errno::EINVAL);
+    }
+    Ok(entry.underfd)
+}
+
+/// Record `kernel_epfd` as the kernel instance behind the epoll fd `epfd` fdtables just
+/// created for `cageid`. The instance owns it from now on.
+pub fn epoll_instance_init(cageid: u64, epfd: u64, kernel_epfd: i32) {
+    let entry = instance_of(cageid, epfd).unwrap();
+    EPOLL_INSTANCES.lock().insert(
+        entry,
+        EpollInstance {
+            kernel_epfd,
+            interest: HashMap::new(),
+            by_hostfd: HashMap::new(),
+        },
+    );
+}
+
+/// Add, change or remove the registration of `fd` with the epoll fd `epfd`. `event` holds the
+/// `events` and `data` of the caller's `epoll_event` and is `None` for `EPOLL_CTL_DEL` only.
+///
+/// ## Returns:
+/// - `Ok(())` on success
+/// - `Err(EBADF)` if either fd is not open, `Err(EINVAL)` if `epfd` is not an epoll fd, is
+///   `fd` itself, or `EPOLLEXCLUSIVE` is used where Linux refuses it, `Err(EEXIST)` or
+///   `Err(ENOENT)` if `fd` is or is not registered already, `Err(EPERM)` if `fd` cannot be
+///   waited for, or the error of the kernel's `epoll_ctl`
+pub fn epoll_ctl_registration(
+    cageid: u64,
+    epfd: u64,
+    op: i32,
+    fd: u64,
+    event: Option<(u32, u64)>,
+) -> Result<(), Errno> {
+    let instance = instance_of(cageid, epfd)?;
+    let entry = fdtables::translate_virtual_fd(cageid, fd).map_err(|_| Errno::EBADF)?;
+    let target = (entry.fdkind, entry.underfd);
+    if target == (FDT_KINDEPOLL, instance) {
+        return Err(Errno::EINVAL);
+    }
+    if let Some((events, _)) = event {
+        if events & EPOLLEXCLUSIVE as u32 != 0
+            && (op == EPOLL_CTL_MOD
+                || entry.fdkind == FDT_KINDEPOLL
+                || events & !EXCLUSIVE_OK_BITS != 0)
+        {
+            return Err(Errno::EINVAL);
+        }
+    }
+
+    let mut instances = EPOLL_INSTANCES.lock();
+    let hostfd = match entry.fdkind {
+        FDKIND_KERNEL | FDKIND_NETNS => Some(entry.underfd as i32),
+        FDT_KINDEPOLL => {
+            let nested = instances.get(&entry.underfd).ok_or(Errno::EBADF)?;
+            Some(nested.kernel_epfd)
+        }
+        FDKIND_DEV | FDKIND_TMPFS => None,
+        _ => return Err(Errno::EPERM),
+    };
+    let ep = instances.get_mut(&instance).ok_or(Errno::EBADF)?;
+
+    match (op, ep.interest.get(&target)) {
+        (EPOLL_CTL_ADD, Some(_)) => return Err(Errno::EEXIST),
+        (EPOLL_CTL_MOD | EPOLL_CTL_DEL, None) => return Err(Errno::ENOENT),
+        (EPOLL_CTL_MOD, Some(reg)) if reg.events & EPOLLEXCLUSIVE as u32 != 0 => {
+            return Err(Errno::EINVAL)
+        }
+        _ => {}
+    }
+
+    if let Some(hostfd) = hostfd {
+        // The kernel reports the registration with the host fd, translated back in
+        // `epoll_ready_events`
+        let mut kernel_event = libc::epoll_event {
+            events: event.map_or(0, |(events, _)| events),
+            u64: hostfd as u64,
+        };
+        let ret = unsafe { libc::epoll_ctl(ep.kernel_epfd, op, hostfd, &mut kernel_event) };
+        if ret < 0 {
+            return Err(last_errno());
+        }
+    }
+
+    match event {
+        Some((events, data)) => {
+            let reg = Registration {
+                events: events | (EPOLLERR | EPOLLHUP) as u32,
+                data,
+                hostfd,
+                reported: 0,
+            };
+            ep.interest.insert(target, reg);
+            if let Some(hostfd) = hostfd {
+                ep.by_hostfd.insert(hostfd, target);
+            }
+        }
+        None => {
+            ep.interest.remove(&target);
+            if let Some(hostfd) = hostfd {
+                ep.by_hostfd.remove(&hostfd);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Readiness of a file evaluated here, in `EPOLL*` bits
+fn readiness((fdkind, underfd): Target) -> u32 {
+    match fdkind {
+        FDKIND_DEV => {
+            devfs_device(underfd).map_or(0, |dev| devfs_poll_revents(dev, POLLIN | POLLOUT) as u32)
+        }
+        // Regular files on tmpfs are always readable and writable, as for poll
+        _ => (EPOLLIN | EPOLLOUT) as u32,
+    }
+}
+
+/// Gather what is ready among the registrations of the epoll fd `epfd` into `events`. The
+/// kernel instance is waited on for up to `timeout_ms` if nothing evaluated here is ready, and
+/// only sampled otherwise.
+///
+/// ## Returns:
+/// - the number of events stored, which is 0 if nothing became ready in time
+/// - `Err(EBADF)` or `Err(EINVAL)` if `epfd` is not an epoll fd, or the error of the kernel's
+///   `epoll_wait`
+pub fn epoll_ready_events(
+    cageid: u64,
+    epfd: u64,
+    events: &mut [libc::epoll_event],
+    timeout_ms: i32,
+) -> Result<usize, Errno> {
+    let instance = instance_of(cageid, epfd)?;
+    let mut count = 0;
+
+    let kernel_epfd = {
+        let mut instances = EPOLL_INSTANCES.lock();
+        let ep = instances.get_mut(&instance).ok_or(Errno::EBADF)?;
+        for (target, reg) in ep.interest.iter_mut() {
+            if count == events.len() {
+                break;
+            }
+            if reg.hostfd.is_some() {
+                continue;
+            }
+            let ready = readiness(*target) & reg.events & !PRIVATE_BITS;
+            if reg.events & EPOLLET as u32 != 0 {
+                let fresh = ready & !reg.reported;
+                reg.reported = ready;
+                if fresh == 0 {
+                    continue;
+                }
+            }
+            if ready == 0 {
+                continue;
+            }
+            events[count] = libc::epoll_event {
+                events: ready,
+                u64: reg.data,
+            };
+            count += 1;
+            if reg.events & EPOLLONESHOT as u32 != 0 {
+                reg.events &= PRIVATE_BITS;
+            }
+        }
+        ep.kernel_epfd
+    };
+    if count == events.len() {
+        return Ok(count);
+    }
+
+    // The kernel writes to a host buffer, as what it reports are host fds
+    let mut kernel_events = vec![libc::epoll_event { events: 0, u64: 0 }; events.len() - count];
+    let timeout_ms = if count > 0 { 0 } else { timeout_ms };
+    let ret = unsafe {
+        libc::epoll_wait(
+            kernel_epfd,
+            kernel_events.as_mut_ptr(),
+            kernel_events.len() as i32,
+            timeout_ms,
+        )
+    };
+    if ret < 0 {
+        let errno = last_errno();
+        return if errno == Errno::EINTR {
+            Ok(count)
+        } else {
+            Err(errno)
+        };
+    }
+
+    let instances = EPOLL_INSTANCES.lock();
+    if let Some(ep) = instances.get(&instance) {
+        for kernel_event in &kernel_events[..ret as usize] {
+            let hostfd = kernel_event.u64 as i32;
+            // Dropped while we waited, by a close or `EPOLL_CTL_DEL` of another thread
+            let Some(reg) = ep.by_hostfd.get(&hostfd).and_then(|t| ep.interest.get(t)) else {
+                continue;
+            };
+            events[count] = libc::epoll_event {
+                events: kernel_event.events,
+                u64: reg.data,
+            };
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Drop the registrations of a file whose last fd was closed, which removes it from every
+/// interest list on Linux. The close handler of every fd kind calls this.
+pub fn epoll_forget(fdkind: u32, underfd: u64) {
+    for ep in EPOLL_INSTANCES.lock().values_mut() {
+        if let Some(reg) = ep.interest.remove(&(fdkind, underfd)) {
+            if let Some(hostfd) = reg.hostfd {
+                ep.by_hostfd.remove(&hostfd);
+            }
+        }
+    }
+}
+
+/// Close handler registered for `FDT_KINDEPOLL`. The instance and its kernel epoll fd go away
+/// once no fd refers to them anymore.
+pub fn epoll_close(fdentry: FDTableEntry, _count: u64) {
+    let removed = EPOLL_INSTANCES.lock().remove(&fdentry.underfd);
+    if let Some(ep) = removed {
+        unsafe { libc::close(ep.kernel_epfd) };
+    }
+    epoll_forget(FDT_KINDEPOLL, fdentry.underfd);
+}
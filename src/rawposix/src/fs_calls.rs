@@ -1,22 +1,49 @@
+use crate::deterministic::{sorted_forget_fd, sorted_getdents, sorted_lseek};
+use crate::devfs::*;
+use crate::epoll::epoll_forget;
+use crate::futex::do_futex;
+use crate::interrupt::interruptible;
+use crate::oom::memory_budget_check;
+use crate::overlay::{
+    overlay_fd_path, overlay_forget_fd, overlay_getdents, overlay_layers, overlay_lseek,
+};
+use crate::procfs::{procfs_lookup, procfs_open};
+use crate::resolver::{resolver_access, resolver_lookup, resolver_open, resolver_stat};
+use crate::sigio::{sigio_fcntl, sigio_forget, sigio_is_async, sigio_set_async};
+use crate::sigpipe::{sigpipe_check, sigpipe_forget};
+use crate::tmpfs::{tmpfs_fd_handle, tmpfs_for_path, tmpfs_for_paths, TmpfsHandle};
+use cage::deterministic::{is_deterministic, virtual_now, virtual_random, virtual_sleep_until};
+use cage::timer::cage_clock_now;
 use cage::{
     get_cage, get_shm_length, is_mmap_error, new_shm_segment, round_up_page, shmat_helper,
-    shmdt_helper, signal::signal::lind_send_signal, MemoryBackingType, VmmapOps, HEAP_ENTRY_INDEX,
+    shmdt_helper, signal_check_trigger, MemoryBackingType, VmmapOps, HEAP_ENTRY_INDEX,
     SHM_METADATA,
 };
 use dashmap::mapref::entry::Entry::{Occupied, Vacant};
 use fdtables;
 use libc::c_void;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use sysdefs::constants::err_const::{get_errno, handle_errno, syscall_error, Errno};
 use sysdefs::constants::fs_const::{
-    FIOASYNC, FIONBIO, F_GETLK64, F_SETLK64, F_SETLKW64, MAP_ANONYMOUS, MAP_FIXED, MAP_POPULATE,
-    MAP_PRIVATE, MAP_SHARED, O_CLOEXEC, PAGESHIFT, PAGESIZE, PROT_EXEC, PROT_NONE, PROT_READ,
-    PROT_WRITE, SHMMAX, SHMMIN, SHM_DEST, SHM_RDONLY, STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO,
-    TIOCGWINSZ,
+    FALLOC_FL_KEEP_SIZE, FIOASYNC, FIONBIO, F_GETLK64, F_GETOWN_EX, F_SETLK64, F_SETLKW64,
+    F_SETOWN_EX, MADV_COLD, MADV_DODUMP, MADV_DOFORK, MADV_DONTDUMP, MADV_DONTFORK, MADV_DONTNEED,
+    MADV_FREE, MADV_HUGEPAGE, MADV_KEEPONFORK, MADV_MERGEABLE, MADV_NOHUGEPAGE, MADV_NORMAL,
+    MADV_PAGEOUT, MADV_RANDOM, MADV_REMOVE, MADV_SEQUENTIAL, MADV_UNMERGEABLE, MADV_WILLNEED,
+    MAP_ANONYMOUS, MAP_FIXED, MAP_GROWSDOWN, MAP_NORESERVE, MAP_POPULATE, MAP_PRIVATE, MAP_SHARED,
+    MREMAP_FIXED, MREMAP_MAYMOVE, MS_ASYNC, MS_INVALIDATE, MS_SYNC, O_ASYNC, O_CLOEXEC, PAGESHIFT,
+    PAGESIZE, PROT_EXEC, PROT_NONE, PROT_READ, PROT_WRITE, SHMMAX, SHMMIN, SHM_DEST, SHM_RDONLY,
+    STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO, TIOCGWINSZ,
 };
 
-use sysdefs::constants::lind_platform_const::{FDKIND_KERNEL, MAXFD, UNUSED_ARG, UNUSED_ID};
-use sysdefs::constants::sys_const::{DEFAULT_GID, DEFAULT_UID, SIGPIPE};
+use sysdefs::constants::lind_platform_const::{
+    FDKIND_DEV, FDKIND_KERNEL, FDKIND_NETNS, MAXFD, UNUSED_ARG, UNUSED_ID,
+};
+use sysdefs::constants::sys_const::{
+    CLOCK_PROCESS_CPUTIME_ID, CLOCK_THREAD_CPUTIME_ID, DEFAULT_GID, DEFAULT_UID, TIMER_ABSTIME,
+};
+use sysdefs::data::fs_struct::StatData;
 use sysdefs::logging::lind_debug_panic;
 use typemap::cage_helpers::*;
 use typemap::datatype_conversion::*;
@@ -32,11 +59,16 @@ use typemap::path_conversion::*;
 /// This function is registered in `fdtables` when creating the cage
 pub fn kernel_close(fdentry: fdtables::FDTableEntry, _count: u64) {
     let kernel_fd = fdentry.underfd as i32;
+    epoll_forget(FDKIND_KERNEL, fdentry.underfd);
+    sigio_forget(FDKIND_KERNEL, fdentry.underfd);
+    sigpipe_forget(kernel_fd);
 
     if kernel_fd == STDIN_FILENO || kernel_fd == STDOUT_FILENO || kernel_fd == STDERR_FILENO {
         return;
     }
 
+    overlay_forget_fd(kernel_fd);
+    sorted_forget_fd(kernel_fd);
     let ret = unsafe { libc::close(fdentry.underfd as i32) };
     if ret < 0 {
         let errno = get_errno();
@@ -97,8 +129,33 @@ pub extern "C" fn open_syscall(
         );
     }
 
-    // Get the kernel fd first
-    let kernel_fd = unsafe { libc::open(path.as_ptr(), oflag, mode) };
+    // Built-in device nodes are served by RawPOSIX and never reach the host
+    if let Some(node) = devfs_lookup(path.as_bytes()) {
+        return devfs_open(cageid, node, oflag);
+    }
+
+    // So are the memory statistics under /proc
+    if let Some(file) = procfs_lookup(cageid, path.as_bytes()) {
+        return procfs_open(cageid, file, oflag);
+    }
+
+    // and the resolver configuration under /etc
+    if let Some(file) = resolver_lookup(path.as_bytes()) {
+        return resolver_open(cageid, file, oflag);
+    }
+
+    if let Some(fs) = tmpfs_for_path(path.as_bytes()) {
+        return fs.open(cageid, path.as_bytes(), oflag, mode);
+    }
+
+    if let Some(layers) = overlay_layers(cageid) {
+        return layers.open(cageid, path.as_bytes(), oflag, mode);
+    }
+
+    // Get the kernel fd first, opening a FIFO waits for its other end
+    let kernel_fd = interruptible(cageid, true, || unsafe {
+        libc::open(path.as_ptr(), oflag, mode)
+    });
 
     if kernel_fd < 0 {
         return handle_errno(get_errno(), "open_syscall");
@@ -172,8 +229,18 @@ pub extern "C" fn read_syscall(
         );
     }
 
+    if let Some(dev) = devfs_fd_device(vfd_cageid, vfd_arg) {
+        return devfs_read(cageid, dev, buf as *mut u8, count);
+    }
+
+    if let Some(handle) = tmpfs_fd_handle(vfd_cageid, vfd_arg) {
+        return handle.read(buf as *mut u8, count);
+    }
+
     // Call the underlying libc read.
-    let ret = unsafe { libc::read(kernel_fd, buf as *mut c_void, count) as i32 };
+    let ret = interruptible(cageid, true, || unsafe {
+        libc::read(kernel_fd, buf as *mut c_void, count)
+    }) as i32;
     if ret < 0 {
         let errno = get_errno();
         return handle_errno(errno, "read");
@@ -241,8 +308,9 @@ pub extern "C" fn close_syscall(
 ///
 /// The Linux `futex()` syscall provides a mechanism for fast user-space locking. It allows a process or thread
 /// to wait for or wake another process or thread on a shared memory location without invoking heavy kernel-side
-/// synchronization primitives unless contention arises. This implementation wraps the futex syscall, allowing
-/// direct invocation with the relevant arguments passed from the current cage context.
+/// synchronization primitives unless contention arises. The futex words are not handed to the host kernel:
+/// RawPOSIX keeps the waiters in a futex table of its own, so that a signal sent to a waiting thread with
+/// `lind_send_signal` ends its wait with `EINTR`. See `crate::futex` for the table and the operations it supports.
 ///
 /// Input:
 ///     - cageid: current cageid
@@ -272,18 +340,13 @@ pub extern "C" fn futex_syscall(
     val3_cageid: u64,
 ) -> i32 {
     let uaddr = uaddr_arg;
-    let futex_op = sc_convert_sysarg_to_u32(futex_op_arg, futex_op_cageid, cageid);
+    let futex_op = sc_convert_sysarg_to_i32(futex_op_arg, futex_op_cageid, cageid);
     let val = sc_convert_sysarg_to_u32(val_arg, val_cageid, cageid);
     let timeout = timeout_arg;
     let uaddr2 = uaddr2_arg;
     let val3 = sc_convert_sysarg_to_u32(val3_arg, val3_cageid, cageid);
 
-    let ret = unsafe { syscall(SYS_futex, uaddr, futex_op, val, timeout, uaddr2, val3) as i32 };
-    if ret < 0 {
-        let errno = get_errno();
-        return handle_errno(errno, "futex");
-    }
-    ret
+    do_futex(cageid, uaddr, futex_op, val, timeout, uaddr2, val3)
 }
 
 /// Reference to Linux: https://man7.org/linux/man-pages/man2/write.2.html
@@ -336,15 +399,21 @@ pub extern "C" fn write_syscall(
         );
     }
 
-    let ret = unsafe { libc::write(kernel_fd, buf as *const c_void, count) as i32 };
+    if let Some(dev) = devfs_fd_device(vfd_cageid, vfd_arg) {
+        return devfs_write(dev, buf, count);
+    }
+
+    if let Some(handle) = tmpfs_fd_handle(vfd_cageid, vfd_arg) {
+        return handle.write(buf, count);
+    }
+
+    let ret = interruptible(cageid, true, || unsafe {
+        libc::write(kernel_fd, buf as *const c_void, count)
+    }) as i32;
 
     if ret < 0 {
-        let errno = get_errno();
         // Linux delivers SIGPIPE before returning EPIPE on broken pipe writes
-        if errno == Errno::EPIPE as i32 {
-            lind_send_signal(cageid, SIGPIPE);
-        }
-        return handle_errno(errno, "write");
+        return sigpipe_check(cageid, kernel_fd, 0, handle_errno(get_errno(), "write"));
     }
     return ret;
 }
@@ -398,6 +467,14 @@ pub extern "C" fn mkdir_syscall(
         );
     }
 
+    if let Some(fs) = tmpfs_for_path(path.as_bytes()) {
+        return fs.mkdir(path.as_bytes(), mode);
+    }
+
+    if let Some(layers) = overlay_layers(cageid) {
+        return layers.mkdir(path.as_bytes(), mode);
+    }
+
     let ret = unsafe { libc::mkdir(path.as_ptr(), mode) };
     // Error handling
     if ret < 0 {
@@ -636,7 +713,9 @@ pub extern "C" fn pipe2_syscall(
 ///
 /// This function processes the `mmap_syscall` by updating the `vmmap` entries and performing
 /// the necessary mmap operations. The handling logic is as follows:
-/// 1. Restrict allowed flags to `MAP_FIXED`, `MAP_SHARED`, `MAP_PRIVATE`, and `MAP_ANONYMOUS`.
+/// 1. Restrict allowed flags to `MAP_FIXED`, `MAP_SHARED`, `MAP_PRIVATE`, `MAP_ANONYMOUS`,
+///    `MAP_POPULATE`, `MAP_NORESERVE` and `MAP_GROWSDOWN`; return `EINVAL` for anything else.
+///    `MAP_GROWSDOWN` is accepted but the mapping never grows.
 /// 2. Disallow `PROT_EXEC`; return `EINVAL` if the `prot` argument includes `PROT_EXEC`.
 /// 3. If `MAP_FIXED` is not specified, query the `vmmap` structure to locate an available memory region.
 ///    Otherwise, use the address provided by the user.
@@ -686,7 +765,7 @@ pub extern "C" fn mmap_syscall(
 
     let mut maxprot = PROT_READ | PROT_WRITE;
 
-    // Validate flags - only these four flags are supported
+    // Validate flags - only the flags below are supported
     // Note: We explicitly validate rather than silently strip unsupported flags to:
     // 1. Prevent security issues (e.g., MAP_FIXED_NOREPLACE being ignored)
     // 2. Maintain program correctness (e.g., MAP_SHARED_VALIDATE expects validation)
@@ -695,13 +774,20 @@ pub extern "C" fn mmap_syscall(
         | MAP_SHARED as i32
         | MAP_PRIVATE as i32
         | MAP_ANONYMOUS as i32
-        | MAP_POPULATE as i32;
+        | MAP_POPULATE as i32
+        | MAP_NORESERVE as i32
+        | MAP_GROWSDOWN as i32;
     if flags & !allowed_flags != 0 {
-        lind_debug_panic("Unsupported mmap flag detected! Only MAP_FIXED, MAP_SHARED, MAP_PRIVATE, MAP_POPULATE AND MAP_ANONYMOUS allowed");
+        return syscall_error(Errno::EINVAL, "mmap", "unsupported mmap flags");
     }
 
+    // The region below a cage mapping is still part of the linear memory reservation, so
+    // letting the host grow the mapping downwards would hand out pages the vmmap doesn't
+    // know about. The mapping is treated as a plain fixed-size one instead.
+    flags &= !(MAP_GROWSDOWN as i32);
+
     if prot & PROT_EXEC > 0 {
-        lind_debug_panic("mmap protection flag PROT_EXEC is not allowed in Lind");
+        return syscall_error(Errno::EINVAL, "mmap", "PROT_EXEC is not allowed");
     }
 
     // check if the provided address is multiple of pages
@@ -763,6 +849,26 @@ pub extern "C" fn mmap_syscall(
     drop(vmmap);
 
     if rounded_length > 0 {
+        // tmpfs files have no host fd to map. Private mappings are served by an anonymous
+        // mapping filled with a copy of the file; shared ones can't be kept coherent.
+        let tmpfs_handle = if flags & MAP_ANONYMOUS as i32 == 0 {
+            tmpfs_fd_handle(vfd_cageid, fildes as u64)
+        } else {
+            None
+        };
+        let mut map_prot = prot;
+        if tmpfs_handle.is_some() {
+            if flags & MAP_SHARED as i32 != 0 {
+                return syscall_error(
+                    Errno::ENODEV,
+                    "mmap",
+                    "shared mappings of tmpfs files are not supported",
+                );
+            }
+            flags |= MAP_ANONYMOUS as i32;
+            map_prot |= PROT_READ | PROT_WRITE;
+        }
+
         if flags & MAP_ANONYMOUS as i32 > 0 {
             fildes = -1;
         }
@@ -771,10 +877,10 @@ pub extern "C" fn mmap_syscall(
             cageid,
             sysaddr as *mut u8,
             rounded_length as usize,
-            prot,
+            map_prot,
             flags,
             fildes,
-            off,
+            if tmpfs_handle.is_some() { 0 } else { off },
         );
 
         // Check for error BEFORE sys_to_user conversion
@@ -783,6 +889,16 @@ pub extern "C" fn mmap_syscall(
             return handle_errno(errno, "mmap");
         }
 
+        if let Some(handle) = tmpfs_handle {
+            let ret = handle.snapshot_into(result as *mut u8, rounded_length as usize, off);
+            if ret < 0 {
+                return ret;
+            }
+            if map_prot != prot {
+                unsafe { libc::mprotect(result as *mut c_void, rounded_length as usize, prot) };
+            }
+        }
+
         let vmmap = cage.vmmap.read();
         let result = vmmap.sys_to_user(result);
         drop(vmmap);
@@ -794,9 +910,16 @@ pub extern "C" fn mmap_syscall(
             }
 
             let mut vmmap = cage.vmmap.write();
+            // shared memory is known by what backs it, so that futexes in it can be shared too
+            let shared = flags as u32 & MAP_SHARED > 0;
             let backing = {
                 if flags as u32 & MAP_ANONYMOUS > 0 {
-                    MemoryBackingType::Anonymous
+                    if shared {
+                        let id = NEXT_SHARED_MAPPING.fetch_add(1, Ordering::Relaxed);
+                        MemoryBackingType::SharedAnonymous(id, useraddr)
+                    } else {
+                        MemoryBackingType::Anonymous
+                    }
                 } else {
                     // if we are doing file-backed mapping, we need to set maxprot to the file permission
                     let flags = fcntl_syscall(
@@ -819,7 +942,20 @@ pub extern "C" fn mmap_syscall(
                             as i32;
                     }
                     maxprot &= flags;
-                    MemoryBackingType::FileDescriptor(fildes as u64)
+                    let identity = if shared {
+                        host_file_identity(cageid, fildes)
+                    } else {
+                        None
+                    };
+                    match identity {
+                        Some((dev, ino)) => MemoryBackingType::SharedFile(
+                            fildes as u64,
+                            dev,
+                            ino,
+                            useraddr as i64 - off,
+                        ),
+                        None => MemoryBackingType::FileDescriptor(fildes as u64),
+                    }
                 }
             };
 
@@ -835,12 +971,34 @@ pub extern "C" fn mmap_syscall(
                 len as i64,
                 cageid,
             );
+            drop(vmmap);
+
+            if let Err(e) = memory_budget_check(cage.cageid) {
+                return syscall_error(e, "mmap", "memory budget exceeded");
+            }
         }
     }
 
     useraddr as i32
 }
 
+/// Ids of the `MAP_SHARED` anonymous mappings, see `MemoryBackingType::SharedAnonymous`
+static NEXT_SHARED_MAPPING: AtomicU64 = AtomicU64::new(1);
+
+/// The host device and inode of the file virtual fd `vfd` of the cage refers to, which identify
+/// a shared mapping of it. `None` if the fd has no host file behind it
+fn host_file_identity(cageid: u64, vfd: i32) -> Option<(u64, u64)> {
+    let entry = fdtables::translate_virtual_fd(cageid, vfd as u64).ok()?;
+    if entry.fdkind != FDKIND_KERNEL {
+        return None;
+    }
+    let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+    if unsafe { libc::fstat(entry.underfd as i32, &mut stat) } < 0 {
+        return None;
+    }
+    Some((stat.st_dev, stat.st_ino))
+}
+
 /// Helper function for `mmap` / `munmap`
 ///
 /// This function calls underlying libc::mmap and serves as helper functions for memory related (vmmap related)
@@ -987,383 +1145,507 @@ pub extern "C" fn munmap_syscall(
     0
 }
 
-/// Handles the `brk_syscall`, interacting with the `vmmap` structure.
+/// Helper for `munmap`-style releases in `mremap`.
 ///
-/// This function processes the `brk_syscall` by updating the `vmmap` entries and performing
-/// the necessary operations to adjust the program break. Specifically, it updates the program
-/// break by modifying the end of the heap entry (the first entry in `vmmap`) and invokes `mmap`
-/// to adjust the memory protection as needed.
+/// Puts `len` bytes at `sysaddr` back to an inaccessible anonymous reservation instead of
+/// unmapping them, so the host never gets a hole inside the cage's linear memory.
+fn release_to_prot_none(sysaddr: usize, len: usize) {
+    let result = unsafe {
+        libc::mmap(
+            sysaddr as *mut c_void,
+            len,
+            PROT_NONE,
+            (MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED) as i32,
+            -1,
+            0,
+        ) as usize
+    };
+    if result != sysaddr {
+        panic!(
+            "mremap: failed to reset released range to PROT_NONE with errno: {:?}",
+            get_errno()
+        );
+    }
+}
+
+/// Handles the `mremap_syscall`, interacting with the `vmmap` structure.
 ///
-/// Per man page: the actual Linux system call returns the new program
-/// break on success.  On failure, the system call returns the current
-/// break.  The glibc wrapper function does some work (i.e., checks
-/// whether the new break is less than addr) to provide the 0 and -1
-/// return values.
+/// The old range has to lie within a single `vmmap` entry. The handling logic is as follows:
+/// 1. Only `MREMAP_MAYMOVE` and `MREMAP_FIXED` are supported; anything else returns `EINVAL`.
+/// 2. Shrinking releases the tail the same way `munmap` does.
+/// 3. Growing first tries to extend the mapping in place when the pages right after it are free
+///    in the `vmmap`.
+/// 4. Otherwise, if `MREMAP_MAYMOVE` is set, a new region is picked with `find_map_space` (or
+///    taken from `new_address` under `MREMAP_FIXED`). File-backed shared mappings are mapped
+///    again at the new address, private ones get their contents copied over. The old range is
+///    released afterwards.
+///
+/// The host `mremap` is deliberately not used for moves since it would leave the old range
+/// unmapped inside linear memory. For the same reason shared anonymous mappings can only be
+/// resized in place.
 ///
 /// # Arguments
-/// * `cageid` - Identifier of the cage that initiated the `brk` syscall.
-/// * `brk` - The new program break address.
+/// * `cageid` - Identifier of the cage that initiated the `mremap` syscall.
+/// * `old_address` - Starting address of the mapping to resize (page aligned).
+/// * `old_size` - Current size of the mapping.
+/// * `new_size` - Requested size of the mapping.
+/// * `flags` - `MREMAP_MAYMOVE` and/or `MREMAP_FIXED`.
+/// * `new_address` - Destination address, only used with `MREMAP_FIXED`.
 ///
-/// ## Returns:
-///     - 0 on success.
-///     - -1 on error, with errno set to indicate the error.
-pub extern "C" fn brk_syscall(
+/// # Returns
+/// * `i32` - The (user) address of the resized mapping, or a negative errno.
+pub extern "C" fn mremap_syscall(
     cageid: u64,
-    brk_arg: u64,
-    brk_cageid: u64,
-    arg2: u64,
-    arg2_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
-    arg4: u64,
-    arg4_cageid: u64,
-    arg5: u64,
-    arg5_cageid: u64,
+    old_addr_arg: u64,
+    old_addr_cageid: u64,
+    old_size_arg: u64,
+    old_size_cageid: u64,
+    new_size_arg: u64,
+    new_size_cageid: u64,
+    flags_arg: u64,
+    flags_cageid: u64,
+    new_addr_arg: u64,
+    new_addr_cageid: u64,
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    let brk = sc_convert_sysarg_to_i32(brk_arg, brk_cageid, cageid);
-    // would sometimes check, sometimes be a no-op depending on the compiler settings
-    if !(sc_unusedarg(arg2, arg2_cageid)
-        && sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
-        && sc_unusedarg(arg5, arg5_cageid)
-        && sc_unusedarg(arg6, arg6_cageid))
-    {
+    let old_addr = sc_convert_to_u8_mut(old_addr_arg, old_addr_cageid, cageid) as usize;
+    let old_size = sc_convert_sysarg_to_usize(old_size_arg, old_size_cageid, cageid);
+    let new_size = sc_convert_sysarg_to_usize(new_size_arg, new_size_cageid, cageid);
+    let flags = sc_convert_sysarg_to_i32(flags_arg, flags_cageid, cageid);
+    let new_addr = sc_convert_to_u8_mut(new_addr_arg, new_addr_cageid, cageid) as usize;
+    if !(sc_unusedarg(arg6, arg6_cageid)) {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "brk_syscall"
+            "mremap_syscall"
         );
     }
 
-    let cage = get_cage(cageid).unwrap();
-
-    let mut vmmap = cage.vmmap.write();
-    let heap = vmmap.find_page(HEAP_ENTRY_INDEX).unwrap().clone();
-
-    assert!(heap.npages == vmmap.program_break);
-
-    // passing 0 to brk will always return the current brk
-    if brk == 0 {
-        return (PAGESIZE * heap.npages) as i32;
+    // MREMAP_DONTUNMAP would leave two live copies of the same pages behind, which the vmmap
+    // can't describe, so it is rejected along with any unknown flag
+    let allowed_flags = (MREMAP_MAYMOVE | MREMAP_FIXED) as i32;
+    if flags & !allowed_flags != 0 {
+        return syscall_error(Errno::EINVAL, "mremap", "unsupported mremap flags");
+    }
+    let may_move = flags & MREMAP_MAYMOVE as i32 != 0;
+    let fixed = flags & MREMAP_FIXED as i32 != 0;
+    if fixed && !may_move {
+        return syscall_error(
+            Errno::EINVAL,
+            "mremap",
+            "MREMAP_FIXED requires MREMAP_MAYMOVE",
+        );
     }
 
-    let old_brk_page = heap.npages;
-    // round up the break to multiple of pages
-    let brk_page = (round_up_page(brk as u64) >> PAGESHIFT) as u32;
-
-    // if we are incrementing program break, we need to check if we have enough space
-    if brk_page > old_brk_page {
-        if vmmap.check_existing_mapping(old_brk_page, brk_page - old_brk_page, 0) {
-            return syscall_error(Errno::ENOMEM, "brk", "no memory");
-        }
+    if round_up_page(old_addr as u64) as usize != old_addr {
+        return syscall_error(Errno::EINVAL, "mremap", "address it not aligned");
+    }
+    if new_size == 0 {
+        return syscall_error(Errno::EINVAL, "mremap", "new size cannot be zero");
+    }
+    // Linux duplicates shared mappings when old_size is zero; we don't support that
+    if old_size == 0 {
+        return syscall_error(Errno::EINVAL, "mremap", "old size cannot be zero");
     }
 
-    // remove the old entries since new entry is overlapping with it.
-    vmmap.remove_entry(0, old_brk_page);
-
-    // update vmmap entry
-    vmmap.add_entry_with_overwrite(
-        0,
-        brk_page,
-        heap.prot,
-        heap.maxprot,
-        heap.flags,
-        heap.backing,
-        heap.file_offset,
-        heap.file_size,
-        heap.cage_id,
-    );
-
-    let old_heap_end_usr = (old_brk_page * PAGESIZE) as u32;
-    let old_heap_end_sys = vmmap.user_to_sys(old_heap_end_usr) as *mut u8;
+    let old_len = round_up_page(old_size as u64) as usize;
+    let new_len = round_up_page(new_size as u64) as usize;
+    let old_npages = (old_len >> PAGESHIFT) as u32;
+    let new_npages = (new_len >> PAGESHIFT) as u32;
 
-    let new_heap_end_usr = (brk_page * PAGESIZE) as u32;
-    let new_heap_end_sys = vmmap.user_to_sys(new_heap_end_usr) as *mut u8;
+    let cage = get_cage(cageid).unwrap();
+    // Hold the write lock for the whole operation so no concurrent mmap can claim the pages
+    // we are about to grow into or move to
+    let mut vmmap = cage.vmmap.write();
 
-    vmmap.set_program_break(brk_page);
+    let old_user = vmmap.sys_to_user(old_addr);
+    let old_page = old_user >> PAGESHIFT;
+    let entry = match vmmap.overlapping_entries(old_page, old_npages).next() {
+        Some((interval, entry))
+            if interval.start() <= old_page && old_page + old_npages <= interval.end() + 1 =>
+        {
+            entry.clone()
+        }
+        _ => {
+            return syscall_error(
+                Errno::EFAULT,
+                "mremap",
+                "old range is not a single existing mapping",
+            );
+        }
+    };
+    if let MemoryBackingType::SharedMemory(_) = entry.backing {
+        return syscall_error(
+            Errno::EINVAL,
+            "mremap",
+            "shared memory segments cannot be remapped",
+        );
+    }
+    let is_shared = entry.flags & MAP_SHARED as i32 != 0;
+    let vfd = match entry.backing {
+        MemoryBackingType::FileDescriptor(vfd) | MemoryBackingType::SharedFile(vfd, ..) => {
+            vfd as i32
+        }
+        _ => -1,
+    };
 
-    drop(vmmap);
+    let target_user = if fixed {
+        let target = vmmap.sys_to_user(new_addr);
+        if round_up_page(new_addr as u64) as usize != new_addr {
+            return syscall_error(Errno::EINVAL, "mremap", "new address it not aligned");
+        }
+        let target_page = target >> PAGESHIFT;
+        if target_page < old_page + old_npages && old_page < target_page + new_npages {
+            return syscall_error(Errno::EINVAL, "mremap", "new range overlaps the old one");
+        }
+        if target_page + new_npages > vmmap.end_address {
+            return syscall_error(Errno::EINVAL, "mremap", "new range is out of bounds");
+        }
+        target
+    } else if new_npages <= old_npages {
+        if new_npages < old_npages {
+            release_to_prot_none(old_addr + new_len, old_len - new_len);
+            let _ = vmmap.remove_entry(old_page + new_npages, old_npages - new_npages);
+        }
+        return old_user as i32;
+    } else {
+        // Grow in place when nothing is mapped right after the old range
+        let tail_page = old_page + old_npages;
+        let new_end_page = old_page + new_npages;
+        let tail_free = new_end_page <= vmmap.end_address
+            && vmmap
+                .overlapping_entries(tail_page, new_npages - old_npages)
+                .next()
+                .is_none();
+        if tail_free {
+            let result = mmap_inner(
+                cageid,
+                (old_addr + old_len) as *mut u8,
+                new_len - old_len,
+                entry.prot,
+                entry.flags | MAP_FIXED as i32,
+                vfd,
+                entry.file_offset + (tail_page - entry.page_num) as i64 * PAGESIZE as i64,
+            );
+            if is_mmap_error(result) {
+                return handle_errno(get_errno(), "mremap");
+            }
+            let _ = vmmap.add_entry_with_overwrite(
+                old_page,
+                new_npages,
+                entry.prot,
+                entry.maxprot,
+                entry.flags,
+                entry.backing,
+                entry.file_offset + (old_page - entry.page_num) as i64 * PAGESIZE as i64,
+                entry.file_size,
+                cageid,
+            );
+            drop(vmmap);
+            if let Err(e) = memory_budget_check(cage.cageid) {
+                return syscall_error(e, "mremap", "memory budget exceeded");
+            }
+            return old_user as i32;
+        }
+        if !may_move {
+            return syscall_error(Errno::ENOMEM, "mremap", "cannot grow mapping in place");
+        }
+        match vmmap.find_map_space(new_npages, 1) {
+            Some(space) => space.start() << PAGESHIFT,
+            None => return syscall_error(Errno::ENOMEM, "mremap", "no memory"),
+        }
+    };
 
-    // if new brk is larger than old brk
-    // we need to mmap the new region
-    if brk_page > old_brk_page {
-        let ret = mmap_inner(
-            brk_cageid,
-            old_heap_end_sys,
-            ((brk_page - old_brk_page) * PAGESIZE) as usize,
-            heap.prot,
-            (heap.flags as u32 | MAP_FIXED) as i32,
-            -1,
-            0,
+    if is_shared && vfd == -1 {
+        return syscall_error(
+            Errno::EINVAL,
+            "mremap",
+            "shared anonymous mappings cannot be moved",
         );
+    }
 
-        // Check for error using page alignment
-        if is_mmap_error(ret) {
-            let errno = get_errno();
-            return handle_errno(errno, "brk");
+    // Map the destination. Private mappings are made writable until their contents are copied
+    let target_sys = vmmap.user_to_sys(target_user);
+    let file_offset = entry.file_offset + (old_page - entry.page_num) as i64 * PAGESIZE as i64;
+    let map_prot = if is_shared {
+        entry.prot
+    } else {
+        entry.prot | PROT_READ | PROT_WRITE
+    };
+    let result = mmap_inner(
+        cageid,
+        target_sys as *mut u8,
+        new_len,
+        map_prot,
+        entry.flags | MAP_FIXED as i32,
+        vfd,
+        file_offset,
+    );
+    if is_mmap_error(result) {
+        return handle_errno(get_errno(), "mremap");
+    }
+
+    if !is_shared {
+        unsafe {
+            if entry.prot & PROT_READ == 0 {
+                libc::mprotect(old_addr as *mut c_void, old_len, PROT_READ);
+            }
+            std::ptr::copy_nonoverlapping(
+                old_addr as *const u8,
+                target_sys as *mut u8,
+                old_len.min(new_len),
+            );
+            if map_prot != entry.prot {
+                libc::mprotect(target_sys as *mut c_void, new_len, entry.prot);
+            }
         }
     }
-    // if we are shrinking the brk
-    // we need to do something similar to munmap
-    // to unmap the extra memory
-    else if brk_page < old_brk_page {
-        let ret = mmap_inner(
-            brk_cageid,
-            new_heap_end_sys,
-            ((old_brk_page - brk_page) * PAGESIZE) as usize,
-            PROT_NONE,
-            (MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED) as i32,
-            -1,
-            0,
-        );
 
-        // Check for error using page alignment
-        if is_mmap_error(ret) {
-            let errno = get_errno();
-            return handle_errno(errno, "brk");
+    // the file of a shared mapping is at a new address now
+    let backing = match entry.backing {
+        MemoryBackingType::SharedFile(vfd, dev, ino, _) => {
+            MemoryBackingType::SharedFile(vfd, dev, ino, target_user as i64 - file_offset)
+        }
+        backing => backing,
+    };
+    release_to_prot_none(old_addr, old_len);
+    let _ = vmmap.remove_entry(old_page, old_npages);
+    let _ = vmmap.add_entry_with_overwrite(
+        target_user >> PAGESHIFT,
+        new_npages,
+        entry.prot,
+        entry.maxprot,
+        entry.flags,
+        backing,
+        file_offset,
+        entry.file_size,
+        cageid,
+    );
+    drop(vmmap);
+
+    if new_npages > old_npages {
+        if let Err(e) = memory_budget_check(cage.cageid) {
+            return syscall_error(e, "mremap", "memory budget exceeded");
         }
     }
 
-    // return brk address
-    (PAGESIZE * brk_page) as i32
+    target_user as i32
 }
 
-//------------------------------------FCNTL SYSCALL------------------------------------
-/// This function will be different in new code base (when splitting out type conversion function)
-/// since the conversion from u64 -> i32 in negative number will be different. These lines are repeated
-/// in 5 out of 6 fcntl_syscall cases, so wrapped these loc into helper functions to make code cleaner.
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/msync.2.html
 ///
-/// ## Arguments
-/// cageid: cage ID associate with virtual file descriptor
-/// vfd_arg: virtual file descriptor
+/// Linux `msync()` flushes changes made to a file-backed mapping back to the file. The range
+/// has to be mapped in the cage's `vmmap`; the flush itself is done by the host kernel.
 ///
-/// ## Return Type
-/// On success:
-/// Return corresponding FDTableEntry that contains
-/// (1) underlying kernel fd.
-/// (2) file descriptor kind.
-/// (3) O_CLOEXEC flag.
-/// (4) file descriptor specific extra information.
+/// ## Arguments:
+///     - cageid: current cage identifier
+///     - addr_arg: page aligned start of the range to flush
+///     - len_arg: length of the range in bytes
+///     - flags_arg: exactly one of `MS_ASYNC` / `MS_SYNC`, optionally with `MS_INVALIDATE`
+///     - arg4, arg5, arg6: unused arguments and their cage IDs
 ///
-/// On error:
-/// Return error num EBADF(Bad File Descriptor)
-pub fn _fcntl_helper(cageid: u64, vfd_arg: u64) -> Result<fdtables::FDTableEntry, Errno> {
-    if vfd_arg > MAXFD as u64 {
-        return Err(Errno::EBADF);
-    }
-    // Get underlying kernel fd
-    let wrappedvfd = fdtables::translate_virtual_fd(cageid, vfd_arg);
-    if wrappedvfd.is_err() {
-        return Err(Errno::EBADF);
+/// ## Returns:
+///     - 0 on success
+///     - `EINVAL` for bad flags or an unaligned address, `ENOMEM` if the range isn't mapped
+pub extern "C" fn msync_syscall(
+    cageid: u64,
+    addr_arg: u64,
+    addr_cageid: u64,
+    len_arg: u64,
+    len_cageid: u64,
+    flags_arg: u64,
+    flags_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let addr = sc_convert_to_u8_mut(addr_arg, addr_cageid, cageid) as usize;
+    let len = sc_convert_sysarg_to_usize(len_arg, len_cageid, cageid);
+    let flags = sc_convert_sysarg_to_i32(flags_arg, flags_cageid, cageid);
+    if !(sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "msync_syscall"
+        );
     }
-    Ok(wrappedvfd.unwrap())
+
+    if flags & !(MS_ASYNC | MS_INVALIDATE | MS_SYNC) != 0
+        || (flags & MS_ASYNC != 0 && flags & MS_SYNC != 0)
+    {
+        return syscall_error(Errno::EINVAL, "msync", "invalid flags");
+    }
+    if round_up_page(addr as u64) as usize != addr {
+        return syscall_error(Errno::EINVAL, "msync", "address it not aligned");
+    }
+
+    let rounded_length = round_up_page(len as u64) as usize;
+    if rounded_length == 0 {
+        return 0;
+    }
+    if let Err(e) = check_mapped_range(cageid, addr, rounded_length) {
+        return syscall_error(e, "msync", "range is not mapped");
+    }
+
+    let ret = unsafe { libc::msync(addr as *mut c_void, rounded_length, flags) };
+    if ret < 0 {
+        return handle_errno(get_errno(), "msync");
+    }
+    ret
 }
 
-/// Reference: https://man7.org/linux/man-pages/man2/fcntl.2.html
-///
-/// Due to the design of `fdtables` library, different virtual fds created by `dup`/`dup2` are
-/// actually refer to the same underlying kernel fd. Therefore, in `fcntl_syscall` we need to
-/// handle the cases of `F_DUPFD`, `F_DUPFD_CLOEXEC`, `F_GETFD`, and `F_SETFD` separately.
-///
-/// Among these, `F_DUPFD` and `F_DUPFD_CLOEXEC` cannot directly use the `dup_syscall` because,
-/// in `fcntl`, the duplicated fd is assigned to the lowest available number starting from `arg`,
-/// whereas the `dup_syscall` does not have this restriction and instead assigns the lowest
-/// available fd number globally.
-///
-/// Additionally, `F_DUPFD_CLOEXEC` and `F_SETFD` require updating the fd flag information
-/// (`O_CLOEXEC`) in fdtables after modifying the underlying kernel fd.
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/madvise.2.html
 ///
-/// For all other command operations, after translating the virtual fd to the corresponding
-/// kernel fd, they are redirected to the kernel `fcntl` syscall.
+/// Linux `madvise()` gives the kernel hints about how a range of memory will be used.
+/// Most of the advice values are passed through to the host as they are only hints.
+/// `MADV_DONTNEED` and `MADV_FREE` must leave private anonymous pages reading back as zero;
+/// since linear memory may be backed by a copy-on-write image, where the host would restore
+/// the image contents instead, those pages are replaced with a fresh anonymous mapping.
+/// `MADV_WIPEONFORK` is rejected because cage fork copies memory itself.
 ///
-/// ## Arguments
-/// vfd_arg: virtual file descriptor
-/// cmd: The operation
-/// arg: an optional third argument.  Whether or not this argument is required is determined by op.  
+/// ## Arguments:
+///     - cageid: current cage identifier
+///     - addr_arg: page aligned start of the range
+///     - len_arg: length of the range in bytes
+///     - advice_arg: one of the `MADV_*` values
+///     - arg4, arg5, arg6: unused arguments and their cage IDs
 ///
 /// ## Returns:
-///     - For a successful call, the return value depends on the operation:
-///       - `F_DUPFD`: The new file descriptor.
-///       - `F_GETFD`: Value of file descriptor flags.
-///       - `F_GETFL`: Value of file status flags.
-///       - `F_GETLEASE`: Type of lease held on file descriptor.
-///       - `F_GETOWN`: Value of file descriptor owner.
-///       - `F_GETSIG`: Value of signal sent when read or write becomes possible, or zero for traditional SIGIO behavior.
-///       - `F_GETPIPE_SZ`, `F_SETPIPE_SZ`: The pipe capacity.
-///       - `F_GET_SEALS`: A bit mask identifying the seals that have been set for the inode referred to by fd.
-///       - All other commands: Zero.
-///     - On error, -1 is returned and errno is set to indicate the error.
-///
-/// TODO: `F_GETOWN`, `F_SETOWN`, `F_GETOWN_EX`, `F_SETOWN_EX`, `F_GETSIG`, and `F_SETSIG` are used to manage I/O availability signals.
-pub extern "C" fn fcntl_syscall(
+///     - 0 on success
+///     - `EINVAL` for unknown advice or an unaligned address, `ENOMEM` if the range isn't mapped
+pub extern "C" fn madvise_syscall(
     cageid: u64,
-    vfd_arg: u64,
-    vfd_cageid: u64,
-    cmd_arg: u64,
-    cmd_cageid: u64,
-    int_arg: u64, // arg3: integer value (for F_DUPFD, F_SETFD, F_GETFL, etc.)
-    int_arg_cageid: u64,
-    ptr_arg: u64, // arg4: translated host pointer (for F_GETLK, F_SETLK, etc.)
-    ptr_arg_cageid: u64,
+    addr_arg: u64,
+    addr_cageid: u64,
+    len_arg: u64,
+    len_cageid: u64,
+    advice_arg: u64,
+    advice_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
     arg5: u64,
     arg5_cageid: u64,
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    let cmd = sc_convert_sysarg_to_i32(cmd_arg, cmd_cageid, cageid);
-
-    // Convert int value only, we handle the pointer args if it's a lock operation
-    let arg = int_arg as i32;
-
-    // Validate unused arguments
-    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
+    let addr = sc_convert_to_u8_mut(addr_arg, addr_cageid, cageid) as usize;
+    let len = sc_convert_sysarg_to_usize(len_arg, len_cageid, cageid);
+    let advice = sc_convert_sysarg_to_i32(advice_arg, advice_cageid, cageid);
+    if !(sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "fcntl_syscall"
+            "madvise_syscall"
         );
     }
 
-    match (cmd, arg) {
-        // Duplicate the file descriptor `vfd_arg` using the lowest-numbered
-        // available file descriptor greater than or equal to `arg`. The operation here
-        // is quite similar to `dup_syscall`, for specific operation explanation, see
-        // comments on `dup_syscall`.
-        (F_DUPFD, arg) => {
-            // Get fdtable entry
-            let vfd = match _fcntl_helper(cageid, vfd_arg) {
-                Ok(entry) => entry,
-                Err(e) => return syscall_error(e, "fcntl", "Bad File Descriptor"),
-            };
-            // Get lowest-numbered available file descriptor greater than or equal to `arg`
-            match fdtables::get_unused_virtual_fd_from_startfd(
-                cageid,
-                vfd.fdkind,
-                vfd.underfd,
-                false,
-                0,
-                arg as u64,
-            ) {
-                Ok(new_vfd) => return new_vfd as i32,
-                Err(_) => return syscall_error(Errno::EBADF, "fcntl", "Bad File Descriptor"),
-            }
-        }
-        // As for `F_DUPFD`, but additionally set the close-on-exec flag
-        // for the duplicate file descriptor.
-        (F_DUPFD_CLOEXEC, arg) => {
-            // Get fdtable entry
-            let vfd = match _fcntl_helper(cageid, vfd_arg) {
-                Ok(entry) => entry,
-                Err(e) => return syscall_error(e, "fcntl", "Bad File Descriptor"),
-            };
-            // Get lowest-numbered available file descriptor greater than or equal to `arg`
-            // and set the `O_CLOEXEC` flag
-            match fdtables::get_unused_virtual_fd_from_startfd(
-                cageid,
-                vfd.fdkind,
-                vfd.underfd,
-                true,
-                0,
-                arg as u64,
-            ) {
-                Ok(new_vfd) => return new_vfd as i32,
-                Err(_) => return syscall_error(Errno::EBADF, "fcntl", "Bad File Descriptor"),
-            }
-        }
-        // Return (as the function result) the file descriptor flags.
-        (F_GETFD, ..) => {
-            // Get fdtable entry
-            let vfd = match _fcntl_helper(cageid, vfd_arg) {
-                Ok(entry) => entry,
-                Err(e) => return syscall_error(e, "fcntl", "Bad File Descriptor"),
-            };
-            return vfd.should_cloexec as i32;
+    if round_up_page(addr as u64) as usize != addr {
+        return syscall_error(Errno::EINVAL, "madvise", "address it not aligned");
+    }
+
+    let zero_pages = match advice {
+        MADV_DONTNEED | MADV_FREE => true,
+        MADV_NORMAL | MADV_RANDOM | MADV_SEQUENTIAL | MADV_WILLNEED | MADV_REMOVE
+        | MADV_MERGEABLE | MADV_UNMERGEABLE | MADV_HUGEPAGE | MADV_NOHUGEPAGE | MADV_DONTDUMP
+        | MADV_DODUMP | MADV_COLD | MADV_PAGEOUT => false,
+        // These only affect host fork, which cages never go through
+        MADV_DONTFORK | MADV_DOFORK | MADV_KEEPONFORK => return 0,
+        _ => return syscall_error(Errno::EINVAL, "madvise", "unsupported advice"),
+    };
+
+    let rounded_length = round_up_page(len as u64) as usize;
+    if rounded_length == 0 {
+        return 0;
+    }
+    if let Err(e) = check_mapped_range(cageid, addr, rounded_length) {
+        return syscall_error(e, "madvise", "range is not mapped");
+    }
+
+    if !zero_pages {
+        let ret = unsafe { libc::madvise(addr as *mut c_void, rounded_length, advice) };
+        if ret < 0 {
+            return handle_errno(get_errno(), "madvise");
         }
-        // Set the file descriptor flags to the value specified by arg.
-        (F_SETFD, arg) => {
-            // Get fdtable entry
-            let vfd = match _fcntl_helper(cageid, vfd_arg) {
-                Ok(entry) => entry,
-                Err(e) => return syscall_error(e, "fcntl", "Bad File Descriptor"),
+        return 0;
+    }
+
+    // Split the range along vmmap entries: private anonymous parts get fresh zero pages with
+    // the entry's protection, everything else follows the host's semantics
+    let cage = get_cage(cageid).unwrap();
+    let vmmap = cage.vmmap.read();
+    let start_page = vmmap.sys_to_user(addr) >> PAGESHIFT;
+    let end_page = start_page + (rounded_length >> PAGESHIFT) as u32;
+    let mut segments = Vec::new();
+    for (interval, entry) in vmmap.overlapping_entries(start_page, end_page - start_page) {
+        let seg_start = interval.start().max(start_page);
+        let seg_end = (interval.end() + 1).min(end_page);
+        let private_anon =
+            entry.backing == MemoryBackingType::Anonymous && entry.flags & MAP_PRIVATE as i32 != 0;
+        segments.push((
+            vmmap.user_to_sys(seg_start << PAGESHIFT),
+            ((seg_end - seg_start) as usize) << PAGESHIFT,
+            entry.prot,
+            private_anon,
+        ));
+    }
+    drop(vmmap);
+
+    for (sysaddr, seg_len, prot, private_anon) in segments {
+        if private_anon {
+            let result = unsafe {
+                libc::mmap(
+                    sysaddr as *mut c_void,
+                    seg_len,
+                    prot,
+                    (MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED) as i32,
+                    -1,
+                    0,
+                ) as usize
             };
-            // Set underlying kernel fd flag
-            let ret = unsafe { libc::fcntl(vfd.underfd as i32, cmd, arg) };
-            if ret < 0 {
-                let errno = get_errno();
-                return handle_errno(errno, "fcntl");
-            }
-            // Set virtual fd flag
-            let cloexec_flag: bool = arg != 0;
-            match fdtables::set_cloexec(cageid, vfd_arg as u64, cloexec_flag) {
-                Ok(_) => return 0,
-                Err(_e) => return syscall_error(Errno::EBADF, "fcntl", "Bad File Descriptor"),
+            if result != sysaddr {
+                return handle_errno(get_errno(), "madvise");
             }
-        }
-        // todo: F_GETOWN and F_SETOWN commands are not implemented yet
-        (F_GETOWN, ..) => DEFAULT_GID as i32,
-        (F_SETOWN, arg) if arg >= 0 => 0,
-        _ => {
-            // Get fdtable entry
-            let vfd = match _fcntl_helper(cageid, vfd_arg) {
-                Ok(entry) => entry,
-                Err(e) => return syscall_error(e, "fcntl", "Bad File Descriptor"),
-            };
-            let is_lock_op = cmd == F_GETLK
-                || cmd == F_SETLK
-                || cmd == F_SETLKW
-                || cmd == F_GETLK64
-                || cmd == F_SETLK64
-                || cmd == F_SETLKW64;
-
-            let ret = if is_lock_op {
-                // Lock operation - use ptr_arg (arg4)
-                unsafe { libc::fcntl(vfd.underfd as i32, cmd, ptr_arg as *mut c_void) }
-            } else {
-                // Other operations - use int_arg (arg3)
-                unsafe { libc::fcntl(vfd.underfd as i32, cmd, arg) }
-            };
-
+        } else {
+            let ret = unsafe { libc::madvise(sysaddr as *mut c_void, seg_len, advice) };
             if ret < 0 {
-                let errno = get_errno();
-                return handle_errno(errno, "fcntl");
+                return handle_errno(get_errno(), "madvise");
             }
-            ret
         }
     }
+
+    0
 }
 
-//------------------------------------LINK SYSCALL------------------------------------
-/// Reference: https://man7.org/linux/man-pages/man2/link.2.html
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/mincore.2.html
 ///
-/// `link_syscall` creates a new link (hard link) to an existing file.
+/// Linux `mincore()` reports which pages of a range are resident in memory, one byte per page
+/// in `vec`. The range has to be mapped in the cage's `vmmap`; residency comes from the host.
 ///
 /// ## Arguments:
-///  - `cageid`: Identifier of the calling Cage (namespace / process-like container).
-///  - `oldpath_arg`: Address of the existing pathname in the caller's address space.
-///  - `oldpath_cageid`: Cage ID associated with `oldpath_arg`.
-///  - `newpath_arg`: Address of the new pathname in the caller's address space.
-///  - `newpath_cageid`: Cage ID associated with `newpath_arg`.
-///  - `arg3`–`arg6` and their corresponding `_cageid`: Reserved arguments (must be unused).
-///
-/// ## Implementation Details:
-///  - The path arguments are translated from the RawPOSIX perspective into host kernel paths
-///    using `sc_convert_path_to_host`, which applies path normalization relative to the cage's CWD.
-///  - The unused arguments are validated with `sc_unusedarg`; any unexpected values are treated
-///    as a security violation.
-///  - The underlying `libc::link()` is invoked with the translated paths.
-///  - On failure, `errno` is retrieved via `get_errno()` and normalized through `handle_errno()`.
+///     - cageid: current cage identifier
+///     - addr_arg: page aligned start of the range
+///     - len_arg: length of the range in bytes
+///     - vec_arg: output vector with one byte per page
+///     - arg4, arg5, arg6: unused arguments and their cage IDs
 ///
-/// ## Return Value:
-///  - `0` on success.
-///  - `-1` on failure, with `errno` set appropriately.
-pub extern "C" fn link_syscall(
+/// ## Returns:
+///     - 0 on success
+///     - `EINVAL` for an unaligned address, `ENOMEM` if the range isn't mapped
+pub extern "C" fn mincore_syscall(
     cageid: u64,
-    oldpath_arg: u64,
-    oldpath_cageid: u64,
-    newpath_arg: u64,
-    newpath_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
+    addr_arg: u64,
+    addr_cageid: u64,
+    len_arg: u64,
+    len_cageid: u64,
+    vec_arg: u64,
+    vec_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -1371,192 +1653,77 @@ pub extern "C" fn link_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    // Type conversion
-    let oldpath = match sc_convert_path_to_host(oldpath_arg, oldpath_cageid, cageid) {
-        Ok(oldpath) => oldpath,
-        Err(e) => return syscall_error(e, "link", "path conversion failed"),
-    };
-    let newpath = match sc_convert_path_to_host(newpath_arg, newpath_cageid, cageid) {
-        Ok(newpath) => newpath,
-        Err(e) => return syscall_error(e, "link", "path conversion failed"),
-    };
-
-    // Validate unused args
-    if !(sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
+    let addr = sc_convert_to_u8_mut(addr_arg, addr_cageid, cageid) as usize;
+    let len = sc_convert_sysarg_to_usize(len_arg, len_cageid, cageid);
+    let vec = sc_convert_to_u8_mut(vec_arg, vec_cageid, cageid);
+    if !(sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "link_syscall"
+            "mincore_syscall"
         );
     }
 
-    let ret = unsafe { libc::link(oldpath.as_ptr(), newpath.as_ptr()) };
+    if round_up_page(addr as u64) as usize != addr {
+        return syscall_error(Errno::EINVAL, "mincore", "address it not aligned");
+    }
 
-    if ret < 0 {
-        let errno = get_errno();
-        return handle_errno(errno, "link");
+    let rounded_length = round_up_page(len as u64) as usize;
+    if rounded_length == 0 {
+        return 0;
     }
-    ret
-}
-
-//------------------------------------XSTAT SYSCALL------------------------------------
-/// `xstat` retrieves file status information (versioned stat interface).
-/// Reference: https://man7.org/linux/man-pages/man2/stat.2.html
-///
-/// ## Arguments:
-///  - `vers`: Version parameter for stat structure compatibility.
-///  - `pathname`: Path to the file to get status information for.
-///  - `statbuf`: Buffer to store the file status information.
-///
-/// ## Implementation Details:
-///  - The path is converted from the RawPOSIX perspective to the host kernel perspective
-///    using `sc_convert_path_to_host`, which handles path normalization relative to the cage's CWD.
-///  - The underlying libc::stat() is called and results are copied to the user buffer.
-///
-/// ## Return Value:
-///  - `0` on success.
-///  - `-1` on failure, with `errno` set appropriately.
-pub extern "C" fn stat_syscall(
-    cageid: u64,
-    path_arg: u64,
-    path_cageid: u64,
-    statbuf_arg: u64,
-    statbuf_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
-    arg4: u64,
-    arg4_cageid: u64,
-    arg5: u64,
-    arg5_cageid: u64,
-    arg6: u64,
-    arg6_cageid: u64,
-) -> i32 {
-    // Type conversion
-    let path = match sc_convert_path_to_host(path_arg, path_cageid, cageid) {
-        Ok(path) => path,
-        Err(e) => return syscall_error(e, "stat", "path conversion failed"),
-    };
-
-    // Validate unused args
-    if !(sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
-        && sc_unusedarg(arg5, arg5_cageid)
-        && sc_unusedarg(arg6, arg6_cageid))
-    {
-        panic!(
-            "{}: unused arguments contain unexpected values -- security violation",
-            "stat_syscall"
-        );
-    }
-
-    // Declare statbuf by ourselves
-    let mut libc_statbuf: stat = unsafe { std::mem::zeroed() };
-    let libcret = unsafe { libc::stat(path.as_ptr(), &mut libc_statbuf) };
-
-    if libcret < 0 {
-        let errno = get_errno();
-        return handle_errno(errno, "xstat");
+    if let Err(e) = check_mapped_range(cageid, addr, rounded_length) {
+        return syscall_error(e, "mincore", "range is not mapped");
     }
 
-    // Convert libc stat to StatData and copy to user buffer
-    match sc_convert_addr_to_statdata(statbuf_arg, statbuf_cageid, cageid) {
-        Ok(statbuf_addr) => convert_statdata_to_user(statbuf_addr, libc_statbuf),
-        Err(e) => return syscall_error(e, "xstat", "Bad address"),
+    let ret = unsafe { libc::mincore(addr as *mut c_void, rounded_length, vec) };
+    if ret < 0 {
+        return handle_errno(get_errno(), "mincore");
     }
-
-    libcret
+    ret
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/statfs.2.html
-///
-/// Linux `statfs()` syscall returns information about a mounted filesystem
-/// that contains the file or directory specified by `path`.  
-/// In RawPOSIX, because each Cage has its own virtualized filesystem view,
-/// the path is first translated from the Cage's namespace into the host
-/// kernel namespace using `sc_convert_path_to_host`.  
-/// After translation, the kernel's `libc::statfs()` is invoked to obtain
-/// the filesystem information. The resulting `statfs` structure is then
-/// converted into our ABI-stable `FStatData` format and copied into the
-/// user-provided buffer in Cage memory.
-///
-/// ## Input:
-/// - `cageid`: Identifier of the current Cage
-/// - `path_arg`: Wasm address of the pathname string
-/// - `path_cageid`: Cage ID associated with `path_arg`
-/// - `statbuf_arg`: Wasm address of the buffer where results will be stored
-/// - `statbuf_cageid`: Cage ID associated with `statbuf_arg`
-/// - `arg3`–`arg6`: Unused arguments (validated for security)
+/// Helper for `msync` / `madvise` / `mincore`
 ///
-/// ## Return Value:
-/// - `0` on success  
-/// - `-1` on failure, with `errno` set appropriately
-pub extern "C" fn statfs_syscall(
-    cageid: u64,
-    path_arg: u64,
-    path_cageid: u64,
-    statbuf_arg: u64,
-    statbuf_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
-    arg4: u64,
-    arg4_cageid: u64,
-    arg5: u64,
-    arg5_cageid: u64,
-    arg6: u64,
-    arg6_cageid: u64,
-) -> i32 {
-    // Type conversion
-    let path = match sc_convert_path_to_host(path_arg, path_cageid, cageid) {
-        Ok(path) => path,
-        Err(e) => return syscall_error(e, "statfs", "path conversion failed"),
-    };
-
-    // Validate unused args
-    if !(sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
-        && sc_unusedarg(arg5, arg5_cageid)
-        && sc_unusedarg(arg6, arg6_cageid))
-    {
-        panic!(
-            "{}: unused arguments contain unexpected values -- security violation",
-            "statfs_syscall"
-        );
-    }
-
-    // Cast directly to libc::statfs and write kernel data into buffer.
-    let statbuf_ptr = statbuf_arg as *mut libc::statfs;
-    let ret = unsafe { libc::statfs(path.as_ptr(), statbuf_ptr) };
-
-    if ret < 0 {
-        let errno = get_errno();
-        return handle_errno(errno, "statfs");
+/// Checks that every page of [`sysaddr`, `sysaddr + len`) is covered by the cage's `vmmap`.
+/// Returns `ENOMEM`, the errno Linux uses for unmapped ranges, otherwise.
+fn check_mapped_range(cageid: u64, sysaddr: usize, len: usize) -> Result<(), Errno> {
+    let cage = get_cage(cageid).unwrap();
+    let vmmap = cage.vmmap.read();
+    let page_num = vmmap.sys_to_user(sysaddr) >> PAGESHIFT;
+    if vmmap.check_existing_mapping(page_num, (len >> PAGESHIFT) as u32, PROT_NONE) {
+        Ok(())
+    } else {
+        Err(Errno::ENOMEM)
     }
-
-    ret
 }
 
-//------------------------------------FSYNC SYSCALL------------------------------------
-/// `fsync` synchronizes a file's in-core state with storage device.
-/// Reference: https://man7.org/linux/man-pages/man2/fsync.2.html
+/// Handles the `brk_syscall`, interacting with the `vmmap` structure.
 ///
-/// ## Arguments:
-///  - `fd`: File descriptor to synchronize.
+/// This function processes the `brk_syscall` by updating the `vmmap` entries and performing
+/// the necessary operations to adjust the program break. Specifically, it updates the program
+/// break by modifying the end of the heap entry (the first entry in `vmmap`) and invokes `mmap`
+/// to adjust the memory protection as needed.
 ///
-/// ## Implementation Details:
-///  - The virtual file descriptor is converted to a kernel file descriptor using `convert_fd_to_host`.
-///  - This ensures proper translation between RawPOSIX virtual fds and host kernel fds.
-///  - The underlying libc::fsync() is called, which synchronizes both file data and metadata.
+/// Per man page: the actual Linux system call returns the new program
+/// break on success.  On failure, the system call returns the current
+/// break.  The glibc wrapper function does some work (i.e., checks
+/// whether the new break is less than addr) to provide the 0 and -1
+/// return values.
 ///
-/// ## Return Value:
-///  - `0` on success.
-///  - `-1` on failure, with `errno` set appropriately.
-pub extern "C" fn fsync_syscall(
+/// # Arguments
+/// * `cageid` - Identifier of the cage that initiated the `brk` syscall.
+/// * `brk` - The new program break address.
+///
+/// ## Returns:
+///     - 0 on success.
+///     - -1 on error, with errno set to indicate the error.
+pub extern "C" fn brk_syscall(
     cageid: u64,
-    fd_arg: u64,
-    fd_cageid: u64,
+    brk_arg: u64,
+    brk_cageid: u64,
     arg2: u64,
     arg2_cageid: u64,
     arg3: u64,
@@ -1568,10 +1735,8 @@ pub extern "C" fn fsync_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    // Type conversion
-    let virtual_fd = sc_convert_sysarg_to_i32(fd_arg, fd_cageid, cageid);
-
-    // Validate unused args
+    let brk = sc_convert_sysarg_to_i32(brk_arg, brk_cageid, cageid);
+    // would sometimes check, sometimes be a no-op depending on the compiler settings
     if !(sc_unusedarg(arg2, arg2_cageid)
         && sc_unusedarg(arg3, arg3_cageid)
         && sc_unusedarg(arg4, arg4_cageid)
@@ -1580,77 +1745,848 @@ pub extern "C" fn fsync_syscall(
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "fsync_syscall"
+            "brk_syscall"
         );
     }
 
-    let kernel_fd = convert_fd_to_host(virtual_fd as u64, fd_cageid, cageid);
-    // convert_fd_to_host returns negative errno values on error
-    if kernel_fd < 0 {
-        return handle_errno(kernel_fd, "read");
-    }
+    let cage = get_cage(cageid).unwrap();
 
-    let ret = unsafe { libc::fsync(kernel_fd) };
+    let mut vmmap = cage.vmmap.write();
+    let heap = vmmap.find_page(HEAP_ENTRY_INDEX).unwrap().clone();
 
-    if ret < 0 {
-        let errno = get_errno();
-        return handle_errno(errno, "fsync");
+    assert!(heap.npages == vmmap.program_break);
+
+    // passing 0 to brk will always return the current brk
+    if brk == 0 {
+        return (PAGESIZE * heap.npages) as i32;
     }
-    return ret;
-}
 
-//------------------------------------FDATASYNC SYSCALL------------------------------------
-/// `fdatasync` synchronizes a file's data to storage device (but not metadata).
-/// Reference: https://man7.org/linux/man-pages/man2/fdatasync.2.html
-///
-/// ## Arguments:
-///  - `fd`: File descriptor to synchronize.
-///
-/// ## Implementation Details:
-///  - The virtual file descriptor is converted to a kernel file descriptor using `convert_fd_to_host`.
-///  - This ensures proper translation between RawPOSIX virtual fds and host kernel fds.
-///  - The underlying libc::fdatasync() is called, which synchronizes only file data (not metadata
-///    like timestamps), making it potentially faster than fsync().
-///
-/// ## Return Value:
-///  - `0` on success.
-///  - `-1` on failure, with `errno` set appropriately.
-pub extern "C" fn fdatasync_syscall(
-    cageid: u64,
-    fd_arg: u64,
-    fd_cageid: u64,
-    arg2: u64,
-    arg2_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
-    arg4: u64,
-    arg4_cageid: u64,
-    arg5: u64,
-    arg5_cageid: u64,
-    arg6: u64,
-    arg6_cageid: u64,
-) -> i32 {
-    // Type conversion
-    let virtual_fd = sc_convert_sysarg_to_i32(fd_arg, fd_cageid, cageid);
+    let old_brk_page = heap.npages;
+    // round up the break to multiple of pages
+    let brk_page = (round_up_page(brk as u64) >> PAGESHIFT) as u32;
 
-    // Validate unused args
-    if !(sc_unusedarg(arg2, arg2_cageid)
-        && sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
-        && sc_unusedarg(arg5, arg5_cageid)
-        && sc_unusedarg(arg6, arg6_cageid))
-    {
-        panic!(
-            "{}: unused arguments contain unexpected values -- security violation",
-            "fdatasync_syscall"
-        );
+    // if we are incrementing program break, we need to check if we have enough space
+    if brk_page > old_brk_page {
+        if vmmap.check_existing_mapping(old_brk_page, brk_page - old_brk_page, 0) {
+            return syscall_error(Errno::ENOMEM, "brk", "no memory");
+        }
     }
 
-    let kernel_fd = convert_fd_to_host(virtual_fd as u64, fd_cageid, cageid);
-    // Return error
-    if kernel_fd < 0 {
-        return handle_errno(kernel_fd, "read");
-    }
+    // remove the old entries since new entry is overlapping with it.
+    vmmap.remove_entry(0, old_brk_page);
+
+    // update vmmap entry
+    vmmap.add_entry_with_overwrite(
+        0,
+        brk_page,
+        heap.prot,
+        heap.maxprot,
+        heap.flags,
+        heap.backing,
+        heap.file_offset,
+        heap.file_size,
+        heap.cage_id,
+    );
+
+    let old_heap_end_usr = (old_brk_page * PAGESIZE) as u32;
+    let old_heap_end_sys = vmmap.user_to_sys(old_heap_end_usr) as *mut u8;
+
+    let new_heap_end_usr = (brk_page * PAGESIZE) as u32;
+    let new_heap_end_sys = vmmap.user_to_sys(new_heap_end_usr) as *mut u8;
+
+    vmmap.set_program_break(brk_page);
+
+    drop(vmmap);
+
+    // if new brk is larger than old brk
+    // we need to mmap the new region
+    if brk_page > old_brk_page {
+        let ret = mmap_inner(
+            brk_cageid,
+            old_heap_end_sys,
+            ((brk_page - old_brk_page) * PAGESIZE) as usize,
+            heap.prot,
+            (heap.flags as u32 | MAP_FIXED) as i32,
+            -1,
+            0,
+        );
+
+        // Check for error using page alignment
+        if is_mmap_error(ret) {
+            let errno = get_errno();
+            return handle_errno(errno, "brk");
+        }
+
+        if let Err(e) = memory_budget_check(cage.cageid) {
+            return syscall_error(e, "brk", "memory budget exceeded");
+        }
+    }
+    // if we are shrinking the brk
+    // we need to do something similar to munmap
+    // to unmap the extra memory
+    else if brk_page < old_brk_page {
+        let ret = mmap_inner(
+            brk_cageid,
+            new_heap_end_sys,
+            ((old_brk_page - brk_page) * PAGESIZE) as usize,
+            PROT_NONE,
+            (MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED) as i32,
+            -1,
+            0,
+        );
+
+        // Check for error using page alignment
+        if is_mmap_error(ret) {
+            let errno = get_errno();
+            return handle_errno(errno, "brk");
+        }
+    }
+
+    // return brk address
+    (PAGESIZE * brk_page) as i32
+}
+
+//------------------------------------FCNTL SYSCALL------------------------------------
+/// This function will be different in new code base (when splitting out type conversion function)
+/// since the conversion from u64 -> i32 in negative number will be different. These lines are repeated
+/// in 5 out of 6 fcntl_syscall cases, so wrapped these loc into helper functions to make code cleaner.
+///
+/// ## Arguments
+/// cageid: cage ID associate with virtual file descriptor
+/// vfd_arg: virtual file descriptor
+///
+/// ## Return Type
+/// On success:
+/// Return corresponding FDTableEntry that contains
+/// (1) underlying kernel fd.
+/// (2) file descriptor kind.
+/// (3) O_CLOEXEC flag.
+/// (4) file descriptor specific extra information.
+///
+/// On error:
+/// Return error num EBADF(Bad File Descriptor)
+pub fn _fcntl_helper(cageid: u64, vfd_arg: u64) -> Result<fdtables::FDTableEntry, Errno> {
+    if vfd_arg > MAXFD as u64 {
+        return Err(Errno::EBADF);
+    }
+    // Get underlying kernel fd
+    let wrappedvfd = fdtables::translate_virtual_fd(cageid, vfd_arg);
+    if wrappedvfd.is_err() {
+        return Err(Errno::EBADF);
+    }
+    Ok(wrappedvfd.unwrap())
+}
+
+/// Reference: https://man7.org/linux/man-pages/man2/fcntl.2.html
+///
+/// Due to the design of `fdtables` library, different virtual fds created by `dup`/`dup2` are
+/// actually refer to the same underlying kernel fd. Therefore, in `fcntl_syscall` we need to
+/// handle the cases of `F_DUPFD`, `F_DUPFD_CLOEXEC`, `F_GETFD`, and `F_SETFD` separately.
+///
+/// Among these, `F_DUPFD` and `F_DUPFD_CLOEXEC` cannot directly use the `dup_syscall` because,
+/// in `fcntl`, the duplicated fd is assigned to the lowest available number starting from `arg`,
+/// whereas the `dup_syscall` does not have this restriction and instead assigns the lowest
+/// available fd number globally.
+///
+/// Additionally, `F_DUPFD_CLOEXEC` and `F_SETFD` require updating the fd flag information
+/// (`O_CLOEXEC`) in fdtables after modifying the underlying kernel fd.
+///
+/// The owner and signal of signal-driven I/O (`F_GETOWN`, `F_SETOWN`, `F_GETOWN_EX`,
+/// `F_SETOWN_EX`, `F_GETSIG`, `F_SETSIG`) and the `O_ASYNC` status flag are kept by
+/// `crate::sigio` for every kind of fd, so they never reach the kernel fd.
+///
+/// For all other command operations, after translating the virtual fd to the corresponding
+/// kernel fd, they are redirected to the kernel `fcntl` syscall.
+///
+/// ## Arguments
+/// vfd_arg: virtual file descriptor
+/// cmd: The operation
+/// arg: an optional third argument.  Whether or not this argument is required is determined by op.  
+/// ptr_arg: the pointer argument of the lock commands and of `F_GETOWN_EX` / `F_SETOWN_EX`
+///
+/// ## Returns:
+///     - For a successful call, the return value depends on the operation:
+///       - `F_DUPFD`: The new file descriptor.
+///       - `F_GETFD`: Value of file descriptor flags.
+///       - `F_GETFL`: Value of file status flags.
+///       - `F_GETLEASE`: Type of lease held on file descriptor.
+///       - `F_GETOWN`: Value of file descriptor owner.
+///       - `F_GETSIG`: Value of signal sent when read or write becomes possible, or zero for traditional SIGIO behavior.
+///       - `F_GETPIPE_SZ`, `F_SETPIPE_SZ`: The pipe capacity.
+///       - `F_GET_SEALS`: A bit mask identifying the seals that have been set for the inode referred to by fd.
+///       - All other commands: Zero.
+///     - On error, -1 is returned and errno is set to indicate the error.
+pub extern "C" fn fcntl_syscall(
+    cageid: u64,
+    vfd_arg: u64,
+    vfd_cageid: u64,
+    cmd_arg: u64,
+    cmd_cageid: u64,
+    int_arg: u64, // arg3: integer value (for F_DUPFD, F_SETFD, F_GETFL, etc.)
+    int_arg_cageid: u64,
+    ptr_arg: u64, // arg4: translated host pointer (for F_GETLK, F_SETLK, etc.)
+    ptr_arg_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let cmd = sc_convert_sysarg_to_i32(cmd_arg, cmd_cageid, cageid);
+
+    // Convert int value only, we handle the pointer args if it's a lock operation
+    let arg = int_arg as i32;
+
+    // Validate unused arguments
+    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "fcntl_syscall"
+        );
+    }
+
+    match (cmd, arg) {
+        // Duplicate the file descriptor `vfd_arg` using the lowest-numbered
+        // available file descriptor greater than or equal to `arg`. The operation here
+        // is quite similar to `dup_syscall`, for specific operation explanation, see
+        // comments on `dup_syscall`.
+        (F_DUPFD, arg) => {
+            // Get fdtable entry
+            let vfd = match _fcntl_helper(cageid, vfd_arg) {
+                Ok(entry) => entry,
+                Err(e) => return syscall_error(e, "fcntl", "Bad File Descriptor"),
+            };
+            // Get lowest-numbered available file descriptor greater than or equal to `arg`
+            match fdtables::get_unused_virtual_fd_from_startfd(
+                cageid,
+                vfd.fdkind,
+                vfd.underfd,
+                false,
+                0,
+                arg as u64,
+            ) {
+                Ok(new_vfd) => return new_vfd as i32,
+                Err(_) => return syscall_error(Errno::EBADF, "fcntl", "Bad File Descriptor"),
+            }
+        }
+        // As for `F_DUPFD`, but additionally set the close-on-exec flag
+        // for the duplicate file descriptor.
+        (F_DUPFD_CLOEXEC, arg) => {
+            // Get fdtable entry
+            let vfd = match _fcntl_helper(cageid, vfd_arg) {
+                Ok(entry) => entry,
+                Err(e) => return syscall_error(e, "fcntl", "Bad File Descriptor"),
+            };
+            // Get lowest-numbered available file descriptor greater than or equal to `arg`
+            // and set the `O_CLOEXEC` flag
+            match fdtables::get_unused_virtual_fd_from_startfd(
+                cageid,
+                vfd.fdkind,
+                vfd.underfd,
+                true,
+                0,
+                arg as u64,
+            ) {
+                Ok(new_vfd) => return new_vfd as i32,
+                Err(_) => return syscall_error(Errno::EBADF, "fcntl", "Bad File Descriptor"),
+            }
+        }
+        // Return (as the function result) the file descriptor flags.
+        (F_GETFD, ..) => {
+            // Get fdtable entry
+            let vfd = match _fcntl_helper(cageid, vfd_arg) {
+                Ok(entry) => entry,
+                Err(e) => return syscall_error(e, "fcntl", "Bad File Descriptor"),
+            };
+            return vfd.should_cloexec as i32;
+        }
+        // Set the file descriptor flags to the value specified by arg.
+        (F_SETFD, arg) => {
+            // Get fdtable entry
+            let vfd = match _fcntl_helper(cageid, vfd_arg) {
+                Ok(entry) => entry,
+                Err(e) => return syscall_error(e, "fcntl", "Bad File Descriptor"),
+            };
+            // Set underlying kernel fd flag
+            if vfd.fdkind == FDKIND_KERNEL {
+                let ret = unsafe { libc::fcntl(vfd.underfd as i32, cmd, arg) };
+                if ret < 0 {
+                    let errno = get_errno();
+                    return handle_errno(errno, "fcntl");
+                }
+            }
+            // Set virtual fd flag
+            let cloexec_flag: bool = arg != 0;
+            match fdtables::set_cloexec(cageid, vfd_arg as u64, cloexec_flag) {
+                Ok(_) => return 0,
+                Err(_e) => return syscall_error(Errno::EBADF, "fcntl", "Bad File Descriptor"),
+            }
+        }
+        // Signal-driven I/O is handled by RawPOSIX for every kind of fd, see `crate::sigio`
+        (F_GETOWN | F_SETOWN | F_GETOWN_EX | F_SETOWN_EX | F_GETSIG | F_SETSIG, arg) => {
+            // Get fdtable entry
+            let vfd = match _fcntl_helper(cageid, vfd_arg) {
+                Ok(entry) => entry,
+                Err(e) => return syscall_error(e, "fcntl", "Bad File Descriptor"),
+            };
+            sigio_fcntl(&vfd, cmd, arg, ptr_arg)
+        }
+        // O_ASYNC is kept by RawPOSIX too, the backends never see it
+        (F_GETFL, ..) => {
+            let vfd = match _fcntl_helper(cageid, vfd_arg) {
+                Ok(entry) => entry,
+                Err(e) => return syscall_error(e, "fcntl", "Bad File Descriptor"),
+            };
+            let ret = _fcntl_backend(cageid, vfd_arg, &vfd, cmd, 0, ptr_arg);
+            if ret >= 0 && sigio_is_async(&vfd) {
+                return ret | O_ASYNC;
+            }
+            ret
+        }
+        (F_SETFL, arg) => {
+            let vfd = match _fcntl_helper(cageid, vfd_arg) {
+                Ok(entry) => entry,
+                Err(e) => return syscall_error(e, "fcntl", "Bad File Descriptor"),
+            };
+            let ret = _fcntl_backend(cageid, vfd_arg, &vfd, cmd, arg & !O_ASYNC, ptr_arg);
+            if ret >= 0 {
+                sigio_set_async(&vfd, arg & O_ASYNC != 0);
+            }
+            ret
+        }
+        _ => {
+            // Get fdtable entry
+            let vfd = match _fcntl_helper(cageid, vfd_arg) {
+                Ok(entry) => entry,
+                Err(e) => return syscall_error(e, "fcntl", "Bad File Descriptor"),
+            };
+            _fcntl_backend(cageid, vfd_arg, &vfd, cmd, arg, ptr_arg)
+        }
+    }
+}
+
+/// The remaining `fcntl` commands, which the backend of the fd handles: devfs, tmpfs or the
+/// host kernel
+fn _fcntl_backend(
+    cageid: u64,
+    vfd_arg: u64,
+    vfd: &fdtables::FDTableEntry,
+    cmd: i32,
+    arg: i32,
+    ptr_arg: u64,
+) -> i32 {
+    if vfd.fdkind == FDKIND_DEV {
+        return devfs_fcntl(cageid, vfd_arg, vfd.perfdinfo, cmd, arg);
+    }
+    if let Some(handle) = tmpfs_fd_handle(cageid, vfd_arg) {
+        return handle.fcntl(cmd, arg);
+    }
+    let is_lock_op = cmd == F_GETLK
+        || cmd == F_SETLK
+        || cmd == F_SETLKW
+        || cmd == F_GETLK64
+        || cmd == F_SETLK64
+        || cmd == F_SETLKW64;
+
+    let ret = if is_lock_op {
+        // Lock operation - use ptr_arg (arg4), F_SETLKW waits for the lock
+        interruptible(cageid, true, || unsafe {
+            libc::fcntl(vfd.underfd as i32, cmd, ptr_arg as *mut c_void)
+        })
+    } else {
+        // Other operations - use int_arg (arg3)
+        unsafe { libc::fcntl(vfd.underfd as i32, cmd, arg) }
+    };
+
+    if ret < 0 {
+        let errno = get_errno();
+        return handle_errno(errno, "fcntl");
+    }
+    ret
+}
+
+//------------------------------------LINK SYSCALL------------------------------------
+/// Reference: https://man7.org/linux/man-pages/man2/link.2.html
+///
+/// `link_syscall` creates a new link (hard link) to an existing file.
+///
+/// ## Arguments:
+///  - `cageid`: Identifier of the calling Cage (namespace / process-like container).
+///  - `oldpath_arg`: Address of the existing pathname in the caller's address space.
+///  - `oldpath_cageid`: Cage ID associated with `oldpath_arg`.
+///  - `newpath_arg`: Address of the new pathname in the caller's address space.
+///  - `newpath_cageid`: Cage ID associated with `newpath_arg`.
+///  - `arg3`–`arg6` and their corresponding `_cageid`: Reserved arguments (must be unused).
+///
+/// ## Implementation Details:
+///  - The path arguments are translated from the RawPOSIX perspective into host kernel paths
+///    using `sc_convert_path_to_host`, which applies path normalization relative to the cage's CWD.
+///  - The unused arguments are validated with `sc_unusedarg`; any unexpected values are treated
+///    as a security violation.
+///  - The underlying `libc::link()` is invoked with the translated paths.
+///  - On failure, `errno` is retrieved via `get_errno()` and normalized through `handle_errno()`.
+///
+/// ## Return Value:
+///  - `0` on success.
+///  - `-1` on failure, with `errno` set appropriately.
+pub extern "C" fn link_syscall(
+    cageid: u64,
+    oldpath_arg: u64,
+    oldpath_cageid: u64,
+    newpath_arg: u64,
+    newpath_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    // Type conversion
+    let oldpath = match sc_convert_path_to_host(oldpath_arg, oldpath_cageid, cageid) {
+        Ok(oldpath) => oldpath,
+        Err(e) => return syscall_error(e, "link", "path conversion failed"),
+    };
+    let newpath = match sc_convert_path_to_host(newpath_arg, newpath_cageid, cageid) {
+        Ok(newpath) => newpath,
+        Err(e) => return syscall_error(e, "link", "path conversion failed"),
+    };
+
+    // Validate unused args
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "link_syscall"
+        );
+    }
+
+    match tmpfs_for_paths(oldpath.as_bytes(), newpath.as_bytes()) {
+        Ok(Some(fs)) => return fs.link(oldpath.as_bytes(), newpath.as_bytes()),
+        Ok(None) => {}
+        Err(e) => return syscall_error(e, "link", "Invalid cross-device link"),
+    }
+
+    if let Some(layers) = overlay_layers(cageid) {
+        return layers.link(oldpath.as_bytes(), newpath.as_bytes());
+    }
+
+    let ret = unsafe { libc::link(oldpath.as_ptr(), newpath.as_ptr()) };
+
+    if ret < 0 {
+        let errno = get_errno();
+        return handle_errno(errno, "link");
+    }
+    ret
+}
+
+//------------------------------------SYMLINK SYSCALL------------------------------------
+/// Reference: https://man7.org/linux/man-pages/man2/symlink.2.html
+///
+/// `symlink_syscall` creates a symbolic link named `linkpath` which contains the string `target`.
+///
+/// ## Arguments:
+///  - `cageid`: Identifier of the calling Cage.
+///  - `target_arg`: Address of the link target string. It is stored verbatim and only resolved
+///    when the link is followed, so it is not converted.
+///  - `linkpath_arg`: Address of the pathname of the new link.
+///  - `arg3`–`arg6` and their corresponding `_cageid`: Reserved arguments (must be unused).
+///
+/// ## Implementation Details:
+///  - `linkpath` is translated with `sc_convert_path_to_host`. Links on a tmpfs mount are created
+///    in memory, everything else goes to `libc::symlink()`.
+///
+/// ## Return Value:
+///  - `0` on success.
+///  - `-1` on failure, with `errno` set appropriately.
+pub extern "C" fn symlink_syscall(
+    cageid: u64,
+    target_arg: u64,
+    target_cageid: u64,
+    linkpath_arg: u64,
+    linkpath_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    // Type conversion
+    let target = match get_cstr(target_arg) {
+        Ok(target) => target,
+        Err(_) => return syscall_error(Errno::EFAULT, "symlink", "invalid target"),
+    };
+    let linkpath = match sc_convert_path_to_host(linkpath_arg, linkpath_cageid, cageid) {
+        Ok(path) => path,
+        Err(e) => return syscall_error(e, "symlink", "path conversion failed"),
+    };
+
+    // Validate unused args
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "symlink_syscall"
+        );
+    }
+
+    if let Some(fs) = tmpfs_for_path(linkpath.as_bytes()) {
+        return fs.symlink(target, linkpath.as_bytes());
+    }
+
+    if let Some(layers) = overlay_layers(cageid) {
+        return layers.symlink(target, linkpath.as_bytes());
+    }
+
+    let c_target = match CString::new(target) {
+        Ok(c_target) => c_target,
+        Err(_) => return syscall_error(Errno::EINVAL, "symlink", "invalid target"),
+    };
+    let ret = unsafe { libc::symlink(c_target.as_ptr(), linkpath.as_ptr()) };
+
+    if ret < 0 {
+        let errno = get_errno();
+        return handle_errno(errno, "symlink");
+    }
+    ret
+}
+
+//------------------------------------XSTAT SYSCALL------------------------------------
+/// `xstat` retrieves file status information (versioned stat interface).
+/// Reference: https://man7.org/linux/man-pages/man2/stat.2.html
+///
+/// ## Arguments:
+///  - `vers`: Version parameter for stat structure compatibility.
+///  - `pathname`: Path to the file to get status information for.
+///  - `statbuf`: Buffer to store the file status information.
+///
+/// ## Implementation Details:
+///  - The path is converted from the RawPOSIX perspective to the host kernel perspective
+///    using `sc_convert_path_to_host`, which handles path normalization relative to the cage's CWD.
+///  - The underlying libc::stat() is called and results are copied to the user buffer.
+///
+/// ## Return Value:
+///  - `0` on success.
+///  - `-1` on failure, with `errno` set appropriately.
+pub extern "C" fn stat_syscall(
+    cageid: u64,
+    path_arg: u64,
+    path_cageid: u64,
+    statbuf_arg: u64,
+    statbuf_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    // Type conversion
+    let path = match sc_convert_path_to_host(path_arg, path_cageid, cageid) {
+        Ok(path) => path,
+        Err(e) => return syscall_error(e, "stat", "path conversion failed"),
+    };
+
+    // Validate unused args
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "stat_syscall"
+        );
+    }
+
+    if let Some(node) = devfs_lookup(path.as_bytes()) {
+        let statbuf_addr = match sc_convert_addr_to_statdata(statbuf_arg, statbuf_cageid, cageid) {
+            Ok(statbuf_addr) => statbuf_addr,
+            Err(e) => return syscall_error(e, "xstat", "Bad address"),
+        };
+        match node {
+            DevNode::Device(dev) => {
+                devfs_stat(dev, statbuf_addr);
+                return 0;
+            }
+            // The std aliases are symlinks to /proc/self/fd/N on Linux; report what they point to
+            DevNode::StdAlias(stdfd) => {
+                return fstat_syscall(
+                    cageid,
+                    stdfd,
+                    cageid,
+                    statbuf_arg,
+                    statbuf_cageid,
+                    UNUSED_ARG,
+                    UNUSED_ID,
+                    UNUSED_ARG,
+                    UNUSED_ID,
+                    UNUSED_ARG,
+                    UNUSED_ID,
+                    UNUSED_ARG,
+                    UNUSED_ID,
+                )
+            }
+        }
+    }
+
+    if let Some(file) = resolver_lookup(path.as_bytes()) {
+        return match sc_convert_addr_to_statdata(statbuf_arg, statbuf_cageid, cageid) {
+            Ok(statbuf_addr) => {
+                resolver_stat(cageid, file, statbuf_addr);
+                0
+            }
+            Err(e) => syscall_error(e, "xstat", "Bad address"),
+        };
+    }
+
+    if let Some(fs) = tmpfs_for_path(path.as_bytes()) {
+        return match sc_convert_addr_to_statdata(statbuf_arg, statbuf_cageid, cageid) {
+            Ok(statbuf_addr) => fs.stat(path.as_bytes(), statbuf_addr),
+            Err(e) => syscall_error(e, "xstat", "Bad address"),
+        };
+    }
+
+    if let Some(layers) = overlay_layers(cageid) {
+        return match sc_convert_addr_to_statdata(statbuf_arg, statbuf_cageid, cageid) {
+            Ok(statbuf_addr) => layers.stat(path.as_bytes(), statbuf_addr),
+            Err(e) => syscall_error(e, "xstat", "Bad address"),
+        };
+    }
+
+    // Declare statbuf by ourselves
+    let mut libc_statbuf: stat = unsafe { std::mem::zeroed() };
+    let libcret = unsafe { libc::stat(path.as_ptr(), &mut libc_statbuf) };
+
+    if libcret < 0 {
+        let errno = get_errno();
+        return handle_errno(errno, "xstat");
+    }
+
+    // Convert libc stat to StatData and copy to user buffer
+    match sc_convert_addr_to_statdata(statbuf_arg, statbuf_cageid, cageid) {
+        Ok(statbuf_addr) => convert_statdata_to_user(statbuf_addr, libc_statbuf),
+        Err(e) => return syscall_error(e, "xstat", "Bad address"),
+    }
+
+    libcret
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/statfs.2.html
+///
+/// Linux `statfs()` syscall returns information about a mounted filesystem
+/// that contains the file or directory specified by `path`.  
+/// In RawPOSIX, because each Cage has its own virtualized filesystem view,
+/// the path is first translated from the Cage's namespace into the host
+/// kernel namespace using `sc_convert_path_to_host`.  
+/// After translation, the kernel's `libc::statfs()` is invoked to obtain
+/// the filesystem information. The resulting `statfs` structure is then
+/// converted into our ABI-stable `FStatData` format and copied into the
+/// user-provided buffer in Cage memory.
+///
+/// ## Input:
+/// - `cageid`: Identifier of the current Cage
+/// - `path_arg`: Wasm address of the pathname string
+/// - `path_cageid`: Cage ID associated with `path_arg`
+/// - `statbuf_arg`: Wasm address of the buffer where results will be stored
+/// - `statbuf_cageid`: Cage ID associated with `statbuf_arg`
+/// - `arg3`–`arg6`: Unused arguments (validated for security)
+///
+/// ## Return Value:
+/// - `0` on success  
+/// - `-1` on failure, with `errno` set appropriately
+pub extern "C" fn statfs_syscall(
+    cageid: u64,
+    path_arg: u64,
+    path_cageid: u64,
+    statbuf_arg: u64,
+    statbuf_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    // Type conversion
+    let path = match sc_convert_path_to_host(path_arg, path_cageid, cageid) {
+        Ok(path) => path,
+        Err(e) => return syscall_error(e, "statfs", "path conversion failed"),
+    };
+
+    // Validate unused args
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "statfs_syscall"
+        );
+    }
+
+    // Cast directly to libc::statfs and write kernel data into buffer.
+    let statbuf_ptr = statbuf_arg as *mut libc::statfs;
+    if let Some(fs) = tmpfs_for_path(path.as_bytes()) {
+        unsafe { *statbuf_ptr = fs.statfs() };
+        return 0;
+    }
+    if let Some(layers) = overlay_layers(cageid) {
+        return match layers.statfs(path.as_bytes()) {
+            Ok(buf) => {
+                unsafe { *statbuf_ptr = buf };
+                0
+            }
+            Err(e) => syscall_error(e, "statfs", "overlay lookup failed"),
+        };
+    }
+    let ret = unsafe { libc::statfs(path.as_ptr(), statbuf_ptr) };
+
+    if ret < 0 {
+        let errno = get_errno();
+        return handle_errno(errno, "statfs");
+    }
+
+    ret
+}
+
+//------------------------------------FSYNC SYSCALL------------------------------------
+/// `fsync` synchronizes a file's in-core state with storage device.
+/// Reference: https://man7.org/linux/man-pages/man2/fsync.2.html
+///
+/// ## Arguments:
+///  - `fd`: File descriptor to synchronize.
+///
+/// ## Implementation Details:
+///  - The virtual file descriptor is converted to a kernel file descriptor using `convert_fd_to_host`.
+///  - This ensures proper translation between RawPOSIX virtual fds and host kernel fds.
+///  - The underlying libc::fsync() is called, which synchronizes both file data and metadata.
+///
+/// ## Return Value:
+///  - `0` on success.
+///  - `-1` on failure, with `errno` set appropriately.
+pub extern "C" fn fsync_syscall(
+    cageid: u64,
+    fd_arg: u64,
+    fd_cageid: u64,
+    arg2: u64,
+    arg2_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    // Type conversion
+    let virtual_fd = sc_convert_sysarg_to_i32(fd_arg, fd_cageid, cageid);
+
+    // Validate unused args
+    if !(sc_unusedarg(arg2, arg2_cageid)
+        && sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "fsync_syscall"
+        );
+    }
+
+    let kernel_fd = convert_fd_to_host(virtual_fd as u64, fd_cageid, cageid);
+    // convert_fd_to_host returns negative errno values on error
+    if kernel_fd < 0 {
+        return handle_errno(kernel_fd, "read");
+    }
+
+    // tmpfs has no backing store to flush
+    if tmpfs_fd_handle(fd_cageid, virtual_fd as u64).is_some() {
+        return 0;
+    }
+
+    let ret = unsafe { libc::fsync(kernel_fd) };
+
+    if ret < 0 {
+        let errno = get_errno();
+        return handle_errno(errno, "fsync");
+    }
+    return ret;
+}
+
+//------------------------------------FDATASYNC SYSCALL------------------------------------
+/// `fdatasync` synchronizes a file's data to storage device (but not metadata).
+/// Reference: https://man7.org/linux/man-pages/man2/fdatasync.2.html
+///
+/// ## Arguments:
+///  - `fd`: File descriptor to synchronize.
+///
+/// ## Implementation Details:
+///  - The virtual file descriptor is converted to a kernel file descriptor using `convert_fd_to_host`.
+///  - This ensures proper translation between RawPOSIX virtual fds and host kernel fds.
+///  - The underlying libc::fdatasync() is called, which synchronizes only file data (not metadata
+///    like timestamps), making it potentially faster than fsync().
+///
+/// ## Return Value:
+///  - `0` on success.
+///  - `-1` on failure, with `errno` set appropriately.
+pub extern "C" fn fdatasync_syscall(
+    cageid: u64,
+    fd_arg: u64,
+    fd_cageid: u64,
+    arg2: u64,
+    arg2_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    // Type conversion
+    let virtual_fd = sc_convert_sysarg_to_i32(fd_arg, fd_cageid, cageid);
+
+    // Validate unused args
+    if !(sc_unusedarg(arg2, arg2_cageid)
+        && sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "fdatasync_syscall"
+        );
+    }
+
+    let kernel_fd = convert_fd_to_host(virtual_fd as u64, fd_cageid, cageid);
+    // Return error
+    if kernel_fd < 0 {
+        return handle_errno(kernel_fd, "read");
+    }
+
+    if tmpfs_fd_handle(fd_cageid, virtual_fd as u64).is_some() {
+        return 0;
+    }
 
     let ret = unsafe { libc::fdatasync(kernel_fd) };
 
@@ -1715,6 +2651,10 @@ pub extern "C" fn sync_file_range_syscall(
         return handle_errno(kernel_fd, "read");
     }
 
+    if tmpfs_fd_handle(fd_cageid, virtual_fd as u64).is_some() {
+        return 0;
+    }
+
     let ret = unsafe { libc::sync_file_range(kernel_fd, offset, nbytes, flags) };
 
     if ret < 0 {
@@ -1788,6 +2728,14 @@ pub extern "C" fn readlink_syscall(
         );
     }
 
+    if let Some(fs) = tmpfs_for_path(path.as_bytes()) {
+        return fs.readlink(path.as_bytes(), buf, buflen);
+    }
+
+    if let Some(layers) = overlay_layers(cageid) {
+        return layers.readlink(path.as_bytes(), buf, buflen);
+    }
+
     // Call to kernel readlink
     let bytes_written = unsafe { libc::readlink(path.as_ptr(), buf as *mut libc::c_char, buflen) };
 
@@ -1854,8 +2802,26 @@ pub extern "C" fn readlinkat_syscall(
     }
 
     let ret = if virtual_fd == libc::AT_FDCWD {
+        if let Some(fs) = tmpfs_for_path(path.as_bytes()) {
+            return fs.readlink(path.as_bytes(), buf as *mut u8, buflen);
+        }
+        if let Some(layers) = overlay_layers(cageid) {
+            return layers.readlink(path.as_bytes(), buf as *mut u8, buflen);
+        }
         unsafe { libc::readlink(path.as_ptr(), buf, buflen) }
     } else {
+        // A tmpfs directory fd resolves against the path it was opened with
+        if let Some(handle) = tmpfs_fd_handle(dirfd_cageid, virtual_fd as u64) {
+            let raw_path = match get_cstr(path_arg) {
+                Ok(p) => p,
+                Err(_) => return syscall_error(Errno::EFAULT, "readlinkat", "invalid path"),
+            };
+            let full = format!("{}/{}", handle.path(), raw_path);
+            return handle
+                .fs()
+                .readlink(full.as_bytes(), buf as *mut u8, buflen);
+        }
+
         // Case 2: Specific directory fd
         let kernel_fd = convert_fd_to_host(virtual_fd as u64, dirfd_cageid, cageid);
         // Return error
@@ -1875,6 +2841,19 @@ pub extern "C" fn readlinkat_syscall(
             }
         };
 
+        // Same for a directory opened through the overlay
+        if let (Some(layers), Some(dir)) = (
+            overlay_layers(cageid),
+            overlay_fd_path(dirfd_cageid, virtual_fd as u64),
+        ) {
+            let full = if raw_path.starts_with('/') {
+                raw_path.to_string()
+            } else {
+                format!("{}/{}", dir, raw_path)
+            };
+            return layers.readlink(full.as_bytes(), buf as *mut u8, buflen);
+        }
+
         unsafe { libc::readlinkat(kernel_fd, raw_path.as_ptr() as *const c_char, buf, buflen) }
     };
 
@@ -1940,6 +2919,16 @@ pub extern "C" fn rename_syscall(
         );
     }
 
+    match tmpfs_for_paths(oldpath.as_bytes(), newpath.as_bytes()) {
+        Ok(Some(fs)) => return fs.rename(oldpath.as_bytes(), newpath.as_bytes()),
+        Ok(None) => {}
+        Err(e) => return syscall_error(e, "rename", "Invalid cross-device link"),
+    }
+
+    if let Some(layers) = overlay_layers(cageid) {
+        return layers.rename(oldpath.as_bytes(), newpath.as_bytes());
+    }
+
     let ret = unsafe { libc::rename(oldpath.as_ptr(), newpath.as_ptr()) };
 
     if ret < 0 {
@@ -1998,6 +2987,14 @@ pub extern "C" fn unlink_syscall(
         );
     }
 
+    if let Some(fs) = tmpfs_for_path(path.as_bytes()) {
+        return fs.unlink(path.as_bytes());
+    }
+
+    if let Some(layers) = overlay_layers(cageid) {
+        return layers.unlink(path.as_bytes());
+    }
+
     let ret = unsafe { libc::unlink(path.as_ptr()) };
 
     if ret < 0 {
@@ -2089,6 +3086,41 @@ pub extern "C" fn unlinkat_syscall(
         vfd.underfd as i32
     };
 
+    // tmpfs entries never reach the host. A tmpfs dirfd resolves relative paths against the
+    // path the directory was opened with.
+    let tmpfs_path = match tmpfs_fd_handle(cageid, dirfd as u64) {
+        Some(handle) if dirfd != AT_FDCWD && !c_path.as_bytes().starts_with(b"/") => {
+            format!("{}/{}", handle.path(), c_path.to_string_lossy()).into_bytes()
+        }
+        _ => c_path.as_bytes().to_vec(),
+    };
+    if tmpfs_path.starts_with(b"/") {
+        if let Some(fs) = tmpfs_for_path(&tmpfs_path) {
+            return if flags & libc::AT_REMOVEDIR != 0 {
+                fs.rmdir(&tmpfs_path)
+            } else {
+                fs.unlink(&tmpfs_path)
+            };
+        }
+    }
+
+    // Same for directories opened through the overlay
+    if let Some(layers) = overlay_layers(cageid) {
+        let overlay_path = if dirfd == AT_FDCWD || c_path.as_bytes().starts_with(b"/") {
+            Some(c_path.as_bytes().to_vec())
+        } else {
+            overlay_fd_path(cageid, dirfd as u64)
+                .map(|dir| format!("{}/{}", dir, c_path.to_string_lossy()).into_bytes())
+        };
+        if let Some(path) = overlay_path {
+            return if flags & libc::AT_REMOVEDIR != 0 {
+                layers.rmdir(&path)
+            } else {
+                layers.unlink(&path)
+            };
+        }
+    }
+
     // Call the underlying libc::unlinkat() function with the fd and pathname.
     let ret = unsafe { libc::unlinkat(kernel_fd, c_path.as_ptr(), flags) };
 
@@ -2097,30 +3129,302 @@ pub extern "C" fn unlinkat_syscall(
         let errno = get_errno();
         return handle_errno(errno, "unlinkat");
     }
-    ret
+    ret
+}
+
+//------------------------------------ACCESS SYSCALL------------------------------------
+/// `access` checks whether the calling process can access the file pathname.
+/// Reference: https://man7.org/linux/man-pages/man2/access.2.html
+/// ## Arguments:
+///  - `pathname`: Path to the file to check accessibility.
+///  - `mode`: Accessibility check mode (F_OK, R_OK, W_OK, X_OK or combinations).
+/// ## Implementation Details:
+///  - The path is converted from the RawPOSIX perspective to the host kernel perspective
+///    using `sc_convert_path_to_host`, which handles path normalization relative to the cage's CWD.
+///  - The mode parameter is passed directly to the underlying libc::access() call.
+/// ## Return Value:
+///  - `0` on success (file is accessible in the requested mode).
+///  - `-1` on failure, with `errno` set appropriately.
+pub extern "C" fn access_syscall(
+    cageid: u64,
+    path_arg: u64,
+    path_cageid: u64,
+    amode_arg: u64,
+    amode_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    // Type conversion
+    let path = match sc_convert_path_to_host(path_arg, path_cageid, cageid) {
+        Ok(path) => path,
+        Err(e) => return syscall_error(e, "access", "path conversion failed"),
+    };
+    let amode = sc_convert_sysarg_to_i32(amode_arg, amode_cageid, cageid);
+
+    // Validate unused args
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "access_syscall"
+        );
+    }
+
+    if devfs_lookup(path.as_bytes()).is_some() {
+        return devfs_access(amode);
+    }
+
+    if resolver_lookup(path.as_bytes()).is_some() {
+        return resolver_access(amode);
+    }
+
+    if let Some(fs) = tmpfs_for_path(path.as_bytes()) {
+        return fs.access(path.as_bytes(), amode);
+    }
+
+    if let Some(layers) = overlay_layers(cageid) {
+        return layers.access(path.as_bytes(), amode);
+    }
+
+    let ret = unsafe { libc::access(path.as_ptr(), amode) };
+    if ret < 0 {
+        let errno = get_errno();
+        return handle_errno(errno, "access");
+    }
+    ret
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/clock_gettime.2.html
+///
+/// `clock_gettime_syscall` retrieves the time of the specified clock and
+/// stores it in a user-provided `timespec` structure.  
+///
+/// ## Implementation Details:
+/// - The `clockid` argument is converted from the Cage's virtual argument
+///   into a host `u32`.
+/// - The `tp` pointer (destination for the `timespec` result) is translated
+///   from Wasm linear memory into a host address via `sc_convert_addr_to_host`.
+/// - Unused arguments `arg3`–`arg6` are validated with `sc_unusedarg`.
+/// - The clock is read as the cage sees it, see `cage::timer::cage_clock_now`: the CPU time
+///   clocks count the time of the cage (or of the calling thread) rather than of the lind
+///   process, and the realtime clocks are moved by the cage's `realtime_offset`, which
+///   `clock_settime_syscall` sets.
+/// - In deterministic mode the clocks are read from `cage::deterministic` instead, and the CPU
+///   time clocks report the virtual CPU time.
+///
+/// ## Arguments:
+/// - `cageid`: Identifier of the calling Cage
+/// - `clockid_arg`: The clock to be queried (e.g., `CLOCK_REALTIME`)
+/// - `tp_arg`: Address of the user buffer for the result `timespec`
+/// - `arg3`–`arg6`: Reserved, must be unused
+///
+/// ## Returns:
+///     - 0 on success.
+///     - `-EINVAL` for an unknown clock, `-EFAULT` if `tp` is NULL.
+pub extern "C" fn clock_gettime_syscall(
+    cageid: u64,
+    clockid_arg: u64,
+    clockid_cageid: u64,
+    tp_arg: u64,
+    tp_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let clockid = sc_convert_sysarg_to_u32(clockid_arg, clockid_cageid, cageid);
+    let tp = tp_arg as *mut u8;
+    // would sometimes check, sometimes be a no-op depending on the compiler settings
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "clock_gettime_syscall"
+        );
+    }
+
+    let Some(now) = cage_clock_now(cageid, clockid as i32) else {
+        return syscall_error(Errno::EINVAL, "clock_gettime", "Invalid clock");
+    };
+    if tp.is_null() {
+        return syscall_error(Errno::EFAULT, "clock_gettime", "Bad address");
+    }
+    unsafe {
+        *(tp as *mut libc::timespec) = libc::timespec {
+            tv_sec: now.as_secs() as i64,
+            tv_nsec: now.subsec_nanos() as i64,
+        };
+    }
+    0
+}
+
+/// Linux Reference: https://man7.org/linux/man-pages/man2/dup.2.html
+///
+/// Since the two file descriptors refer to the same open file description, they share file offset
+/// and file status flags. Then, in RawPOSIX, we mapped duplicated file descriptor to same underlying
+/// kernel fd.
+///
+/// ## Arguments:
+/// - `virtual_fd`: virtual file descriptor
+///
+/// ## Returns:
+///     - On success, the new file descriptor is returned.
+///     - On error, -1 is returned and errno is set to indicate the error.
+pub extern "C" fn dup_syscall(
+    cageid: u64,
+    vfd_arg: u64,
+    vfd_cageid: u64,
+    arg2: u64,
+    arg2_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    if !(sc_unusedarg(arg2, arg2_cageid)
+        && sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "dup_syscall"
+        );
+    }
+
+    let wrappedvfd = fdtables::translate_virtual_fd(cageid, vfd_arg as u64);
+    if wrappedvfd.is_err() {
+        return syscall_error(Errno::EBADF, "dup", "Bad File Descriptor");
+    }
+    let vfd = wrappedvfd.unwrap();
+    if vfd.fdkind != FDKIND_KERNEL {
+        // Only kernel fds have a host fd to duplicate. The others share their underfd, as with
+        // dup2(), and are released by their close handler once the last copy is closed.
+        let ret_vfd =
+            fdtables::get_unused_virtual_fd(cageid, vfd.fdkind, vfd.underfd, false, vfd.perfdinfo)
+                .unwrap();
+        return ret_vfd as i32;
+    }
+    let ret_kernelfd = unsafe { libc::dup(vfd.underfd as i32) };
+    let ret_vfd =
+        fdtables::get_unused_virtual_fd(cageid, vfd.fdkind, ret_kernelfd as u64, false, 0).unwrap();
+    return ret_vfd as i32;
+}
+
+/// dup2() performs the same task as dup(), so we utilize dup() here and mapping underlying kernel
+/// fd with specific `new_virutalfd`
+///
+/// ## Arguments:
+/// - `old_virtualfd`: original virtual file descriptor
+/// - `new_virtualfd`: specified new virtual file descriptor
+///
+/// ## Returns:
+///     - On success, returns the new file descriptor.
+///     - On error, -1 is returned and errno is set to indicate the error.
+pub extern "C" fn dup2_syscall(
+    cageid: u64,
+    old_vfd_arg: u64,
+    old_vfd_cageid: u64,
+    new_vfd_arg: u64,
+    new_vfd_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    // would sometimes check, sometimes be a no-op depending on the compiler settings
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "dup2_syscall"
+        );
+    }
+
+    // Validate both virtual fds
+    if old_vfd_arg > MAXFD as u64 || new_vfd_arg > MAXFD as u64 {
+        return syscall_error(Errno::EBADF, "dup2", "Bad File Descriptor");
+    } else if old_vfd_arg == new_vfd_arg {
+        // Does nothing
+        return new_vfd_arg as i32;
+    }
+
+    // If the file descriptor newfd was previously open, it is closed before being reused; the
+    // close is performed silently (i.e., any errors during the close are not reported by dup2()).
+    // This step is handled inside `fdtables`
+    match fdtables::translate_virtual_fd(cageid, old_vfd_arg) {
+        Ok(old_vfd) => {
+            // Request another virtual fd to refer to same underlying kernel fd as `virtual_fd`
+            // from input.
+            // The two file descriptors do not share file descriptor flags (the
+            // close-on-exec flag).  The close-on-exec flag (FD_CLOEXEC; see fcntl_syscall())
+            // for the duplicate descriptor is off
+            let _ = fdtables::get_specific_virtual_fd(
+                cageid,
+                new_vfd_arg,
+                old_vfd.fdkind,
+                old_vfd.underfd,
+                false,
+                old_vfd.perfdinfo,
+            )
+            .unwrap();
+
+            return new_vfd_arg as i32;
+        }
+        Err(_e) => {
+            return syscall_error(Errno::EBADF, "dup2", "Bad File Descriptor");
+        }
+    }
 }
 
-//------------------------------------ACCESS SYSCALL------------------------------------
-/// `access` checks whether the calling process can access the file pathname.
-/// Reference: https://man7.org/linux/man-pages/man2/access.2.html
+/// dup3() duplicates `old_virtualfd` to `new_virtualfd`, similar to dup2(),
+/// but requires the two descriptors to differ and allows setting FD_CLOEXEC via `flags`.
+/// It first calls `dup2_syscall` to copy the file descriptor, then sets the close-on-exec flag if requested.
+
 /// ## Arguments:
-///  - `pathname`: Path to the file to check accessibility.
-///  - `mode`: Accessibility check mode (F_OK, R_OK, W_OK, X_OK or combinations).
-/// ## Implementation Details:
-///  - The path is converted from the RawPOSIX perspective to the host kernel perspective
-///    using `sc_convert_path_to_host`, which handles path normalization relative to the cage's CWD.
-///  - The mode parameter is passed directly to the underlying libc::access() call.
-/// ## Return Value:
-///  - `0` on success (file is accessible in the requested mode).
-///  - `-1` on failure, with `errno` set appropriately.
-pub extern "C" fn access_syscall(
+/// - `old_virtualfd`: source virtual file descriptor
+/// - `new_virtualfd`: target virtual file descriptor
+/// - `flags`: must be 0 or O_CLOEXEC
+///
+/// ## Returns:
+///     - On success, returns the new file descriptor.
+///     - On error, -1 is returned and errno is set to indicate the error (EBADF or EINVAL).
+pub extern "C" fn dup3_syscall(
     cageid: u64,
-    path_arg: u64,
-    path_cageid: u64,
-    amode_arg: u64,
-    amode_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
+    old_vfd_arg: u64,
+    old_vfd_cageid: u64,
+    new_vfd_arg: u64,
+    new_vfd_cageid: u64,
+    flags_arg: u64,
+    flags_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -2128,64 +3432,78 @@ pub extern "C" fn access_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    // Type conversion
-    let path = match sc_convert_path_to_host(path_arg, path_cageid, cageid) {
-        Ok(path) => path,
-        Err(e) => return syscall_error(e, "access", "path conversion failed"),
-    };
-    let amode = sc_convert_sysarg_to_i32(amode_arg, amode_cageid, cageid);
+    let flags = sc_convert_sysarg_to_i32(flags_arg, flags_cageid, cageid);
 
-    // Validate unused args
-    if !(sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
+    if !(sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "access_syscall"
+            "dup3_syscall"
         );
     }
 
-    let ret = unsafe { libc::access(path.as_ptr(), amode) };
+    if old_vfd_arg > MAXFD as u64 || new_vfd_arg > MAXFD as u64 {
+        return syscall_error(Errno::EBADF, "dup3", "Bad File Descriptor");
+    }
+
+    if old_vfd_arg == new_vfd_arg {
+        return syscall_error(Errno::EINVAL, "dup3", "oldfd and newfd must be different");
+    }
+
+    if flags != 0 && flags != O_CLOEXEC {
+        return syscall_error(Errno::EINVAL, "dup3", "Invalid flags");
+    }
+
+    let ret = dup2_syscall(
+        cageid,
+        old_vfd_arg,
+        old_vfd_cageid,
+        new_vfd_arg,
+        new_vfd_cageid,
+        UNUSED_ARG,
+        UNUSED_ID,
+        UNUSED_ARG,
+        UNUSED_ID,
+        UNUSED_ARG,
+        UNUSED_ID,
+        UNUSED_ARG,
+        UNUSED_ID,
+    );
     if ret < 0 {
-        let errno = get_errno();
-        return handle_errno(errno, "access");
+        return ret;
     }
-    ret
+
+    if flags == O_CLOEXEC {
+        let _ = fdtables::set_cloexec(cageid, new_vfd_arg, true);
+    }
+
+    return new_vfd_arg as i32;
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/clock_gettime.2.html
-///
-/// `clock_gettime_syscall` retrieves the time of the specified clock and
-/// stores it in a user-provided `timespec` structure.  
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/fchdir.2.html
 ///
-/// ## Implementation Details:
-/// - The `clockid` argument is converted from the Cage's virtual argument
-///   into a host `u32`.
-/// - The `tp` pointer (destination for the `timespec` result) is translated
-///   from Wasm linear memory into a host address via `sc_convert_addr_to_host`.
-/// - Unused arguments `arg3`–`arg6` are validated with `sc_unusedarg`.
-/// - The underlying `SYS_clock_gettime` syscall is invoked directly with the
-///   converted arguments.
-/// - On error, `errno` is retrieved with `get_errno()` and normalized through
-///   `handle_errno()`.
+/// Linux `fchdir()` syscall changes the current working directory of the calling process to the
+/// directory referred to by the open file descriptor `fd`. Since we implement a file descriptor
+/// management subsystem (called `fdtables`), we first translate the virtual file descriptor to the
+/// corresponding kernel file descriptor before invoking the kernel's `libc::fchdir()` function.
 ///
-/// ## Arguments:
-/// - `cageid`: Identifier of the calling Cage
-/// - `clockid_arg`: The clock to be queried (e.g., `CLOCK_REALTIME`)
-/// - `tp_arg`: Address of the user buffer for the result `timespec`
-/// - `arg3`–`arg6`: Reserved, must be unused
+/// ## Input:
+///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
+///     - cageid: current cage identifier
+///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment referring to a directory
+///     - arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
 ///
 /// ## Returns:
 ///     - 0 on success.
-///     - -1 on failure, with errno set appropriately.
-pub extern "C" fn clock_gettime_syscall(
+///     - -1 on error, with errno set to indicate the error.
+pub extern "C" fn fchdir_syscall(
     cageid: u64,
-    clockid_arg: u64,
-    clockid_cageid: u64,
-    tp_arg: u64,
-    tp_cageid: u64,
+    vfd_arg: u64,
+    vfd_cageid: u64,
+    arg2: u64,
+    arg2_cageid: u64,
     arg3: u64,
     arg3_cageid: u64,
     arg4: u64,
@@ -2195,50 +3513,90 @@ pub extern "C" fn clock_gettime_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    let clockid = sc_convert_sysarg_to_u32(clockid_arg, clockid_cageid, cageid);
-    let tp = tp_arg as *mut u8;
-    // would sometimes check, sometimes be a no-op depending on the compiler settings
-    if !(sc_unusedarg(arg3, arg3_cageid)
+    let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
+    // Return error
+    if kernel_fd < 0 {
+        return handle_errno(kernel_fd, "fchdir");
+    }
+
+    if !(sc_unusedarg(arg2, arg2_cageid)
+        && sc_unusedarg(arg3, arg3_cageid)
         && sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "clock_gettime_syscall"
+            "fchdir_syscall"
         );
     }
 
-    let ret = unsafe { syscall(SYS_clock_gettime, clockid, tp) as i32 };
+    if let Some(handle) = tmpfs_fd_handle(vfd_cageid, vfd_arg) {
+        let ret = handle.fs().chdir(handle.path().as_bytes());
+        if ret == 0 {
+            if let Some(cage) = get_cage(cageid) {
+                *cage.cwd.write() = Arc::new(PathBuf::from(handle.path()));
+            }
+        }
+        return ret;
+    }
+
+    // The directory may only exist in the upper layer, so the host cwd is left alone
+    if let Some(path) = overlay_fd_path(vfd_cageid, vfd_arg) {
+        if let Some(cage) = get_cage(cageid) {
+            *cage.cwd.write() = Arc::new(PathBuf::from(path));
+        }
+        return 0;
+    }
 
+    let ret = unsafe { libc::fchdir(kernel_fd) };
     if ret < 0 {
-        let errno = get_errno();
-        return handle_errno(errno, "clock_gettime");
+        return handle_errno(get_errno(), "fchdir");
+    }
+
+    // Update the cage's current working directory
+    // We need to get the current working directory from the kernel to update the cage
+    let mut cwd_buf = [0u8; PATH_MAX as usize];
+    let cwd_ptr = unsafe { libc::getcwd(cwd_buf.as_mut_ptr() as *mut i8, cwd_buf.len()) };
+    if !cwd_ptr.is_null() {
+        if let Some(cage) = get_cage(cageid) {
+            let host_path = unsafe { std::ffi::CStr::from_ptr(cwd_ptr) }.to_string_lossy();
+            let user_path = PathBuf::from(host_path.as_ref());
+            let mut cwd = cage.cwd.write();
+            *cwd = Arc::new(user_path);
+        }
     }
 
     ret
 }
 
-/// Linux Reference: https://man7.org/linux/man-pages/man2/dup.2.html
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/writev.2.html
 ///
-/// Since the two file descriptors refer to the same open file description, they share file offset
-/// and file status flags. Then, in RawPOSIX, we mapped duplicated file descriptor to same underlying
-/// kernel fd.
+/// Linux `writev()` syscall performs scatter-gather output by writing data from multiple buffers
+/// to a file descriptor in a single operation. Since we implement a file descriptor management
+/// subsystem (called `fdtables`), we first translate the virtual file descriptor to the corresponding
+/// kernel file descriptor, then translate the iovec array pointer from cage virtual memory to host
+/// memory before invoking the kernel's `libc::writev()` function.
 ///
-/// ## Arguments:
-/// - `virtual_fd`: virtual file descriptor
+/// ## Input:
+///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
+///     - cageid: current cage identifier
+///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
+///     - iov_arg: pointer to an array of iovec structures describing the buffers (user's perspective)
+///     - iovcnt_arg: number of iovec structures in the array
+///     - arg4, arg5, arg6: additional arguments which are expected to be unused
 ///
 /// ## Returns:
-///     - On success, the new file descriptor is returned.
+///     - On success, the number of bytes written is returned.
 ///     - On error, -1 is returned and errno is set to indicate the error.
-pub extern "C" fn dup_syscall(
+pub extern "C" fn writev_syscall(
     cageid: u64,
     vfd_arg: u64,
     vfd_cageid: u64,
-    arg2: u64,
-    arg2_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
+    iov_arg: u64,
+    iov_cageid: u64,
+    iovcnt_arg: u64,
+    iovcnt_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -2246,45 +3604,87 @@ pub extern "C" fn dup_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    if !(sc_unusedarg(arg2, arg2_cageid)
-        && sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
+    let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
+    // Return error
+    if kernel_fd < 0 {
+        return handle_errno(kernel_fd, "writev");
+    }
+
+    let iovcnt = sc_convert_sysarg_to_i32(iovcnt_arg, iovcnt_cageid, cageid);
+    let iov_ptr = sc_convert_buf(iov_arg, iov_cageid, cageid);
+
+    if !(sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "dup_syscall"
+            "writev"
         );
     }
 
-    let wrappedvfd = fdtables::translate_virtual_fd(cageid, vfd_arg as u64);
-    if wrappedvfd.is_err() {
-        return syscall_error(Errno::EBADF, "dup", "Bad File Descriptor");
+    if let Some(dev) = devfs_fd_device(vfd_cageid, vfd_arg) {
+        let iovs = unsafe {
+            std::slice::from_raw_parts(iov_ptr as *const libc::iovec, iovcnt.max(0) as usize)
+        };
+        let mut total = 0;
+        for iov in iovs {
+            let ret = devfs_write(dev, iov.iov_base as *const u8, iov.iov_len);
+            if ret < 0 {
+                return if total > 0 { total } else { ret };
+            }
+            total += ret;
+        }
+        return total;
     }
-    let vfd = wrappedvfd.unwrap();
-    let ret_kernelfd = unsafe { libc::dup(vfd.underfd as i32) };
-    let ret_vfd =
-        fdtables::get_unused_virtual_fd(cageid, vfd.fdkind, ret_kernelfd as u64, false, 0).unwrap();
-    return ret_vfd as i32;
+
+    if let Some(handle) = tmpfs_fd_handle(vfd_cageid, vfd_arg) {
+        let iovs = unsafe {
+            std::slice::from_raw_parts(iov_ptr as *const libc::iovec, iovcnt.max(0) as usize)
+        };
+        let mut total = 0;
+        for iov in iovs {
+            let ret = handle.write(iov.iov_base as *const u8, iov.iov_len);
+            if ret < 0 {
+                return if total > 0 { total } else { ret };
+            }
+            total += ret;
+        }
+        return total;
+    }
+
+    let ret = interruptible(cageid, true, || unsafe {
+        libc::writev(kernel_fd, iov_ptr as *const libc::iovec, iovcnt)
+    }) as i32;
+    if ret < 0 {
+        return kernel_write_error(cageid, kernel_fd, get_errno(), "writev");
+    }
+    ret
 }
 
-/// dup2() performs the same task as dup(), so we utilize dup() here and mapping underlying kernel
-/// fd with specific `new_virutalfd`
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/fstat.2.html
 ///
-/// ## Arguments:
-/// - `old_virtualfd`: original virtual file descriptor
-/// - `new_virtualfd`: specified new virtual file descriptor
+/// Linux `fstat()` syscall retrieves information about the file referred to by the open file descriptor `fd`.
+/// Since we implement a file descriptor management subsystem (called `fdtables`), we first translate the virtual
+/// file descriptor to the corresponding kernel file descriptor, then call the kernel's `libc::fstat()` function.
+/// The returned stat structure is converted to our ABI-stable StatData format and copied to the user's buffer.
 ///
-/// ## Returns:
-///     - On success, returns the new file descriptor.
-///     - On error, -1 is returned and errno is set to indicate the error.
-pub extern "C" fn dup2_syscall(
+/// ## Input:
+///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
+///     - cageid: current cage identifier
+///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
+///     - stat_arg: pointer to a stat structure where the file information will be stored (user's perspective)
+///     - arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
+///
+/// ## Returns:
+///     - 0 on success.
+///     - -1 on error, with errno set to indicate the error.
+pub extern "C" fn fstat_syscall(
     cageid: u64,
-    old_vfd_arg: u64,
-    old_vfd_cageid: u64,
-    new_vfd_arg: u64,
-    new_vfd_cageid: u64,
+    vfd_arg: u64,
+    vfd_cageid: u64,
+    statbuf_arg: u64,
+    statbuf_cageid: u64,
     arg3: u64,
     arg3_cageid: u64,
     arg4: u64,
@@ -2294,7 +3694,12 @@ pub extern "C" fn dup2_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    // would sometimes check, sometimes be a no-op depending on the compiler settings
+    let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
+    // Return error
+    if kernel_fd < 0 {
+        return handle_errno(kernel_fd, "fstat");
+    }
+
     if !(sc_unusedarg(arg3, arg3_cageid)
         && sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
@@ -2302,66 +3707,67 @@ pub extern "C" fn dup2_syscall(
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "dup2_syscall"
+            "fstat_syscall"
         );
     }
 
-    // Validate both virtual fds
-    if old_vfd_arg > MAXFD as u64 || new_vfd_arg > MAXFD as u64 {
-        return syscall_error(Errno::EBADF, "dup2", "Bad File Descriptor");
-    } else if old_vfd_arg == new_vfd_arg {
-        // Does nothing
-        return new_vfd_arg as i32;
+    if let Some(dev) = devfs_fd_device(vfd_cageid, vfd_arg) {
+        match sc_convert_addr_to_statdata(statbuf_arg, statbuf_cageid, cageid) {
+            Ok(statbuf_addr) => devfs_stat(dev, statbuf_addr),
+            Err(e) => return syscall_error(e, "fstat", "Bad address"),
+        }
+        return 0;
     }
 
-    // If the file descriptor newfd was previously open, it is closed before being reused; the
-    // close is performed silently (i.e., any errors during the close are not reported by dup2()).
-    // This step is handled inside `fdtables`
-    match fdtables::translate_virtual_fd(cageid, old_vfd_arg) {
-        Ok(old_vfd) => {
-            // Request another virtual fd to refer to same underlying kernel fd as `virtual_fd`
-            // from input.
-            // The two file descriptors do not share file descriptor flags (the
-            // close-on-exec flag).  The close-on-exec flag (FD_CLOEXEC; see fcntl_syscall())
-            // for the duplicate descriptor is off
-            let _ = fdtables::get_specific_virtual_fd(
-                cageid,
-                new_vfd_arg,
-                old_vfd.fdkind,
-                old_vfd.underfd,
-                false,
-                old_vfd.perfdinfo,
-            )
-            .unwrap();
+    if let Some(handle) = tmpfs_fd_handle(vfd_cageid, vfd_arg) {
+        return match sc_convert_addr_to_statdata(statbuf_arg, statbuf_cageid, cageid) {
+            Ok(statbuf_addr) => handle.fstat(statbuf_addr),
+            Err(e) => syscall_error(e, "fstat", "Bad address"),
+        };
+    }
 
-            return new_vfd_arg as i32;
-        }
-        Err(_e) => {
-            return syscall_error(Errno::EBADF, "dup2", "Bad File Descriptor");
-        }
+    // Cast directly to libc::stat and write kernel data into buffer.
+    let mut host_stat: libc::stat = unsafe { std::mem::zeroed() };
+    let ret = unsafe { libc::fstat(kernel_fd, &mut host_stat as *mut libc::stat) };
+    if ret < 0 {
+        return handle_errno(get_errno(), "fstat");
     }
-}
 
-/// dup3() duplicates `old_virtualfd` to `new_virtualfd`, similar to dup2(),
-/// but requires the two descriptors to differ and allows setting FD_CLOEXEC via `flags`.
-/// It first calls `dup2_syscall` to copy the file descriptor, then sets the close-on-exec flag if requested.
+    // Validate guest buffer range and writability
+    match sc_convert_addr_to_statdata(statbuf_arg, statbuf_cageid, cageid) {
+        // 3) Populate StatData directly
+        Ok(statbuf_addr) => convert_statdata_to_user(statbuf_addr, host_stat),
+        Err(e) => return syscall_error(e, "fstat", "Bad address"),
+    }
 
-/// ## Arguments:
-/// - `old_virtualfd`: source virtual file descriptor
-/// - `new_virtualfd`: target virtual file descriptor
-/// - `flags`: must be 0 or O_CLOEXEC
+    ret
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/ftruncate.2.html
+///
+/// Linux `ftruncate()` syscall truncates the file referred to by the file descriptor `fd` to be at most
+/// `length` bytes in size. Since we implement a file descriptor management subsystem (called `fdtables`),
+/// we first translate the virtual file descriptor to the corresponding kernel file descriptor, then convert
+/// the length argument from u64 to i64 type before invoking the kernel's `libc::ftruncate()` function.
+///
+/// ## Input:
+///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
+///     - cageid: current cage identifier
+///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
+///     - length_arg: the desired length in bytes for the file truncation
+///     - arg4, arg5, arg6: additional arguments which are expected to be unused
 ///
 /// ## Returns:
-///     - On success, returns the new file descriptor.
-///     - On error, -1 is returned and errno is set to indicate the error (EBADF or EINVAL).
-pub extern "C" fn dup3_syscall(
+///     - 0 on success.
+///     - -1 on error, with errno set to indicate the error.
+pub extern "C" fn ftruncate_syscall(
     cageid: u64,
-    old_vfd_arg: u64,
-    old_vfd_cageid: u64,
-    new_vfd_arg: u64,
-    new_vfd_cageid: u64,
-    flags_arg: u64,
-    flags_cageid: u64,
+    vfd_arg: u64,
+    vfd_cageid: u64,
+    length_arg: u64,
+    length_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -2369,78 +3775,60 @@ pub extern "C" fn dup3_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    let flags = sc_convert_sysarg_to_i32(flags_arg, flags_cageid, cageid);
+    let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
+    // Return error
+    if kernel_fd < 0 {
+        return handle_errno(kernel_fd, "ftruncate");
+    }
 
-    if !(sc_unusedarg(arg4, arg4_cageid)
+    let length = sc_convert_sysarg_to_i64(length_arg, length_cageid, cageid);
+
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "dup3_syscall"
+            "ftruncate_syscall"
         );
     }
 
-    if old_vfd_arg > MAXFD as u64 || new_vfd_arg > MAXFD as u64 {
-        return syscall_error(Errno::EBADF, "dup3", "Bad File Descriptor");
-    }
-
-    if old_vfd_arg == new_vfd_arg {
-        return syscall_error(Errno::EINVAL, "dup3", "oldfd and newfd must be different");
-    }
-
-    if flags != 0 && flags != O_CLOEXEC {
-        return syscall_error(Errno::EINVAL, "dup3", "Invalid flags");
+    if let Some(handle) = tmpfs_fd_handle(vfd_cageid, vfd_arg) {
+        return handle.ftruncate(length);
     }
 
-    let ret = dup2_syscall(
-        cageid,
-        old_vfd_arg,
-        old_vfd_cageid,
-        new_vfd_arg,
-        new_vfd_cageid,
-        UNUSED_ARG,
-        UNUSED_ID,
-        UNUSED_ARG,
-        UNUSED_ID,
-        UNUSED_ARG,
-        UNUSED_ID,
-        UNUSED_ARG,
-        UNUSED_ID,
-    );
+    let ret = unsafe { libc::ftruncate(kernel_fd, length) };
     if ret < 0 {
-        return ret;
-    }
-
-    if flags == O_CLOEXEC {
-        let _ = fdtables::set_cloexec(cageid, new_vfd_arg, true);
+        return handle_errno(get_errno(), "ftruncate");
     }
-
-    return new_vfd_arg as i32;
+    ret
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/fchdir.2.html
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/fstatfs.2.html
 ///
-/// Linux `fchdir()` syscall changes the current working directory of the calling process to the
-/// directory referred to by the open file descriptor `fd`. Since we implement a file descriptor
-/// management subsystem (called `fdtables`), we first translate the virtual file descriptor to the
-/// corresponding kernel file descriptor before invoking the kernel's `libc::fchdir()` function.
+/// Linux `fstatfs()` syscall returns information about a mounted filesystem referred to by the open file
+/// descriptor `fd`. Since we implement a file descriptor management subsystem (called `fdtables`), we first
+/// translate the virtual file descriptor to the corresponding kernel file descriptor, then call the kernel's
+/// `libc::fstatfs()` function. The returned statfs structure is converted to our ABI-stable FSData format
+/// and copied to the user's buffer.
 ///
 /// ## Input:
 ///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
 ///     - cageid: current cage identifier
-///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment referring to a directory
-///     - arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
+///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
+///     - statfs_arg: pointer to a statfs structure where the filesystem information will be stored (user's perspective)
+///     - arg4, arg5, arg6: additional arguments which are expected to be unused
 ///
 /// ## Returns:
 ///     - 0 on success.
 ///     - -1 on error, with errno set to indicate the error.
-pub extern "C" fn fchdir_syscall(
+pub extern "C" fn fstatfs_syscall(
     cageid: u64,
     vfd_arg: u64,
     vfd_cageid: u64,
-    arg2: u64,
-    arg2_cageid: u64,
+    statfs_arg: u64,
+    statfs_cageid: u64,
     arg3: u64,
     arg3_cageid: u64,
     arg4: u64,
@@ -2453,69 +3841,70 @@ pub extern "C" fn fchdir_syscall(
     let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
     // Return error
     if kernel_fd < 0 {
-        return handle_errno(kernel_fd, "fchdir");
+        return handle_errno(kernel_fd, "fstatfs");
     }
 
-    if !(sc_unusedarg(arg2, arg2_cageid)
-        && sc_unusedarg(arg3, arg3_cageid)
+    if !(sc_unusedarg(arg3, arg3_cageid)
         && sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "fchdir_syscall"
+            "fstatfs_syscall"
         );
     }
 
-    let ret = unsafe { libc::fchdir(kernel_fd) };
+    // 1) Call host fstatfs into a local host variable
+    let mut host_statfs: libc::statfs = unsafe { std::mem::zeroed() };
+    let ret = match tmpfs_fd_handle(vfd_cageid, vfd_arg) {
+        Some(handle) => {
+            host_statfs = handle.fs().statfs();
+            0
+        }
+        None => unsafe { libc::fstatfs(kernel_fd, &mut host_statfs) },
+    };
     if ret < 0 {
-        return handle_errno(get_errno(), "fchdir");
+        return handle_errno(get_errno(), "fstatfs");
     }
 
-    // Update the cage's current working directory
-    // We need to get the current working directory from the kernel to update the cage
-    let mut cwd_buf = [0u8; PATH_MAX as usize];
-    let cwd_ptr = unsafe { libc::getcwd(cwd_buf.as_mut_ptr() as *mut i8, cwd_buf.len()) };
-    if !cwd_ptr.is_null() {
-        if let Some(cage) = get_cage(cageid) {
-            let host_path = unsafe { std::ffi::CStr::from_ptr(cwd_ptr) }.to_string_lossy();
-            let user_path = PathBuf::from(host_path.as_ref());
-            let mut cwd = cage.cwd.write();
-            *cwd = Arc::new(user_path);
-        }
+    // 2) Validate guest buffer range and writability
+    match sc_convert_addr_to_fstatdata(statfs_arg, statfs_cageid, cageid) {
+        // 3) Populate StatData directly
+        Ok(statbuf_addr) => convert_fstatdata_to_user(statbuf_addr, host_statfs),
+        Err(e) => return syscall_error(e, "fstatfs", "Bad address"),
     }
 
     ret
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/writev.2.html
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/getdents64.2.html
 ///
-/// Linux `writev()` syscall performs scatter-gather output by writing data from multiple buffers
-/// to a file descriptor in a single operation. Since we implement a file descriptor management
-/// subsystem (called `fdtables`), we first translate the virtual file descriptor to the corresponding
-/// kernel file descriptor, then translate the iovec array pointer from cage virtual memory to host
-/// memory before invoking the kernel's `libc::writev()` function.
+/// Linux `getdents()` syscall reads several directory entries from the directory referred to by the open file
+/// descriptor `fd` into the buffer pointed to by `dirp`. Since we implement a file descriptor management
+/// subsystem (called `fdtables`), we first translate the virtual file descriptor to the corresponding kernel
+/// file descriptor, then call the kernel's `getdents64` syscall directly to retrieve directory entries.
 ///
 /// ## Input:
 ///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
 ///     - cageid: current cage identifier
-///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
-///     - iov_arg: pointer to an array of iovec structures describing the buffers (user's perspective)
-///     - iovcnt_arg: number of iovec structures in the array
+///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment referring to a directory
+///     - dirp_arg: pointer to a buffer where the directory entries will be stored (user's perspective)
+///     - count_arg: size of the buffer pointed to by dirp
 ///     - arg4, arg5, arg6: additional arguments which are expected to be unused
 ///
 /// ## Returns:
-///     - On success, the number of bytes written is returned.
+///     - On success, the number of bytes read is returned.
+///     - On end of directory, 0 is returned.
 ///     - On error, -1 is returned and errno is set to indicate the error.
-pub extern "C" fn writev_syscall(
+pub extern "C" fn getdents_syscall(
     cageid: u64,
     vfd_arg: u64,
     vfd_cageid: u64,
-    iov_arg: u64,
-    iov_cageid: u64,
-    iovcnt_arg: u64,
-    iovcnt_cageid: u64,
+    dirp_arg: u64,
+    dirp_cageid: u64,
+    count_arg: u64,
+    count_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -2526,11 +3915,11 @@ pub extern "C" fn writev_syscall(
     let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
     // Return error
     if kernel_fd < 0 {
-        return handle_errno(kernel_fd, "writev");
+        return handle_errno(kernel_fd, "getdents");
     }
 
-    let iovcnt = sc_convert_sysarg_to_i32(iovcnt_arg, iovcnt_cageid, cageid);
-    let iov_ptr = sc_convert_buf(iov_arg, iov_cageid, cageid);
+    let dirp = sc_convert_buf(dirp_arg, dirp_cageid, cageid);
+    let count = sc_convert_sysarg_to_usize(count_arg, count_cageid, cageid);
 
     if !(sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
@@ -2538,42 +3927,55 @@ pub extern "C" fn writev_syscall(
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "writev"
+            "getdents_syscall"
         );
     }
 
-    let ret = unsafe { libc::writev(kernel_fd, iov_ptr as *const libc::iovec, iovcnt) as i32 };
-    if ret < 0 {
-        return handle_errno(get_errno(), "writev");
+    if let Some(handle) = tmpfs_fd_handle(vfd_cageid, vfd_arg) {
+        return handle.getdents(dirp as *mut u8, count);
     }
-    ret
+
+    if let Some(ret) = overlay_getdents(vfd_cageid, vfd_arg, dirp as *mut u8, count) {
+        return ret;
+    }
+
+    if let Some(ret) = sorted_getdents(kernel_fd, dirp as *mut u8, count) {
+        return ret;
+    }
+
+    let ret =
+        unsafe { libc::syscall(libc::SYS_getdents64 as libc::c_long, kernel_fd, dirp, count) };
+
+    ret as i32
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/fstat.2.html
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/lseek.2.html
 ///
-/// Linux `fstat()` syscall retrieves information about the file referred to by the open file descriptor `fd`.
-/// Since we implement a file descriptor management subsystem (called `fdtables`), we first translate the virtual
-/// file descriptor to the corresponding kernel file descriptor, then call the kernel's `libc::fstat()` function.
-/// The returned stat structure is converted to our ABI-stable StatData format and copied to the user's buffer.
+/// Linux `lseek()` syscall repositions the file offset of the open file description associated with the file
+/// descriptor `fd` to the argument `offset` according to the directive `whence`. Since we implement a file
+/// descriptor management subsystem (called `fdtables`), we first translate the virtual file descriptor to the
+/// corresponding kernel file descriptor, then convert the offset and whence parameters from cage memory before
+/// invoking the kernel's `libc::lseek()` function.
 ///
 /// ## Input:
 ///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
 ///     - cageid: current cage identifier
 ///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
-///     - stat_arg: pointer to a stat structure where the file information will be stored (user's perspective)
-///     - arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
+///     - offset_arg: the new offset according to the directive whence
+///     - whence_arg: how to interpret the offset (SEEK_SET, SEEK_CUR, or SEEK_END)
+///     - arg4, arg5, arg6: additional arguments which are expected to be unused
 ///
 /// ## Returns:
-///     - 0 on success.
-///     - -1 on error, with errno set to indicate the error.
-pub extern "C" fn fstat_syscall(
+///     - On success, the resulting offset location as measured in bytes from the beginning of the file is returned.
+///     - On error, -1 is returned and errno is set to indicate the error.
+pub extern "C" fn lseek_syscall(
     cageid: u64,
     vfd_arg: u64,
     vfd_cageid: u64,
-    statbuf_arg: u64,
-    statbuf_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
+    offset_arg: u64,
+    offset_cageid: u64,
+    whence_arg: u64,
+    whence_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -2584,64 +3986,76 @@ pub extern "C" fn fstat_syscall(
     let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
     // Return error
     if kernel_fd < 0 {
-        return handle_errno(kernel_fd, "fstat");
+        return handle_errno(kernel_fd, "lseek");
     }
 
-    if !(sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
+    let offset = sc_convert_sysarg_to_i64(offset_arg, offset_cageid, cageid);
+    let whence = sc_convert_sysarg_to_i32(whence_arg, whence_cageid, cageid);
+
+    if !(sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "fstat_syscall"
+            "lseek_syscall"
         );
     }
 
-    // Cast directly to libc::stat and write kernel data into buffer.
-    let mut host_stat: libc::stat = unsafe { std::mem::zeroed() };
-    let ret = unsafe { libc::fstat(kernel_fd, &mut host_stat as *mut libc::stat) };
-    if ret < 0 {
-        return handle_errno(get_errno(), "fstat");
+    if let Some(dev) = devfs_fd_device(vfd_cageid, vfd_arg) {
+        return devfs_lseek(dev);
     }
 
-    // Validate guest buffer range and writability
-    match sc_convert_addr_to_statdata(statbuf_arg, statbuf_cageid, cageid) {
-        // 3) Populate StatData directly
-        Ok(statbuf_addr) => convert_statdata_to_user(statbuf_addr, host_stat),
-        Err(e) => return syscall_error(e, "fstat", "Bad address"),
+    if let Some(handle) = tmpfs_fd_handle(vfd_cageid, vfd_arg) {
+        return handle.lseek(offset, whence);
     }
 
-    ret
+    if let Some(ret) = overlay_lseek(vfd_cageid, vfd_arg, offset, whence) {
+        return ret;
+    }
+
+    if let Some(ret) = sorted_lseek(kernel_fd, offset, whence) {
+        return ret;
+    }
+
+    let ret = unsafe { libc::lseek(kernel_fd, offset, whence) };
+    if ret < 0 {
+        return handle_errno(get_errno(), "lseek");
+    }
+
+    ret as i32
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/ftruncate.2.html
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/pread.2.html
 ///
-/// Linux `ftruncate()` syscall truncates the file referred to by the file descriptor `fd` to be at most
-/// `length` bytes in size. Since we implement a file descriptor management subsystem (called `fdtables`),
-/// we first translate the virtual file descriptor to the corresponding kernel file descriptor, then convert
-/// the length argument from u64 to i64 type before invoking the kernel's `libc::ftruncate()` function.
+/// Linux `pread()` syscall reads up to `count` bytes from the file descriptor `fd` at the
+/// given `offset` without changing the file offset. Since we implement a file descriptor management
+/// subsystem (called `fdtables`), we first translate the virtual file descriptor to the corresponding
+/// kernel file descriptor, then convert the buffer and offset from cage memory before invoking the
+/// kernel's `libc::pread()` function.
 ///
 /// ## Input:
 ///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
 ///     - cageid: current cage identifier
 ///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
-///     - length_arg: the desired length in bytes for the file truncation
-///     - arg4, arg5, arg6: additional arguments which are expected to be unused
+///     - buf_arg: pointer to a buffer where the read data will be stored (user's perspective)
+///     - count_arg: the maximum number of bytes to read from the file descriptor
+///     - offset_arg: file offset at which the input/output operation takes place
+///     - arg5, arg6: additional arguments which are expected to be unused
 ///
 /// ## Returns:
-///     - 0 on success.
-///     - -1 on error, with errno set to indicate the error.
-pub extern "C" fn ftruncate_syscall(
+///     - On success, the number of bytes read is returned (zero indicates end of file).
+///     - On error, -1 is returned and errno is set to indicate the error.
+pub extern "C" fn pread_syscall(
     cageid: u64,
     vfd_arg: u64,
     vfd_cageid: u64,
-    length_arg: u64,
-    length_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
-    arg4: u64,
-    arg4_cageid: u64,
+    buf_arg: u64,
+    buf_cageid: u64,
+    count_arg: u64,
+    count_cageid: u64,
+    offset_arg: u64,
+    offset_cageid: u64,
     arg5: u64,
     arg5_cageid: u64,
     arg6: u64,
@@ -2650,57 +4064,67 @@ pub extern "C" fn ftruncate_syscall(
     let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
     // Return error
     if kernel_fd < 0 {
-        return handle_errno(kernel_fd, "ftruncate");
+        return handle_errno(kernel_fd, "pread");
     }
 
-    let length = sc_convert_sysarg_to_i64(length_arg, length_cageid, cageid);
+    let buf = sc_convert_buf(buf_arg, buf_cageid, cageid);
+    let count = sc_convert_sysarg_to_usize(count_arg, count_cageid, cageid);
+    let offset = sc_convert_sysarg_to_i64(offset_arg, offset_cageid, cageid);
 
-    if !(sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
-        && sc_unusedarg(arg5, arg5_cageid)
-        && sc_unusedarg(arg6, arg6_cageid))
-    {
+    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "ftruncate_syscall"
+            "pread_syscall"
         );
     }
 
-    let ret = unsafe { libc::ftruncate(kernel_fd, length) };
+    // Devices ignore the offset, so pread behaves exactly like read
+    if let Some(dev) = devfs_fd_device(vfd_cageid, vfd_arg) {
+        return devfs_read(cageid, dev, buf as *mut u8, count);
+    }
+
+    if let Some(handle) = tmpfs_fd_handle(vfd_cageid, vfd_arg) {
+        return handle.pread(buf as *mut u8, count, offset);
+    }
+
+    let ret = unsafe { libc::pread(kernel_fd, buf as *mut c_void, count, offset) as i32 };
     if ret < 0 {
-        return handle_errno(get_errno(), "ftruncate");
+        let errno = get_errno();
+        return handle_errno(errno, "pread");
     }
     ret
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/fstatfs.2.html
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/pwrite.2.html
 ///
-/// Linux `fstatfs()` syscall returns information about a mounted filesystem referred to by the open file
-/// descriptor `fd`. Since we implement a file descriptor management subsystem (called `fdtables`), we first
-/// translate the virtual file descriptor to the corresponding kernel file descriptor, then call the kernel's
-/// `libc::fstatfs()` function. The returned statfs structure is converted to our ABI-stable FSData format
-/// and copied to the user's buffer.
+/// Linux `pwrite()` syscall writes up to `count` bytes from the buffer pointed to by `buf` to the file
+/// associated with the open file descriptor, `fd`, starting at the given `offset` without changing the
+/// file offset. Since we implement a file descriptor management subsystem (called `fdtables`), we first
+/// translate the virtual file descriptor to the corresponding kernel file descriptor, then convert the
+/// buffer, count, and offset from cage memory before invoking the kernel's `libc::pwrite()` function.
 ///
 /// ## Input:
 ///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
 ///     - cageid: current cage identifier
 ///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
-///     - statfs_arg: pointer to a statfs structure where the filesystem information will be stored (user's perspective)
-///     - arg4, arg5, arg6: additional arguments which are expected to be unused
+///     - buf_arg: pointer to a buffer that stores the data to be written (user's perspective)
+///     - count_arg: the maximum number of bytes to write to the file descriptor
+///     - offset_arg: file offset at which the input/output operation takes place
+///     - arg5, arg6: additional arguments which are expected to be unused
 ///
 /// ## Returns:
-///     - 0 on success.
-///     - -1 on error, with errno set to indicate the error.
-pub extern "C" fn fstatfs_syscall(
+///     - On success, the number of bytes written is returned.
+///     - On error, -1 is returned and errno is set to indicate the error.
+pub extern "C" fn pwrite_syscall(
     cageid: u64,
     vfd_arg: u64,
     vfd_cageid: u64,
-    statfs_arg: u64,
-    statfs_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
-    arg4: u64,
-    arg4_cageid: u64,
+    buf_arg: u64,
+    buf_cageid: u64,
+    count_arg: u64,
+    count_cageid: u64,
+    offset_arg: u64,
+    offset_cageid: u64,
     arg5: u64,
     arg5_cageid: u64,
     arg6: u64,
@@ -2709,64 +4133,61 @@ pub extern "C" fn fstatfs_syscall(
     let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
     // Return error
     if kernel_fd < 0 {
-        return handle_errno(kernel_fd, "fstatfs");
+        return handle_errno(kernel_fd, "pwrite");
     }
 
-    if !(sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
-        && sc_unusedarg(arg5, arg5_cageid)
-        && sc_unusedarg(arg6, arg6_cageid))
-    {
+    let buf = sc_convert_buf(buf_arg, buf_cageid, cageid);
+    let count = sc_convert_sysarg_to_usize(count_arg, count_cageid, cageid);
+    let offset = sc_convert_sysarg_to_i64(offset_arg, offset_cageid, cageid);
+
+    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "fstatfs_syscall"
+            "pwrite_syscall"
         );
     }
 
-    // 1) Call host fstatfs into a local host variable
-    let mut host_statfs: libc::statfs = unsafe { std::mem::zeroed() };
-    let ret = unsafe { libc::fstatfs(kernel_fd, &mut host_statfs) };
-    if ret < 0 {
-        return handle_errno(get_errno(), "fstatfs");
+    if let Some(dev) = devfs_fd_device(vfd_cageid, vfd_arg) {
+        return devfs_write(dev, buf, count);
     }
 
-    // 2) Validate guest buffer range and writability
-    match sc_convert_addr_to_fstatdata(statfs_arg, statfs_cageid, cageid) {
-        // 3) Populate StatData directly
-        Ok(statbuf_addr) => convert_fstatdata_to_user(statbuf_addr, host_statfs),
-        Err(e) => return syscall_error(e, "fstatfs", "Bad address"),
+    if let Some(handle) = tmpfs_fd_handle(vfd_cageid, vfd_arg) {
+        return handle.pwrite(buf, count, offset);
     }
 
+    let ret = unsafe { libc::pwrite(kernel_fd, buf as *const c_void, count, offset) as i32 };
+    if ret < 0 {
+        let errno = get_errno();
+        return handle_errno(errno, "pwrite");
+    }
     ret
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/getdents64.2.html
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/chdir.2.html
 ///
-/// Linux `getdents()` syscall reads several directory entries from the directory referred to by the open file
-/// descriptor `fd` into the buffer pointed to by `dirp`. Since we implement a file descriptor management
-/// subsystem (called `fdtables`), we first translate the virtual file descriptor to the corresponding kernel
-/// file descriptor, then call the kernel's `getdents64` syscall directly to retrieve directory entries.
+/// Linux `chdir()` syscall changes the current working directory of the calling process to the directory
+/// specified by path. Since path seen by user is different from actual path on host, we need to convert
+/// the path first. RawPOSIX also updates the cage's current working directory in the cage structure after
+/// successfully changing the directory, ensuring cage isolation is maintained.
 ///
 /// ## Input:
 ///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
 ///     - cageid: current cage identifier
-///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment referring to a directory
-///     - dirp_arg: pointer to a buffer where the directory entries will be stored (user's perspective)
-///     - count_arg: size of the buffer pointed to by dirp
-///     - arg4, arg5, arg6: additional arguments which are expected to be unused
+///     - path_arg: pointer to a pathname naming the directory (user's perspective)
+///     - path_cageid: cage identifier for the path argument
+///     - arg2, arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
 ///
 /// ## Returns:
-///     - On success, the number of bytes read is returned.
-///     - On end of directory, 0 is returned.
-///     - On error, -1 is returned and errno is set to indicate the error.
-pub extern "C" fn getdents_syscall(
+///     - 0 on success.
+///     - -1 on error, with errno set to indicate the error.
+pub extern "C" fn chdir_syscall(
     cageid: u64,
-    vfd_arg: u64,
-    vfd_cageid: u64,
-    dirp_arg: u64,
-    dirp_cageid: u64,
-    count_arg: u64,
-    count_cageid: u64,
+    path_arg: u64,
+    path_cageid: u64,
+    arg2: u64,
+    arg2_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -2774,58 +4195,78 @@ pub extern "C" fn getdents_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
-    // Return error
-    if kernel_fd < 0 {
-        return handle_errno(kernel_fd, "getdents");
-    }
-
-    let dirp = sc_convert_buf(dirp_arg, dirp_cageid, cageid);
-    let count = sc_convert_sysarg_to_usize(count_arg, count_cageid, cageid);
+    // Type conversion
+    let path = match sc_convert_path_to_host(path_arg, path_cageid, cageid) {
+        Ok(path) => path,
+        Err(e) => return syscall_error(e, "chdir", "path conversion failed"),
+    };
 
-    if !(sc_unusedarg(arg4, arg4_cageid)
+    // would sometimes check, sometimes be a no-op depending on the compiler settings
+    if !(sc_unusedarg(arg2, arg2_cageid)
+        && sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "getdents_syscall"
+            "chdir_syscall"
         );
     }
 
-    let ret =
-        unsafe { libc::syscall(libc::SYS_getdents64 as libc::c_long, kernel_fd, dirp, count) };
-
-    ret as i32
+    // tmpfs directories don't exist on the host, and overlay ones may live in the upper layer
+    // only. For both, just the cage's cwd is updated.
+    let ret = if let Some(fs) = tmpfs_for_path(path.as_bytes()) {
+        fs.chdir(path.as_bytes())
+    } else if let Some(layers) = overlay_layers(cageid) {
+        layers.chdir(path.as_bytes())
+    } else {
+        // Call the kernel chdir function
+        match unsafe { libc::chdir(path.as_ptr()) } {
+            ret if ret < 0 => handle_errno(get_errno(), "chdir"),
+            ret => ret,
+        }
+    };
+
+    // Error handling
+    if ret < 0 {
+        return ret;
+    }
+
+    // Update the cage's current working directory
+    if let Some(cage) = get_cage(cageid) {
+        let user_path = PathBuf::from(path.to_string_lossy().as_ref());
+        let mut cwd = cage.cwd.write();
+        *cwd = Arc::new(user_path);
+    }
+
+    ret
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/lseek.2.html
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/rmdir.2.html
 ///
-/// Linux `lseek()` syscall repositions the file offset of the open file description associated with the file
-/// descriptor `fd` to the argument `offset` according to the directive `whence`. Since we implement a file
-/// descriptor management subsystem (called `fdtables`), we first translate the virtual file descriptor to the
-/// corresponding kernel file descriptor, then convert the offset and whence parameters from cage memory before
-/// invoking the kernel's `libc::lseek()` function.
+/// Linux `rmdir()` syscall removes a directory, which must be empty. Since path seen by user is different
+/// from actual path on host, we need to convert the path first. RawPOSIX doesn't have any other operations,
+/// so all operations will be handled by host. RawPOSIX does error handling for this syscall.
 ///
 /// ## Input:
 ///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
 ///     - cageid: current cage identifier
-///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
-///     - offset_arg: the new offset according to the directive whence
-///     - whence_arg: how to interpret the offset (SEEK_SET, SEEK_CUR, or SEEK_END)
-///     - arg4, arg5, arg6: additional arguments which are expected to be unused
+///     - path_arg: pointer to a pathname naming the directory to be removed (user's perspective)
+///     - path_cageid: cage identifier for the path argument
+///     - arg2, arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
 ///
 /// ## Returns:
-///     - On success, the resulting offset location as measured in bytes from the beginning of the file is returned.
-///     - On error, -1 is returned and errno is set to indicate the error.
-pub extern "C" fn lseek_syscall(
+///     - 0 on success.
+///     - -1 on error, with errno set to indicate the error.
+pub extern "C" fn rmdir_syscall(
     cageid: u64,
-    vfd_arg: u64,
-    vfd_cageid: u64,
-    offset_arg: u64,
-    offset_cageid: u64,
-    whence_arg: u64,
-    whence_cageid: u64,
+    path_arg: u64,
+    path_cageid: u64,
+    arg2: u64,
+    arg2_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -2833,123 +4274,145 @@ pub extern "C" fn lseek_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
-    // Return error
-    if kernel_fd < 0 {
-        return handle_errno(kernel_fd, "lseek");
-    }
-
-    let offset = sc_convert_sysarg_to_i64(offset_arg, offset_cageid, cageid);
-    let whence = sc_convert_sysarg_to_i32(whence_arg, whence_cageid, cageid);
+    // Type conversion
+    let path = match sc_convert_path_to_host(path_arg, path_cageid, cageid) {
+        Ok(path) => path,
+        Err(e) => return syscall_error(e, "rmdir", "path conversion failed"),
+    };
 
-    if !(sc_unusedarg(arg4, arg4_cageid)
+    // would sometimes check, sometimes be a no-op depending on the compiler settings
+    if !(sc_unusedarg(arg2, arg2_cageid)
+        && sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "lseek_syscall"
+            "rmdir_syscall"
         );
     }
 
-    let ret = unsafe { libc::lseek(kernel_fd, offset, whence) };
+    // Call the kernel rmdir function
+    if let Some(fs) = tmpfs_for_path(path.as_bytes()) {
+        return fs.rmdir(path.as_bytes());
+    }
+
+    if let Some(layers) = overlay_layers(cageid) {
+        return layers.rmdir(path.as_bytes());
+    }
+
+    let ret = unsafe { libc::rmdir(path.as_ptr()) };
+
+    // Error handling
     if ret < 0 {
-        return handle_errno(get_errno(), "lseek");
+        let errno = get_errno();
+        return handle_errno(errno, "rmdir");
     }
 
-    ret as i32
+    ret
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/pread.2.html
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/chmod.2.html
 ///
-/// Linux `pread()` syscall reads up to `count` bytes from the file descriptor `fd` at the
-/// given `offset` without changing the file offset. Since we implement a file descriptor management
-/// subsystem (called `fdtables`), we first translate the virtual file descriptor to the corresponding
-/// kernel file descriptor, then convert the buffer and offset from cage memory before invoking the
-/// kernel's `libc::pread()` function.
+/// Linux `chmod()` syscall changes the permissions of a file. Since path seen by user is different from
+/// actual path on host, we need to convert the path first. The mode argument specifies the permissions
+/// to be assigned to the file and is passed directly to the kernel after type conversion.
 ///
 /// ## Input:
 ///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
 ///     - cageid: current cage identifier
-///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
-///     - buf_arg: pointer to a buffer where the read data will be stored (user's perspective)
-///     - count_arg: the maximum number of bytes to read from the file descriptor
-///     - offset_arg: file offset at which the input/output operation takes place
-///     - arg5, arg6: additional arguments which are expected to be unused
+///     - path_arg: pointer to a pathname naming the file (user's perspective)
+///     - path_cageid: cage identifier for the path argument
+///     - mode_arg: the new file permissions (user's perspective)
+///     - mode_cageid: cage identifier for the mode argument
+///     - arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
 ///
 /// ## Returns:
-///     - On success, the number of bytes read is returned (zero indicates end of file).
-///     - On error, -1 is returned and errno is set to indicate the error.
-pub extern "C" fn pread_syscall(
+///     - 0 on success.
+///     - -1 on error, with errno set to indicate the error.
+pub extern "C" fn chmod_syscall(
     cageid: u64,
-    vfd_arg: u64,
-    vfd_cageid: u64,
-    buf_arg: u64,
-    buf_cageid: u64,
-    count_arg: u64,
-    count_cageid: u64,
-    offset_arg: u64,
-    offset_cageid: u64,
+    path_arg: u64,
+    path_cageid: u64,
+    mode_arg: u64,
+    mode_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
     arg5: u64,
     arg5_cageid: u64,
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
-    // Return error
-    if kernel_fd < 0 {
-        return handle_errno(kernel_fd, "pread");
-    }
-
-    let buf = sc_convert_buf(buf_arg, buf_cageid, cageid);
-    let count = sc_convert_sysarg_to_usize(count_arg, count_cageid, cageid);
-    let offset = sc_convert_sysarg_to_i64(offset_arg, offset_cageid, cageid);
+    // Type conversion
+    let path = match sc_convert_path_to_host(path_arg, path_cageid, cageid) {
+        Ok(path) => path,
+        Err(e) => return syscall_error(e, "chmod", "path conversion failed"),
+    };
+    let mode = sc_convert_sysarg_to_u32(mode_arg, mode_cageid, cageid);
 
-    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
+    // would sometimes check, sometimes be a no-op depending on the compiler settings
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "pread_syscall"
+            "chmod_syscall"
         );
     }
 
-    let ret = unsafe { libc::pread(kernel_fd, buf as *mut c_void, count, offset) as i32 };
+    // Call the kernel chmod function
+    if let Some(fs) = tmpfs_for_path(path.as_bytes()) {
+        return fs.chmod(path.as_bytes(), mode);
+    }
+
+    if let Some(layers) = overlay_layers(cageid) {
+        return layers.chmod(path.as_bytes(), mode);
+    }
+
+    let ret = unsafe { libc::chmod(path.as_ptr(), mode) };
+
+    // Error handling
     if ret < 0 {
         let errno = get_errno();
-        return handle_errno(errno, "pread");
+        return handle_errno(errno, "chmod");
     }
+
     ret
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/pwrite.2.html
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/fchmod.2.html
 ///
-/// Linux `pwrite()` syscall writes up to `count` bytes from the buffer pointed to by `buf` to the file
-/// associated with the open file descriptor, `fd`, starting at the given `offset` without changing the
-/// file offset. Since we implement a file descriptor management subsystem (called `fdtables`), we first
-/// translate the virtual file descriptor to the corresponding kernel file descriptor, then convert the
-/// buffer, count, and offset from cage memory before invoking the kernel's `libc::pwrite()` function.
+/// Linux `fchmod()` syscall changes the permissions of an open file referred to by file descriptor `fd`.
+/// Since we implement a file descriptor management subsystem (called `fdtables`), we first translate the
+/// virtual file descriptor to the corresponding kernel file descriptor, then convert the mode from cage
+/// memory before invoking the kernel's `libc::fchmod()` function.
 ///
 /// ## Input:
 ///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
 ///     - cageid: current cage identifier
 ///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
-///     - buf_arg: pointer to a buffer that stores the data to be written (user's perspective)
-///     - count_arg: the maximum number of bytes to write to the file descriptor
-///     - offset_arg: file offset at which the input/output operation takes place
-///     - arg5, arg6: additional arguments which are expected to be unused
+///     - mode_arg: the new file permissions to be applied to the file
+///     - mode_cageid: cage identifier for the mode argument
+///     - arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
 ///
 /// ## Returns:
-///     - On success, the number of bytes written is returned.
-///     - On error, -1 is returned and errno is set to indicate the error.
-pub extern "C" fn pwrite_syscall(
+///     - 0 on success.
+///     - -1 on error, with errno set to indicate the error.
+pub extern "C" fn fchmod_syscall(
     cageid: u64,
     vfd_arg: u64,
     vfd_cageid: u64,
-    buf_arg: u64,
-    buf_cageid: u64,
-    count_arg: u64,
-    count_cageid: u64,
-    offset_arg: u64,
-    offset_cageid: u64,
+    mode_arg: u64,
+    mode_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
     arg5: u64,
     arg5_cageid: u64,
     arg6: u64,
@@ -2958,51 +4421,68 @@ pub extern "C" fn pwrite_syscall(
     let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
     // Return error
     if kernel_fd < 0 {
-        return handle_errno(kernel_fd, "pwrite");
+        return handle_errno(kernel_fd, "fchmod");
     }
 
-    let buf = sc_convert_buf(buf_arg, buf_cageid, cageid);
-    let count = sc_convert_sysarg_to_usize(count_arg, count_cageid, cageid);
-    let offset = sc_convert_sysarg_to_i64(offset_arg, offset_cageid, cageid);
+    let mode = sc_convert_sysarg_to_u32(mode_arg, mode_cageid, cageid);
 
-    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "pwrite_syscall"
+            "fchmod_syscall"
         );
     }
 
-    let ret = unsafe { libc::pwrite(kernel_fd, buf as *const c_void, count, offset) as i32 };
+    if let Some(handle) = tmpfs_fd_handle(vfd_cageid, vfd_arg) {
+        return handle.fchmod(mode);
+    }
+
+    // Changing the mode must not reach a file of the read-only image
+    if let (Some(layers), Some(path)) =
+        (overlay_layers(cageid), overlay_fd_path(vfd_cageid, vfd_arg))
+    {
+        return layers.chmod(path.as_bytes(), mode);
+    }
+
+    let ret = unsafe { libc::fchmod(kernel_fd, mode) };
     if ret < 0 {
         let errno = get_errno();
-        return handle_errno(errno, "pwrite");
+        return handle_errno(errno, "fchmod");
     }
     ret
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/chdir.2.html
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/getcwd.2.html
 ///
-/// Linux `chdir()` syscall changes the current working directory of the calling process to the directory
-/// specified by path. Since path seen by user is different from actual path on host, we need to convert
-/// the path first. RawPOSIX also updates the cage's current working directory in the cage structure after
-/// successfully changing the directory, ensuring cage isolation is maintained.
+/// `getcwd_syscall` retrieves the current working directory for the calling Cage.
+///
+/// Unlike directly calling `libc::getcwd`, this implementation uses the Cage's
+/// own `cwd` field maintained inside the Cage struct. Each Cage has its own
+/// logical working directory, which may differ from the host kernel's notion
+/// of the filesystem root. Because of this mismatch between the Cage's root
+/// and the kernel root, we cannot delegate to `libc::getcwd` and must instead
+/// return the Cage-specific `cwd` value.
 ///
 /// ## Input:
 ///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
 ///     - cageid: current cage identifier
-///     - path_arg: pointer to a pathname naming the directory (user's perspective)
-///     - path_cageid: cage identifier for the path argument
-///     - arg2, arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
+///     - buf_arg: pointer to a buffer where the current working directory path will be stored (user's perspective)
+///     - size_arg: the size of the buffer in bytes
+///     - arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
 ///
 /// ## Returns:
-///     - 0 on success.
-///     - -1 on error, with errno set to indicate the error.
-pub extern "C" fn chdir_syscall(
+///     - On success, returns 0 after copying the current working directory path to the buffer.
+///     - On error, returns -1 and errno is set to indicate the error.
+pub extern "C" fn getcwd_syscall(
     cageid: u64,
-    path_arg: u64,
-    path_cageid: u64,
-    arg2: u64,
-    arg2_cageid: u64,
+    buf_arg: u64,
+    buf_cageid: u64,
+    size_arg: u64,
+    size_cageid: u64,
     arg3: u64,
     arg3_cageid: u64,
     arg4: u64,
@@ -3010,68 +4490,64 @@ pub extern "C" fn chdir_syscall(
     arg5: u64,
     arg5_cageid: u64,
     arg6: u64,
-    arg6_cageid: u64,
-) -> i32 {
-    // Type conversion
-    let path = match sc_convert_path_to_host(path_arg, path_cageid, cageid) {
-        Ok(path) => path,
-        Err(e) => return syscall_error(e, "chdir", "path conversion failed"),
-    };
+    arg6_cageid: u64,
+) -> i32 {
+    let buf = buf_arg as *mut u8;
 
-    // would sometimes check, sometimes be a no-op depending on the compiler settings
-    if !(sc_unusedarg(arg2, arg2_cageid)
-        && sc_unusedarg(arg3, arg3_cageid)
+    let size = sc_convert_sysarg_to_usize(size_arg, size_cageid, cageid);
+    if size == 0 {
+        return syscall_error(Errno::EINVAL, "getcwd", "Size cannot be zero");
+    }
+
+    if !(sc_unusedarg(arg3, arg3_cageid)
         && sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "chdir_syscall"
+            "getcwd_syscall"
         );
     }
 
-    // Call the kernel chdir function
-    let ret = unsafe { libc::chdir(path.as_ptr()) };
-
-    // Error handling
-    if ret < 0 {
-        let errno = get_errno();
-        return handle_errno(errno, "chdir");
+    let cage = get_cage(cageid).unwrap();
+    let cwd_container = cage.cwd.read();
+    let path = cwd_container.to_str().unwrap();
+    // The required size includes the null terminator
+    let required_size = path.len() + 1;
+    if required_size > size as usize {
+        return syscall_error(Errno::ERANGE, "getcwd_syscall", "Invalid buffer size");
     }
-
-    // Update the cage's current working directory
-    if let Some(cage) = get_cage(cageid) {
-        let user_path = PathBuf::from(path.to_string_lossy().as_ref());
-        let mut cwd = cage.cwd.write();
-        *cwd = Arc::new(user_path);
+    unsafe {
+        ptr::copy(path.as_ptr(), buf, path.len());
+        *buf.add(path.len()) = 0;
     }
 
-    ret
+    // std::copy guarantees it copies exactly path.len() bytes.
+    let bytes_written: i32 = path.len().try_into().unwrap();
+
+    bytes_written
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/rmdir.2.html
-///
-/// Linux `rmdir()` syscall removes a directory, which must be empty. Since path seen by user is different
-/// from actual path on host, we need to convert the path first. RawPOSIX doesn't have any other operations,
-/// so all operations will be handled by host. RawPOSIX does error handling for this syscall.
+/// Truncate a file to a specified length
 ///
-/// ## Input:
-///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
+/// ## Arguments:
 ///     - cageid: current cage identifier
-///     - path_arg: pointer to a pathname naming the directory to be removed (user's perspective)
+///     - path_arg: pointer to the pathname of the file to truncate
 ///     - path_cageid: cage identifier for the path argument
-///     - arg2, arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
+///     - length_arg: the new length to truncate the file to
+///     - length_cageid: cage identifier for the length argument
+///     - arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
 ///
 /// ## Returns:
 ///     - 0 on success.
 ///     - -1 on error, with errno set to indicate the error.
-pub extern "C" fn rmdir_syscall(
+pub extern "C" fn truncate_syscall(
     cageid: u64,
     path_arg: u64,
     path_cageid: u64,
-    arg2: u64,
-    arg2_cageid: u64,
+    length_arg: u64,
+    length_cageid: u64,
     arg3: u64,
     arg3_cageid: u64,
     arg4: u64,
@@ -3081,127 +4557,217 @@ pub extern "C" fn rmdir_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    // Type conversion
-    let path = match sc_convert_path_to_host(path_arg, path_cageid, cageid) {
-        Ok(path) => path,
-        Err(e) => return syscall_error(e, "rmdir", "path conversion failed"),
-    };
-
-    // would sometimes check, sometimes be a no-op depending on the compiler settings
-    if !(sc_unusedarg(arg2, arg2_cageid)
-        && sc_unusedarg(arg3, arg3_cageid)
+    // Validate unused arguments
+    if !(sc_unusedarg(arg3, arg3_cageid)
         && sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "rmdir_syscall"
+            "truncate_syscall"
         );
     }
 
-    // Call the kernel rmdir function
-    let ret = unsafe { libc::rmdir(path.as_ptr()) };
+    // Type conversion
+    let path = match sc_convert_path_to_host(path_arg, path_cageid, cageid) {
+        Ok(path) => path,
+        Err(e) => return syscall_error(e, "truncate", "path conversion failed"),
+    };
+    let length = sc_convert_sysarg_to_i64(length_arg, length_cageid, cageid);
 
-    // Error handling
-    if ret < 0 {
+    if let Some(fs) = tmpfs_for_path(path.as_bytes()) {
+        return fs.truncate(path.as_bytes(), length);
+    }
+
+    if let Some(layers) = overlay_layers(cageid) {
+        return layers.truncate(path.as_bytes(), length);
+    }
+
+    // Call libc truncate
+    let ret = unsafe { libc::truncate(path.as_ptr() as *const i8, length) };
+
+    if ret == -1 {
         let errno = get_errno();
-        return handle_errno(errno, "rmdir");
+        return handle_errno(errno, "truncate");
     }
 
-    ret
+    0
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/chmod.2.html
+/// How long a sleep waits on the host at most before it checks for signals again
+const SLEEP_CHUNK: Duration = Duration::from_millis(100);
+
+/// How long a sleep on a CPU time clock waits at most before it reads the clock again
+const CPU_SLEEP_CHUNK: Duration = Duration::from_millis(1);
+
+/// Sleep until clock `clockid` of cage `cageid` reads `deadline`, in chunks, so that a signal
+/// for the cage ends the sleep early and a change of the cage's realtime offset moves its end.
 ///
-/// Linux `chmod()` syscall changes the permissions of a file. Since path seen by user is different from
-/// actual path on host, we need to convert the path first. The mode argument specifies the permissions
-/// to be assigned to the file and is passed directly to the kernel after type conversion.
+/// ## Returns:
+/// - `Ok(())` once the deadline is reached
+/// - `Err(remaining)` if the sleep was interrupted
+fn clock_sleep_until(
+    cageid: u64,
+    clockid: libc::clockid_t,
+    deadline: Duration,
+) -> Result<(), Duration> {
+    let cpu_clock = clockid < 0 || clockid == CLOCK_PROCESS_CPUTIME_ID;
+    loop {
+        // The cage whose CPU clock is slept on may be gone
+        let Some(now) = cage_clock_now(cageid, clockid) else {
+            return Ok(());
+        };
+        if now >= deadline {
+            return Ok(());
+        }
+        if signal_check_trigger(cageid) {
+            return Err(deadline - now);
+        }
+        let chunk = if cpu_clock {
+            CPU_SLEEP_CHUNK
+        } else {
+            SLEEP_CHUNK
+        };
+        std::thread::sleep((deadline - now).min(chunk));
+    }
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/clock_nanosleep.2.html
 ///
-/// ## Input:
-///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
-///     - cageid: current cage identifier
-///     - path_arg: pointer to a pathname naming the file (user's perspective)
-///     - path_cageid: cage identifier for the path argument
-///     - mode_arg: the new file permissions (user's perspective)
-///     - mode_cageid: cage identifier for the mode argument
-///     - arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
+/// `clock_nanosleep_syscall` suspends execution of the calling thread until
+/// the clock `clockid` has advanced by the requested interval, or, with
+/// `TIMER_ABSTIME` in `flags`, until it reads the requested time. It backs
+/// `nanosleep()` (syscall 35) as well as `clock_nanosleep()`.
+///
+/// ## Implementation Details:
+/// - The `req` (requested time) and `rem` (remaining time) pointers are
+///   converted from Wasm linear memory to host addresses using
+///   `sc_convert_buf`.
+/// - Unused arguments `arg5` and `arg6` are validated with `sc_unusedarg`.
+/// - The clock is read as the cage sees it, see `cage::timer::cage_clock_now`,
+///   so an absolute sleep on `CLOCK_REALTIME` ends at the cage's time, and a
+///   sleep on `CLOCK_PROCESS_CPUTIME_ID` lasts until the cage has used that
+///   much CPU time. Like on Linux, a thread cannot sleep on its own CPU clock.
+/// - The sleep is cut into chunks, between which the cage is checked for
+///   signals, see `clock_sleep_until`. A signal ends it with `EINTR`, and a
+///   relative sleep stores the time that was left in `rem`.
+/// - In deterministic mode the sleep only moves the virtual clock forward, see
+///   `cage::deterministic::virtual_sleep_until`.
+///
+/// ## Arguments:
+/// - `cageid`: Identifier of the calling Cage
+/// - `clockid_arg`: The clock against which the sleep interval is measured
+/// - `flags_arg`: 0 or `TIMER_ABSTIME`
+/// - `req_arg`: Address of the requested sleep interval (`timespec`)
+/// - `rem_arg`: Address of the remaining interval (`timespec`) if interrupted
 ///
 /// ## Returns:
 ///     - 0 on success.
-///     - -1 on error, with errno set to indicate the error.
-pub extern "C" fn chmod_syscall(
+///     - `-EINTR` if a signal interrupted the sleep.
+///     - `-EINVAL` for an invalid time or a clock that cannot be slept on,
+///       `-EFAULT` if `req` is NULL.
+pub extern "C" fn clock_nanosleep_syscall(
     cageid: u64,
-    path_arg: u64,
-    path_cageid: u64,
-    mode_arg: u64,
-    mode_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
-    arg4: u64,
-    arg4_cageid: u64,
+    clockid_arg: u64,
+    clockid_cageid: u64,
+    flags_arg: u64,
+    flags_cageid: u64,
+    req_arg: u64,
+    req_cageid: u64,
+    rem_arg: u64,
+    rem_cageid: u64,
     arg5: u64,
     arg5_cageid: u64,
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
     // Type conversion
-    let path = match sc_convert_path_to_host(path_arg, path_cageid, cageid) {
-        Ok(path) => path,
-        Err(e) => return syscall_error(e, "chmod", "path conversion failed"),
-    };
-    let mode = sc_convert_sysarg_to_u32(mode_arg, mode_cageid, cageid);
-
+    let clockid = sc_convert_sysarg_to_i32(clockid_arg, clockid_cageid, cageid);
+    let flags = sc_convert_sysarg_to_i32(flags_arg, flags_cageid, cageid);
+    let req = sc_convert_buf(req_arg, req_cageid, cageid);
+    let rem = sc_convert_buf(rem_arg, rem_cageid, cageid);
     // would sometimes check, sometimes be a no-op depending on the compiler settings
-    if !(sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
-        && sc_unusedarg(arg5, arg5_cageid)
-        && sc_unusedarg(arg6, arg6_cageid))
-    {
+    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "chmod_syscall"
+            "clock_nanosleep_syscall"
         );
     }
 
-    // Call the kernel chmod function
-    let ret = unsafe { libc::chmod(path.as_ptr(), mode) };
-
-    // Error handling
-    if ret < 0 {
-        let errno = get_errno();
-        return handle_errno(errno, "chmod");
+    if req.is_null() {
+        return syscall_error(Errno::EFAULT, "clock_nanosleep", "Bad address");
+    }
+    let req = unsafe { *(req as *const libc::timespec) };
+    if req.tv_sec < 0 || !(0..1_000_000_000).contains(&req.tv_nsec) {
+        return syscall_error(Errno::EINVAL, "clock_nanosleep", "Invalid time");
     }
+    // The CPU clock of the calling thread does not advance while it sleeps
+    if clockid == CLOCK_THREAD_CPUTIME_ID || (clockid < 0 && clockid & 4 != 0) {
+        return syscall_error(Errno::EINVAL, "clock_nanosleep", "Invalid clock");
+    }
+    let Some(clock_now) = cage_clock_now(cageid, clockid) else {
+        return syscall_error(Errno::EINVAL, "clock_nanosleep", "Invalid clock");
+    };
+    let value = Duration::new(req.tv_sec as u64, req.tv_nsec as u32);
+    let deadline = if flags & TIMER_ABSTIME != 0 {
+        value
+    } else {
+        clock_now.saturating_add(value)
+    };
 
-    ret
+    let slept = if is_deterministic() {
+        // The virtual clocks run in step with the monotonic one, and the virtual CPU time of a
+        // cage no faster
+        let deadline = virtual_now().saturating_add(deadline.saturating_sub(clock_now));
+        virtual_sleep_until(deadline, || signal_check_trigger(cageid))
+    } else {
+        clock_sleep_until(cageid, clockid, deadline)
+    };
+    match slept {
+        Ok(()) => 0,
+        Err(remaining) => {
+            if flags & TIMER_ABSTIME == 0 && !rem.is_null() {
+                unsafe {
+                    *(rem as *mut libc::timespec) = libc::timespec {
+                        tv_sec: remaining.as_secs() as i64,
+                        tv_nsec: remaining.subsec_nanos() as i64,
+                    };
+                }
+            }
+            syscall_error(Errno::EINTR, "clock_nanosleep", "interrupted")
+        }
+    }
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/fchmod.2.html
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/mprotect.2.html
 ///
-/// Linux `fchmod()` syscall changes the permissions of an open file referred to by file descriptor `fd`.
-/// Since we implement a file descriptor management subsystem (called `fdtables`), we first translate the
-/// virtual file descriptor to the corresponding kernel file descriptor, then convert the mode from cage
-/// memory before invoking the kernel's `libc::fchmod()` function.
+/// Linux `mprotect()` syscall changes the protection of memory pages. It sets the protection
+/// bits for memory pages in the range [addr, addr+len-1] to prot.
 ///
-/// ## Input:
-///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
+/// ## Arguments:
 ///     - cageid: current cage identifier
-///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
-///     - mode_arg: the new file permissions to be applied to the file
-///     - mode_cageid: cage identifier for the mode argument
-///     - arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
+///     - addr_arg: pointer to the start of the memory region to change protection for
+///     - addr_cageid: cage ID for addr argument validation
+///     - len_arg: length of the memory region in bytes
+///     - len_cageid: cage ID for len argument validation  
+///     - prot_arg: new protection flags (PROT_READ, PROT_WRITE, PROT_EXEC, PROT_NONE)
+///     - prot_cageid: cage ID for prot argument validation
+///     - arg4, arg4_cageid: unused argument and its cage ID
+///     - arg5, arg5_cageid: unused argument and its cage ID
+///     - arg6, arg6_cageid: unused argument and its cage ID
 ///
 /// ## Returns:
-///     - 0 on success.
-///     - -1 on error, with errno set to indicate the error.
-pub extern "C" fn fchmod_syscall(
+///     - 0 on success
+///     - -1 on error with appropriate errno set
+pub extern "C" fn mprotect_syscall(
     cageid: u64,
-    vfd_arg: u64,
-    vfd_cageid: u64,
-    mode_arg: u64,
-    mode_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
+    addr_arg: u64,
+    addr_cageid: u64,
+    len_arg: u64,
+    len_cageid: u64,
+    prot_arg: u64,
+    prot_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -3209,62 +4775,97 @@ pub extern "C" fn fchmod_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
-    // Return error
-    if kernel_fd < 0 {
-        return handle_errno(kernel_fd, "fchmod");
-    }
-
-    let mode = sc_convert_sysarg_to_u32(mode_arg, mode_cageid, cageid);
+    // Type conversion
+    let addr = addr_arg as *mut u8;
+    let len = sc_convert_sysarg_to_usize(len_arg, len_cageid, cageid);
+    let prot = sc_convert_sysarg_to_i32(prot_arg, prot_cageid, cageid);
 
-    if !(sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
+    // Validate unused args
+    if !(sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "fchmod_syscall"
+            "mprotect_syscall"
         );
     }
 
-    let ret = unsafe { libc::fchmod(kernel_fd, mode) };
+    // Validate protection flags
+    let valid_prot = PROT_READ | PROT_WRITE | PROT_EXEC | PROT_NONE;
+    if prot & !valid_prot != 0 {
+        return syscall_error(Errno::EINVAL, "mprotect", "Invalid protection flags");
+    }
+
+    // For security, we don't allow PROT_EXEC in lind-wasm
+    if prot & PROT_EXEC != 0 {
+        return syscall_error(Errno::EINVAL, "mprotect", "PROT_EXEC is not allowed");
+    }
+
+    // Round length up to page boundary (mprotect operates on whole pages)
+    let rounded_length = round_up_page(len as u64) as usize;
+
+    // Call the kernel mprotect
+    let ret = unsafe { libc::mprotect(addr as *mut c_void, rounded_length, prot) };
     if ret < 0 {
         let errno = get_errno();
-        return handle_errno(errno, "fchmod");
+        return handle_errno(errno, "mprotect");
+    }
+
+    // Update vmmap to reflect the new protection flags
+    // Skip if length is zero (no pages to update) — Linux treats len=0 as a no-op
+    if rounded_length > 0 {
+        let cage = get_cage(cageid).unwrap();
+        let mut vmmap = cage.vmmap.write();
+        let user_addr = vmmap.sys_to_user(addr as usize) as u32;
+        vmmap.change_prot(
+            user_addr >> PAGESHIFT,
+            (rounded_length >> PAGESHIFT) as u32,
+            prot,
+        );
+        drop(vmmap);
+
+        // Making PROT_NONE pages accessible commits them
+        if prot != PROT_NONE {
+            if let Err(e) = memory_budget_check(cage.cageid) {
+                return syscall_error(e, "mprotect", "memory budget exceeded");
+            }
+        }
     }
+
     ret
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/getcwd.2.html
-///
-/// `getcwd_syscall` retrieves the current working directory for the calling Cage.
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/ioctl.2.html
 ///
-/// Unlike directly calling `libc::getcwd`, this implementation uses the Cage's
-/// own `cwd` field maintained inside the Cage struct. Each Cage has its own
-/// logical working directory, which may differ from the host kernel's notion
-/// of the filesystem root. Because of this mismatch between the Cage's root
-/// and the kernel root, we cannot delegate to `libc::getcwd` and must instead
-/// return the Cage-specific `cwd` value.
+/// Linux `ioctl()` syscall manipulates the underlying device parameters of special files.
+/// In particular, many operating characteristics of character special files (e.g., terminals)
+/// may be controlled with ioctl() operations. The argument fd must be an open file descriptor.
 ///
-/// ## Input:
-///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
+/// ## Arguments:
 ///     - cageid: current cage identifier
-///     - buf_arg: pointer to a buffer where the current working directory path will be stored (user's perspective)
-///     - size_arg: the size of the buffer in bytes
-///     - arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
+///     - vfd_arg: virtual file descriptor (must be an open file descriptor)
+///     - vfd_cageid: cage ID for vfd argument validation
+///     - req_arg: device-dependent operation code
+///     - req_cageid: cage ID for req argument validation
+///     - ptrunion_arg: pointer to memory (untyped pointer, traditionally char *argp)
+///     - ptrunion_cageid: cage ID for ptrunion argument validation
+///     - arg4, arg4_cageid: unused argument and its cage ID
+///     - arg5, arg5_cageid: unused argument and its cage ID
+///     - arg6, arg6_cageid: unused argument and its cage ID
 ///
 /// ## Returns:
-///     - On success, returns 0 after copying the current working directory path to the buffer.
-///     - On error, returns -1 and errno is set to indicate the error.
-pub extern "C" fn getcwd_syscall(
+///     - Usually, on success zero is returned. A few ioctl() operations use the return value
+///       as an output parameter and return a nonnegative value on success.
+///     - On error, -1 is returned, and errno is set to indicate the error.
+pub extern "C" fn ioctl_syscall(
     cageid: u64,
-    buf_arg: u64,
-    buf_cageid: u64,
-    size_arg: u64,
-    size_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
+    vfd_arg: u64,
+    vfd_cageid: u64,
+    req_arg: u64,
+    req_cageid: u64,
+    ptrunion_arg: u64,
+    ptrunion_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -3272,62 +4873,98 @@ pub extern "C" fn getcwd_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    let buf = buf_arg as *mut u8;
-
-    let size = sc_convert_sysarg_to_usize(size_arg, size_cageid, cageid);
-    if size == 0 {
-        return syscall_error(Errno::EINVAL, "getcwd", "Size cannot be zero");
-    }
+    // Type conversion
+    let ptrunion = ptrunion_arg as *mut u8;
+    let req = sc_convert_sysarg_to_u32(req_arg, req_cageid, cageid);
 
-    if !(sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
+    // Validate unused args
+    if !(sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "getcwd_syscall"
+            "ioctl_syscall"
         );
     }
 
-    let cage = get_cage(cageid).unwrap();
-    let cwd_container = cage.cwd.read();
-    let path = cwd_container.to_str().unwrap();
-    // The required size includes the null terminator
-    let required_size = path.len() + 1;
-    if required_size > size as usize {
-        return syscall_error(Errno::ERANGE, "getcwd_syscall", "Invalid buffer size");
+    // handle FIOCLEX, set close_on_exec flag for the file descriptor
+    if req as u64 == FIOCLEX {
+        let ret = match fdtables::set_cloexec(cageid, vfd_arg, true) {
+            Ok(_) => 0,
+            Err(_) => syscall_error(Errno::EBADF, "ioctl", "Bad File Descriptor"),
+        };
+
+        return ret;
     }
-    unsafe {
-        ptr::copy(path.as_ptr(), buf, path.len());
-        *buf.add(path.len()) = 0;
+
+    let wrappedvfd = fdtables::translate_virtual_fd(cageid, vfd_arg);
+    if wrappedvfd.is_err() {
+        return syscall_error(Errno::EBADF, "ioctl", "Bad File Descriptor");
     }
 
-    // std::copy guarantees it copies exactly path.len() bytes.
-    let bytes_written: i32 = path.len().try_into().unwrap();
+    let vfd = wrappedvfd.unwrap();
 
-    bytes_written
+    // Like F_SETFL, FIOASYNC sets the O_ASYNC flag RawPOSIX keeps, see `crate::sigio`
+    if req == FIOASYNC {
+        if ptrunion.is_null() {
+            return syscall_error(Errno::EFAULT, "ioctl", "Invalid address");
+        }
+        sigio_set_async(&vfd, unsafe { *(ptrunion as *const i32) } != 0);
+        return 0;
+    }
+
+    // Device nodes and tmpfs files have no host fd, every request is answered in RawPOSIX
+    if let Some(dev) = devfs_fd_device(cageid, vfd_arg) {
+        return devfs_ioctl(cageid, vfd_arg, vfd.perfdinfo, dev, req, ptrunion);
+    }
+
+    if let Some(handle) = tmpfs_fd_handle(cageid, vfd_arg) {
+        return handle.ioctl(req, ptrunion);
+    }
+
+    // Besides FIOCLEX, we only support FIONBIO, FIOASYNC, and TIOCGWINSZ right now.
+    // Return error for unsupported requests.
+    if req != FIONBIO && req != TIOCGWINSZ {
+        lind_debug_panic("Lind unsupported ioctl request");
+    }
+
+    let ret = unsafe { libc::ioctl(vfd.underfd as i32, req as u64, ptrunion as *mut c_void) };
+
+    if ret < 0 {
+        let errno = get_errno();
+        return handle_errno(errno, "ioctl");
+    }
+    return ret;
 }
 
-/// Truncate a file to a specified length
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/flock.2.html
+///
+/// `Flock()` syscall applies or removes an advisory lock on an open file. We first translate the virtual file descriptor to the
+/// corresponding kernel file descriptor, then convert the operation flags from cage memory before invoking
+/// the kernel's `libc::flock()` function.
 ///
 /// ## Arguments:
+///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
 ///     - cageid: current cage identifier
-///     - path_arg: pointer to the pathname of the file to truncate
-///     - path_cageid: cage identifier for the path argument
-///     - length_arg: the new length to truncate the file to
-///     - length_cageid: cage identifier for the length argument
-///     - arg3, arg4, arg5, arg6: additional arguments which are expected to be unused
+///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
+///     - vfd_cageid: cage ID for vfd argument validation
+///     - op_arg: operation flags (LOCK_SH, LOCK_EX, LOCK_UN, optionally ORed with LOCK_NB)
+///     - op_cageid: cage ID for op argument validation
+///     - arg3, arg3_cageid: unused argument and its cage ID
+///     - arg4, arg4_cageid: unused argument and its cage ID
+///     - arg5, arg5_cageid: unused argument and its cage ID
+///     - arg6, arg6_cageid: unused argument and its cage ID
 ///
 /// ## Returns:
-///     - 0 on success.
-///     - -1 on error, with errno set to indicate the error.
-pub extern "C" fn truncate_syscall(
+///     - 0 on success
+///     - -1 on error, with errno set to indicate the error (EBADF, EINTR, EINVAL, ENOLCK, EWOULDBLOCK)
+pub extern "C" fn flock_syscall(
     cageid: u64,
-    path_arg: u64,
-    path_cageid: u64,
-    length_arg: u64,
-    length_cageid: u64,
+    vfd_arg: u64,
+    vfd_cageid: u64,
+    op_arg: u64,
+    op_cageid: u64,
     arg3: u64,
     arg3_cageid: u64,
     arg4: u64,
@@ -3337,6 +4974,14 @@ pub extern "C" fn truncate_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
+    let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
+    // Return error
+    if kernel_fd < 0 {
+        return handle_errno(kernel_fd, "flock");
+    }
+
+    let op = sc_convert_sysarg_to_i32(op_arg, op_cageid, cageid);
+
     // Validate unused arguments
     if !(sc_unusedarg(arg3, arg3_cageid)
         && sc_unusedarg(arg4, arg4_cageid)
@@ -3345,114 +4990,207 @@ pub extern "C" fn truncate_syscall(
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "truncate_syscall"
+            "flock_syscall"
         );
     }
 
-    // Type conversion
-    let path = match sc_convert_path_to_host(path_arg, path_cageid, cageid) {
-        Ok(path) => path,
-        Err(e) => return syscall_error(e, "truncate", "path conversion failed"),
-    };
-    let length = sc_convert_sysarg_to_i64(length_arg, length_cageid, cageid);
+    // Validate operation flags
+    let valid_ops = libc::LOCK_SH | libc::LOCK_EX | libc::LOCK_UN | libc::LOCK_NB;
+    if op & !valid_ops != 0 {
+        return syscall_error(Errno::EINVAL, "flock", "Invalid operation flags");
+    }
 
-    // Call libc truncate
-    let ret = unsafe { libc::truncate(path.as_ptr() as *const i8, length) };
+    // Ensure at least one primary operation is specified
+    let primary_ops = libc::LOCK_SH | libc::LOCK_EX | libc::LOCK_UN;
+    if op & primary_ops == 0 {
+        return syscall_error(Errno::EINVAL, "flock", "No primary operation specified");
+    }
 
-    if ret == -1 {
+    // tmpfs files are only visible to this process, so there is nobody to contend with
+    if tmpfs_fd_handle(vfd_cageid, vfd_arg).is_some() {
+        return 0;
+    }
+
+    let ret = interruptible(cageid, true, || unsafe { libc::flock(kernel_fd, op) });
+    if ret < 0 {
         let errno = get_errno();
-        return handle_errno(errno, "truncate");
+        return handle_errno(errno, "flock");
     }
 
-    0
+    ret
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/clock_nanosleep.2.html
+/// Linux reference: https://man7.org/linux/man-pages/man2/shmget.2.html
+///
+/// shmget checks whether a shared memory key already exists, and either returns the existing
+/// segment ID or creates a new segment (after validating flags and size) before registering
+/// it in the metadata tables.
 ///
-/// `nanosleep_time64_syscall` suspends execution of the calling Cage for
-/// the time interval specified in the requested `timespec` structure.
+/// 1. **Convert arguments**  
+///    - Convert `key_arg`, `size_arg`, and `shmflg_arg` into native types,
+///      while validating `arg4`–`arg6` are unused.
 ///
-/// ## Implementation Details:
-/// - The `req` (requested time) and `rem` (remaining time) pointers are
-///   converted from Wasm linear memory to host addresses using
-///   `sc_convert_buf`.
-/// - Unused arguments `arg5` and `arg6` are validated with `sc_unusedarg`.
-/// - The underlying `SYS_clock_nanosleep` syscall is invoked directly.  
-/// - On error, `errno` is retrieved and normalized through `handle_errno()`.
+/// 2. **Special case: IPC_PRIVATE**  
+///    - Return `ENOENT` since `IPC_PRIVATE` segments are not supported yet.
 ///
-/// ## Arguments:
-/// - `cageid`: Identifier of the calling Cage
-/// - `clockid_arg`: The clock against which the sleep interval is measured
-/// - `flags_arg`: Flags controlling sleep behavior
-/// - `req_arg`: Address of the requested sleep interval (`timespec`)
-/// - `rem_arg`: Address of the remaining interval (`timespec`) if interrupted
+/// 3. **Check if key exists**  
+///    - If the key already exists in `shmkeyidtable`:  
+///       - If both `IPC_CREAT` and `IPC_EXCL` are set → error `EEXIST`.  
+///       - Else return the existing `shmid`.
+///
+/// 4. **Key does not exist**  
+///    - If `IPC_CREAT` not set → error `ENOENT`.  
+///    - Validate `size` against `SHMMIN` and `SHMMAX`.  
+///    - Allocate new `shmid` via `new_keyid()`.  
+///    - Insert into `shmkeyidtable`.  
+///    - Create a new shared memory segment with owner `cageid`, default `uid/gid`,
+///      and mode = lowest 9 bits of `shmflg`.  
+///    - Insert segment into `shmtable`.
+///
+/// ## Arguments
+/// * `cageid`       – The ID of the calling cage, used for ownership and validation.
+/// * `key_arg`      – The key used to identify the shared memory segment (raw u64).
+/// * `key_cageid`   – The cage ID associated with `key_arg` for validation.
+/// * `size_arg`     – The size (in bytes) of the requested segment (raw u64).
+/// * `size_cageid`  – The cage ID associated with `size_arg`.
+/// * `shmflg_arg`   – Flags controlling creation, permissions, and behavior (raw u64).
+/// * `shmflg_cageid`– The cage ID associated with `shmflg_arg`.
 ///
 /// ## Returns:
-///     - 0 on success.
-///     - -1 on failure, with errno set appropriately.
-pub extern "C" fn nanosleep_time64_syscall(
-    cageid: u64,
-    clockid_arg: u64,
-    clockid_cageid: u64,
-    flags_arg: u64,
-    flags_cageid: u64,
-    req_arg: u64,
-    req_cageid: u64,
-    rem_arg: u64,
-    rem_cageid: u64,
+/// On success, it returns the segment ID; on failure, it returns an appropriate error code.
+pub extern "C" fn shmget_syscall(
+    cageid: u64,
+    key_arg: u64,
+    key_cageid: u64,
+    size_arg: u64,
+    size_cageid: u64,
+    shmflg_arg: u64,
+    shmflg_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
     arg5: u64,
     arg5_cageid: u64,
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    // Type conversion
-    let clockid = sc_convert_sysarg_to_u32(clockid_arg, clockid_cageid, cageid);
-    let flags = sc_convert_sysarg_to_i32(flags_arg, flags_cageid, cageid);
-    let req = sc_convert_buf(req_arg, req_cageid, cageid);
-    let rem = sc_convert_buf(rem_arg, rem_cageid, cageid);
-    // would sometimes check, sometimes be a no-op depending on the compiler settings
-    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
+    let key = sc_convert_sysarg_to_i32(key_arg, key_cageid, cageid);
+    let size = sc_convert_sysarg_to_usize(size_arg, size_cageid, cageid);
+    let shmflg = sc_convert_sysarg_to_i32(shmflg_arg, shmflg_cageid, cageid);
+    // Validate unused args
+    if !(sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "nanosleep_time64_syscall"
+            "shmget_syscall"
         );
     }
-    let ret = unsafe { syscall(SYS_clock_nanosleep, clockid, flags, req, rem) as i32 };
-    if ret < 0 {
-        let errno = get_errno();
-        return handle_errno(errno, "nanosleep");
+
+    if key == IPC_PRIVATE {
+        lind_debug_panic("shmget key IPC_PRIVATE is not allowed in Lind");
     }
-    ret
+    let shmid: i32;
+    let metadata = &SHM_METADATA;
+
+    // The size of the segment should be rounded up to a multiple of pages
+    let rounded_size = round_up_page(size as u64) as usize;
+
+    match metadata.shmkeyidtable.entry(key) {
+        Occupied(occupied) => {
+            if (IPC_CREAT | IPC_EXCL) == (shmflg & (IPC_CREAT | IPC_EXCL)) {
+                return syscall_error(
+                    Errno::EEXIST,
+                    "shmget",
+                    "key already exists and IPC_CREAT and IPC_EXCL were used",
+                );
+            }
+            shmid = *occupied.get();
+        }
+        Vacant(vacant) => {
+            if 0 == (shmflg & IPC_CREAT) {
+                return syscall_error(
+                    Errno::ENOENT,
+                    "shmget",
+                    "tried to use a key that did not exist, and IPC_CREAT was not specified",
+                );
+            }
+
+            if (rounded_size as u32) < SHMMIN || (rounded_size as u32) > SHMMAX {
+                return syscall_error(
+                    Errno::EINVAL,
+                    "shmget",
+                    "Size is less than SHMMIN or more than SHMMAX",
+                );
+            }
+
+            shmid = metadata.new_keyid();
+            vacant.insert(shmid);
+            let mode = (shmflg & 0x1FF) as u16; // mode is 9 least signficant bits of shmflag, even if we dont really do anything with them
+
+            let segment = new_shm_segment(
+                key,
+                rounded_size,
+                cageid as u32,
+                DEFAULT_UID,
+                DEFAULT_GID,
+                mode,
+            );
+            metadata.shmtable.insert(shmid, segment);
+        }
+    };
+    shmid // return the shmid
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/mprotect.2.html
+/// Linux reference: https://man7.org/linux/man-pages/man3/shmat.3p.html
 ///
-/// Linux `mprotect()` syscall changes the protection of memory pages. It sets the protection
-/// bits for memory pages in the range [addr, addr+len-1] to prot.
+/// Handles the shmat syscall by mapping shared memory segments into the cage's address space.
+/// This function manages the attachment of shared memory segments by updating the cage's vmmap
+/// and handling the raw shmat helpers.
 ///
-/// ## Arguments:
-///     - cageid: current cage identifier
-///     - addr_arg: pointer to the start of the memory region to change protection for
-///     - addr_cageid: cage ID for addr argument validation
-///     - len_arg: length of the memory region in bytes
-///     - len_cageid: cage ID for len argument validation  
-///     - prot_arg: new protection flags (PROT_READ, PROT_WRITE, PROT_EXEC, PROT_NONE)
-///     - prot_cageid: cage ID for prot argument validation
-///     - arg4, arg4_cageid: unused argument and its cage ID
-///     - arg5, arg5_cageid: unused argument and its cage ID
-///     - arg6, arg6_cageid: unused argument and its cage ID
+/// 1) **Parse & validate args**
+///    - Decode `shmid`, `shmaddr`, `shmflg`; ensure `arg4..arg6` are truly unused (panic if not).
+/// 2) **Resolve access mode**
+///    - If `SHM_RDONLY` set → `prot = PROT_READ`; otherwise `prot = PROT_READ|PROT_WRITE`.
+/// 3) **Lookup segment length**
+///    - `get_shm_length(shmid)` → error `EINVAL` if unknown segment.
+/// 4) **Validate alignment & sizes**
+///    - `shmaddr` must be page-aligned (error `EINVAL` if not).
+///    - Round segment length up to page size.
+/// 5) **Choose placement in vmmap**
+///    - If `shmaddr == 0` → `find_map_space(pages, align=1)`.
+///    - Else           → `find_map_space_with_hint(pages, align=1, hint=shmaddr)`.
+///    - No fit → error `ENOMEM`.
+/// 6) **Translate to system address**
+///    - Convert chosen user address to system address via `vmmap.user_to_sys`.
+/// 7) **Perform attach in the backend**
+///    - Call `shmat_helper(cageid, sysaddr, shmflg, shmid)`.
+///    - Must return the same user address; mismatch to panic.
+/// 8) **Record mapping**
+///    - Add a `vmmap` entry with `backing = SharedMemory(shmid)`,
+///      `prot` and `maxprot = prot`, length in pages, offset 0, `len` as filelen.
 ///
-/// ## Returns:
-///     - 0 on success
-///     - -1 on error with appropriate errno set
-pub extern "C" fn mprotect_syscall(
+/// # Arguments
+/// * `cageid` - The cage ID that is performing the shmat operation
+/// * `addr` - The requested address to attach the shared memory segment (can be null)
+/// * `prot` - The memory protection flags for the mapping
+/// * `shmflag` - Flags controlling the shared memory attachment behavior
+/// * `shmid` - The ID of the shared memory segment to attach
+///
+/// # Returns
+/// * `u32` - The address where the shared memory segment was attached, or an error code
+///
+/// # Errors
+/// * `EINVAL` - If the provided address is not page-aligned
+/// * `ENOMEM` - If there is insufficient memory to complete the attachment
+pub extern "C" fn shmat_syscall(
     cageid: u64,
-    addr_arg: u64,
-    addr_cageid: u64,
-    len_arg: u64,
-    len_cageid: u64,
-    prot_arg: u64,
-    prot_cageid: u64,
+    shmid_arg: u64,
+    shmid_cageid: u64,
+    shmaddr_arg: u64,
+    shmaddr_cageid: u64,
+    shmflg_arg: u64,
+    shmflg_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -3460,11 +5198,16 @@ pub extern "C" fn mprotect_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    // Type conversion
-    let addr = addr_arg as *mut u8;
-    let len = sc_convert_sysarg_to_usize(len_arg, len_cageid, cageid);
-    let prot = sc_convert_sysarg_to_i32(prot_arg, prot_cageid, cageid);
-
+    let shmid = sc_convert_sysarg_to_i32(shmid_arg, shmid_cageid, cageid);
+    let mut useraddr = {
+        if shmaddr_arg == 0 {
+            0 as u32
+        } else {
+            sc_convert_sysarg_to_u32(shmaddr_arg, shmaddr_cageid, cageid)
+        }
+    };
+    let shmflag = sc_convert_sysarg_to_i32(shmflg_arg, shmflg_cageid, cageid);
+    let mut prot = 0;
     // Validate unused args
     if !(sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
@@ -3472,77 +5215,155 @@ pub extern "C" fn mprotect_syscall(
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "mprotect_syscall"
+            "shmat_syscall"
         );
     }
 
-    // Validate protection flags
-    let valid_prot = PROT_READ | PROT_WRITE | PROT_EXEC | PROT_NONE;
-    if prot & !valid_prot != 0 {
-        return syscall_error(Errno::EINVAL, "mprotect", "Invalid protection flags");
+    // Get the cage reference.
+    let cage = get_cage(cageid).unwrap();
+
+    // If SHM_RDONLY is set in shmflag, then use read-only protection,
+    // otherwise default to read–write.
+    prot = if shmflag & SHM_RDONLY != 0 {
+        PROT_READ
+    } else {
+        PROT_READ | PROT_WRITE
+    };
+    let len = match get_shm_length(shmid) {
+        Some(l) => l,
+        None => return syscall_error(Errno::EINVAL, "shmat", "invalid shmid"),
+    };
+
+    // Check that the provided address is page aligned.
+    let rounded_addr = round_up_page(useraddr as u64);
+    if rounded_addr != useraddr as u64 {
+        return syscall_error(Errno::EINVAL, "shmat", "address is not aligned");
     }
 
-    // For security, we don't allow PROT_EXEC in lind-wasm
-    if prot & PROT_EXEC != 0 {
-        return syscall_error(Errno::EINVAL, "mprotect", "PROT_EXEC is not allowed");
+    // Round up the length to a multiple of the page size.
+    let rounded_length = round_up_page(len as u64);
+
+    // Initialize the user address from the provided address pointer.
+    // If addr is null (0), we need to allocate memory space from the virtual memory map (vmmap).
+    let mut vmmap = cage.vmmap.write();
+    let result;
+    if useraddr == 0 {
+        // Allocate a suitable space in the virtual memory map for the shared memory segment
+        // based on the rounded length of the segment.
+        result = vmmap.find_map_space((rounded_length >> PAGESHIFT) as u32, 1);
+    } else {
+        // Use the user-specified address as a hint to find an appropriate memory address
+        // for the shared memory segment.
+        result = vmmap.find_map_space_with_hint(
+            rounded_length as u32 >> PAGESHIFT,
+            1,
+            useraddr as u32 >> PAGESHIFT,
+        );
     }
+    // drop the write lock of vmmap to avoid deadlock
+    drop(vmmap);
 
-    // Round length up to page boundary (mprotect operates on whole pages)
-    let rounded_length = round_up_page(len as u64) as usize;
+    if result.is_none() {
+        // If no suitable memory space is found, return an error indicating insufficient memory.
+        return syscall_error(Errno::ENOMEM, "shmat", "no memory") as i32;
+    }
+    let space = result.unwrap();
+    // Update the user address to the start of the allocated memory space.
+    useraddr = (space.start() << PAGESHIFT) as u32;
 
-    // Call the kernel mprotect
-    let ret = unsafe { libc::mprotect(addr as *mut c_void, rounded_length, prot) };
-    if ret < 0 {
+    // Convert the user address into a system address.
+    // Read the virtual memory map to access the user address space.
+    let vmmap = cage.vmmap.read();
+    // Convert the user address to the corresponding system address for the shared memory segment.
+    let sysaddr = vmmap.user_to_sys(useraddr);
+    // Release the lock on the virtual memory map as we no longer need it.
+    drop(vmmap);
+
+    // Call the raw shmat helper to attach the shared memory segment.
+    let result = shmat_helper(cageid, sysaddr as *mut u8, shmflag, shmid);
+
+    // Check for error BEFORE sys_to_user conversion
+    if is_mmap_error(result) {
         let errno = get_errno();
-        return handle_errno(errno, "mprotect");
+        return handle_errno(errno, "shmat");
     }
 
-    // Update vmmap to reflect the new protection flags
-    // Skip if length is zero (no pages to update) — Linux treats len=0 as a no-op
-    if rounded_length > 0 {
-        let cage = get_cage(cageid).unwrap();
+    let vmmap = cage.vmmap.read();
+    let result = vmmap.sys_to_user(result);
+    drop(vmmap);
+
+    // If the syscall succeeded, update the vmmap entry.
+    if result as i32 >= 0 {
+        // Ensure the syscall attached the segment at the expected address.
+        if result as u32 != useraddr {
+            panic!("shmat did not attach at the expected address");
+        }
         let mut vmmap = cage.vmmap.write();
-        let user_addr = vmmap.sys_to_user(addr as usize) as u32;
-        vmmap.change_prot(
-            user_addr >> PAGESHIFT,
-            (rounded_length >> PAGESHIFT) as u32,
-            prot,
-        );
+        let backing = MemoryBackingType::SharedMemory(shmid as u64);
+        // Use the effective protection (prot) for both the current and maximum protection.
+        let maxprot = prot;
+        // Add a new vmmap entry for the shared memory segment.
+        // Since shared memory is not file-backed, there are no extra mapping flags
+        // or file offset parameters to consider; thus, we pass 0 for both.
+        vmmap
+            .add_entry_with_overwrite(
+                useraddr >> PAGESHIFT,
+                (rounded_length >> PAGESHIFT) as u32,
+                prot,
+                maxprot,
+                0, // No flags for shared memory mapping
+                backing,
+                0, // Offset is not applicable for shared memory
+                len as i64,
+                cageid,
+            )
+            .expect("shmat: failed to add vmmap entry");
+        drop(vmmap);
+
+        if let Err(e) = memory_budget_check(cage.cageid) {
+            return syscall_error(e, "shmat", "memory budget exceeded");
+        }
+    } else {
+        // If the syscall failed, propagate the error.
+        return result as i32;
     }
 
-    ret
+    useraddr as i32
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/ioctl.2.html
+/// Linux reference: https://man7.org/linux/man-pages/man3/shmdt.3p.html
 ///
-/// Linux `ioctl()` syscall manipulates the underlying device parameters of special files.
-/// In particular, many operating characteristics of character special files (e.g., terminals)
-/// may be controlled with ioctl() operations. The argument fd must be an open file descriptor.
+/// `shmdt_syscall`, interacting with the `vmmap` structure.
 ///
-/// ## Arguments:
-///     - cageid: current cage identifier
-///     - vfd_arg: virtual file descriptor (must be an open file descriptor)
-///     - vfd_cageid: cage ID for vfd argument validation
-///     - req_arg: device-dependent operation code
-///     - req_cageid: cage ID for req argument validation
-///     - ptrunion_arg: pointer to memory (untyped pointer, traditionally char *argp)
-///     - ptrunion_cageid: cage ID for ptrunion argument validation
-///     - arg4, arg4_cageid: unused argument and its cage ID
-///     - arg5, arg5_cageid: unused argument and its cage ID
-///     - arg6, arg6_cageid: unused argument and its cage ID
+/// This function processes the `shmdt_syscall` by updating the `vmmap` entries and managing
+/// the shared memory detachment operation. It performs address validation, converts user
+/// addresses to system addresses, and updates the virtual memory mappings accordingly.
 ///
-/// ## Returns:
-///     - Usually, on success zero is returned. A few ioctl() operations use the return value
-///       as an output parameter and return a nonnegative value on success.
-///     - On error, -1 is returned, and errno is set to indicate the error.
-pub extern "C" fn ioctl_syscall(
+/// 1) **Parse & validate args**
+///    - Decode `shmaddr`; ensure `arg2..arg6` are unused (panic if not).
+/// 2) **Validate alignment**
+///    - `shmaddr` must be page-aligned (error `EINVAL` if not).
+/// 3) **Translate to system address**
+///    - `vmmap.user_to_sys(shmaddr)` to get the underlying system pointer.
+/// 4) **Perform detach in the backend**
+///    - `shmdt_helper(cageid, sysaddr)` → returns detached length (bytes) or negative errno.
+/// 5) **Remove mapping from vmmap**
+///    - Remove `length >> PAGESHIFT` pages starting at `shmaddr >> PAGESHIFT`.
+///
+/// # Arguments
+/// * `cageid` - Identifier of the cage that calls the `shmdt`
+/// * `addr` - Starting address of the shared memory region to detach
+///
+/// # Returns
+/// * `i32` - 0 for success and negative errno for failure
+pub extern "C" fn shmdt_syscall(
     cageid: u64,
-    vfd_arg: u64,
-    vfd_cageid: u64,
-    req_arg: u64,
-    req_cageid: u64,
-    ptrunion_arg: u64,
-    ptrunion_cageid: u64,
+    shmaddr_arg: u64,
+    shmaddr_cageid: u64,
+    arg2: u64,
+    arg2_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -3550,82 +5371,94 @@ pub extern "C" fn ioctl_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    // Type conversion
-    let ptrunion = ptrunion_arg as *mut u8;
-    let req = sc_convert_sysarg_to_u32(req_arg, req_cageid, cageid);
-
-    // Validate unused args
-    if !(sc_unusedarg(arg4, arg4_cageid)
+    let useraddr = sc_convert_sysarg_to_u32(shmaddr_arg, shmaddr_cageid, cageid);
+    if !(sc_unusedarg(arg2, arg2_cageid)
+        && sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "ioctl_syscall"
+            "shmdt_syscall"
         );
     }
 
-    // handle FIOCLEX, set close_on_exec flag for the file descriptor
-    if req as u64 == FIOCLEX {
-        let ret = match fdtables::set_cloexec(cageid, vfd_arg, true) {
-            Ok(_) => 0,
-            Err(_) => syscall_error(Errno::EBADF, "ioctl", "Bad File Descriptor"),
-        };
+    // Retrieve the cage reference.
+    let cage = get_cage(cageid).unwrap();
 
-        return ret;
+    // Check that the provided address is aligned on a page boundary.
+    let rounded_addr = round_up_page(useraddr as u64) as usize;
+    if rounded_addr != useraddr as usize {
+        return syscall_error(Errno::EINVAL, "shmdt", "address is not aligned");
     }
 
-    // Besides FIOCLEX, we only support FIONBIO, FIOASYNC, and TIOCGWINSZ right now.
-    // Return error for unsupported requests.
-    if req != FIONBIO && req != FIOASYNC && req != TIOCGWINSZ {
-        lind_debug_panic("Lind unsupported ioctl request");
-    }
+    // Convert the user address into a system address using the vmmap.
+    let vmmap = cage.vmmap.read();
+    let sysaddr = vmmap.user_to_sys(rounded_addr as u32);
+    drop(vmmap);
 
-    let wrappedvfd = fdtables::translate_virtual_fd(cageid, vfd_arg);
-    if wrappedvfd.is_err() {
-        return syscall_error(Errno::EBADF, "ioctl", "Bad File Descriptor");
+    // Call shmdt_helper which returns length of the detached segment
+    let length = shmdt_helper(cageid, sysaddr as *mut u8);
+    if length < 0 {
+        return length;
     }
 
-    let vfd = wrappedvfd.unwrap();
-
-    let ret = unsafe { libc::ioctl(vfd.underfd as i32, req as u64, ptrunion as *mut c_void) };
+    // Remove the mapping from the vmmap.
+    // This call removes the range starting at the page-aligned user address,
+    // for the number of pages that cover the shared memory region.
+    let mut vmmap = cage.vmmap.write();
+    vmmap
+        .remove_entry(
+            rounded_addr as u32 >> PAGESHIFT,
+            (length as u32) >> PAGESHIFT,
+        )
+        .expect("shmdt: remove_entry failed");
 
-    if ret < 0 {
-        let errno = get_errno();
-        return handle_errno(errno, "ioctl");
-    }
-    return ret;
+    0
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/flock.2.html
+/// Linux reference: https://man7.org/linux/man-pages/man3/shmctl.3p.html
 ///
-/// `Flock()` syscall applies or removes an advisory lock on an open file. We first translate the virtual file descriptor to the
-/// corresponding kernel file descriptor, then convert the operation flags from cage memory before invoking
-/// the kernel's `libc::flock()` function.
+/// It converts and validates the `shmid`, `cmd`, and optional `buf` arguments, enforces that unused
+/// arguments are truly unused (panicking on unexpected values), and then applies
+/// the requested control operation: `IPC_STAT` copies the segment’s metadata
+/// (`shminfo`) into the caller-provided buffer, and `IPC_RMID` marks the segment
+/// for removal (setting `SHM_DEST`) and deletes it immediately if there are no
+/// attachments, also clearing the key to id mapping.
 ///
-/// ## Arguments:
-///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
-///     - cageid: current cage identifier
-///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
-///     - vfd_cageid: cage ID for vfd argument validation
-///     - op_arg: operation flags (LOCK_SH, LOCK_EX, LOCK_UN, optionally ORed with LOCK_NB)
-///     - op_cageid: cage ID for op argument validation
-///     - arg3, arg3_cageid: unused argument and its cage ID
-///     - arg4, arg4_cageid: unused argument and its cage ID
-///     - arg5, arg5_cageid: unused argument and its cage ID
-///     - arg6, arg6_cageid: unused argument and its cage ID
+/// 1) **Parse & validate args**
+///    - Decode `shmid`, `cmd`, and optional `buf` pointer; ensure `arg4..arg6` unused (panic if not).
+/// 2) **Locate segment**
+///    - Lookup `shmid` in `shmtable`; if not found → error `EINVAL`.
+/// 3) **Dispatch by `cmd`**
+///    - `IPC_STAT`: copy `segment.shminfo` into caller’s `*buf`.
+///    - `IPC_RMID`: mark for removal (`segment.rmid = true`, set `SHM_DEST` in mode).
+///        * If `shm_nattch == 0`, remove the segment immediately and clear the key→id mapping.
+///    - Otherwise: error `EINVAL` (unsupported command).
+///
+/// ## Arguments
+/// * `cageid`        – The ID of the calling cage, used for ownership and validation.
+/// * `shmid_arg`     – Shared memory segment ID (raw u64).
+/// * `shmid_cageid`  – Cage ID associated with `shmid_arg`, used for validation.
+/// * `cmd_arg`       – Control command (e.g., `IPC_STAT`, `IPC_RMID`) (raw u64).
+/// * `cmd_cageid`    – Cage ID associated with `cmd_arg`.
+/// * `buf_arg`       – Pointer to a buffer (struct shmid_ds) used for returning or
+///                     updating segment info, depending on the command.
+/// * `buf_cageid`    – Cage ID associated with `buf_arg`.
 ///
 /// ## Returns:
-///     - 0 on success
-///     - -1 on error, with errno set to indicate the error (EBADF, EINTR, EINVAL, ENOLCK, EWOULDBLOCK)
-pub extern "C" fn flock_syscall(
+/// On invalid identifiers or
+/// unsupported commands, it returns `-EINVAL` via `syscall_error`; on success,
+/// returns `0`.
+pub extern "C" fn shmctl_syscall(
     cageid: u64,
-    vfd_arg: u64,
-    vfd_cageid: u64,
-    op_arg: u64,
-    op_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
+    shmid_arg: u64,
+    shmid_cageid: u64,
+    cmd_arg: u64,
+    cmd_cageid: u64,
+    buf_arg: u64,
+    buf_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -3633,93 +5466,102 @@ pub extern "C" fn flock_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    let kernel_fd = convert_fd_to_host(vfd_arg, vfd_cageid, cageid);
-    // Return error
-    if kernel_fd < 0 {
-        return handle_errno(kernel_fd, "flock");
-    }
-
-    let op = sc_convert_sysarg_to_i32(op_arg, op_cageid, cageid);
+    let shmid = sc_convert_sysarg_to_i32(shmid_arg, shmid_cageid, cageid);
+    let cmd = sc_convert_sysarg_to_i32(cmd_arg, cmd_cageid, cageid);
+    let buf = sc_convert_addr_to_shmidstruct(buf_arg, buf_cageid, cageid).unwrap();
 
-    // Validate unused arguments
-    if !(sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
+    // Validate unused args
+    if !(sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "flock_syscall"
+            "shmctl_syscall"
         );
     }
 
-    // Validate operation flags
-    let valid_ops = libc::LOCK_SH | libc::LOCK_EX | libc::LOCK_UN | libc::LOCK_NB;
-    if op & !valid_ops != 0 {
-        return syscall_error(Errno::EINVAL, "flock", "Invalid operation flags");
-    }
-
-    // Ensure at least one primary operation is specified
-    let primary_ops = libc::LOCK_SH | libc::LOCK_EX | libc::LOCK_UN;
-    if op & primary_ops == 0 {
-        return syscall_error(Errno::EINVAL, "flock", "No primary operation specified");
-    }
+    let metadata = &SHM_METADATA;
 
-    let ret = unsafe { libc::flock(kernel_fd, op) };
-    if ret < 0 {
-        let errno = get_errno();
-        return handle_errno(errno, "flock");
+    if let Some(mut segment) = metadata.shmtable.get_mut(&shmid) {
+        match cmd {
+            IPC_STAT => {
+                *buf = segment.shminfo;
+            }
+            IPC_RMID => {
+                segment.rmid = true;
+                segment.shminfo.shm_perm.mode |= SHM_DEST as u16;
+                if segment.shminfo.shm_nattch == 0 {
+                    let key = segment.key;
+                    drop(segment);
+                    metadata.shmtable.remove(&shmid);
+                    metadata.shmkeyidtable.remove(&key);
+                }
+            }
+            _ => {
+                return syscall_error(
+                    Errno::EINVAL,
+                    "shmctl",
+                    "Arguments provided do not match implemented parameters",
+                );
+            }
+        }
+    } else {
+        return syscall_error(Errno::EINVAL, "shmctl", "Invalid identifier");
     }
 
-    ret
+    0 //shmctl has succeeded!
 }
 
-/// Linux reference: https://man7.org/linux/man-pages/man2/shmget.2.html
-///
-/// shmget checks whether a shared memory key already exists, and either returns the existing
-/// segment ID or creates a new segment (after validating flags and size) before registering
-/// it in the metadata tables.
+/// Linux reference: https://man7.org/linux/man-pages/man2/getrandom.2.html
 ///
-/// 1. **Convert arguments**  
-///    - Convert `key_arg`, `size_arg`, and `shmflg_arg` into native types,
-///      while validating `arg4`–`arg6` are unused.
+/// Implements the `getrandom(2)` syscall for a cage. This wrapper converts and
+/// validates all caller-provided arguments, resolves the user buffer pointer into
+/// a host pointer, enforces cage-ownership consistency, and then directly invokes
+/// the host kernel’s `SYS_getrandom` via `syscall()`. Any error from the host is
+/// converted into a cage-appropriate errno using `handle_errno`.
 ///
-/// 2. **Special case: IPC_PRIVATE**  
-///    - Return `ENOENT` since `IPC_PRIVATE` segments are not supported yet.
+/// 1) **Parse & validate args**
+///    - `buf_arg` is interpreted as a user pointer and converted to a host pointer
+///      using `sc_convert_uaddr_to_host`, ensuring it belongs to `cageid`.
+///    - `buflen_arg` and `flags_arg` are converted to 32-bit values via
+///      `sc_convert_sysarg_to_u32`, validating cage ownership and rejecting
+///      malformed arguments.
+///    - Unused arguments `arg4..arg6` must be zero; if not, they cause a panic
+///      (enforcing a strict syscall ABI).
 ///
-/// 3. **Check if key exists**  
-///    - If the key already exists in `shmkeyidtable`:  
-///       - If both `IPC_CREAT` and `IPC_EXCL` are set → error `EEXIST`.  
-///       - Else return the existing `shmid`.
+/// 2) **Invoke host syscall**
+///    - Calls `syscall(SYS_getrandom, buf, buflen, flags)` unsafely to request
+///      random bytes from the host kernel.
+///    - On negative return, retrieves `errno` using `get_errno()` and converts it
+///      to a standardized cage-side error with `handle_errno`.
 ///
-/// 4. **Key does not exist**  
-///    - If `IPC_CREAT` not set → error `ENOENT`.  
-///    - Validate `size` against `SHMMIN` and `SHMMAX`.  
-///    - Allocate new `shmid` via `new_keyid()`.  
-///    - Insert into `shmkeyidtable`.  
-///    - Create a new shared memory segment with owner `cageid`, default `uid/gid`,
-///      and mode = lowest 9 bits of `shmflg`.  
-///    - Insert segment into `shmtable`.
+/// 3) **Return value**
+///    - On success, returns the number of random bytes written (0 ≤ n ≤ buflen).
+///    - On failure, returns a negative errno (`-EINTR`, `-EAGAIN`, `-EINVAL`, etc.).
 ///
 /// ## Arguments
-/// * `cageid`       – The ID of the calling cage, used for ownership and validation.
-/// * `key_arg`      – The key used to identify the shared memory segment (raw u64).
-/// * `key_cageid`   – The cage ID associated with `key_arg` for validation.
-/// * `size_arg`     – The size (in bytes) of the requested segment (raw u64).
-/// * `size_cageid`  – The cage ID associated with `size_arg`.
-/// * `shmflg_arg`   – Flags controlling creation, permissions, and behavior (raw u64).
-/// * `shmflg_cageid`– The cage ID associated with `shmflg_arg`.
+/// * `cageid`            – Cage issuing the syscall; used to validate all arguments.
+/// * `buf_arg`           – Raw user pointer to the destination buffer.
+/// * `buf_arg_cageid`    – Cage ID associated with `buf_arg`.
+/// * `buflen_arg`        – Number of random bytes requested (raw u64).
+/// * `buflen_arg_cageid` – Cage ID associated with `buflen_arg`.
+/// * `flags_arg`         – `getrandom` flags (e.g., `GRND_NONBLOCK`) as raw u64.
+/// * `flags_arg_cageid`  – Cage ID associated with `flags_arg`.
+/// * `arg4..arg6`        – Unused placeholder arguments for syscall ABI; must be zero.
+/// * `arg4_cageid..arg6_cageid` – Cage IDs for unused arguments; checked but unused.
 ///
-/// ## Returns:
-/// On success, it returns the segment ID; on failure, it returns an appropriate error code.
-pub extern "C" fn shmget_syscall(
+/// ## Returns
+/// On success: number of bytes written (positive `i32`).  
+/// On failure: a negative errno from `handle_errno`.
+pub extern "C" fn getrandom_syscall(
     cageid: u64,
-    key_arg: u64,
-    key_cageid: u64,
-    size_arg: u64,
-    size_cageid: u64,
-    shmflg_arg: u64,
-    shmflg_cageid: u64,
+    buf_arg: u64,
+    buf_arg_cageid: u64,
+    buflen_arg: u64,
+    buflen_arg_cageid: u64,
+    flags_arg: u64,
+    flags_arg_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -3727,9 +5569,10 @@ pub extern "C" fn shmget_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    let key = sc_convert_sysarg_to_i32(key_arg, key_cageid, cageid);
-    let size = sc_convert_sysarg_to_usize(size_arg, size_cageid, cageid);
-    let shmflg = sc_convert_sysarg_to_i32(shmflg_arg, shmflg_cageid, cageid);
+    let buf = buf_arg;
+    let buflen = sc_convert_sysarg_to_u32(buflen_arg, buflen_arg_cageid, cageid);
+    let flags = sc_convert_sysarg_to_u32(flags_arg, flags_arg_cageid, cageid);
+
     // Validate unused args
     if !(sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
@@ -3737,114 +5580,292 @@ pub extern "C" fn shmget_syscall(
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "shmget_syscall"
+            "getrandom_syscall"
         );
     }
 
-    if key == IPC_PRIVATE {
-        lind_debug_panic("shmget key IPC_PRIVATE is not allowed in Lind");
+    // In deterministic mode the bytes come from the cage's seeded stream
+    if is_deterministic() {
+        if buf == 0 {
+            return syscall_error(Errno::EFAULT, "getrandom", "Bad address");
+        }
+        let len = buflen.min(i32::MAX as u32) as usize;
+        virtual_random(cageid, unsafe {
+            std::slice::from_raw_parts_mut(buf as *mut u8, len)
+        });
+        return len as i32;
     }
-    let shmid: i32;
-    let metadata = &SHM_METADATA;
 
-    // The size of the segment should be rounded up to a multiple of pages
-    let rounded_size = round_up_page(size as u64) as usize;
+    let ret = unsafe { getrandom(buf as *mut c_void, buflen.try_into().unwrap(), flags) };
+    if ret < 0 {
+        let errno = get_errno();
+        return handle_errno(errno, "getrandom");
+    }
 
-    match metadata.shmkeyidtable.entry(key) {
-        Occupied(occupied) => {
-            if (IPC_CREAT | IPC_EXCL) == (shmflg & (IPC_CREAT | IPC_EXCL)) {
-                return syscall_error(
-                    Errno::EEXIST,
-                    "shmget",
-                    "key already exists and IPC_CREAT and IPC_EXCL were used",
-                );
+    // convert isize to i32 safely, as ret shouldn't be larger than 32-bit
+    // due to buflen being u32
+    ret.try_into().unwrap()
+}
+
+/// The object behind a virtual fd, for the vectored and fd-to-fd copying syscalls below, which have
+/// a host primitive only for kernel-backed fds and fall back to RawPOSIX's own loops otherwise.
+enum FdBacking {
+    Kernel(i32),
+    Dev(u64),
+    Tmpfs(Arc<TmpfsHandle>),
+}
+
+/// Largest byte count a single call moves, as Linux caps reads and writes at `MAX_RW_COUNT`. It
+/// also keeps the result representable in our `i32` return value.
+const MAX_RW_COUNT: usize = 0x7fff_f000;
+
+/// Buffer size of the copy loop
+const COPY_CHUNK: usize = 64 * 1024;
+
+fn fd_backing(cageid: u64, vfd: u64) -> Result<FdBacking, Errno> {
+    if let Some(dev) = devfs_fd_device(cageid, vfd) {
+        return Ok(FdBacking::Dev(dev));
+    }
+    if let Some(handle) = tmpfs_fd_handle(cageid, vfd) {
+        return Ok(FdBacking::Tmpfs(handle));
+    }
+    match fdtables::translate_virtual_fd(cageid, vfd) {
+        Ok(entry) if entry.fdkind == FDKIND_KERNEL => Ok(FdBacking::Kernel(entry.underfd as i32)),
+        // namespace sockets carry their data over a host socket
+        Ok(entry) if entry.fdkind == FDKIND_NETNS => Ok(FdBacking::Kernel(entry.underfd as i32)),
+        // epoll instances have nothing to read or write
+        Ok(_) => Err(Errno::EINVAL),
+        Err(_) => Err(Errno::EBADF),
+    }
+}
+
+impl FdBacking {
+    /// `read()`, or `pread()` at `offset` if one is given. Devices ignore the offset.
+    fn read(&self, cageid: u64, buf: *mut u8, count: usize, offset: Option<i64>) -> i32 {
+        match (self, offset) {
+            (FdBacking::Dev(dev), _) => devfs_read(cageid, *dev, buf, count),
+            (FdBacking::Tmpfs(handle), None) => handle.read(buf, count),
+            (FdBacking::Tmpfs(handle), Some(offset)) => handle.pread(buf, count, offset),
+            (FdBacking::Kernel(fd), offset) => {
+                let ret = interruptible(cageid, true, || match offset {
+                    None => unsafe { libc::read(*fd, buf as *mut c_void, count) },
+                    Some(offset) => unsafe { libc::pread(*fd, buf as *mut c_void, count, offset) },
+                });
+                if ret < 0 {
+                    return handle_errno(get_errno(), "read");
+                }
+                ret as i32
             }
-            shmid = *occupied.get();
         }
-        Vacant(vacant) => {
-            if 0 == (shmflg & IPC_CREAT) {
-                return syscall_error(
-                    Errno::ENOENT,
-                    "shmget",
-                    "tried to use a key that did not exist, and IPC_CREAT was not specified",
-                );
+    }
+
+    /// `write()`, or `pwrite()` at `offset` if one is given. Devices ignore the offset.
+    fn write(&self, cageid: u64, buf: *const u8, count: usize, offset: Option<i64>) -> i32 {
+        match (self, offset) {
+            (FdBacking::Dev(dev), _) => devfs_write(*dev, buf, count),
+            (FdBacking::Tmpfs(handle), None) => handle.write(buf, count),
+            (FdBacking::Tmpfs(handle), Some(offset)) => handle.pwrite(buf, count, offset),
+            (FdBacking::Kernel(fd), offset) => {
+                let ret = interruptible(cageid, true, || match offset {
+                    None => unsafe { libc::write(*fd, buf as *const c_void, count) },
+                    Some(offset) => unsafe {
+                        libc::pwrite(*fd, buf as *const c_void, count, offset)
+                    },
+                });
+                if ret < 0 {
+                    return kernel_write_error(cageid, *fd, get_errno(), "write");
+                }
+                ret as i32
             }
+        }
+    }
 
-            if (rounded_size as u32) < SHMMIN || (rounded_size as u32) > SHMMAX {
-                return syscall_error(
-                    Errno::EINVAL,
-                    "shmget",
-                    "Size is less than SHMMIN or more than SHMMAX",
-                );
+    /// Move the file offset back by `count` bytes, for data that was read but could not be
+    /// written. Unseekable files (pipes, sockets, devices) lose that data, as they would with a
+    /// `read()` followed by a failed `write()`.
+    fn unread(&self, count: usize) {
+        match self {
+            FdBacking::Kernel(fd) => unsafe {
+                libc::lseek(*fd, -(count as i64), libc::SEEK_CUR);
+            },
+            FdBacking::Tmpfs(handle) => {
+                handle.lseek(-(count as i64), libc::SEEK_CUR);
             }
+            FdBacking::Dev(_) => {}
+        }
+    }
 
-            shmid = metadata.new_keyid();
-            vacant.insert(shmid);
-            let mode = (shmflg & 0x1FF) as u16; // mode is 9 least signficant bits of shmflag, even if we dont really do anything with them
+    /// Whether this is a pipe, which `splice()` requires on one side
+    fn is_pipe(&self) -> bool {
+        self.file_type() == Some(libc::S_IFIFO)
+    }
 
-            let segment = new_shm_segment(
-                key,
-                rounded_size,
-                cageid as u32,
-                DEFAULT_UID,
-                DEFAULT_GID,
-                mode,
-            );
-            metadata.shmtable.insert(shmid, segment);
+    /// Whether this is a regular file, which `copy_file_range()` requires on both sides
+    fn is_regular(&self) -> bool {
+        self.file_type() == Some(libc::S_IFREG)
+    }
+
+    fn file_type(&self) -> Option<u32> {
+        match self {
+            FdBacking::Kernel(fd) => {
+                let mut st: libc::stat = unsafe { std::mem::zeroed() };
+                if unsafe { libc::fstat(*fd, &mut st) } < 0 {
+                    return None;
+                }
+                Some(st.st_mode & libc::S_IFMT)
+            }
+            FdBacking::Tmpfs(handle) => {
+                let mut st = StatData::default();
+                handle.fstat(&mut st);
+                Some(st.st_mode & libc::S_IFMT)
+            }
+            FdBacking::Dev(_) => Some(libc::S_IFCHR),
         }
-    };
-    shmid // return the shmid
+    }
 }
 
-/// Linux reference: https://man7.org/linux/man-pages/man3/shmat.3p.html
-///
-/// Handles the shmat syscall by mapping shared memory segments into the cage's address space.
-/// This function manages the attachment of shared memory segments by updating the cage's vmmap
-/// and handling the raw shmat helpers.
-///
-/// 1) **Parse & validate args**
-///    - Decode `shmid`, `shmaddr`, `shmflg`; ensure `arg4..arg6` are truly unused (panic if not).
-/// 2) **Resolve access mode**
-///    - If `SHM_RDONLY` set → `prot = PROT_READ`; otherwise `prot = PROT_READ|PROT_WRITE`.
-/// 3) **Lookup segment length**
-///    - `get_shm_length(shmid)` → error `EINVAL` if unknown segment.
-/// 4) **Validate alignment & sizes**
-///    - `shmaddr` must be page-aligned (error `EINVAL` if not).
-///    - Round segment length up to page size.
-/// 5) **Choose placement in vmmap**
-///    - If `shmaddr == 0` → `find_map_space(pages, align=1)`.
-///    - Else           → `find_map_space_with_hint(pages, align=1, hint=shmaddr)`.
-///    - No fit → error `ENOMEM`.
-/// 6) **Translate to system address**
-///    - Convert chosen user address to system address via `vmmap.user_to_sys`.
-/// 7) **Perform attach in the backend**
-///    - Call `shmat_helper(cageid, sysaddr, shmflg, shmid)`.
-///    - Must return the same user address; mismatch to panic.
-/// 8) **Record mapping**
-///    - Add a `vmmap` entry with `backing = SharedMemory(shmid)`,
-///      `prot` and `maxprot = prot`, length in pages, offset 0, `len` as filelen.
+/// Error path of a host write to `kernel_fd`: like `write_syscall`, raise `SIGPIPE` before
+/// returning `EPIPE`.
+fn kernel_write_error(cageid: u64, kernel_fd: i32, errno: i32, syscall: &str) -> i32 {
+    sigpipe_check(cageid, kernel_fd, 0, handle_errno(errno, syscall))
+}
+
+/// Read into `iovs` one buffer at a time, stopping at the first short read. Used where the host's
+/// `readv()`/`preadv()` cannot be, i.e. for devices and tmpfs files.
+fn readv_fallback(cageid: u64, fd: &FdBacking, iovs: &[libc::iovec], offset: Option<i64>) -> i32 {
+    let mut total = 0usize;
+    for iov in iovs {
+        let count = iov.iov_len.min(MAX_RW_COUNT - total);
+        let ret = fd.read(
+            cageid,
+            iov.iov_base as *mut u8,
+            count,
+            offset.map(|offset| offset + total as i64),
+        );
+        if ret < 0 {
+            return if total > 0 { total as i32 } else { ret };
+        }
+        total += ret as usize;
+        if (ret as usize) < iov.iov_len || total == MAX_RW_COUNT {
+            break;
+        }
+    }
+    total as i32
+}
+
+/// Write `iovs` one buffer at a time, stopping at the first short write
+fn writev_fallback(cageid: u64, fd: &FdBacking, iovs: &[libc::iovec], offset: Option<i64>) -> i32 {
+    let mut total = 0usize;
+    for iov in iovs {
+        let count = iov.iov_len.min(MAX_RW_COUNT - total);
+        let ret = fd.write(
+            cageid,
+            iov.iov_base as *const u8,
+            count,
+            offset.map(|offset| offset + total as i64),
+        );
+        if ret < 0 {
+            return if total > 0 { total as i32 } else { ret };
+        }
+        total += ret as usize;
+        if (ret as usize) < iov.iov_len || total == MAX_RW_COUNT {
+            break;
+        }
+    }
+    total as i32
+}
+
+/// Copy up to `len` bytes from `src` to `dst` through a bounce buffer, for `sendfile()`,
+/// `splice()` and `copy_file_range()` when the host cannot do it without going through user
+/// space, i.e. when one side is a device or a tmpfs file.
 ///
-/// # Arguments
-/// * `cageid` - The cage ID that is performing the shmat operation
-/// * `addr` - The requested address to attach the shared memory segment (can be null)
-/// * `prot` - The memory protection flags for the mapping
-/// * `shmflag` - Flags controlling the shared memory attachment behavior
-/// * `shmid` - The ID of the shared memory segment to attach
+/// With an offset, reads or writes happen at that offset, which is advanced by the bytes copied;
+/// without one, the file's own offset is used. Like the host primitives, the copy stops at end of
+/// file, at a short write, or at an error, and an error is only reported if nothing was copied.
+fn copy_loop(
+    cageid: u64,
+    src: &FdBacking,
+    mut src_off: Option<&mut i64>,
+    dst: &FdBacking,
+    mut dst_off: Option<&mut i64>,
+    len: usize,
+) -> i32 {
+    let len = len.min(MAX_RW_COUNT);
+    let mut buf = vec![0u8; len.min(COPY_CHUNK)];
+    let mut total = 0usize;
+
+    while total < len {
+        let want = (len - total).min(buf.len());
+        let nread = src.read(cageid, buf.as_mut_ptr(), want, src_off.as_deref().copied());
+        if nread < 0 {
+            return if total > 0 { total as i32 } else { nread };
+        }
+        if nread == 0 {
+            break;
+        }
+        let nread = nread as usize;
+
+        let mut written = 0usize;
+        while written < nread {
+            let ret = dst.write(
+                cageid,
+                unsafe { buf.as_ptr().add(written) },
+                nread - written,
+                dst_off.as_deref().map(|off| *off + written as i64),
+            );
+            if ret <= 0 {
+                break;
+            }
+            written += ret as usize;
+        }
+
+        if let Some(off) = src_off.as_deref_mut() {
+            *off += written as i64;
+        } else if written < nread {
+            src.unread(nread - written);
+        }
+        if let Some(off) = dst_off.as_deref_mut() {
+            *off += written as i64;
+        }
+        total += written;
+
+        if written < nread {
+            // Report the write error if nothing made it through
+            if total == 0 {
+                return dst.write(cageid, buf.as_ptr(), nread, dst_off.as_deref().copied());
+            }
+            break;
+        }
+    }
+    total as i32
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/readv.2.html
 ///
-/// # Returns
-/// * `u32` - The address where the shared memory segment was attached, or an error code
+/// Linux `readv()` syscall performs scatter input: it reads from a file descriptor into several
+/// buffers. Unlike `writev_syscall`, which gets its iovecs translated by glibc, the guest iovec array
+/// is passed as is and translated with `sc_convert_iovec`. Kernel-backed fds use the host's
+/// `libc::readv()`; devices and tmpfs files are read one buffer at a time.
 ///
-/// # Errors
-/// * `EINVAL` - If the provided address is not page-aligned
-/// * `ENOMEM` - If there is insufficient memory to complete the attachment
-pub extern "C" fn shmat_syscall(
+/// ## Input:
+///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
+///     - cageid: current cage identifier
+///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
+///     - iov_arg: pointer to an array of iovec structures describing the buffers (user's perspective)
+///     - iovcnt_arg: number of iovec structures in the array
+///     - arg4, arg5, arg6: additional arguments which are expected to be unused
+///
+/// ## Returns:
+///     - On success, the number of bytes read is returned.
+///     - On error, -1 is returned and errno is set to indicate the error.
+pub extern "C" fn readv_syscall(
     cageid: u64,
-    shmid_arg: u64,
-    shmid_cageid: u64,
-    shmaddr_arg: u64,
-    shmaddr_cageid: u64,
-    shmflg_arg: u64,
-    shmflg_cageid: u64,
+    vfd_arg: u64,
+    vfd_cageid: u64,
+    iov_arg: u64,
+    iov_cageid: u64,
+    iovcnt_arg: u64,
+    iovcnt_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -3852,394 +5873,557 @@ pub extern "C" fn shmat_syscall(
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    let shmid = sc_convert_sysarg_to_i32(shmid_arg, shmid_cageid, cageid);
-    let mut useraddr = {
-        if shmaddr_arg == 0 {
-            0 as u32
-        } else {
-            sc_convert_sysarg_to_u32(shmaddr_arg, shmaddr_cageid, cageid)
-        }
-    };
-    let shmflag = sc_convert_sysarg_to_i32(shmflg_arg, shmflg_cageid, cageid);
-    let mut prot = 0;
-    // Validate unused args
+    let iovcnt = sc_convert_sysarg_to_i32(iovcnt_arg, iovcnt_cageid, cageid);
+
     if !(sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "shmat_syscall"
+            "readv_syscall"
         );
     }
 
-    // Get the cage reference.
-    let cage = get_cage(cageid).unwrap();
-
-    // If SHM_RDONLY is set in shmflag, then use read-only protection,
-    // otherwise default to read–write.
-    prot = if shmflag & SHM_RDONLY != 0 {
-        PROT_READ
-    } else {
-        PROT_READ | PROT_WRITE
+    let fd = match fd_backing(vfd_cageid, vfd_arg) {
+        Ok(fd) => fd,
+        Err(e) => return syscall_error(e, "readv", "invalid file descriptor"),
     };
-    let len = match get_shm_length(shmid) {
-        Some(l) => l,
-        None => return syscall_error(Errno::EINVAL, "shmat", "invalid shmid"),
+    let iovs = match sc_convert_iovec(iov_arg, iov_cageid, iovcnt, cageid) {
+        Ok(iovs) => iovs,
+        Err(e) => return syscall_error(e, "readv", "invalid iovec array"),
     };
 
-    // Check that the provided address is page aligned.
-    let rounded_addr = round_up_page(useraddr as u64);
-    if rounded_addr != useraddr as u64 {
-        return syscall_error(Errno::EINVAL, "shmat", "address is not aligned");
+    match fd {
+        FdBacking::Kernel(kernel_fd) => {
+            let ret = interruptible(cageid, true, || unsafe {
+                libc::readv(kernel_fd, iovs.as_ptr(), iovs.len() as i32)
+            });
+            if ret < 0 {
+                return handle_errno(get_errno(), "readv");
+            }
+            ret as i32
+        }
+        _ => readv_fallback(cageid, &fd, &iovs, None),
     }
+}
 
-    // Round up the length to a multiple of the page size.
-    let rounded_length = round_up_page(len as u64);
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/preadv.2.html
+///
+/// Linux `preadv()` syscall is `readv()` at a given file offset, leaving the file offset untouched.
+/// The iovec array is translated as in `readv_syscall`.
+///
+/// ## Input:
+///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
+///     - cageid: current cage identifier
+///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
+///     - iov_arg: pointer to an array of iovec structures describing the buffers (user's perspective)
+///     - iovcnt_arg: number of iovec structures in the array
+///     - offset_arg: file offset at which the input operation takes place
+///     - arg5, arg6: additional arguments which are expected to be unused
+///
+/// ## Returns:
+///     - On success, the number of bytes read is returned.
+///     - On error, -1 is returned and errno is set to indicate the error.
+pub extern "C" fn preadv_syscall(
+    cageid: u64,
+    vfd_arg: u64,
+    vfd_cageid: u64,
+    iov_arg: u64,
+    iov_cageid: u64,
+    iovcnt_arg: u64,
+    iovcnt_cageid: u64,
+    offset_arg: u64,
+    offset_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let iovcnt = sc_convert_sysarg_to_i32(iovcnt_arg, iovcnt_cageid, cageid);
+    let offset = sc_convert_sysarg_to_i64(offset_arg, offset_cageid, cageid);
 
-    // Initialize the user address from the provided address pointer.
-    // If addr is null (0), we need to allocate memory space from the virtual memory map (vmmap).
-    let mut vmmap = cage.vmmap.write();
-    let result;
-    if useraddr == 0 {
-        // Allocate a suitable space in the virtual memory map for the shared memory segment
-        // based on the rounded length of the segment.
-        result = vmmap.find_map_space((rounded_length >> PAGESHIFT) as u32, 1);
-    } else {
-        // Use the user-specified address as a hint to find an appropriate memory address
-        // for the shared memory segment.
-        result = vmmap.find_map_space_with_hint(
-            rounded_length as u32 >> PAGESHIFT,
-            1,
-            useraddr as u32 >> PAGESHIFT,
+    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "preadv_syscall"
         );
     }
-    // drop the write lock of vmmap to avoid deadlock
-    drop(vmmap);
 
-    if result.is_none() {
-        // If no suitable memory space is found, return an error indicating insufficient memory.
-        return syscall_error(Errno::ENOMEM, "shmat", "no memory") as i32;
+    let fd = match fd_backing(vfd_cageid, vfd_arg) {
+        Ok(fd) => fd,
+        Err(e) => return syscall_error(e, "preadv", "invalid file descriptor"),
+    };
+    let iovs = match sc_convert_iovec(iov_arg, iov_cageid, iovcnt, cageid) {
+        Ok(iovs) => iovs,
+        Err(e) => return syscall_error(e, "preadv", "invalid iovec array"),
+    };
+    if offset < 0 {
+        return syscall_error(Errno::EINVAL, "preadv", "negative offset");
     }
-    let space = result.unwrap();
-    // Update the user address to the start of the allocated memory space.
-    useraddr = (space.start() << PAGESHIFT) as u32;
 
-    // Convert the user address into a system address.
-    // Read the virtual memory map to access the user address space.
-    let vmmap = cage.vmmap.read();
-    // Convert the user address to the corresponding system address for the shared memory segment.
-    let sysaddr = vmmap.user_to_sys(useraddr);
-    // Release the lock on the virtual memory map as we no longer need it.
-    drop(vmmap);
+    match fd {
+        FdBacking::Kernel(kernel_fd) => {
+            let ret = unsafe { libc::preadv(kernel_fd, iovs.as_ptr(), iovs.len() as i32, offset) };
+            if ret < 0 {
+                return handle_errno(get_errno(), "preadv");
+            }
+            ret as i32
+        }
+        _ => readv_fallback(cageid, &fd, &iovs, Some(offset)),
+    }
+}
 
-    // Call the raw shmat helper to attach the shared memory segment.
-    let result = shmat_helper(cageid, sysaddr as *mut u8, shmflag, shmid);
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/pwritev.2.html
+///
+/// Linux `pwritev()` syscall is `writev()` at a given file offset, leaving the file offset
+/// untouched. The iovec array is translated as in `readv_syscall`.
+///
+/// ## Input:
+///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
+///     - cageid: current cage identifier
+///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
+///     - iov_arg: pointer to an array of iovec structures describing the buffers (user's perspective)
+///     - iovcnt_arg: number of iovec structures in the array
+///     - offset_arg: file offset at which the output operation takes place
+///     - arg5, arg6: additional arguments which are expected to be unused
+///
+/// ## Returns:
+///     - On success, the number of bytes written is returned.
+///     - On error, -1 is returned and errno is set to indicate the error.
+pub extern "C" fn pwritev_syscall(
+    cageid: u64,
+    vfd_arg: u64,
+    vfd_cageid: u64,
+    iov_arg: u64,
+    iov_cageid: u64,
+    iovcnt_arg: u64,
+    iovcnt_cageid: u64,
+    offset_arg: u64,
+    offset_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let iovcnt = sc_convert_sysarg_to_i32(iovcnt_arg, iovcnt_cageid, cageid);
+    let offset = sc_convert_sysarg_to_i64(offset_arg, offset_cageid, cageid);
 
-    // Check for error BEFORE sys_to_user conversion
-    if is_mmap_error(result) {
-        let errno = get_errno();
-        return handle_errno(errno, "shmat");
+    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "pwritev_syscall"
+        );
     }
 
-    let vmmap = cage.vmmap.read();
-    let result = vmmap.sys_to_user(result);
-    drop(vmmap);
+    let fd = match fd_backing(vfd_cageid, vfd_arg) {
+        Ok(fd) => fd,
+        Err(e) => return syscall_error(e, "pwritev", "invalid file descriptor"),
+    };
+    let iovs = match sc_convert_iovec(iov_arg, iov_cageid, iovcnt, cageid) {
+        Ok(iovs) => iovs,
+        Err(e) => return syscall_error(e, "pwritev", "invalid iovec array"),
+    };
+    if offset < 0 {
+        return syscall_error(Errno::EINVAL, "pwritev", "negative offset");
+    }
 
-    // If the syscall succeeded, update the vmmap entry.
-    if result as i32 >= 0 {
-        // Ensure the syscall attached the segment at the expected address.
-        if result as u32 != useraddr {
-            panic!("shmat did not attach at the expected address");
+    match fd {
+        FdBacking::Kernel(kernel_fd) => {
+            let ret = unsafe { libc::pwritev(kernel_fd, iovs.as_ptr(), iovs.len() as i32, offset) };
+            if ret < 0 {
+                return kernel_write_error(cageid, kernel_fd, get_errno(), "pwritev");
+            }
+            ret as i32
         }
-        let mut vmmap = cage.vmmap.write();
-        let backing = MemoryBackingType::SharedMemory(shmid as u64);
-        // Use the effective protection (prot) for both the current and maximum protection.
-        let maxprot = prot;
-        // Add a new vmmap entry for the shared memory segment.
-        // Since shared memory is not file-backed, there are no extra mapping flags
-        // or file offset parameters to consider; thus, we pass 0 for both.
-        vmmap
-            .add_entry_with_overwrite(
-                useraddr >> PAGESHIFT,
-                (rounded_length >> PAGESHIFT) as u32,
-                prot,
-                maxprot,
-                0, // No flags for shared memory mapping
-                backing,
-                0, // Offset is not applicable for shared memory
-                len as i64,
-                cageid,
-            )
-            .expect("shmat: failed to add vmmap entry");
-    } else {
-        // If the syscall failed, propagate the error.
-        return result as i32;
+        _ => writev_fallback(cageid, &fd, &iovs, Some(offset)),
     }
-
-    useraddr as i32
 }
 
-/// Linux reference: https://man7.org/linux/man-pages/man3/shmdt.3p.html
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/sendfile.2.html
 ///
-/// `shmdt_syscall`, interacting with the `vmmap` structure.
-///
-/// This function processes the `shmdt_syscall` by updating the `vmmap` entries and managing
-/// the shared memory detachment operation. It performs address validation, converts user
-/// addresses to system addresses, and updates the virtual memory mappings accordingly.
-///
-/// 1) **Parse & validate args**
-///    - Decode `shmaddr`; ensure `arg2..arg6` are unused (panic if not).
-/// 2) **Validate alignment**
-///    - `shmaddr` must be page-aligned (error `EINVAL` if not).
-/// 3) **Translate to system address**
-///    - `vmmap.user_to_sys(shmaddr)` to get the underlying system pointer.
-/// 4) **Perform detach in the backend**
-///    - `shmdt_helper(cageid, sysaddr)` → returns detached length (bytes) or negative errno.
-/// 5) **Remove mapping from vmmap**
-///    - Remove `length >> PAGESHIFT` pages starting at `shmaddr >> PAGESHIFT`.
+/// Linux `sendfile()` syscall copies data from one file descriptor to another within the kernel.
+/// When both virtual fds are kernel-backed this is the host's `libc::sendfile()`; otherwise the data
+/// goes through `copy_loop`.
 ///
-/// # Arguments
-/// * `cageid` - Identifier of the cage that calls the `shmdt`
-/// * `addr` - Starting address of the shared memory region to detach
+/// ## Input:
+///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
+///     - cageid: current cage identifier
+///     - out_fd_arg: the virtual file descriptor written to
+///     - in_fd_arg: the virtual file descriptor read from
+///     - offset_arg: optional pointer to the offset to read `in_fd` at, updated past the bytes read;
+///       if NULL, `in_fd`'s file offset is used and updated
+///     - count_arg: the number of bytes to copy
+///     - arg5, arg6: additional arguments which are expected to be unused
 ///
-/// # Returns
-/// * `i32` - 0 for success and negative errno for failure
-pub extern "C" fn shmdt_syscall(
+/// ## Returns:
+///     - On success, the number of bytes copied is returned.
+///     - On error, -1 is returned and errno is set to indicate the error.
+pub extern "C" fn sendfile_syscall(
     cageid: u64,
-    shmaddr_arg: u64,
-    shmaddr_cageid: u64,
-    arg2: u64,
-    arg2_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
-    arg4: u64,
-    arg4_cageid: u64,
+    out_fd_arg: u64,
+    out_fd_cageid: u64,
+    in_fd_arg: u64,
+    in_fd_cageid: u64,
+    offset_arg: u64,
+    offset_cageid: u64,
+    count_arg: u64,
+    count_cageid: u64,
     arg5: u64,
     arg5_cageid: u64,
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    let useraddr = sc_convert_sysarg_to_u32(shmaddr_arg, shmaddr_cageid, cageid);
-    if !(sc_unusedarg(arg2, arg2_cageid)
-        && sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
-        && sc_unusedarg(arg5, arg5_cageid)
-        && sc_unusedarg(arg6, arg6_cageid))
-    {
+    let count = sc_convert_sysarg_to_usize(count_arg, count_cageid, cageid);
+    let offset_nullity = sc_convert_arg_nullity(offset_arg, offset_cageid, cageid);
+
+    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "shmdt_syscall"
+            "sendfile_syscall"
         );
     }
 
-    // Retrieve the cage reference.
-    let cage = get_cage(cageid).unwrap();
+    let out_fd = match fd_backing(out_fd_cageid, out_fd_arg) {
+        Ok(fd) => fd,
+        Err(e) => return syscall_error(e, "sendfile", "invalid output file descriptor"),
+    };
+    let in_fd = match fd_backing(in_fd_cageid, in_fd_arg) {
+        Ok(fd) => fd,
+        Err(e) => return syscall_error(e, "sendfile", "invalid input file descriptor"),
+    };
+    let offset = if offset_nullity {
+        None
+    } else {
+        let offset = unsafe { &mut *(offset_arg as *mut i64) };
+        if *offset < 0 {
+            return syscall_error(Errno::EINVAL, "sendfile", "negative offset");
+        }
+        Some(offset)
+    };
 
-    // Check that the provided address is aligned on a page boundary.
-    let rounded_addr = round_up_page(useraddr as u64) as usize;
-    if rounded_addr != useraddr as usize {
-        return syscall_error(Errno::EINVAL, "shmdt", "address is not aligned");
+    if let (FdBacking::Kernel(out_kfd), FdBacking::Kernel(in_kfd)) = (&out_fd, &in_fd) {
+        let offset_ptr = offset.map_or(std::ptr::null_mut(), |offset| offset as *mut i64);
+        let ret = interruptible(cageid, true, || unsafe {
+            libc::sendfile(*out_kfd, *in_kfd, offset_ptr, count)
+        });
+        if ret < 0 {
+            return kernel_write_error(cageid, *out_kfd, get_errno(), "sendfile");
+        }
+        return ret as i32;
     }
 
-    // Convert the user address into a system address using the vmmap.
-    let vmmap = cage.vmmap.read();
-    let sysaddr = vmmap.user_to_sys(rounded_addr as u32);
-    drop(vmmap);
+    copy_loop(cageid, &in_fd, offset, &out_fd, None, count)
+}
 
-    // Call shmdt_helper which returns length of the detached segment
-    let length = shmdt_helper(cageid, sysaddr as *mut u8);
-    if length < 0 {
-        return length;
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/splice.2.html
+///
+/// Linux `splice()` syscall moves data between two file descriptors, one of which must be a pipe.
+/// When both virtual fds are kernel-backed this is the host's `libc::splice()`. Devices and tmpfs
+/// files are never pipes, so otherwise the other side must be a host pipe, and the data goes through
+/// `copy_loop`; `SPLICE_F_*` flags are only hints there and are ignored.
+///
+/// ## Input:
+///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
+///     - cageid: current cage identifier
+///     - fd_in_arg: the virtual file descriptor read from
+///     - off_in_arg: optional pointer to the offset to read at, updated; must be NULL for a pipe
+///     - fd_out_arg: the virtual file descriptor written to
+///     - off_out_arg: optional pointer to the offset to write at, updated; must be NULL for a pipe
+///     - len_arg: the number of bytes to move
+///     - flags_arg: `SPLICE_F_*` flags
+///
+/// ## Returns:
+///     - On success, the number of bytes moved is returned.
+///     - On error, -1 is returned and errno is set to indicate the error.
+pub extern "C" fn splice_syscall(
+    cageid: u64,
+    fd_in_arg: u64,
+    fd_in_cageid: u64,
+    off_in_arg: u64,
+    off_in_cageid: u64,
+    fd_out_arg: u64,
+    fd_out_cageid: u64,
+    off_out_arg: u64,
+    off_out_cageid: u64,
+    len_arg: u64,
+    len_cageid: u64,
+    flags_arg: u64,
+    flags_cageid: u64,
+) -> i32 {
+    let off_in_nullity = sc_convert_arg_nullity(off_in_arg, off_in_cageid, cageid);
+    let off_out_nullity = sc_convert_arg_nullity(off_out_arg, off_out_cageid, cageid);
+    let len = sc_convert_sysarg_to_usize(len_arg, len_cageid, cageid);
+    let flags = sc_convert_sysarg_to_u32(flags_arg, flags_cageid, cageid);
+
+    let fd_in = match fd_backing(fd_in_cageid, fd_in_arg) {
+        Ok(fd) => fd,
+        Err(e) => return syscall_error(e, "splice", "invalid input file descriptor"),
+    };
+    let fd_out = match fd_backing(fd_out_cageid, fd_out_arg) {
+        Ok(fd) => fd,
+        Err(e) => return syscall_error(e, "splice", "invalid output file descriptor"),
+    };
+    let off_in = (!off_in_nullity).then(|| unsafe { &mut *(off_in_arg as *mut i64) });
+    let off_out = (!off_out_nullity).then(|| unsafe { &mut *(off_out_arg as *mut i64) });
+
+    if let (FdBacking::Kernel(in_kfd), FdBacking::Kernel(out_kfd)) = (&fd_in, &fd_out) {
+        let off_in_ptr = off_in.map_or(std::ptr::null_mut(), |off| off as *mut i64);
+        let off_out_ptr = off_out.map_or(std::ptr::null_mut(), |off| off as *mut i64);
+        let ret = interruptible(cageid, true, || unsafe {
+            libc::splice(*in_kfd, off_in_ptr, *out_kfd, off_out_ptr, len, flags)
+        });
+        if ret < 0 {
+            return kernel_write_error(cageid, *out_kfd, get_errno(), "splice");
+        }
+        return ret as i32;
     }
 
-    // Remove the mapping from the vmmap.
-    // This call removes the range starting at the page-aligned user address,
-    // for the number of pages that cover the shared memory region.
-    let mut vmmap = cage.vmmap.write();
-    vmmap
-        .remove_entry(
-            rounded_addr as u32 >> PAGESHIFT,
-            (length as u32) >> PAGESHIFT,
-        )
-        .expect("shmdt: remove_entry failed");
+    let (in_pipe, out_pipe) = (fd_in.is_pipe(), fd_out.is_pipe());
+    if !(in_pipe || out_pipe) {
+        return syscall_error(Errno::EINVAL, "splice", "neither descriptor is a pipe");
+    }
+    if (in_pipe && off_in.is_some()) || (out_pipe && off_out.is_some()) {
+        return syscall_error(Errno::ESPIPE, "splice", "offset given for a pipe");
+    }
+    if off_in.as_deref().is_some_and(|off| *off < 0)
+        || off_out.as_deref().is_some_and(|off| *off < 0)
+    {
+        return syscall_error(Errno::EINVAL, "splice", "negative offset");
+    }
 
-    0
+    copy_loop(cageid, &fd_in, off_in, &fd_out, off_out, len)
 }
 
-/// Linux reference: https://man7.org/linux/man-pages/man3/shmctl.3p.html
-///
-/// It converts and validates the `shmid`, `cmd`, and optional `buf` arguments, enforces that unused
-/// arguments are truly unused (panicking on unexpected values), and then applies
-/// the requested control operation: `IPC_STAT` copies the segment’s metadata
-/// (`shminfo`) into the caller-provided buffer, and `IPC_RMID` marks the segment
-/// for removal (setting `SHM_DEST`) and deletes it immediately if there are no
-/// attachments, also clearing the key to id mapping.
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/tee.2.html
 ///
-/// 1) **Parse & validate args**
-///    - Decode `shmid`, `cmd`, and optional `buf` pointer; ensure `arg4..arg6` unused (panic if not).
-/// 2) **Locate segment**
-///    - Lookup `shmid` in `shmtable`; if not found → error `EINVAL`.
-/// 3) **Dispatch by `cmd`**
-///    - `IPC_STAT`: copy `segment.shminfo` into caller’s `*buf`.
-///    - `IPC_RMID`: mark for removal (`segment.rmid = true`, set `SHM_DEST` in mode).
-///        * If `shm_nattch == 0`, remove the segment immediately and clear the key→id mapping.
-///    - Otherwise: error `EINVAL` (unsupported command).
+/// Linux `tee()` syscall duplicates data from one pipe to another without consuming it. Only host
+/// pipes can do that, so this is the host's `libc::tee()` for kernel-backed fds, and `EINVAL` (not a
+/// pipe) for any other.
 ///
-/// ## Arguments
-/// * `cageid`        – The ID of the calling cage, used for ownership and validation.
-/// * `shmid_arg`     – Shared memory segment ID (raw u64).
-/// * `shmid_cageid`  – Cage ID associated with `shmid_arg`, used for validation.
-/// * `cmd_arg`       – Control command (e.g., `IPC_STAT`, `IPC_RMID`) (raw u64).
-/// * `cmd_cageid`    – Cage ID associated with `cmd_arg`.
-/// * `buf_arg`       – Pointer to a buffer (struct shmid_ds) used for returning or
-///                     updating segment info, depending on the command.
-/// * `buf_cageid`    – Cage ID associated with `buf_arg`.
+/// ## Input:
+///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
+///     - cageid: current cage identifier
+///     - fd_in_arg: the virtual file descriptor of the pipe read from
+///     - fd_out_arg: the virtual file descriptor of the pipe written to
+///     - len_arg: the number of bytes to duplicate
+///     - flags_arg: `SPLICE_F_*` flags
+///     - arg5, arg6: additional arguments which are expected to be unused
 ///
 /// ## Returns:
-/// On invalid identifiers or
-/// unsupported commands, it returns `-EINVAL` via `syscall_error`; on success,
-/// returns `0`.
-pub extern "C" fn shmctl_syscall(
+///     - On success, the number of bytes duplicated is returned.
+///     - On error, -1 is returned and errno is set to indicate the error.
+pub extern "C" fn tee_syscall(
     cageid: u64,
-    shmid_arg: u64,
-    shmid_cageid: u64,
-    cmd_arg: u64,
-    cmd_cageid: u64,
-    buf_arg: u64,
-    buf_cageid: u64,
-    arg4: u64,
-    arg4_cageid: u64,
+    fd_in_arg: u64,
+    fd_in_cageid: u64,
+    fd_out_arg: u64,
+    fd_out_cageid: u64,
+    len_arg: u64,
+    len_cageid: u64,
+    flags_arg: u64,
+    flags_cageid: u64,
     arg5: u64,
     arg5_cageid: u64,
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    let shmid = sc_convert_sysarg_to_i32(shmid_arg, shmid_cageid, cageid);
-    let cmd = sc_convert_sysarg_to_i32(cmd_arg, cmd_cageid, cageid);
-    let buf = sc_convert_addr_to_shmidstruct(buf_arg, buf_cageid, cageid).unwrap();
+    let len = sc_convert_sysarg_to_usize(len_arg, len_cageid, cageid);
+    let flags = sc_convert_sysarg_to_u32(flags_arg, flags_cageid, cageid);
 
-    // Validate unused args
-    if !(sc_unusedarg(arg4, arg4_cageid)
-        && sc_unusedarg(arg5, arg5_cageid)
-        && sc_unusedarg(arg6, arg6_cageid))
-    {
+    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "shmctl_syscall"
+            "tee_syscall"
         );
     }
 
-    let metadata = &SHM_METADATA;
+    let fd_in = match fd_backing(fd_in_cageid, fd_in_arg) {
+        Ok(fd) => fd,
+        Err(e) => return syscall_error(e, "tee", "invalid input file descriptor"),
+    };
+    let fd_out = match fd_backing(fd_out_cageid, fd_out_arg) {
+        Ok(fd) => fd,
+        Err(e) => return syscall_error(e, "tee", "invalid output file descriptor"),
+    };
 
-    if let Some(mut segment) = metadata.shmtable.get_mut(&shmid) {
-        match cmd {
-            IPC_STAT => {
-                *buf = segment.shminfo;
-            }
-            IPC_RMID => {
-                segment.rmid = true;
-                segment.shminfo.shm_perm.mode |= SHM_DEST as u16;
-                if segment.shminfo.shm_nattch == 0 {
-                    let key = segment.key;
-                    drop(segment);
-                    metadata.shmtable.remove(&shmid);
-                    metadata.shmkeyidtable.remove(&key);
-                }
-            }
-            _ => {
-                return syscall_error(
-                    Errno::EINVAL,
-                    "shmctl",
-                    "Arguments provided do not match implemented parameters",
-                );
+    match (fd_in, fd_out) {
+        (FdBacking::Kernel(in_kfd), FdBacking::Kernel(out_kfd)) => {
+            let ret = unsafe { libc::tee(in_kfd, out_kfd, len, flags) };
+            if ret < 0 {
+                return handle_errno(get_errno(), "tee");
             }
+            ret as i32
         }
-    } else {
-        return syscall_error(Errno::EINVAL, "shmctl", "Invalid identifier");
+        _ => syscall_error(Errno::EINVAL, "tee", "descriptor is not a pipe"),
     }
-
-    0 //shmctl has succeeded!
 }
 
-/// Linux reference: https://man7.org/linux/man-pages/man2/getrandom.2.html
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/copy_file_range.2.html
 ///
-/// Implements the `getrandom(2)` syscall for a cage. This wrapper converts and
-/// validates all caller-provided arguments, resolves the user buffer pointer into
-/// a host pointer, enforces cage-ownership consistency, and then directly invokes
-/// the host kernel’s `SYS_getrandom` via `syscall()`. Any error from the host is
-/// converted into a cage-appropriate errno using `handle_errno`.
+/// Linux `copy_file_range()` syscall copies a range of one regular file to another. When both
+/// virtual fds are kernel-backed this is the host's `libc::copy_file_range()`, falling back to
+/// `copy_loop` if the host cannot copy between the two filesystems; a tmpfs file on either side
+/// always goes through `copy_loop`.
 ///
-/// 1) **Parse & validate args**
-///    - `buf_arg` is interpreted as a user pointer and converted to a host pointer
-///      using `sc_convert_uaddr_to_host`, ensuring it belongs to `cageid`.
-///    - `buflen_arg` and `flags_arg` are converted to 32-bit values via
-///      `sc_convert_sysarg_to_u32`, validating cage ownership and rejecting
-///      malformed arguments.
-///    - Unused arguments `arg4..arg6` must be zero; if not, they cause a panic
-///      (enforcing a strict syscall ABI).
+/// ## Input:
+///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
+///     - cageid: current cage identifier
+///     - fd_in_arg: the virtual file descriptor read from
+///     - off_in_arg: optional pointer to the offset to read at, updated; if NULL the file offset is used
+///     - fd_out_arg: the virtual file descriptor written to
+///     - off_out_arg: optional pointer to the offset to write at, updated; if NULL the file offset is used
+///     - len_arg: the number of bytes to copy
+///     - flags_arg: must be 0
 ///
-/// 2) **Invoke host syscall**
-///    - Calls `syscall(SYS_getrandom, buf, buflen, flags)` unsafely to request
-///      random bytes from the host kernel.
-///    - On negative return, retrieves `errno` using `get_errno()` and converts it
-///      to a standardized cage-side error with `handle_errno`.
+/// ## Returns:
+///     - On success, the number of bytes copied is returned.
+///     - On error, -1 is returned and errno is set to indicate the error.
+pub extern "C" fn copy_file_range_syscall(
+    cageid: u64,
+    fd_in_arg: u64,
+    fd_in_cageid: u64,
+    off_in_arg: u64,
+    off_in_cageid: u64,
+    fd_out_arg: u64,
+    fd_out_cageid: u64,
+    off_out_arg: u64,
+    off_out_cageid: u64,
+    len_arg: u64,
+    len_cageid: u64,
+    flags_arg: u64,
+    flags_cageid: u64,
+) -> i32 {
+    let off_in_nullity = sc_convert_arg_nullity(off_in_arg, off_in_cageid, cageid);
+    let off_out_nullity = sc_convert_arg_nullity(off_out_arg, off_out_cageid, cageid);
+    let len = sc_convert_sysarg_to_usize(len_arg, len_cageid, cageid);
+    let flags = sc_convert_sysarg_to_u32(flags_arg, flags_cageid, cageid);
+
+    if flags != 0 {
+        return syscall_error(Errno::EINVAL, "copy_file_range", "flags must be 0");
+    }
+
+    let fd_in = match fd_backing(fd_in_cageid, fd_in_arg) {
+        Ok(fd) => fd,
+        Err(e) => return syscall_error(e, "copy_file_range", "invalid input file descriptor"),
+    };
+    let fd_out = match fd_backing(fd_out_cageid, fd_out_arg) {
+        Ok(fd) => fd,
+        Err(e) => return syscall_error(e, "copy_file_range", "invalid output file descriptor"),
+    };
+    let mut off_in = (!off_in_nullity).then(|| unsafe { &mut *(off_in_arg as *mut i64) });
+    let mut off_out = (!off_out_nullity).then(|| unsafe { &mut *(off_out_arg as *mut i64) });
+
+    if let (FdBacking::Kernel(in_kfd), FdBacking::Kernel(out_kfd)) = (&fd_in, &fd_out) {
+        let off_in_ptr = off_in
+            .as_deref_mut()
+            .map_or(std::ptr::null_mut(), |off| off as *mut i64);
+        let off_out_ptr = off_out
+            .as_deref_mut()
+            .map_or(std::ptr::null_mut(), |off| off as *mut i64);
+        let ret = unsafe {
+            libc::copy_file_range(*in_kfd, off_in_ptr, *out_kfd, off_out_ptr, len, flags)
+        };
+        if ret >= 0 {
+            return ret as i32;
+        }
+        let errno = get_errno();
+        if errno != Errno::EXDEV as i32 && errno != Errno::EOPNOTSUPP as i32 {
+            return handle_errno(errno, "copy_file_range");
+        }
+    }
+
+    if !(fd_in.is_regular() && fd_out.is_regular()) {
+        return syscall_error(Errno::EINVAL, "copy_file_range", "not a regular file");
+    }
+    if off_in.as_deref().is_some_and(|off| *off < 0)
+        || off_out.as_deref().is_some_and(|off| *off < 0)
+    {
+        return syscall_error(Errno::EINVAL, "copy_file_range", "negative offset");
+    }
+
+    copy_loop(cageid, &fd_in, off_in, &fd_out, off_out, len)
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/fallocate.2.html
 ///
-/// 3) **Return value**
-///    - On success, returns the number of random bytes written (0 ≤ n ≤ buflen).
-///    - On failure, returns a negative errno (`-EINTR`, `-EAGAIN`, `-EINVAL`, etc.).
+/// Linux `fallocate()` syscall manipulates the disk space allocated to a file. Kernel-backed fds use
+/// the host's `libc::fallocate()`. tmpfs files have no notion of allocated space beyond their size,
+/// so the default mode only extends the file and `FALLOC_FL_KEEP_SIZE` is a no-op; punching holes
+/// and zeroing ranges are not supported there. Devices return `ENODEV`, as on Linux.
 ///
-/// ## Arguments
-/// * `cageid`            – Cage issuing the syscall; used to validate all arguments.
-/// * `buf_arg`           – Raw user pointer to the destination buffer.
-/// * `buf_arg_cageid`    – Cage ID associated with `buf_arg`.
-/// * `buflen_arg`        – Number of random bytes requested (raw u64).
-/// * `buflen_arg_cageid` – Cage ID associated with `buflen_arg`.
-/// * `flags_arg`         – `getrandom` flags (e.g., `GRND_NONBLOCK`) as raw u64.
-/// * `flags_arg_cageid`  – Cage ID associated with `flags_arg`.
-/// * `arg4..arg6`        – Unused placeholder arguments for syscall ABI; must be zero.
-/// * `arg4_cageid..arg6_cageid` – Cage IDs for unused arguments; checked but unused.
+/// ## Input:
+///     This call will have one cageid indicating the current cage, and several regular arguments similar to Linux:
+///     - cageid: current cage identifier
+///     - vfd_arg: the virtual file descriptor from the RawPOSIX environment
+///     - mode_arg: 0 or a combination of `FALLOC_FL_*` flags
+///     - offset_arg: start of the range
+///     - len_arg: length of the range
+///     - arg5, arg6: additional arguments which are expected to be unused
 ///
-/// ## Returns
-/// On success: number of bytes written (positive `i32`).  
-/// On failure: a negative errno from `handle_errno`.
-pub extern "C" fn getrandom_syscall(
+/// ## Returns:
+///     - 0 on success.
+///     - -1 on error, with errno set to indicate the error.
+pub extern "C" fn fallocate_syscall(
     cageid: u64,
-    buf_arg: u64,
-    buf_arg_cageid: u64,
-    buflen_arg: u64,
-    buflen_arg_cageid: u64,
-    flags_arg: u64,
-    flags_arg_cageid: u64,
-    arg4: u64,
-    arg4_cageid: u64,
+    vfd_arg: u64,
+    vfd_cageid: u64,
+    mode_arg: u64,
+    mode_cageid: u64,
+    offset_arg: u64,
+    offset_cageid: u64,
+    len_arg: u64,
+    len_cageid: u64,
     arg5: u64,
     arg5_cageid: u64,
     arg6: u64,
     arg6_cageid: u64,
 ) -> i32 {
-    let buf = buf_arg;
-    let buflen = sc_convert_sysarg_to_u32(buflen_arg, buflen_arg_cageid, cageid);
-    let flags = sc_convert_sysarg_to_u32(flags_arg, flags_arg_cageid, cageid);
+    let mode = sc_convert_sysarg_to_i32(mode_arg, mode_cageid, cageid);
+    let offset = sc_convert_sysarg_to_i64(offset_arg, offset_cageid, cageid);
+    let len = sc_convert_sysarg_to_i64(len_arg, len_cageid, cageid);
 
-    // Validate unused args
-    if !(sc_unusedarg(arg4, arg4_cageid)
-        && sc_unusedarg(arg5, arg5_cageid)
-        && sc_unusedarg(arg6, arg6_cageid))
-    {
+    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
-            "getrandom_syscall"
+            "fallocate_syscall"
         );
     }
 
-    let ret = unsafe { getrandom(buf as *mut c_void, buflen.try_into().unwrap(), flags) };
-    if ret < 0 {
-        let errno = get_errno();
-        return handle_errno(errno, "getrandom");
+    if offset < 0 || len <= 0 {
+        return syscall_error(Errno::EINVAL, "fallocate", "invalid offset or length");
     }
+    let end = match offset.checked_add(len) {
+        Some(end) => end,
+        None => return syscall_error(Errno::EFBIG, "fallocate", "range too large"),
+    };
 
-    // convert isize to i32 safely, as ret shouldn't be larger than 32-bit
-    // due to buflen being u32
-    ret.try_into().unwrap()
+    match fd_backing(vfd_cageid, vfd_arg) {
+        Ok(FdBacking::Kernel(kernel_fd)) => {
+            let ret = unsafe { libc::fallocate(kernel_fd, mode, offset, len) };
+            if ret < 0 {
+                return handle_errno(get_errno(), "fallocate");
+            }
+            ret
+        }
+        Ok(FdBacking::Tmpfs(handle)) => match mode {
+            0 => {
+                let mut st = StatData::default();
+                handle.fstat(&mut st);
+                if end > st.st_size as i64 {
+                    handle.ftruncate(end)
+                } else {
+                    0
+                }
+            }
+            FALLOC_FL_KEEP_SIZE => 0,
+            _ => syscall_error(
+                Errno::EOPNOTSUPP,
+                "fallocate",
+                "mode not supported on tmpfs",
+            ),
+        },
+        Ok(FdBacking::Dev(_)) => syscall_error(Errno::ENODEV, "fallocate", "not a regular file"),
+        Err(e) => syscall_error(e, "fallocate", "invalid file descriptor"),
+    }
 }
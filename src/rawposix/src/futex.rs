@@ -0,0 +1,785 @@
+//! Futexes
+//!
+//! A futex word lives in the linear memory of a cage, which is mapped into the lind process, so
+//! the host's `futex()` could serve it directly. A wait there would know nothing of lind's
+//! signals, though, and grates could not follow it. RawPOSIX keeps a futex table of its own
+//! instead: a waiter queues up under the key of its word and sleeps on a condition variable of
+//! its own, until a wake, its timeout or a signal for its thread ends the wait.
+//!
+//! A word is keyed by its cage and host address, like the `mm` and address of a private futex
+//! on Linux. A shared futex (one without `FUTEX_PRIVATE_FLAG`) in shared memory is keyed by what
+//! backs the memory and its offset in it instead, so that cages which have the memory at
+//! different host addresses meet on the same word: an attached System V shared memory segment,
+//! the file of a `MAP_SHARED` file mapping, or the `MAP_SHARED` anonymous mapping that a cage
+//! and the cages forked from it share.
+//!
+//! As in the futex hash of Linux, the queues are spread over a fixed number of buckets by the
+//! hash of their key, each with a lock of its own, so that ops on unrelated words do not
+//! contend. An op on two words locks both buckets, lower index first. A waiter sleeps holding
+//! none of them, on a lock and condition variable of its own, as a requeue may move it to
+//! another bucket meanwhile.
+//!
+//! A wait ends with `EINTR` once its thread is picked to handle a signal, whether the signal
+//! was sent to the thread or to the whole cage, and when the cage kills its threads.
+//! `cage::signal` reports both through `futex_signal_wakeup`. A wait without a timeout fails
+//! with `ERESTARTSYS` instead if the handler has `SA_RESTART`, so that it is made again once
+//! the handler returns, see `crate::interrupt`.
+//!
+//! The priority-inheritance ops keep the protocol of the futex word: the owner's thread id in
+//! `FUTEX_TID_MASK`, `FUTEX_WAITERS` while anyone waits, and the lock handed straight to the
+//! first waiter on unlock. Lind has no thread priorities, so there is nothing to inherit. Taking
+//! a PI lock is not interrupted by signals, as Linux restarts it after the handler.
+//!
+//! Timeouts are read off the clocks of the cage, see `cage::timer::cage_clock_now`. In
+//! deterministic mode a timed wait that nobody wakes moves the virtual clock on to its deadline.
+
+use crate::interrupt::interrupted;
+use cage::deterministic::{is_deterministic, virtual_now, virtual_sleep_until};
+use cage::memory::vmmap::{MemoryBackingType, VmmapOps};
+use cage::timer::cage_clock_now;
+use cage::{
+    current_cage_thread, get_cage, search_for_addr_in_region, signal_check_trigger,
+    thread_check_killed, Cage,
+};
+use lazy_static::lazy_static;
+use parking_lot::{Condvar, Mutex, MutexGuard};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use sysdefs::constants::err_const::{handle_errno, syscall_error, Errno};
+use sysdefs::constants::fs_const::PAGESHIFT;
+use sysdefs::constants::sys_const::{
+    FUTEX_BITSET_MATCH_ANY, FUTEX_CLOCK_REALTIME, FUTEX_CMD_MASK, FUTEX_CMP_REQUEUE,
+    FUTEX_CMP_REQUEUE_PI, FUTEX_LOCK_PI, FUTEX_LOCK_PI2, FUTEX_OP_ADD, FUTEX_OP_ANDN,
+    FUTEX_OP_CMP_EQ, FUTEX_OP_CMP_GE, FUTEX_OP_CMP_GT, FUTEX_OP_CMP_LE, FUTEX_OP_CMP_LT,
+    FUTEX_OP_CMP_NE, FUTEX_OP_OPARG_SHIFT, FUTEX_OP_OR, FUTEX_OP_SET, FUTEX_OP_XOR,
+    FUTEX_OWNER_DIED, FUTEX_PRIVATE_FLAG, FUTEX_REQUEUE, FUTEX_TID_MASK, FUTEX_TRYLOCK_PI,
+    FUTEX_UNLOCK_PI, FUTEX_WAIT, FUTEX_WAITERS, FUTEX_WAIT_BITSET, FUTEX_WAIT_REQUEUE_PI,
+    FUTEX_WAKE, FUTEX_WAKE_BITSET, FUTEX_WAKE_OP,
+};
+
+/// How long a timed wait sleeps on the host at most before it reads its clock again, as the
+/// realtime offset or the virtual clock of the cage may have moved
+const WAIT_CHUNK: Duration = Duration::from_millis(100);
+
+/// Number of buckets of the futex table
+const FUTEX_BUCKETS: usize = 256;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum FutexKey {
+    /// A word of a cage, by its cage id and host address
+    Private(u64, u64),
+    /// A word in a System V shared memory segment, by the shmid and the offset in the segment
+    Shared(i32, u64),
+    /// A word in `MAP_SHARED` anonymous memory, by the id of the mapping and the offset in it
+    Anonymous(u64, u64),
+    /// A word in a `MAP_SHARED` file mapping, by the device and inode of the file and the offset
+    /// in the file
+    File(u64, u64, u64),
+}
+
+/// A deadline on clock `.0` of the cage
+type Deadline = (libc::clockid_t, Duration);
+
+struct Waiter {
+    cageid: u64,
+    /// The thread of the cage that waits, if it is one of the cage's threads
+    threadid: Option<i32>,
+    /// The bitset of `FUTEX_WAIT_BITSET`, which a wake has to share a bit with
+    bitset: u32,
+    /// The PI futex a `FUTEX_WAIT_REQUEUE_PI` waiter expects to be requeued to
+    requeue_pi: Option<FutexKey>,
+    /// The key the waiter is queued under, which a requeue changes
+    key: Mutex<FutexKey>,
+    /// Whether it waits for a PI lock, which it owns once it is woken
+    pi: AtomicBool,
+    /// Set by the wake that takes the waiter off the table, which `cond` waits on
+    woken: Mutex<bool>,
+    cond: Condvar,
+}
+
+impl Waiter {
+    fn new(
+        cageid: u64,
+        threadid: Option<i32>,
+        key: FutexKey,
+        bitset: u32,
+        pi: bool,
+        requeue_pi: Option<FutexKey>,
+    ) -> Arc<Self> {
+        Arc::new(Waiter {
+            cageid,
+            threadid,
+            bitset,
+            requeue_pi,
+            key: Mutex::new(key),
+            pi: AtomicBool::new(pi),
+            woken: Mutex::new(false),
+            cond: Condvar::new(),
+        })
+    }
+
+    /// The thread id a PI lock handed to the waiter is stored as
+    fn tid(&self) -> u32 {
+        self.threadid.unwrap_or_default() as u32 & FUTEX_TID_MASK
+    }
+
+    fn wake(&self) {
+        *self.woken.lock() = true;
+        self.cond.notify_one();
+    }
+
+    /// Have the waiter check whether it was interrupted
+    fn notify(&self) {
+        let _woken = self.woken.lock();
+        self.cond.notify_one();
+    }
+
+    /// Whether the thread of the waiter was killed, or has a signal to handle. Only the former
+    /// ends a wait for a PI lock.
+    fn interrupted(&self) -> bool {
+        if get_cage(self.cageid).is_none() {
+            return true;
+        }
+        if self.pi.load(Ordering::Relaxed) {
+            return self
+                .threadid
+                .is_some_and(|threadid| thread_check_killed(self.cageid, threadid as u64));
+        }
+        // the wait runs on the thread of the waiter, whose epoch this checks
+        signal_check_trigger(self.cageid)
+    }
+}
+
+/// The queues of a bucket, by key
+type Futexes = HashMap<FutexKey, VecDeque<Arc<Waiter>>>;
+
+lazy_static! {
+    static ref FUTEXES: Vec<Mutex<Futexes>> = (0..FUTEX_BUCKETS)
+        .map(|_| Mutex::new(HashMap::new()))
+        .collect();
+}
+
+fn bucket_index(key: FutexKey) -> usize {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish() as usize % FUTEX_BUCKETS
+}
+
+/// The locked bucket of `key`
+fn lock_bucket(key: FutexKey) -> MutexGuard<'static, Futexes> {
+    FUTEXES[bucket_index(key)].lock()
+}
+
+/// The locked buckets of two keys, for the ops on two words
+struct Buckets {
+    first: (usize, MutexGuard<'static, Futexes>),
+    /// `None` if both keys are in the same bucket
+    second: Option<(usize, MutexGuard<'static, Futexes>)>,
+}
+
+impl Buckets {
+    /// Lock the buckets of `key` and `key2`, lower index first, so that two ops on the same
+    /// pair of buckets cannot deadlock
+    fn lock(key: FutexKey, key2: FutexKey) -> Self {
+        let (a, b) = (bucket_index(key), bucket_index(key2));
+        let (low, high) = (a.min(b), a.max(b));
+        let first = (low, FUTEXES[low].lock());
+        let second = (high != low).then(|| (high, FUTEXES[high].lock()));
+        Buckets { first, second }
+    }
+
+    /// The queues of the bucket of `key`, which is one of the two locked
+    fn of(&mut self, key: FutexKey) -> &mut Futexes {
+        let index = bucket_index(key);
+        match &mut self.second {
+            Some((second, futexes)) if *second == index => futexes,
+            _ => &mut self.first.1,
+        }
+    }
+}
+
+/// The futex word at host address `uaddr`, which has been checked to be aligned
+fn futex_word(uaddr: u64) -> &'static AtomicU32 {
+    // SAFETY: the word is in the linear memory of the cage, which stays mapped for as long as
+    // a thread of the cage can call futex()
+    unsafe { &*(uaddr as *const AtomicU32) }
+}
+
+fn futex_key(cageid: u64, uaddr: u64, private: bool) -> FutexKey {
+    if !private {
+        if let Some(cage) = get_cage(cageid) {
+            if let Some((base, shmid)) = search_for_addr_in_region(&cage.rev_shm.lock(), uaddr) {
+                return FutexKey::Shared(shmid, uaddr - base);
+            }
+            if let Some(key) = mapping_key(&cage, uaddr) {
+                return key;
+            }
+        }
+    }
+    FutexKey::Private(cageid, uaddr)
+}
+
+/// The key of a word at host address `uaddr` in `MAP_SHARED` memory from `mmap()`, if it is in
+/// such memory of the cage
+fn mapping_key(cage: &Cage, uaddr: u64) -> Option<FutexKey> {
+    let vmmap = cage.vmmap.read();
+    let useraddr = vmmap.sys_to_user(uaddr as usize);
+    let entry = vmmap.find_page(useraddr >> PAGESHIFT)?;
+    match entry.backing {
+        MemoryBackingType::SharedAnonymous(id, start) => {
+            let offset = useraddr.checked_sub(start)?;
+            Some(FutexKey::Anonymous(id, offset as u64))
+        }
+        MemoryBackingType::SharedFile(_, dev, ino, origin) => {
+            let offset = u64::try_from(useraddr as i64 - origin).ok()?;
+            Some(FutexKey::File(dev, ino, offset))
+        }
+        _ => None,
+    }
+}
+
+fn check_uaddr(uaddr: u64) -> Result<(), Errno> {
+    if uaddr == 0 {
+        return Err(Errno::EFAULT);
+    }
+    if uaddr % 4 != 0 {
+        return Err(Errno::EINVAL);
+    }
+    Ok(())
+}
+
+/// The deadline of the `struct timespec` at host address `timeout` on clock `clockid`, which
+/// is relative to now unless `absolute`. `None` if there is no timeout.
+fn deadline(
+    cageid: u64,
+    timeout: u64,
+    clockid: libc::clockid_t,
+    absolute: bool,
+) -> Result<Option<Deadline>, Errno> {
+    if timeout == 0 {
+        return Ok(None);
+    }
+    let ts = unsafe { *(timeout as *const libc::timespec) };
+    if ts.tv_sec < 0 || !(0..1_000_000_000).contains(&ts.tv_nsec) {
+        return Err(Errno::EINVAL);
+    }
+    let time = Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32);
+    if absolute {
+        return Ok(Some((clockid, time)));
+    }
+    let now = cage_clock_now(cageid, clockid).unwrap_or_default();
+    Ok(Some((clockid, now.saturating_add(time))))
+}
+
+/// Take `waiter` off the table after its wait ended without a wake. A requeue may move it to
+/// another bucket until that bucket is locked, so its key is checked again once it is.
+///
+/// ## Returns:
+/// - `true` if the waiter was taken off, `false` if a wake took it off first
+fn dequeue(waiter: &Arc<Waiter>) -> bool {
+    loop {
+        let key = *waiter.key.lock();
+        let mut futexes = lock_bucket(key);
+        if *waiter.key.lock() != key {
+            continue;
+        }
+        let Some(queue) = futexes.get_mut(&key) else {
+            return false;
+        };
+        let queued = queue.len();
+        queue.retain(|w| !Arc::ptr_eq(w, waiter));
+        let removed = queue.len() != queued;
+        if queue.is_empty() {
+            futexes.remove(&key);
+        }
+        return removed;
+    }
+}
+
+/// Sleep until `waiter` is woken, its deadline passes or its thread is interrupted. The
+/// waiter is off the table again when this returns. A wake that comes before the waiter is
+/// taken off wins over the timeout or the signal, as on Linux.
+fn sleep(waiter: &Arc<Waiter>, deadline: Option<Deadline>) -> Result<(), Errno> {
+    let mut woken = waiter.woken.lock();
+    let ended = loop {
+        if *woken {
+            return Ok(());
+        }
+        if waiter.interrupted() {
+            break Errno::EINTR;
+        }
+        let Some((clockid, at)) = deadline else {
+            waiter.cond.wait(&mut woken);
+            continue;
+        };
+        let now = cage_clock_now(waiter.cageid, clockid).unwrap_or(at);
+        if now >= at {
+            break Errno::ETIMEDOUT;
+        }
+        let timed_out = waiter
+            .cond
+            .wait_for(&mut woken, (at - now).min(WAIT_CHUNK))
+            .timed_out();
+        if timed_out && is_deterministic() {
+            // the virtual clock only moves with syscalls, which may all be waiting
+            let until = virtual_now() + (at - now);
+            MutexGuard::unlocked(&mut woken, || {
+                let _ = virtual_sleep_until(until, || waiter.interrupted());
+            });
+        }
+    };
+    // a wake locks the bucket and then the waiter, so let go of the waiter first
+    drop(woken);
+    if dequeue(waiter) {
+        Err(ended)
+    } else {
+        Ok(())
+    }
+}
+
+/// Wake up to `nr` waiters on `key` that share a bit with `bitset`
+fn wake(futexes: &mut Futexes, key: FutexKey, nr: u32, bitset: u32) -> u32 {
+    let Some(queue) = futexes.get_mut(&key) else {
+        return 0;
+    };
+    let mut woken = 0;
+    queue.retain(|waiter| {
+        if woken < nr && waiter.bitset & bitset != 0 {
+            waiter.wake();
+            woken += 1;
+            false
+        } else {
+            true
+        }
+    });
+    if queue.is_empty() {
+        futexes.remove(&key);
+    }
+    woken
+}
+
+/// Move up to `nr` waiters from `key` to `key2`
+fn requeue(buckets: &mut Buckets, key: FutexKey, key2: FutexKey, nr: u32) -> u32 {
+    let futexes = buckets.of(key);
+    let Some(queue) = futexes.get_mut(&key) else {
+        return 0;
+    };
+    let moved: Vec<_> = queue.drain(..queue.len().min(nr as usize)).collect();
+    if queue.is_empty() {
+        futexes.remove(&key);
+    }
+    let count = moved.len() as u32;
+    let queue2 = buckets.of(key2).entry(key2).or_default();
+    for waiter in moved {
+        *waiter.key.lock() = key2;
+        queue2.push_back(waiter);
+    }
+    count
+}
+
+fn futex_wait(
+    key: FutexKey,
+    uaddr: u64,
+    val: u32,
+    waiter: Arc<Waiter>,
+    deadline: Option<Deadline>,
+) -> Result<i32, Errno> {
+    {
+        let mut futexes = lock_bucket(key);
+        if futex_word(uaddr).load(Ordering::SeqCst) != val {
+            return Err(Errno::EAGAIN);
+        }
+        futexes.entry(key).or_default().push_back(waiter.clone());
+    }
+    sleep(&waiter, deadline)?;
+    // a FUTEX_WAIT_REQUEUE_PI waiter that was woken rather than requeued has no lock to return with
+    if waiter.requeue_pi.is_some() && !waiter.pi.load(Ordering::Relaxed) {
+        return Err(Errno::EAGAIN);
+    }
+    Ok(0)
+}
+
+/// `FUTEX_WAKE_OP`: apply the operation in `val3` to the word at `uaddr2`, wake up to `nr_wake`
+/// waiters on `key`, and up to `nr_wake2` on `key2` if the old value of the word passes the
+/// comparison in `val3`
+fn futex_wake_op(
+    key: FutexKey,
+    key2: FutexKey,
+    uaddr2: u64,
+    nr_wake: u32,
+    nr_wake2: u32,
+    val3: u32,
+) -> Result<i32, Errno> {
+    let op = (val3 >> 28) & 7;
+    let cmp = (val3 >> 24) & 15;
+    let mut oparg = ((val3 << 8) as i32 >> 20) as u32;
+    let cmparg = (val3 << 20) as i32 >> 20;
+    if (val3 >> 28) & FUTEX_OP_OPARG_SHIFT != 0 {
+        oparg = 1 << (oparg & 31);
+    }
+    let apply: fn(u32, u32) -> u32 = match op {
+        FUTEX_OP_SET => |_, arg| arg,
+        FUTEX_OP_ADD => |old, arg| old.wrapping_add(arg),
+        FUTEX_OP_OR => |old, arg| old | arg,
+        FUTEX_OP_ANDN => |old, arg| old & !arg,
+        FUTEX_OP_XOR => |old, arg| old ^ arg,
+        _ => return Err(Errno::ENOSYS),
+    };
+    if cmp > FUTEX_OP_CMP_GE {
+        return Err(Errno::ENOSYS);
+    }
+
+    let mut buckets = Buckets::lock(key, key2);
+    let old = futex_word(uaddr2)
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |old| {
+            Some(apply(old, oparg))
+        })
+        .unwrap() as i32;
+    let mut woken = wake(buckets.of(key), key, nr_wake, FUTEX_BITSET_MATCH_ANY);
+    let passed = match cmp {
+        FUTEX_OP_CMP_EQ => old == cmparg,
+        FUTEX_OP_CMP_NE => old != cmparg,
+        FUTEX_OP_CMP_LT => old < cmparg,
+        FUTEX_OP_CMP_LE => old <= cmparg,
+        FUTEX_OP_CMP_GT => old > cmparg,
+        _ => old >= cmparg,
+    };
+    if passed {
+        woken += wake(buckets.of(key2), key2, nr_wake2, FUTEX_BITSET_MATCH_ANY);
+    }
+    Ok(woken as i32)
+}
+
+/// `FUTEX_LOCK_PI`, `FUTEX_LOCK_PI2` and, with `trylock`, `FUTEX_TRYLOCK_PI`
+fn futex_lock_pi(
+    cageid: u64,
+    threadid: Option<i32>,
+    key: FutexKey,
+    uaddr: u64,
+    deadline: Option<Deadline>,
+    trylock: bool,
+) -> Result<i32, Errno> {
+    let Some(threadid) = threadid else {
+        return Err(Errno::ESRCH);
+    };
+    let tid = threadid as u32 & FUTEX_TID_MASK;
+    let word = futex_word(uaddr);
+
+    let mut futexes = lock_bucket(key);
+    let mut cur = word.load(Ordering::SeqCst);
+    loop {
+        let owner = cur & FUTEX_TID_MASK;
+        if owner == tid {
+            return Err(Errno::EDEADLK);
+        }
+        // a free lock, possibly left behind by an owner that died, is taken right away
+        let new = if owner == 0 {
+            let waiters = if futexes.contains_key(&key) {
+                FUTEX_WAITERS
+            } else {
+                0
+            };
+            tid | waiters | (cur & FUTEX_OWNER_DIED)
+        } else if trylock {
+            return Err(Errno::EAGAIN);
+        } else {
+            cur | FUTEX_WAITERS
+        };
+        match word.compare_exchange(cur, new, Ordering::SeqCst, Ordering::SeqCst) {
+            Ok(_) if owner == 0 => return Ok(0),
+            Ok(_) => break,
+            Err(actual) => cur = actual,
+        }
+    }
+
+    let waiter = Waiter::new(
+        cageid,
+        Some(threadid),
+        key,
+        FUTEX_BITSET_MATCH_ANY,
+        true,
+        None,
+    );
+    futexes.entry(key).or_default().push_back(waiter.clone());
+    drop(futexes);
+    sleep(&waiter, deadline)?;
+    Ok(0)
+}
+
+/// `FUTEX_UNLOCK_PI`: hand the lock to the first waiter for it, or free it
+fn futex_unlock_pi(threadid: Option<i32>, key: FutexKey, uaddr: u64) -> Result<i32, Errno> {
+    let Some(threadid) = threadid else {
+        return Err(Errno::EPERM);
+    };
+    let word = futex_word(uaddr);
+    let mut futexes = lock_bucket(key);
+    if word.load(Ordering::SeqCst) & FUTEX_TID_MASK != threadid as u32 & FUTEX_TID_MASK {
+        return Err(Errno::EPERM);
+    }
+
+    let Some(queue) = futexes.get_mut(&key) else {
+        word.store(0, Ordering::SeqCst);
+        return Ok(0);
+    };
+    let next = queue
+        .iter()
+        .position(|w| w.pi.load(Ordering::Relaxed))
+        .and_then(|index| queue.remove(index));
+    let more = queue.iter().any(|w| w.pi.load(Ordering::Relaxed));
+    if queue.is_empty() {
+        futexes.remove(&key);
+    }
+    match next {
+        Some(waiter) => {
+            let waiters = if more { FUTEX_WAITERS } else { 0 };
+            word.store(waiter.tid() | waiters, Ordering::SeqCst);
+            waiter.wake();
+        }
+        None => word.store(0, Ordering::SeqCst),
+    }
+    Ok(0)
+}
+
+/// `FUTEX_REQUEUE` and, with `cmp`, `FUTEX_CMP_REQUEUE`
+fn futex_requeue(
+    key: FutexKey,
+    key2: FutexKey,
+    uaddr: u64,
+    nr_wake: u32,
+    nr_move: u32,
+    cmp: Option<u32>,
+) -> Result<i32, Errno> {
+    if (nr_wake as i32) < 0 || (nr_move as i32) < 0 {
+        return Err(Errno::EINVAL);
+    }
+    let mut buckets = Buckets::lock(key, key2);
+    if cmp.is_some_and(|val| futex_word(uaddr).load(Ordering::SeqCst) != val) {
+        return Err(Errno::EAGAIN);
+    }
+    let woken = wake(buckets.of(key), key, nr_wake, FUTEX_BITSET_MATCH_ANY);
+    let moved = requeue(&mut buckets, key, key2, nr_move);
+    // only FUTEX_CMP_REQUEUE counts the waiters it moved
+    Ok(if cmp.is_some() { woken + moved } else { woken } as i32)
+}
+
+/// Take up to `nr` of the `FUTEX_WAIT_REQUEUE_PI` waiters on `key` that expect to be requeued
+/// to `key2` off the table
+fn take_requeue_pi(
+    futexes: &mut Futexes,
+    key: FutexKey,
+    key2: FutexKey,
+    nr: usize,
+) -> Vec<Arc<Waiter>> {
+    let Some(queue) = futexes.get_mut(&key) else {
+        return Vec::new();
+    };
+    let mut taken = Vec::new();
+    queue.retain(|waiter| {
+        if taken.len() < nr && waiter.requeue_pi == Some(key2) {
+            taken.push(waiter.clone());
+            false
+        } else {
+            true
+        }
+    });
+    if queue.is_empty() {
+        futexes.remove(&key);
+    }
+    taken
+}
+
+/// `FUTEX_CMP_REQUEUE_PI`: the first `FUTEX_WAIT_REQUEUE_PI` waiter on `key` takes the PI lock
+/// at `uaddr2` if it is free, and up to `nr_move` more move over to wait for it
+fn futex_cmp_requeue_pi(
+    key: FutexKey,
+    key2: FutexKey,
+    uaddr: u64,
+    uaddr2: u64,
+    nr_move: u32,
+    val3: u32,
+) -> Result<i32, Errno> {
+    if (nr_move as i32) < 0 {
+        return Err(Errno::EINVAL);
+    }
+    let word2 = futex_word(uaddr2);
+    let mut buckets = Buckets::lock(key, key2);
+    if futex_word(uaddr).load(Ordering::SeqCst) != val3 {
+        return Err(Errno::EAGAIN);
+    }
+    let mut count = 0;
+    let cur = word2.load(Ordering::SeqCst);
+    if cur & FUTEX_TID_MASK == 0 {
+        if let Some(first) = take_requeue_pi(buckets.of(key), key, key2, 1).pop() {
+            let waiters = if buckets.of(key2).contains_key(&key2) {
+                FUTEX_WAITERS
+            } else {
+                0
+            };
+            word2.store(
+                first.tid() | waiters | (cur & FUTEX_OWNER_DIED),
+                Ordering::SeqCst,
+            );
+            first.pi.store(true, Ordering::Relaxed);
+            first.wake();
+            count += 1;
+        }
+    }
+    let moved = take_requeue_pi(buckets.of(key), key, key2, nr_move as usize);
+    if !moved.is_empty() {
+        word2.fetch_or(FUTEX_WAITERS, Ordering::SeqCst);
+    }
+    for waiter in moved {
+        waiter.pi.store(true, Ordering::Relaxed);
+        *waiter.key.lock() = key2;
+        buckets.of(key2).entry(key2).or_default().push_back(waiter);
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Wake the waits of cage `cageid` up to check whether they were interrupted. Registered with
+/// `cage::register_signal_wakeup`.
+pub fn futex_signal_wakeup(cageid: u64) {
+    for bucket in FUTEXES.iter() {
+        let futexes = bucket.lock();
+        for waiter in futexes.values().flatten() {
+            if waiter.cageid == cageid {
+                waiter.notify();
+            }
+        }
+    }
+}
+
+/// The futex operation `futex_op` of cage `cageid` on the word at host address `uaddr`. The
+/// meaning of `timeout` and `uaddr2` depends on the operation, as for the Linux syscall.
+///
+/// ## Returns:
+/// - the result of the operation, or a negative errno
+pub fn do_futex(
+    cageid: u64,
+    uaddr: u64,
+    futex_op: i32,
+    val: u32,
+    timeout: u64,
+    uaddr2: u64,
+    val3: u32,
+) -> i32 {
+    match futex_dispatch(cageid, uaddr, futex_op, val, timeout, uaddr2, val3) {
+        Ok(ret) => ret,
+        Err(Errno::EAGAIN) => syscall_error(Errno::EAGAIN, "futex", "futex word changed"),
+        Err(Errno::ETIMEDOUT) => syscall_error(Errno::ETIMEDOUT, "futex", "timed out"),
+        Err(Errno::EINTR) if timeout == 0 => handle_errno(interrupted(cageid, true), "futex"),
+        Err(Errno::EINTR) => syscall_error(Errno::EINTR, "futex", "interrupted"),
+        Err(Errno::ENOSYS) => syscall_error(Errno::ENOSYS, "futex", "unsupported operation"),
+        Err(e) => syscall_error(e, "futex", "invalid futex operation"),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn futex_dispatch(
+    cageid: u64,
+    uaddr: u64,
+    futex_op: i32,
+    val: u32,
+    timeout: u64,
+    uaddr2: u64,
+    val3: u32,
+) -> Result<i32, Errno> {
+    check_uaddr(uaddr)?;
+    let private = futex_op & FUTEX_PRIVATE_FLAG != 0;
+    let cmd = futex_op & FUTEX_CMD_MASK;
+    let clockid = if futex_op & FUTEX_CLOCK_REALTIME != 0 {
+        if !matches!(
+            cmd,
+            FUTEX_WAIT | FUTEX_WAIT_BITSET | FUTEX_WAIT_REQUEUE_PI | FUTEX_LOCK_PI2
+        ) {
+            return Err(Errno::ENOSYS);
+        }
+        libc::CLOCK_REALTIME
+    } else {
+        libc::CLOCK_MONOTONIC
+    };
+    let key = futex_key(cageid, uaddr, private);
+    let key2 = || -> Result<FutexKey, Errno> {
+        check_uaddr(uaddr2)?;
+        Ok(futex_key(cageid, uaddr2, private))
+    };
+    let threadid = current_cage_thread(cageid);
+    // the ops that take no timeout get a count in its place
+    let val2 = timeout as u32;
+
+    match cmd {
+        FUTEX_WAIT | FUTEX_WAIT_BITSET => {
+            let bitset = if cmd == FUTEX_WAIT {
+                FUTEX_BITSET_MATCH_ANY
+            } else {
+                val3
+            };
+            if bitset == 0 {
+                return Err(Errno::EINVAL);
+            }
+            // like on Linux, FUTEX_WAIT always waits for a relative time on the monotonic clock
+            let deadline = if cmd == FUTEX_WAIT {
+                deadline(cageid, timeout, libc::CLOCK_MONOTONIC, false)?
+            } else {
+                deadline(cageid, timeout, clockid, true)?
+            };
+            let waiter = Waiter::new(cageid, threadid, key, bitset, false, None);
+            futex_wait(key, uaddr, val, waiter, deadline)
+        }
+        FUTEX_WAKE | FUTEX_WAKE_BITSET => {
+            let bitset = if cmd == FUTEX_WAKE {
+                FUTEX_BITSET_MATCH_ANY
+            } else {
+                val3
+            };
+            if bitset == 0 {
+                return Err(Errno::EINVAL);
+            }
+            Ok(wake(&mut lock_bucket(key), key, val, bitset) as i32)
+        }
+        FUTEX_REQUEUE => futex_requeue(key, key2()?, uaddr, val, val2, None),
+        FUTEX_CMP_REQUEUE => futex_requeue(key, key2()?, uaddr, val, val2, Some(val3)),
+        FUTEX_WAKE_OP => futex_wake_op(key, key2()?, uaddr2, val, val2, val3),
+        FUTEX_LOCK_PI => {
+            let deadline = deadline(cageid, timeout, libc::CLOCK_REALTIME, true)?;
+            futex_lock_pi(cageid, threadid, key, uaddr, deadline, false)
+        }
+        FUTEX_LOCK_PI2 => {
+            let deadline = deadline(cageid, timeout, clockid, true)?;
+            futex_lock_pi(cageid, threadid, key, uaddr, deadline, false)
+        }
+        FUTEX_TRYLOCK_PI => futex_lock_pi(cageid, threadid, key, uaddr, None, true),
+        FUTEX_UNLOCK_PI => futex_unlock_pi(threadid, key, uaddr),
+        FUTEX_WAIT_REQUEUE_PI => {
+            let key2 = key2()?;
+            if key2 == key || threadid.is_none() {
+                return Err(Errno::EINVAL);
+            }
+            let deadline = deadline(cageid, timeout, clockid, true)?;
+            let waiter = Waiter::new(
+                cageid,
+                threadid,
+                key,
+                FUTEX_BITSET_MATCH_ANY,
+                false,
+                Some(key2),
+            );
+            futex_wait(key, uaddr, val, waiter, deadline)
+        }
+        FUTEX_CMP_REQUEUE_PI => {
+            let key2 = key2()?;
+            // Linux only lets the first waiter take the lock, and moves the others
+            if val != 1 || key2 == key {
+                return Err(Errno::EINVAL);
+            }
+            futex_cmp_requeue_pi(key, key2, uaddr, uaddr2, val2, val3)
+        }
+        // FUTEX_FD is gone from Linux since 2.6.26
+        _ => Err(Errno::ENOSYS),
+    }
+}
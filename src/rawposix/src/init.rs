@@ -1,17 +1,28 @@
+use crate::devfs::{devfs_close, devfs_new_description, DEV_NULL};
+use crate::epoll::epoll_close;
 use crate::fs_calls::kernel_close;
+use crate::interrupt::{interrupt_init, interrupt_signal_wakeup};
+use crate::netns::netns_close;
+use crate::scm::SCM_INFLIGHT_FDTABLE;
+use crate::sighup::sighup_init;
 use crate::sys_calls::exit_syscall;
 use crate::syscall_table::*;
-use cage::{add_cage, cagetable_clear, cagetable_init, timer::IntervalTimer, Cage, Vmmap};
+use crate::tmpfs::tmpfs_close;
+use crate::uts::uts_default;
+use cage::{
+    add_cage, cagetable_clear, cagetable_init, cputime::CpuAccount, register_signal_wakeup,
+    timer::CageTimers, Cage, Vmmap,
+};
 use dashmap::DashMap;
 use fdtables;
 use parking_lot::{Mutex, RwLock};
 use std::ffi::CString;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicI32, AtomicU64, Ordering::*};
+use std::sync::atomic::{AtomicI32, AtomicI64, AtomicU64, Ordering::*};
 use std::sync::Arc;
 use sysdefs::constants::{
-    EXIT_SUCCESS, FDKIND_KERNEL, LINDFS_ROOT, RAWPOSIX_CAGEID, STDERR_FILENO, STDIN_FILENO,
-    STDOUT_FILENO, THREEI_CAGEID, VERBOSE,
+    EXIT_SUCCESS, FDKIND_DEV, FDKIND_KERNEL, FDKIND_NETNS, FDKIND_TMPFS, LINDFS_ROOT,
+    RAWPOSIX_CAGEID, STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO, THREEI_CAGEID, VERBOSE,
 };
 use threei::{
     copy_data_between_cages, copy_handler_table_to_cage, register_handler,
@@ -225,29 +236,30 @@ pub fn rawposix_start(verbosity: isize) {
     // init cage table
     cagetable_init();
 
+    // signals end the futex waits and the blocking host calls of the threads they are for
+    register_signal_wakeup(interrupt_signal_wakeup);
+    interrupt_init();
+    // a hangup of the terminal lind runs in is raised in the cages
+    sighup_init();
+
     // register kernel close to fdtables
     fdtables::register_close_handlers(FDKIND_KERNEL, fdtables::NULL_FUNC, kernel_close);
+    // built-in device nodes hold no host resources
+    fdtables::register_close_handlers(FDKIND_DEV, fdtables::NULL_FUNC, devfs_close);
+    // open tmpfs files are released once their last fd is gone
+    fdtables::register_close_handlers(FDKIND_TMPFS, fdtables::NULL_FUNC, tmpfs_close);
+    // a namespace socket gives its port back once its last fd is gone
+    fdtables::register_close_handlers(FDKIND_NETNS, fdtables::NULL_FUNC, netns_close);
+    // an epoll instance lives until no fd refers to it anymore, whichever cage opened it
+    fdtables::register_close_handlers(fdtables::FDT_KINDEPOLL, fdtables::NULL_FUNC, epoll_close);
+    // fds in flight in SCM_RIGHTS messages are parked in a table of RawPOSIX's own
+    fdtables::init_empty_cage(SCM_INFLIGHT_FDTABLE);
 
     // register syscalls for init cage
     register_rawposix_syscall(1);
 
     register_threei_syscall(1);
 
-    // Set up standard file descriptors for the init cage
-    // TODO:
-    // Replace the hardcoded values with variables (possibly by adding a LIND-specific constants file)
-    let dev_null = CString::new("/dev/null").unwrap();
-
-    // Make sure that the standard file descriptors (stdin, stdout, stderr) are always valid
-    // Standard input (fd = 0) is redirected to /dev/null
-    // Standard output (fd = 1) is redirected to /dev/null
-    // Standard error (fd = 2) is set to copy of stdout
-    unsafe {
-        libc::open(dev_null.as_ptr(), libc::O_RDONLY);
-        libc::open(dev_null.as_ptr(), libc::O_WRONLY);
-        libc::dup(1);
-    }
-
     //init cage is its own parent
     let initcage = Cage {
         cageid: 1,
@@ -255,14 +267,16 @@ pub fn rawposix_start(verbosity: isize) {
         parent: 1,
         rev_shm: Mutex::new(Vec::new()),
         main_threadid: RwLock::new(0),
-        interval_timer: IntervalTimer::new(1),
+        timers: CageTimers::new(1),
+        cpu: CpuAccount::default(),
+        realtime_offset: AtomicI64::new(0),
         epoch_handler: DashMap::new(),
         signalhandler: DashMap::new(),
         pending_signals: RwLock::new(vec![]),
-        sigset: AtomicU64::new(0),
         zombies: RwLock::new(vec![]),
         child_num: AtomicU64::new(0),
         vmmap: RwLock::new(Vmmap::new()),
+        uts: RwLock::new(uts_default()),
     };
 
     // Add cage to cagetable
@@ -274,36 +288,24 @@ pub fn rawposix_start(verbosity: isize) {
     // init fdtables for cageid 1
     fdtables::init_empty_cage(1);
     // Set the first 3 fd to STDIN / STDOUT / STDERR
-    // STDIN
-    fdtables::get_specific_virtual_fd(
-        1,
-        STDIN_FILENO as u64,
-        FDKIND_KERNEL,
-        STDIN_FILENO as u64,
-        false,
-        0,
-    )
-    .unwrap();
-    // STDOUT
-    fdtables::get_specific_virtual_fd(
-        1,
-        STDOUT_FILENO as u64,
-        FDKIND_KERNEL,
-        STDOUT_FILENO as u64,
-        false,
-        0,
-    )
-    .unwrap();
-    // STDERR
-    fdtables::get_specific_virtual_fd(
-        1,
-        STDERR_FILENO as u64,
-        FDKIND_KERNEL,
-        STDERR_FILENO as u64,
-        false,
-        0,
-    )
-    .unwrap();
+    //
+    // Lind-WASM shares the host's standard streams. If the host started us with one of
+    // them closed, the guest gets the virtual /dev/null in its place rather than a host
+    // /dev/null, which does not exist inside the lindfs chroot anyway.
+    for stdfd in [STDIN_FILENO, STDOUT_FILENO, STDERR_FILENO] {
+        let host_fd_valid = unsafe { libc::fcntl(stdfd, libc::F_GETFD) } >= 0;
+        let (fdkind, underfd, perfdinfo) = if host_fd_valid {
+            (FDKIND_KERNEL, stdfd as u64, 0)
+        } else {
+            (
+                FDKIND_DEV,
+                devfs_new_description(DEV_NULL),
+                libc::O_RDWR as u64,
+            )
+        };
+        fdtables::get_specific_virtual_fd(1, stdfd as u64, fdkind, underfd, false, perfdinfo)
+            .unwrap();
+    }
 }
 
 /// Shut down the RawPOSIX runtime.
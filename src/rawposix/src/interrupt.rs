@@ -0,0 +1,181 @@
+//! Interruptible blocking calls
+//!
+//! A syscall that may block for good in the host kernel, such as `read()` on a pipe, `accept()`
+//! or `connect()`, runs through `interruptible`. A signal for the cage only triggers the epoch
+//! of the thread that is to handle it, which the guest looks at once the syscall has returned,
+//! so that thread has to be woken up first. While the host call runs, its host thread is
+//! registered together with the cage and thread it runs for. The `lind-interrupt` thread sends
+//! every registered host thread whose epoch is triggered a host real-time signal reserved for
+//! this, see `siglind_interrupt`. Its handler does nothing and is installed without `SA_RESTART`,
+//! so the host call fails with `EINTR`. A kick may come just before the host call starts and
+//! be lost, so the thread is kicked again every `KICK_RETRY` for as long as it stays blocked.
+//!
+//! What the guest sees then depends on the handler, as on Linux. The call fails with `EINTR`,
+//! unless it is one Linux restarts and the signal has a handler with `SA_RESTART` (see
+//! `cage::signal_restart_check`). Then it fails with the kernel internal `ERESTARTSYS`
+//! instead, on which glibc runs the handler through the epoch callback and makes the call
+//! again. Calls with a timeout of their own (`poll()`, `select()`, `epoll_wait()`, the sleeps)
+//! check for signals while they wait and always fail with `EINTR`, as on Linux.
+
+use crate::futex::futex_signal_wakeup;
+use cage::signal::signal::{
+    current_threadid, signal_check_trigger, signal_restart_check, thread_check_trigger,
+};
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use parking_lot::{Condvar, Mutex};
+use std::time::Duration;
+use sysdefs::constants::err_const::{get_errno, Errno};
+
+/// How long the `lind-interrupt` thread waits before it kicks a thread that is still blocked
+const KICK_RETRY: Duration = Duration::from_millis(2);
+
+/// The host signal that interrupts a blocking call, the first real-time signal glibc leaves
+/// to applications
+fn siglind_interrupt() -> i32 {
+    libc::SIGRTMIN()
+}
+
+lazy_static! {
+    /// Host threads in an interruptible call, with the cage and thread they make it for
+    static ref BLOCKED: DashMap<libc::pthread_t, (u64, i32)> = DashMap::new();
+    /// Set when a signal was sent, the `lind-interrupt` thread waits on it
+    static ref KICK: (Mutex<bool>, Condvar) = (Mutex::new(false), Condvar::new());
+}
+
+/// The calling host thread, registered as blocked for as long as it lives
+struct Blocked(libc::pthread_t);
+
+impl Blocked {
+    fn new(cageid: u64) -> Self {
+        let thread = unsafe { libc::pthread_self() };
+        // a cage that is gone has no thread to interrupt, 0 matches none
+        let threadid = current_threadid(cageid).unwrap_or(0);
+        BLOCKED.insert(thread, (cageid, threadid));
+        Blocked(thread)
+    }
+}
+
+impl Drop for Blocked {
+    fn drop(&mut self) {
+        BLOCKED.remove(&self.0);
+    }
+}
+
+/// Run the blocking host call `call` for cage `cageid` so that a signal for the calling thread
+/// ends it. `call` returns what the host function does, a negative value with `errno` set on
+/// failure, and is made again if the host interrupts it for some reason of its own.
+///
+/// `restart` tells whether Linux restarts the call after a handler with `SA_RESTART`.
+///
+/// ## Returns:
+/// - what the host call returned, with `errno` set as it left it, or -1 with `errno` set to
+///   `EINTR` or `ERESTARTSYS` if a signal interrupted it
+pub fn interruptible<T: PartialOrd + From<i8>>(
+    cageid: u64,
+    restart: bool,
+    mut call: impl FnMut() -> T,
+) -> T {
+    loop {
+        let blocked = Blocked::new(cageid);
+        // a signal that came before the thread was registered did not kick it
+        let ret = if signal_check_trigger(cageid) {
+            None
+        } else {
+            Some(call())
+        };
+        let errno = get_errno();
+        drop(blocked);
+
+        match ret {
+            Some(ret) if ret >= T::from(0) || errno != libc::EINTR => {
+                set_errno(errno);
+                return ret;
+            }
+            // interrupted by the host for a reason of its own
+            Some(_) if !signal_check_trigger(cageid) => continue,
+            _ => {
+                set_errno(interrupted(cageid, restart));
+                return T::from(-1);
+            }
+        }
+    }
+}
+
+fn set_errno(errno: i32) {
+    unsafe { *libc::__errno_location() = errno };
+}
+
+/// The errno of a call for cage `cageid` that a signal interrupted, `ERESTARTSYS` if the call
+/// is to be restarted once the signal is handled, see `interruptible`
+pub fn interrupted(cageid: u64, restart: bool) -> i32 {
+    if restart && signal_restart_check(cageid) {
+        Errno::ERESTARTSYS as i32
+    } else {
+        Errno::EINTR as i32
+    }
+}
+
+/// Wake up whatever waits cage `cageid` has in RawPOSIX, to check whether a signal interrupted
+/// them. Registered with `cage::register_signal_wakeup`.
+pub fn interrupt_signal_wakeup(cageid: u64) {
+    futex_signal_wakeup(cageid);
+    let (kick, cond) = &*KICK;
+    *kick.lock() = true;
+    cond.notify_one();
+}
+
+/// Install the host handler of `siglind_interrupt()` and start the `lind-interrupt` thread
+pub fn interrupt_init() {
+    unsafe {
+        let mut act: libc::sigaction = std::mem::zeroed();
+        act.sa_sigaction = interrupt_handler as extern "C" fn(i32) as libc::sighandler_t;
+        // no SA_RESTART, interrupting the host call is the point
+        act.sa_flags = 0;
+        libc::sigemptyset(&mut act.sa_mask);
+        if libc::sigaction(siglind_interrupt(), &act, std::ptr::null_mut()) < 0 {
+            panic!(
+                "Failed to install the handler of blocking call interrupts: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+    std::thread::Builder::new()
+        .name("lind-interrupt".to_string())
+        .spawn(kick_blocked)
+        .expect("Failed to start the blocking call interrupt thread");
+}
+
+extern "C" fn interrupt_handler(_signo: i32) {}
+
+/// Body of the `lind-interrupt` thread
+fn kick_blocked() {
+    let (kick, cond) = &*KICK;
+    let mut pending = kick.lock();
+    loop {
+        if !*pending {
+            cond.wait(&mut pending);
+            continue;
+        }
+        *pending = false;
+        drop(pending);
+
+        let mut kicked = false;
+        // a registered thread is still in `interruptible`, which it cannot leave while its
+        // entry is looked at, so its pthread_t is valid
+        for entry in BLOCKED.iter() {
+            let (cageid, threadid) = *entry.value();
+            if thread_check_trigger(cageid, threadid) {
+                unsafe { libc::pthread_kill(*entry.key(), siglind_interrupt()) };
+                kicked = true;
+            }
+        }
+
+        pending = kick.lock();
+        if kicked && !*pending {
+            // look again in a while, in case a kick came before the host call started
+            cond.wait_for(&mut pending, KICK_RETRY);
+            *pending = true;
+        }
+    }
+}
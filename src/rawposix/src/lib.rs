@@ -3,10 +3,28 @@
 // This library provides POSIX-compliant system call implementations that operate
 // within the Lind-WASM sandbox environment using the 3i (Three Interposition) system.
 
+pub mod deterministic;
+pub mod devfs;
+pub mod epoll;
 pub mod fs_calls;
+pub mod futex;
 pub mod init;
+pub mod interrupt;
 pub mod net_calls;
+pub mod netns;
+pub mod netpolicy;
+pub mod oom;
+pub mod overlay;
+pub mod procfs;
+pub mod resolver;
+pub mod scm;
+pub mod sighup;
+pub mod sigio;
+pub mod sigpipe;
 pub mod sys_calls;
 pub mod syscall_table;
+pub mod tmpfs;
+pub mod unixns;
+pub mod uts;
 
 pub use syscall_table::*;
@@ -1,32 +1,84 @@
-use cage::{readtimer, signal_check_trigger, starttimer, timeout_setup_ms, Duration};
+use crate::deterministic::{host_timeout, virtual_timeout};
+use crate::devfs::{devfs_device, devfs_poll_revents};
+use crate::epoll::{epoll_ctl_registration, epoll_instance_init, epoll_ready_events};
+use crate::interrupt::interruptible;
+use crate::netns::{self, netns_fd_socket};
+use crate::netpolicy::{
+    netpolicy_check_bind, netpolicy_check_destination, netpolicy_check_listen,
+    netpolicy_check_socket, netpolicy_local_addr,
+};
+use crate::scm;
+use crate::sigpipe::{sigpipe_check, sigpipe_getsockopt, sigpipe_setsockopt};
+use crate::unixns::{
+    unixns_autobind, unixns_copy_out, unixns_is_autobind, unixns_name_out, unixns_to_host,
+};
+use crate::uts::uts_field;
+use cage::{
+    get_cage, readtimer, signal_check_trigger, signal_mask_restore, signal_mask_swap, starttimer,
+    timeout_setup, Duration,
+};
 use fdtables;
-use fdtables::epoll_event;
-use lazy_static::lazy_static;
 use libc::*;
-use parking_lot::Mutex;
 use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 use std::{mem, ptr};
 use sysdefs::constants::err_const::{get_errno, handle_errno, syscall_error, Errno};
-use sysdefs::constants::net_const::{EPOLL_CLOEXEC, EPOLL_CTL_ADD, EPOLL_CTL_DEL, EPOLL_CTL_MOD};
-use sysdefs::constants::FDKIND_KERNEL;
+use sysdefs::constants::fs_const::IOV_MAX;
+use sysdefs::constants::net_const::{
+    EPOLL_CLOEXEC, EPOLL_CTL_ADD, EPOLL_CTL_DEL, EPOLL_CTL_MOD, MSG_CMSG_CLOEXEC, MSG_CTRUNC,
+    SOL_SOCKET, SO_LIND_NOSIGPIPE,
+};
+use sysdefs::constants::{FDKIND_DEV, FDKIND_KERNEL, FDKIND_NETNS, FDKIND_TMPFS};
+use sysdefs::data::fs_struct::SigsetType;
 use sysdefs::data::net_struct::SockAddr;
 use sysdefs::*;
 use typemap::cage_helpers::convert_fd_to_host;
 use typemap::datatype_conversion::*;
-use typemap::network_helpers::{convert_host_sockaddr, convert_sockpair, copy_out_sockaddr};
-
-/// `epoll_ctl` handles registering, modifying, and removing the watch set, while `epoll_wait`
-/// simply gathers ready events based on what's already registered and writes them back to the
-/// user buffer. Since we currently only have a virtual-FD to kernel-FD mapping, allowing user
-/// space to use `events[i].data.fd` directly after `epoll_wait` requires translating kernel-side
-/// info back to the virtual side. We'll maintain a global reverse mapping (cage, underfd) to vfd,
-/// register entries during `epoll_ctl`, and translate data from underfd to vfd before writing
-/// events back in `epoll_wait`.
-lazy_static! {
-    // A hashmap used to store epoll mapping relationships
-    // <virtual_epfd <kernel_fd, virtual_fd>>
-    static ref REAL_EPOLL_MAP: Mutex<HashMap<u64, HashMap<i32, u64>>> = Mutex::new(HashMap::new());
+use typemap::network_helpers::{
+    convert_guest_mmsghdr, convert_host_sockaddr, convert_sockpair, copy_out_mmsghdr,
+    copy_out_sockaddr,
+};
+
+/// Read the timeout of `ppoll`, `pselect6` or `epoll_pwait2`. glibc hands over a host
+/// `struct timespec` for the guest's, whose `tv_nsec` is narrower, like for `recvmmsg`.
+///
+/// ## Returns:
+/// - `Ok(None)` for a NULL timeout, which waits forever
+/// - `Ok(Some(timeout))` otherwise
+/// - `Err(EINVAL)` if the timespec is negative or its nanoseconds are out of range
+fn timespec_timeout(
+    timeout_arg: u64,
+    timeout_cageid: u64,
+    cageid: u64,
+) -> Result<Option<Duration>, Errno> {
+    if sc_convert_arg_nullity(timeout_arg, timeout_cageid, cageid) {
+        return Ok(None);
+    }
+    let ts = unsafe { *(sc_convert_buf(timeout_arg, timeout_cageid, cageid) as *const timespec) };
+    if ts.tv_sec < 0 || !(0..1_000_000_000).contains(&ts.tv_nsec) {
+        return Err(Errno::EINVAL);
+    }
+    Ok(Some(Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)))
+}
+
+/// Run `wait` with the signal mask at `sigmask_arg` in place of the cage's, as `ppoll`,
+/// `pselect6` and `epoll_pwait` do. The swap is atomic with respect to signal delivery:
+/// signals the mask unblocks that are already pending interrupt the wait right away, and
+/// when one interrupts it the mask stays until its handler has run, see `signal_mask_restore`.
+/// A NULL mask leaves the cage's as it is.
+fn wait_with_sigmask(
+    cageid: u64,
+    sigmask_arg: u64,
+    sigmask_cageid: u64,
+    wait: impl FnOnce() -> i32,
+) -> i32 {
+    let Some(sigmask) = sc_convert_sigset(sigmask_arg, sigmask_cageid, cageid) else {
+        return wait();
+    };
+    let oldmask = signal_mask_swap(cageid, *sigmask);
+    let ret = wait();
+    signal_mask_restore(cageid, oldmask, ret == -(Errno::EINTR as i32));
+    ret
 }
 
 /// Reference to Linux: https://man7.org/linux/man-pages/man2/poll.2.html
@@ -82,6 +134,20 @@ pub extern "C" fn poll_syscall(
         return syscall_error(Errno::EFAULT, "poll_syscall", "Invalid Cage ID");
     }
 
+    let timeout_ms = sc_convert_sysarg_to_i32(timeout_arg, timeout_cageid, cageid);
+    let timeout = (timeout_ms >= 0).then(|| Duration::from_millis(timeout_ms as u64));
+    poll_common(cageid, fds_arg, fds_cageid, nfds_arg, nfds_cageid, timeout)
+}
+
+/// The wait of `poll_syscall` and `ppoll_syscall`, with `None` waiting forever
+fn poll_common(
+    cageid: u64,
+    fds_arg: u64,
+    fds_cageid: u64,
+    nfds_arg: u64,
+    nfds_cageid: u64,
+    timeout: Option<Duration>,
+) -> i32 {
     // Basic bounds checking - validate arguments before conversion - FD_PER_PROCESS_MAX is defined in fdtables constants
     if nfds_arg > fdtables::FD_PER_PROCESS_MAX {
         return syscall_error(Errno::EINVAL, "poll_syscall", "Too many file descriptors");
@@ -93,7 +159,6 @@ pub extern "C" fn poll_syscall(
 
     // Convert arguments after validation
     let nfds = sc_convert_sysarg_to_usize(nfds_arg, nfds_cageid, cageid);
-    let original_timeout = sc_convert_sysarg_to_i32(timeout_arg, timeout_cageid, cageid);
 
     // Convert pollfd array from user space
     let fds_ptr = sc_convert_buf(fds_arg, fds_cageid, cageid) as *mut libc::pollfd;
@@ -129,7 +194,7 @@ pub extern "C" fn poll_syscall(
     }
 
     // Convert virtual fds to kernel fds by fdkind using fdtables API
-    let (poll_data_by_fdkind, fdtables_mapping_table) =
+    let (poll_data_by_fdkind, _mappingtable) =
         fdtables::convert_virtualfds_for_poll(cageid, virtual_fds);
 
     // Process kernel-backed FDs and handle invalid FDs
@@ -139,8 +204,8 @@ pub extern "C" fn poll_syscall(
 
     for (fdkind, fd_set) in poll_data_by_fdkind {
         match fdkind {
-            FDKIND_KERNEL => {
-                // Collect all kernel FDs for polling
+            FDKIND_KERNEL | FDKIND_NETNS => {
+                // Collect all kernel FDs for polling, namespace sockets being host sockets too
                 for (vfd, fdentry) in fd_set {
                     // Use O(1) lookup to find original events for this virtual fd
                     let events = *vfd_to_events.get(&(vfd as i32)).unwrap_or(&0);
@@ -155,6 +220,32 @@ pub extern "C" fn poll_syscall(
                     });
                 }
             }
+            FDKIND_DEV => {
+                // Device nodes never block, their readiness is known right away
+                for (vfd, fdentry) in fd_set {
+                    if let Some(&array_index) = vfd_to_index.get(&(vfd as i32)) {
+                        let events = fds_slice[array_index].events;
+                        let revents = devfs_device(fdentry.underfd)
+                            .map_or(0, |dev| devfs_poll_revents(dev, events));
+                        if revents != 0 {
+                            fds_slice[array_index].revents = revents;
+                            total_ready += 1;
+                        }
+                    }
+                }
+            }
+            FDKIND_TMPFS => {
+                // Regular files on tmpfs are always readable and writable, as on Linux
+                for (vfd, _fdentry) in fd_set {
+                    if let Some(&array_index) = vfd_to_index.get(&(vfd as i32)) {
+                        let revents = fds_slice[array_index].events & (POLLIN | POLLOUT);
+                        if revents != 0 {
+                            fds_slice[array_index].revents = revents;
+                            total_ready += 1;
+                        }
+                    }
+                }
+            }
             fdtables::FDT_INVALID_FD => {
                 // Handle invalid FDs immediately - fdtables has already identified them
                 for (vfd, _fdentry) in fd_set {
@@ -174,8 +265,14 @@ pub extern "C" fn poll_syscall(
     // Poll all kernel-backed fds with timeout/signal checking loop
     if !all_kernel_pollfds.is_empty() {
         let start_time = starttimer();
+        // If virtual fds are already ready, only sample the kernel fds instead of blocking
+        let timeout = if total_ready > 0 {
+            Some(Duration::ZERO)
+        } else {
+            timeout
+        };
         // Keep track of total duration for our exit check in the poll loop
-        let (duration, chunk_timeout) = timeout_setup_ms(original_timeout);
+        let (duration, chunk_timeout) = timeout_setup(host_timeout(timeout));
 
         let ret;
         loop {
@@ -216,21 +313,19 @@ pub extern "C" fn poll_syscall(
             }
         }
 
-        // Convert kernel results back to virtual fds using fdtables helper
+        if ret == 0 && virtual_timeout(cageid, timeout).is_err() {
+            return syscall_error(Errno::EINTR, "poll_syscall", "interrupted");
+        }
+
+        // Convert kernel results back to virtual fds
         for (kernel_index, kernel_pollfd) in all_kernel_pollfds.iter().enumerate() {
             if kernel_pollfd.revents != 0 {
+                // The index mapping already names the virtual fd, whatever its kind
                 if let Some(&virtual_fd) = kernel_to_vfd_mapping.get(&kernel_index) {
-                    // Use fdtables helper to convert kernel fd back to virtual fd
-                    if let Some(converted_vfd) = fdtables::convert_poll_result_back_to_virtual(
-                        FDKIND_KERNEL,
-                        kernel_pollfd.fd as u64,
-                        &fdtables_mapping_table,
-                    ) {
-                        // Use O(1) lookup to update original user array
-                        if let Some(&array_index) = vfd_to_index.get(&(converted_vfd as i32)) {
-                            fds_slice[array_index].revents = kernel_pollfd.revents;
-                            total_ready += 1;
-                        }
+                    // Use O(1) lookup to update original user array
+                    if let Some(&array_index) = vfd_to_index.get(&(virtual_fd as i32)) {
+                        fds_slice[array_index].revents = kernel_pollfd.revents;
+                        total_ready += 1;
                     }
                 }
             }
@@ -240,6 +335,58 @@ pub extern "C" fn poll_syscall(
     total_ready
 }
 
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/ppoll.2.html
+///
+/// Linux `ppoll()` is `poll()` with a nanosecond timeout and a signal mask that replaces the
+/// cage's for the duration of the wait, see `wait_with_sigmask`. Unlike the kernel, the
+/// remaining time is not written back to the timeout, which glibc copies anyway.
+///
+/// ## Arguments:
+///     - cageid: current cage identifier.
+///     - fds_arg: pointer to array of pollfd structures (user's perspective).
+///     - nfds_arg: number of items in the fds array.
+///     - timeout_arg: pointer to a host `struct timespec`, NULL to wait forever.
+///     - sigmask_arg: pointer to the signal mask to wait with, NULL to keep the cage's.
+///     - sigsetsize_arg: size of the signal mask, which has to be 8 bytes.
+///     - arg6: unused argument.
+///
+/// ## Returns:
+///     - positive value: number of file descriptors ready for I/O
+///     - 0: timeout occurred with no file descriptors ready
+///     - negative value: error occurred (errno set)
+pub extern "C" fn ppoll_syscall(
+    cageid: u64,
+    fds_arg: u64,
+    fds_cageid: u64,
+    nfds_arg: u64,
+    nfds_cageid: u64,
+    timeout_arg: u64,
+    timeout_cageid: u64,
+    sigmask_arg: u64,
+    sigmask_cageid: u64,
+    sigsetsize_arg: u64,
+    sigsetsize_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    if !sc_unusedarg(arg6, arg6_cageid) {
+        return syscall_error(Errno::EFAULT, "ppoll_syscall", "Invalid Cage ID");
+    }
+
+    let sigsetsize = sc_convert_sysarg_to_usize(sigsetsize_arg, sigsetsize_cageid, cageid);
+    if sigmask_arg != 0 && sigsetsize != mem::size_of::<SigsetType>() {
+        return syscall_error(Errno::EINVAL, "ppoll_syscall", "Invalid sigset size");
+    }
+    let timeout = match timespec_timeout(timeout_arg, timeout_cageid, cageid) {
+        Ok(timeout) => timeout,
+        Err(e) => return syscall_error(e, "ppoll_syscall", "Invalid timeout"),
+    };
+
+    wait_with_sigmask(cageid, sigmask_arg, sigmask_cageid, || {
+        poll_common(cageid, fds_arg, fds_cageid, nfds_arg, nfds_cageid, timeout)
+    })
+}
+
 /// Reference to Linux: https://man7.org/linux/man-pages/man2/select.2.html
 ///
 /// Linux `select()` syscall waits for one of a set of file descriptors to become ready to perform I/O.
@@ -298,6 +445,45 @@ pub extern "C" fn select_syscall(
         return syscall_error(Errno::EFAULT, "select_syscall", "Invalid Cage ID");
     }
 
+    // Convert timeout pointer - can be null
+    let timeout = if timeout_arg != 0 {
+        let t = unsafe { *(sc_convert_buf(timeout_arg, timeout_cageid, cageid) as *const timeval) };
+        if t.tv_sec < 0 || !(0..1_000_000).contains(&t.tv_usec) {
+            return syscall_error(Errno::EINVAL, "select_syscall", "Invalid timeout");
+        }
+        Some(Duration::new(t.tv_sec as u64, t.tv_usec as u32 * 1000))
+    } else {
+        None
+    };
+
+    select_common(
+        cageid,
+        nfds_arg,
+        nfds_cageid,
+        readfds_arg,
+        readfds_cageid,
+        writefds_arg,
+        writefds_cageid,
+        exceptfds_arg,
+        exceptfds_cageid,
+        timeout,
+    )
+}
+
+/// The wait of `select_syscall` and `pselect6_syscall`, with `None` waiting forever
+#[allow(clippy::too_many_arguments)]
+fn select_common(
+    cageid: u64,
+    nfds_arg: u64,
+    nfds_cageid: u64,
+    readfds_arg: u64,
+    readfds_cageid: u64,
+    writefds_arg: u64,
+    writefds_cageid: u64,
+    exceptfds_arg: u64,
+    exceptfds_cageid: u64,
+    timeout: Option<Duration>,
+) -> i32 {
     // Convert arguments
     let nfds = sc_convert_sysarg_to_i32(nfds_arg, nfds_cageid, cageid);
 
@@ -320,13 +506,6 @@ pub extern "C" fn select_syscall(
         None
     };
 
-    // Convert timeout pointer - can be null
-    let timeout_ptr = if timeout_arg != 0 {
-        Some(sc_convert_buf(timeout_arg, timeout_cageid, cageid) as *mut libc::timeval)
-    } else {
-        None
-    };
-
     // Create fdkindset for fdtables processing
     let mut fdkindset = HashSet::new();
     fdkindset.insert(FDKIND_KERNEL);
@@ -372,24 +551,64 @@ pub extern "C" fn select_syscall(
 
     let mut realnewnfds = readnfd.max(writenfd).max(errornfd);
 
-    // Convert timeval pointer to milliseconds for consistency with poll/epoll_wait timeout handling
-    // select takes a timeval* (tv_sec + tv_usec), but poll/epoll_wait use integer milliseconds
-    let timeout_ms = if let Some(timeout_ptr) = timeout_ptr {
-        let t = unsafe { *timeout_ptr };
-        (t.tv_sec as i32 * 1000) + (t.tv_usec as i32 / 1000)
-    } else {
-        -1 // No timeout (infinite)
-    };
+    // Sockets of the network namespace are host sockets as well, so they are selected on along
+    // with the kernel fds and moved out of the results afterwards
+    let mut netns_fds = [Vec::new(), Vec::new(), Vec::new()];
+    for ((unparsed, real), netns) in unparsedtables
+        .iter()
+        .zip([&mut real_readfds, &mut real_writefds, &mut real_errorfds])
+        .zip(netns_fds.iter_mut())
+    {
+        if let Some(entries) = unparsed.get(&FDKIND_NETNS) {
+            for entry in entries {
+                fdtables::_fd_set(entry.underfd, real);
+                realnewnfds = realnewnfds.max(entry.underfd + 1);
+                netns.push(entry.underfd);
+            }
+        }
+    }
+
+    // Device nodes and tmpfs files never block, so their readiness is collected up front and
+    // merged into the kernel results below. If any of them is ready the kernel fds are only sampled.
+    // They are looked at through the virtual fds of the caller's sets: fds open on the same
+    // device, or dup()ed from the same tmpfs file, share an underfd, which the mapping table
+    // would only map back to one of them.
+    let mut unreal_read = HashSet::new();
+    let mut unreal_write = HashSet::new();
+    for (fds_ptr, unreal, events) in [
+        (readfds_ptr, &mut unreal_read, POLLIN),
+        (writefds_ptr, &mut unreal_write, POLLOUT),
+    ] {
+        let Some(fds_ptr) = fds_ptr else {
+            continue;
+        };
+        let fds = unsafe { *fds_ptr };
+        for vfd in 0..nfds as u64 {
+            if !fdtables::_fd_isset(vfd, &fds) {
+                continue;
+            }
+            let ready = match fdtables::translate_virtual_fd(cageid, vfd) {
+                Ok(entry) if entry.fdkind == FDKIND_DEV => devfs_device(entry.underfd)
+                    .is_some_and(|dev| devfs_poll_revents(dev, events) != 0),
+                Ok(entry) => entry.fdkind == FDKIND_TMPFS,
+                Err(_) => false,
+            };
+            if ready {
+                unreal.insert(vfd);
+            }
+        }
+    }
 
-    // Always use zero timeout for instant return from select - libc select requires timeval struct format
-    let mut zero_timeout = libc::timeval {
-        tv_sec: 0,
-        tv_usec: 0,
+    // If a device node or tmpfs file is ready, the kernel fds are only sampled
+    let timeout = if !unreal_read.is_empty() || !unreal_write.is_empty() {
+        Some(Duration::ZERO)
+    } else {
+        timeout
     };
 
     let start_time = starttimer();
     // Keep track of total timeout duration for exit handling later
-    let (duration, chunk_timeout) = timeout_setup_ms(timeout_ms);
+    let (duration, chunk_timeout) = timeout_setup(host_timeout(timeout));
     // Convert chunk_timeout (ms) to timeval for select
 
     let mut ret;
@@ -432,11 +651,8 @@ pub extern "C" fn select_syscall(
                 } else {
                     std::ptr::null_mut()
                 },
-                if timeout_ptr.is_some() {
-                    &mut current_timeout as *mut _ // libc select requires timeval struct format
-                } else {
-                    std::ptr::null_mut()
-                },
+                // Always bounded by the chunk, so that signals are checked for in between
+                &mut current_timeout as *mut _,
             )
         };
 
@@ -463,11 +679,31 @@ pub extern "C" fn select_syscall(
         }
     }
 
-    let mut unreal_read = HashSet::new();
-    let mut unreal_write = HashSet::new();
+    if ret == 0 && virtual_timeout(cageid, timeout).is_err() {
+        return syscall_error(Errno::EINTR, "select_syscall", "interrupted");
+    }
+
+    let mut unreal_error = HashSet::new();
+    for ((real, unreal), netns) in [
+        (&mut real_readfds, &mut unreal_read),
+        (&mut real_writefds, &mut unreal_write),
+        (&mut real_errorfds, &mut unreal_error),
+    ]
+    .into_iter()
+    .zip(netns_fds)
+    {
+        for underfd in netns {
+            if fdtables::_fd_isset(underfd, real) {
+                unsafe { FD_CLR(underfd as i32, real) };
+                if let Some(&vfd) = mappingtable.get(&(FDKIND_NETNS, underfd)) {
+                    unreal.insert(vfd);
+                }
+            }
+        }
+    }
 
     // TODO: Implement in-memory FD checking for select syscall
-    // Currently only kernel FDs are supported. In-memory pipes and sockets
+    // Currently only kernel FDs and device nodes are supported. In-memory pipes and sockets
     // will require custom polling logic when in-memory system is integrated.
 
     // Convert kernel FD results back to virtual FDs and subsequently write to user memory
@@ -508,7 +744,7 @@ pub extern "C" fn select_syscall(
         FDKIND_KERNEL,
         realnewnfds as u64,
         Some(real_errorfds),
-        HashSet::new(), // Assuming there are no unreal errorsets
+        unreal_error,
         None,
         &mappingtable,
     );
@@ -523,6 +759,62 @@ pub extern "C" fn select_syscall(
     (read_flags + write_flags + error_flags) as i32
 }
 
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/pselect6.2.html
+///
+/// Linux `pselect6()` is `select()` with a nanosecond timeout and a signal mask that replaces
+/// the cage's for the duration of the wait, see `wait_with_sigmask`. The kernel takes a pointer
+/// to a `{ sigset pointer, size }` pair as the sixth argument; glibc passes the signal mask
+/// itself instead, as it would otherwise have to translate the pointer inside the pair.
+///
+/// ## Arguments:
+///     - cageid: current cage identifier.
+///     - nfds_arg: highest-numbered file descriptor in any of the three sets, plus 1.
+///     - readfds_arg: pointer to fd_set for read file descriptors (user's perspective).
+///     - writefds_arg: pointer to fd_set for write file descriptors (user's perspective).
+///     - exceptfds_arg: pointer to fd_set for exception file descriptors (user's perspective).
+///     - timeout_arg: pointer to a host `struct timespec`, NULL to wait forever.
+///     - sigmask_arg: pointer to the signal mask to wait with, NULL to keep the cage's.
+///
+/// ## Returns:
+///     - positive value: number of file descriptors ready for I/O
+///     - 0: timeout occurred with no file descriptors ready
+///     - negative value: error occurred (errno set)
+pub extern "C" fn pselect6_syscall(
+    cageid: u64,
+    nfds_arg: u64,
+    nfds_cageid: u64,
+    readfds_arg: u64,
+    readfds_cageid: u64,
+    writefds_arg: u64,
+    writefds_cageid: u64,
+    exceptfds_arg: u64,
+    exceptfds_cageid: u64,
+    timeout_arg: u64,
+    timeout_cageid: u64,
+    sigmask_arg: u64,
+    sigmask_cageid: u64,
+) -> i32 {
+    let timeout = match timespec_timeout(timeout_arg, timeout_cageid, cageid) {
+        Ok(timeout) => timeout,
+        Err(e) => return syscall_error(e, "pselect6_syscall", "Invalid timeout"),
+    };
+
+    wait_with_sigmask(cageid, sigmask_arg, sigmask_cageid, || {
+        select_common(
+            cageid,
+            nfds_arg,
+            nfds_cageid,
+            readfds_arg,
+            readfds_cageid,
+            writefds_arg,
+            writefds_cageid,
+            exceptfds_arg,
+            exceptfds_cageid,
+            timeout,
+        )
+    })
+}
+
 /// Reference to Linux: https://man7.org/linux/man-pages/man2/epoll_create.2.html
 ///
 /// Linux `epoll_create()` creates an epoll instance and returns a file descriptor referring to that instance.
@@ -581,6 +873,7 @@ pub extern "C" fn epoll_create_syscall(
     // Get the virtual epfd and register to fdtables
     let virtual_epfd = fdtables::epoll_create_empty(cageid, false).unwrap();
     fdtables::epoll_add_underfd(cageid, virtual_epfd, FDKIND_KERNEL, kernel_fd as u64);
+    epoll_instance_init(cageid, virtual_epfd, kernel_fd);
 
     // Return virtual epfd
     virtual_epfd as i32
@@ -658,6 +951,7 @@ pub extern "C" fn epoll_create1_syscall(
     // Get the virtual epfd and register to fdtables
     let virtual_epfd = fdtables::epoll_create_empty(cageid, should_cloexec).unwrap();
     fdtables::epoll_add_underfd(cageid, virtual_epfd, FDKIND_KERNEL, kernel_fd as u64);
+    epoll_instance_init(cageid, virtual_epfd, kernel_fd);
 
     // Return virtual epfd
     virtual_epfd as i32
@@ -669,9 +963,10 @@ pub extern "C" fn epoll_create1_syscall(
 ///
 /// ## Implementation Approach:
 ///
-/// Uses the fdtables infrastructure to manage virtual epoll file descriptors and their associated
-/// file descriptors. The function translates virtual FDs and validates operations before calling
-/// the fdtables::virtualize_epoll_ctl() function.
+/// The interest list of the instance is kept by `crate::epoll`: kernel-backed fds are handed to
+/// the kernel epoll instance behind `epfd`, device nodes and tmpfs files are evaluated by
+/// RawPOSIX itself. The guest's `epoll_event` is only read, its `data` is handed back untouched
+/// by `epoll_wait`.
 ///
 /// ## Arguments:
 ///     - cageid: current cage identifier.
@@ -681,7 +976,7 @@ pub extern "C" fn epoll_create1_syscall(
 ///     - op_cageid: cage ID for op_arg validation.
 ///     - fd_arg: target file descriptor.
 ///     - fd_cageid: cage ID for fd_arg validation.
-///     - event_arg: pointer to epoll_event structure.
+///     - event_arg: pointer to epoll_event structure, ignored for EPOLL_CTL_DEL.
 ///     - event_cageid: cage ID for event_arg validation.
 ///     - arg5-arg6: unused arguments with their respective cage IDs.
 ///
@@ -710,95 +1005,25 @@ pub extern "C" fn epoll_ctl_syscall(
         return syscall_error(Errno::EFAULT, "epoll_ctl_syscall", "Invalid Cage ID");
     }
 
-    // Get the underfd of type FDKIND_KERNEL to the vitual fd
-    // Details see documentation on fdtables/epoll_get_underfd_hashmap.md
-    let epfd = *fdtables::epoll_get_underfd_hashmap(cageid, epfd_arg)
-        .unwrap()
-        .get(&FDKIND_KERNEL)
-        .unwrap();
-
     // Validate operation
     if op != EPOLL_CTL_ADD && op != EPOLL_CTL_MOD && op != EPOLL_CTL_DEL {
         return syscall_error(Errno::EINVAL, "epoll_ctl_syscall", "Invalid operation");
     }
 
-    // Translate virtual FDs to kernel FDs. We only need to translate this since this is a
-    // normal fd, not epfd
-    let wrappedvfd = fdtables::translate_virtual_fd(cageid, fd_arg);
-    if wrappedvfd.is_err() {
-        return syscall_error(Errno::EBADF, "epoll_ctl_syscall", "Bad File Descriptor");
-    }
-
-    let vfd = wrappedvfd.unwrap();
-
-    // Convert epoll_event
-    let user_event_opt = match sc_convert_addr_to_epollevent(event_arg, event_cageid, cageid) {
-        Ok(p) => Some(p),
-        Err(_) => {
-            if op == EPOLL_CTL_DEL {
-                None
-            } else {
-                return syscall_error(Errno::EFAULT, "epoll_ctl_syscall", "Invalid address");
-            }
+    // Convert epoll_event, which EPOLL_CTL_DEL does not need
+    let event = if op == EPOLL_CTL_DEL {
+        None
+    } else {
+        match sc_convert_addr_to_epollevent(event_arg, event_cageid, cageid) {
+            Ok(user_event) => Some((user_event.events, user_event.u64)),
+            Err(_) => return syscall_error(Errno::EFAULT, "epoll_ctl_syscall", "Invalid address"),
         }
     };
 
-    // We intentionally DO NOT overwrite the user's epoll_event inside the guest's
-    // linear memory. At this layer we translate the user-visible (virtual) FD into a
-    // kernel FD (underfd), which is not visible to user space. Mutating
-    // the guest-provided epoll_event->data to hold an underfd would leak a kernel-
-    // side detail into user memory
-    //
-    // Instead, we allocate a host-side (non-linear-memory) epoll_event and populate it
-    // with the same 'events' mask but with 'data.u64' set to the kernel FD. For DEL we
-    // pass a NULL pointer. This host-only struct is passed to libc::epoll_ctl and lives
-    // just long enough for the syscall. User memory remains untouched, and on the way
-    // back (epoll_wait) we translate from underfd to vfd using our reverse map so that
-    // user space continues to see virtual FDs/cookies only.
-    let mut tmp: Option<libc::epoll_event> = user_event_opt.map(|ue| libc::epoll_event {
-        events: ue.events,
-        u64: vfd.underfd as u64,
-    });
-
-    let kernel_epoll_event: *mut libc::epoll_event = tmp
-        .as_mut()
-        .map(|e| e as *mut libc::epoll_event)
-        .unwrap_or(ptr::null_mut());
-
-    // Call actual kernel epoll_ctl
-    let ret = unsafe { libc::epoll_ctl(epfd as i32, op, vfd.underfd as i32, kernel_epoll_event) };
-
-    if ret < 0 {
-        let errno = get_errno();
-        return handle_errno(errno, "epoll_ctl_syscall");
-    }
-
-    // Update the virtual list -- but we only handle the non-real fd case
-    //  try_epoll_ctl will directly return a real fd in libc case
-    //  - maybe we could create a new mapping table to handle the mapping relationship..?
-    //      ceate inside the fdtable interface? or we could handle inside rawposix..?
-
-    // Update the mapping table for epoll
-    if op == libc::EPOLL_CTL_DEL {
-        let mut epollmapping = REAL_EPOLL_MAP.lock();
-        if let Some(fdmap) = epollmapping.get_mut(&(epfd)) {
-            if fdmap.remove(&(vfd.underfd as i32)).is_some() {
-                if fdmap.is_empty() {
-                    epollmapping.remove(&(epfd));
-                }
-                return ret;
-            }
-        }
-    } else {
-        let mut epollmapping = REAL_EPOLL_MAP.lock();
-        epollmapping
-            .entry(epfd)
-            .or_insert_with(HashMap::new)
-            .insert(vfd.underfd as i32, fd_arg);
-        return ret;
+    match epoll_ctl_registration(cageid, epfd_arg, op, fd_arg, event) {
+        Ok(()) => 0,
+        Err(e) => syscall_error(e, "epoll_ctl_syscall", "epoll_ctl failed"),
     }
-
-    ret
 }
 
 /// Reference to Linux: https://man7.org/linux/man-pages/man2/epoll_wait.2.html
@@ -807,21 +1032,11 @@ pub extern "C" fn epoll_ctl_syscall(
 ///
 /// ## Implementation Approach:
 ///
-/// Uses the fdtables infrastructure to get virtual epoll data and handles both kernel-backed
-/// and in-memory file descriptors. For kernel FDs, calls libc::epoll_wait() on the underlying
-/// kernel epoll FD.
-///
-/// Implementation follows a 4-step isolation pattern: (1) translate virtual epoll FD to kernel FD,
-/// (2) create host-side buffer to prevent kernel writing directly to user memory, (3) call
-/// libc::epoll_wait() with timeout/signal handling, (4) translate returned kernel FDs back to
-/// virtual FDs before writing to user-space events array. This ensures kernel identifiers never leak
-/// into user memory while maintaining proper syscall semantics.
-///
-/// TODO: Implement in-memory FD support for epoll_wait
-/// For in-memory FDs, custom polling logic needs to be implemented when in-memory system
-/// is integrated. This will involve checking in-memory pipe/socket readiness and converting
-/// results back to virtual FDs. Results are converted back to virtual FDs and written to
-/// the user-space events array.
+/// The registrations RawPOSIX evaluates itself are checked first, see `crate::epoll`. If none
+/// of them is ready, the kernel epoll instance behind `epfd` is waited on in chunks of at most
+/// 100ms, checking for signals in between, until something is ready or the timeout expires.
+/// The kernel reports host fds, so it writes to a host-side buffer, and only the `data` each
+/// registration was made with is copied to the guest's events array.
 ///
 /// ## Arguments:
 ///     - cageid: current cage identifier.
@@ -859,16 +1074,32 @@ pub extern "C" fn epoll_wait_syscall(
         return syscall_error(Errno::EFAULT, "epoll_wait_syscall", "Invalid Cage ID");
     }
 
-    // Get the underfd of type FDKIND_KERNEL to the vitual fd
-    // Details see documentation on fdtables/epoll_get_underfd_hashmap.md
-    let epfd = *fdtables::epoll_get_underfd_hashmap(cageid, epfd_arg)
-        .unwrap()
-        .get(&FDKIND_KERNEL)
-        .unwrap();
+    let timeout_ms = sc_convert_sysarg_to_i32(timeout_arg, timeout_cageid, cageid);
+    let timeout = (timeout_ms >= 0).then(|| Duration::from_millis(timeout_ms as u64));
+    epoll_wait_common(
+        cageid,
+        epfd_arg,
+        events_arg,
+        events_cageid,
+        maxevents_arg,
+        maxevents_cageid,
+        timeout,
+    )
+}
 
+/// The wait of `epoll_wait_syscall`, `epoll_pwait_syscall` and `epoll_pwait2_syscall`, with
+/// `None` waiting forever
+fn epoll_wait_common(
+    cageid: u64,
+    epfd_arg: u64,
+    events_arg: u64,
+    events_cageid: u64,
+    maxevents_arg: u64,
+    maxevents_cageid: u64,
+    timeout: Option<Duration>,
+) -> i32 {
     // Convert arguments
     let maxevents = sc_convert_sysarg_to_i32(maxevents_arg, maxevents_cageid, cageid);
-    let timeout = sc_convert_sysarg_to_i32(timeout_arg, timeout_cageid, cageid);
 
     // Validate maxevents
     if maxevents <= 0 {
@@ -880,110 +1111,182 @@ pub extern "C" fn epoll_wait_syscall(
     }
 
     // Convert events array from user space
-    let mut events_ptr = match sc_convert_addr_to_epollevent(events_arg, events_cageid, cageid) {
+    let events_ptr = match sc_convert_addr_to_epollevent(events_arg, events_cageid, cageid) {
         Ok(p) => p,
-        Err(e) => return syscall_error(Errno::EFAULT, "epoll_wait_syscall", "Invalid address"),
+        Err(_) => return syscall_error(Errno::EFAULT, "epoll_wait_syscall", "Invalid address"),
     };
+    let events = unsafe { std::slice::from_raw_parts_mut(events_ptr, maxevents as usize) };
 
-    // We do not let the kernel write epoll events directly into the guest's
-    // linear memory. The kernel reports events using kernel-side identifiers
-    // (underfd) in the epoll_event.data field, which are not visible to user space
-    // in our model. To preserve isolation and avoid leaking underfd
-    // values into guest memory, we allocate a host-side (non-linear-memory) buffer
-    // `kernel_events` and pass its pointer to epoll_wait. After epoll_wait returns,
-    // we translate each reported underfd back into the corresponding virtual FD
-    // using our (cage, underfd) to vfd reverse map, and only then write the
-    // translated (events, vfd) into the user-provided events array. In short:
-    //   kernel to host buffer (underfd) --> translate to guest buffer (vfd).
-    let mut events = unsafe { std::slice::from_raw_parts_mut(events_ptr, maxevents as usize) };
-
-    let mut kernel_events: Vec<libc::epoll_event> =
-        vec![libc::epoll_event { events: 0, u64: 0 }; maxevents as usize];
-
-    if maxevents != 0 {
-        let start_time = starttimer();
-        // Keep track of total duration for our exit check in the epoll loop
-        let (duration, _) = timeout_setup_ms(timeout);
-
-        // Copy results to the guest's events array *after* translation:
-        // - `kernel_events[i].u64` holds an underfd (kernel-only).
-        // - We map (cage, underfd) to vfd and store the vfd in the user's event data.
-        // - We also copy the event mask verbatim.
-        // This ensures guest memory only ever contains guest-visible virtual FDs.
-        let mut ret;
-        loop {
-            ret = unsafe {
-                libc::epoll_wait(
-                    epfd as i32,
-                    kernel_events.as_mut_ptr(),
-                    maxevents,
-                    0, // Trigger instant return from libc epoll as we check elapsed time from start_time for our timeout handling logic
-                )
-            };
+    let start_time = starttimer();
+    // Keep track of total duration for our exit check in the epoll loop
+    let (duration, chunk_timeout) = timeout_setup(host_timeout(timeout));
 
-            if ret < 0 {
-                let errno = get_errno();
-                return handle_errno(errno, "epoll");
-            }
+    loop {
+        let current_chunk_timeout = if duration == Duration::MAX {
+            chunk_timeout
+        } else {
+            std::cmp::min(
+                chunk_timeout as u128,
+                duration.saturating_sub(readtimer(start_time)).as_millis(),
+            ) as i32
+        };
 
-            // check for timeout against total duration or if epoll_wait returned successfully.
-            if ret > 0 || readtimer(start_time) >= duration {
-                break;
-            }
+        match epoll_ready_events(cageid, epfd_arg, events, current_chunk_timeout) {
+            Ok(0) => {}
+            Ok(ready) => return ready as i32,
+            Err(e) => return syscall_error(e, "epoll_wait_syscall", "epoll_wait failed"),
+        }
 
-            // Check for signals that may have interrupted the select operation
-            // This implements POSIX signal semantics where epoll() should return EINTR
-            // if interrupted by a signal before any file descriptors become ready or timeout occurs.
-            // The signal checking happens in the retry loop to ensure we don't block indefinitely
-            if signal_check_trigger(cageid) {
-                return syscall_error(Errno::EINTR, "epoll", "interrupted");
-            }
+        if readtimer(start_time) >= duration {
+            return match virtual_timeout(cageid, timeout) {
+                Ok(()) => 0,
+                Err(e) => syscall_error(e, "epoll", "interrupted"),
+            };
         }
-        // Convert back to user's data structure
-        // Loop over virtual epollfd to find corresponding mapping relationship between kernel fd and virtual fd
-        for i in 0..ret as usize {
-            let ret_kernelfd = kernel_events[i].u64;
-            let epollmapping = REAL_EPOLL_MAP.lock();
-            let ret_virtualfd = epollmapping
-                .get(&(epfd))
-                .and_then(|kernel_map| kernel_map.get(&(ret_kernelfd as i32)).copied());
-
-            // Write back to user's buffer: store virtual fd in the u64 data field
-            events[i].u64 = ret_virtualfd.unwrap() as u64;
-            events[i].events = kernel_events[i].events;
+
+        // Check for signals that may have interrupted the epoll operation
+        // This implements POSIX signal semantics where epoll() should return EINTR
+        // if interrupted by a signal before any file descriptors become ready or timeout occurs.
+        if signal_check_trigger(cageid) {
+            return syscall_error(Errno::EINTR, "epoll", "interrupted");
         }
-        return ret;
     }
-
-    return 0; // Should never reach
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man2/socket.2.html
-///
-/// The Linux `socket()` syscall creates an endpoint for communication and returns a file descriptor
-/// for the newly created socket. This implementation wraps the syscall and registers the resulting
-/// file descriptor in our virtual file descriptor table (`fdtables`) under the current cage.
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/epoll_pwait.2.html
 ///
-/// The `fdtables` system manages per-cage file descriptors and tracks their lifecycle.
+/// Linux `epoll_pwait()` is `epoll_wait()` with a signal mask that replaces the cage's for the
+/// duration of the wait, see `wait_with_sigmask`.
 ///
-/// ## Input:
-///     - cageid: current cageid
-///     - domain_arg: communication domain (e.g., AF_INET, AF_UNIX)
-///     - socktype_arg: socket type (e.g., SOCK_STREAM, SOCK_DGRAM)
-///     - protocol_arg: protocol to be used (usually 0)
+/// ## Arguments:
+///     - cageid: current cage identifier.
+///     - epfd_arg: epoll file descriptor.
+///     - events_arg: pointer to array of epoll_event structures.
+///     - maxevents_arg: maximum number of events to return.
+///     - timeout_arg: timeout in milliseconds (-1 = infinite, 0 = non-blocking).
+///     - sigmask_arg: pointer to the signal mask to wait with, NULL to keep the cage's.
+///     - sigsetsize_arg: size of the signal mask, which has to be 8 bytes.
 ///
-/// ## Return:
-///     - On success: a newly allocated virtual file descriptor within the current cage
-///     - On failure: a negative errno value indicating the syscall error
-pub extern "C" fn socket_syscall(
+/// ## Returns:
+///     - positive value: number of file descriptors ready for I/O
+///     - 0: timeout occurred with no file descriptors ready
+///     - negative value: error occurred (errno set)
+pub extern "C" fn epoll_pwait_syscall(
     cageid: u64,
-    domain_arg: u64,
-    domain_cageid: u64,
-    socktype_arg: u64,
-    socktype_cageid: u64,
-    protocol_arg: u64,
-    protocol_cageid: u64,
-    arg4: u64,
+    epfd_arg: u64,
+    epfd_cageid: u64,
+    events_arg: u64,
+    events_cageid: u64,
+    maxevents_arg: u64,
+    maxevents_cageid: u64,
+    timeout_arg: u64,
+    timeout_cageid: u64,
+    sigmask_arg: u64,
+    sigmask_cageid: u64,
+    sigsetsize_arg: u64,
+    sigsetsize_cageid: u64,
+) -> i32 {
+    let sigsetsize = sc_convert_sysarg_to_usize(sigsetsize_arg, sigsetsize_cageid, cageid);
+    if sigmask_arg != 0 && sigsetsize != mem::size_of::<SigsetType>() {
+        return syscall_error(Errno::EINVAL, "epoll_pwait_syscall", "Invalid sigset size");
+    }
+    let timeout_ms = sc_convert_sysarg_to_i32(timeout_arg, timeout_cageid, cageid);
+    let timeout = (timeout_ms >= 0).then(|| Duration::from_millis(timeout_ms as u64));
+
+    wait_with_sigmask(cageid, sigmask_arg, sigmask_cageid, || {
+        epoll_wait_common(
+            cageid,
+            epfd_arg,
+            events_arg,
+            events_cageid,
+            maxevents_arg,
+            maxevents_cageid,
+            timeout,
+        )
+    })
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/epoll_pwait2.2.html
+///
+/// Linux `epoll_pwait2()` is `epoll_pwait()` with a nanosecond timeout, a host
+/// `struct timespec` as for `ppoll_syscall`.
+///
+/// ## Arguments:
+///     - cageid: current cage identifier.
+///     - epfd_arg: epoll file descriptor.
+///     - events_arg: pointer to array of epoll_event structures.
+///     - maxevents_arg: maximum number of events to return.
+///     - timeout_arg: pointer to a host `struct timespec`, NULL to wait forever.
+///     - sigmask_arg: pointer to the signal mask to wait with, NULL to keep the cage's.
+///     - sigsetsize_arg: size of the signal mask, which has to be 8 bytes.
+///
+/// ## Returns:
+///     - positive value: number of file descriptors ready for I/O
+///     - 0: timeout occurred with no file descriptors ready
+///     - negative value: error occurred (errno set)
+pub extern "C" fn epoll_pwait2_syscall(
+    cageid: u64,
+    epfd_arg: u64,
+    epfd_cageid: u64,
+    events_arg: u64,
+    events_cageid: u64,
+    maxevents_arg: u64,
+    maxevents_cageid: u64,
+    timeout_arg: u64,
+    timeout_cageid: u64,
+    sigmask_arg: u64,
+    sigmask_cageid: u64,
+    sigsetsize_arg: u64,
+    sigsetsize_cageid: u64,
+) -> i32 {
+    let sigsetsize = sc_convert_sysarg_to_usize(sigsetsize_arg, sigsetsize_cageid, cageid);
+    if sigmask_arg != 0 && sigsetsize != mem::size_of::<SigsetType>() {
+        return syscall_error(Errno::EINVAL, "epoll_pwait2_syscall", "Invalid sigset size");
+    }
+    let timeout = match timespec_timeout(timeout_arg, timeout_cageid, cageid) {
+        Ok(timeout) => timeout,
+        Err(e) => return syscall_error(e, "epoll_pwait2_syscall", "Invalid timeout"),
+    };
+
+    wait_with_sigmask(cageid, sigmask_arg, sigmask_cageid, || {
+        epoll_wait_common(
+            cageid,
+            epfd_arg,
+            events_arg,
+            events_cageid,
+            maxevents_arg,
+            maxevents_cageid,
+            timeout,
+        )
+    })
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/socket.2.html
+///
+/// The Linux `socket()` syscall creates an endpoint for communication and returns a file descriptor
+/// for the newly created socket. This implementation wraps the syscall and registers the resulting
+/// file descriptor in our virtual file descriptor table (`fdtables`) under the current cage.
+///
+/// The `fdtables` system manages per-cage file descriptors and tracks their lifecycle.
+///
+/// ## Input:
+///     - cageid: current cageid
+///     - domain_arg: communication domain (e.g., AF_INET, AF_UNIX)
+///     - socktype_arg: socket type (e.g., SOCK_STREAM, SOCK_DGRAM)
+///     - protocol_arg: protocol to be used (usually 0)
+///
+/// ## Return:
+///     - On success: a newly allocated virtual file descriptor within the current cage
+///     - On failure: a negative errno value indicating the syscall error
+pub extern "C" fn socket_syscall(
+    cageid: u64,
+    domain_arg: u64,
+    domain_cageid: u64,
+    socktype_arg: u64,
+    socktype_cageid: u64,
+    protocol_arg: u64,
+    protocol_cageid: u64,
+    arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
     arg5_cageid: u64,
@@ -1006,6 +1309,15 @@ pub extern "C" fn socket_syscall(
         );
     }
 
+    if let Err(e) = netpolicy_check_socket(cageid, "socket", domain, socktype) {
+        return syscall_error(e, "socket", "socket denied by network policy");
+    }
+
+    // With the network namespace enabled, internet sockets never reach the host network
+    if (domain == AF_INET || domain == AF_INET6) && netns::netns_enabled() {
+        return netns::netns_socket(cageid, domain, socktype, protocol);
+    }
+
     let kernel_fd = unsafe { libc::socket(domain, socktype, protocol) };
 
     if kernel_fd < 0 {
@@ -1043,7 +1355,8 @@ pub extern "C" fn socket_syscall(
 ///   - `fd_cageid`:      Cage id that `fd_arg` belongs to (validated when `secure` is enabled).
 ///   - `addr_arg`:       Guest virtual address of a sockaddr buffer supplied by the caller.
 ///   - `addr_cageid`:    Cage id that `addr_arg` belongs to (validated when `secure` is enabled).
-///   - `arg3..arg6`:     Unused here; must be empty/zero. When the `secure` feature is enabled,
+///   - `addrlen_arg`:    Length of the sockaddr buffer.
+///   - `arg4..arg6`:     Unused here; must be empty/zero. When the `secure` feature is enabled,
 ///                       non-empty values cause the call to fail with EFAULT (cage id misuse).
 ///
 /// ## Returns:
@@ -1055,8 +1368,8 @@ pub extern "C" fn connect_syscall(
     fd_cageid: u64,
     addr_arg: u64,
     addr_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
+    addrlen_arg: u64,
+    addrlen_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -1066,11 +1379,11 @@ pub extern "C" fn connect_syscall(
 ) -> i32 {
     let fd = convert_fd_to_host(fd_arg, fd_cageid, cageid);
     let addr = addr_arg as *mut u8;
+    let addrlen = sc_convert_sysarg_to_u32(addrlen_arg, addrlen_cageid, cageid);
 
     // would check when `secure` flag has been set during compilation,
     // no-op by default
-    if !(sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
+    if !(sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
@@ -1080,9 +1393,26 @@ pub extern "C" fn connect_syscall(
         );
     }
 
-    let (finalsockaddr, addrlen) = convert_host_sockaddr(addr, addr_cageid, cageid);
+    if let Err(e) = unsafe { netpolicy_check_destination(cageid, "connect", addr, addrlen) } {
+        return syscall_error(e, "connect", "destination denied by network policy");
+    }
 
-    let ret = unsafe { libc::connect(fd, finalsockaddr, addrlen) };
+    if let Some((hostfd, sock)) = netns_fd_socket(fd_cageid, fd_arg) {
+        return unsafe { netns::netns_connect(cageid, &sock, hostfd, addr, addrlen) };
+    }
+
+    let host_unix = unsafe { unixns_to_host(addr, addrlen) };
+    let (finalsockaddr, addrlen) = match &host_unix {
+        Some(host_addr) => (host_addr.as_ptr(), host_addr.addrlen()),
+        None => {
+            let (finalsockaddr, addrlen) = convert_host_sockaddr(addr, addr_cageid, cageid);
+            (finalsockaddr as *const sockaddr, addrlen)
+        }
+    };
+
+    let ret = interruptible(cageid, true, || unsafe {
+        libc::connect(fd, finalsockaddr, addrlen)
+    });
     if ret < 0 {
         let errno = get_errno();
         return handle_errno(errno, "connect");
@@ -1102,6 +1432,7 @@ pub extern "C" fn connect_syscall(
 ///     - cageid: current cageid
 ///     - fd_arg: virtual file descriptor to be bound
 ///     - addr_arg: pointer to a `sockaddr_un` structure containing the local address
+///     - addrlen_arg: length of that structure
 ///
 /// ## Return:
 ///     - On success: 0
@@ -1112,8 +1443,8 @@ pub extern "C" fn bind_syscall(
     fd_cageid: u64,
     addr_arg: u64,
     addr_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
+    addrlen_arg: u64,
+    addrlen_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -1123,11 +1454,11 @@ pub extern "C" fn bind_syscall(
 ) -> i32 {
     let fd = convert_fd_to_host(fd_arg, fd_cageid, cageid);
     let addr = addr_arg as *mut u8;
+    let addrlen = sc_convert_sysarg_to_u32(addrlen_arg, addrlen_cageid, cageid);
 
     // would check when `secure` flag has been set during compilation,
     // no-op by default
-    if !(sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
+    if !(sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
@@ -1137,7 +1468,27 @@ pub extern "C" fn bind_syscall(
         );
     }
 
-    let (finalsockaddr, addrlen) = convert_host_sockaddr(addr, addr_cageid, cageid);
+    if let Err(e) = unsafe { netpolicy_check_bind(cageid, addr, addrlen) } {
+        return syscall_error(e, "bind", "address denied by network policy");
+    }
+
+    if let Some((hostfd, sock)) = netns_fd_socket(fd_cageid, fd_arg) {
+        return unsafe { netns::netns_bind(&sock, hostfd, addr, addrlen) };
+    }
+
+    // Linux picks an abstract name for an empty unix address, which has to be one of ours
+    if unsafe { unixns_is_autobind(addr, addrlen) } {
+        return unixns_autobind(fd);
+    }
+
+    let host_unix = unsafe { unixns_to_host(addr, addrlen) };
+    let (finalsockaddr, addrlen) = match &host_unix {
+        Some(host_addr) => (host_addr.as_ptr(), host_addr.addrlen()),
+        None => {
+            let (finalsockaddr, addrlen) = convert_host_sockaddr(addr, addr_cageid, cageid);
+            (finalsockaddr as *const sockaddr, addrlen)
+        }
+    };
 
     let ret = unsafe { libc::bind(fd, finalsockaddr, addrlen) };
     if ret < 0 {
@@ -1192,6 +1543,19 @@ pub extern "C" fn listen_syscall(
         );
     }
 
+    let netns_sock = netns_fd_socket(fd_cageid, fd_arg);
+    let local = match &netns_sock {
+        Some((_, sock)) => sock.local_addr(),
+        None => netpolicy_local_addr(fd),
+    };
+    if let Err(e) = netpolicy_check_listen(cageid, local) {
+        return syscall_error(e, "listen", "port denied by network policy");
+    }
+
+    if let Some((hostfd, sock)) = netns_sock {
+        return netns::netns_listen(&sock, hostfd, backlog);
+    }
+
     let ret = unsafe { libc::listen(fd, backlog) };
 
     if ret < 0 {
@@ -1206,14 +1570,14 @@ pub extern "C" fn listen_syscall(
 /// The Linux `accept()` syscall extracts the first connection request on the queue of pending
 /// connections for the listening socket, creates a new connected socket, and returns a new file descriptor
 /// referring to that socket. In this implementation, we convert the virtual file descriptor to the host one,
-/// and if provided, transform the sockaddr pointer for use inside the kernel. The returned host file
-/// descriptor is then assigned a new virtual file descriptor.
+/// and if requested, copy the peer address out of a host buffer with `unixns_copy_out`. The returned host
+/// file descriptor is then assigned a new virtual file descriptor.
 ///
 /// ## Input:
 ///     - cageid: current cageid
 ///     - fd_arg: virtual file descriptor referring to the listening socket
 ///     - addr_arg: optional pointer to a buffer that will receive the address of the connecting entity
-///     - len_arg: not used in this implementation
+///     - len_arg: optional pointer to the size of that buffer, updated with the address length
 ///
 /// ## Return:
 ///     - On success: new virtual file descriptor associated with the accepted socket
@@ -1248,15 +1612,55 @@ pub extern "C" fn accept_syscall(
         );
     }
 
-    let (finalsockaddr, mut addrlen) = convert_host_sockaddr(addr, addr_cageid, cageid);
+    if let Some((hostfd, sock)) = netns_fd_socket(fd_cageid, fd_arg) {
+        let addrlen = if sc_convert_arg_nullity(len_arg, len_cageid, cageid) {
+            ptr::null_mut()
+        } else {
+            len_arg as *mut socklen_t
+        };
+        return unsafe {
+            netns::netns_accept(
+                cageid,
+                &sock,
+                hostfd,
+                addr as *mut SockAddr,
+                addrlen,
+                0,
+                "accept",
+            )
+        };
+    }
 
-    let ret_kernelfd = unsafe { libc::accept(fd, finalsockaddr, &mut addrlen as *mut u32) };
+    // As in accept4, the peer address goes through a host buffer so that unix names can be
+    // translated back
+    let mut src_storage: sockaddr_storage = unsafe { mem::zeroed() };
+    let mut src_len = mem::size_of::<sockaddr_storage>() as socklen_t;
+    let ret_kernelfd = interruptible(cageid, true, || unsafe {
+        libc::accept(
+            fd,
+            &mut src_storage as *mut _ as *mut sockaddr,
+            &mut src_len as *mut socklen_t,
+        )
+    });
 
     if ret_kernelfd < 0 {
         let errno = get_errno();
         return handle_errno(errno, "accept");
     }
 
+    if !(sc_convert_arg_nullity(addr_arg, addr_cageid, cageid)
+        || sc_convert_arg_nullity(len_arg, len_cageid, cageid))
+    {
+        unsafe {
+            unixns_copy_out(
+                addr as *mut SockAddr,
+                len_arg as *mut socklen_t,
+                &src_storage,
+                src_len,
+            )
+        };
+    }
+
     // We need to register this new kernel fd in fdtables
     let ret_virtualfd =
         fdtables::get_unused_virtual_fd(cageid, FDKIND_KERNEL, ret_kernelfd as u64, false, 0)
@@ -1265,6 +1669,105 @@ pub extern "C" fn accept_syscall(
     ret_virtualfd as i32
 }
 
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/accept4.2.html
+///
+/// The Linux `accept4()` syscall is `accept()` with a `flags` argument: `SOCK_NONBLOCK` makes the new
+/// socket non-blocking and `SOCK_CLOEXEC` marks it close-on-exec. The former is kernel state of the
+/// host socket and is passed through, the latter is recorded on the new virtual file descriptor.
+///
+/// ## Input:
+///     - cageid: current cageid
+///     - fd_arg: virtual file descriptor referring to the listening socket
+///     - addr_arg: optional pointer to a buffer that will receive the address of the connecting entity
+///     - len_arg: optional pointer to the size of that buffer, updated with the address length
+///     - flags_arg: `SOCK_NONBLOCK` and/or `SOCK_CLOEXEC`
+///
+/// ## Return:
+///     - On success: new virtual file descriptor associated with the accepted socket
+///     - On failure: a negative errno value indicating the syscall error
+pub extern "C" fn accept4_syscall(
+    cageid: u64,
+    fd_arg: u64,
+    fd_cageid: u64,
+    addr_arg: u64,
+    addr_cageid: u64,
+    len_arg: u64,
+    len_cageid: u64,
+    flags_arg: u64,
+    flags_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let fd = convert_fd_to_host(fd_arg, fd_cageid, cageid);
+    if fd < 0 {
+        return handle_errno(-fd, "accept4");
+    }
+    let flags = sc_convert_sysarg_to_i32(flags_arg, flags_cageid, cageid);
+
+    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "accept4_syscall"
+        );
+    }
+
+    if flags & !(SOCK_NONBLOCK | SOCK_CLOEXEC) != 0 {
+        return syscall_error(Errno::EINVAL, "accept4", "invalid flags");
+    }
+
+    // true means user passed NULL for that pointer
+    let want_addr = !(sc_convert_arg_nullity(addr_arg, addr_cageid, cageid)
+        || sc_convert_arg_nullity(len_arg, len_cageid, cageid));
+
+    if let Some((hostfd, sock)) = netns_fd_socket(fd_cageid, fd_arg) {
+        let (addr, addrlen) = if want_addr {
+            (addr_arg as *mut SockAddr, len_arg as *mut socklen_t)
+        } else {
+            (ptr::null_mut(), ptr::null_mut())
+        };
+        return unsafe {
+            netns::netns_accept(cageid, &sock, hostfd, addr, addrlen, flags, "accept4")
+        };
+    }
+
+    let mut src_storage: sockaddr_storage = unsafe { mem::zeroed() };
+    let mut src_len = mem::size_of::<sockaddr_storage>() as socklen_t;
+    let ret_kernelfd = interruptible(cageid, true, || unsafe {
+        libc::accept4(
+            fd,
+            &mut src_storage as *mut _ as *mut sockaddr,
+            &mut src_len as *mut socklen_t,
+            flags,
+        )
+    });
+    if ret_kernelfd < 0 {
+        let errno = get_errno();
+        return handle_errno(errno, "accept4");
+    }
+
+    if want_addr {
+        unsafe {
+            unixns_copy_out(
+                addr_arg as *mut SockAddr,
+                len_arg as *mut socklen_t,
+                &src_storage,
+                src_len,
+            );
+        }
+    }
+
+    let cloexec = (flags & SOCK_CLOEXEC) != 0;
+    match fdtables::get_unused_virtual_fd(cageid, FDKIND_KERNEL, ret_kernelfd as u64, cloexec, 0) {
+        Ok(vfd) => vfd as i32,
+        Err(_) => {
+            unsafe { libc::close(ret_kernelfd) };
+            syscall_error(Errno::EMFILE, "accept4", "Too many files opened")
+        }
+    }
+}
+
 /// Reference to Linux: https://man7.org/linux/man-pages/man2/setsockopt.2.html
 ///
 /// The Linux `setsockopt()` syscall sets options for a socket. Options may exist at multiple protocol levels.
@@ -1312,6 +1815,15 @@ pub extern "C" fn setsockopt_syscall(
         );
     }
 
+    // SO_LIND_NOSIGPIPE is RawPOSIX's own, the host does not know it
+    if level == SOL_SOCKET && optname == SO_LIND_NOSIGPIPE {
+        return unsafe { sigpipe_setsockopt(fd, optval, optlen) };
+    }
+
+    if let Some((hostfd, sock)) = netns_fd_socket(fd_cageid, fd_arg) {
+        return unsafe { netns::netns_setsockopt(&sock, hostfd, level, optname, optval, optlen) };
+    }
+
     let ret = unsafe { libc::setsockopt(fd, level, optname, optval as *mut c_void, optlen) };
 
     if ret < 0 {
@@ -1384,7 +1896,7 @@ pub extern "C" fn shutdown_syscall(
 ///
 /// This implementation follows the isolation pattern: instead of letting the kernel write
 /// directly to guest memory, we use an intermediate host-side buffer. The kernel writes
-/// to `sockaddr_storage`, then we use `copy_out_sockaddr()` to translate and copy the
+/// to `sockaddr_storage`, then we use `unixns_copy_out()` to translate and copy the
 /// result into the guest's SockAddr structure. This ensures proper isolation and allows
 /// for any necessary path transformations (e.g., for Unix domain sockets in chroot).
 ///
@@ -1432,13 +1944,14 @@ pub extern "C" fn getsockname_syscall(
         return syscall_error(Errno::EFAULT, "getsockname_syscall", "len is null");
     }
 
-    // Read initial length and clamp to storage size
-    let mut len: socklen_t = unsafe { *lenp };
-    let max_len = mem::size_of::<sockaddr_storage>() as socklen_t;
-    if len > max_len {
-        len = max_len;
+    if let Some((_, sock)) = netns_fd_socket(fd_cageid, fd_arg) {
+        return unsafe { netns::netns_getsockname(&sock, user_addr, lenp) };
     }
 
+    // The whole address is needed to translate unix names, the copy out truncates it to
+    // the guest's buffer
+    let mut len = mem::size_of::<sockaddr_storage>() as socklen_t;
+
     // Call kernel into temporary buffer (avoid writing into SockAddr directly)
     let mut storage: sockaddr_storage = unsafe { mem::zeroed() };
     let ret = unsafe {
@@ -1456,7 +1969,7 @@ pub extern "C" fn getsockname_syscall(
 
     // Copy into guest-visible SockAddr wrapper + write back len
     unsafe {
-        copy_out_sockaddr(user_addr, lenp, &storage);
+        unixns_copy_out(user_addr, lenp, &storage, len);
     }
 
     ret
@@ -1502,14 +2015,39 @@ pub extern "C" fn sendto_syscall(
     let sockaddr = sockaddr_arg as *mut u8;
     let addrlen = sc_convert_sysarg_to_u32(addrlen_arg, addrlen_cageid, cageid);
 
+    if let Err(e) = unsafe { netpolicy_check_destination(cageid, "sendto", sockaddr, addrlen) } {
+        return syscall_error(e, "sendto", "destination denied by network policy");
+    }
+
+    if let Some((hostfd, sock)) = netns_fd_socket(fd_cageid, fd_arg) {
+        let mut iov = libc::iovec {
+            iov_base: buf as *mut c_void,
+            iov_len: buflen,
+        };
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_name = sockaddr as *mut c_void;
+        msg.msg_namelen = if sockaddr.is_null() { 0 } else { addrlen };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        let ret = unsafe { netns::netns_sendmsg(cageid, &sock, hostfd, &msg, flag, "sendto") };
+        return sigpipe_check(cageid, hostfd, flag, ret);
+    }
+
     // We do not need to explicitly handle the NULL case in `sendto`,
     // because `convert_host_sockaddr` already returns `(ptr::null_mut(), 0)`
     // when the caller provides no address. In addition, sendto does not
     // modify the `sockaddr` passed in, so the pointer type does not need
     // to be mutable.
-    let (finalsockaddr, addrlen) = convert_host_sockaddr(sockaddr, sockaddr_cageid, cageid);
+    let host_unix = unsafe { unixns_to_host(sockaddr, addrlen) };
+    let (finalsockaddr, addrlen) = match &host_unix {
+        Some(host_addr) => (host_addr.as_ptr(), host_addr.addrlen()),
+        None => {
+            let (finalsockaddr, addrlen) = convert_host_sockaddr(sockaddr, sockaddr_cageid, cageid);
+            (finalsockaddr as *const sockaddr, addrlen)
+        }
+    };
 
-    let ret = unsafe {
+    let ret = interruptible(cageid, true, || unsafe {
         libc::sendto(
             fd,
             buf as *const c_void,
@@ -1518,11 +2056,11 @@ pub extern "C" fn sendto_syscall(
             finalsockaddr,
             addrlen,
         ) as i32
-    };
+    });
 
     if ret < 0 {
         let errno = get_errno();
-        return handle_errno(errno, "sendto");
+        return sigpipe_check(cageid, fd, flag, handle_errno(errno, "sendto"));
     }
 
     ret
@@ -1571,11 +2109,39 @@ pub extern "C" fn recvfrom_syscall(
     let addr_nullity = sc_convert_arg_nullity(addr_arg, addr_cageid, cageid);
     let addrlen_nullity = sc_convert_arg_nullity(addrlen_arg, addrlen_cageid, cageid);
 
+    if let Some((hostfd, sock)) = netns_fd_socket(fd_cageid, fd_arg) {
+        let want_addr = !(addr_nullity || addrlen_nullity);
+        let mut src_storage: sockaddr_storage = unsafe { mem::zeroed() };
+        let mut iov = libc::iovec {
+            iov_base: buf as *mut c_void,
+            iov_len: buflen,
+        };
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        if want_addr {
+            msg.msg_name = &mut src_storage as *mut _ as *mut c_void;
+            msg.msg_namelen = mem::size_of::<sockaddr_storage>() as socklen_t;
+        }
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        let ret =
+            unsafe { netns::netns_recvmsg(cageid, &sock, hostfd, &mut msg, flag, "recvfrom") };
+        if ret >= 0 && want_addr {
+            let lenp = addrlen_arg as *mut socklen_t;
+            if msg.msg_namelen > 0 {
+                unsafe { copy_out_sockaddr(addr_arg as *mut SockAddr, lenp, &src_storage) };
+            } else {
+                // Connected streams report no peer, as on Linux
+                unsafe { *lenp = 0 };
+            }
+        }
+        return ret;
+    }
+
     // Case 1: both NULL → caller doesn’t want peer address
     // In this case recvfrom() won’t write to addr/addrlen,
     // so we can pass null pointers directly to libc.
     if addr_nullity && addrlen_nullity {
-        let ret = unsafe {
+        let ret = interruptible(cageid, true, || unsafe {
             libc::recvfrom(
                 fd,
                 buf as *mut c_void,
@@ -1584,7 +2150,7 @@ pub extern "C" fn recvfrom_syscall(
                 ptr::null_mut(),
                 ptr::null_mut(),
             ) as i32
-        };
+        });
 
         if ret < 0 {
             let errno = get_errno();
@@ -1599,7 +2165,7 @@ pub extern "C" fn recvfrom_syscall(
 
         let mut src_storage: sockaddr_storage = unsafe { mem::zeroed() };
         let mut src_len: socklen_t = unsafe { mem::size_of::<sockaddr_storage>() as socklen_t };
-        let ret = unsafe {
+        let ret = interruptible(cageid, true, || unsafe {
             libc::recvfrom(
                 fd,
                 buf as *mut c_void,
@@ -1608,7 +2174,7 @@ pub extern "C" fn recvfrom_syscall(
                 &mut src_storage as *mut _ as *mut sockaddr,
                 &mut src_len as *mut socklen_t,
             ) as i32
-        };
+        });
 
         if ret < 0 {
             let errno = get_errno();
@@ -1618,7 +2184,7 @@ pub extern "C" fn recvfrom_syscall(
         // Copy peer address back to user’s src_addr / addrlen
         if ret >= 0 {
             unsafe {
-                copy_out_sockaddr(addr, src_len as *mut u32, &src_storage);
+                unixns_copy_out(addr, addrlen_arg as *mut socklen_t, &src_storage, src_len);
             }
         }
 
@@ -1628,9 +2194,102 @@ pub extern "C" fn recvfrom_syscall(
     0
 }
 
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/sendmsg.2.html
+///
+/// The Linux `sendmsg()` syscall sends a message, possibly gathered from several buffers and
+/// carrying ancillary data, on a socket. glibc's sendmsg.c translates the guest msghdr and
+/// iovec into a host-layout msghdr the same way recvmsg.c does, but leaves the control buffer
+/// in the guest layout: `scm::control_to_host` converts it, translating the virtual fds of
+/// `SCM_RIGHTS` and checking the credentials of `SCM_CREDENTIALS`.
+///
+/// ## Input:
+///     - cageid: identifier of the current cage
+///     - fd_arg: virtual file descriptor representing the socket
+///     - msg_arg: pointer to the host-layout msghdr built by glibc
+///     - flags_arg: flags influencing message transmission behavior
+///
+/// ## Return:
+///     - On success: number of bytes sent
+///     - On failure: negative errno indicating the error
+pub extern "C" fn sendmsg_syscall(
+    cageid: u64,
+    fd_arg: u64,
+    fd_cageid: u64,
+    msg_arg: u64,
+    msg_cageid: u64,
+    flags_arg: u64,
+    flags_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    if !(sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        return syscall_error(Errno::EFAULT, "sendmsg_syscall", "Invalid Cage ID");
+    }
+
+    let fd = convert_fd_to_host(fd_arg, fd_cageid, cageid);
+    if fd < 0 {
+        return handle_errno(-fd, "sendmsg");
+    }
+    let flags = sc_convert_sysarg_to_i32(flags_arg, flags_cageid, cageid);
+    let msg_ptr = sc_convert_buf(msg_arg, msg_cageid, cageid) as *mut libc::msghdr;
+    let mut msg = unsafe { *msg_ptr };
+
+    let dest = msg.msg_name as *const u8;
+    if let Err(e) = unsafe { netpolicy_check_destination(cageid, "sendmsg", dest, msg.msg_namelen) }
+    {
+        return syscall_error(e, "sendmsg", "destination denied by network policy");
+    }
+
+    if let Some((hostfd, sock)) = netns_fd_socket(fd_cageid, fd_arg) {
+        let ret = unsafe { netns::netns_sendmsg(cageid, &sock, hostfd, &msg, flags, "sendmsg") };
+        return sigpipe_check(cageid, hostfd, flags, ret);
+    }
+
+    let host_unix = unsafe { unixns_to_host(msg.msg_name as *const u8, msg.msg_namelen) };
+    if let Some(host_addr) = &host_unix {
+        msg.msg_name = host_addr.as_ptr() as *mut c_void;
+        msg.msg_namelen = host_addr.addrlen();
+    }
+
+    // glibc passes the guest's control buffer through as is, translated to a host pointer
+    let mut control = match unsafe {
+        scm::control_to_host(
+            cageid,
+            msg.msg_control as *const u8,
+            msg.msg_controllen as usize,
+        )
+    } {
+        Ok(control) => control,
+        Err(e) => return syscall_error(e, "sendmsg", "invalid ancillary data"),
+    };
+    msg.msg_control = control.as_mut_ptr();
+    msg.msg_controllen = control.controllen() as _;
+
+    let ret = interruptible(cageid, true, || unsafe {
+        libc::sendmsg(fd, &msg, flags) as i32
+    });
+    if ret < 0 {
+        let errno = get_errno();
+        control.finish_send(false);
+        return sigpipe_check(cageid, fd, flags, handle_errno(errno, "sendmsg"));
+    }
+    control.finish_send(true);
+    ret
+}
+
 /// recvmsg syscall: receive message from socket (wasm32 guest to host pointer translation).
 /// Reads guest msghdr/iovec (ILP32 32-bit layout), translates pointers to host,
 /// calls libc::recvmsg, copies back output fields.
+/// Ancillary data is received into a host-layout buffer and converted into the guest's
+/// buffer by `scm::control_to_guest`, which installs passed fds into this cage's fd table
+/// (close-on-exec with `MSG_CMSG_CLOEXEC`).
 /// Returns: bytes received on success, negative errno on failure.
 /// Reference: https://man7.org/linux/man-pages/man2/recvmsg.2.html
 pub extern "C" fn recvmsg_syscall(
@@ -1665,10 +2324,345 @@ pub extern "C" fn recvmsg_syscall(
     // to host layout using the split-pointer trick, so msg_arg is a host pointer
     // to a host-layout msghdr ready for libc::recvmsg.
     let msg_ptr = sc_convert_buf(msg_arg, msg_cageid, cageid) as *mut libc::msghdr;
+    let msg = unsafe { &mut *msg_ptr };
+
+    if let Some((hostfd, sock)) = netns_fd_socket(fd_cageid, fd_arg) {
+        return unsafe { netns::netns_recvmsg(cageid, &sock, hostfd, msg, flags, "recvmsg") };
+    }
+
+    // Only the control buffer is still in the guest layout. MSG_CMSG_CLOEXEC is ours to
+    // handle, the host would apply it to the host fds.
+    let guest_control = msg.msg_control as *mut u8;
+    let guest_controllen = msg.msg_controllen as usize;
+    let mut control = scm::HostControl::for_recv(guest_controllen);
+    msg.msg_control = control.as_mut_ptr();
+    msg.msg_controllen = control.controllen() as _;
+
+    // The sender's address is received on the host side too, its unix name is translated
+    // on the way out
+    let guest_name = msg.msg_name as *mut u8;
+    let guest_namelen = msg.msg_namelen;
+    let mut name: sockaddr_storage = unsafe { mem::zeroed() };
+    if !guest_name.is_null() {
+        msg.msg_name = &mut name as *mut _ as *mut c_void;
+        msg.msg_namelen = mem::size_of::<sockaddr_storage>() as socklen_t;
+    }
 
-    let ret = unsafe { libc::recvmsg(fd, msg_ptr, flags) as i32 };
+    let ret = interruptible(cageid, true, || unsafe {
+        libc::recvmsg(fd, msg_ptr, flags & !MSG_CMSG_CLOEXEC) as i32
+    });
+    msg.msg_name = guest_name as *mut c_void;
     if ret < 0 {
-        return handle_errno(get_errno(), "recvmsg");
+        let errno = get_errno();
+        msg.msg_namelen = guest_namelen;
+        msg.msg_control = guest_control as *mut c_void;
+        msg.msg_controllen = guest_controllen as _;
+        return handle_errno(errno, "recvmsg");
+    }
+    if !guest_name.is_null() {
+        msg.msg_namelen =
+            unsafe { unixns_name_out(&name, msg.msg_namelen, guest_name, guest_namelen) };
+    }
+
+    let (written, truncated) = unsafe {
+        scm::control_to_guest(
+            cageid,
+            &control,
+            msg.msg_controllen as usize,
+            guest_control,
+            guest_controllen,
+            flags,
+            (flags & MSG_CMSG_CLOEXEC) != 0,
+        )
+    };
+    msg.msg_control = guest_control as *mut c_void;
+    msg.msg_controllen = written as _;
+    if truncated {
+        msg.msg_flags |= MSG_CTRUNC;
+    }
+    ret
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/sendmmsg.2.html
+///
+/// The Linux `sendmmsg()` syscall sends several messages on a socket with a single call. glibc passes
+/// the guest `struct mmsghdr` array as is; it is translated with `typemap::convert_guest_mmsghdr` and
+/// the control buffer of every message goes through `scm::control_to_host`, as for `sendmsg()`.
+///
+/// ## Input:
+///     - cageid: identifier of the current cage
+///     - fd_arg: virtual file descriptor representing the socket
+///     - msgvec_arg: pointer to the guest mmsghdr array
+///     - vlen_arg: number of messages in the array (capped at `IOV_MAX`, as on Linux)
+///     - flags_arg: flags influencing message transmission behavior
+///
+/// ## Return:
+///     - On success: number of messages sent, with `msg_len` of each set to the bytes sent
+///     - On failure: negative errno indicating the error
+pub extern "C" fn sendmmsg_syscall(
+    cageid: u64,
+    fd_arg: u64,
+    fd_cageid: u64,
+    msgvec_arg: u64,
+    msgvec_cageid: u64,
+    vlen_arg: u64,
+    vlen_cageid: u64,
+    flags_arg: u64,
+    flags_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
+        return syscall_error(Errno::EFAULT, "sendmmsg_syscall", "Invalid Cage ID");
+    }
+
+    let fd = convert_fd_to_host(fd_arg, fd_cageid, cageid);
+    if fd < 0 {
+        return handle_errno(-fd, "sendmmsg");
+    }
+    let vlen = sc_convert_sysarg_to_u32(vlen_arg, vlen_cageid, cageid).min(IOV_MAX as u32) as usize;
+    let flags = sc_convert_sysarg_to_i32(flags_arg, flags_cageid, cageid);
+    if vlen == 0 {
+        return 0;
+    }
+
+    let mut msgs = match convert_guest_mmsghdr(msgvec_arg, msgvec_cageid, vlen, cageid) {
+        Ok(msgs) => msgs,
+        Err(e) => return syscall_error(e, "sendmmsg", "invalid message vector"),
+    };
+
+    // A message to a denied destination ends the batch, like one that cannot be sent
+    for i in 0..msgs.len() {
+        let (dest, destlen) = (msgs[i].hdr.msg_name as *const u8, msgs[i].hdr.msg_namelen);
+        if let Err(e) = unsafe { netpolicy_check_destination(cageid, "sendmmsg", dest, destlen) } {
+            if i == 0 {
+                return syscall_error(e, "sendmmsg", "destination denied by network policy");
+            }
+            msgs.truncate(i);
+            break;
+        }
+    }
+
+    if let Some((hostfd, sock)) = netns_fd_socket(fd_cageid, fd_arg) {
+        let mut sent = 0;
+        for (i, msg) in msgs.iter().enumerate() {
+            let ret =
+                unsafe { netns::netns_sendmsg(cageid, &sock, hostfd, &msg.hdr, flags, "sendmmsg") };
+            let ret = sigpipe_check(cageid, hostfd, flags, ret);
+            if ret < 0 {
+                return if sent == 0 { ret } else { sent };
+            }
+            unsafe { copy_out_mmsghdr(msgvec_arg, i, &msg.hdr, ret as u32) };
+            sent += 1;
+        }
+        return sent;
+    }
+
+    // As on Linux, a message that cannot be sent ends the batch; the error is only
+    // reported if it is the first one
+    let mut controls = Vec::with_capacity(vlen);
+    for msg in msgs.iter() {
+        let control = match unsafe {
+            scm::control_to_host(
+                cageid,
+                msg.hdr.msg_control as *const u8,
+                msg.hdr.msg_controllen,
+            )
+        } {
+            Ok(control) => control,
+            Err(e) if controls.is_empty() => {
+                return syscall_error(e, "sendmmsg", "invalid ancillary data")
+            }
+            Err(_) => break,
+        };
+        controls.push(control);
+    }
+
+    let host_unix: Vec<_> = msgs
+        .iter()
+        .map(|msg| unsafe { unixns_to_host(msg.hdr.msg_name as *const u8, msg.hdr.msg_namelen) })
+        .collect();
+    let mut host_msgs: Vec<libc::mmsghdr> = msgs
+        .iter()
+        .zip(controls.iter_mut())
+        .zip(host_unix.iter())
+        .map(|((msg, control), host_addr)| {
+            let mut msg_hdr = msg.hdr;
+            msg_hdr.msg_control = control.as_mut_ptr();
+            msg_hdr.msg_controllen = control.controllen();
+            if let Some(host_addr) = host_addr {
+                msg_hdr.msg_name = host_addr.as_ptr() as *mut c_void;
+                msg_hdr.msg_namelen = host_addr.addrlen();
+            }
+            libc::mmsghdr {
+                msg_hdr,
+                msg_len: 0,
+            }
+        })
+        .collect();
+
+    let ret = interruptible(cageid, true, || unsafe {
+        libc::sendmmsg(fd, host_msgs.as_mut_ptr(), host_msgs.len() as u32, flags)
+    });
+    let errno = get_errno();
+
+    let sent = ret.max(0) as usize;
+    for (i, control) in controls.into_iter().enumerate() {
+        control.finish_send(i < sent);
+    }
+    if ret < 0 {
+        return sigpipe_check(cageid, fd, flags, handle_errno(errno, "sendmmsg"));
+    }
+    for (i, host_msg) in host_msgs.iter().take(sent).enumerate() {
+        unsafe { copy_out_mmsghdr(msgvec_arg, i, &msgs[i].hdr, host_msg.msg_len) };
+    }
+    ret
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/recvmmsg.2.html
+///
+/// The Linux `recvmmsg()` syscall receives several messages from a socket with a single call. The
+/// guest `struct mmsghdr` array is translated like for `sendmmsg_syscall`, and the ancillary data of
+/// every received message is converted back with `scm::control_to_guest`, as for `recvmsg()`.
+///
+/// ## Input:
+///     - cageid: identifier of the current cage
+///     - fd_arg: virtual file descriptor representing the socket
+///     - msgvec_arg: pointer to the guest mmsghdr array
+///     - vlen_arg: number of messages in the array (capped at `IOV_MAX`, as on Linux)
+///     - flags_arg: flags controlling message reception behavior
+///     - timeout_arg: optional pointer to a `struct timespec` bounding the whole call
+///
+/// ## Return:
+///     - On success: number of messages received
+///     - On failure: negative errno indicating the error
+pub extern "C" fn recvmmsg_syscall(
+    cageid: u64,
+    fd_arg: u64,
+    fd_cageid: u64,
+    msgvec_arg: u64,
+    msgvec_cageid: u64,
+    vlen_arg: u64,
+    vlen_cageid: u64,
+    flags_arg: u64,
+    flags_cageid: u64,
+    timeout_arg: u64,
+    timeout_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    if !sc_unusedarg(arg6, arg6_cageid) {
+        return syscall_error(Errno::EFAULT, "recvmmsg_syscall", "Invalid Cage ID");
+    }
+
+    let fd = convert_fd_to_host(fd_arg, fd_cageid, cageid);
+    if fd < 0 {
+        return handle_errno(-fd, "recvmmsg");
+    }
+    let vlen = sc_convert_sysarg_to_u32(vlen_arg, vlen_cageid, cageid).min(IOV_MAX as u32) as usize;
+    let flags = sc_convert_sysarg_to_i32(flags_arg, flags_cageid, cageid);
+    let timeout = if sc_convert_arg_nullity(timeout_arg, timeout_cageid, cageid) {
+        ptr::null_mut()
+    } else {
+        timeout_arg as *mut libc::timespec
+    };
+    if vlen == 0 {
+        return 0;
+    }
+
+    let msgs = match convert_guest_mmsghdr(msgvec_arg, msgvec_cageid, vlen, cageid) {
+        Ok(msgs) => msgs,
+        Err(e) => return syscall_error(e, "recvmmsg", "invalid message vector"),
+    };
+
+    // Sockets of the network namespace receive one message at a time. Only the first one may
+    // block, so the timeout has nothing left to bound.
+    if let Some((hostfd, sock)) = netns_fd_socket(fd_cageid, fd_arg) {
+        let mut received = 0;
+        for (i, msg) in msgs.iter().enumerate() {
+            let mut hdr = msg.hdr;
+            let flags = if i == 0 { flags } else { flags | MSG_DONTWAIT };
+            let ret =
+                unsafe { netns::netns_recvmsg(cageid, &sock, hostfd, &mut hdr, flags, "recvmmsg") };
+            if ret < 0 {
+                return if received == 0 { ret } else { received };
+            }
+            unsafe { copy_out_mmsghdr(msgvec_arg, i, &hdr, ret as u32) };
+            received += 1;
+        }
+        return received;
+    }
+
+    let mut controls: Vec<scm::HostControl> = msgs
+        .iter()
+        .map(|msg| scm::HostControl::for_recv(msg.hdr.msg_controllen))
+        .collect();
+    // Sender addresses are translated on the way out, see recvmsg_syscall
+    let mut names: Vec<sockaddr_storage> = vec![unsafe { mem::zeroed() }; msgs.len()];
+    let mut host_msgs: Vec<libc::mmsghdr> = msgs
+        .iter()
+        .zip(controls.iter_mut())
+        .zip(names.iter_mut())
+        .map(|((msg, control), name)| {
+            let mut msg_hdr = msg.hdr;
+            msg_hdr.msg_control = control.as_mut_ptr();
+            msg_hdr.msg_controllen = control.controllen();
+            if !msg_hdr.msg_name.is_null() {
+                msg_hdr.msg_name = name as *mut _ as *mut c_void;
+                msg_hdr.msg_namelen = mem::size_of::<sockaddr_storage>() as socklen_t;
+            }
+            libc::mmsghdr {
+                msg_hdr,
+                msg_len: 0,
+            }
+        })
+        .collect();
+
+    // MSG_CMSG_CLOEXEC applies to the virtual fds, see recvmsg_syscall
+    let ret = interruptible(cageid, true, || unsafe {
+        libc::recvmmsg(
+            fd,
+            host_msgs.as_mut_ptr(),
+            host_msgs.len() as u32,
+            flags & !MSG_CMSG_CLOEXEC,
+            timeout,
+        )
+    });
+    if ret < 0 {
+        let errno = get_errno();
+        return handle_errno(errno, "recvmmsg");
+    }
+
+    for (i, host_msg) in host_msgs.iter().take(ret as usize).enumerate() {
+        let (written, truncated) = unsafe {
+            scm::control_to_guest(
+                cageid,
+                &controls[i],
+                host_msg.msg_hdr.msg_controllen,
+                msgs[i].hdr.msg_control as *mut u8,
+                msgs[i].hdr.msg_controllen,
+                flags,
+                (flags & MSG_CMSG_CLOEXEC) != 0,
+            )
+        };
+        let mut hdr = host_msg.msg_hdr;
+        hdr.msg_controllen = written;
+        if !msgs[i].hdr.msg_name.is_null() {
+            hdr.msg_namelen = unsafe {
+                unixns_name_out(
+                    &names[i],
+                    hdr.msg_namelen,
+                    msgs[i].hdr.msg_name as *mut u8,
+                    msgs[i].hdr.msg_namelen,
+                )
+            };
+        }
+        if truncated {
+            hdr.msg_flags |= MSG_CTRUNC;
+        }
+        unsafe { copy_out_mmsghdr(msgvec_arg, i, &hdr, host_msg.msg_len) };
     }
     ret
 }
@@ -1677,7 +2671,7 @@ pub extern "C" fn recvmsg_syscall(
 ///
 /// The Linux `gethostname()` syscall returns the current host name of the system.
 /// This implementation retrieves the destination buffer and length from the current cage,
-/// and stores the host name into user space.
+/// and stores the node name of the cage's UTS record (not the host's) into user space.
 ///
 /// ## Input:
 ///     - cageid: identifier of the current cage
@@ -1718,13 +2712,22 @@ pub extern "C" fn gethostname_syscall(
         );
     }
 
-    let ret = unsafe { libc::gethostname(name as *mut i8, len) };
-    if ret < 0 {
-        let errno = get_errno();
-        return handle_errno(errno, "gethostname");
-    }
+    // The cage's own host name, see `crate::uts`
+    let cage = get_cage(cageid).unwrap();
+    let nodename = cage.uts.read().nodename;
+    let hostname = uts_field(&nodename);
 
-    ret
+    // Like glibc, copy what fits and report a name that does not fit with its NUL
+    if len > 0 && name.is_null() {
+        return syscall_error(Errno::EFAULT, "gethostname", "name is null");
+    }
+    let copied = hostname.len().min(len);
+    unsafe { ptr::copy_nonoverlapping(hostname.as_ptr(), name, copied) };
+    if copied < len {
+        unsafe { *name.add(copied) = 0 };
+        return 0;
+    }
+    syscall_error(Errno::ENAMETOOLONG, "gethostname", "buffer too small")
 }
 
 /// Reference to Linux: https://man7.org/linux/man-pages/man2/getsockopt.2.html
@@ -1773,6 +2776,16 @@ pub extern "C" fn getsockopt_syscall(
         );
     }
 
+    if level == SOL_SOCKET && optname == SO_LIND_NOSIGPIPE {
+        return unsafe { sigpipe_getsockopt(fd, optval as *mut u8, optlen) };
+    }
+
+    if let Some((hostfd, sock)) = netns_fd_socket(fd_cageid, fd_arg) {
+        return unsafe {
+            netns::netns_getsockopt(&sock, hostfd, level, optname, optval as *mut u8, optlen)
+        };
+    }
+
     let ret = unsafe { libc::getsockopt(fd, level, optname, optval, optlen) };
     if ret < 0 {
         let errno = get_errno();
@@ -1792,6 +2805,7 @@ pub extern "C" fn getsockopt_syscall(
 ///     - cageid: identifier of the current cage
 ///     - fd_arg: virtual file descriptor of the connected socket
 ///     - addr_arg: pointer to a buffer in user space to store the peer address
+///     - addrlen_arg: pointer to the size of that buffer, updated with the address length
 ///
 /// ## Return:
 ///     - On success: 0  
@@ -1802,8 +2816,8 @@ pub extern "C" fn getpeername_syscall(
     fd_cageid: u64,
     addr_arg: u64,
     addr_cageid: u64,
-    arg3: u64,
-    arg3_cageid: u64,
+    addrlen_arg: u64,
+    addrlen_cageid: u64,
     arg4: u64,
     arg4_cageid: u64,
     arg5: u64,
@@ -1816,8 +2830,7 @@ pub extern "C" fn getpeername_syscall(
 
     // would check when `secure` flag has been set during compilation,
     // no-op by default
-    if !(sc_unusedarg(arg3, arg3_cageid)
-        && sc_unusedarg(arg4, arg4_cageid)
+    if !(sc_unusedarg(arg4, arg4_cageid)
         && sc_unusedarg(arg5, arg5_cageid)
         && sc_unusedarg(arg6, arg6_cageid))
     {
@@ -1827,14 +2840,35 @@ pub extern "C" fn getpeername_syscall(
         );
     }
 
-    let (finalsockaddr, mut addrlen) = convert_host_sockaddr(addr, addr_cageid, cageid);
-    let ret = unsafe { libc::getpeername(fd, finalsockaddr, &mut addrlen as *mut u32) };
+    if sc_convert_arg_nullity(addrlen_arg, addrlen_cageid, cageid) {
+        return syscall_error(Errno::EFAULT, "getpeername", "len is null");
+    }
+    let lenp = addrlen_arg as *mut socklen_t;
+
+    if let Some((_, sock)) = netns_fd_socket(fd_cageid, fd_arg) {
+        return unsafe { netns::netns_getpeername(&sock, addr as *mut SockAddr, lenp) };
+    }
+
+    // Like getsockname_syscall, through a host buffer
+    let mut storage: sockaddr_storage = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<sockaddr_storage>() as socklen_t;
+    let ret = unsafe {
+        libc::getpeername(
+            fd,
+            &mut storage as *mut _ as *mut sockaddr,
+            &mut len as *mut socklen_t,
+        )
+    };
 
     if ret < 0 {
         let errno = get_errno();
         return handle_errno(errno, "getpeername");
     }
 
+    if !sc_convert_arg_nullity(addr_arg, addr_cageid, cageid) {
+        unsafe { unixns_copy_out(addr as *mut SockAddr, lenp, &storage, len) };
+    }
+
     ret
 }
 
@@ -1888,6 +2922,10 @@ pub extern "C" fn socketpair_syscall(
         );
     }
 
+    if let Err(e) = netpolicy_check_socket(cageid, "socketpair", domain, typ) {
+        return syscall_error(e, "socketpair", "socket denied by network policy");
+    }
+
     let mut kernel_socket_vector: [i32; 2] = [0, 0];
 
     let ret = unsafe { libc::socketpair(domain, typ, protocol, kernel_socket_vector.as_mut_ptr()) };
@@ -0,0 +1,902 @@
+//! Virtual network namespace
+//!
+//! With `--netns`, the cages of a lind instance get a network of their own instead of the
+//! host's. Every `AF_INET` / `AF_INET6` TCP or UDP socket lives on a private loopback network
+//! that only the cages of this instance can reach:
+//! - ports come from a port space of the instance, so parallel runs never collide;
+//! - every loopback address and the unspecified address refer to the namespace's only host,
+//!   and connecting or sending anywhere else fails with `ENETUNREACH`;
+//! - selected ports can be forwarded from the host (`--netns-forward GUEST_PORT:HOST_PORT`),
+//!   so that host tools can reach a service running in a cage.
+//!
+//! The traffic is carried by host `AF_UNIX` sockets of the same type, bound to abstract names
+//! that encode the instance and the port (`lind-netns.<pid>.tcp.<port>`). Binding a port binds
+//! the name, connecting to a port connects to the name, and the name of the peer of an accepted
+//! connection or a received datagram gives its port back. The virtual fd has kind
+//! `FDKIND_NETNS` with the host socket as its `underfd`, so reads, writes, `poll`, `select` and
+//! `epoll` work on it like on a kernel fd. What this module keeps is what the host socket
+//! cannot know: the addresses the guest sees, translated at `bind`, `connect`, `accept`,
+//! `sendto`, `recvfrom`, `sendmsg`, `recvmsg`, `getsockname` and `getpeername`, and the IP and
+//! TCP level socket options, which are recorded and reported back but have no effect.
+//!
+//! Peers are always reported as `127.0.0.1` (`::1` on an `AF_INET6` socket), and ancillary
+//! data is not supported.
+
+use crate::epoll::epoll_forget;
+use crate::interrupt::interruptible;
+use crate::sigio::sigio_forget;
+use crate::sigpipe::sigpipe_forget;
+use dashmap::DashMap;
+use fdtables;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::io;
+use std::mem::{size_of, zeroed};
+use std::net::{
+    IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, SocketAddrV4, SocketAddrV6, TcpListener,
+    TcpStream,
+};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::net::UnixStream;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use sysdefs::constants::err_const::{get_errno, handle_errno, syscall_error, Errno};
+use sysdefs::constants::lind_platform_const::FDKIND_NETNS;
+use sysdefs::constants::net_const::{
+    AF_INET, AF_INET6, AF_UNIX, AF_UNSPEC, IPPROTO_IP, IPPROTO_IPV6, IPPROTO_TCP, IPPROTO_UDP,
+    MSG_CMSG_CLOEXEC, SOCK_CLOEXEC, SOCK_DGRAM, SOCK_NONBLOCK, SOCK_STREAM, SOL_SOCKET, SO_DOMAIN,
+    SO_PROTOCOL,
+};
+use sysdefs::data::net_struct::SockAddr;
+use typemap::network_helpers::copy_out_sockaddr;
+
+/// Ephemeral ports, Linux's default `ip_local_port_range`
+const EPHEMERAL_FIRST: u16 = 32768;
+const EPHEMERAL_LAST: u16 = 60999;
+
+/// Namespace configuration, set once by `netns_init`
+struct NetnsConfig {
+    /// Host ports forwarded into the namespace, by guest port
+    forwards: HashMap<u16, u16>,
+}
+
+static NETNS: OnceLock<NetnsConfig> = OnceLock::new();
+
+static NEXT_EPHEMERAL: AtomicU16 = AtomicU16::new(EPHEMERAL_FIRST);
+
+lazy_static! {
+    /// Namespace sockets, keyed by their host socket
+    static ref NETNS_SOCKETS: DashMap<u64, Arc<NetnsSocket>> = DashMap::new();
+    /// Prefix of the abstract names of this instance
+    static ref NAME_PREFIX: String = format!("lind-netns.{}.", std::process::id());
+}
+
+/// A TCP or UDP socket of the namespace
+pub struct NetnsSocket {
+    domain: i32,
+    socktype: i32,
+    state: Mutex<SocketState>,
+}
+
+#[derive(Default)]
+struct SocketState {
+    local: Option<SocketAddr>,
+    peer: Option<SocketAddr>,
+    /// IP and TCP level options, by (level, optname)
+    options: HashMap<(i32, i32), i32>,
+    /// Host port forwarded to this socket while it listens
+    forward: Option<Arc<Forward>>,
+}
+
+/// Enable the namespace, forwarding each `(guest port, host port)` of `forwards`. Has to be
+/// called before `rawposix_start`.
+///
+/// ## Returns:
+/// - `Ok(())` on success
+/// - `Err(EINVAL)` for port 0 or a guest port forwarded twice, `Err(EBUSY)` if the namespace
+///   was already enabled
+pub fn netns_init(forwards: &[(u16, u16)]) -> Result<(), Errno> {
+    let mut map = HashMap::new();
+    for &(guest_port, host_port) in forwards {
+        if guest_port == 0 || host_port == 0 || map.insert(guest_port, host_port).is_some() {
+            return Err(Errno::EINVAL);
+        }
+    }
+    NETNS
+        .set(NetnsConfig { forwards: map })
+        .map_err(|_| Errno::EBUSY)
+}
+
+/// Whether `AF_INET` / `AF_INET6` sockets go to the namespace instead of the host
+pub fn netns_enabled() -> bool {
+    NETNS.get().is_some()
+}
+
+/// Look up the namespace socket behind a virtual fd, together with its host socket. Returns
+/// `None` for fds of any other kind.
+pub fn netns_fd_socket(cageid: u64, vfd: u64) -> Option<(i32, Arc<NetnsSocket>)> {
+    match fdtables::translate_virtual_fd(cageid, vfd) {
+        Ok(entry) if entry.fdkind == FDKIND_NETNS => NETNS_SOCKETS
+            .get(&entry.underfd)
+            .map(|sock| (entry.underfd as i32, Arc::clone(sock.value()))),
+        _ => None,
+    }
+}
+
+/// Close handler registered for `FDKIND_NETNS`, called once the last fd referring to a socket
+/// goes away. Closing the host socket releases its port.
+pub fn netns_close(fdentry: fdtables::FDTableEntry, _count: u64) {
+    epoll_forget(FDKIND_NETNS, fdentry.underfd);
+    sigio_forget(FDKIND_NETNS, fdentry.underfd);
+    sigpipe_forget(fdentry.underfd as i32);
+    if let Some((_, sock)) = NETNS_SOCKETS.remove(&fdentry.underfd) {
+        if let Some(forward) = sock.state.lock().forward.take() {
+            forward.stop();
+        }
+    }
+    unsafe { libc::close(fdentry.underfd as i32) };
+}
+
+fn last_errno() -> Errno {
+    Errno::from_discriminant(get_errno()).unwrap_or(Errno::EIO)
+}
+
+fn io_errno(e: io::Error) -> Errno {
+    e.raw_os_error()
+        .and_then(|errno| Errno::from_discriminant(errno).ok())
+        .unwrap_or(Errno::EIO)
+}
+
+/// Abstract name of `port` in the port space of `socktype`
+fn host_name(socktype: i32, port: u16) -> (libc::sockaddr_un, libc::socklen_t) {
+    let proto = if socktype == SOCK_STREAM {
+        "tcp"
+    } else {
+        "udp"
+    };
+    let name = format!("{}{}.{}", *NAME_PREFIX, proto, port);
+
+    let mut sun: libc::sockaddr_un = unsafe { zeroed() };
+    sun.sun_family = AF_UNIX as libc::sa_family_t;
+    // sun_path[0] stays 0, which makes the name abstract
+    for (dst, src) in sun.sun_path[1..].iter_mut().zip(name.bytes()) {
+        *dst = src as libc::c_char;
+    }
+    let len = size_of::<libc::sa_family_t>() + 1 + name.len();
+    (sun, len as libc::socklen_t)
+}
+
+/// Port encoded in the abstract name of a peer, or `None` if the peer is not bound to a name of
+/// this instance
+fn port_of_host_name(sun: &libc::sockaddr_un, len: libc::socklen_t) -> Option<u16> {
+    let path_len = (len as usize)
+        .checked_sub(size_of::<libc::sa_family_t>())?
+        .min(sun.sun_path.len());
+    if path_len < 1 || sun.sun_path[0] != 0 {
+        return None;
+    }
+    let name: Vec<u8> = sun.sun_path[1..path_len].iter().map(|c| *c as u8).collect();
+    let rest = name.strip_prefix(NAME_PREFIX.as_bytes())?;
+    let dot = rest.iter().position(|c| *c == b'.')?;
+    std::str::from_utf8(&rest[dot + 1..]).ok()?.parse().ok()
+}
+
+fn host_bind(fd: i32, socktype: i32, port: u16) -> Result<(), Errno> {
+    let (sun, len) = host_name(socktype, port);
+    if unsafe { libc::bind(fd, &sun as *const _ as *const libc::sockaddr, len) } < 0 {
+        return Err(last_errno());
+    }
+    Ok(())
+}
+
+/// Bind a host socket to `port`, or to a free ephemeral port if `port` is 0.
+///
+/// ## Returns:
+/// - `Ok(port)` with the port bound
+/// - `Err(EADDRINUSE)` if the port, or every ephemeral port, is taken
+fn bind_port(fd: i32, socktype: i32, port: u16) -> Result<u16, Errno> {
+    if port != 0 {
+        return host_bind(fd, socktype, port).map(|_| port);
+    }
+    for _ in EPHEMERAL_FIRST..=EPHEMERAL_LAST {
+        let port = NEXT_EPHEMERAL
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |port| {
+                Some(if port >= EPHEMERAL_LAST {
+                    EPHEMERAL_FIRST
+                } else {
+                    port + 1
+                })
+            })
+            .unwrap();
+        match host_bind(fd, socktype, port) {
+            Err(Errno::EADDRINUSE) => continue,
+            res => return res.map(|_| port),
+        }
+    }
+    Err(Errno::EADDRINUSE)
+}
+
+/// A host socket bound to `port` of the namespace, for a service RawPOSIX runs inside it (see
+/// `crate::resolver`). Cages reach it on the loopback address like a socket of another cage.
+pub(crate) fn netns_service_socket(socktype: i32, port: u16) -> Result<OwnedFd, Errno> {
+    let fd = unsafe { libc::socket(AF_UNIX, socktype | SOCK_CLOEXEC, 0) };
+    if fd < 0 {
+        return Err(last_errno());
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+    host_bind(fd.as_raw_fd(), socktype, port)?;
+    Ok(fd)
+}
+
+/// Read a guest `sockaddr_in` / `sockaddr_in6`
+///
+/// # Safety
+/// `addr` must be NULL or point to `len` readable bytes.
+pub(crate) unsafe fn read_inet(addr: *const u8, len: u32) -> Result<SocketAddr, Errno> {
+    if addr.is_null() {
+        return Err(Errno::EFAULT);
+    }
+    if (len as usize) < size_of::<libc::sa_family_t>() {
+        return Err(Errno::EINVAL);
+    }
+    match (addr as *const libc::sa_family_t).read_unaligned() as i32 {
+        AF_INET if len as usize >= size_of::<libc::sockaddr_in>() => {
+            let sin = (addr as *const libc::sockaddr_in).read_unaligned();
+            Ok(SocketAddr::V4(SocketAddrV4::new(
+                Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr)),
+                u16::from_be(sin.sin_port),
+            )))
+        }
+        AF_INET6 if len as usize >= size_of::<libc::sockaddr_in6>() => {
+            let sin6 = (addr as *const libc::sockaddr_in6).read_unaligned();
+            Ok(SocketAddr::V6(SocketAddrV6::new(
+                Ipv6Addr::from(sin6.sin6_addr.s6_addr),
+                u16::from_be(sin6.sin6_port),
+                sin6.sin6_flowinfo,
+                sin6.sin6_scope_id,
+            )))
+        }
+        AF_INET | AF_INET6 => Err(Errno::EINVAL),
+        _ => Err(Errno::EAFNOSUPPORT),
+    }
+}
+
+/// `addr` as a host `sockaddr_storage`, with its length
+fn to_storage(addr: SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+    let mut storage: libc::sockaddr_storage = unsafe { zeroed() };
+    let len = match addr {
+        SocketAddr::V4(addr) => {
+            let sin = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in) };
+            sin.sin_family = AF_INET as libc::sa_family_t;
+            sin.sin_port = addr.port().to_be();
+            sin.sin_addr.s_addr = u32::from(*addr.ip()).to_be();
+            size_of::<libc::sockaddr_in>()
+        }
+        SocketAddr::V6(addr) => {
+            let sin6 = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in6) };
+            sin6.sin6_family = AF_INET6 as libc::sa_family_t;
+            sin6.sin6_port = addr.port().to_be();
+            sin6.sin6_flowinfo = addr.flowinfo();
+            sin6.sin6_addr.s6_addr = addr.ip().octets();
+            sin6.sin6_scope_id = addr.scope_id();
+            size_of::<libc::sockaddr_in6>()
+        }
+    };
+    (storage, len as libc::socklen_t)
+}
+
+/// Whether `ip` is an address of the namespace's only host
+fn is_local(ip: IpAddr) -> bool {
+    let is_local_v4 = |ip: Ipv4Addr| ip.is_loopback() || ip.is_unspecified();
+    match ip {
+        IpAddr::V4(ip) => is_local_v4(ip),
+        IpAddr::V6(ip) => {
+            ip.is_loopback() || ip.is_unspecified() || ip.to_ipv4_mapped().is_some_and(is_local_v4)
+        }
+    }
+}
+
+impl NetnsSocket {
+    /// Address the socket is bound to, as the guest sees it
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.state.lock().local
+    }
+
+    fn loopback(&self, port: u16) -> SocketAddr {
+        if self.domain == AF_INET6 {
+            SocketAddr::new(Ipv6Addr::LOCALHOST.into(), port)
+        } else {
+            SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port)
+        }
+    }
+
+    fn unspecified(&self, port: u16) -> SocketAddr {
+        if self.domain == AF_INET6 {
+            SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), port)
+        } else {
+            SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), port)
+        }
+    }
+
+    /// Read a guest address for this socket, which has to be of the socket's family
+    ///
+    /// # Safety
+    /// See `read_inet`.
+    unsafe fn read_addr(&self, addr: *const u8, len: u32) -> Result<SocketAddr, Errno> {
+        let addr = read_inet(addr, len)?;
+        let family = if addr.is_ipv4() { AF_INET } else { AF_INET6 };
+        if family != self.domain {
+            return Err(Errno::EAFNOSUPPORT);
+        }
+        Ok(addr)
+    }
+
+    /// Bind an unbound socket to an ephemeral port, as Linux does on `connect()`, `listen()`
+    /// and the first `sendto()`
+    fn autobind(&self, fd: i32, state: &mut SocketState) -> Result<(), Errno> {
+        if state.local.is_none() {
+            let port = bind_port(fd, self.socktype, 0)?;
+            state.local = Some(self.unspecified(port));
+        }
+        Ok(())
+    }
+
+    /// Whether options of `level` exist on this socket. `SOL_SOCKET` ones are the host socket's.
+    fn has_level(&self, level: i32) -> bool {
+        match level {
+            IPPROTO_IP => true,
+            IPPROTO_IPV6 => self.domain == AF_INET6,
+            IPPROTO_TCP => self.socktype == SOCK_STREAM,
+            IPPROTO_UDP => self.socktype == SOCK_DGRAM,
+            _ => false,
+        }
+    }
+}
+
+/// Register a host socket as a namespace socket and give it a virtual fd in `cageid`
+fn install(cageid: u64, fd: i32, sock: NetnsSocket, cloexec: bool, syscall: &str) -> i32 {
+    NETNS_SOCKETS.insert(fd as u64, Arc::new(sock));
+    match fdtables::get_unused_virtual_fd(cageid, FDKIND_NETNS, fd as u64, cloexec, 0) {
+        Ok(vfd) => vfd as i32,
+        Err(_) => {
+            NETNS_SOCKETS.remove(&(fd as u64));
+            unsafe { libc::close(fd) };
+            syscall_error(Errno::EMFILE, syscall, "Too many files opened")
+        }
+    }
+}
+
+/// `socket()` for `AF_INET` / `AF_INET6` while the namespace is enabled. Only TCP and UDP
+/// sockets exist in the namespace.
+pub fn netns_socket(cageid: u64, domain: i32, socktype: i32, protocol: i32) -> i32 {
+    let flags = socktype & (SOCK_NONBLOCK | SOCK_CLOEXEC);
+    let socktype = socktype & !flags;
+    let expected_protocol = match socktype {
+        SOCK_STREAM => IPPROTO_TCP,
+        SOCK_DGRAM => IPPROTO_UDP,
+        _ => {
+            return syscall_error(
+                Errno::EPROTONOSUPPORT,
+                "socket",
+                "only TCP and UDP exist in the network namespace",
+            )
+        }
+    };
+    if protocol != 0 && protocol != expected_protocol {
+        return syscall_error(Errno::EPROTONOSUPPORT, "socket", "protocol not supported");
+    }
+
+    let fd = unsafe { libc::socket(AF_UNIX, socktype | (flags & SOCK_NONBLOCK), 0) };
+    if fd < 0 {
+        return handle_errno(get_errno(), "socket");
+    }
+
+    let sock = NetnsSocket {
+        domain,
+        socktype,
+        state: Mutex::new(SocketState::default()),
+    };
+    install(cageid, fd, sock, (flags & SOCK_CLOEXEC) != 0, "socket")
+}
+
+/// `bind()` on a namespace socket. Only local addresses can be bound, and port 0 picks an
+/// ephemeral port.
+///
+/// # Safety
+/// `addr` must be NULL or point to `addrlen` readable bytes.
+pub unsafe fn netns_bind(sock: &NetnsSocket, fd: i32, addr: *const u8, addrlen: u32) -> i32 {
+    let target = match unsafe { sock.read_addr(addr, addrlen) } {
+        Ok(target) => target,
+        Err(e) => return syscall_error(e, "bind", "invalid address"),
+    };
+    if !is_local(target.ip()) {
+        return syscall_error(
+            Errno::EADDRNOTAVAIL,
+            "bind",
+            "address is not in the network namespace",
+        );
+    }
+
+    let mut state = sock.state.lock();
+    if state.local.is_some() {
+        return syscall_error(Errno::EINVAL, "bind", "socket is already bound");
+    }
+    match bind_port(fd, sock.socktype, target.port()) {
+        Ok(port) => {
+            state.local = Some(SocketAddr::new(target.ip(), port));
+            0
+        }
+        Err(e) => syscall_error(e, "bind", "cannot bind port"),
+    }
+}
+
+/// `connect()` on a namespace socket. `AF_UNSPEC` dissolves the association of a datagram
+/// socket.
+///
+/// # Safety
+/// `addr` must be NULL or point to `addrlen` readable bytes.
+pub unsafe fn netns_connect(
+    cageid: u64,
+    sock: &NetnsSocket,
+    fd: i32,
+    addr: *const u8,
+    addrlen: u32,
+) -> i32 {
+    let is_unspec = !addr.is_null()
+        && addrlen as usize >= size_of::<libc::sa_family_t>()
+        && unsafe { (addr as *const libc::sa_family_t).read_unaligned() } as i32 == AF_UNSPEC;
+    if is_unspec && sock.socktype == SOCK_DGRAM {
+        let unspec = libc::sockaddr {
+            sa_family: AF_UNSPEC as libc::sa_family_t,
+            sa_data: [0; 14],
+        };
+        let len = size_of::<libc::sockaddr>() as libc::socklen_t;
+        if unsafe { libc::connect(fd, &unspec, len) } < 0 {
+            return handle_errno(get_errno(), "connect");
+        }
+        sock.state.lock().peer = None;
+        return 0;
+    }
+
+    let target = match unsafe { sock.read_addr(addr, addrlen) } {
+        Ok(target) => target,
+        Err(e) => return syscall_error(e, "connect", "invalid address"),
+    };
+    if !is_local(target.ip()) {
+        return syscall_error(Errno::ENETUNREACH, "connect", "Network is unreachable");
+    }
+
+    {
+        let mut state = sock.state.lock();
+        if sock.socktype == SOCK_STREAM && state.peer.is_some() {
+            return syscall_error(Errno::EISCONN, "connect", "socket is already connected");
+        }
+        if let Err(e) = sock.autobind(fd, &mut state) {
+            return syscall_error(e, "connect", "cannot bind port");
+        }
+    }
+
+    // A stream connect may wait for room in the listener's backlog, so without the lock
+    let (sun, len) = host_name(sock.socktype, target.port());
+    let ret = interruptible(cageid, true, || unsafe {
+        libc::connect(fd, &sun as *const _ as *const libc::sockaddr, len)
+    });
+    if ret < 0 {
+        return handle_errno(get_errno(), "connect");
+    }
+
+    let loopback = sock.loopback(target.port());
+    let mut state = sock.state.lock();
+    state.peer = Some(if target.ip().is_unspecified() {
+        loopback
+    } else {
+        target
+    });
+    if let Some(local) = state.local.as_mut() {
+        if local.ip().is_unspecified() {
+            local.set_ip(loopback.ip());
+        }
+    }
+    0
+}
+
+/// `listen()` on a namespace socket. If the port is forwarded, the host port is opened here.
+pub fn netns_listen(sock: &NetnsSocket, fd: i32, backlog: i32) -> i32 {
+    if sock.socktype != SOCK_STREAM {
+        return syscall_error(
+            Errno::EOPNOTSUPP,
+            "listen",
+            "socket does not accept connections",
+        );
+    }
+
+    let mut state = sock.state.lock();
+    if let Err(e) = sock.autobind(fd, &mut state) {
+        return syscall_error(e, "listen", "cannot bind port");
+    }
+
+    let port = state.local.map_or(0, |local| local.port());
+    let host_port = NETNS
+        .get()
+        .and_then(|netns| netns.forwards.get(&port).copied());
+    let mut started = None;
+    if let (Some(host_port), None) = (host_port, &state.forward) {
+        match Forward::start(port, host_port) {
+            Ok(forward) => started = Some(forward),
+            Err(e) => return syscall_error(e, "listen", "cannot open the forwarded host port"),
+        }
+    }
+
+    if unsafe { libc::listen(fd, backlog) } < 0 {
+        let errno = get_errno();
+        if let Some(forward) = started {
+            forward.stop();
+        }
+        return handle_errno(errno, "listen");
+    }
+    if started.is_some() {
+        state.forward = started;
+    }
+    0
+}
+
+/// `accept()` / `accept4()` on a namespace socket, `flags` being those of `accept4()`
+///
+/// # Safety
+/// `addr` and `addrlen` must be NULL or valid for writes, see `copy_out_sockaddr`.
+pub unsafe fn netns_accept(
+    cageid: u64,
+    sock: &NetnsSocket,
+    fd: i32,
+    addr: *mut SockAddr,
+    addrlen: *mut libc::socklen_t,
+    flags: i32,
+    syscall: &str,
+) -> i32 {
+    if sock.socktype != SOCK_STREAM {
+        return syscall_error(
+            Errno::EOPNOTSUPP,
+            syscall,
+            "socket does not accept connections",
+        );
+    }
+
+    let mut sun: libc::sockaddr_un = unsafe { zeroed() };
+    let mut len = size_of::<libc::sockaddr_un>() as libc::socklen_t;
+    let conn_fd = interruptible(cageid, true, || unsafe {
+        libc::accept4(
+            fd,
+            &mut sun as *mut _ as *mut libc::sockaddr,
+            &mut len,
+            flags & SOCK_NONBLOCK,
+        )
+    });
+    if conn_fd < 0 {
+        return handle_errno(get_errno(), syscall);
+    }
+
+    let loopback = sock.loopback(0);
+    let local = sock.state.lock().local.map(|local| {
+        if local.ip().is_unspecified() {
+            SocketAddr::new(loopback.ip(), local.port())
+        } else {
+            local
+        }
+    });
+    let peer = sock.loopback(port_of_host_name(&sun, len).unwrap_or(0));
+    let conn = NetnsSocket {
+        domain: sock.domain,
+        socktype: sock.socktype,
+        state: Mutex::new(SocketState {
+            local,
+            peer: Some(peer),
+            ..Default::default()
+        }),
+    };
+
+    let vfd = install(cageid, conn_fd, conn, (flags & SOCK_CLOEXEC) != 0, syscall);
+    if vfd >= 0 && !addr.is_null() && !addrlen.is_null() {
+        let (storage, _) = to_storage(peer);
+        unsafe { copy_out_sockaddr(addr, addrlen, &storage) };
+    }
+    vfd
+}
+
+/// `getsockname()` on a namespace socket. An unbound socket reports the unspecified address.
+///
+/// # Safety
+/// `addr` and `addrlen` must be NULL or valid for writes, see `copy_out_sockaddr`.
+pub unsafe fn netns_getsockname(
+    sock: &NetnsSocket,
+    addr: *mut SockAddr,
+    addrlen: *mut libc::socklen_t,
+) -> i32 {
+    let local = sock
+        .state
+        .lock()
+        .local
+        .unwrap_or_else(|| sock.unspecified(0));
+    let (storage, _) = to_storage(local);
+    unsafe { copy_out_sockaddr(addr, addrlen, &storage) };
+    0
+}
+
+/// `getpeername()` on a namespace socket
+///
+/// # Safety
+/// `addr` and `addrlen` must be NULL or valid for writes, see `copy_out_sockaddr`.
+pub unsafe fn netns_getpeername(
+    sock: &NetnsSocket,
+    addr: *mut SockAddr,
+    addrlen: *mut libc::socklen_t,
+) -> i32 {
+    let peer = match sock.state.lock().peer {
+        Some(peer) => peer,
+        None => return syscall_error(Errno::ENOTCONN, "getpeername", "socket is not connected"),
+    };
+    let (storage, _) = to_storage(peer);
+    unsafe { copy_out_sockaddr(addr, addrlen, &storage) };
+    0
+}
+
+/// `setsockopt()` on a namespace socket. `SOL_SOCKET` options go to the host socket, the
+/// others are only recorded.
+///
+/// # Safety
+/// `optval` must be NULL or point to `optlen` readable bytes.
+pub unsafe fn netns_setsockopt(
+    sock: &NetnsSocket,
+    fd: i32,
+    level: i32,
+    optname: i32,
+    optval: *const u8,
+    optlen: u32,
+) -> i32 {
+    if level == SOL_SOCKET {
+        if unsafe { libc::setsockopt(fd, level, optname, optval as *const libc::c_void, optlen) }
+            < 0
+        {
+            return handle_errno(get_errno(), "setsockopt");
+        }
+        return 0;
+    }
+    if !sock.has_level(level) {
+        return syscall_error(Errno::ENOPROTOOPT, "setsockopt", "Protocol not available");
+    }
+    if optval.is_null() {
+        return syscall_error(Errno::EFAULT, "setsockopt", "optval is null");
+    }
+    if (optlen as usize) < size_of::<i32>() {
+        return syscall_error(Errno::EINVAL, "setsockopt", "optlen is too small");
+    }
+
+    let value = unsafe { (optval as *const i32).read_unaligned() };
+    sock.state.lock().options.insert((level, optname), value);
+    0
+}
+
+/// `getsockopt()` on a namespace socket, see `netns_setsockopt`. `SO_DOMAIN` and `SO_PROTOCOL`
+/// are answered here, as the host socket is an `AF_UNIX` one.
+///
+/// # Safety
+/// `optval` and `optlen` must be NULL or valid, `optval` for `*optlen` bytes.
+pub unsafe fn netns_getsockopt(
+    sock: &NetnsSocket,
+    fd: i32,
+    level: i32,
+    optname: i32,
+    optval: *mut u8,
+    optlen: *mut libc::socklen_t,
+) -> i32 {
+    let value = match (level, optname) {
+        (SOL_SOCKET, SO_DOMAIN) => sock.domain,
+        (SOL_SOCKET, SO_PROTOCOL) if sock.socktype == SOCK_STREAM => IPPROTO_TCP,
+        (SOL_SOCKET, SO_PROTOCOL) => IPPROTO_UDP,
+        (SOL_SOCKET, _) => {
+            let ret = unsafe {
+                libc::getsockopt(fd, level, optname, optval as *mut libc::c_void, optlen)
+            };
+            if ret < 0 {
+                return handle_errno(get_errno(), "getsockopt");
+            }
+            return 0;
+        }
+        _ if sock.has_level(level) => sock
+            .state
+            .lock()
+            .options
+            .get(&(level, optname))
+            .copied()
+            .unwrap_or(0),
+        _ => return syscall_error(Errno::ENOPROTOOPT, "getsockopt", "Protocol not available"),
+    };
+
+    if optval.is_null() || optlen.is_null() {
+        return syscall_error(Errno::EFAULT, "getsockopt", "optval is null");
+    }
+    unsafe {
+        let len = (*optlen as usize).min(size_of::<i32>());
+        ptr::copy_nonoverlapping(value.to_ne_bytes().as_ptr(), optval, len);
+        *optlen = len as libc::socklen_t;
+    }
+    0
+}
+
+/// `sendmsg()` on a namespace socket, also used for `sendto()` and `sendmmsg()`.
+///
+/// The name of a datagram is translated to the receiver's host name; stream sockets ignore it,
+/// like TCP does. A datagram sent to a port nobody has bound is dropped without an error.
+///
+/// # Safety
+/// `msg` must be a host-layout msghdr whose name and iovecs are valid host pointers.
+pub unsafe fn netns_sendmsg(
+    cageid: u64,
+    sock: &NetnsSocket,
+    fd: i32,
+    msg: &libc::msghdr,
+    flags: i32,
+    syscall: &str,
+) -> i32 {
+    if msg.msg_controllen != 0 {
+        return syscall_error(Errno::EINVAL, syscall, "ancillary data is not supported");
+    }
+
+    let mut hdr = *msg;
+    let mut name = None;
+    if sock.socktype == SOCK_DGRAM && !msg.msg_name.is_null() {
+        let target = match sock.read_addr(msg.msg_name as *const u8, msg.msg_namelen) {
+            Ok(target) => target,
+            Err(e) => return syscall_error(e, syscall, "invalid address"),
+        };
+        if !is_local(target.ip()) {
+            return syscall_error(Errno::ENETUNREACH, syscall, "Network is unreachable");
+        }
+        if let Err(e) = sock.autobind(fd, &mut sock.state.lock()) {
+            return syscall_error(e, syscall, "cannot bind port");
+        }
+        name = Some(host_name(SOCK_DGRAM, target.port()));
+    }
+    match name.as_mut() {
+        Some((sun, len)) => {
+            hdr.msg_name = sun as *mut _ as *mut libc::c_void;
+            hdr.msg_namelen = *len;
+        }
+        None => {
+            hdr.msg_name = ptr::null_mut();
+            hdr.msg_namelen = 0;
+        }
+    }
+
+    let ret = interruptible(cageid, true, || libc::sendmsg(fd, &hdr, flags));
+    if ret < 0 {
+        let errno = get_errno();
+        if errno == libc::ECONNREFUSED && name.is_some() {
+            let iov = std::slice::from_raw_parts(msg.msg_iov, msg.msg_iovlen);
+            return iov.iter().map(|iov| iov.iov_len).sum::<usize>() as i32;
+        }
+        if errno == libc::ENOTCONN && sock.socktype == SOCK_DGRAM {
+            return syscall_error(Errno::EDESTADDRREQ, syscall, "Destination address required");
+        }
+        return handle_errno(errno, syscall);
+    }
+    ret as i32
+}
+
+/// `recvmsg()` on a namespace socket, also used for `recvfrom()` and `recvmmsg()`.
+///
+/// The sender of a datagram is reported as a loopback address with the sender's port. A stream
+/// socket reports no name, like TCP does, and no ancillary data is ever received.
+///
+/// # Safety
+/// `msg` must be a host-layout msghdr whose name and iovecs are valid host pointers.
+pub unsafe fn netns_recvmsg(
+    cageid: u64,
+    sock: &NetnsSocket,
+    fd: i32,
+    msg: &mut libc::msghdr,
+    flags: i32,
+    syscall: &str,
+) -> i32 {
+    let mut sun: libc::sockaddr_un = zeroed();
+    let mut hdr = *msg;
+    hdr.msg_name = &mut sun as *mut _ as *mut libc::c_void;
+    hdr.msg_namelen = size_of::<libc::sockaddr_un>() as libc::socklen_t;
+    hdr.msg_control = ptr::null_mut();
+    hdr.msg_controllen = 0;
+
+    let ret = interruptible(cageid, true, || {
+        libc::recvmsg(fd, &mut hdr, flags & !MSG_CMSG_CLOEXEC)
+    });
+    if ret < 0 {
+        return handle_errno(get_errno(), syscall);
+    }
+
+    msg.msg_flags = hdr.msg_flags;
+    msg.msg_controllen = 0;
+    let port = port_of_host_name(&sun, hdr.msg_namelen);
+    match port {
+        Some(port) if sock.socktype == SOCK_DGRAM && !msg.msg_name.is_null() => {
+            let (storage, len) = to_storage(sock.loopback(port));
+            ptr::copy_nonoverlapping(
+                &storage as *const _ as *const u8,
+                msg.msg_name as *mut u8,
+                (len as usize).min(msg.msg_namelen as usize),
+            );
+            msg.msg_namelen = len;
+        }
+        _ => msg.msg_namelen = 0,
+    }
+    ret as i32
+}
+
+/// A host port forwarded to a listening socket of the namespace
+struct Forward {
+    listener: TcpListener,
+    stopped: AtomicBool,
+}
+
+impl Forward {
+    /// Listen on `host_port` of the host's loopback interface and connect every connection made
+    /// to it to `guest_port` of the namespace
+    fn start(guest_port: u16, host_port: u16) -> Result<Arc<Forward>, Errno> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, host_port)).map_err(io_errno)?;
+        let forward = Arc::new(Forward {
+            listener,
+            stopped: AtomicBool::new(false),
+        });
+
+        let acceptor = Arc::clone(&forward);
+        thread::spawn(move || {
+            for conn in acceptor.listener.incoming() {
+                if acceptor.stopped.load(Ordering::Acquire) {
+                    break;
+                }
+                // A connection that cannot be forwarded is simply closed
+                if let Ok(host) = conn {
+                    let _ = forward_connection(host, guest_port);
+                }
+            }
+        });
+        Ok(forward)
+    }
+
+    fn stop(&self) {
+        self.stopped.store(true, Ordering::Release);
+        // Wakes up the acceptor thread blocked in accept()
+        unsafe { libc::shutdown(self.listener.as_raw_fd(), libc::SHUT_RDWR) };
+    }
+}
+
+/// Connect a host connection to `guest_port` and copy data both ways until each side has
+/// shut down its direction
+fn forward_connection(host: TcpStream, guest_port: u16) -> Result<(), Errno> {
+    let fd = unsafe { libc::socket(AF_UNIX, SOCK_STREAM | SOCK_CLOEXEC, 0) };
+    if fd < 0 {
+        return Err(last_errno());
+    }
+    let guest = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    // The guest sees the connection coming from an ephemeral port, like any other
+    bind_port(fd, SOCK_STREAM, 0)?;
+    let (sun, len) = host_name(SOCK_STREAM, guest_port);
+    if unsafe { libc::connect(fd, &sun as *const _ as *const libc::sockaddr, len) } < 0 {
+        return Err(last_errno());
+    }
+
+    let guest = UnixStream::from(guest);
+    let mut host_rd = host.try_clone().map_err(io_errno)?;
+    let mut guest_rd = guest.try_clone().map_err(io_errno)?;
+    let (mut host_wr, mut guest_wr) = (host, guest);
+    thread::spawn(move || {
+        let _ = io::copy(&mut host_rd, &mut guest_wr);
+        let _ = guest_wr.shutdown(Shutdown::Write);
+    });
+    thread::spawn(move || {
+        let _ = io::copy(&mut guest_rd, &mut host_wr);
+        let _ = host_wr.shutdown(Shutdown::Write);
+    });
+    Ok(())
+}
@@ -0,0 +1,394 @@
+//! Network policy
+//!
+//! lind-boot can restrict what the cages do on the network (see `netpolicy_init`), for running
+//! untrusted code that may only talk to known destinations:
+//! - outbound rules decide which internet addresses `connect`, `sendto`, `sendmsg` and
+//!   `sendmmsg` may target. A denied destination fails with `ENETUNREACH`, as if there was no
+//!   route to it;
+//! - bind rules decide which local addresses and ports `bind` accepts, and a list of listen
+//!   ports which ports `listen` accepts. Both fail with `EACCES`, like a privileged port;
+//! - socket restrictions deny whole address families (`AF_INET6`, `AF_PACKET`, ...) or raw
+//!   sockets at `socket` and `socketpair`, also with `EACCES`.
+//!
+//! A rule is a CIDR block with an optional port range, `10.0.0.0/8`, `192.0.2.1:443`,
+//! `[2001:db8::]/32:8000-8100` or `*:53`. A list of rules permits an address if no deny rule
+//! matches it, and either there are no allow rules or one of them matches. IPv4-mapped IPv6
+//! addresses are matched as the IPv4 address they carry. Unix domain addresses are not
+//! subject to the rules.
+//!
+//! The checks see the addresses the guest passes, so with `--netns` the rules apply to the
+//! addresses of the virtual network. With an audit log, every decision taken is appended to it.
+
+use crate::netns::read_inet;
+use parking_lot::Mutex;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::mem::{size_of, zeroed};
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use sysdefs::constants::err_const::Errno;
+use sysdefs::constants::net_const::{
+    AF_INET, AF_INET6, AF_NETLINK, AF_PACKET, AF_UNIX, SOCK_CLOEXEC, SOCK_NONBLOCK, SOCK_RAW,
+};
+
+/// An inclusive range of ports
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PortRange {
+    first: u16,
+    last: u16,
+}
+
+impl PortRange {
+    fn contains(&self, port: u16) -> bool {
+        (self.first..=self.last).contains(&port)
+    }
+}
+
+impl FromStr for PortRange {
+    type Err = String;
+
+    /// `PORT` or `FIRST-LAST`
+    fn from_str(s: &str) -> Result<Self, String> {
+        let parse = |port: &str| {
+            port.parse::<u16>()
+                .map_err(|_| format!("invalid port: {}", port))
+        };
+        let (first, last) = match s.split_once('-') {
+            Some((first, last)) => (parse(first)?, parse(last)?),
+            None => (parse(s)?, parse(s)?),
+        };
+        if first > last {
+            return Err(format!("invalid port range: {}", s));
+        }
+        Ok(PortRange { first, last })
+    }
+}
+
+/// A CIDR block with an optional port range. Without a block (`*`) the rule matches every
+/// internet address.
+#[derive(Clone, Debug)]
+pub struct NetRule {
+    net: Option<(IpAddr, u8)>,
+    ports: Option<PortRange>,
+}
+
+impl NetRule {
+    fn matches(&self, addr: &SocketAddr) -> bool {
+        let ip_matches = match self.net {
+            None => true,
+            Some((net, prefix)) => match (net, canonical_ip(addr.ip())) {
+                (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                    let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+                    (u32::from(net) ^ u32::from(ip)) & mask == 0
+                }
+                (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                    let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+                    (u128::from(net) ^ u128::from(ip)) & mask == 0
+                }
+                _ => false,
+            },
+        };
+        // An unknown port (binding port 0) only matches rules that take any port
+        ip_matches
+            && self
+                .ports
+                .is_none_or(|ports| addr.port() != 0 && ports.contains(addr.port()))
+    }
+}
+
+impl FromStr for NetRule {
+    type Err = String;
+
+    /// `ADDR[/PREFIX][:PORTS]`, with IPv6 addresses in brackets if a port range follows, or
+    /// `*[:PORTS]`
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (net, ports) = if let Some(rest) = s.strip_prefix('[') {
+            let (addr, rest) = rest
+                .split_once(']')
+                .ok_or_else(|| format!("unterminated [ in rule: {}", s))?;
+            let (prefix, ports) = match rest.split_once(':') {
+                Some((prefix, ports)) => (prefix, Some(ports)),
+                None => (rest, None),
+            };
+            (format!("{}{}", addr, prefix), ports)
+        } else if s.matches(':').count() > 1 {
+            // A bare IPv6 block cannot carry ports
+            (s.to_string(), None)
+        } else {
+            match s.split_once(':') {
+                Some((net, ports)) => (net.to_string(), Some(ports)),
+                None => (s.to_string(), None),
+            }
+        };
+
+        let net = if net == "*" {
+            None
+        } else {
+            let (addr, prefix) = match net.split_once('/') {
+                Some((addr, prefix)) => (addr, Some(prefix)),
+                None => (net.as_str(), None),
+            };
+            let addr: IpAddr = addr
+                .parse()
+                .map_err(|_| format!("invalid address in rule: {}", s))?;
+            let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+            let prefix = match prefix {
+                Some(prefix) => prefix
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|prefix| *prefix <= max_prefix)
+                    .ok_or_else(|| format!("invalid prefix length in rule: {}", s))?,
+                None => max_prefix,
+            };
+            Some((addr, prefix))
+        };
+        let ports = ports.map(str::parse).transpose()?;
+        Ok(NetRule { net, ports })
+    }
+}
+
+/// A kind of socket that may not be created
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeniedSocket {
+    /// Every socket of an address family
+    Family(i32),
+    /// Raw sockets of any family
+    Raw,
+}
+
+impl FromStr for DeniedSocket {
+    type Err = String;
+
+    /// `unix`, `inet`, `inet6`, `netlink`, `packet` or `raw`
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "unix" => Ok(DeniedSocket::Family(AF_UNIX)),
+            "inet" => Ok(DeniedSocket::Family(AF_INET)),
+            "inet6" => Ok(DeniedSocket::Family(AF_INET6)),
+            "netlink" => Ok(DeniedSocket::Family(AF_NETLINK)),
+            "packet" => Ok(DeniedSocket::Family(AF_PACKET)),
+            "raw" => Ok(DeniedSocket::Raw),
+            _ => Err(format!("unknown socket kind: {}", s)),
+        }
+    }
+}
+
+/// The policy enforced on all cages. Empty lists put no restriction in place.
+#[derive(Clone, Debug, Default)]
+pub struct NetPolicy {
+    /// Destinations of `connect`, `sendto` and `sendmsg` that are permitted
+    pub allow: Vec<NetRule>,
+    /// Destinations of `connect`, `sendto` and `sendmsg` that are denied
+    pub deny: Vec<NetRule>,
+    /// Local addresses that `bind` permits
+    pub bind_allow: Vec<NetRule>,
+    /// Local addresses that `bind` denies
+    pub bind_deny: Vec<NetRule>,
+    /// Ports that `listen` permits
+    pub listen_ports: Vec<PortRange>,
+    /// Sockets that `socket` and `socketpair` refuse to create
+    pub denied_sockets: Vec<DeniedSocket>,
+}
+
+struct NetPolicyState {
+    policy: NetPolicy,
+    audit: Option<Mutex<File>>,
+}
+
+static NET_POLICY: OnceLock<NetPolicyState> = OnceLock::new();
+
+/// Enforce `policy`, appending every decision to the file at `audit_path` if one is given.
+/// Has to be called before `rawposix_start`, as the audit log lives outside the lindfs image.
+///
+/// ## Returns:
+/// - `Ok(())` on success
+/// - `Err(errno)` if the audit log cannot be opened, `Err(EBUSY)` if a policy was already set
+pub fn netpolicy_init(policy: NetPolicy, audit_path: Option<&str>) -> Result<(), Errno> {
+    let audit = match audit_path {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| {
+                    e.raw_os_error()
+                        .and_then(|errno| Errno::from_discriminant(errno).ok())
+                        .unwrap_or(Errno::EIO)
+                })?;
+            Some(Mutex::new(file))
+        }
+        None => None,
+    };
+    NET_POLICY
+        .set(NetPolicyState { policy, audit })
+        .map_err(|_| Errno::EBUSY)
+}
+
+/// Canonical form of an address: IPv4-mapped IPv6 addresses become IPv4
+fn canonical_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        ip => ip,
+    }
+}
+
+fn permits(allow: &[NetRule], deny: &[NetRule], addr: &SocketAddr) -> bool {
+    !deny.iter().any(|rule| rule.matches(addr))
+        && (allow.is_empty() || allow.iter().any(|rule| rule.matches(addr)))
+}
+
+impl NetPolicyState {
+    fn decide(
+        &self,
+        cageid: u64,
+        op: &str,
+        target: impl fmt::Display,
+        allowed: bool,
+        errno: Errno,
+    ) -> Result<(), Errno> {
+        if let Some(audit) = &self.audit {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            let verdict = if allowed { "allow" } else { "deny" };
+            // A full disk must not fail the syscall, the decision stands either way
+            let _ = writeln!(
+                audit.lock(),
+                "{}.{:03} cage={} op={} target={} verdict={}",
+                now.as_secs(),
+                now.subsec_millis(),
+                cageid,
+                op,
+                target,
+                verdict
+            );
+        }
+        if allowed {
+            Ok(())
+        } else {
+            Err(errno)
+        }
+    }
+}
+
+/// Internet address of a guest sockaddr, or `None` for other families, which the rules do not
+/// cover. An invalid internet address is left for the syscall to reject.
+///
+/// # Safety
+/// `addr` must be NULL or point to `addrlen` readable bytes.
+unsafe fn inet_target(addr: *const u8, addrlen: u32) -> Option<SocketAddr> {
+    if addr.is_null() || (addrlen as usize) < size_of::<libc::sa_family_t>() {
+        return None;
+    }
+    match (addr as *const libc::sa_family_t).read_unaligned() as i32 {
+        AF_INET | AF_INET6 => read_inet(addr, addrlen).ok(),
+        _ => None,
+    }
+}
+
+/// Check that `socket()` / `socketpair()` may create a socket of `domain` and `socktype`.
+///
+/// ## Returns:
+/// - `Err(EACCES)` if the family, or raw sockets, are denied
+pub fn netpolicy_check_socket(
+    cageid: u64,
+    op: &str,
+    domain: i32,
+    socktype: i32,
+) -> Result<(), Errno> {
+    let Some(state) = NET_POLICY.get() else {
+        return Ok(());
+    };
+    let socktype = socktype & !(SOCK_NONBLOCK | SOCK_CLOEXEC);
+    let allowed = !state
+        .policy
+        .denied_sockets
+        .iter()
+        .any(|denied| match denied {
+            DeniedSocket::Family(family) => *family == domain,
+            DeniedSocket::Raw => socktype == SOCK_RAW,
+        });
+    let target = format!("family={},type={}", domain, socktype);
+    state.decide(cageid, op, target, allowed, Errno::EACCES)
+}
+
+/// Check that `connect()`, `sendto()` or `sendmsg()` (named by `op`) may reach `addr`.
+///
+/// ## Returns:
+/// - `Err(ENETUNREACH)` if the destination is denied
+///
+/// # Safety
+/// `addr` must be NULL or point to `addrlen` readable bytes.
+pub unsafe fn netpolicy_check_destination(
+    cageid: u64,
+    op: &str,
+    addr: *const u8,
+    addrlen: u32,
+) -> Result<(), Errno> {
+    let Some(state) = NET_POLICY.get() else {
+        return Ok(());
+    };
+    let Some(target) = inet_target(addr, addrlen) else {
+        return Ok(());
+    };
+    let allowed = permits(&state.policy.allow, &state.policy.deny, &target);
+    state.decide(cageid, op, target, allowed, Errno::ENETUNREACH)
+}
+
+/// Check that `bind()` may bind to `addr`.
+///
+/// ## Returns:
+/// - `Err(EACCES)` if the address or port is denied
+///
+/// # Safety
+/// `addr` must be NULL or point to `addrlen` readable bytes.
+pub unsafe fn netpolicy_check_bind(
+    cageid: u64,
+    addr: *const u8,
+    addrlen: u32,
+) -> Result<(), Errno> {
+    let Some(state) = NET_POLICY.get() else {
+        return Ok(());
+    };
+    let Some(target) = inet_target(addr, addrlen) else {
+        return Ok(());
+    };
+    let allowed = permits(&state.policy.bind_allow, &state.policy.bind_deny, &target);
+    state.decide(cageid, "bind", target, allowed, Errno::EACCES)
+}
+
+/// Check that `listen()` may listen on a socket bound to `local`, `None` standing for a
+/// socket that is not an internet one. An unbound socket would listen on an ephemeral port,
+/// which is not permitted while listen ports are restricted.
+///
+/// ## Returns:
+/// - `Err(EACCES)` if the port is not a permitted listen port
+pub fn netpolicy_check_listen(cageid: u64, local: Option<SocketAddr>) -> Result<(), Errno> {
+    let Some(state) = NET_POLICY.get() else {
+        return Ok(());
+    };
+    let Some(local) = local else {
+        return Ok(());
+    };
+    let ports = &state.policy.listen_ports;
+    let allowed =
+        ports.is_empty() || (local.port() != 0 && ports.iter().any(|p| p.contains(local.port())));
+    state.decide(cageid, "listen", local, allowed, Errno::EACCES)
+}
+
+/// Local internet address of host socket `fd`, for `netpolicy_check_listen`
+pub fn netpolicy_local_addr(fd: i32) -> Option<SocketAddr> {
+    NET_POLICY.get()?;
+    let mut storage: libc::sockaddr_storage = unsafe { zeroed() };
+    let mut len = size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    let ret =
+        unsafe { libc::getsockname(fd, &mut storage as *mut _ as *mut libc::sockaddr, &mut len) };
+    if ret < 0 {
+        // Leave the error to listen() itself
+        return None;
+    }
+    unsafe { inet_target(&storage as *const _ as *const u8, len) }
+}
@@ -0,0 +1,134 @@
+//! Per-cage memory budgets and the OOM killer
+//!
+//! Every cage's `Vmmap` keeps track of how many pages it has committed (mapped with any
+//! protection other than `PROT_NONE`). lind-boot can cap that number per cage
+//! (`--cage-memory-limit`) and across all cages (`--memory-limit`), see `memory_budget_init`.
+//!
+//! The budgets are enforced after the fact, like the Linux OOM killer rather than
+//! `RLIMIT_AS`: the syscalls that grow a cage's address space (`mmap`, `mremap`, `mprotect`,
+//! `brk`, `shmat` and `fork`) call `memory_budget_check` once they succeeded, and if a budget
+//! is exceeded a victim cage is sent `SIGKILL`:
+//! - over the per-cage budget, the victim is the cage that crossed it;
+//! - over the global budget, the victim is the cage with the most committed pages.
+//!
+//! Nothing is torn down from inside the syscall. The victim's threads stop at their next epoch
+//! check, where the default action of `SIGKILL` ends the cage and its parent reaps it with
+//! `SIGKILL` as the wait status. If the calling cage is the victim, the syscall fails with
+//! `ENOMEM` so that it doesn't carry on with memory it can't have.
+
+use cage::signal::signal::lind_send_signal;
+use cage::{cagetable_ids, get_cage};
+use dashmap::DashSet;
+use lazy_static::lazy_static;
+use std::sync::OnceLock;
+use sysdefs::constants::err_const::Errno;
+use sysdefs::constants::fs_const::PAGESHIFT;
+use sysdefs::constants::sys_const::SIGKILL;
+
+/// Budgets in pages, `None` meaning unlimited
+struct MemoryBudget {
+    per_cage_pages: Option<u64>,
+    global_pages: Option<u64>,
+}
+
+static MEMORY_BUDGET: OnceLock<MemoryBudget> = OnceLock::new();
+
+lazy_static! {
+    /// Cages already picked by the OOM killer. They stay accounted until their memory is
+    /// gone, so without this a second allocation racing with the kill would pick them again.
+    static ref OOM_KILLED: DashSet<u64> = DashSet::new();
+}
+
+/// Set the memory budgets, in bytes (rounded down to whole pages). Has to be called before
+/// `rawposix_start`.
+///
+/// ## Returns:
+/// - `Ok(())` on success
+/// - `Err(EINVAL)` if a budget is smaller than one page, `Err(EBUSY)` if the budgets were
+///   already set
+pub fn memory_budget_init(
+    per_cage_bytes: Option<u64>,
+    global_bytes: Option<u64>,
+) -> Result<(), Errno> {
+    let to_pages = |bytes: Option<u64>| -> Result<Option<u64>, Errno> {
+        match bytes.map(|bytes| bytes >> PAGESHIFT) {
+            Some(0) => Err(Errno::EINVAL),
+            pages => Ok(pages),
+        }
+    };
+
+    let budget = MemoryBudget {
+        per_cage_pages: to_pages(per_cage_bytes)?,
+        global_pages: to_pages(global_bytes)?,
+    };
+    MEMORY_BUDGET.set(budget).map_err(|_| Errno::EBUSY)
+}
+
+/// The global budget in pages, if one was set
+pub fn memory_budget_global_pages() -> Option<u64> {
+    MEMORY_BUDGET.get().and_then(|budget| budget.global_pages)
+}
+
+/// Committed pages of `cageid`, or 0 if the cage is gone or already being killed
+fn committed_pages(cageid: u64) -> u64 {
+    if OOM_KILLED.contains(&cageid) {
+        return 0;
+    }
+    match get_cage(cageid) {
+        Some(cage) => cage.vmmap.read().committed_pages,
+        None => 0,
+    }
+}
+
+/// Committed pages summed over all live cages
+pub fn total_committed_pages() -> u64 {
+    cagetable_ids().into_iter().map(committed_pages).sum()
+}
+
+/// Check the budgets after `cageid` grew its address space, killing a victim if one of them
+/// is exceeded.
+///
+/// Must be called without holding any cage's `vmmap` lock.
+///
+/// ## Returns:
+/// - `Ok(())` if the budgets hold, or if another cage was picked as the victim
+/// - `Err(ENOMEM)` if `cageid` itself was picked
+pub fn memory_budget_check(cageid: u64) -> Result<(), Errno> {
+    let budget = match MEMORY_BUDGET.get() {
+        Some(budget) => budget,
+        None => return Ok(()),
+    };
+
+    if let Some(limit) = budget.per_cage_pages {
+        if committed_pages(cageid) > limit {
+            oom_kill(cageid);
+            return Err(Errno::ENOMEM);
+        }
+    }
+
+    if let Some(limit) = budget.global_pages {
+        let usage: Vec<(u64, u64)> = cagetable_ids()
+            .into_iter()
+            .map(|id| (id, committed_pages(id)))
+            .collect();
+        if usage.iter().map(|(_, pages)| pages).sum::<u64>() > limit {
+            // Ties go to the most recently created cage, as it has likely done the least work
+            if let Some(&(victim, _)) = usage.iter().max_by_key(|(id, pages)| (*pages, *id)) {
+                oom_kill(victim);
+                if victim == cageid {
+                    return Err(Errno::ENOMEM);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Send `SIGKILL` to `victim`, once. The cage goes away at its next epoch check; until then
+/// it no longer counts towards the global budget.
+fn oom_kill(victim: u64) {
+    if OOM_KILLED.insert(victim) {
+        lind_send_signal(victim, SIGKILL);
+    }
+}
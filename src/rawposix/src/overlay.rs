@@ -0,0 +1,1110 @@
+//! Overlay (copy-up) filesystem mode
+//!
+//! With `--overlay-upper DIR`, lind-boot treats the lindfs image as a read-only lower layer and
+//! sends every modification to a separate upper directory on the host, so one image can be shared
+//! by many runs without being modified (see `overlay_init`). With `--overlay-per-cage` each cage
+//! gets its own upper directory; a forked child stacks a fresh upper on top of its parent's layers,
+//! so it starts from the parent's view, and its own writes are not visible to the parent.
+//!
+//! The path-based syscalls in `fs_calls` hand the normalized guest path to the cage's `Layers`
+//! (from `overlay_layers`) instead of the host:
+//! - lookups walk the path one component at a time over all layers, top-down. A directory
+//!   merges the directories of the same name in every layer below it, while any other file type
+//!   hides whatever the lower layers hold under that name;
+//! - opening a file for writing, or changing its metadata, first copies it into the upper layer
+//!   together with any missing parent directories ("copy-up");
+//! - removing an entry that still exists in a lower layer leaves an aufs-style whiteout file
+//!   `.wh.<name>` in the upper directory, and a directory that replaces a removed one is marked
+//!   opaque with a `.wh..wh..opq` file so the old lower contents stay hidden;
+//! - `getdents` on a directory opened through the overlay returns the merged listing.
+//!
+//! Opened files are plain host fds (`FDKIND_KERNEL`). The overlay remembers the guest path of
+//! each one so that `fchdir`, `fchmod`, `getdents` and the `*at` calls can find their way back.
+//! All host access is relative to the layer root fds and goes through `openat2` with
+//! `RESOLVE_IN_ROOT`, so symlinks inside a layer can never escape it.
+//!
+//! Known limitations: renaming a directory that exists in a lower layer fails with `EXDEV` (as
+//! overlayfs does without `redirect_dir`, callers fall back to copying), names starting with
+//! `.wh.` are reserved, fds opened read-only before a copy-up keep reading the lower file, and
+//! binaries are still loaded by `exec` from the lindfs image.
+
+use cage::deterministic::is_deterministic;
+use dashmap::DashMap;
+use fdtables;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::collections::{HashSet, VecDeque};
+use std::ffi::CString;
+use std::fs::File;
+use std::os::fd::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use sysdefs::constants::err_const::{get_errno, syscall_error, Errno};
+use sysdefs::constants::fs_const::{O_ACCMODE, O_CLOEXEC, O_CREAT, O_EXCL, O_RDONLY, O_TRUNC};
+use sysdefs::constants::lind_platform_const::{FDKIND_KERNEL, LINDFS_ROOT, PATH_MAX};
+use sysdefs::data::fs_struct::StatData;
+use typemap::filesystem_helpers::{convert_statdata_to_user, dirent64_reclen, write_dirent64};
+
+/// Prefix of the whiteout file that hides `<name>` in the layers below
+const WHITEOUT_PREFIX: &[u8] = b".wh.";
+/// Marker file that hides the contents of the lower directories of the same name
+const OPAQUE_MARKER: &[u8] = b".wh..wh..opq";
+/// Same limit as the kernel's `MAXSYMLINKS`
+const MAX_SYMLINK_HOPS: usize = 40;
+
+struct OverlayConfig {
+    lower: Arc<OwnedFd>,
+    upper_root: Arc<OwnedFd>,
+    per_cage: bool,
+    shared: Arc<Layers>, // the single layer stack used when `per_cage` is off
+}
+
+/// Bookkeeping for an fd opened through the overlay
+struct OverlayFd {
+    path: String,                            // guest path the fd was resolved to
+    listing: Option<(Vec<DirEntry>, usize)>, // merged directory listing and read position
+}
+
+#[derive(Clone)]
+pub(crate) struct DirEntry {
+    pub(crate) ino: u64,
+    pub(crate) d_type: u8,
+    pub(crate) name: String,
+}
+
+static OVERLAY: OnceLock<OverlayConfig> = OnceLock::new();
+
+lazy_static! {
+    /// Layer stacks of the cages in per-cage mode
+    static ref CAGE_LAYERS: DashMap<u64, Arc<Layers>> = DashMap::new();
+    /// Host fds opened through the overlay, keyed by host fd
+    static ref OVERLAY_FDS: DashMap<RawFd, OverlayFd> = DashMap::new();
+    /// Serializes operations that modify the upper layer so that two cages copying up the
+    /// same file don't race
+    static ref COPYUP_LOCK: Mutex<()> = Mutex::new(());
+}
+
+// Sequence number for per-cage upper directories, cage ids get reused
+static NEXT_UPPER: AtomicU64 = AtomicU64::new(0);
+
+/// Enable the overlay mode. `upper` is the host directory receiving all modifications (created
+/// if needed), and `per_cage` selects one upper directory per cage (created inside `upper`)
+/// instead of one for the whole run. Has to be called before `rawposix_start`, which chroots
+/// into the lindfs image.
+///
+/// ## Returns:
+/// - `Ok(())` on success
+/// - `Err(EINVAL)` if `upper` lies inside the lindfs image, `Err(EBUSY)` if the overlay was
+///   already enabled, or the error from creating or opening the directories
+pub fn overlay_init(upper: &str, per_cage: bool) -> Result<(), Errno> {
+    let inside_image = |path: &std::path::Path| {
+        path.starts_with(LINDFS_ROOT) || std::path::Path::new(LINDFS_ROOT).starts_with(path)
+    };
+    if inside_image(&std::path::absolute(upper).map_err(io_errno)?) {
+        return Err(Errno::EINVAL);
+    }
+    std::fs::create_dir_all(LINDFS_ROOT).map_err(io_errno)?;
+    std::fs::create_dir_all(upper).map_err(io_errno)?;
+    let lower_path = std::fs::canonicalize(LINDFS_ROOT).map_err(io_errno)?;
+    let upper_path = std::fs::canonicalize(upper).map_err(io_errno)?;
+    if upper_path.starts_with(&lower_path) || lower_path.starts_with(&upper_path) {
+        return Err(Errno::EINVAL);
+    }
+
+    let open_root = |path: &std::path::Path| -> Result<Arc<OwnedFd>, Errno> {
+        let cpath = CString::new(path.as_os_str().as_encoded_bytes()).map_err(|_| Errno::EINVAL)?;
+        let fd = unsafe {
+            libc::open(
+                cpath.as_ptr(),
+                libc::O_PATH | libc::O_DIRECTORY | libc::O_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            return Err(last_errno());
+        }
+        Ok(Arc::new(unsafe { OwnedFd::from_raw_fd(fd) }))
+    };
+    let lower = open_root(&lower_path)?;
+    let upper_root = open_root(&upper_path)?;
+
+    let config = OverlayConfig {
+        shared: Arc::new(Layers {
+            fds: vec![Arc::clone(&upper_root), Arc::clone(&lower)],
+        }),
+        lower,
+        upper_root,
+        per_cage,
+    };
+    OVERLAY.set(config).map_err(|_| Errno::EBUSY)
+}
+
+/// The layer stack seen by `cageid`, or `None` if the overlay mode is off.
+pub fn overlay_layers(cageid: u64) -> Option<Arc<Layers>> {
+    let config = OVERLAY.get()?;
+    if !config.per_cage {
+        return Some(Arc::clone(&config.shared));
+    }
+    if let Some(layers) = CAGE_LAYERS.get(&cageid) {
+        return Some(Arc::clone(layers.value()));
+    }
+    // A cage that wasn't forked (the init cage) starts from the bare image
+    let layers = Arc::new(Layers {
+        fds: vec![new_cage_upper(config, cageid), Arc::clone(&config.lower)],
+    });
+    Some(Arc::clone(
+        CAGE_LAYERS.entry(cageid).or_insert(layers).value(),
+    ))
+}
+
+/// Called by `fork` in per-cage mode: the child gets a new upper directory on top of the
+/// parent's layers.
+pub fn overlay_fork(parent_cageid: u64, child_cageid: u64) {
+    let config = match OVERLAY.get() {
+        Some(config) if config.per_cage => config,
+        _ => return,
+    };
+    let parent = overlay_layers(parent_cageid).unwrap();
+    let mut fds = vec![new_cage_upper(config, child_cageid)];
+    fds.extend(parent.fds.iter().cloned());
+    CAGE_LAYERS.insert(child_cageid, Arc::new(Layers { fds }));
+}
+
+/// Called by `exit`: drops the cage's layer stack. Its upper directory is left on the host.
+pub fn overlay_exit(cageid: u64) {
+    CAGE_LAYERS.remove(&cageid);
+}
+
+// Create the upper directory of a cage in per-cage mode. Directories left behind by an
+// earlier run in the same `--overlay-upper` directory are skipped, never reused.
+fn new_cage_upper(config: &OverlayConfig, cageid: u64) -> Arc<OwnedFd> {
+    let root = config.upper_root.as_raw_fd();
+    loop {
+        let name = format!(
+            "cage{}.{}",
+            cageid,
+            NEXT_UPPER.fetch_add(1, Ordering::Relaxed)
+        );
+        let res = mkdir_at(root, name.as_bytes(), 0o755)
+            .and_then(|_| openat2(root, name.as_bytes(), libc::O_PATH | libc::O_DIRECTORY, 0));
+        match res {
+            Ok(fd) => return Arc::new(fd),
+            Err(Errno::EEXIST) => continue,
+            // Carrying on without an upper layer would write straight into the image
+            Err(e) => panic!("overlay: cannot create upper directory {}: {:?}", name, e),
+        }
+    }
+}
+
+// The host fd behind a virtual fd, if it is a kernel fd
+fn host_fd(cageid: u64, vfd: u64) -> Option<RawFd> {
+    match fdtables::translate_virtual_fd(cageid, vfd) {
+        Ok(entry) if entry.fdkind == FDKIND_KERNEL => Some(entry.underfd as RawFd),
+        _ => None,
+    }
+}
+
+/// The guest path of a virtual fd opened through the overlay.
+pub fn overlay_fd_path(cageid: u64, vfd: u64) -> Option<String> {
+    OVERLAY_FDS
+        .get(&host_fd(cageid, vfd)?)
+        .map(|f| f.path.clone())
+}
+
+/// Forget a host fd opened through the overlay. Called when it is closed.
+pub fn overlay_forget_fd(kernel_fd: RawFd) {
+    OVERLAY_FDS.remove(&kernel_fd);
+}
+
+/// `getdents64()` on a directory opened through the overlay: fills `dirp` from the merged
+/// listing, which is rebuilt whenever reading starts over at position 0.
+///
+/// ## Returns:
+/// `None` if `vfd` isn't an overlay directory, otherwise the syscall result
+pub fn overlay_getdents(cageid: u64, vfd: u64, dirp: *mut u8, count: usize) -> Option<i32> {
+    let mut entry = OVERLAY_FDS.get_mut(&host_fd(cageid, vfd)?)?;
+    let path = entry.path.clone();
+    let (listing, pos) = entry.listing.as_mut()?;
+
+    if *pos == 0 {
+        let layers = overlay_layers(cageid)?;
+        *listing = match layers.resolve(path.as_bytes(), true) {
+            Ok(r) => match &r.found {
+                Lookup::Dir(dirs) => match layers.merged_entries(dirs, &r.rel(), true) {
+                    Ok(entries) => entries,
+                    Err(e) => return Some(syscall_error(e, "getdents", "cannot list directory")),
+                },
+                _ => {
+                    return Some(syscall_error(
+                        Errno::ENOENT,
+                        "getdents",
+                        "Directory was removed",
+                    ))
+                }
+            },
+            Err(e) => return Some(syscall_error(e, "getdents", "Directory was removed")),
+        };
+        if is_deterministic() {
+            listing.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+    }
+
+    Some(fill_dirents(listing, pos, dirp, count))
+}
+
+/// Fill `dirp` with the records of `listing` from position `pos` on, like `getdents64()`.
+/// The `d_off` of a record is the position after it.
+pub(crate) fn fill_dirents(
+    listing: &[DirEntry],
+    pos: &mut usize,
+    dirp: *mut u8,
+    count: usize,
+) -> i32 {
+    let mut written = 0usize;
+    for (idx, dirent) in listing.iter().enumerate().skip(*pos) {
+        if written + dirent64_reclen(&dirent.name) > count {
+            if written == 0 {
+                return syscall_error(Errno::EINVAL, "getdents", "Result buffer is too small");
+            }
+            break;
+        }
+        written += write_dirent64(
+            unsafe { dirp.add(written) },
+            dirent.ino,
+            (idx + 1) as i64,
+            dirent.d_type,
+            &dirent.name,
+        );
+        *pos = idx + 1;
+    }
+    written as i32
+}
+
+/// `lseek()` on a directory opened through the overlay. The position counts entries of the
+/// merged listing, matching the `d_off` values handed out by `overlay_getdents`.
+///
+/// ## Returns:
+/// `None` if `vfd` isn't an overlay directory, otherwise the syscall result
+pub fn overlay_lseek(cageid: u64, vfd: u64, offset: i64, whence: i32) -> Option<i32> {
+    let mut entry = OVERLAY_FDS.get_mut(&host_fd(cageid, vfd)?)?;
+    let (_, pos) = entry.listing.as_mut()?;
+    let newpos = match whence {
+        libc::SEEK_SET => offset,
+        libc::SEEK_CUR => *pos as i64 + offset,
+        _ => -1,
+    };
+    if newpos < 0 {
+        return Some(syscall_error(Errno::EINVAL, "lseek", "Invalid argument"));
+    }
+    *pos = newpos as usize;
+    Some(newpos as i32)
+}
+
+/// Result of looking up one name in a directory
+enum Lookup {
+    /// A directory, merged from these layers (top-down)
+    Dir(Vec<usize>),
+    /// Any other file type, taken from this layer
+    Other(usize, libc::stat),
+    Missing,
+}
+
+/// A resolved guest path: the layer-relative path of its parent directory, the layers that
+/// directory is merged from, and the last component. Only the root has an empty name.
+struct Resolved {
+    parent: Vec<u8>,
+    parent_layers: Vec<usize>,
+    name: Vec<u8>,
+    found: Lookup,
+}
+
+impl Resolved {
+    fn rel(&self) -> Vec<u8> {
+        join(&self.parent, &self.name)
+    }
+}
+
+/// Stack of layer roots as seen by a cage, the writable upper layer first and the lindfs
+/// image last
+pub struct Layers {
+    fds: Vec<Arc<OwnedFd>>,
+}
+
+impl Layers {
+    fn fd(&self, layer: usize) -> RawFd {
+        self.fds[layer].as_raw_fd()
+    }
+
+    // Look `name` up in the directory `dir`, which is merged from the layers in `visible`
+    fn step(&self, visible: &[usize], dir: &[u8], name: &[u8]) -> Result<Lookup, Errno> {
+        if name.starts_with(WHITEOUT_PREFIX) {
+            return Ok(Lookup::Missing);
+        }
+        let rel = join(dir, name);
+        let whiteout = join(dir, &[WHITEOUT_PREFIX, name].concat());
+        let mut dirs = Vec::new();
+        for &layer in visible {
+            match lstat_at(self.fd(layer), &rel) {
+                Ok(st) if is_dir(&st) => {
+                    dirs.push(layer);
+                    if lstat_at(self.fd(layer), &join(&rel, OPAQUE_MARKER)).is_ok() {
+                        break;
+                    }
+                    continue;
+                }
+                // a non-directory hides everything below, but is itself hidden by a directory
+                Ok(st) if dirs.is_empty() => return Ok(Lookup::Other(layer, st)),
+                Ok(_) => break,
+                Err(Errno::ENOENT) => {}
+                Err(e) => return Err(e),
+            }
+            if lstat_at(self.fd(layer), &whiteout).is_ok() {
+                break;
+            }
+        }
+        Ok(if dirs.is_empty() {
+            Lookup::Missing
+        } else {
+            Lookup::Dir(dirs)
+        })
+    }
+
+    // Resolve a normalized absolute guest path, following symlinks in every component but
+    // the last one unless `follow` is set
+    fn resolve(&self, path: &[u8], follow: bool) -> Result<Resolved, Errno> {
+        let mut stack: Vec<(Vec<u8>, Vec<usize>)> =
+            vec![(Vec::new(), (0..self.fds.len()).collect())];
+        let mut pending: VecDeque<Vec<u8>> = components(path).collect();
+        let mut hops = 0;
+
+        while let Some(comp) = pending.pop_front() {
+            if comp == b".." {
+                if stack.len() > 1 {
+                    stack.pop();
+                }
+                continue;
+            }
+            let last = pending.is_empty();
+            let (dir, visible) = stack.last().unwrap();
+            match self.step(visible, dir, &comp)? {
+                Lookup::Dir(layers) if !last => {
+                    let rel = join(dir, &comp);
+                    stack.push((rel, layers));
+                }
+                Lookup::Other(layer, st) if is_lnk(&st) && (!last || follow) => {
+                    hops += 1;
+                    if hops > MAX_SYMLINK_HOPS {
+                        return Err(Errno::ELOOP);
+                    }
+                    let target = readlink_at(self.fd(layer), &join(dir, &comp))?;
+                    if target.starts_with(b"/") {
+                        stack.truncate(1);
+                    }
+                    for c in components(&target).collect::<Vec<_>>().into_iter().rev() {
+                        pending.push_front(c);
+                    }
+                }
+                Lookup::Other(..) if !last => return Err(Errno::ENOTDIR),
+                Lookup::Missing if !last => return Err(Errno::ENOENT),
+                found => {
+                    let (parent, parent_layers) = stack.pop().unwrap();
+                    return Ok(Resolved {
+                        parent,
+                        parent_layers,
+                        name: comp,
+                        found,
+                    });
+                }
+            }
+        }
+
+        // The path ended in a directory reached through "..", a symlink or the root itself
+        let (rel, layers) = stack.pop().unwrap();
+        let (parent, parent_layers) = stack.pop().unwrap_or_default();
+        let name = rel[parent.len()..]
+            .strip_prefix(b"/")
+            .unwrap_or(&rel[parent.len()..])
+            .to_vec();
+        Ok(Resolved {
+            parent,
+            parent_layers,
+            name,
+            found: Lookup::Dir(layers),
+        })
+    }
+
+    // Whether a layer below the upper one shows an entry at the location of `r`
+    fn lower_has(&self, r: &Resolved) -> Result<bool, Errno> {
+        let lower: Vec<usize> = r
+            .parent_layers
+            .iter()
+            .copied()
+            .filter(|&l| l != 0)
+            .collect();
+        Ok(!matches!(
+            self.step(&lower, &r.parent, &r.name)?,
+            Lookup::Missing
+        ))
+    }
+
+    // Merged listing of a directory. "." and ".." are taken from the top layer.
+    fn merged_entries(
+        &self,
+        layers: &[usize],
+        rel: &[u8],
+        dots: bool,
+    ) -> Result<Vec<DirEntry>, Errno> {
+        let mut seen = HashSet::new();
+        let mut hidden = HashSet::new();
+        let mut merged = Vec::new();
+        for (i, &layer) in layers.iter().enumerate() {
+            let dirfd = openat2(self.fd(layer), rel, libc::O_RDONLY | libc::O_DIRECTORY, 0)?;
+            let mut whiteouts = Vec::new();
+            for dirent in read_dir(dirfd.as_raw_fd())? {
+                if dirent.name == "." || dirent.name == ".." {
+                    if dots && i == 0 {
+                        merged.push(dirent);
+                    }
+                    continue;
+                }
+                if let Some(name) = dirent.name.strip_prefix(".wh.") {
+                    whiteouts.push(name.to_string());
+                    continue;
+                }
+                if hidden.contains(&dirent.name) || !seen.insert(dirent.name.clone()) {
+                    continue;
+                }
+                merged.push(dirent);
+            }
+            // whiteouts only hide the layers below their own
+            hidden.extend(whiteouts);
+        }
+        Ok(merged)
+    }
+
+    // Make sure the directory `rel` exists in the upper layer, copying the missing ones (and
+    // their permission bits) from the lower layers
+    fn copy_up_dir(&self, rel: &[u8]) -> Result<(), Errno> {
+        let mut cur = Vec::new();
+        for comp in components(rel) {
+            cur = join(&cur, &comp);
+            match lstat_at(self.fd(0), &cur) {
+                Ok(st) if is_dir(&st) => continue,
+                Ok(_) => return Err(Errno::ENOTDIR),
+                Err(Errno::ENOENT) => {}
+                Err(e) => return Err(e),
+            }
+            let mode = (1..self.fds.len())
+                .find_map(|layer| lstat_at(self.fd(layer), &cur).ok().filter(is_dir))
+                .map_or(0o755, |st| st.st_mode & 0o7777);
+            mkdir_at(self.fd(0), &cur, mode)?;
+        }
+        Ok(())
+    }
+
+    // Copy the entry `r` into the upper layer unless it already lives there
+    fn copy_up(&self, r: &Resolved) -> Result<(), Errno> {
+        let (layer, st) = match &r.found {
+            Lookup::Missing => return Err(Errno::ENOENT),
+            Lookup::Dir(_) => return self.copy_up_dir(&r.rel()),
+            Lookup::Other(0, _) => return Ok(()),
+            Lookup::Other(layer, st) => (*layer, st),
+        };
+        let rel = r.rel();
+        let (parent, name) = self.upper_slot(r)?;
+        match st.st_mode & libc::S_IFMT {
+            libc::S_IFREG => {
+                let src = openat2(self.fd(layer), &rel, libc::O_RDONLY | libc::O_NOFOLLOW, 0)?;
+                let dst = openat2(
+                    parent.as_raw_fd(),
+                    name.as_bytes(),
+                    libc::O_WRONLY | libc::O_CREAT | libc::O_EXCL,
+                    st.st_mode & 0o7777,
+                )?;
+                let (mut src, mut dst) = (File::from(src), File::from(dst));
+                std::io::copy(&mut src, &mut dst).map_err(io_errno)?;
+                let times = [
+                    libc::timespec {
+                        tv_sec: st.st_atime,
+                        tv_nsec: st.st_atime_nsec,
+                    },
+                    libc::timespec {
+                        tv_sec: st.st_mtime,
+                        tv_nsec: st.st_mtime_nsec,
+                    },
+                ];
+                unsafe {
+                    libc::fchmod(dst.as_raw_fd(), st.st_mode & 0o7777);
+                    libc::futimens(dst.as_raw_fd(), times.as_ptr());
+                }
+                Ok(())
+            }
+            libc::S_IFLNK => {
+                let target = cstring(&readlink_at(self.fd(layer), &rel)?)?;
+                check(unsafe {
+                    libc::symlinkat(target.as_ptr(), parent.as_raw_fd(), name.as_ptr())
+                })
+                .map(drop)
+            }
+            _ => Err(Errno::EOPNOTSUPP),
+        }
+    }
+
+    // The parent directory of `r` in the upper layer (copied up if needed) and the name of `r`
+    // in it. For the root this is the upper root and ".".
+    fn upper_slot(&self, r: &Resolved) -> Result<(OwnedFd, CString), Errno> {
+        if r.name.is_empty() {
+            let root = openat2(self.fd(0), b"", libc::O_PATH | libc::O_DIRECTORY, 0)?;
+            return Ok((root, cstring(b".")?));
+        }
+        self.copy_up_dir(&r.parent)?;
+        let parent = openat2(self.fd(0), &r.parent, libc::O_PATH | libc::O_DIRECTORY, 0)?;
+        Ok((parent, cstring(&r.name)?))
+    }
+
+    // Remove what is left in the upper copy of an empty merged directory (whiteouts and the
+    // opaque marker)
+    fn purge_upper_dir(&self, rel: &[u8]) -> Result<(), Errno> {
+        let dirfd = openat2(self.fd(0), rel, libc::O_RDONLY | libc::O_DIRECTORY, 0)?;
+        for dirent in read_dir(dirfd.as_raw_fd())? {
+            if dirent.name != "." && dirent.name != ".." {
+                let name = cstring(dirent.name.as_bytes())?;
+                check(unsafe { libc::unlinkat(dirfd.as_raw_fd(), name.as_ptr(), 0) })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn make_opaque(&self, rel: &[u8]) -> Result<(), Errno> {
+        openat2(
+            self.fd(0),
+            &join(rel, OPAQUE_MARKER),
+            libc::O_RDONLY | libc::O_CREAT,
+            0,
+        )
+        .map(drop)
+    }
+
+    /// `open()` through the overlay. Allocates a virtual fd for the host fd.
+    pub fn open(&self, cageid: u64, path: &[u8], oflag: i32, mode: u32) -> i32 {
+        let writes = oflag & O_ACCMODE != O_RDONLY || oflag & (O_CREAT | O_TRUNC) != 0;
+        let _guard = writes.then(|| COPYUP_LOCK.lock());
+        let res = self
+            .resolve(path, oflag & libc::O_NOFOLLOW == 0)
+            .and_then(|r| {
+                let fd = match &r.found {
+                    Lookup::Missing if oflag & O_CREAT == 0 => return Err(Errno::ENOENT),
+                    Lookup::Missing => {
+                        check_name(&r.name)?;
+                        let (parent, name) = self.upper_slot(&r)?;
+                        let fd = openat2(parent.as_raw_fd(), name.as_bytes(), oflag, mode)?;
+                        clear_whiteout(parent.as_raw_fd(), &r.name)?;
+                        fd
+                    }
+                    _ if oflag & O_CREAT != 0 && oflag & O_EXCL != 0 => return Err(Errno::EEXIST),
+                    Lookup::Dir(_) if oflag & O_ACCMODE != O_RDONLY => return Err(Errno::EISDIR),
+                    Lookup::Dir(layers) => openat2(self.fd(layers[0]), &r.rel(), oflag, mode)?,
+                    Lookup::Other(layer, _) => {
+                        let mut layer = *layer;
+                        if layer != 0 && (oflag & O_ACCMODE != O_RDONLY || oflag & O_TRUNC != 0) {
+                            self.copy_up(&r)?;
+                            layer = 0;
+                        }
+                        openat2(self.fd(layer), &r.rel(), oflag, mode)?
+                    }
+                };
+                Ok((fd, r))
+            });
+        let (fd, r) = match res {
+            Ok(res) => res,
+            Err(e) => return syscall_error(e, "open", "overlay lookup failed"),
+        };
+
+        let kernel_fd = fd.into_raw_fd();
+        let listing = match r.found {
+            Lookup::Dir(_) => Some((Vec::new(), 0)),
+            _ => None,
+        };
+        OVERLAY_FDS.insert(
+            kernel_fd,
+            OverlayFd {
+                path: guest_path(&r.rel()),
+                listing,
+            },
+        );
+        match fdtables::get_unused_virtual_fd(
+            cageid,
+            FDKIND_KERNEL,
+            kernel_fd as u64,
+            oflag & O_CLOEXEC != 0,
+            0,
+        ) {
+            Ok(vfd) => vfd as i32,
+            Err(_) => {
+                overlay_forget_fd(kernel_fd);
+                unsafe { libc::close(kernel_fd) };
+                syscall_error(Errno::EMFILE, "open", "Too many files opened")
+            }
+        }
+    }
+
+    /// `stat()` through the overlay
+    pub fn stat(&self, path: &[u8], statbuf: &mut StatData) -> i32 {
+        let res = self.resolve(path, true).and_then(|r| match &r.found {
+            Lookup::Missing => Err(Errno::ENOENT),
+            Lookup::Other(_, st) => Ok(*st),
+            Lookup::Dir(layers) => lstat_at(self.fd(layers[0]), &r.rel()),
+        });
+        match res {
+            Ok(st) => {
+                convert_statdata_to_user(statbuf, st);
+                0
+            }
+            Err(e) => syscall_error(e, "stat", "overlay lookup failed"),
+        }
+    }
+
+    /// `access()` through the overlay. Write access to a lower file is granted on its
+    /// permission bits, since writing would copy it up.
+    pub fn access(&self, path: &[u8], amode: i32) -> i32 {
+        let res = self.resolve(path, true).and_then(|r| {
+            let (layer, st) = match &r.found {
+                Lookup::Missing => return Err(Errno::ENOENT),
+                Lookup::Other(layer, st) => (*layer, *st),
+                Lookup::Dir(layers) => (layers[0], lstat_at(self.fd(layers[0]), &r.rel())?),
+            };
+            let mut host_mode = amode;
+            if layer != 0 && amode & libc::W_OK != 0 {
+                if st.st_mode & 0o222 == 0 {
+                    return Err(Errno::EACCES);
+                }
+                host_mode &= !libc::W_OK;
+            }
+            let fd = openat2(self.fd(layer), &r.rel(), libc::O_PATH, 0)?;
+            let empty = cstring(b"")?;
+            let ret = unsafe {
+                libc::syscall(
+                    libc::SYS_faccessat2,
+                    fd.as_raw_fd(),
+                    empty.as_ptr(),
+                    host_mode,
+                    libc::AT_EMPTY_PATH,
+                )
+            };
+            check(ret as i32).map(|_| 0)
+        });
+        ret_or_error(res, "access")
+    }
+
+    /// `mkdir()` through the overlay
+    pub fn mkdir(&self, path: &[u8], mode: u32) -> i32 {
+        let _guard = COPYUP_LOCK.lock();
+        let res = self.resolve(path, false).and_then(|r| {
+            if !matches!(r.found, Lookup::Missing) {
+                return Err(Errno::EEXIST);
+            }
+            check_name(&r.name)?;
+            let (parent, name) = self.upper_slot(&r)?;
+            check(unsafe { libc::mkdirat(parent.as_raw_fd(), name.as_ptr(), mode) })?;
+            // a directory replacing a removed one must not show the old contents
+            if clear_whiteout(parent.as_raw_fd(), &r.name)? {
+                self.make_opaque(&r.rel())?;
+            }
+            Ok(0)
+        });
+        ret_or_error(res, "mkdir")
+    }
+
+    /// `rmdir()` through the overlay. Leaves a whiteout if a lower layer has the directory.
+    pub fn rmdir(&self, path: &[u8]) -> i32 {
+        let _guard = COPYUP_LOCK.lock();
+        let res = self.resolve(path, false).and_then(|r| {
+            let layers = match &r.found {
+                _ if r.name.is_empty() => return Err(Errno::EBUSY),
+                Lookup::Missing => return Err(Errno::ENOENT),
+                Lookup::Other(..) => return Err(Errno::ENOTDIR),
+                Lookup::Dir(layers) => layers,
+            };
+            let rel = r.rel();
+            if !self.merged_entries(layers, &rel, false)?.is_empty() {
+                return Err(Errno::ENOTEMPTY);
+            }
+            let lower = self.lower_has(&r)?;
+            let (parent, name) = self.upper_slot(&r)?;
+            if layers[0] == 0 {
+                self.purge_upper_dir(&rel)?;
+                check(unsafe {
+                    libc::unlinkat(parent.as_raw_fd(), name.as_ptr(), libc::AT_REMOVEDIR)
+                })?;
+            }
+            if lower {
+                add_whiteout(parent.as_raw_fd(), &r.name)?;
+            }
+            Ok(0)
+        });
+        ret_or_error(res, "rmdir")
+    }
+
+    /// `unlink()` through the overlay. Leaves a whiteout if a lower layer has the file.
+    pub fn unlink(&self, path: &[u8]) -> i32 {
+        let _guard = COPYUP_LOCK.lock();
+        let res = self.resolve(path, false).and_then(|r| {
+            let layer = match r.found {
+                Lookup::Missing => return Err(Errno::ENOENT),
+                Lookup::Dir(_) => return Err(Errno::EISDIR),
+                Lookup::Other(layer, _) => layer,
+            };
+            let lower = self.lower_has(&r)?;
+            let (parent, name) = self.upper_slot(&r)?;
+            if layer == 0 {
+                check(unsafe { libc::unlinkat(parent.as_raw_fd(), name.as_ptr(), 0) })?;
+            }
+            if lower {
+                add_whiteout(parent.as_raw_fd(), &r.name)?;
+            }
+            Ok(0)
+        });
+        ret_or_error(res, "unlink")
+    }
+
+    /// `rename()` through the overlay
+    pub fn rename(&self, oldpath: &[u8], newpath: &[u8]) -> i32 {
+        let _guard = COPYUP_LOCK.lock();
+        let res = self.resolve(oldpath, false).and_then(|old| {
+            let new = self.resolve(newpath, false)?;
+            if old.name.is_empty() || new.name.is_empty() {
+                return Err(Errno::EBUSY);
+            }
+            let (old_rel, new_rel) = (old.rel(), new.rel());
+            if old_rel == new_rel {
+                return Ok(0);
+            }
+            let old_is_dir = match (&old.found, &new.found) {
+                (Lookup::Missing, _) => return Err(Errno::ENOENT),
+                (Lookup::Dir(_), Lookup::Other(..)) => return Err(Errno::ENOTDIR),
+                (Lookup::Other(..), Lookup::Dir(_)) => return Err(Errno::EISDIR),
+                (Lookup::Dir(_), _) => true,
+                _ => false,
+            };
+            if old_is_dir && new_rel.starts_with(&[old_rel.as_slice(), b"/"].concat()) {
+                return Err(Errno::EINVAL);
+            }
+            check_name(&new.name)?;
+            let old_lower = self.lower_has(&old)?;
+            if old_is_dir && old_lower {
+                return Err(Errno::EXDEV);
+            }
+            let new_lower = self.lower_has(&new)?;
+            if let Lookup::Dir(layers) = &new.found {
+                if !self.merged_entries(layers, &new_rel, false)?.is_empty() {
+                    return Err(Errno::ENOTEMPTY);
+                }
+                if layers[0] == 0 {
+                    self.purge_upper_dir(&new_rel)?;
+                }
+            }
+
+            self.copy_up(&old)?;
+            let (old_parent, old_name) = self.upper_slot(&old)?;
+            let (new_parent, new_name) = self.upper_slot(&new)?;
+            check(unsafe {
+                libc::renameat(
+                    old_parent.as_raw_fd(),
+                    old_name.as_ptr(),
+                    new_parent.as_raw_fd(),
+                    new_name.as_ptr(),
+                )
+            })?;
+            clear_whiteout(new_parent.as_raw_fd(), &new.name)?;
+            if old_lower {
+                add_whiteout(old_parent.as_raw_fd(), &old.name)?;
+            }
+            if old_is_dir && new_lower {
+                self.make_opaque(&new_rel)?;
+            }
+            Ok(0)
+        });
+        ret_or_error(res, "rename")
+    }
+
+    /// `link()` through the overlay. The source is copied up first.
+    pub fn link(&self, oldpath: &[u8], newpath: &[u8]) -> i32 {
+        let _guard = COPYUP_LOCK.lock();
+        let res = self.resolve(oldpath, false).and_then(|old| {
+            let new = self.resolve(newpath, false)?;
+            match old.found {
+                Lookup::Missing => return Err(Errno::ENOENT),
+                Lookup::Dir(_) => return Err(Errno::EPERM),
+                Lookup::Other(..) => {}
+            }
+            if !matches!(new.found, Lookup::Missing) {
+                return Err(Errno::EEXIST);
+            }
+            check_name(&new.name)?;
+            self.copy_up(&old)?;
+            let (old_parent, old_name) = self.upper_slot(&old)?;
+            let (new_parent, new_name) = self.upper_slot(&new)?;
+            check(unsafe {
+                libc::linkat(
+                    old_parent.as_raw_fd(),
+                    old_name.as_ptr(),
+                    new_parent.as_raw_fd(),
+                    new_name.as_ptr(),
+                    0,
+                )
+            })?;
+            clear_whiteout(new_parent.as_raw_fd(), &new.name)?;
+            Ok(0)
+        });
+        ret_or_error(res, "link")
+    }
+
+    /// `symlink()` through the overlay. `target` is stored as given.
+    pub fn symlink(&self, target: &str, linkpath: &[u8]) -> i32 {
+        let _guard = COPYUP_LOCK.lock();
+        let res = self.resolve(linkpath, false).and_then(|r| {
+            if !matches!(r.found, Lookup::Missing) {
+                return Err(Errno::EEXIST);
+            }
+            check_name(&r.name)?;
+            let target = cstring(target.as_bytes())?;
+            let (parent, name) = self.upper_slot(&r)?;
+            check(unsafe { libc::symlinkat(target.as_ptr(), parent.as_raw_fd(), name.as_ptr()) })?;
+            clear_whiteout(parent.as_raw_fd(), &r.name)?;
+            Ok(0)
+        });
+        ret_or_error(res, "symlink")
+    }
+
+    /// `readlink()` through the overlay
+    pub fn readlink(&self, path: &[u8], buf: *mut u8, bufsiz: usize) -> i32 {
+        let res = self.resolve(path, false).and_then(|r| match &r.found {
+            Lookup::Missing => Err(Errno::ENOENT),
+            Lookup::Other(layer, st) if is_lnk(st) => {
+                let target = readlink_at(self.fd(*layer), &r.rel())?;
+                let len = target.len().min(bufsiz);
+                unsafe { std::ptr::copy_nonoverlapping(target.as_ptr(), buf, len) };
+                Ok(len as i32)
+            }
+            _ => Err(Errno::EINVAL),
+        });
+        ret_or_error(res, "readlink")
+    }
+
+    /// `chmod()` through the overlay. The file is copied up first.
+    pub fn chmod(&self, path: &[u8], mode: u32) -> i32 {
+        let _guard = COPYUP_LOCK.lock();
+        let res = self.resolve(path, true).and_then(|r| {
+            self.copy_up(&r)?;
+            let (parent, name) = self.upper_slot(&r)?;
+            check(unsafe { libc::fchmodat(parent.as_raw_fd(), name.as_ptr(), mode, 0) })
+        });
+        ret_or_error(res, "chmod")
+    }
+
+    /// `truncate()` through the overlay. The file is copied up first.
+    pub fn truncate(&self, path: &[u8], length: i64) -> i32 {
+        let _guard = COPYUP_LOCK.lock();
+        let res = self.resolve(path, true).and_then(|r| {
+            if let Lookup::Dir(_) = r.found {
+                return Err(Errno::EISDIR);
+            }
+            self.copy_up(&r)?;
+            let fd = openat2(self.fd(0), &r.rel(), libc::O_WRONLY, 0)?;
+            check(unsafe { libc::ftruncate(fd.as_raw_fd(), length) })
+        });
+        ret_or_error(res, "truncate")
+    }
+
+    /// Checks that `path` names a directory. The caller only updates the cage's cwd, since
+    /// the directory may not exist in the lindfs image.
+    pub fn chdir(&self, path: &[u8]) -> i32 {
+        let res = self.resolve(path, true).and_then(|r| match r.found {
+            Lookup::Dir(_) => Ok(0),
+            Lookup::Other(..) => Err(Errno::ENOTDIR),
+            Lookup::Missing => Err(Errno::ENOENT),
+        });
+        ret_or_error(res, "chdir")
+    }
+
+    /// `statfs()` through the overlay, which reports the filesystem holding the upper layer
+    pub fn statfs(&self, path: &[u8]) -> Result<libc::statfs, Errno> {
+        if let Lookup::Missing = self.resolve(path, true)?.found {
+            return Err(Errno::ENOENT);
+        }
+        let mut buf: libc::statfs = unsafe { std::mem::zeroed() };
+        check(unsafe { libc::fstatfs(self.fd(0), &mut buf) })?;
+        Ok(buf)
+    }
+}
+
+fn ret_or_error(res: Result<i32, Errno>, syscall: &str) -> i32 {
+    res.unwrap_or_else(|e| syscall_error(e, syscall, "overlay operation failed"))
+}
+
+pub(crate) fn last_errno() -> Errno {
+    Errno::from_discriminant(get_errno()).unwrap_or(Errno::EIO)
+}
+
+fn io_errno(e: std::io::Error) -> Errno {
+    e.raw_os_error()
+        .and_then(|errno| Errno::from_discriminant(errno).ok())
+        .unwrap_or(Errno::EIO)
+}
+
+fn check(ret: i32) -> Result<i32, Errno> {
+    if ret < 0 {
+        Err(last_errno())
+    } else {
+        Ok(ret)
+    }
+}
+
+fn cstring(bytes: &[u8]) -> Result<CString, Errno> {
+    CString::new(bytes).map_err(|_| Errno::EINVAL)
+}
+
+fn check_name(name: &[u8]) -> Result<(), Errno> {
+    if name.starts_with(WHITEOUT_PREFIX) {
+        return Err(Errno::EINVAL);
+    }
+    Ok(())
+}
+
+fn is_dir(st: &libc::stat) -> bool {
+    st.st_mode & libc::S_IFMT == libc::S_IFDIR
+}
+
+fn is_lnk(st: &libc::stat) -> bool {
+    st.st_mode & libc::S_IFMT == libc::S_IFLNK
+}
+
+fn join(dir: &[u8], name: &[u8]) -> Vec<u8> {
+    if dir.is_empty() {
+        name.to_vec()
+    } else if name.is_empty() {
+        dir.to_vec()
+    } else {
+        [dir, b"/", name].concat()
+    }
+}
+
+fn components(path: &[u8]) -> impl Iterator<Item = Vec<u8>> + '_ {
+    path.split(|&b| b == b'/')
+        .filter(|c| !c.is_empty() && *c != b".")
+        .map(|c| c.to_vec())
+}
+
+fn guest_path(rel: &[u8]) -> String {
+    format!("/{}", String::from_utf8_lossy(rel))
+}
+
+// Open `rel` below the layer root `dirfd` without ever leaving it
+fn openat2(dirfd: RawFd, rel: &[u8], flags: i32, mode: u32) -> Result<OwnedFd, Errno> {
+    let path = cstring(if rel.is_empty() { b"." } else { rel })?;
+    let mut how: libc::open_how = unsafe { std::mem::zeroed() };
+    how.flags = (flags | libc::O_CLOEXEC) as u64;
+    if flags & (libc::O_CREAT | libc::O_TMPFILE) != 0 {
+        how.mode = (mode & 0o7777) as u64;
+    }
+    how.resolve = libc::RESOLVE_IN_ROOT | libc::RESOLVE_NO_MAGICLINKS;
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_openat2,
+            dirfd,
+            path.as_ptr(),
+            &how as *const libc::open_how,
+            std::mem::size_of::<libc::open_how>(),
+        )
+    };
+    if fd < 0 {
+        return Err(last_errno());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as RawFd) })
+}
+
+fn lstat_at(dirfd: RawFd, rel: &[u8]) -> Result<libc::stat, Errno> {
+    let fd = openat2(dirfd, rel, libc::O_PATH | libc::O_NOFOLLOW, 0)?;
+    let mut st: libc::stat = unsafe { std::mem::zeroed() };
+    check(unsafe { libc::fstat(fd.as_raw_fd(), &mut st) })?;
+    Ok(st)
+}
+
+fn readlink_at(dirfd: RawFd, rel: &[u8]) -> Result<Vec<u8>, Errno> {
+    let fd = openat2(dirfd, rel, libc::O_PATH | libc::O_NOFOLLOW, 0)?;
+    let mut buf = vec![0u8; PATH_MAX];
+    let empty = cstring(b"")?;
+    let len = unsafe {
+        libc::readlinkat(
+            fd.as_raw_fd(),
+            empty.as_ptr(),
+            buf.as_mut_ptr() as *mut libc::c_char,
+            buf.len(),
+        )
+    };
+    if len < 0 {
+        return Err(last_errno());
+    }
+    buf.truncate(len as usize);
+    Ok(buf)
+}
+
+// Create the directory `rel` (whose parent exists) below the layer root `dirfd` with exactly
+// the permission bits `mode`, regardless of the umask
+fn mkdir_at(dirfd: RawFd, rel: &[u8], mode: u32) -> Result<(), Errno> {
+    let (parent, name) = match rel.iter().rposition(|&b| b == b'/') {
+        Some(pos) => (&rel[..pos], &rel[pos + 1..]),
+        None => (&b""[..], rel),
+    };
+    let parent = openat2(dirfd, parent, libc::O_PATH | libc::O_DIRECTORY, 0)?;
+    let name = cstring(name)?;
+    check(unsafe { libc::mkdirat(parent.as_raw_fd(), name.as_ptr(), mode) })?;
+    check(unsafe { libc::fchmodat(parent.as_raw_fd(), name.as_ptr(), mode, 0) }).map(drop)
+}
+
+fn add_whiteout(parent: RawFd, name: &[u8]) -> Result<(), Errno> {
+    openat2(
+        parent,
+        &[WHITEOUT_PREFIX, name].concat(),
+        libc::O_RDONLY | libc::O_CREAT,
+        0,
+    )
+    .map(drop)
+}
+
+// Returns whether there was a whiteout to remove
+fn clear_whiteout(parent: RawFd, name: &[u8]) -> Result<bool, Errno> {
+    let whiteout = cstring(&[WHITEOUT_PREFIX, name].concat())?;
+    match check(unsafe { libc::unlinkat(parent, whiteout.as_ptr(), 0) }) {
+        Ok(_) => Ok(true),
+        Err(Errno::ENOENT) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+pub(crate) fn read_dir(dirfd: RawFd) -> Result<Vec<DirEntry>, Errno> {
+    let mut entries = Vec::new();
+    let mut buf = vec![0u8; 8192];
+    loop {
+        let n = unsafe { libc::syscall(libc::SYS_getdents64, dirfd, buf.as_mut_ptr(), buf.len()) };
+        if n < 0 {
+            return Err(last_errno());
+        }
+        if n == 0 {
+            return Ok(entries);
+        }
+        let mut off = 0usize;
+        while off < n as usize {
+            let rec = &buf[off..];
+            let ino = u64::from_ne_bytes(rec[0..8].try_into().unwrap());
+            let reclen = u16::from_ne_bytes(rec[16..18].try_into().unwrap()) as usize;
+            let name = &rec[19..reclen];
+            let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+            entries.push(DirEntry {
+                ino,
+                d_type: rec[18],
+                name: String::from_utf8_lossy(name).into_owned(),
+            });
+            off += reclen;
+        }
+    }
+}
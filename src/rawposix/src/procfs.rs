@@ -0,0 +1,198 @@
+//! `/proc`-style memory statistics and system identity
+//!
+//! lindfs has no `/proc`, and the host's would describe the runtime rather than the cage. The
+//! few files that report memory usage are generated here from the cage's `Vmmap` instead:
+//! - `/proc/self/statm` and `/proc/<cageid>/statm`
+//! - `/proc/self/status` and `/proc/<cageid>/status` (the `Vm*` lines only)
+//! - `/proc/meminfo`, whose total is the global memory budget when one is set
+//!
+//! and those under `/proc/sys/kernel` that name the system from the cage's UTS record (see
+//! `crate::uts`): `hostname`, `domainname`, `ostype`, `osrelease` and `version`. Unlike on
+//! Linux they cannot be written to; `sethostname()` and `setdomainname()` can.
+//!
+//! The content is rendered once when the file is opened and handed to the cage as a sealed,
+//! read-only memfd, so reads, seeks and `fstat` behave like on any other regular file.
+//! "Size" figures are the committed pages, as the reserved but inaccessible part of the wasm
+//! linear memory (`PROT_NONE`) is not memory the cage uses.
+
+use crate::oom::{memory_budget_global_pages, total_committed_pages};
+use crate::uts::uts_field;
+use cage::get_cage;
+use fdtables;
+use std::ffi::CString;
+use sysdefs::constants::err_const::{get_errno, handle_errno, syscall_error, Errno};
+use sysdefs::constants::fs_const::{O_ACCMODE, O_CLOEXEC, O_RDONLY, PAGESHIFT};
+use sysdefs::constants::lind_platform_const::FDKIND_KERNEL;
+use sysdefs::constants::sys_const::UTSNAME_LENGTH;
+use sysdefs::data::sys_struct::UtsNameStruct;
+
+/// A file served by this module
+#[derive(Clone, Copy, Debug)]
+pub enum ProcFile {
+    Statm(u64),
+    Status(u64),
+    Meminfo,
+    /// A `/proc/sys/kernel` file of the cage, the field of its UTS record it shows
+    Kernel(u64, fn(&UtsNameStruct) -> &[u8; UTSNAME_LENGTH]),
+}
+
+/// Resolve a normalized absolute path to one of the generated `/proc` files of `cageid`.
+///
+/// ## Returns:
+/// - `Some(file)` if the path names one of the files above
+/// - `None` otherwise, in which case the caller should fall back to the filesystem
+pub fn procfs_lookup(cageid: u64, path: &[u8]) -> Option<ProcFile> {
+    if path == b"/proc/meminfo" {
+        return Some(ProcFile::Meminfo);
+    }
+    if let Some(name) = path.strip_prefix(b"/proc/sys/kernel/") {
+        let field: fn(&UtsNameStruct) -> &[u8; UTSNAME_LENGTH] = match name {
+            b"hostname" => |uts| &uts.nodename,
+            b"domainname" => |uts| &uts.domainname,
+            b"ostype" => |uts| &uts.sysname,
+            b"osrelease" => |uts| &uts.release,
+            b"version" => |uts| &uts.version,
+            _ => return None,
+        };
+        return Some(ProcFile::Kernel(cageid, field));
+    }
+
+    let rest = path.strip_prefix(b"/proc/")?;
+    let slash = rest.iter().position(|c| *c == b'/')?;
+    let (dir, file) = (&rest[..slash], &rest[slash + 1..]);
+    let target = if dir == b"self" {
+        cageid
+    } else {
+        std::str::from_utf8(dir).ok()?.parse::<u64>().ok()?
+    };
+
+    match file {
+        b"statm" => Some(ProcFile::Statm(target)),
+        b"status" => Some(ProcFile::Status(target)),
+        _ => None,
+    }
+}
+
+/// Open a generated `/proc` file for `cageid`.
+///
+/// ## Returns:
+/// - the new virtual fd on success
+/// - `-EACCES` when opened for writing, `-ENOENT` if the cage does not exist, `-EMFILE` if the
+///   cage has no free fd, or the error from creating the memfd
+pub fn procfs_open(cageid: u64, file: ProcFile, oflag: i32) -> i32 {
+    if (oflag & O_ACCMODE) != O_RDONLY {
+        return syscall_error(Errno::EACCES, "open", "procfs files are read-only");
+    }
+
+    let content = match render(file) {
+        Some(content) => content,
+        None => return syscall_error(Errno::ENOENT, "open", "no such cage"),
+    };
+
+    open_generated(cageid, "lind-procfs", content.as_bytes(), oflag)
+}
+
+/// Hand `content` to `cageid` as a new fd on a sealed, read-only memfd named `name`, the way
+/// every generated file is served.
+///
+/// ## Returns:
+/// - the new virtual fd on success
+/// - `-EMFILE` if the cage has no free fd, or the error from creating the memfd
+pub(crate) fn open_generated(cageid: u64, name: &str, content: &[u8], oflag: i32) -> i32 {
+    let name = CString::new(name).unwrap();
+    let kernel_fd =
+        unsafe { libc::memfd_create(name.as_ptr(), libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING) };
+    if kernel_fd < 0 {
+        return handle_errno(get_errno(), "open");
+    }
+
+    let written = unsafe {
+        libc::write(
+            kernel_fd,
+            content.as_ptr() as *const libc::c_void,
+            content.len(),
+        )
+    };
+    let seals = libc::F_SEAL_WRITE | libc::F_SEAL_GROW | libc::F_SEAL_SHRINK | libc::F_SEAL_SEAL;
+    if written != content.len() as isize
+        || unsafe { libc::fcntl(kernel_fd, libc::F_ADD_SEALS, seals) } < 0
+        || unsafe { libc::lseek(kernel_fd, 0, libc::SEEK_SET) } < 0
+    {
+        let ret = handle_errno(get_errno(), "open");
+        unsafe { libc::close(kernel_fd) };
+        return ret;
+    }
+
+    let should_cloexec = (oflag & O_CLOEXEC) != 0;
+    match fdtables::get_unused_virtual_fd(
+        cageid,
+        FDKIND_KERNEL,
+        kernel_fd as u64,
+        should_cloexec,
+        0,
+    ) {
+        Ok(vfd) => vfd as i32,
+        Err(_) => {
+            unsafe { libc::close(kernel_fd) };
+            syscall_error(Errno::EMFILE, "open", "Too many files opened")
+        }
+    }
+}
+
+/// Pages to kilobytes
+fn kb(pages: u64) -> u64 {
+    pages << (PAGESHIFT - 10)
+}
+
+/// Render the content of `file`, or `None` if the cage it describes does not exist
+fn render(file: ProcFile) -> Option<String> {
+    match file {
+        ProcFile::Statm(target) => {
+            let cage = get_cage(target)?;
+            let mut vmmap = cage.vmmap.write();
+            let resident = vmmap.sample_resident_pages();
+            // size resident shared text lib data dt
+            Some(format!(
+                "{} {} 0 0 0 {} 0\n",
+                vmmap.committed_pages, resident, vmmap.committed_pages
+            ))
+        }
+        ProcFile::Status(target) => {
+            let cage = get_cage(target)?;
+            let mut vmmap = cage.vmmap.write();
+            let resident = vmmap.sample_resident_pages();
+            Some(format!(
+                "Pid:\t{}\nPPid:\t{}\nVmPeak:\t{:8} kB\nVmSize:\t{:8} kB\nVmHWM:\t{:8} kB\nVmRSS:\t{:8} kB\n",
+                target,
+                cage.parent,
+                kb(vmmap.peak_committed_pages),
+                kb(vmmap.committed_pages),
+                kb(vmmap.peak_resident_pages),
+                kb(resident),
+            ))
+        }
+        ProcFile::Meminfo => {
+            let total = match memory_budget_global_pages() {
+                Some(pages) => pages,
+                None => {
+                    let pages = unsafe { libc::sysconf(libc::_SC_PHYS_PAGES) };
+                    let pagesize = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+                    ((pages.max(0) as u64) * (pagesize.max(0) as u64)) >> PAGESHIFT
+                }
+            };
+            let free = total.saturating_sub(total_committed_pages());
+            Some(format!(
+                "MemTotal:       {:8} kB\nMemFree:        {:8} kB\nMemAvailable:   {:8} kB\n",
+                kb(total),
+                kb(free),
+                kb(free),
+            ))
+        }
+        ProcFile::Kernel(target, field) => {
+            let cage = get_cage(target)?;
+            let uts = cage.uts.read();
+            let name = String::from_utf8_lossy(uts_field(field(&uts))).into_owned();
+            Some(name + "\n")
+        }
+    }
+}
@@ -0,0 +1,422 @@
+//! Resolver configuration and DNS stub
+//!
+//! lindfs ships no resolver configuration, so `getaddrinfo` in a guest fails even for
+//! `localhost`, or falls back to whatever answers DNS on the host's loopback. With
+//! `--resolver-files`, `--add-host` or `--dns-stub`, lind-boot calls `resolver_init` and the
+//! files glibc's resolver reads are generated here instead, like the ones of `crate::procfs`:
+//! - `/etc/hosts`: `localhost`, the cage's host name (see `crate::uts`) on `127.0.1.1`, and
+//!   every `--add-host NAME:ADDR`
+//! - `/etc/nsswitch.conf`: host names are looked up in `/etc/hosts`, then in DNS if the stub
+//!   runs, and nowhere else
+//! - `/etc/resolv.conf`: the stub as the only name server, if it runs
+//!
+//! The DNS stub (`--dns-stub`, which needs `--netns`) listens on UDP port 53 of the network
+//! namespace, so the `127.0.0.1` of `resolv.conf` reaches it without any privilege on the
+//! host. It answers `A`, `AAAA` and `PTR` queries from the same table as `/etc/hosts` (the host
+//! name excepted, as it differs between cages) and fails every other name with `NXDOMAIN`, so no
+//! query ever leaves the instance.
+
+use crate::netns::{netns_enabled, netns_service_socket};
+use crate::procfs::open_generated;
+use cage::get_cage;
+use std::fmt::Write;
+use std::net::IpAddr;
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::thread;
+use sysdefs::constants::err_const::{syscall_error, Errno};
+use sysdefs::constants::fs_const::{O_ACCMODE, O_RDONLY, S_IFREG};
+use sysdefs::constants::net_const::SOCK_DGRAM;
+use sysdefs::data::fs_struct::StatData;
+
+/// The port of the stub in the namespace
+const DNS_PORT: u16 = 53;
+/// Largest DNS message over UDP without EDNS
+const DNS_UDP_MAX: usize = 512;
+/// TTL of the answers, in seconds
+const DNS_TTL: u32 = 60;
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_AAAA: u16 = 28;
+const TYPE_ANY: u16 = 255;
+const CLASS_IN: u16 = 1;
+const CLASS_ANY: u16 = 255;
+
+const RCODE_FORMERR: u16 = 1;
+const RCODE_NXDOMAIN: u16 = 3;
+const RCODE_NOTIMP: u16 = 4;
+const RCODE_REFUSED: u16 = 5;
+
+/// A static host entry, `NAME:ADDR` on the command line
+#[derive(Clone, Debug)]
+pub struct HostEntry {
+    pub name: String,
+    pub addr: IpAddr,
+}
+
+impl FromStr for HostEntry {
+    type Err = String;
+
+    /// Parse `NAME:ADDR`, where an IPv6 `ADDR` may be put in brackets
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, addr) = s
+            .split_once(':')
+            .ok_or_else(|| format!("host entry must be NAME:ADDR: {}", s))?;
+        let addr = addr
+            .strip_prefix('[')
+            .and_then(|addr| addr.strip_suffix(']'))
+            .unwrap_or(addr);
+        let valid_name = !name.is_empty()
+            && name.len() <= 253
+            && name
+                .split('.')
+                .all(|label| !label.is_empty() && label.len() <= 63)
+            && !name.bytes().any(|c| c.is_ascii_whitespace() || c == b'#');
+        if !valid_name {
+            return Err(format!("host entry has an invalid name: {}", s));
+        }
+        let addr = addr
+            .parse()
+            .map_err(|_| format!("host entry has an invalid address: {}", s))?;
+        Ok(HostEntry {
+            name: name.to_string(),
+            addr,
+        })
+    }
+}
+
+/// What lind-boot asks for
+#[derive(Clone, Debug, Default)]
+pub struct ResolverConfig {
+    /// Entries added to `localhost` in `/etc/hosts` and in the stub's table
+    pub hosts: Vec<HostEntry>,
+    /// Whether to run the DNS stub
+    pub dns_stub: bool,
+}
+
+static RESOLVER: OnceLock<ResolverConfig> = OnceLock::new();
+
+/// Generate the resolver files and start the DNS stub if requested. Has to be called after
+/// `netns_init` and before `rawposix_start`.
+///
+/// ## Returns:
+/// - `Ok(())` on success
+/// - `Err(EINVAL)` if the stub is requested without the network namespace, `Err(EBUSY)` if the
+///   resolver was already set up, or the error from binding the stub's socket
+pub fn resolver_init(config: ResolverConfig) -> Result<(), Errno> {
+    if config.dns_stub && !netns_enabled() {
+        return Err(Errno::EINVAL);
+    }
+    let socket = if config.dns_stub {
+        Some(netns_service_socket(SOCK_DGRAM, DNS_PORT)?)
+    } else {
+        None
+    };
+    RESOLVER.set(config).map_err(|_| Errno::EBUSY)?;
+
+    if let Some(socket) = socket {
+        thread::Builder::new()
+            .name("lind-dns-stub".to_string())
+            .spawn(move || serve(socket))
+            .map_err(|_| Errno::EAGAIN)?;
+    }
+    Ok(())
+}
+
+/// A file served by this module
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResolverFile {
+    Hosts,
+    Nsswitch,
+    ResolvConf,
+}
+
+/// Resolve a normalized absolute path to one of the generated resolver files.
+///
+/// ## Returns:
+/// - `Some(file)` if the resolver is set up and the path names one of its files
+/// - `None` otherwise, in which case the caller should fall back to the filesystem
+pub fn resolver_lookup(path: &[u8]) -> Option<ResolverFile> {
+    RESOLVER.get()?;
+    match path {
+        b"/etc/hosts" => Some(ResolverFile::Hosts),
+        b"/etc/nsswitch.conf" => Some(ResolverFile::Nsswitch),
+        b"/etc/resolv.conf" => Some(ResolverFile::ResolvConf),
+        _ => None,
+    }
+}
+
+/// Open a generated resolver file for `cageid`.
+///
+/// ## Returns:
+/// - the new virtual fd on success
+/// - `-EACCES` when opened for writing, or the error of `open_generated`
+pub fn resolver_open(cageid: u64, file: ResolverFile, oflag: i32) -> i32 {
+    if (oflag & O_ACCMODE) != O_RDONLY {
+        return syscall_error(Errno::EACCES, "open", "resolver files are read-only");
+    }
+    let content = render(cageid, file);
+    open_generated(cageid, "lind-resolver", content.as_bytes(), oflag)
+}
+
+/// Fill `statbuf` for a generated resolver file, a `rw-r--r--` regular file of root
+pub fn resolver_stat(cageid: u64, file: ResolverFile, statbuf: &mut StatData) {
+    let size = render(cageid, file).len();
+
+    *statbuf = StatData::default();
+    statbuf.st_dev = 0x1d; // an anonymous device, like a memfd's
+    statbuf.st_ino = file as usize + 1;
+    statbuf.st_mode = (S_IFREG | 0o644) as u32;
+    statbuf.st_nlink = 1;
+    statbuf.st_size = size;
+    statbuf.st_blksize = 4096;
+    statbuf.st_blocks = size.div_ceil(512) as u32;
+}
+
+/// Check an access mode against a generated resolver file, which can only be read
+pub fn resolver_access(amode: i32) -> i32 {
+    if amode & (libc::W_OK | libc::X_OK) != 0 {
+        return syscall_error(Errno::EACCES, "access", "Permission denied");
+    }
+    0
+}
+
+/// The configuration. Only called for files `resolver_lookup` found, so it is set.
+fn config() -> &'static ResolverConfig {
+    RESOLVER.get().unwrap()
+}
+
+/// `localhost` followed by the configured entries
+fn host_table() -> impl Iterator<Item = (&'static str, IpAddr)> {
+    let localhost = [
+        ("localhost", IpAddr::from([127, 0, 0, 1])),
+        ("localhost", IpAddr::from([0, 0, 0, 0, 0, 0, 0, 1])),
+    ];
+    localhost.into_iter().chain(
+        config()
+            .hosts
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.addr)),
+    )
+}
+
+fn render(cageid: u64, file: ResolverFile) -> String {
+    let mut content = String::from("# Generated by lind-boot\n");
+    match file {
+        ResolverFile::Hosts => {
+            for (name, addr) in host_table() {
+                let _ = writeln!(content, "{}\t{}", addr, name);
+            }
+            if let Some(cage) = get_cage(cageid) {
+                let nodename = cage.uts.read().nodename;
+                let hostname = crate::uts::uts_field(&nodename);
+                if !hostname.is_empty() {
+                    let _ = writeln!(content, "127.0.1.1\t{}", String::from_utf8_lossy(hostname));
+                }
+            }
+        }
+        ResolverFile::Nsswitch => {
+            let hosts = if config().dns_stub {
+                "files dns"
+            } else {
+                "files"
+            };
+            let _ = writeln!(content, "passwd:\tfiles\ngroup:\tfiles\nhosts:\t{}", hosts);
+            content.push_str("networks:\tfiles\nprotocols:\tfiles\nservices:\tfiles\n");
+        }
+        ResolverFile::ResolvConf => {
+            if config().dns_stub {
+                content.push_str("nameserver 127.0.0.1\n");
+            }
+            content.push_str("options attempts:1 timeout:1\n");
+        }
+    }
+    content
+}
+
+/// Answer the queries of the cages until the instance exits
+fn serve(socket: OwnedFd) {
+    let mut query = [0u8; DNS_UDP_MAX];
+    loop {
+        let mut peer: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+        let mut peerlen = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+        let len = unsafe {
+            libc::recvfrom(
+                socket.as_raw_fd(),
+                query.as_mut_ptr() as *mut libc::c_void,
+                query.len(),
+                0,
+                &mut peer as *mut _ as *mut libc::sockaddr,
+                &mut peerlen,
+            )
+        };
+        if len < 0 {
+            continue;
+        }
+        if let Some(reply) = answer(&query[..len as usize]) {
+            unsafe {
+                libc::sendto(
+                    socket.as_raw_fd(),
+                    reply.as_ptr() as *const libc::c_void,
+                    reply.len(),
+                    0,
+                    &peer as *const _ as *const libc::sockaddr,
+                    peerlen,
+                )
+            };
+        }
+    }
+}
+
+fn be16(msg: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*msg.get(pos)?, *msg.get(pos + 1)?]))
+}
+
+/// Read the uncompressed name at `pos` of a query, lowercased and without the root label.
+///
+/// ## Returns:
+/// The name and the position after it, or `None` if it is malformed
+fn read_name(msg: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut name = String::new();
+    loop {
+        let len = *msg.get(pos)? as usize;
+        pos += 1;
+        if len == 0 {
+            return Some((name, pos));
+        }
+        // Queries carry a single name, so there is nothing to point back to
+        if len > 63 {
+            return None;
+        }
+        let label = std::str::from_utf8(msg.get(pos..pos + len)?).ok()?;
+        if !name.is_empty() {
+            name.push('.');
+        }
+        name.push_str(&label.to_ascii_lowercase());
+        pos += len;
+    }
+}
+
+fn write_name(out: &mut Vec<u8>, name: &str) {
+    for label in name.split('.').filter(|label| !label.is_empty()) {
+        out.push(label.len() as u8);
+        out.extend_from_slice(label.as_bytes());
+    }
+    out.push(0);
+}
+
+/// The `in-addr.arpa` or `ip6.arpa` name of `addr`
+fn reverse_name(addr: IpAddr) -> String {
+    match addr {
+        IpAddr::V4(addr) => {
+            let [a, b, c, d] = addr.octets();
+            format!("{}.{}.{}.{}.in-addr.arpa", d, c, b, a)
+        }
+        IpAddr::V6(addr) => {
+            let mut name = String::new();
+            for byte in addr.octets().iter().rev() {
+                let _ = write!(name, "{:x}.{:x}.", byte & 0xf, byte >> 4);
+            }
+            name + "ip6.arpa"
+        }
+    }
+}
+
+/// The reply to a DNS query, or `None` if the message is not worth one
+fn answer(query: &[u8]) -> Option<Vec<u8>> {
+    let flags = be16(query, 2)?;
+    let qdcount = be16(query, 4)?;
+    // Never answer a response
+    if flags & 0x8000 != 0 {
+        return None;
+    }
+    let opcode = (flags >> 11) & 0xf;
+
+    let reply_header = |rcode: u16, qdcount: u16, ancount: u16| {
+        // QR, the opcode, AA, RD as asked and RA
+        let flags = 0x8000 | (opcode << 11) | 0x0400 | (flags & 0x0100) | 0x0080 | rcode;
+        let mut reply = query[..2].to_vec();
+        for field in [flags, qdcount, ancount, 0, 0] {
+            reply.extend_from_slice(&field.to_be_bytes());
+        }
+        reply
+    };
+
+    if opcode != 0 {
+        return Some(reply_header(RCODE_NOTIMP, 0, 0));
+    }
+    if qdcount != 1 {
+        return Some(reply_header(RCODE_FORMERR, 0, 0));
+    }
+    let Some((name, end)) = read_name(query, 12) else {
+        return Some(reply_header(RCODE_FORMERR, 0, 0));
+    };
+    let (Some(qtype), Some(qclass)) = (be16(query, end), be16(query, end + 2)) else {
+        return Some(reply_header(RCODE_FORMERR, 0, 0));
+    };
+    let question = &query[12..end + 4];
+    if qclass != CLASS_IN && qclass != CLASS_ANY {
+        let mut reply = reply_header(RCODE_REFUSED, 1, 0);
+        reply.extend_from_slice(question);
+        return Some(reply);
+    }
+
+    // (type, rdata) of the records of the name
+    let mut known = false;
+    let mut records: Vec<(u16, Vec<u8>)> = Vec::new();
+    for (host, addr) in host_table() {
+        if host.eq_ignore_ascii_case(&name) {
+            known = true;
+            match addr {
+                IpAddr::V4(v4) if matches!(qtype, TYPE_A | TYPE_ANY) => {
+                    records.push((TYPE_A, v4.octets().to_vec()))
+                }
+                IpAddr::V6(v6) if matches!(qtype, TYPE_AAAA | TYPE_ANY) => {
+                    records.push((TYPE_AAAA, v6.octets().to_vec()))
+                }
+                _ => {}
+            }
+        } else if reverse_name(addr) == name {
+            known = true;
+            if matches!(qtype, TYPE_PTR | TYPE_ANY) {
+                let mut rdata = Vec::new();
+                write_name(&mut rdata, host);
+                records.push((TYPE_PTR, rdata));
+            }
+        }
+    }
+    if !known {
+        let mut reply = reply_header(RCODE_NXDOMAIN, 1, 0);
+        reply.extend_from_slice(question);
+        return Some(reply);
+    }
+
+    let mut answers = Vec::new();
+    let mut ancount = 0u16;
+    let mut truncated = false;
+    for (rtype, rdata) in records {
+        let mut record = vec![0xc0, 12]; // the name of the question
+        for field in [rtype, CLASS_IN] {
+            record.extend_from_slice(&field.to_be_bytes());
+        }
+        record.extend_from_slice(&DNS_TTL.to_be_bytes());
+        record.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        record.extend_from_slice(&rdata);
+        if 12 + question.len() + answers.len() + record.len() > DNS_UDP_MAX {
+            truncated = true;
+            break;
+        }
+        answers.extend_from_slice(&record);
+        ancount += 1;
+    }
+
+    let mut reply = reply_header(0, 1, ancount);
+    if truncated {
+        reply[2] |= 0x02; // TC
+    }
+    reply.extend_from_slice(question);
+    reply.extend_from_slice(&answers);
+    Some(reply)
+}
@@ -0,0 +1,474 @@
+//! Ancillary data for `sendmsg` / `recvmsg`
+//!
+//! Guests build their control messages in the wasm32 layout (`struct cmsghdr` with a 4 byte
+//! `cmsg_len`, 4 byte alignment), while the host kernel expects the x86_64 one (8 byte
+//! `cmsg_len`, 8 byte alignment). Besides converting between the two, the payload of two
+//! message types has to be virtualized:
+//!
+//! - `SCM_RIGHTS` carries virtual fds. If every fd being sent is kernel-backed, the host fds
+//!   are passed through the kernel as usual, so the peer may even be a host process. Otherwise
+//!   the `fdtables` entries are parked in RawPOSIX's own fd table (`SCM_INFLIGHT_FDTABLE`),
+//!   which keeps the underlying objects alive, and a fresh memfd is sent in their place as a
+//!   ticket. The receiver recognizes the ticket by its inode and installs the parked entries
+//!   into its own table, in the order they were sent.
+//! - `SCM_CREDENTIALS` carries a pid, which for a cage is its cage id, and the ids of the
+//!   cage user. Sent credentials are checked against the sending cage and ride along with the
+//!   ticket, as the host would only ever report the runtime's own pid. They are delivered
+//!   only if the receiver asked for credentials with `SO_PASSCRED`; credentials the host
+//!   attaches by itself are reported with pid 0, as Linux does for a sender outside the
+//!   receiver's pid namespace.
+//!
+//! A ticket whose message is never received (e.g. the socket is closed with the message still
+//! queued) leaves its entries parked until RawPOSIX shuts down.
+
+use dashmap::DashMap;
+use fdtables::{self, FDTableEntry, FDT_KINDEPOLL};
+use lazy_static::lazy_static;
+use std::ffi::CString;
+use std::mem::size_of;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::ptr;
+use sysdefs::constants::err_const::Errno;
+use sysdefs::constants::lind_platform_const::{FDKIND_KERNEL, RAWPOSIX_CAGEID};
+use sysdefs::constants::net_const::{MSG_PEEK, SCM_CREDENTIALS, SCM_RIGHTS, SOL_SOCKET};
+use sysdefs::constants::sys_const::{DEFAULT_GID, DEFAULT_UID};
+
+/// fd table holding the entries of fds that are in flight in a message
+pub const SCM_INFLIGHT_FDTABLE: u64 = RAWPOSIX_CAGEID;
+
+/// Guest `struct cmsghdr` size and alignment: `size_t` is 4 bytes on wasm32
+const GUEST_CMSGHDR_LEN: usize = 12;
+const GUEST_CMSG_ALIGN: usize = 4;
+/// Host `struct cmsghdr` size and alignment
+const HOST_CMSGHDR_LEN: usize = 16;
+const HOST_CMSG_ALIGN: usize = 8;
+/// Extra room in the host receive buffer. The host message may be laid out differently from
+/// what the guest gets (wider headers, one ticket standing in for many fds), so the guest's
+/// buffer size is only a hint; whatever does not fit is released after conversion.
+const HOST_CONTROL_SLACK: usize = 512;
+
+/// Entries and credentials travelling with one ticket
+struct Inflight {
+    fds: Vec<u64>, // fds in `SCM_INFLIGHT_FDTABLE`, in the order they were sent
+    creds: Option<libc::ucred>,
+}
+
+lazy_static! {
+    /// In-flight tickets, keyed by the (st_dev, st_ino) of the ticket memfd
+    static ref INFLIGHT: DashMap<(u64, u64), Inflight> = DashMap::new();
+}
+
+fn align(len: usize, to: usize) -> usize {
+    (len + to - 1) & !(to - 1)
+}
+
+fn fd_key(fd: i32) -> Option<(u64, u64)> {
+    let mut st: libc::stat = unsafe { std::mem::zeroed() };
+    if unsafe { libc::fstat(fd, &mut st) } < 0 {
+        return None;
+    }
+    Some((st.st_dev as u64, st.st_ino as u64))
+}
+
+fn ucred_bytes(creds: &libc::ucred) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(size_of::<libc::ucred>());
+    bytes.extend_from_slice(&creds.pid.to_ne_bytes());
+    bytes.extend_from_slice(&creds.uid.to_ne_bytes());
+    bytes.extend_from_slice(&creds.gid.to_ne_bytes());
+    bytes
+}
+
+fn ucred_from_bytes(data: &[u8]) -> Option<libc::ucred> {
+    if data.len() < size_of::<libc::ucred>() {
+        return None;
+    }
+    let word = |i: usize| [data[i], data[i + 1], data[i + 2], data[i + 3]];
+    Some(libc::ucred {
+        pid: i32::from_ne_bytes(word(0)),
+        uid: u32::from_ne_bytes(word(4)),
+        gid: u32::from_ne_bytes(word(8)),
+    })
+}
+
+/// A control buffer in the host layout, kept 8 byte aligned
+pub struct HostControl {
+    buf: Vec<u64>,
+    len: usize,
+    // Ticket sent with the message; closed once the kernel holds its own reference
+    ticket: Option<(OwnedFd, (u64, u64))>,
+}
+
+impl HostControl {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let mut buf = vec![0u64; bytes.len().div_ceil(8)];
+        unsafe {
+            ptr::copy_nonoverlapping(bytes.as_ptr(), buf.as_mut_ptr() as *mut u8, bytes.len())
+        };
+        HostControl {
+            buf,
+            len: bytes.len(),
+            ticket: None,
+        }
+    }
+
+    /// An empty buffer to receive up to `len` bytes into
+    pub fn for_recv(guest_len: usize) -> Self {
+        let len = guest_len * 2 + HOST_CONTROL_SLACK;
+        HostControl {
+            buf: vec![0u64; len.div_ceil(8)],
+            len,
+            ticket: None,
+        }
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut libc::c_void {
+        if self.len == 0 {
+            ptr::null_mut()
+        } else {
+            self.buf.as_mut_ptr() as *mut libc::c_void
+        }
+    }
+
+    pub fn controllen(&self) -> usize {
+        self.len
+    }
+
+    /// Called once the host `sendmsg` returned. If the message was not sent, the fds parked
+    /// for it are released again.
+    pub fn finish_send(mut self, sent: bool) {
+        if let Some((_ticket, key)) = self.ticket.take() {
+            if !sent {
+                if let Some((_, inflight)) = INFLIGHT.remove(&key) {
+                    release_parked(&inflight.fds);
+                }
+            }
+        }
+    }
+}
+
+fn push_host_cmsg(out: &mut Vec<u8>, level: i32, ty: i32, data: &[u8]) {
+    let start = out.len();
+    out.extend_from_slice(&((HOST_CMSGHDR_LEN + data.len()) as u64).to_ne_bytes());
+    out.extend_from_slice(&level.to_ne_bytes());
+    out.extend_from_slice(&ty.to_ne_bytes());
+    out.extend_from_slice(data);
+    out.resize(
+        start + align(HOST_CMSGHDR_LEN + data.len(), HOST_CMSG_ALIGN),
+        0,
+    );
+}
+
+fn release_parked(fds: &[u64]) {
+    for fd in fds {
+        let _ = fdtables::close_virtualfd(SCM_INFLIGHT_FDTABLE, *fd);
+    }
+}
+
+/// Convert the guest control buffer of a `sendmsg` into the host layout.
+///
+/// ## Returns:
+/// - the host control buffer, which must be handed back through `finish_send`
+/// - `Err(EINVAL)` for a malformed buffer, `Err(EBADF)` for an fd that is not open,
+///   `Err(EOPNOTSUPP)` for an epoll fd, `Err(EPERM)` for credentials that are not the
+///   cage's own, `Err(ETOOMANYREFS)` if too many fds are in flight
+///
+/// # Safety
+/// `guest` must be null or valid for reads of `guest_len` bytes.
+pub unsafe fn control_to_host(
+    cageid: u64,
+    guest: *const u8,
+    guest_len: usize,
+) -> Result<HostControl, Errno> {
+    let mut entries: Vec<FDTableEntry> = Vec::new();
+    let mut creds: Option<libc::ucred> = None;
+    let mut others: Vec<(i32, i32, Vec<u8>)> = Vec::new();
+
+    let mut off = 0;
+    while !guest.is_null() && off + GUEST_CMSGHDR_LEN <= guest_len {
+        let hdr = unsafe { guest.add(off) };
+        let cmsg_len = unsafe { ptr::read_unaligned(hdr as *const u32) } as usize;
+        let level = unsafe { ptr::read_unaligned(hdr.add(4) as *const i32) };
+        let ty = unsafe { ptr::read_unaligned(hdr.add(8) as *const i32) };
+        if cmsg_len < GUEST_CMSGHDR_LEN || off + cmsg_len > guest_len {
+            return Err(Errno::EINVAL);
+        }
+        let data = unsafe {
+            std::slice::from_raw_parts(hdr.add(GUEST_CMSGHDR_LEN), cmsg_len - GUEST_CMSGHDR_LEN)
+        };
+
+        match (level, ty) {
+            (SOL_SOCKET, SCM_RIGHTS) => {
+                for vfd in data.chunks_exact(4) {
+                    let vfd = i32::from_ne_bytes([vfd[0], vfd[1], vfd[2], vfd[3]]);
+                    let entry = match fdtables::translate_virtual_fd(cageid, vfd as u64) {
+                        Ok(entry) if vfd >= 0 => entry,
+                        _ => return Err(Errno::EBADF),
+                    };
+                    // epoll instances live in the sending cage's own tables
+                    if entry.fdkind == FDT_KINDEPOLL {
+                        return Err(Errno::EOPNOTSUPP);
+                    }
+                    entries.push(entry);
+                }
+            }
+            (SOL_SOCKET, SCM_CREDENTIALS) => {
+                let sent = ucred_from_bytes(data).ok_or(Errno::EINVAL)?;
+                if sent.pid as u64 != cageid || sent.uid != DEFAULT_UID || sent.gid != DEFAULT_GID {
+                    return Err(Errno::EPERM);
+                }
+                creds = Some(sent);
+            }
+            _ => others.push((level, ty, data.to_vec())),
+        }
+
+        off += align(cmsg_len, GUEST_CMSG_ALIGN);
+    }
+
+    let mut out = Vec::new();
+    for (level, ty, data) in &others {
+        push_host_cmsg(&mut out, *level, *ty, data);
+    }
+
+    let virtualized = creds.is_some() || entries.iter().any(|e| e.fdkind != FDKIND_KERNEL);
+    if !virtualized {
+        if !entries.is_empty() {
+            let fds: Vec<u8> = entries
+                .iter()
+                .flat_map(|e| (e.underfd as i32).to_ne_bytes())
+                .collect();
+            push_host_cmsg(&mut out, SOL_SOCKET, SCM_RIGHTS, &fds);
+        }
+        return Ok(HostControl::from_bytes(&out));
+    }
+
+    // Park the entries; each parked copy holds a reference on the underlying object
+    let mut parked = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        match fdtables::get_unused_virtual_fd(
+            SCM_INFLIGHT_FDTABLE,
+            entry.fdkind,
+            entry.underfd,
+            false,
+            entry.perfdinfo,
+        ) {
+            Ok(fd) => parked.push(fd),
+            Err(_) => {
+                release_parked(&parked);
+                return Err(Errno::ETOOMANYREFS);
+            }
+        }
+    }
+
+    let name = CString::new("lind-scm-ticket").unwrap();
+    let ticket = unsafe { libc::memfd_create(name.as_ptr(), libc::MFD_CLOEXEC) };
+    let key = if ticket >= 0 { fd_key(ticket) } else { None };
+    let key = match key {
+        Some(key) => key,
+        None => {
+            if ticket >= 0 {
+                unsafe { libc::close(ticket) };
+            }
+            release_parked(&parked);
+            return Err(Errno::ENOMEM);
+        }
+    };
+    let ticket = unsafe { OwnedFd::from_raw_fd(ticket) };
+    INFLIGHT.insert(key, Inflight { fds: parked, creds });
+
+    push_host_cmsg(
+        &mut out,
+        SOL_SOCKET,
+        SCM_RIGHTS,
+        &ticket.as_raw_fd().to_ne_bytes(),
+    );
+    let mut control = HostControl::from_bytes(&out);
+    control.ticket = Some((ticket, key));
+    Ok(control)
+}
+
+/// A control message received from the host, with its fds already installed
+enum Received {
+    Rights(Vec<u64>),
+    Creds(libc::ucred),
+    Other(i32, i32, Vec<u8>),
+}
+
+/// Convert the host control buffer filled by `recvmsg` into the guest layout, installing
+/// received fds into `cageid`'s table.
+///
+/// ## Arguments:
+/// - `host_len`: the `msg_controllen` returned by the host
+/// - `guest` / `guest_len`: the guest's control buffer
+/// - `flags`: the `recvmsg` flags (`MSG_PEEK`)
+/// - `cloexec`: whether installed fds get close-on-exec (`MSG_CMSG_CLOEXEC`)
+///
+/// ## Returns:
+/// The number of bytes written to the guest buffer, and whether anything had to be dropped
+/// for lack of room (the caller reports it with `MSG_CTRUNC`).
+///
+/// # Safety
+/// `guest` must be null or valid for writes of `guest_len` bytes.
+pub unsafe fn control_to_guest(
+    cageid: u64,
+    host: &HostControl,
+    host_len: usize,
+    guest: *mut u8,
+    guest_len: usize,
+    flags: i32,
+    cloexec: bool,
+) -> (usize, bool) {
+    let host_bytes = unsafe {
+        std::slice::from_raw_parts(host.buf.as_ptr() as *const u8, host_len.min(host.len))
+    };
+    let mut truncated = false;
+    let mut received = Vec::new();
+    let mut ticket_creds = None;
+
+    let install = |entry: FDTableEntry, out: &mut Vec<u64>, truncated: &mut bool| {
+        match fdtables::get_unused_virtual_fd(
+            cageid,
+            entry.fdkind,
+            entry.underfd,
+            cloexec,
+            entry.perfdinfo,
+        ) {
+            Ok(vfd) => out.push(vfd),
+            Err(_) => *truncated = true,
+        }
+    };
+
+    let mut off = 0;
+    while off + HOST_CMSGHDR_LEN <= host_bytes.len() {
+        let word = |i: usize| {
+            let mut w = [0u8; 4];
+            w.copy_from_slice(&host_bytes[i..i + 4]);
+            w
+        };
+        let mut len_bytes = [0u8; 8];
+        len_bytes.copy_from_slice(&host_bytes[off..off + 8]);
+        let cmsg_len = u64::from_ne_bytes(len_bytes) as usize;
+        let level = i32::from_ne_bytes(word(off + 8));
+        let ty = i32::from_ne_bytes(word(off + 12));
+        if cmsg_len < HOST_CMSGHDR_LEN || off + cmsg_len > host_bytes.len() {
+            break;
+        }
+        let data = &host_bytes[off + HOST_CMSGHDR_LEN..off + cmsg_len];
+
+        match (level, ty) {
+            (SOL_SOCKET, SCM_RIGHTS) => {
+                let mut vfds = Vec::new();
+                for fd in data.chunks_exact(4) {
+                    let fd = i32::from_ne_bytes([fd[0], fd[1], fd[2], fd[3]]);
+                    let ticket = fd_key(fd).and_then(|key| {
+                        if flags & MSG_PEEK != 0 {
+                            INFLIGHT.get(&key).map(|t| (t.fds.clone(), t.creds, false))
+                        } else {
+                            INFLIGHT.remove(&key).map(|(_, t)| (t.fds, t.creds, true))
+                        }
+                    });
+                    match ticket {
+                        Some((parked, creds, consumed)) => {
+                            unsafe { libc::close(fd) };
+                            for parked_fd in &parked {
+                                if let Ok(entry) =
+                                    fdtables::translate_virtual_fd(SCM_INFLIGHT_FDTABLE, *parked_fd)
+                                {
+                                    install(entry, &mut vfds, &mut truncated);
+                                }
+                            }
+                            if consumed {
+                                release_parked(&parked);
+                            }
+                            if creds.is_some() {
+                                ticket_creds = creds;
+                            }
+                        }
+                        None => {
+                            let entry = FDTableEntry {
+                                fdkind: FDKIND_KERNEL,
+                                underfd: fd as u64,
+                                should_cloexec: cloexec,
+                                perfdinfo: 0,
+                            };
+                            let before = vfds.len();
+                            install(entry, &mut vfds, &mut truncated);
+                            if vfds.len() == before {
+                                unsafe { libc::close(fd) };
+                            }
+                        }
+                    }
+                }
+                received.push(Received::Rights(vfds));
+            }
+            (SOL_SOCKET, SCM_CREDENTIALS) => {
+                if let Some(creds) = ucred_from_bytes(data) {
+                    received.push(Received::Creds(creds));
+                }
+            }
+            _ => received.push(Received::Other(level, ty, data.to_vec())),
+        }
+
+        off += align(cmsg_len, HOST_CMSG_ALIGN);
+    }
+
+    // Lay the messages out for the guest, releasing whatever does not fit
+    let guest_len = if guest.is_null() { 0 } else { guest_len };
+    let mut written = 0;
+    for msg in received {
+        let (level, ty, data) = match msg {
+            Received::Rights(vfds) => {
+                let room = guest_len.saturating_sub(written + GUEST_CMSGHDR_LEN) / 4;
+                let fit = if written + GUEST_CMSGHDR_LEN <= guest_len {
+                    vfds.len().min(room)
+                } else {
+                    0
+                };
+                for vfd in &vfds[fit..] {
+                    let _ = fdtables::close_virtualfd(cageid, *vfd);
+                    truncated = true;
+                }
+                if fit == 0 {
+                    continue;
+                }
+                let data: Vec<u8> = vfds[..fit]
+                    .iter()
+                    .flat_map(|vfd| (*vfd as i32).to_ne_bytes())
+                    .collect();
+                (SOL_SOCKET, SCM_RIGHTS, data)
+            }
+            Received::Creds(host_creds) => {
+                let creds = ticket_creds.unwrap_or(libc::ucred {
+                    pid: 0,
+                    uid: if host_creds.uid == unsafe { libc::getuid() } {
+                        DEFAULT_UID
+                    } else {
+                        host_creds.uid
+                    },
+                    gid: if host_creds.gid == unsafe { libc::getgid() } {
+                        DEFAULT_GID
+                    } else {
+                        host_creds.gid
+                    },
+                });
+                (SOL_SOCKET, SCM_CREDENTIALS, ucred_bytes(&creds))
+            }
+            Received::Other(level, ty, data) => (level, ty, data),
+        };
+
+        let cmsg_len = GUEST_CMSGHDR_LEN + data.len();
+        if written + cmsg_len > guest_len {
+            truncated = true;
+            continue;
+        }
+        unsafe {
+            let hdr = guest.add(written);
+            ptr::write_unaligned(hdr as *mut u32, cmsg_len as u32);
+            ptr::write_unaligned(hdr.add(4) as *mut i32, level);
+            ptr::write_unaligned(hdr.add(8) as *mut i32, ty);
+            ptr::copy_nonoverlapping(data.as_ptr(), hdr.add(GUEST_CMSGHDR_LEN), data.len());
+        }
+        written = (written + align(cmsg_len, GUEST_CMSG_ALIGN)).min(guest_len);
+    }
+
+    (written, truncated)
+}
@@ -0,0 +1,96 @@
+//! SIGHUP on terminal hangup
+//!
+//! The cages share the controlling terminal of the lind process: `/dev/tty` and the standard
+//! streams they inherit are the host's. When that terminal hangs up, the host kernel (or the
+//! shell that started lind, as for any of its jobs) sends SIGHUP to the lind process. RawPOSIX
+//! catches it and raises SIGHUP in every live cage through `lind_send_signal`, as Linux does for
+//! the foreground process group. Lind has no sessions or process groups, so all cages count as
+//! that group, and the default action ends each of them instead of the lind process at once.
+//!
+//! The host handler only writes to a pipe, the one thing it may safely do. The `lind-sighup`
+//! thread reads from the pipe and raises the signals.
+//!
+//! A pipe or socket whose other end closes raises no SIGHUP, on Linux neither: readers see end of
+//! file, and `poll()` reports `POLLHUP`.
+
+use cage::cagetable_ids;
+use cage::signal::signal::lind_send_signal;
+use std::sync::atomic::{AtomicI32, Ordering};
+use sysdefs::constants::err_const::get_errno;
+use sysdefs::constants::sys_const::SIGHUP;
+
+/// Write end of the pipe the host handler signals the thread through
+static HANGUP_PIPE: AtomicI32 = AtomicI32::new(-1);
+
+/// Host SIGHUP handler: wake the `lind-sighup` thread
+extern "C" fn hangup_handler(_signo: i32) {
+    let byte = 0u8;
+    // a full pipe already has a hangup waiting to be raised
+    unsafe {
+        libc::write(
+            HANGUP_PIPE.load(Ordering::Relaxed),
+            &byte as *const u8 as *const libc::c_void,
+            1,
+        )
+    };
+}
+
+/// Install the host SIGHUP handler and start the thread that raises SIGHUP in the cages
+pub fn sighup_init() {
+    let mut fds = [0i32; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC | libc::O_NONBLOCK) } < 0 {
+        panic!(
+            "Failed to create the terminal hangup pipe: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+    let [read_end, write_end] = fds;
+    // only the handler writes, the thread blocks on its end
+    unsafe {
+        let flags = libc::fcntl(read_end, libc::F_GETFL);
+        libc::fcntl(read_end, libc::F_SETFL, flags & !libc::O_NONBLOCK);
+    }
+    HANGUP_PIPE.store(write_end, Ordering::Relaxed);
+
+    std::thread::Builder::new()
+        .name("lind-sighup".to_string())
+        .spawn(move || watch(read_end))
+        .expect("Failed to start the terminal hangup thread");
+
+    unsafe {
+        let mut act: libc::sigaction = std::mem::zeroed();
+        act.sa_sigaction = hangup_handler as extern "C" fn(i32) as libc::sighandler_t;
+        act.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut act.sa_mask);
+        if libc::sigaction(libc::SIGHUP, &act, std::ptr::null_mut()) < 0 {
+            panic!(
+                "Failed to install the terminal hangup handler: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+/// Body of the `lind-sighup` thread
+fn watch(read_end: i32) {
+    let mut buf = [0u8; 64];
+    loop {
+        let ret = unsafe { libc::read(read_end, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+        if ret < 0 {
+            if get_errno() == libc::EINTR {
+                continue;
+            }
+            panic!(
+                "Failed to wait for terminal hangups: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+        // hangups arriving together are one, as pending host signals are
+        for cageid in cagetable_ids() {
+            // cages on their way out are not signalled anymore
+            if !threei::EXITING_TABLE.contains(&cageid) {
+                lind_send_signal(cageid, SIGHUP);
+            }
+        }
+    }
+}
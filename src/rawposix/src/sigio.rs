@@ -0,0 +1,298 @@
+//! Signal-driven I/O
+//!
+//! `fcntl(F_SETOWN)` or `F_SETOWN_EX` names the cage a file signals when it becomes ready,
+//! `F_SETSIG` the signal (`SIGIO` unless set), and `O_ASYNC` or `ioctl(FIOASYNC)` turns the
+//! signals on. As on Linux, this state belongs to the open file description. It is keyed by the
+//! `(fdkind, underfd)` of the fdtables entry, so `dup`ed and inherited fds share it. None of it
+//! reaches the host fd: with `O_ASYNC` and an owner there, the host kernel would signal the
+//! lind process itself.
+//!
+//! A file that is owned and has `O_ASYNC` set is watched by the `lind-sigio` thread. The thread
+//! waits on an edge-triggered kernel epoll instance for the host fds behind those files, and
+//! raises the signal in the owner through `lind_send_signal` every time one becomes ready
+//! again. A file that is ready already when it starts being watched signals once right away.
+//! Kernel fds and namespace sockets are watched directly, and the tty device node through the
+//! host's stdin. The other device nodes and tmpfs files are always ready, so they never signal,
+//! like regular files on Linux.
+//!
+//! Lind has neither process groups nor thread ids, so an owner given as either is the cage
+//! with that id. Signal handlers get no `siginfo_t` either, so the `si_fd` and `si_band` a
+//! signal set with `F_SETSIG` carries on Linux are not reported.
+
+use crate::devfs::{devfs_device, DEV_TTY};
+use cage::get_cage;
+use cage::signal::signal::lind_send_signal;
+use fdtables::FDTableEntry;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use sysdefs::constants::err_const::{get_errno, syscall_error, Errno};
+use sysdefs::constants::fs_const::{
+    F_GETOWN, F_GETOWN_EX, F_GETSIG, F_OWNER_PGRP, F_OWNER_PID, F_OWNER_TID, F_SETOWN, F_SETOWN_EX,
+    F_SETSIG, STDIN_FILENO,
+};
+use sysdefs::constants::net_const::{EPOLLET, EPOLLIN, EPOLLOUT, EPOLLPRI, EPOLLRDHUP};
+use sysdefs::constants::sys_const::SIGIO;
+use sysdefs::constants::{FDKIND_DEV, FDKIND_KERNEL, FDKIND_NETNS};
+use sysdefs::data::fs_struct::FOwnerEx;
+
+/// Readiness changes that signal, as on Linux: input, output, urgent data and hangups
+const WATCHED_EVENTS: u32 = (EPOLLIN | EPOLLOUT | EPOLLPRI | EPOLLRDHUP | EPOLLET) as u32;
+
+/// The open file description, as the `(fdkind, underfd)` of its fdtables entry
+type Target = (u32, u64);
+
+#[derive(Clone, Copy)]
+struct Owner {
+    /// `F_OWNER_TID`, `F_OWNER_PID` or `F_OWNER_PGRP`
+    kind: i32,
+    id: i32,
+}
+
+#[derive(Default)]
+struct AsyncFile {
+    owner: Option<Owner>,
+    /// The signal set with `F_SETSIG`, 0 for `SIGIO`
+    sig: i32,
+    /// `O_ASYNC`
+    enabled: bool,
+    /// The host fd the watcher waits on for it, while it is owned and enabled
+    watched: Option<i32>,
+}
+
+struct Watcher {
+    /// The kernel epoll instance of the `lind-sigio` thread, started with the first file
+    epfd: Option<i32>,
+    files: HashMap<Target, AsyncFile>,
+    /// The watched files, by the host fd they are watched through
+    by_hostfd: HashMap<i32, Vec<Target>>,
+}
+
+lazy_static! {
+    static ref WATCHER: Mutex<Watcher> = Mutex::new(Watcher {
+        epfd: None,
+        files: HashMap::new(),
+        by_hostfd: HashMap::new(),
+    });
+}
+
+fn target(entry: &FDTableEntry) -> Target {
+    (entry.fdkind, entry.underfd)
+}
+
+/// The host fd whose readiness is that of a file, if it can ever change
+fn hostfd_of((fdkind, underfd): Target) -> Option<i32> {
+    match fdkind {
+        FDKIND_KERNEL | FDKIND_NETNS => Some(underfd as i32),
+        FDKIND_DEV if devfs_device(underfd) == Some(DEV_TTY) => Some(STDIN_FILENO),
+        _ => None,
+    }
+}
+
+impl Watcher {
+    /// Start or stop watching a file after its owner or `O_ASYNC` changed
+    fn update(&mut self, target: Target) {
+        let Some(file) = self.files.get_mut(&target) else {
+            return;
+        };
+        let wanted = match (file.owner, file.enabled) {
+            (Some(_), true) => hostfd_of(target),
+            _ => None,
+        };
+        if wanted == file.watched {
+            return;
+        }
+        let unwatch = file.watched.take();
+        file.watched = wanted;
+
+        if let Some(hostfd) = unwatch {
+            let targets = self.by_hostfd.get_mut(&hostfd).unwrap();
+            targets.retain(|t| *t != target);
+            if targets.is_empty() {
+                self.by_hostfd.remove(&hostfd);
+                let epfd = self.epfd.unwrap();
+                unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_DEL, hostfd, std::ptr::null_mut()) };
+            }
+        }
+        if let Some(hostfd) = wanted {
+            let epfd = *self.epfd.get_or_insert_with(start_watcher);
+            let targets = self.by_hostfd.entry(hostfd).or_default();
+            if targets.is_empty() {
+                let mut event = libc::epoll_event {
+                    events: WATCHED_EVENTS,
+                    u64: hostfd as u64,
+                };
+                unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, hostfd, &mut event) };
+            }
+            targets.push(target);
+        }
+    }
+}
+
+/// Create the kernel epoll instance of the watcher and start the thread waiting on it
+fn start_watcher() -> i32 {
+    let epfd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+    if epfd < 0 {
+        panic!(
+            "Failed to create the epoll instance for signal-driven I/O: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+    std::thread::Builder::new()
+        .name("lind-sigio".to_string())
+        .spawn(move || watch(epfd))
+        .expect("Failed to start the signal-driven I/O thread");
+    epfd
+}
+
+/// Body of the `lind-sigio` thread
+fn watch(epfd: i32) {
+    let mut events = [libc::epoll_event { events: 0, u64: 0 }; 64];
+    loop {
+        let ret = unsafe { libc::epoll_wait(epfd, events.as_mut_ptr(), events.len() as i32, -1) };
+        if ret < 0 {
+            if get_errno() == libc::EINTR {
+                continue;
+            }
+            panic!(
+                "Failed to wait for signal-driven I/O: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+
+        // Collect the signals first, `lind_send_signal` does not need the watcher locked
+        let mut signals = Vec::new();
+        {
+            let watcher = WATCHER.lock();
+            for event in &events[..ret as usize] {
+                let hostfd = event.u64 as i32;
+                for target in watcher.by_hostfd.get(&hostfd).into_iter().flatten() {
+                    let file = &watcher.files[target];
+                    if let Some(owner) = file.owner {
+                        let sig = if file.sig == 0 { SIGIO } else { file.sig };
+                        signals.push((owner.id as u64, sig));
+                    }
+                }
+            }
+        }
+        for (cageid, sig) in signals {
+            // An owner that exited in the meantime is not signalled
+            lind_send_signal(cageid, sig);
+        }
+    }
+}
+
+/// `fcntl()` commands of signal-driven I/O on the file of `entry`: `F_GETOWN`, `F_SETOWN`,
+/// `F_GETOWN_EX`, `F_SETOWN_EX` (with `ptr_arg` pointing to the `f_owner_ex`), `F_GETSIG` and
+/// `F_SETSIG`. A negative `F_GETOWN` / `F_SETOWN` value is a process group, as on Linux.
+pub fn sigio_fcntl(entry: &FDTableEntry, cmd: i32, arg: i32, ptr_arg: u64) -> i32 {
+    let owner_ex = ptr_arg as *mut FOwnerEx;
+    let ret = match cmd {
+        F_GETOWN => match get_owner(entry) {
+            (F_OWNER_PGRP, id) => Ok(-id),
+            (_, id) => Ok(id),
+        },
+        F_SETOWN if arg < 0 => set_owner(entry, F_OWNER_PGRP, arg.wrapping_neg()).map(|()| 0),
+        F_SETOWN => set_owner(entry, F_OWNER_PID, arg).map(|()| 0),
+        F_GETOWN_EX | F_SETOWN_EX if owner_ex.is_null() => Err(Errno::EFAULT),
+        F_GETOWN_EX => {
+            let (owner_type, pid) = get_owner(entry);
+            unsafe { *owner_ex = FOwnerEx { owner_type, pid } };
+            Ok(0)
+        }
+        F_SETOWN_EX => {
+            let FOwnerEx { owner_type, pid } = unsafe { *owner_ex };
+            set_owner(entry, owner_type, pid).map(|()| 0)
+        }
+        F_GETSIG => Ok(get_sig(entry)),
+        F_SETSIG => set_sig(entry, arg).map(|()| 0),
+        _ => Err(Errno::EINVAL),
+    };
+    ret.unwrap_or_else(|e| syscall_error(e, "fcntl", "signal-driven I/O"))
+}
+
+/// The owner type and id of a file, `(F_OWNER_PID, 0)` if it has no owner
+fn get_owner(entry: &FDTableEntry) -> (i32, i32) {
+    let watcher = WATCHER.lock();
+    match watcher
+        .files
+        .get(&target(entry))
+        .and_then(|file| file.owner)
+    {
+        Some(owner) => (owner.kind, owner.id),
+        None => (F_OWNER_PID, 0),
+    }
+}
+
+/// Make the cage `id` the owner of a file, or remove the owner if `id` is 0
+///
+/// ## Returns:
+/// - `Ok(())` on success
+/// - `Err(EINVAL)` for an unknown owner type or a negative id, `Err(ESRCH)` if there is no
+///   cage `id`
+fn set_owner(entry: &FDTableEntry, kind: i32, id: i32) -> Result<(), Errno> {
+    if ![F_OWNER_TID, F_OWNER_PID, F_OWNER_PGRP].contains(&kind) || id < 0 {
+        return Err(Errno::EINVAL);
+    }
+    if id > 0 && get_cage(id as u64).is_none() {
+        return Err(Errno::ESRCH);
+    }
+
+    let mut watcher = WATCHER.lock();
+    let target = target(entry);
+    watcher.files.entry(target).or_default().owner = (id > 0).then_some(Owner { kind, id });
+    watcher.update(target);
+    Ok(())
+}
+
+/// The signal set with `F_SETSIG`, 0 meaning `SIGIO`
+fn get_sig(entry: &FDTableEntry) -> i32 {
+    let watcher = WATCHER.lock();
+    watcher.files.get(&target(entry)).map_or(0, |file| file.sig)
+}
+
+/// Set the signal a file raises, 0 for `SIGIO`
+///
+/// ## Returns:
+/// - `Ok(())` on success
+/// - `Err(EINVAL)` if `sig` is not a signal lind can deliver
+fn set_sig(entry: &FDTableEntry, sig: i32) -> Result<(), Errno> {
+    if !(0..32).contains(&sig) {
+        return Err(Errno::EINVAL);
+    }
+    let mut watcher = WATCHER.lock();
+    watcher.files.entry(target(entry)).or_default().sig = sig;
+    Ok(())
+}
+
+/// Whether `O_ASYNC` is set on a file
+pub fn sigio_is_async(entry: &FDTableEntry) -> bool {
+    let watcher = WATCHER.lock();
+    watcher
+        .files
+        .get(&target(entry))
+        .is_some_and(|file| file.enabled)
+}
+
+/// Set or clear `O_ASYNC` on a file
+pub fn sigio_set_async(entry: &FDTableEntry, enabled: bool) {
+    let mut watcher = WATCHER.lock();
+    let target = target(entry);
+    if !enabled && !watcher.files.contains_key(&target) {
+        return;
+    }
+    watcher.files.entry(target).or_default().enabled = enabled;
+    watcher.update(target);
+}
+
+/// Drop the state of a file whose last fd was closed. The close handler of every fd kind calls
+/// this, before the host fd is closed.
+pub fn sigio_forget(fdkind: u32, underfd: u64) {
+    let mut watcher = WATCHER.lock();
+    let target = (fdkind, underfd);
+    if let Some(file) = watcher.files.get_mut(&target) {
+        file.enabled = false;
+        watcher.update(target);
+        watcher.files.remove(&target);
+    }
+}
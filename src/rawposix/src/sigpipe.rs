@@ -0,0 +1,101 @@
+//! SIGPIPE
+//!
+//! A write to a pipe nobody reads anymore, or to a stream socket that is shut down for writing
+//! or whose peer is gone, fails with `EPIPE`, and Linux raises SIGPIPE in the writer first.
+//! RawPOSIX raises it through `lind_send_signal` once the host call failed: the host's own
+//! SIGPIPE never reaches a cage, as the Rust runtime ignores it in the lind process.
+//!
+//! No signal is raised for `send()`, `sendto()`, `sendmsg()` or `sendmmsg()` with
+//! `MSG_NOSIGNAL`, nor for any write to a socket `SO_LIND_NOSIGPIPE` is set on. Linux only has
+//! the former. The latter is lind's own take on `SO_NOSIGPIPE` of the BSDs, declared in
+//! `<lind_syscall.h>` rather than in the libc socket headers. It belongs to the socket, so it is
+//! kept per host fd, which `dup`ed and inherited fds share, and goes away with the last fd
+//! closing it. Kernel fds and namespace sockets both have a host socket behind them.
+
+use cage::signal::signal::lind_send_signal;
+use dashmap::DashSet;
+use lazy_static::lazy_static;
+use std::mem::size_of;
+use sysdefs::constants::err_const::{get_errno, handle_errno, syscall_error, Errno};
+use sysdefs::constants::net_const::{MSG_NOSIGNAL, SOL_SOCKET, SO_TYPE};
+use sysdefs::constants::sys_const::SIGPIPE;
+
+lazy_static! {
+    /// Host sockets `SO_LIND_NOSIGPIPE` is set on
+    static ref NOSIGPIPE: DashSet<i32> = DashSet::new();
+}
+
+/// Result `ret` of a write or send with `flags` on host fd `hostfd`: raise SIGPIPE in the cage
+/// if it is `-EPIPE` and neither `MSG_NOSIGNAL` nor `SO_LIND_NOSIGPIPE` says otherwise. Returns `ret`.
+pub fn sigpipe_check(cageid: u64, hostfd: i32, flags: i32, ret: i32) -> i32 {
+    if ret == -(Errno::EPIPE as i32) && flags & MSG_NOSIGNAL == 0 && !NOSIGPIPE.contains(&hostfd) {
+        lind_send_signal(cageid, SIGPIPE);
+    }
+    ret
+}
+
+/// `setsockopt(SOL_SOCKET, SO_LIND_NOSIGPIPE)` on host socket `hostfd`, the option being the `int`
+/// at `optval`
+///
+/// # Safety
+/// `optval` must be a valid host pointer to `optlen` bytes, or null.
+pub unsafe fn sigpipe_setsockopt(hostfd: i32, optval: *const u8, optlen: u32) -> i32 {
+    if let Err(ret) = check_socket(hostfd, "setsockopt") {
+        return ret;
+    }
+    if optval.is_null() || (optlen as usize) < size_of::<i32>() {
+        return syscall_error(Errno::EINVAL, "setsockopt", "option value is too short");
+    }
+    if (optval as *const i32).read_unaligned() != 0 {
+        NOSIGPIPE.insert(hostfd);
+    } else {
+        NOSIGPIPE.remove(&hostfd);
+    }
+    0
+}
+
+/// `getsockopt(SOL_SOCKET, SO_LIND_NOSIGPIPE)` on host socket `hostfd`
+///
+/// # Safety
+/// `optval` and `optlen` must be valid host pointers, as for `getsockopt()`.
+pub unsafe fn sigpipe_getsockopt(hostfd: i32, optval: *mut u8, optlen: *mut u32) -> i32 {
+    if let Err(ret) = check_socket(hostfd, "getsockopt") {
+        return ret;
+    }
+    if optval.is_null() || optlen.is_null() {
+        return syscall_error(Errno::EFAULT, "getsockopt", "invalid option buffer");
+    }
+    // like Linux, a short buffer gets the leading bytes of the value
+    let value = (NOSIGPIPE.contains(&hostfd) as i32).to_ne_bytes();
+    let len = (*optlen as usize).min(value.len());
+    std::ptr::copy_nonoverlapping(value.as_ptr(), optval, len);
+    *optlen = len as u32;
+    0
+}
+
+/// Drop the `SO_LIND_NOSIGPIPE` state of host socket `hostfd` once it is closed
+pub fn sigpipe_forget(hostfd: i32) {
+    NOSIGPIPE.remove(&hostfd);
+}
+
+/// `EBADF` for a bad fd and `ENOTSOCK` for a file that is no socket, as the host would report
+fn check_socket(hostfd: i32, syscall: &str) -> Result<(), i32> {
+    if hostfd < 0 {
+        return Err(handle_errno(-hostfd, syscall));
+    }
+    let mut socktype = 0i32;
+    let mut len = size_of::<i32>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            hostfd,
+            SOL_SOCKET,
+            SO_TYPE,
+            &mut socktype as *mut i32 as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if ret < 0 {
+        return Err(handle_errno(get_errno(), syscall));
+    }
+    Ok(())
+}
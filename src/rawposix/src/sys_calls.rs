@@ -1,10 +1,20 @@
 //! System syscalls implementation
 //!
 //! This module contains all system calls that are being emulated/faked in Lind.
+use crate::interrupt::interrupted;
+use crate::oom::memory_budget_check;
+use crate::overlay::{overlay_exit, overlay_fork};
+use crate::uts::uts_set_field;
+use cage::cputime::{clock_read, cpu_clock_self, CpuAccount, CpuTimes};
+use cage::deterministic::{is_deterministic, virtual_clock};
 use cage::memory::vmmap::{VmmapOps, *};
-use cage::signal::signal::{convert_signal_mask, lind_send_signal, signal_check_trigger};
-use cage::timer::IntervalTimer;
-use cage::{add_cage, get_cage, remove_cage, Cage, Zombie};
+use cage::signal::signal::{
+    altstack_contains, current_threadid, lind_send_signal, lind_send_thread_signal,
+    signal_altstack_get, signal_altstack_set, signal_check_trigger, signal_mask_get,
+    signal_mask_swap,
+};
+use cage::timer::{cage_clock_now, CageTimers, TimerClock};
+use cage::{add_cage, get_cage, remove_cage, Cage, Zombie, ALTSTACK_DISABLED};
 use dashmap::DashMap;
 use fdtables;
 use libc::sched_yield;
@@ -12,19 +22,27 @@ use parking_lot::{Mutex, RwLock};
 use std::ffi::CString;
 use std::path::PathBuf;
 use std::sync::atomic::Ordering::*;
-use std::sync::atomic::{AtomicI32, AtomicU64};
+use std::sync::atomic::{AtomicI32, AtomicI64, AtomicU64};
 use std::sync::Arc;
 use std::time::Duration;
 use sysdefs::constants::err_const::{get_errno, handle_errno, syscall_error, Errno, VERBOSE};
-use sysdefs::constants::fs_const::{STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO};
+use sysdefs::constants::fs_const::{PAGESHIFT, STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO};
 use sysdefs::constants::lind_platform_const::{
     RAWPOSIX_CAGEID, UNUSED_ARG, UNUSED_ID, UNUSED_NAME, WASMTIME_CAGEID,
 };
 use sysdefs::constants::sys_const::{
-    DEFAULT_GID, DEFAULT_UID, EXIT_SUCCESS, ITIMER_REAL, SIGCHLD, SIGKILL, SIGSTOP, SIG_BLOCK,
-    SIG_SETMASK, SIG_UNBLOCK, WNOHANG,
+    CLK_TCK, CLOCK_BOOTTIME, CLOCK_BOOTTIME_ALARM, CLOCK_MONOTONIC, CLOCK_MONOTONIC_COARSE,
+    CLOCK_MONOTONIC_RAW, CLOCK_PROCESS_CPUTIME_ID, CLOCK_REALTIME, CLOCK_REALTIME_ALARM,
+    CLOCK_REALTIME_COARSE, CLOCK_SETTIME_SYSCALL, CLOCK_TAI, CLOCK_THREAD_CPUTIME_ID, DEFAULT_GID,
+    DEFAULT_UID, EXIT_SUCCESS, HOST_NAME_MAX, ITIMER_REAL, MINSIGSTKSZ, RUSAGE_CHILDREN,
+    RUSAGE_SELF, RUSAGE_THREAD, SA_NOCLDWAIT, SIGALRM, SIGCHLD, SIGEV_NONE, SIGEV_SIGNAL,
+    SIGEV_THREAD_ID, SIGKILL, SIGSTOP, SIG_BLOCK, SIG_IGN, SIG_SETMASK, SIG_UNBLOCK, SS_DISABLE,
+    SS_ONSTACK, TIMER_ABSTIME, UTSNAME_LENGTH, WNOHANG,
+};
+use sysdefs::data::fs_struct::{
+    ITimerSpec, SigEvent, SigactionStruct, StackStruct, TimeSpec, TimeVal, Tms,
 };
-use sysdefs::data::fs_struct::{ITimerVal, SigactionStruct};
+use sysdefs::data::sys_struct::UtsNameStruct;
 use sysdefs::{constants::sys_const, data::sys_struct};
 use typemap::datatype_conversion::*;
 
@@ -115,10 +133,15 @@ pub extern "C" fn fork_syscall(
     if isthread == 0 {
         // Allocate a fresh cage ID for the child.
         child_cageid = cage::alloc_cage_id().unwrap();
+        // A replayed child stands in for the cage the log has for this fork
+        threei::record::fork_child(parent_cageid, parent_tid, child_cageid);
 
         // Duplicate the parent's file descriptor table.
         fdtables::copy_fdtable_for_cage(parent_cageid, child_cageid).unwrap();
 
+        // In per-cage overlay mode the child writes into its own upper layer
+        overlay_fork(parent_cageid, child_cageid);
+
         // Get the self cage
         let selfcage = get_cage(parent_cageid).unwrap();
 
@@ -133,14 +156,16 @@ pub extern "C" fn fork_syscall(
             parent: parent_cageid,
             rev_shm: Mutex::new(Vec::new()),
             main_threadid: RwLock::new(0),
-            interval_timer: IntervalTimer::new(child_cageid),
+            timers: CageTimers::new(child_cageid),
+            cpu: CpuAccount::default(),
+            realtime_offset: AtomicI64::new(selfcage.realtime_offset.load(Relaxed)),
             epoch_handler: DashMap::new(),
             pending_signals: RwLock::new(vec![]),
             signalhandler: selfcage.signalhandler.clone(),
-            sigset: AtomicU64::new(0),
             zombies: RwLock::new(vec![]),
             child_num: AtomicU64::new(0),
             vmmap: RwLock::new(new_vmmap),
+            uts: RwLock::new(*selfcage.uts.read()),
         };
 
         // increment child counter for parent
@@ -183,7 +208,7 @@ pub extern "C" fn fork_syscall(
     //   - Resolve the correct VMContext
     //   - Complete fork semantics
     //   - Resume execution in parent and child
-    threei::make_syscall(
+    let ret = threei::make_syscall(
         RAWPOSIX_CAGEID,
         56, // clone syscall number
         UNUSED_NAME,
@@ -200,7 +225,16 @@ pub extern "C" fn fork_syscall(
         UNUSED_ID,
         UNUSED_ARG,
         UNUSED_ID,
-    )
+    );
+
+    // The child committed a copy of every private page of the parent. Only the parent
+    // returns here, once the child's memory has been populated. A child over budget is
+    // killed, but the fork itself succeeded and the parent reaps it like any other.
+    if isthread == 0 && ret >= 0 {
+        let _ = memory_budget_check(child_cageid);
+    }
+
+    ret
 }
 
 /// Reference to Linux: https://man7.org/linux/man-pages/man3/exec.3.html
@@ -213,7 +247,7 @@ pub extern "C" fn fork_syscall(
 /// (closing or inheriting them based on the `should_cloexec` flag in fdtable), resetting semaphores, and
 /// managing process attributes and threads (terminating unnecessary threads). This allows us to fully implement
 /// the exec functionality while aligning with POSIX standards. Cage fields remained in exec():
-/// cageid, cwd, parent, interval_timer
+/// cageid, cwd, parent, the interval timers in timers, and the CPU time in cpu
 pub extern "C" fn exec_syscall(
     cageid: u64,
     path: u64,
@@ -260,8 +294,13 @@ pub extern "C" fn exec_syscall(
     // all the signal handler becomes default after exec
     // pending signals should be perserved though
     selfcage.signalhandler.clear();
-    // the sigset will be reset after exec
-    selfcage.sigset.store(0, Relaxed);
+    // the sigset will be reset after exec: the thread that goes on starts over with an empty mask
+    // and without the alternate signal stack, which was in the memory exec replaces
+    // POSIX timers are deleted, interval timers keep running. The CPU time of the threads
+    // exec ends stays with the cage, the thread that goes on is counted again once wasmtime
+    // re-establishes it
+    selfcage.timers.exec();
+    selfcage.cpu.threads_exit();
     // we also clean up epoch handler and main thread id
     // since they will be re-established from wasmtime
     selfcage.epoch_handler.clear();
@@ -346,6 +385,18 @@ pub extern "C" fn exit_syscall(
     // in the cage (0 = no, 1 = yes).
     let mut is_last_thread = 0;
 
+    // Harsh exit (see `threei::trigger_harsh_cage_exit`): no thread of the cage is
+    // exiting here, `status` is the signal that killed it. Release the cage's resources
+    // and report it to the parent now; its threads are stopped by the runtime and never
+    // reach the exit path below, so there is nothing to hand back to Wasmtime.
+    //
+    // The cage stays in the cage table, as its threads may still be running until their
+    // next epoch check.
+    if threei::EXITING_TABLE.contains(&selfcageid) {
+        cage_exit_cleanup(selfcageid, status & 0x7f);
+        return 0;
+    }
+
     // Perform thread exit inside RawPOSIX.
     //
     // `lind_thread_exit` returns true if this thread was the last
@@ -354,40 +405,12 @@ pub extern "C" fn exit_syscall(
         // Need to perform cage-level resource cleanup
         is_last_thread = 1;
 
-        // Cleanup fdtable
-        fdtables::remove_cage_from_fdtable(selfcageid);
+        cage_exit_cleanup(selfcageid, (status & 0xff) << 8);
 
-        // Cleanup cage table and process hierarchy state.
-        //
-        // If the cage has a parent, we:
-        //   - Decrement the parent's child count
-        //   - Record this cage as a zombie
-        //   - Send SIGCHLD to the parent
+        // Remove the cage from the global cage table.
         //
         //may not be removable in case of lindrustfinalize, we don't unwrap the remove result
-        if let Some(selfcage) = get_cage(selfcageid) {
-            if selfcage.parent != selfcageid {
-                let parent_cage = get_cage(selfcage.parent);
-                if let Some(parent) = parent_cage {
-                    parent.child_num.fetch_sub(1, SeqCst);
-                    let mut zombie_vec = parent.zombies.write();
-                    zombie_vec.push(Zombie {
-                        cageid: selfcageid,
-                        exit_code: status,
-                    });
-                } else {
-                    // if parent already exited
-                    // BUG: we currently do not handle the situation where a parent has exited already
-                }
-            }
-
-            // if the cage has parent (i.e. it is not the "root" cage)
-            if selfcageid != selfcage.parent {
-                // Notify parent via SIGCHLD if this is not the root cage.
-                lind_send_signal(selfcage.parent, SIGCHLD);
-            }
-
-            // Remove the cage from the global cage table.
+        if get_cage(selfcageid).is_some() {
             remove_cage(selfcageid);
         }
     }
@@ -414,12 +437,86 @@ pub extern "C" fn exit_syscall(
     )
 }
 
+/// Cage-level cleanup shared by a normal exit of the last thread and a harsh exit.
+///
+/// `wait_status` is what the parent's `waitpid()` reports, encoded as on Linux: the exit code
+/// in bits 8-15 for a normal exit, the signal number in the low 7 bits for a harsh one.
+///
+/// Releases the fd table and overlay state, then, if the cage has a parent:
+///   - Decrements the parent's child count
+///   - Records this cage as a zombie, unless the parent ignores SIGCHLD or set `SA_NOCLDWAIT`
+///     on it, in which case the child is reaped right away as on Linux
+///   - Sends SIGCHLD to the parent
+///
+/// Children are never stopped in Lind, so SIGCHLD only reports exits and `SA_NOCLDSTOP` has
+/// nothing to suppress.
+fn cage_exit_cleanup(selfcageid: u64, wait_status: i32) {
+    // Cleanup fdtable
+    fdtables::remove_cage_from_fdtable(selfcageid);
+    overlay_exit(selfcageid);
+
+    if let Some(selfcage) = get_cage(selfcageid) {
+        selfcage.timers.clear();
+
+        if selfcage.parent != selfcageid {
+            let parent_cage = get_cage(selfcage.parent);
+            if let Some(parent) = parent_cage {
+                // Peak RSS for the parent's wait4(), in kilobytes
+                let mut vmmap = selfcage.vmmap.write();
+                vmmap.sample_resident_pages();
+                let maxrss = (vmmap.peak_resident_pages << PAGESHIFT) as i64 / 1024;
+                drop(vmmap);
+
+                // The children of the cage count in its time once it is waited for
+                let own = selfcage.cpu.times();
+                let children = selfcage.cpu.children();
+                let cpu = CpuTimes {
+                    user: own.user + children.user,
+                    system: own.system + children.system,
+                };
+
+                // no zombie is left behind for a parent that ignores SIGCHLD or set
+                // SA_NOCLDWAIT, its wait calls only ever see the children still running
+                let autoreap = parent.signalhandler.get(&SIGCHLD).is_some_and(|act| {
+                    act.sa_handler == SIG_IGN as u32 || act.sa_flags as u32 & SA_NOCLDWAIT != 0
+                });
+                // hold the zombie list while the child count drops, a waiting parent must not
+                // find the child gone from the count before its zombie is there
+                let mut zombie_vec = parent.zombies.write();
+                parent.child_num.fetch_sub(1, SeqCst);
+                if !autoreap {
+                    zombie_vec.push(Zombie {
+                        cageid: selfcageid,
+                        exit_code: wait_status,
+                        maxrss,
+                        cpu,
+                    });
+                }
+            } else {
+                // if parent already exited
+                // BUG: we currently do not handle the situation where a parent has exited already
+            }
+        }
+
+        // if the cage has parent (i.e. it is not the "root" cage)
+        if selfcageid != selfcage.parent {
+            // Notify parent via SIGCHLD if this is not the root cage.
+            lind_send_signal(selfcage.parent, SIGCHLD);
+        }
+    }
+}
+
 /// Reference to Linux: https://man7.org/linux/man-pages/man3/waitpid.3p.html
 ///
 /// waitpid() will return the cageid of waited cage, or 0 when WNOHANG is set and there is no cage already exited
 /// waitpid_syscall utilizes the zombie list stored in cage struct. When a cage exited, a zombie entry will be inserted
 /// into the end of its parent's zombie list. Then when parent wants to wait for any of child, it could just check its
 /// zombie list and retrieve the first entry from it (first in, first out).
+///
+/// The same call backs `wait4()`, which passes a `struct rusage` pointer as the fourth argument.
+/// Only `ru_utime` / `ru_stime` (the CPU time of the child and of the children it waited for)
+/// and `ru_maxrss` (the child's peak resident set size) are tracked, all other fields are zeroed.
+/// The CPU time is added to the children time of the cage, see `getrusage_syscall`.
 pub extern "C" fn waitpid_syscall(
     cageid: u64,
     child_cageid_arg: u64,
@@ -428,8 +525,8 @@ pub extern "C" fn waitpid_syscall(
     status_cageid: u64,
     options_arg: u64,
     options_cageid: u64,
-    arg4: u64,
-    arg4_cageid: u64,
+    rusage_arg: u64,
+    rusage_cageid: u64,
     arg5: u64,
     arg5_cageid: u64,
     arg6: u64,
@@ -449,12 +546,10 @@ pub extern "C" fn waitpid_syscall(
     let options = sc_convert_sysarg_to_i32(options_arg, options_cageid, cageid);
     let cage_id_to_wait =
         sc_convert_sysarg_to_i32(child_cageid_arg, child_cageid_arg_cageid, cageid);
+    let rusage = sc_convert_addr_to_rusage(rusage_arg, rusage_cageid, cageid);
     // would check when `secure` flag has been set during compilation,
     // no-op by default
-    if !(sc_unusedarg(arg4, arg4_cageid)
-        && sc_unusedarg(arg5, arg5_cageid)
-        && sc_unusedarg(arg6, arg6_cageid))
-    {
+    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
         panic!(
             "{}: unused arguments contain unexpected values -- security violation",
             "waitpid_syscall"
@@ -498,10 +593,19 @@ pub extern "C" fn waitpid_syscall(
                 }
                 // Check for pending signals after yielding (only if WNOHANG is not set)
                 if (options & WNOHANG == 0) && signal_check_trigger(cage.cageid) {
-                    return syscall_error(Errno::EINTR, "waitpid", "interrupted by signal");
+                    return handle_errno(interrupted(cage.cageid, true), "waitpid");
                 }
                 // after sleep, get the write access of zombies list back
                 zombies = cage.zombies.write();
+                // children reaped on exit (SIGCHLD ignored or SA_NOCLDWAIT) leave no zombie,
+                // once all of them are gone there is nothing left to wait for
+                if zombies.len() == 0 && cage.child_num.load(Relaxed) == 0 {
+                    return syscall_error(
+                        Errno::ECHILD,
+                        "waitpid",
+                        "no existing unwaited-for child processes",
+                    );
+                }
                 continue;
             } else {
                 // there are zombies avaliable
@@ -554,7 +658,7 @@ pub extern "C" fn waitpid_syscall(
                 }
                 // Check for pending signals after yielding (only if WNOHANG is not set)
                 if (options & WNOHANG == 0) && signal_check_trigger(cage.cageid) {
-                    return syscall_error(Errno::EINTR, "waitpid", "interrupted by signal");
+                    return handle_errno(interrupted(cage.cageid, true), "waitpid");
                 }
                 // after sleep, get the write access of zombies list back
                 zombies = cage.zombies.write();
@@ -568,6 +672,10 @@ pub extern "C" fn waitpid_syscall(
                     zombie_opt = Some(zombies.remove(index));
                     break;
                 }
+                // the child exited without leaving a zombie, it was reaped on exit
+                if get_cage(cage_id_to_wait as u64).is_none() || cage.child_num.load(Relaxed) == 0 {
+                    return syscall_error(Errno::ECHILD, "waitpid", "child was reaped on exit");
+                }
 
                 continue;
             }
@@ -576,10 +684,17 @@ pub extern "C" fn waitpid_syscall(
 
     // reach here means we already found the desired exited child
     let zombie = zombie_opt.unwrap();
+    cage.cpu.add_children(zombie.cpu);
     // update the status
     if let Some(status) = status {
         *status = zombie.exit_code;
     }
+    if let Some(rusage) = rusage {
+        *rusage = unsafe { std::mem::zeroed() };
+        rusage.ru_utime = duration_timeval(zombie.cpu.user);
+        rusage.ru_stime = duration_timeval(zombie.cpu.system);
+        rusage.ru_maxrss = zombie.maxrss;
+    }
 
     // return child's cageid
     zombie.cageid as i32
@@ -668,6 +783,157 @@ pub extern "C" fn getppid_syscall(
     return cage.parent as i32;
 }
 
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/gettid.2.html
+///
+/// Implements `gettid`.
+/// A thread of a cage is identified by the thread id wasmtime gave it when it was created, the
+/// same id `pthread_create()` stores in the thread's descriptor. The main thread of a cage has
+/// id 1.
+///
+/// ## Returns
+/// The thread id of the calling thread
+pub extern "C" fn gettid_syscall(
+    cageid: u64,
+    arg1: u64,
+    arg1_cageid: u64,
+    arg2: u64,
+    arg2_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    // would check when `secure` flag has been set during compilation,
+    // no-op by default
+    if !(sc_unusedarg(arg1, arg1_cageid)
+        && sc_unusedarg(arg2, arg2_cageid)
+        && sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "gettid_syscall"
+        );
+    }
+
+    match current_threadid(cageid) {
+        Ok(threadid) => threadid,
+        Err(errno) => syscall_error(errno, "gettid", "the cage is gone"),
+    }
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/tgkill.2.html
+///
+/// Implements `tgkill`, which sends a signal to one thread of a cage. The thread group id is
+/// the cage id and the thread id is the one `gettid` returns. Unlike the signals `kill` sends,
+/// only that thread may handle the signal, once it does not block it.
+///
+/// ## Arguments
+/// * `tgid_arg` – The cage the thread belongs to.
+/// * `tid_arg` – The thread in that cage.
+/// * `sig_arg` – The signal number. 0 only checks that the thread exists.
+///
+/// ## Returns
+/// `0` on success, or a negative errno on failure.
+///
+/// ## Errors
+/// * `EINVAL` – Invalid cage id, thread id or signal number.
+/// * `ESRCH` – No such thread in the cage.
+pub extern "C" fn tgkill_syscall(
+    cageid: u64,
+    tgid_arg: u64,
+    tgid_cageid: u64,
+    tid_arg: u64,
+    tid_cageid: u64,
+    sig_arg: u64,
+    sig_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let tgid = sc_convert_sysarg_to_i32(tgid_arg, tgid_cageid, cageid);
+    let tid = sc_convert_sysarg_to_i32(tid_arg, tid_cageid, cageid);
+    let sig = sc_convert_sysarg_to_i32(sig_arg, sig_cageid, cageid);
+    if !(sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "tgkill_syscall"
+        );
+    }
+
+    if tgid <= 0 || tid <= 0 {
+        return syscall_error(Errno::EINVAL, "tgkill", "Invalid thread group or thread id");
+    }
+    if !(0..32).contains(&sig) {
+        return syscall_error(Errno::EINVAL, "tgkill", "Invalid signal number");
+    }
+
+    if !lind_send_thread_signal(tgid as u64, tid, sig) {
+        return syscall_error(Errno::ESRCH, "tgkill", "No such thread");
+    }
+    0
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/tkill.2.html
+///
+/// Implements `tkill`, the older form of `tgkill` without the thread group id. Thread ids are
+/// only unique within a cage, so the thread is looked up in the calling cage.
+///
+/// ## Returns
+/// `0` on success, or a negative errno on failure, see `tgkill_syscall`.
+pub extern "C" fn tkill_syscall(
+    cageid: u64,
+    tid_arg: u64,
+    tid_cageid: u64,
+    sig_arg: u64,
+    sig_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let tid = sc_convert_sysarg_to_i32(tid_arg, tid_cageid, cageid);
+    let sig = sc_convert_sysarg_to_i32(sig_arg, sig_cageid, cageid);
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "tkill_syscall"
+        );
+    }
+
+    if tid <= 0 {
+        return syscall_error(Errno::EINVAL, "tkill", "Invalid thread id");
+    }
+    if !(0..32).contains(&sig) {
+        return syscall_error(Errno::EINVAL, "tkill", "Invalid signal number");
+    }
+
+    if !lind_send_thread_signal(cageid, tid, sig) {
+        return syscall_error(Errno::ESRCH, "tkill", "No such thread");
+    }
+    0
+}
+
 /// Reference to Linux: https://man7.org/linux/man-pages/man2/getgid.2.html
 ///
 /// Get the real **host** group ID of the calling process.
@@ -988,15 +1254,16 @@ pub extern "C" fn kill_syscall(
 
 /// Reference to Linux: https://man7.org/linux/man-pages/man2/sigprocmask.2.html
 ///
-/// This function allows a cage to examine or change its
-/// signal mask, i.e., the set of signals currently blocked from delivery.
+/// This function allows a thread of a cage to examine or change its own
+/// signal mask, i.e., the set of signals currently blocked from delivery to it.
 /// If `oldset` is provided, copies the current signal mask into it. If `set` is
 /// provided, updates the mask according to `how`:
 ///    - `SIG_BLOCK`: add signals from `set` to the mask.
 ///    - `SIG_UNBLOCK`: remove signals from `set` from the mask; if any pending
-///       signals are now unblocked, trigger a signal epoch.
+///       signals are now unblocked, trigger the epoch of the thread.
 ///    - `SIG_SETMASK`: replace the mask with `set`; if any previously blocked
-///       pending signals are now unblocked, trigger a signal epoch.
+///       pending signals are now unblocked, trigger the epoch of the thread.
+/// SIGKILL and SIGSTOP cannot be blocked and are left out of the mask silently.
 ///
 /// ## Arguments
 /// * `cageid` – The ID of the calling cage.
@@ -1042,61 +1309,121 @@ pub extern "C" fn sigprocmask_syscall(
         );
     }
 
-    let cage = get_cage(cageid).unwrap();
-
-    let mut res = 0;
-
+    // the mask is the calling thread's own
+    let curr_sigset = signal_mask_get(cageid);
     if let Some(some_oldset) = oldset {
-        *some_oldset = cage.sigset.load(Relaxed);
+        *some_oldset = curr_sigset;
     }
 
     if let Some(some_set) = set {
-        let curr_sigset = cage.sigset.load(Relaxed);
-        res = match how {
-            SIG_BLOCK => {
-                // Block signals in set
-                cage.sigset.store(curr_sigset | *some_set, Relaxed);
-                0
-            }
-            SIG_UNBLOCK => {
-                // Unblock signals in set
-                let newset = curr_sigset & !*some_set;
-                cage.sigset.store(newset, Relaxed);
-                // check if any of the unblocked signals are in the pending signal list
-                // and trigger the epoch if it has
-                let pending_signals = cage.pending_signals.read();
-                if pending_signals
-                    .iter()
-                    .any(|signo| (*some_set & convert_signal_mask(*signo)) != 0)
-                {
-                    cage::signal_epoch_trigger(cage.cageid);
-                }
-                0
-            }
-            SIG_SETMASK => {
-                let pending_signals = cage.pending_signals.read();
-                // find all signals switched from blocking to nonblocking
-                // 1. perform a xor operation to find signals that switched state
-                // all the signal masks changed from 0 to 1, or 1 to 0 are filtered in this step
-                // 2. perform an and operation to the old sigset, this further filtered masks and only
-                // left masks changed from 1 to 0
-                let unblocked_signals = (curr_sigset ^ *some_set) & curr_sigset;
-                // check if any of the unblocked signals are in the pending signal list
-                // and trigger the epoch if it has
-                if pending_signals
-                    .iter()
-                    .any(|signo| (unblocked_signals & convert_signal_mask(*signo)) != 0)
-                {
-                    cage::signal_epoch_trigger(cage.cageid);
-                }
-                // Set sigset to set
-                cage.sigset.store(*some_set, Relaxed);
-                0
+        let newset = match how {
+            // Block signals in set
+            SIG_BLOCK => curr_sigset | *some_set,
+            // Unblock signals in set
+            SIG_UNBLOCK => curr_sigset & !*some_set,
+            // Set sigset to set
+            SIG_SETMASK => *some_set,
+            _ => return syscall_error(Errno::EINVAL, "sigprocmask", "Invalid value for how"),
+        };
+        // pending signals the new mask unblocks trigger the epoch of the thread, the ones it
+        // blocks now are handed to other threads
+        signal_mask_swap(cageid, newset);
+    }
+    0
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/sigaltstack.2.html
+///
+/// Examine or change the alternate signal stack of the calling thread, the stack handlers
+/// installed with `SA_ONSTACK` run on. If `old_ss` is provided, the current stack is copied into
+/// it, with `SS_ONSTACK` set if the thread runs on it. If `ss` is provided, it becomes the new
+/// stack, or the thread has none anymore if its flags are `SS_DISABLE`. The stacks are in the
+/// cage's linear memory: `ss_sp` is a guest address, and the stack pointer that tells whether the
+/// thread is on its alternate stack is the shadow stack pointer, which glibc passes along.
+///
+/// ## Arguments
+/// * `cageid` – The ID of the calling cage.
+/// * `ss_arg` / `ss_cageid` – Optional pointer to the new `stack_t`.
+/// * `old_ss_arg` / `old_ss_cageid` – Optional pointer where the current `stack_t` is stored.
+/// * `sp_arg` / `sp_cageid` – The caller's `__stack_pointer`.
+///
+/// ## Returns:
+/// Returns `0` on success, or an error code (`EPERM`, `EINVAL`, `ENOMEM`) on failure.
+///
+/// ## Errors
+/// * `EPERM` – The stack was to be changed while the thread runs on it.
+/// * `EINVAL` – `ss_flags` is neither `0`, `SS_ONSTACK` nor `SS_DISABLE`.
+/// * `ENOMEM` – The new stack is smaller than `MINSIGSTKSZ`.
+pub extern "C" fn sigaltstack_syscall(
+    cageid: u64,
+    ss_arg: u64,
+    ss_cageid: u64,
+    old_ss_arg: u64,
+    old_ss_cageid: u64,
+    sp_arg: u64,
+    sp_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let ss = sc_convert_addr_to_stack(ss_arg, ss_cageid, cageid);
+    let old_ss = sc_convert_addr_to_stack_mut(old_ss_arg, old_ss_cageid, cageid);
+    let sp = sc_convert_sysarg_to_u32(sp_arg, sp_cageid, cageid);
+    if !(sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "sigaltstack_syscall"
+        );
+    }
+
+    let threadid = match current_threadid(cageid) {
+        Ok(threadid) => threadid,
+        Err(errno) => return syscall_error(errno, "sigaltstack", "the cage is gone"),
+    };
+    let curr_stack = signal_altstack_get(cageid, threadid);
+    let on_stack = altstack_contains(&curr_stack, sp);
+
+    // read before old_ss is written, the two may be the same struct
+    if let Some(&new_stack) = ss {
+        if on_stack {
+            return syscall_error(
+                Errno::EPERM,
+                "sigaltstack",
+                "Cannot change the alternate signal stack while running on it",
+            );
+        }
+        let new_stack = match new_stack.ss_flags {
+            SS_DISABLE => ALTSTACK_DISABLED,
+            // SS_ONSTACK is accepted for old programs, and means the same as 0
+            0 | SS_ONSTACK if new_stack.ss_size < MINSIGSTKSZ => {
+                return syscall_error(
+                    Errno::ENOMEM,
+                    "sigaltstack",
+                    "Alternate signal stack is too small",
+                )
             }
-            _ => syscall_error(Errno::EINVAL, "sigprocmask", "Invalid value for how"),
+            0 | SS_ONSTACK => StackStruct {
+                ss_flags: 0,
+                ..new_stack
+            },
+            _ => return syscall_error(Errno::EINVAL, "sigaltstack", "Invalid value for ss_flags"),
+        };
+        signal_altstack_set(cageid, threadid, new_stack);
+    }
+
+    if let Some(old_ss) = old_ss {
+        *old_ss = curr_stack;
+        if on_stack {
+            old_ss.ss_flags = SS_ONSTACK;
         }
     }
-    res
+    0
 }
 
 /// Reference to Linux: https://man7.org/linux/man-pages/man2/sched_yield.2.html
@@ -1138,31 +1465,65 @@ pub extern "C" fn sched_yield_syscall(
     (unsafe { sched_yield() }) as i32
 }
 
-/// Reference to Linux: https://man7.org/linux/man-pages/man3/setitimer.3p.html
+/// A `timeval` as a duration
+///
+/// ## Returns:
+/// - `Err(EINVAL)` if it is negative or its microseconds are out of range
+fn timeval_duration(tv: &TimeVal) -> Result<Duration, Errno> {
+    if tv.tv_sec < 0 || !(0..1_000_000).contains(&tv.tv_usec) {
+        return Err(Errno::EINVAL);
+    }
+    Ok(Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000))
+}
+
+/// A duration as a `timeval`, rounded up to the microsecond so that an armed timer never
+/// reads as disarmed
+fn duration_timeval(duration: Duration) -> TimeVal {
+    let usec = duration.as_nanos().div_ceil(1000);
+    TimeVal {
+        tv_sec: (usec / 1_000_000) as i64,
+        tv_usec: (usec % 1_000_000) as i64,
+    }
+}
+
+/// A `timespec` as a duration
+///
+/// ## Returns:
+/// - `Err(EINVAL)` if it is negative or its nanoseconds are out of range
+fn timespec_duration(ts: &TimeSpec) -> Result<Duration, Errno> {
+    if ts.tv_sec < 0 || !(0..1_000_000_000).contains(&ts.tv_nsec) {
+        return Err(Errno::EINVAL);
+    }
+    Ok(Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+}
+
+fn duration_timespec(duration: Duration) -> TimeSpec {
+    TimeSpec {
+        tv_sec: duration.as_secs() as i64,
+        tv_nsec: duration.subsec_nanos() as i64,
+    }
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/setitimer.2.html
 ///
-/// This syscall allows a cage to set or retrieve the value of an interval timer.
-/// Currently only `ITIMER_REAL` is supported, which decrements in real (wall-clock)
-/// time and delivers `SIGALRM` upon expiration.
+/// Sets one of the three interval timers of the cage, which are served by the timer thread of
+/// `cage::timer`:
+/// - `ITIMER_REAL` counts wall-clock time and raises `SIGALRM`
+/// - `ITIMER_VIRTUAL` counts the user CPU time of the cage and raises `SIGVTALRM`
+/// - `ITIMER_PROF` counts its user and system CPU time and raises `SIGPROF`
 ///
-/// For `ITIMER_REAL`:  
-///    - If `old_value` is provided, copies the current timer’s remaining time and interval
-///      into it.  
-///    - If `new_value` is provided, updates the interval timer with the new durations.  
-/// For `ITIMER_VIRTUAL` and `ITIMER_PROF`, no action is taken (not implemented).
+/// The timer expires after `it_value` and then every `it_interval`; a zero `it_value` disarms
+/// it. Like on Linux, a NULL `new_value` disarms it too. The timers are not inherited by
+/// `fork`, but survive `exec`.
 ///
 /// ## Arguments
-/// * `cageid` – The ID of the calling cage.
-/// * `which_arg` / `which_arg_cageid` – Encoded argument specifying which timer to use
-///   (`ITIMER_REAL`, `ITIMER_VIRTUAL`, `ITIMER_PROF`). Only `ITIMER_REAL` is implemented.
-/// * `new_value_arg` / `new_value_arg_cageid` – Pointer to a new `itimerval` struct.
-///   If non-null, this specifies the new timer settings.
-/// * `old_value_arg` / `old_value_arg_cageid` – Pointer to an `itimerval` struct
-///   where the previous timer value should be stored. If non-null, the current
-///   timer is copied here before being changed.
+/// * `which_arg` – the timer, `ITIMER_REAL`, `ITIMER_VIRTUAL` or `ITIMER_PROF`.
+/// * `new_value_arg` – pointer to the new `itimerval`.
+/// * `old_value_arg` – pointer to an `itimerval` that receives the previous setting, or NULL.
 ///
 /// ## Returns
 /// * `0` on success.
-/// * Negative errno (`EFAULT`, etc.) on failure.
+/// * `-EINVAL` for an unknown timer or an `itimerval` out of range.
 pub extern "C" fn setitimer_syscall(
     cageid: u64,
     which_arg: u64,
@@ -1194,32 +1555,1010 @@ pub extern "C" fn setitimer_syscall(
 
     // get the cage instance
     let cage = get_cage(cageid).unwrap();
+    let Some(timer) = cage.timers.itimer(which) else {
+        return syscall_error(Errno::EINVAL, "setitimer", "invalid timer");
+    };
 
-    match which {
-        ITIMER_REAL => {
-            if let Some(some_old_value) = old_value {
-                let (curr_duration, next_duration) = cage.interval_timer.get_itimer();
-                some_old_value.it_value.tv_sec = curr_duration.as_secs() as i64;
-                some_old_value.it_value.tv_usec = curr_duration.subsec_millis() as i64;
-                some_old_value.it_interval.tv_sec = next_duration.as_secs() as i64;
-                some_old_value.it_interval.tv_usec = next_duration.subsec_millis() as i64;
-            }
+    let (value, interval) = match new_value {
+        Some(new_value) => match (
+            timeval_duration(&new_value.it_value),
+            timeval_duration(&new_value.it_interval),
+        ) {
+            (Ok(value), Ok(interval)) => (value, interval),
+            _ => return syscall_error(Errno::EINVAL, "setitimer", "invalid itimerval"),
+        },
+        None => (Duration::ZERO, Duration::ZERO),
+    };
 
-            if let Some(some_new_value) = new_value {
-                let curr_duration = Duration::new(
-                    some_new_value.it_value.tv_sec as u64,
-                    some_new_value.it_value.tv_usec as u32,
-                );
-                let next_duration = Duration::new(
-                    some_new_value.it_interval.tv_sec as u64,
-                    some_new_value.it_interval.tv_usec as u32,
-                );
-
-                cage.interval_timer.set_itimer(curr_duration, next_duration);
-            }
-        }
+    let (old_remaining, old_interval) = timer.set(value, interval, false);
+    if let Some(old_value) = old_value {
+        old_value.it_value = duration_timeval(old_remaining);
+        old_value.it_interval = duration_timeval(old_interval);
+    }
+    0
+}
 
-        _ => { /* ITIMER_VIRTUAL and ITIMER_PROF is not implemented*/ }
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/getitimer.2.html
+///
+/// Reads one of the interval timers of the cage, see `setitimer_syscall`: the time until it
+/// expires, zero if it is disarmed, and its interval.
+///
+/// ## Arguments
+/// * `which_arg` – the timer, `ITIMER_REAL`, `ITIMER_VIRTUAL` or `ITIMER_PROF`.
+/// * `curr_value_arg` – pointer to the `itimerval` that receives the setting.
+///
+/// ## Returns
+/// * `0` on success.
+/// * `-EINVAL` for an unknown timer, `-EFAULT` if `curr_value_arg` is NULL.
+pub extern "C" fn getitimer_syscall(
+    cageid: u64,
+    which_arg: u64,
+    which_arg_cageid: u64,
+    curr_value_arg: u64,
+    curr_value_arg_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let which = sc_convert_sysarg_to_i32(which_arg, which_arg_cageid, cageid);
+    let curr_value = sc_convert_itimerval_mut(curr_value_arg, curr_value_arg_cageid, cageid);
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "getitimer_syscall"
+        );
     }
+
+    let cage = get_cage(cageid).unwrap();
+    let Some(timer) = cage.timers.itimer(which) else {
+        return syscall_error(Errno::EINVAL, "getitimer", "invalid timer");
+    };
+    let Some(curr_value) = curr_value else {
+        return syscall_error(Errno::EFAULT, "getitimer", "curr_value is NULL");
+    };
+
+    let (remaining, interval) = timer.get();
+    curr_value.it_value = duration_timeval(remaining);
+    curr_value.it_interval = duration_timeval(interval);
     0
 }
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/alarm.2.html
+///
+/// Arms `ITIMER_REAL` to raise `SIGALRM` once after `seconds`, or disarms it if `seconds` is 0.
+/// `alarm` and `setitimer(ITIMER_REAL)` share the same timer.
+///
+/// ## Arguments
+/// * `seconds_arg` – the delay in seconds.
+///
+/// ## Returns
+/// * the seconds that were left on the previous alarm, rounded to the nearest second but at
+///   least 1 if it was armed, or 0 if there was none.
+pub extern "C" fn alarm_syscall(
+    cageid: u64,
+    seconds_arg: u64,
+    seconds_arg_cageid: u64,
+    arg2: u64,
+    arg2_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let seconds = sc_convert_sysarg_to_u32(seconds_arg, seconds_arg_cageid, cageid);
+    if !(sc_unusedarg(arg2, arg2_cageid)
+        && sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "alarm_syscall"
+        );
+    }
+
+    let cage = get_cage(cageid).unwrap();
+    let timer = cage.timers.itimer(ITIMER_REAL).unwrap();
+    let (remaining, _) = timer.set(Duration::from_secs(seconds as u64), Duration::ZERO, false);
+
+    let mut secs = remaining.as_secs();
+    if remaining.subsec_micros() >= 500_000 || (secs == 0 && !remaining.is_zero()) {
+        secs += 1;
+    }
+    secs as i32
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/timer_create.2.html
+///
+/// Creates a disarmed POSIX timer for the cage, served like the interval timers by the timer
+/// thread of `cage::timer`. It counts one of these clocks:
+/// - `CLOCK_REALTIME`, `CLOCK_MONOTONIC`, `CLOCK_BOOTTIME` and `CLOCK_TAI`, the host's
+/// - `CLOCK_PROCESS_CPUTIME_ID`, the CPU time of the cage
+/// - `CLOCK_THREAD_CPUTIME_ID`, the CPU time of the calling thread, or of the cage in
+///   deterministic mode, which only accounts for cages
+///
+/// On expiry, the timer raises `sigev_signo` in the cage for `SIGEV_SIGNAL` and nothing for
+/// `SIGEV_NONE`. Lind has no thread-directed signals yet, so `SIGEV_THREAD_ID` raises it in the
+/// cage as well, once the thread is checked to be one of the cage's. Signal handlers get no
+/// `siginfo_t`, so the `sigev_value` of the timer is not reported to them. glibc implements
+/// `SIGEV_THREAD` on top of `SIGEV_THREAD_ID` with a real-time signal, which lind cannot raise.
+///
+/// ## Arguments
+/// * `clockid_arg` – the clock the timer counts.
+/// * `sevp_arg` – pointer to the `SigEvent` glibc translated the `struct sigevent` into, or NULL
+///   for `SIGEV_SIGNAL` with `SIGALRM`.
+/// * `timerid_arg` – pointer to the `int` that receives the id of the timer.
+///
+/// ## Returns
+/// * `0` on success.
+/// * `-EINVAL` for an unknown clock or an invalid `sigevent`, `-EOPNOTSUPP` for a clock that
+///   cannot be used for timers, `-EPERM` for the alarm clocks, `-EFAULT` if `timerid_arg` is
+///   NULL.
+pub extern "C" fn timer_create_syscall(
+    cageid: u64,
+    clockid_arg: u64,
+    clockid_arg_cageid: u64,
+    sevp_arg: u64,
+    sevp_arg_cageid: u64,
+    timerid_arg: u64,
+    timerid_arg_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let clockid = sc_convert_sysarg_to_i32(clockid_arg, clockid_arg_cageid, cageid);
+    if !(sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "timer_create_syscall"
+        );
+    }
+
+    let clock = match clockid {
+        CLOCK_REALTIME | CLOCK_MONOTONIC | CLOCK_BOOTTIME | CLOCK_TAI => TimerClock::Host(clockid),
+        CLOCK_PROCESS_CPUTIME_ID => TimerClock::CageCpu,
+        // Syscalls run on the host thread of the calling cage thread
+        CLOCK_THREAD_CPUTIME_ID if is_deterministic() => TimerClock::CageCpu,
+        CLOCK_THREAD_CPUTIME_ID => TimerClock::Thread(cpu_clock_self()),
+        CLOCK_MONOTONIC_RAW | CLOCK_REALTIME_COARSE | CLOCK_MONOTONIC_COARSE => {
+            return syscall_error(Errno::EOPNOTSUPP, "timer_create", "clock has no timers");
+        }
+        CLOCK_REALTIME_ALARM | CLOCK_BOOTTIME_ALARM => {
+            return syscall_error(
+                Errno::EPERM,
+                "timer_create",
+                "alarm clocks are not permitted",
+            );
+        }
+        _ => return syscall_error(Errno::EINVAL, "timer_create", "invalid clock"),
+    };
+
+    let sev = if sc_convert_arg_nullity(sevp_arg, sevp_arg_cageid, cageid) {
+        SigEvent {
+            sigev_signo: SIGALRM,
+            sigev_notify: SIGEV_SIGNAL,
+            sigev_notify_thread_id: 0,
+        }
+    } else {
+        unsafe { *(sc_convert_buf(sevp_arg, sevp_arg_cageid, cageid) as *const SigEvent) }
+    };
+
+    let cage = get_cage(cageid).unwrap();
+    let signo = match sev.sigev_notify {
+        SIGEV_NONE => None,
+        SIGEV_SIGNAL | SIGEV_THREAD_ID => {
+            if !(1..32).contains(&sev.sigev_signo) {
+                return syscall_error(Errno::EINVAL, "timer_create", "invalid signal");
+            }
+            if sev.sigev_notify == SIGEV_THREAD_ID
+                && !cage.epoch_handler.contains_key(&sev.sigev_notify_thread_id)
+            {
+                return syscall_error(Errno::EINVAL, "timer_create", "no such thread");
+            }
+            Some(sev.sigev_signo)
+        }
+        _ => return syscall_error(Errno::EINVAL, "timer_create", "invalid sigev_notify"),
+    };
+
+    if sc_convert_arg_nullity(timerid_arg, timerid_arg_cageid, cageid) {
+        return syscall_error(Errno::EFAULT, "timer_create", "timerid is NULL");
+    }
+    let timerid = sc_convert_sysarg_to_i32_ref(timerid_arg, timerid_arg_cageid, cageid);
+    *timerid = cage.timers.create(clock, signo);
+    0
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/timer_settime.2.html
+///
+/// Arms a POSIX timer of the cage to expire at `it_value` and then every `it_interval`, or
+/// disarms it if `it_value` is zero. With `TIMER_ABSTIME` in `flags`, `it_value` is a reading of
+/// the timer's clock, and a time that has passed already expires the timer right away.
+///
+/// ## Arguments
+/// * `timerid_arg` – the id `timer_create` returned.
+/// * `flags_arg` – 0 or `TIMER_ABSTIME`.
+/// * `new_value_arg` – pointer to the new `itimerspec`, in the host layout.
+/// * `old_value_arg` – pointer to an `itimerspec` that receives the previous setting, or NULL.
+///
+/// ## Returns
+/// * `0` on success.
+/// * `-EINVAL` for an unknown timer or an `itimerspec` that is NULL or out of range.
+pub extern "C" fn timer_settime_syscall(
+    cageid: u64,
+    timerid_arg: u64,
+    timerid_arg_cageid: u64,
+    flags_arg: u64,
+    flags_arg_cageid: u64,
+    new_value_arg: u64,
+    new_value_arg_cageid: u64,
+    old_value_arg: u64,
+    old_value_arg_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let timerid = sc_convert_sysarg_to_i32(timerid_arg, timerid_arg_cageid, cageid);
+    let flags = sc_convert_sysarg_to_i32(flags_arg, flags_arg_cageid, cageid);
+    if !(sc_unusedarg(arg5, arg5_cageid) && sc_unusedarg(arg6, arg6_cageid)) {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "timer_settime_syscall"
+        );
+    }
+
+    let cage = get_cage(cageid).unwrap();
+    let Some(timer) = cage.timers.get(timerid) else {
+        return syscall_error(Errno::EINVAL, "timer_settime", "invalid timer");
+    };
+    if sc_convert_arg_nullity(new_value_arg, new_value_arg_cageid, cageid) {
+        return syscall_error(Errno::EINVAL, "timer_settime", "new_value is NULL");
+    }
+    let new_value = unsafe {
+        &*(sc_convert_buf(new_value_arg, new_value_arg_cageid, cageid) as *const ITimerSpec)
+    };
+    let (value, interval) = match (
+        timespec_duration(&new_value.it_value),
+        timespec_duration(&new_value.it_interval),
+    ) {
+        (Ok(value), Ok(interval)) => (value, interval),
+        _ => return syscall_error(Errno::EINVAL, "timer_settime", "invalid itimerspec"),
+    };
+
+    let (old_remaining, old_interval) = timer.set(value, interval, flags & TIMER_ABSTIME != 0);
+    if !sc_convert_arg_nullity(old_value_arg, old_value_arg_cageid, cageid) {
+        let old_value = unsafe {
+            &mut *(sc_convert_buf(old_value_arg, old_value_arg_cageid, cageid) as *mut ITimerSpec)
+        };
+        old_value.it_value = duration_timespec(old_remaining);
+        old_value.it_interval = duration_timespec(old_interval);
+    }
+    0
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/timer_gettime.2.html
+///
+/// Reads a POSIX timer of the cage: the time until it expires, zero if it is disarmed, and its
+/// interval.
+///
+/// ## Arguments
+/// * `timerid_arg` – the id `timer_create` returned.
+/// * `curr_value_arg` – pointer to the `itimerspec` that receives the setting, in the host
+///   layout.
+///
+/// ## Returns
+/// * `0` on success.
+/// * `-EINVAL` for an unknown timer, `-EFAULT` if `curr_value_arg` is NULL.
+pub extern "C" fn timer_gettime_syscall(
+    cageid: u64,
+    timerid_arg: u64,
+    timerid_arg_cageid: u64,
+    curr_value_arg: u64,
+    curr_value_arg_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let timerid = sc_convert_sysarg_to_i32(timerid_arg, timerid_arg_cageid, cageid);
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "timer_gettime_syscall"
+        );
+    }
+
+    let cage = get_cage(cageid).unwrap();
+    let Some(timer) = cage.timers.get(timerid) else {
+        return syscall_error(Errno::EINVAL, "timer_gettime", "invalid timer");
+    };
+    if sc_convert_arg_nullity(curr_value_arg, curr_value_arg_cageid, cageid) {
+        return syscall_error(Errno::EFAULT, "timer_gettime", "curr_value is NULL");
+    }
+    let curr_value = unsafe {
+        &mut *(sc_convert_buf(curr_value_arg, curr_value_arg_cageid, cageid) as *mut ITimerSpec)
+    };
+
+    let (remaining, interval) = timer.get();
+    curr_value.it_value = duration_timespec(remaining);
+    curr_value.it_interval = duration_timespec(interval);
+    0
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/timer_getoverrun.2.html
+///
+/// Returns the overrun count of a POSIX timer of the cage: how many times it expired while the
+/// last signal it raised that was delivered was still pending, including periods that went by
+/// before the timer thread got to it.
+///
+/// ## Arguments
+/// * `timerid_arg` – the id `timer_create` returned.
+///
+/// ## Returns
+/// * the overrun count, which saturates at `DELAYTIMER_MAX` (`INT_MAX`).
+/// * `-EINVAL` for an unknown timer.
+pub extern "C" fn timer_getoverrun_syscall(
+    cageid: u64,
+    timerid_arg: u64,
+    timerid_arg_cageid: u64,
+    arg2: u64,
+    arg2_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let timerid = sc_convert_sysarg_to_i32(timerid_arg, timerid_arg_cageid, cageid);
+    if !(sc_unusedarg(arg2, arg2_cageid)
+        && sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "timer_getoverrun_syscall"
+        );
+    }
+
+    let cage = get_cage(cageid).unwrap();
+    match cage.timers.get(timerid) {
+        Some(timer) => timer.overrun(),
+        None => syscall_error(Errno::EINVAL, "timer_getoverrun", "invalid timer"),
+    }
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/timer_delete.2.html
+///
+/// Disarms and deletes a POSIX timer of the cage. A signal it raised that is still pending
+/// stays pending.
+///
+/// ## Arguments
+/// * `timerid_arg` – the id `timer_create` returned.
+///
+/// ## Returns
+/// * `0` on success.
+/// * `-EINVAL` for an unknown timer.
+pub extern "C" fn timer_delete_syscall(
+    cageid: u64,
+    timerid_arg: u64,
+    timerid_arg_cageid: u64,
+    arg2: u64,
+    arg2_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let timerid = sc_convert_sysarg_to_i32(timerid_arg, timerid_arg_cageid, cageid);
+    if !(sc_unusedarg(arg2, arg2_cageid)
+        && sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "timer_delete_syscall"
+        );
+    }
+
+    let cage = get_cage(cageid).unwrap();
+    if !cage.timers.delete(timerid) {
+        return syscall_error(Errno::EINVAL, "timer_delete", "invalid timer");
+    }
+    0
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/clock_getres.2.html
+///
+/// Reports the resolution of one of the clocks `clock_gettime_syscall` reads. The CPU time
+/// clocks of the cages count nanoseconds, as do the virtual clocks of deterministic mode; the
+/// other clocks have the resolution of the host's.
+///
+/// ## Arguments
+/// * `clockid_arg` – the clock.
+/// * `res_arg` – pointer to the `timespec` that receives the resolution, in the host layout, or
+///   NULL to only check that the clock exists.
+///
+/// ## Returns
+/// * `0` on success.
+/// * `-EINVAL` for an unknown clock.
+pub extern "C" fn clock_getres_syscall(
+    cageid: u64,
+    clockid_arg: u64,
+    clockid_arg_cageid: u64,
+    res_arg: u64,
+    res_arg_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let clockid = sc_convert_sysarg_to_i32(clockid_arg, clockid_arg_cageid, cageid);
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "clock_getres_syscall"
+        );
+    }
+
+    if cage_clock_now(cageid, clockid).is_none() {
+        return syscall_error(Errno::EINVAL, "clock_getres", "invalid clock");
+    }
+    if sc_convert_arg_nullity(res_arg, res_arg_cageid, cageid) {
+        return 0;
+    }
+    let res = unsafe { &mut *(sc_convert_buf(res_arg, res_arg_cageid, cageid) as *mut TimeSpec) };
+
+    let host_clock = !is_deterministic()
+        && clockid >= 0
+        && clockid != CLOCK_PROCESS_CPUTIME_ID
+        && clockid != CLOCK_THREAD_CPUTIME_ID;
+    *res = duration_timespec(Duration::from_nanos(1));
+    if host_clock {
+        let mut ts = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        if unsafe { libc::clock_getres(clockid, &mut ts) } == 0 {
+            res.tv_sec = ts.tv_sec;
+            res.tv_nsec = ts.tv_nsec;
+        }
+    }
+    0
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/clock_settime.2.html
+///
+/// Sets `CLOCK_REALTIME` of a cage. The host's clock is left alone: the cage's clock is moved by
+/// an offset from it instead (`Cage::realtime_offset`), which the other realtime clocks of the
+/// cage follow as well, and which its children inherit.
+///
+/// Setting the clock is privileged. The cage whose clock is set is the one the arguments belong
+/// to (`clockid_arg_cageid`), and the caller has to be the grate that interposes on
+/// `clock_settime` for that cage. Anyone else, the cage itself included, gets `EPERM` like an
+/// unprivileged process on Linux.
+///
+/// ## Arguments
+/// * `clockid_arg` – the clock, which has to be `CLOCK_REALTIME`.
+/// * `tp_arg` – pointer to the new time, a `timespec` in the host layout.
+///
+/// ## Returns
+/// * `0` on success.
+/// * `-EFAULT` if `tp_arg` is NULL, `-EINVAL` for another clock or an invalid time, `-EPERM` if
+///   the caller may not set the clock of the cage.
+pub extern "C" fn clock_settime_syscall(
+    cageid: u64,
+    clockid_arg: u64,
+    clockid_arg_cageid: u64,
+    tp_arg: u64,
+    tp_arg_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let clockid = sc_convert_sysarg_to_i32(clockid_arg, clockid_arg_cageid, cageid);
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "clock_settime_syscall"
+        );
+    }
+
+    if sc_convert_arg_nullity(tp_arg, tp_arg_cageid, cageid) {
+        return syscall_error(Errno::EFAULT, "clock_settime", "tp is NULL");
+    }
+    let tp = unsafe { &*(sc_convert_buf(tp_arg, tp_arg_cageid, cageid) as *const TimeSpec) };
+    if clockid != CLOCK_REALTIME {
+        return syscall_error(Errno::EINVAL, "clock_settime", "clock cannot be set");
+    }
+    let Ok(time) = timespec_duration(tp) else {
+        return syscall_error(Errno::EINVAL, "clock_settime", "invalid time");
+    };
+
+    let targetcageid = clockid_arg_cageid;
+    if targetcageid == cageid
+        || !threei::handler_table::_check_grate_handles_syscall(
+            targetcageid,
+            CLOCK_SETTIME_SYSCALL,
+            cageid,
+        )
+    {
+        return syscall_error(
+            Errno::EPERM,
+            "clock_settime",
+            "only a grate interposing on clock_settime can set the clock",
+        );
+    }
+    let Some(targetcage) = get_cage(targetcageid) else {
+        return syscall_error(Errno::ESRCH, "clock_settime", "no such cage");
+    };
+
+    let host = virtual_clock(CLOCK_REALTIME)
+        .or_else(|| clock_read(CLOCK_REALTIME))
+        .unwrap_or_default();
+    let offset = (time.as_nanos() as i128 - host.as_nanos() as i128)
+        .clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+    targetcage.realtime_offset.store(offset, Relaxed);
+    0
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/gettimeofday.2.html
+///
+/// Reads `CLOCK_REALTIME` of the cage, see `clock_gettime_syscall`, in microseconds. The
+/// timezone is obsolete and always reads as UTC.
+///
+/// ## Arguments
+/// * `tv_arg` – pointer to the `timeval` that receives the time, in the host layout, or NULL.
+/// * `tz_arg` – pointer to the `struct timezone` that is zeroed, or NULL.
+///
+/// ## Returns
+/// * `0` on success.
+pub extern "C" fn gettimeofday_syscall(
+    cageid: u64,
+    tv_arg: u64,
+    tv_arg_cageid: u64,
+    tz_arg: u64,
+    tz_arg_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "gettimeofday_syscall"
+        );
+    }
+
+    if !sc_convert_arg_nullity(tv_arg, tv_arg_cageid, cageid) {
+        let tv = unsafe { &mut *(sc_convert_buf(tv_arg, tv_arg_cageid, cageid) as *mut TimeVal) };
+        let now = cage_clock_now(cageid, CLOCK_REALTIME).unwrap_or_default();
+        tv.tv_sec = now.as_secs() as i64;
+        tv.tv_usec = now.subsec_micros() as i64;
+    }
+    if !sc_convert_arg_nullity(tz_arg, tz_arg_cageid, cageid) {
+        // struct timezone: tz_minuteswest and tz_dsttime
+        let tz = sc_convert_buf(tz_arg, tz_arg_cageid, cageid) as *mut [i32; 2];
+        unsafe { *tz = [0, 0] };
+    }
+    0
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/time.2.html
+///
+/// Reads `CLOCK_REALTIME` of the cage, see `clock_gettime_syscall`, in seconds. The return value
+/// of a syscall is only 32 bits wide, so glibc reads the time from `tloc_arg`, which holds all of
+/// it.
+///
+/// ## Arguments
+/// * `tloc_arg` – pointer to the 64-bit `time_t` that receives the time, or NULL.
+///
+/// ## Returns
+/// * the time in seconds, truncated to 32 bits.
+pub extern "C" fn time_syscall(
+    cageid: u64,
+    tloc_arg: u64,
+    tloc_arg_cageid: u64,
+    arg2: u64,
+    arg2_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    if !(sc_unusedarg(arg2, arg2_cageid)
+        && sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "time_syscall"
+        );
+    }
+
+    let now = cage_clock_now(cageid, CLOCK_REALTIME)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    if !sc_convert_arg_nullity(tloc_arg, tloc_arg_cageid, cageid) {
+        let tloc = sc_convert_buf(tloc_arg, tloc_arg_cageid, cageid) as *mut i64;
+        unsafe { *tloc = now };
+    }
+    now as i32
+}
+
+/// `time` in clock ticks of `times()`
+fn duration_ticks(time: Duration) -> i64 {
+    (time.as_nanos() * CLK_TCK / 1_000_000_000) as i64
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/times.2.html
+///
+/// Reports the CPU time of the cage, see `cage::cputime`, and of the children it waited for, in
+/// clock ticks (`CLK_TCK` per second).
+///
+/// ## Arguments
+/// * `buf_arg` – pointer to the `tms` that receives the times, in the host layout, or NULL.
+///
+/// ## Returns
+/// * the monotonic clock of the cage in clock ticks, truncated to 32 bits, which glibc hands on
+///   as is. Only the difference between two calls means anything, like on Linux.
+pub extern "C" fn times_syscall(
+    cageid: u64,
+    buf_arg: u64,
+    buf_arg_cageid: u64,
+    arg2: u64,
+    arg2_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    if !(sc_unusedarg(arg2, arg2_cageid)
+        && sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "times_syscall"
+        );
+    }
+
+    let cage = get_cage(cageid).unwrap();
+    if !sc_convert_arg_nullity(buf_arg, buf_arg_cageid, cageid) {
+        let buf = unsafe { &mut *(sc_convert_buf(buf_arg, buf_arg_cageid, cageid) as *mut Tms) };
+        let own = cage.cpu.times();
+        let children = cage.cpu.children();
+        *buf = Tms {
+            tms_utime: duration_ticks(own.user),
+            tms_stime: duration_ticks(own.system),
+            tms_cutime: duration_ticks(children.user),
+            tms_cstime: duration_ticks(children.system),
+        };
+    }
+    let uptime = cage_clock_now(cageid, CLOCK_MONOTONIC).unwrap_or_default();
+    duration_ticks(uptime) as i32
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/getrusage.2.html
+///
+/// Reports the resource usage of the cage (`RUSAGE_SELF`), of the calling thread
+/// (`RUSAGE_THREAD`), or of the children the cage waited for and of theirs
+/// (`RUSAGE_CHILDREN`). Like for `wait4()`, only the CPU time and the peak resident set size
+/// are tracked, the latter for the cage itself only; all other fields are zeroed.
+///
+/// ## Arguments
+/// * `who_arg` – `RUSAGE_SELF`, `RUSAGE_CHILDREN` or `RUSAGE_THREAD`.
+/// * `usage_arg` – pointer to the `rusage` that receives the usage, in the host layout.
+///
+/// ## Returns
+/// * `0` on success.
+/// * `-EINVAL` for another `who_arg`, `-EFAULT` if `usage_arg` is NULL.
+pub extern "C" fn getrusage_syscall(
+    cageid: u64,
+    who_arg: u64,
+    who_arg_cageid: u64,
+    usage_arg: u64,
+    usage_arg_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let who = sc_convert_sysarg_to_i32(who_arg, who_arg_cageid, cageid);
+    let usage = sc_convert_addr_to_rusage(usage_arg, usage_arg_cageid, cageid);
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "getrusage_syscall"
+        );
+    }
+
+    let cage = get_cage(cageid).unwrap();
+    let (cpu, maxrss) = match who {
+        RUSAGE_SELF => {
+            let mut vmmap = cage.vmmap.write();
+            vmmap.sample_resident_pages();
+            let maxrss = (vmmap.peak_resident_pages << PAGESHIFT) as i64 / 1024;
+            (cage.cpu.times(), maxrss)
+        }
+        RUSAGE_THREAD => (cage.cpu.thread_times(), 0),
+        RUSAGE_CHILDREN => (cage.cpu.children(), 0),
+        _ => return syscall_error(Errno::EINVAL, "getrusage", "invalid who"),
+    };
+    let Some(usage) = usage else {
+        return syscall_error(Errno::EFAULT, "getrusage", "usage is NULL");
+    };
+    *usage = unsafe { std::mem::zeroed() };
+    usage.ru_utime = duration_timeval(cpu.user);
+    usage.ru_stime = duration_timeval(cpu.system);
+    usage.ru_maxrss = maxrss;
+    0
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/uname.2.html
+///
+/// Implements `uname`. The names come from the cage's own UTS record (`Cage::uts`), which lind-boot
+/// configures for the init cage and `fork` copies into children, so the guest never sees the host's
+/// identity. `struct utsname` is made of char arrays only and has the same layout in the guest.
+///
+/// ## Arguments
+/// - buf_arg: pointer to the guest's `struct utsname`
+///
+/// ## Returns
+/// - 0 on success
+/// - `-EFAULT` if `buf_arg` is NULL
+pub extern "C" fn uname_syscall(
+    cageid: u64,
+    buf_arg: u64,
+    buf_cageid: u64,
+    arg2: u64,
+    arg2_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    // would check when `secure` flag has been set during compilation,
+    // no-op by default
+    if !(sc_unusedarg(arg2, arg2_cageid)
+        && sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "uname_syscall"
+        );
+    }
+
+    if sc_convert_arg_nullity(buf_arg, buf_cageid, cageid) {
+        return syscall_error(Errno::EFAULT, "uname", "buf is null");
+    }
+
+    let cage = get_cage(cageid).unwrap();
+    let uts = *cage.uts.read();
+    unsafe { (buf_arg as *mut UtsNameStruct).write_unaligned(uts) };
+    0
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/sethostname.2.html
+///
+/// Implements `sethostname`. Only the calling cage's UTS record changes; cages forked from it
+/// afterwards inherit the new name.
+///
+/// ## Arguments
+/// - name_arg: pointer to the new name, not necessarily NUL-terminated
+/// - len_arg: length of the name, at most `HOST_NAME_MAX`
+///
+/// ## Returns
+/// - 0 on success
+/// - `-EINVAL` if the name is too long, `-EFAULT` if `name_arg` is NULL
+pub extern "C" fn sethostname_syscall(
+    cageid: u64,
+    name_arg: u64,
+    name_cageid: u64,
+    len_arg: u64,
+    len_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    // would check when `secure` flag has been set during compilation,
+    // no-op by default
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "sethostname_syscall"
+        );
+    }
+
+    set_uts_name(
+        cageid,
+        name_arg,
+        name_cageid,
+        len_arg,
+        len_cageid,
+        "sethostname",
+        |uts| &mut uts.nodename,
+    )
+}
+
+/// Reference to Linux: https://man7.org/linux/man-pages/man2/setdomainname.2.html
+///
+/// Implements `setdomainname`, the NIS domain name counterpart of `sethostname_syscall`.
+///
+/// ## Arguments
+/// - name_arg: pointer to the new name, not necessarily NUL-terminated
+/// - len_arg: length of the name, at most `HOST_NAME_MAX`
+///
+/// ## Returns
+/// - 0 on success
+/// - `-EINVAL` if the name is too long, `-EFAULT` if `name_arg` is NULL
+pub extern "C" fn setdomainname_syscall(
+    cageid: u64,
+    name_arg: u64,
+    name_cageid: u64,
+    len_arg: u64,
+    len_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    // would check when `secure` flag has been set during compilation,
+    // no-op by default
+    if !(sc_unusedarg(arg3, arg3_cageid)
+        && sc_unusedarg(arg4, arg4_cageid)
+        && sc_unusedarg(arg5, arg5_cageid)
+        && sc_unusedarg(arg6, arg6_cageid))
+    {
+        panic!(
+            "{}: unused arguments contain unexpected values -- security violation",
+            "setdomainname_syscall"
+        );
+    }
+
+    set_uts_name(
+        cageid,
+        name_arg,
+        name_cageid,
+        len_arg,
+        len_cageid,
+        "setdomainname",
+        |uts| &mut uts.domainname,
+    )
+}
+
+/// Store the guest's `len_arg` bytes at `name_arg` into the field of the cage's UTS record
+/// selected by `field`
+fn set_uts_name(
+    cageid: u64,
+    name_arg: u64,
+    name_cageid: u64,
+    len_arg: u64,
+    len_cageid: u64,
+    syscall: &str,
+    field: fn(&mut UtsNameStruct) -> &mut [u8; UTSNAME_LENGTH],
+) -> i32 {
+    let len = sc_convert_sysarg_to_usize(len_arg, len_cageid, cageid);
+    if len > HOST_NAME_MAX {
+        return syscall_error(Errno::EINVAL, syscall, "name too long");
+    }
+    if len > 0 && sc_convert_arg_nullity(name_arg, name_cageid, cageid) {
+        return syscall_error(Errno::EFAULT, syscall, "name is null");
+    }
+    let name = if len == 0 {
+        &[][..]
+    } else {
+        unsafe { std::slice::from_raw_parts(name_arg as *const u8, len) }
+    };
+
+    let cage = get_cage(cageid).unwrap();
+    let mut uts = cage.uts.write();
+    match uts_set_field(field(&mut uts), name) {
+        Ok(()) => 0,
+        Err(e) => syscall_error(e, syscall, "invalid name"),
+    }
+}
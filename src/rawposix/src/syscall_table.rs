@@ -5,28 +5,37 @@
 //! Keep these in sync with glibc's lind_syscall_num.h
 use super::fs_calls::{
     access_syscall, brk_syscall, chdir_syscall, chmod_syscall, clock_gettime_syscall,
-    close_syscall, dup2_syscall, dup3_syscall, dup_syscall, fchdir_syscall, fchmod_syscall,
-    fcntl_syscall, fdatasync_syscall, flock_syscall, fstat_syscall, fstatfs_syscall, fsync_syscall,
+    clock_nanosleep_syscall, close_syscall, copy_file_range_syscall, dup2_syscall, dup3_syscall,
+    dup_syscall, fallocate_syscall, fchdir_syscall, fchmod_syscall, fcntl_syscall,
+    fdatasync_syscall, flock_syscall, fstat_syscall, fstatfs_syscall, fsync_syscall,
     ftruncate_syscall, futex_syscall, getcwd_syscall, getdents_syscall, getrandom_syscall,
-    ioctl_syscall, link_syscall, lseek_syscall, mkdir_syscall, mmap_syscall, mprotect_syscall,
-    munmap_syscall, nanosleep_time64_syscall, open_syscall, pipe2_syscall, pipe_syscall,
-    pread_syscall, pwrite_syscall, read_syscall, readlink_syscall, readlinkat_syscall,
-    rename_syscall, rmdir_syscall, shmat_syscall, shmctl_syscall, shmdt_syscall, shmget_syscall,
-    stat_syscall, statfs_syscall, sync_file_range_syscall, truncate_syscall, unlink_syscall,
-    unlinkat_syscall, write_syscall, writev_syscall,
+    ioctl_syscall, link_syscall, lseek_syscall, madvise_syscall, mincore_syscall, mkdir_syscall,
+    mmap_syscall, mprotect_syscall, mremap_syscall, msync_syscall, munmap_syscall, open_syscall,
+    pipe2_syscall, pipe_syscall, pread_syscall, preadv_syscall, pwrite_syscall, pwritev_syscall,
+    read_syscall, readlink_syscall, readlinkat_syscall, readv_syscall, rename_syscall,
+    rmdir_syscall, sendfile_syscall, shmat_syscall, shmctl_syscall, shmdt_syscall, shmget_syscall,
+    splice_syscall, stat_syscall, statfs_syscall, symlink_syscall, sync_file_range_syscall,
+    tee_syscall, truncate_syscall, unlink_syscall, unlinkat_syscall, write_syscall, writev_syscall,
 };
 use super::init::RawCallFunc;
 use super::net_calls::{
-    accept_syscall, bind_syscall, connect_syscall, epoll_create1_syscall, epoll_create_syscall,
-    epoll_ctl_syscall, epoll_wait_syscall, gethostname_syscall, getpeername_syscall,
-    getsockname_syscall, getsockopt_syscall, listen_syscall, poll_syscall, recvfrom_syscall,
-    recvmsg_syscall, select_syscall, sendto_syscall, setsockopt_syscall, shutdown_syscall,
-    socket_syscall, socketpair_syscall,
+    accept4_syscall, accept_syscall, bind_syscall, connect_syscall, epoll_create1_syscall,
+    epoll_create_syscall, epoll_ctl_syscall, epoll_pwait2_syscall, epoll_pwait_syscall,
+    epoll_wait_syscall, gethostname_syscall, getpeername_syscall, getsockname_syscall,
+    getsockopt_syscall, listen_syscall, poll_syscall, ppoll_syscall, pselect6_syscall,
+    recvfrom_syscall, recvmmsg_syscall, recvmsg_syscall, select_syscall, sendmmsg_syscall,
+    sendmsg_syscall, sendto_syscall, setsockopt_syscall, shutdown_syscall, socket_syscall,
+    socketpair_syscall,
 };
 use super::sys_calls::{
-    exec_syscall, exit_syscall, fork_syscall, getegid_syscall, geteuid_syscall, getgid_syscall,
-    getpid_syscall, getppid_syscall, getuid_syscall, kill_syscall, sched_yield_syscall,
-    setitimer_syscall, sigaction_syscall, sigprocmask_syscall, waitpid_syscall,
+    alarm_syscall, clock_getres_syscall, clock_settime_syscall, exec_syscall, exit_syscall,
+    fork_syscall, getegid_syscall, geteuid_syscall, getgid_syscall, getitimer_syscall,
+    getpid_syscall, getppid_syscall, getrusage_syscall, gettid_syscall, gettimeofday_syscall,
+    getuid_syscall, kill_syscall, sched_yield_syscall, setdomainname_syscall, sethostname_syscall,
+    setitimer_syscall, sigaction_syscall, sigaltstack_syscall, sigprocmask_syscall, tgkill_syscall,
+    time_syscall, timer_create_syscall, timer_delete_syscall, timer_getoverrun_syscall,
+    timer_gettime_syscall, timer_settime_syscall, times_syscall, tkill_syscall, uname_syscall,
+    waitpid_syscall,
 };
 
 pub const SYSCALL_TABLE: &[(u64, RawCallFunc)] = &[
@@ -47,24 +56,33 @@ pub const SYSCALL_TABLE: &[(u64, RawCallFunc)] = &[
     (16, ioctl_syscall),
     (17, pread_syscall),
     (18, pwrite_syscall),
+    (19, readv_syscall),
     (20, writev_syscall),
     (21, access_syscall),
     (22, pipe_syscall),
     (23, select_syscall),
     (24, sched_yield_syscall),
+    (25, mremap_syscall),
+    (26, msync_syscall),
+    (27, mincore_syscall),
+    (28, madvise_syscall),
     (29, shmget_syscall),
     (30, shmat_syscall),
     (31, shmctl_syscall),
     (32, dup_syscall),
     (33, dup2_syscall),
-    (35, nanosleep_time64_syscall),
+    (35, clock_nanosleep_syscall),
+    (36, getitimer_syscall),
+    (37, alarm_syscall),
     (38, setitimer_syscall),
     (39, getpid_syscall),
+    (40, sendfile_syscall),
     (41, socket_syscall),
     (42, connect_syscall),
     (43, accept_syscall),
     (44, sendto_syscall),
     (45, recvfrom_syscall),
+    (46, sendmsg_syscall),
     (47, recvmsg_syscall),
     (48, shutdown_syscall),
     (49, bind_syscall),
@@ -80,6 +98,7 @@ pub const SYSCALL_TABLE: &[(u64, RawCallFunc)] = &[
     (60, exit_syscall),
     (61, waitpid_syscall),
     (62, kill_syscall),
+    (63, uname_syscall),
     (67, shmdt_syscall),
     (72, fcntl_syscall),
     (73, flock_syscall),
@@ -96,27 +115,60 @@ pub const SYSCALL_TABLE: &[(u64, RawCallFunc)] = &[
     (84, rmdir_syscall),
     (86, link_syscall),
     (87, unlink_syscall),
+    (88, symlink_syscall),
     (89, readlink_syscall),
     (90, chmod_syscall),
     (91, fchmod_syscall),
+    (96, gettimeofday_syscall),
+    (98, getrusage_syscall),
+    (100, times_syscall),
     (102, getuid_syscall),
     (104, getgid_syscall),
     (107, geteuid_syscall),
     (108, getegid_syscall),
     (110, getppid_syscall),
+    (131, sigaltstack_syscall),
     (137, statfs_syscall),
     (138, fstatfs_syscall),
     (170, gethostname_syscall),
+    (171, setdomainname_syscall),
+    (186, gettid_syscall),
+    (200, tkill_syscall),
+    (201, time_syscall),
     (202, futex_syscall),
     (213, epoll_create_syscall),
+    (222, timer_create_syscall),
+    (223, timer_settime_syscall),
+    (224, timer_gettime_syscall),
+    (225, timer_getoverrun_syscall),
+    (226, timer_delete_syscall),
+    (227, clock_settime_syscall),
     (228, clock_gettime_syscall),
+    (229, clock_getres_syscall),
+    (230, clock_nanosleep_syscall),
     (232, epoll_wait_syscall),
     (233, epoll_ctl_syscall),
+    (234, tgkill_syscall),
     (263, unlinkat_syscall),
     (267, readlinkat_syscall),
+    (270, pselect6_syscall),
+    (271, ppoll_syscall),
+    (275, splice_syscall),
+    (276, tee_syscall),
     (277, sync_file_range_syscall),
+    (281, epoll_pwait_syscall),
+    (285, fallocate_syscall),
+    (288, accept4_syscall),
     (291, epoll_create1_syscall),
     (292, dup3_syscall),
     (293, pipe2_syscall),
+    (295, preadv_syscall),
+    (296, pwritev_syscall),
+    (299, recvmmsg_syscall),
+    (307, sendmmsg_syscall),
     (318, getrandom_syscall),
+    (326, copy_file_range_syscall),
+    (441, epoll_pwait2_syscall),
+    // Linux's sethostname number (170) is taken by gethostname, which has none on Linux
+    (1004, sethostname_syscall),
 ];
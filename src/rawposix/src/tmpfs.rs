@@ -0,0 +1,1180 @@
+//! In-memory tmpfs
+//!
+//! Test suites tend to create lots of short-lived files under `/tmp`. Routing those through the
+//! host-backed lindfs is slow and leaves garbage behind whenever a run crashes, so RawPOSIX can
+//! mount purely in-memory filesystems at chosen guest paths (see `tmpfs_mount`, wired to the
+//! `--tmpfs` option of lind-boot).
+//!
+//! Every path-based syscall in `fs_calls` asks `tmpfs_for_path` whether the normalized guest path
+//! lives on a mount and, if so, hands the call to the matching `Tmpfs` method instead of the host.
+//! Opening a tmpfs file creates a `TmpfsHandle` (the open file description, holding the offset
+//! and status flags) and a virtual fd of kind `FDKIND_TMPFS` whose `underfd` is the handle id.
+//! fd-based syscalls look the handle up with `tmpfs_fd_handle`.
+//!
+//! Supported objects are regular files, directories, symbolic links and hard links, with
+//! permission bits and timestamps. All cages run as `DEFAULT_UID`, so permission checks use the
+//! owner bits. Symlinks are resolved inside the mount; an absolute target that points outside of
+//! it is reported as `ENOENT`. An optional quota limits the bytes held by file contents and link
+//! targets, and operations that would exceed it fail with `ENOSPC`.
+use crate::epoll::epoll_forget;
+use crate::sigio::sigio_forget;
+use dashmap::DashMap;
+use fdtables;
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use sysdefs::constants::err_const::{syscall_error, Errno};
+use sysdefs::constants::fs_const::{
+    FIONBIO, F_GETFL, F_OK, F_SETFL, O_ACCMODE, O_APPEND, O_CLOEXEC, O_CREAT, O_EXCL, O_NONBLOCK,
+    O_RDONLY, O_RDWR, O_TRUNC, O_WRONLY, R_OK, SEEK_CUR, SEEK_END, SEEK_SET, S_IFDIR, S_IFLNK,
+    S_IFREG, W_OK, X_OK,
+};
+use sysdefs::constants::lind_platform_const::FDKIND_TMPFS;
+use sysdefs::constants::sys_const::{DEFAULT_GID, DEFAULT_UID};
+use sysdefs::data::fs_struct::StatData;
+use typemap::filesystem_helpers::{dirent64_reclen, write_dirent64};
+
+/// Inode number of the root directory of every mount
+const TMPFS_ROOT_INO: u64 = 1;
+/// `f_type` reported by statfs, same as Linux's TMPFS_MAGIC
+const TMPFS_MAGIC: u64 = 0x0102_1994;
+/// Block size reported by stat/statfs
+const TMPFS_BLKSIZE: u64 = 4096;
+/// Maximum number of symlinks followed during one lookup (Linux uses 40 as well)
+const TMPFS_MAXSYMLINKS: usize = 40;
+/// Maximum length of a single path component
+const TMPFS_NAME_MAX: usize = 255;
+
+lazy_static! {
+    // Mounted tmpfs instances. Mounting happens once at boot, lookups happen on every
+    // path-based syscall, hence the RwLock.
+    static ref TMPFS_MOUNTS: RwLock<Vec<Arc<Tmpfs>>> = RwLock::new(Vec::new());
+    // Open file descriptions, keyed by the `underfd` of the FDKIND_TMPFS fds referring to them
+    static ref TMPFS_HANDLES: DashMap<u64, Arc<TmpfsHandle>> = DashMap::new();
+}
+
+static NEXT_HANDLE_ID: AtomicU64 = AtomicU64::new(0);
+
+fn now() -> (u64, u64) {
+    let t = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    (t.as_secs(), t.subsec_nanos() as u64)
+}
+
+fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|c| !c.is_empty())
+}
+
+enum InodeData {
+    File(Vec<u8>),
+    Dir {
+        entries: BTreeMap<String, u64>,
+        parent: u64,
+    },
+    Symlink(String),
+}
+
+struct Inode {
+    data: InodeData,
+    mode: u32, // permission bits only, the type comes from `data`
+    uid: u32,
+    gid: u32,
+    nlink: u32,
+    open_count: u32, // number of TmpfsHandle referring to this inode
+    atime: (u64, u64),
+    mtime: (u64, u64),
+    ctime: (u64, u64),
+}
+
+impl Inode {
+    fn new(data: InodeData, mode: u32) -> Self {
+        let t = now();
+        let nlink = match data {
+            InodeData::Dir { .. } => 2,
+            _ => 1,
+        };
+        Inode {
+            data,
+            mode: mode & 0o7777,
+            uid: DEFAULT_UID,
+            gid: DEFAULT_GID,
+            nlink,
+            open_count: 0,
+            atime: t,
+            mtime: t,
+            ctime: t,
+        }
+    }
+
+    fn type_bits(&self) -> u32 {
+        match self.data {
+            InodeData::File(_) => S_IFREG as u32,
+            InodeData::Dir { .. } => S_IFDIR as u32,
+            InodeData::Symlink(_) => S_IFLNK as u32,
+        }
+    }
+
+    fn size(&self) -> usize {
+        match &self.data {
+            InodeData::File(data) => data.len(),
+            InodeData::Dir { entries, .. } => entries.len() * 32,
+            InodeData::Symlink(target) => target.len(),
+        }
+    }
+
+    // bytes charged against the mount quota
+    fn charged_bytes(&self) -> usize {
+        match &self.data {
+            InodeData::File(data) => data.len(),
+            InodeData::Symlink(target) => target.len(),
+            InodeData::Dir { .. } => 0,
+        }
+    }
+
+    fn is_dir(&self) -> bool {
+        matches!(self.data, InodeData::Dir { .. })
+    }
+
+    // all cages run as DEFAULT_UID, so only the owner bits matter
+    fn permits(&self, amode: u32) -> bool {
+        let owner = (self.mode >> 6) & 0o7;
+        amode & !owner == 0
+    }
+
+    fn touch_modified(&mut self) {
+        let t = now();
+        self.mtime = t;
+        self.ctime = t;
+    }
+}
+
+struct TmpfsInner {
+    inodes: HashMap<u64, Inode>,
+    next_ino: u64,
+    used_bytes: usize,
+}
+
+/// One mounted in-memory filesystem
+pub struct Tmpfs {
+    mountpoint: String, // normalized guest path without trailing '/'
+    devid: u64,         // st_dev reported for every inode of this mount
+    quota: Option<usize>,
+    inner: Mutex<TmpfsInner>,
+}
+
+/// An open file description on a tmpfs mount
+pub struct TmpfsHandle {
+    fs: Arc<Tmpfs>,
+    ino: u64,
+    path: String,     // guest path at open time, used for *at() lookups and fchdir
+    flags: AtomicI32, // file status flags and access mode
+    offset: Mutex<u64>,
+}
+
+/// Mount a new, empty tmpfs at `mountpoint` (an absolute guest path). `quota` is the
+/// maximum number of bytes the mount may hold, or `None` for no limit.
+///
+/// ## Returns:
+/// - `Ok(())` on success
+/// - `Err(EINVAL)` if `mountpoint` is not absolute, `Err(EBUSY)` if it is already mounted
+pub fn tmpfs_mount(mountpoint: &str, quota: Option<usize>) -> Result<(), Errno> {
+    if !mountpoint.starts_with('/') {
+        return Err(Errno::EINVAL);
+    }
+    let normalized = format!("/{}", components(mountpoint).collect::<Vec<_>>().join("/"));
+
+    let mut mounts = TMPFS_MOUNTS.write();
+    if mounts.iter().any(|m| m.mountpoint == normalized) {
+        return Err(Errno::EBUSY);
+    }
+
+    let mut inodes = HashMap::new();
+    inodes.insert(
+        TMPFS_ROOT_INO,
+        Inode::new(
+            InodeData::Dir {
+                entries: BTreeMap::new(),
+                parent: TMPFS_ROOT_INO,
+            },
+            0o1777,
+        ),
+    );
+    let devid = 0x100 + mounts.len() as u64;
+    mounts.push(Arc::new(Tmpfs {
+        mountpoint: normalized,
+        devid,
+        quota,
+        inner: Mutex::new(TmpfsInner {
+            inodes,
+            next_ino: TMPFS_ROOT_INO + 1,
+            used_bytes: 0,
+        }),
+    }));
+    Ok(())
+}
+
+/// Find the mount that a normalized absolute guest path lives on, if any. When mounts are
+/// nested the innermost one wins.
+pub fn tmpfs_for_path(path: &[u8]) -> Option<Arc<Tmpfs>> {
+    let mounts = TMPFS_MOUNTS.read();
+    if mounts.is_empty() {
+        return None;
+    }
+    let path = std::str::from_utf8(path).ok()?;
+    mounts
+        .iter()
+        .filter(|m| {
+            m.mountpoint == "/"
+                || path == m.mountpoint
+                || (path.starts_with(&m.mountpoint) && path.as_bytes()[m.mountpoint.len()] == b'/')
+        })
+        .max_by_key(|m| m.mountpoint.len())
+        .cloned()
+}
+
+/// Find the mount shared by the two paths of `rename()` / `link()`.
+///
+/// ## Returns:
+/// - `Ok(Some(fs))` if both paths are on the same tmpfs mount
+/// - `Ok(None)` if neither is on a tmpfs mount
+/// - `Err(EXDEV)` if the paths are on different filesystems
+pub fn tmpfs_for_paths(oldpath: &[u8], newpath: &[u8]) -> Result<Option<Arc<Tmpfs>>, Errno> {
+    match (tmpfs_for_path(oldpath), tmpfs_for_path(newpath)) {
+        (None, None) => Ok(None),
+        (Some(a), Some(b)) if Arc::ptr_eq(&a, &b) => Ok(Some(a)),
+        _ => Err(Errno::EXDEV),
+    }
+}
+
+/// Look up the open tmpfs file behind a virtual fd. Returns `None` for fds of any other kind.
+pub fn tmpfs_fd_handle(cageid: u64, vfd: u64) -> Option<Arc<TmpfsHandle>> {
+    match fdtables::translate_virtual_fd(cageid, vfd) {
+        Ok(entry) if entry.fdkind == FDKIND_TMPFS => TMPFS_HANDLES
+            .get(&entry.underfd)
+            .map(|h| Arc::clone(h.value())),
+        _ => None,
+    }
+}
+
+/// Close handler registered for `FDKIND_TMPFS`, called once the last fd referring to an open
+/// file description goes away. Inodes that were unlinked while open are freed here.
+pub fn tmpfs_close(fdentry: fdtables::FDTableEntry, _count: u64) {
+    epoll_forget(FDKIND_TMPFS, fdentry.underfd);
+    sigio_forget(FDKIND_TMPFS, fdentry.underfd);
+    if let Some((_, handle)) = TMPFS_HANDLES.remove(&fdentry.underfd) {
+        let mut inner = handle.fs.inner.lock();
+        if let Some(inode) = inner.inodes.get_mut(&handle.ino) {
+            inode.open_count -= 1;
+        }
+        inner.maybe_free(handle.ino);
+    }
+}
+
+impl TmpfsInner {
+    fn alloc(&mut self, inode: Inode) -> u64 {
+        let ino = self.next_ino;
+        self.next_ino += 1;
+        self.inodes.insert(ino, inode);
+        ino
+    }
+
+    // Account for a change in stored bytes, failing with ENOSPC if it exceeds the quota
+    fn charge(
+        &mut self,
+        quota: Option<usize>,
+        old_len: usize,
+        new_len: usize,
+    ) -> Result<(), Errno> {
+        let used = self.used_bytes - old_len + new_len;
+        if new_len > old_len {
+            if let Some(limit) = quota {
+                if used > limit {
+                    return Err(Errno::ENOSPC);
+                }
+            }
+        }
+        self.used_bytes = used;
+        Ok(())
+    }
+
+    // Drop an inode once it is neither linked nor open
+    fn maybe_free(&mut self, ino: u64) {
+        let unused = match self.inodes.get(&ino) {
+            Some(inode) => inode.nlink == 0 && inode.open_count == 0,
+            None => false,
+        };
+        if unused {
+            let inode = self.inodes.remove(&ino).unwrap();
+            self.used_bytes -= inode.charged_bytes();
+        }
+    }
+
+    fn dir_entries(&self, ino: u64) -> Result<(&BTreeMap<String, u64>, u64), Errno> {
+        match &self.inodes[&ino].data {
+            InodeData::Dir { entries, parent } => Ok((entries, *parent)),
+            _ => Err(Errno::ENOTDIR),
+        }
+    }
+
+    fn dir_entries_mut(&mut self, ino: u64) -> &mut BTreeMap<String, u64> {
+        match &mut self.inodes.get_mut(&ino).unwrap().data {
+            InodeData::Dir { entries, .. } => entries,
+            _ => unreachable!("tmpfs: parent inode is not a directory"),
+        }
+    }
+
+    /// Resolve a path relative to the mount root to an inode. Intermediate symlinks are always
+    /// followed, the last component only if `follow_last` is set.
+    fn walk(&self, rel: &str, mountpoint: &str, follow_last: bool) -> Result<u64, Errno> {
+        let mut pending: VecDeque<String> = components(rel).map(String::from).collect();
+        let mut cur = TMPFS_ROOT_INO;
+        let mut links = 0;
+
+        while let Some(name) = pending.pop_front() {
+            let (entries, parent) = self.dir_entries(cur)?;
+            if !self.inodes[&cur].permits(X_OK) {
+                return Err(Errno::EACCES);
+            }
+            match name.as_str() {
+                "." => continue,
+                ".." => {
+                    cur = parent;
+                    continue;
+                }
+                _ => {}
+            }
+            if name.len() > TMPFS_NAME_MAX {
+                return Err(Errno::ENAMETOOLONG);
+            }
+            let child = *entries.get(&name).ok_or(Errno::ENOENT)?;
+
+            if let InodeData::Symlink(target) = &self.inodes[&child].data {
+                if !pending.is_empty() || follow_last {
+                    links += 1;
+                    if links > TMPFS_MAXSYMLINKS {
+                        return Err(Errno::ELOOP);
+                    }
+                    let target_rel = if target.starts_with('/') {
+                        cur = TMPFS_ROOT_INO;
+                        match target.strip_prefix(mountpoint) {
+                            Some(rest)
+                                if mountpoint == "/"
+                                    || rest.is_empty()
+                                    || rest.starts_with('/') =>
+                            {
+                                rest
+                            }
+                            _ => return Err(Errno::ENOENT),
+                        }
+                    } else {
+                        target.as_str()
+                    };
+                    for comp in components(target_rel).collect::<Vec<_>>().into_iter().rev() {
+                        pending.push_front(comp.to_string());
+                    }
+                    continue;
+                }
+            }
+            cur = child;
+        }
+        Ok(cur)
+    }
+
+    /// Resolve everything but the last component, which is returned as-is. The parent must be
+    /// a searchable directory.
+    fn walk_parent(&self, rel: &str, mountpoint: &str) -> Result<(u64, String), Errno> {
+        let rel = rel.trim_end_matches('/');
+        let (dir, name) = match rel.rfind('/') {
+            Some(idx) => (&rel[..idx], &rel[idx + 1..]),
+            None => ("", rel),
+        };
+        // The mount root itself has no parent on this filesystem
+        if name.is_empty() {
+            return Err(Errno::EBUSY);
+        }
+        if name.len() > TMPFS_NAME_MAX {
+            return Err(Errno::ENAMETOOLONG);
+        }
+        let parent = self.walk(dir, mountpoint, true)?;
+        self.dir_entries(parent)?;
+        Ok((parent, name.to_string()))
+    }
+
+    // The caller needs write and search permission on a directory to change its entries
+    fn check_dir_writable(&self, dir: u64) -> Result<(), Errno> {
+        if self.inodes[&dir].permits(W_OK | X_OK) {
+            Ok(())
+        } else {
+            Err(Errno::EACCES)
+        }
+    }
+
+    // Detach `name` from directory `dir` and drop the link it held
+    fn unlink_entry(&mut self, dir: u64, name: &str) {
+        let ino = self.dir_entries_mut(dir).remove(name).unwrap();
+        let is_dir = self.inodes[&ino].is_dir();
+        {
+            let inode = self.inodes.get_mut(&ino).unwrap();
+            // a directory's own "." link goes away with its name
+            inode.nlink = if is_dir { 0 } else { inode.nlink - 1 };
+            inode.ctime = now();
+        }
+        let parent = self.inodes.get_mut(&dir).unwrap();
+        if is_dir {
+            parent.nlink -= 1;
+        }
+        parent.touch_modified();
+        self.maybe_free(ino);
+    }
+
+    fn fill_stat(&self, ino: u64, devid: u64, statbuf: &mut StatData) {
+        let inode = &self.inodes[&ino];
+        let size = inode.size();
+        *statbuf = StatData::default();
+        statbuf.st_dev = devid;
+        statbuf.st_ino = ino as usize;
+        statbuf.st_mode = inode.type_bits() | inode.mode;
+        statbuf.st_nlink = inode.nlink;
+        statbuf.st_uid = inode.uid;
+        statbuf.st_gid = inode.gid;
+        statbuf.st_size = size;
+        statbuf.st_blksize = TMPFS_BLKSIZE as i32;
+        statbuf.st_blocks = size.div_ceil(512) as u32;
+        statbuf.st_atim = inode.atime;
+        statbuf.st_mtim = inode.mtime;
+        statbuf.st_ctim = inode.ctime;
+    }
+
+    fn truncate(&mut self, ino: u64, quota: Option<usize>, len: usize) -> Result<(), Errno> {
+        let old_len = match &self.inodes[&ino].data {
+            InodeData::File(data) => data.len(),
+            InodeData::Dir { .. } => return Err(Errno::EISDIR),
+            InodeData::Symlink(_) => return Err(Errno::EINVAL),
+        };
+        self.charge(quota, old_len, len)?;
+        let inode = self.inodes.get_mut(&ino).unwrap();
+        if let InodeData::File(data) = &mut inode.data {
+            data.resize(len, 0);
+        }
+        inode.touch_modified();
+        Ok(())
+    }
+
+    fn write_at(
+        &mut self,
+        ino: u64,
+        quota: Option<usize>,
+        offset: usize,
+        src: &[u8],
+    ) -> Result<usize, Errno> {
+        let old_len = match &self.inodes[&ino].data {
+            InodeData::File(data) => data.len(),
+            InodeData::Dir { .. } => return Err(Errno::EISDIR),
+            InodeData::Symlink(_) => return Err(Errno::EINVAL),
+        };
+        let end = offset.checked_add(src.len()).ok_or(Errno::EFBIG)?;
+        if end > old_len {
+            self.charge(quota, old_len, end)?;
+        }
+        let inode = self.inodes.get_mut(&ino).unwrap();
+        if let InodeData::File(data) = &mut inode.data {
+            if end > data.len() {
+                data.resize(end, 0);
+            }
+            data[offset..end].copy_from_slice(src);
+        }
+        if !src.is_empty() {
+            inode.touch_modified();
+        }
+        Ok(src.len())
+    }
+
+    fn read_at(&mut self, ino: u64, offset: usize, dst: &mut [u8]) -> Result<usize, Errno> {
+        let inode = self.inodes.get_mut(&ino).unwrap();
+        let data = match &inode.data {
+            InodeData::File(data) => data,
+            InodeData::Dir { .. } => return Err(Errno::EISDIR),
+            InodeData::Symlink(_) => return Err(Errno::EINVAL),
+        };
+        if offset >= data.len() {
+            return Ok(0);
+        }
+        let n = dst.len().min(data.len() - offset);
+        dst[..n].copy_from_slice(&data[offset..offset + n]);
+        inode.atime = now();
+        Ok(n)
+    }
+}
+
+impl Tmpfs {
+    // Path relative to the mount root
+    fn rel<'a>(&self, path: &'a [u8]) -> &'a str {
+        let path = std::str::from_utf8(path).unwrap_or("");
+        if self.mountpoint == "/" {
+            path
+        } else {
+            &path[self.mountpoint.len().min(path.len())..]
+        }
+    }
+
+    /// `open()` on a tmpfs path. Allocates the open file description and a virtual fd.
+    pub fn open(self: &Arc<Self>, cageid: u64, path: &[u8], oflag: i32, mode: u32) -> i32 {
+        let rel = self.rel(path);
+        let accmode = oflag & O_ACCMODE;
+        let mut inner = self.inner.lock();
+
+        let ino = match inner.walk(rel, &self.mountpoint, oflag & libc::O_NOFOLLOW == 0) {
+            Ok(ino) => {
+                if oflag & O_CREAT != 0 && oflag & O_EXCL != 0 {
+                    return syscall_error(Errno::EEXIST, "open", "File exists");
+                }
+                ino
+            }
+            Err(Errno::ENOENT) if oflag & O_CREAT != 0 => {
+                let (parent, name) = match inner.walk_parent(rel, &self.mountpoint) {
+                    Ok(res) => res,
+                    Err(e) => return syscall_error(e, "open", "cannot resolve parent"),
+                };
+                // the name exists but is a dangling symlink
+                if inner.dir_entries(parent).unwrap().0.contains_key(&name) {
+                    return syscall_error(Errno::ENOENT, "open", "No such file or directory");
+                }
+                if let Err(e) = inner.check_dir_writable(parent) {
+                    return syscall_error(e, "open", "Permission denied");
+                }
+                let ino = inner.alloc(Inode::new(InodeData::File(Vec::new()), mode));
+                inner.dir_entries_mut(parent).insert(name, ino);
+                inner.inodes.get_mut(&parent).unwrap().touch_modified();
+                ino
+            }
+            Err(e) => return syscall_error(e, "open", "cannot resolve path"),
+        };
+
+        let inode = &inner.inodes[&ino];
+        if let InodeData::Symlink(_) = inode.data {
+            return syscall_error(Errno::ELOOP, "open", "Too many symbolic links");
+        }
+        if inode.is_dir() && accmode != O_RDONLY {
+            return syscall_error(Errno::EISDIR, "open", "Is a directory");
+        }
+        if !inode.is_dir() && oflag & libc::O_DIRECTORY != 0 {
+            return syscall_error(Errno::ENOTDIR, "open", "Not a directory");
+        }
+        let wanted = match accmode {
+            O_WRONLY => W_OK,
+            O_RDWR => R_OK | W_OK,
+            _ => R_OK,
+        };
+        if !inode.permits(wanted) {
+            return syscall_error(Errno::EACCES, "open", "Permission denied");
+        }
+        if oflag & O_TRUNC != 0 && accmode != O_RDONLY {
+            if let Err(e) = inner.truncate(ino, self.quota, 0) {
+                return syscall_error(e, "open", "truncate failed");
+            }
+        }
+        inner.inodes.get_mut(&ino).unwrap().open_count += 1;
+        drop(inner);
+
+        let handle_id = NEXT_HANDLE_ID.fetch_add(1, Ordering::Relaxed);
+        TMPFS_HANDLES.insert(
+            handle_id,
+            Arc::new(TmpfsHandle {
+                fs: self.clone(),
+                ino,
+                path: String::from_utf8_lossy(path).into_owned(),
+                flags: AtomicI32::new(oflag & !(O_CREAT | O_EXCL | O_TRUNC | O_CLOEXEC)),
+                offset: Mutex::new(0),
+            }),
+        );
+
+        match fdtables::get_unused_virtual_fd(
+            cageid,
+            FDKIND_TMPFS,
+            handle_id,
+            oflag & O_CLOEXEC != 0,
+            0,
+        ) {
+            Ok(vfd) => vfd as i32,
+            Err(_) => {
+                tmpfs_close(
+                    fdtables::FDTableEntry {
+                        fdkind: FDKIND_TMPFS,
+                        underfd: handle_id,
+                        should_cloexec: false,
+                        perfdinfo: 0,
+                    },
+                    0,
+                );
+                syscall_error(Errno::EMFILE, "open", "Too many files opened")
+            }
+        }
+    }
+
+    /// `stat()` on a tmpfs path (symlinks followed)
+    pub fn stat(&self, path: &[u8], statbuf: &mut StatData) -> i32 {
+        let inner = self.inner.lock();
+        match inner.walk(self.rel(path), &self.mountpoint, true) {
+            Ok(ino) => {
+                inner.fill_stat(ino, self.devid, statbuf);
+                0
+            }
+            Err(e) => syscall_error(e, "stat", "cannot resolve path"),
+        }
+    }
+
+    /// `access()` on a tmpfs path
+    pub fn access(&self, path: &[u8], amode: i32) -> i32 {
+        let inner = self.inner.lock();
+        let ino = match inner.walk(self.rel(path), &self.mountpoint, true) {
+            Ok(ino) => ino,
+            Err(e) => return syscall_error(e, "access", "cannot resolve path"),
+        };
+        if amode as u32 != F_OK && !inner.inodes[&ino].permits(amode as u32) {
+            return syscall_error(Errno::EACCES, "access", "Permission denied");
+        }
+        0
+    }
+
+    /// `mkdir()` on a tmpfs path
+    pub fn mkdir(&self, path: &[u8], mode: u32) -> i32 {
+        let rel = self.rel(path);
+        let mut inner = self.inner.lock();
+        let (parent, name) = match inner.walk_parent(rel, &self.mountpoint) {
+            Ok(res) => res,
+            // mkdir on the mount point itself
+            Err(Errno::EBUSY) => return syscall_error(Errno::EEXIST, "mkdir", "File exists"),
+            Err(e) => return syscall_error(e, "mkdir", "cannot resolve parent"),
+        };
+        if inner.dir_entries(parent).unwrap().0.contains_key(&name) {
+            return syscall_error(Errno::EEXIST, "mkdir", "File exists");
+        }
+        if let Err(e) = inner.check_dir_writable(parent) {
+            return syscall_error(e, "mkdir", "Permission denied");
+        }
+        let ino = inner.alloc(Inode::new(
+            InodeData::Dir {
+                entries: BTreeMap::new(),
+                parent,
+            },
+            mode,
+        ));
+        inner.dir_entries_mut(parent).insert(name, ino);
+        let parent_inode = inner.inodes.get_mut(&parent).unwrap();
+        parent_inode.nlink += 1;
+        parent_inode.touch_modified();
+        0
+    }
+
+    /// `rmdir()` on a tmpfs path
+    pub fn rmdir(&self, path: &[u8]) -> i32 {
+        let rel = self.rel(path);
+        let mut inner = self.inner.lock();
+        let (parent, name) = match inner.walk_parent(rel, &self.mountpoint) {
+            Ok(res) => res,
+            Err(e) => return syscall_error(e, "rmdir", "cannot resolve parent"),
+        };
+        let ino = match inner.dir_entries(parent).unwrap().0.get(&name) {
+            Some(&ino) => ino,
+            None => return syscall_error(Errno::ENOENT, "rmdir", "No such file or directory"),
+        };
+        match inner.dir_entries(ino) {
+            Ok((entries, _)) if !entries.is_empty() => {
+                return syscall_error(Errno::ENOTEMPTY, "rmdir", "Directory not empty")
+            }
+            Ok(_) => {}
+            Err(e) => return syscall_error(e, "rmdir", "Not a directory"),
+        }
+        if let Err(e) = inner.check_dir_writable(parent) {
+            return syscall_error(e, "rmdir", "Permission denied");
+        }
+        inner.unlink_entry(parent, &name);
+        0
+    }
+
+    /// `unlink()` on a tmpfs path. Open files stay readable until their last fd is closed.
+    pub fn unlink(&self, path: &[u8]) -> i32 {
+        let rel = self.rel(path);
+        let mut inner = self.inner.lock();
+        let (parent, name) = match inner.walk_parent(rel, &self.mountpoint) {
+            Ok(res) => res,
+            Err(e) => return syscall_error(e, "unlink", "cannot resolve parent"),
+        };
+        let ino = match inner.dir_entries(parent).unwrap().0.get(&name) {
+            Some(&ino) => ino,
+            None => return syscall_error(Errno::ENOENT, "unlink", "No such file or directory"),
+        };
+        if inner.inodes[&ino].is_dir() {
+            return syscall_error(Errno::EISDIR, "unlink", "Is a directory");
+        }
+        if let Err(e) = inner.check_dir_writable(parent) {
+            return syscall_error(e, "unlink", "Permission denied");
+        }
+        inner.unlink_entry(parent, &name);
+        0
+    }
+
+    /// `rename()` where both paths are on this mount
+    pub fn rename(&self, oldpath: &[u8], newpath: &[u8]) -> i32 {
+        let (oldrel, newrel) = (self.rel(oldpath), self.rel(newpath));
+        let mut inner = self.inner.lock();
+        let (oldparent, oldname) = match inner.walk_parent(oldrel, &self.mountpoint) {
+            Ok(res) => res,
+            Err(e) => return syscall_error(e, "rename", "cannot resolve old parent"),
+        };
+        let (newparent, newname) = match inner.walk_parent(newrel, &self.mountpoint) {
+            Ok(res) => res,
+            Err(e) => return syscall_error(e, "rename", "cannot resolve new parent"),
+        };
+        let ino = match inner.dir_entries(oldparent).unwrap().0.get(&oldname) {
+            Some(&ino) => ino,
+            None => return syscall_error(Errno::ENOENT, "rename", "No such file or directory"),
+        };
+        for dir in [oldparent, newparent] {
+            if let Err(e) = inner.check_dir_writable(dir) {
+                return syscall_error(e, "rename", "Permission denied");
+            }
+        }
+        let moving_dir = inner.inodes[&ino].is_dir();
+
+        // A directory can't be moved below itself
+        if moving_dir {
+            let mut cur = newparent;
+            loop {
+                if cur == ino {
+                    return syscall_error(Errno::EINVAL, "rename", "cannot move into itself");
+                }
+                if cur == TMPFS_ROOT_INO {
+                    break;
+                }
+                cur = inner.dir_entries(cur).unwrap().1;
+            }
+        }
+
+        if let Some(&existing) = inner.dir_entries(newparent).unwrap().0.get(&newname) {
+            if existing == ino {
+                return 0;
+            }
+            match (moving_dir, inner.dir_entries(existing)) {
+                (true, Err(_)) => {
+                    return syscall_error(Errno::ENOTDIR, "rename", "Not a directory")
+                }
+                (false, Ok(_)) => return syscall_error(Errno::EISDIR, "rename", "Is a directory"),
+                (true, Ok((entries, _))) if !entries.is_empty() => {
+                    return syscall_error(Errno::ENOTEMPTY, "rename", "Directory not empty")
+                }
+                _ => {}
+            }
+            inner.unlink_entry(newparent, &newname);
+        }
+
+        inner.dir_entries_mut(oldparent).remove(&oldname);
+        inner.dir_entries_mut(newparent).insert(newname, ino);
+        if moving_dir && oldparent != newparent {
+            if let InodeData::Dir { parent, .. } = &mut inner.inodes.get_mut(&ino).unwrap().data {
+                *parent = newparent;
+            }
+            inner.inodes.get_mut(&oldparent).unwrap().nlink -= 1;
+            inner.inodes.get_mut(&newparent).unwrap().nlink += 1;
+        }
+        inner.inodes.get_mut(&ino).unwrap().ctime = now();
+        inner.inodes.get_mut(&oldparent).unwrap().touch_modified();
+        inner.inodes.get_mut(&newparent).unwrap().touch_modified();
+        0
+    }
+
+    /// `link()` where both paths are on this mount
+    pub fn link(&self, oldpath: &[u8], newpath: &[u8]) -> i32 {
+        let (oldrel, newrel) = (self.rel(oldpath), self.rel(newpath));
+        let mut inner = self.inner.lock();
+        // link(2) does not follow a symlink in oldpath
+        let ino = match inner.walk(oldrel, &self.mountpoint, false) {
+            Ok(ino) => ino,
+            Err(e) => return syscall_error(e, "link", "cannot resolve old path"),
+        };
+        if inner.inodes[&ino].is_dir() {
+            return syscall_error(Errno::EPERM, "link", "cannot hard link a directory");
+        }
+        let (newparent, newname) = match inner.walk_parent(newrel, &self.mountpoint) {
+            Ok(res) => res,
+            Err(e) => return syscall_error(e, "link", "cannot resolve new parent"),
+        };
+        if inner
+            .dir_entries(newparent)
+            .unwrap()
+            .0
+            .contains_key(&newname)
+        {
+            return syscall_error(Errno::EEXIST, "link", "File exists");
+        }
+        if let Err(e) = inner.check_dir_writable(newparent) {
+            return syscall_error(e, "link", "Permission denied");
+        }
+        inner.dir_entries_mut(newparent).insert(newname, ino);
+        let inode = inner.inodes.get_mut(&ino).unwrap();
+        inode.nlink += 1;
+        inode.ctime = now();
+        inner.inodes.get_mut(&newparent).unwrap().touch_modified();
+        0
+    }
+
+    /// `symlink()` creating `linkpath` on this mount. The target is stored verbatim.
+    pub fn symlink(&self, target: &str, linkpath: &[u8]) -> i32 {
+        let rel = self.rel(linkpath);
+        let mut inner = self.inner.lock();
+        let (parent, name) = match inner.walk_parent(rel, &self.mountpoint) {
+            Ok(res) => res,
+            Err(Errno::EBUSY) => return syscall_error(Errno::EEXIST, "symlink", "File exists"),
+            Err(e) => return syscall_error(e, "symlink", "cannot resolve parent"),
+        };
+        if inner.dir_entries(parent).unwrap().0.contains_key(&name) {
+            return syscall_error(Errno::EEXIST, "symlink", "File exists");
+        }
+        if let Err(e) = inner.check_dir_writable(parent) {
+            return syscall_error(e, "symlink", "Permission denied");
+        }
+        if let Err(e) = inner.charge(self.quota, 0, target.len()) {
+            return syscall_error(e, "symlink", "No space left on device");
+        }
+        let ino = inner.alloc(Inode::new(InodeData::Symlink(target.to_string()), 0o777));
+        inner.dir_entries_mut(parent).insert(name, ino);
+        inner.inodes.get_mut(&parent).unwrap().touch_modified();
+        0
+    }
+
+    /// `readlink()` on a tmpfs path. Like Linux, the result is not NUL terminated.
+    pub fn readlink(&self, path: &[u8], buf: *mut u8, bufsiz: usize) -> i32 {
+        let inner = self.inner.lock();
+        let ino = match inner.walk(self.rel(path), &self.mountpoint, false) {
+            Ok(ino) => ino,
+            Err(e) => return syscall_error(e, "readlink", "cannot resolve path"),
+        };
+        match &inner.inodes[&ino].data {
+            InodeData::Symlink(target) => {
+                let n = target.len().min(bufsiz);
+                unsafe { std::ptr::copy_nonoverlapping(target.as_ptr(), buf, n) };
+                n as i32
+            }
+            _ => syscall_error(Errno::EINVAL, "readlink", "Not a symbolic link"),
+        }
+    }
+
+    /// `chmod()` on a tmpfs path
+    pub fn chmod(&self, path: &[u8], mode: u32) -> i32 {
+        let mut inner = self.inner.lock();
+        match inner.walk(self.rel(path), &self.mountpoint, true) {
+            Ok(ino) => {
+                let inode = inner.inodes.get_mut(&ino).unwrap();
+                inode.mode = mode & 0o7777;
+                inode.ctime = now();
+                0
+            }
+            Err(e) => syscall_error(e, "chmod", "cannot resolve path"),
+        }
+    }
+
+    /// `truncate()` on a tmpfs path
+    pub fn truncate(&self, path: &[u8], length: i64) -> i32 {
+        if length < 0 {
+            return syscall_error(Errno::EINVAL, "truncate", "negative length");
+        }
+        let mut inner = self.inner.lock();
+        let ino = match inner.walk(self.rel(path), &self.mountpoint, true) {
+            Ok(ino) => ino,
+            Err(e) => return syscall_error(e, "truncate", "cannot resolve path"),
+        };
+        if !inner.inodes[&ino].is_dir() && !inner.inodes[&ino].permits(W_OK) {
+            return syscall_error(Errno::EACCES, "truncate", "Permission denied");
+        }
+        match inner.truncate(ino, self.quota, length as usize) {
+            Ok(()) => 0,
+            Err(e) => syscall_error(e, "truncate", "truncate failed"),
+        }
+    }
+
+    /// Check that `path` is a searchable directory, for `chdir()`
+    pub fn chdir(&self, path: &[u8]) -> i32 {
+        let inner = self.inner.lock();
+        let ino = match inner.walk(self.rel(path), &self.mountpoint, true) {
+            Ok(ino) => ino,
+            Err(e) => return syscall_error(e, "chdir", "cannot resolve path"),
+        };
+        if !inner.inodes[&ino].is_dir() {
+            return syscall_error(Errno::ENOTDIR, "chdir", "Not a directory");
+        }
+        if !inner.inodes[&ino].permits(X_OK) {
+            return syscall_error(Errno::EACCES, "chdir", "Permission denied");
+        }
+        0
+    }
+
+    /// `statfs()` / `fstatfs()` for this mount, in the host layout so that callers can hand
+    /// it to the same conversion as a kernel result.
+    pub fn statfs(&self) -> libc::statfs {
+        let inner = self.inner.lock();
+        let total_blocks = match self.quota {
+            Some(limit) => (limit as u64).div_ceil(TMPFS_BLKSIZE),
+            // without a quota, advertise whatever is left of a 4 GiB nominal size
+            None => (1u64 << 32) / TMPFS_BLKSIZE,
+        };
+        let used_blocks = (inner.used_bytes as u64).div_ceil(TMPFS_BLKSIZE);
+        let free_blocks = total_blocks.saturating_sub(used_blocks);
+
+        let mut buf: libc::statfs = unsafe { std::mem::zeroed() };
+        buf.f_type = TMPFS_MAGIC as _;
+        buf.f_bsize = TMPFS_BLKSIZE as _;
+        buf.f_blocks = total_blocks;
+        buf.f_bfree = free_blocks;
+        buf.f_bavail = free_blocks;
+        buf.f_files = u32::MAX as u64;
+        buf.f_ffree = u32::MAX as u64 - inner.inodes.len() as u64;
+        buf.f_namelen = TMPFS_NAME_MAX as _;
+        buf.f_frsize = TMPFS_BLKSIZE as _;
+        buf
+    }
+}
+
+impl TmpfsHandle {
+    fn readable(&self) -> bool {
+        self.flags.load(Ordering::Relaxed) & O_ACCMODE != O_WRONLY
+    }
+
+    fn writable(&self) -> bool {
+        self.flags.load(Ordering::Relaxed) & O_ACCMODE != O_RDONLY
+    }
+
+    /// Guest path this file was opened with
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The mount this file lives on
+    pub fn fs(&self) -> &Arc<Tmpfs> {
+        &self.fs
+    }
+
+    /// `read()` at the current offset
+    pub fn read(&self, buf: *mut u8, count: usize) -> i32 {
+        if !self.readable() {
+            return syscall_error(Errno::EBADF, "read", "fd not open for reading");
+        }
+        let mut offset = self.offset.lock();
+        let dst = unsafe { std::slice::from_raw_parts_mut(buf, count) };
+        match self
+            .fs
+            .inner
+            .lock()
+            .read_at(self.ino, *offset as usize, dst)
+        {
+            Ok(n) => {
+                *offset += n as u64;
+                n as i32
+            }
+            Err(e) => syscall_error(e, "read", "read failed"),
+        }
+    }
+
+    /// `pread()`, leaving the offset untouched
+    pub fn pread(&self, buf: *mut u8, count: usize, offset: i64) -> i32 {
+        if !self.readable() {
+            return syscall_error(Errno::EBADF, "pread", "fd not open for reading");
+        }
+        if offset < 0 {
+            return syscall_error(Errno::EINVAL, "pread", "negative offset");
+        }
+        let dst = unsafe { std::slice::from_raw_parts_mut(buf, count) };
+        match self.fs.inner.lock().read_at(self.ino, offset as usize, dst) {
+            Ok(n) => n as i32,
+            Err(e) => syscall_error(e, "pread", "read failed"),
+        }
+    }
+
+    /// `write()` at the current offset, or at the end of file for `O_APPEND`
+    pub fn write(&self, buf: *const u8, count: usize) -> i32 {
+        if !self.writable() {
+            return syscall_error(Errno::EBADF, "write", "fd not open for writing");
+        }
+        let mut offset = self.offset.lock();
+        let src = unsafe { std::slice::from_raw_parts(buf, count) };
+        let mut inner = self.fs.inner.lock();
+        if self.flags.load(Ordering::Relaxed) & O_APPEND != 0 {
+            *offset = inner.inodes[&self.ino].size() as u64;
+        }
+        match inner.write_at(self.ino, self.fs.quota, *offset as usize, src) {
+            Ok(n) => {
+                *offset += n as u64;
+                n as i32
+            }
+            Err(e) => syscall_error(e, "write", "write failed"),
+        }
+    }
+
+    /// `pwrite()`, leaving the offset untouched
+    pub fn pwrite(&self, buf: *const u8, count: usize, offset: i64) -> i32 {
+        if !self.writable() {
+            return syscall_error(Errno::EBADF, "pwrite", "fd not open for writing");
+        }
+        if offset < 0 {
+            return syscall_error(Errno::EINVAL, "pwrite", "negative offset");
+        }
+        let src = unsafe { std::slice::from_raw_parts(buf, count) };
+        let mut inner = self.fs.inner.lock();
+        // as on Linux, O_APPEND makes pwrite append regardless of the offset
+        let offset = if self.flags.load(Ordering::Relaxed) & O_APPEND != 0 {
+            inner.inodes[&self.ino].size()
+        } else {
+            offset as usize
+        };
+        match inner.write_at(self.ino, self.fs.quota, offset, src) {
+            Ok(n) => n as i32,
+            Err(e) => syscall_error(e, "pwrite", "write failed"),
+        }
+    }
+
+    /// `lseek()`. For directories the offset is an index into the listing and only
+    /// absolute seeks are meaningful.
+    pub fn lseek(&self, offset: i64, whence: i32) -> i32 {
+        let mut cur = self.offset.lock();
+        let size = self.fs.inner.lock().inodes[&self.ino].size() as i64;
+        let base = match whence {
+            SEEK_SET => 0,
+            SEEK_CUR => *cur as i64,
+            SEEK_END => size,
+            _ => return syscall_error(Errno::EINVAL, "lseek", "invalid whence"),
+        };
+        let newoff = match base.checked_add(offset) {
+            Some(off) if off >= 0 => off,
+            _ => return syscall_error(Errno::EINVAL, "lseek", "resulting offset is negative"),
+        };
+        *cur = newoff as u64;
+        newoff as i32
+    }
+
+    /// `fstat()`
+    pub fn fstat(&self, statbuf: &mut StatData) -> i32 {
+        self.fs
+            .inner
+            .lock()
+            .fill_stat(self.ino, self.fs.devid, statbuf);
+        0
+    }
+
+    /// `ftruncate()`
+    pub fn ftruncate(&self, length: i64) -> i32 {
+        if length < 0 || !self.writable() {
+            return syscall_error(Errno::EINVAL, "ftruncate", "invalid length or fd mode");
+        }
+        match self
+            .fs
+            .inner
+            .lock()
+            .truncate(self.ino, self.fs.quota, length as usize)
+        {
+            Ok(()) => 0,
+            Err(e) => syscall_error(e, "ftruncate", "truncate failed"),
+        }
+    }
+
+    /// `fchmod()`
+    pub fn fchmod(&self, mode: u32) -> i32 {
+        let mut inner = self.fs.inner.lock();
+        let inode = inner.inodes.get_mut(&self.ino).unwrap();
+        inode.mode = mode & 0o7777;
+        inode.ctime = now();
+        0
+    }
+
+    /// `fcntl()` commands that only concern the open file description
+    pub fn fcntl(&self, cmd: i32, arg: i32) -> i32 {
+        match cmd {
+            F_GETFL => self.flags.load(Ordering::Relaxed),
+            F_SETFL => {
+                // the access mode is fixed at open time
+                let accmode = self.flags.load(Ordering::Relaxed) & O_ACCMODE;
+                self.flags
+                    .store(accmode | (arg & !O_ACCMODE), Ordering::Relaxed);
+                0
+            }
+            // tmpfs files are private to this process, so locks never conflict
+            libc::F_GETLK | libc::F_SETLK | libc::F_SETLKW => 0,
+            _ => syscall_error(Errno::EINVAL, "fcntl", "Invalid command for tmpfs file"),
+        }
+    }
+
+    /// `ioctl()` requests. `FIONBIO` sets `O_NONBLOCK` like `F_SETFL` would (it never changes
+    /// anything for a regular file); a tmpfs file is no terminal, so everything else fails
+    /// with `ENOTTY` as on Linux.
+    pub fn ioctl(&self, req: u32, arg: *mut u8) -> i32 {
+        match req {
+            FIONBIO => {
+                if arg.is_null() {
+                    return syscall_error(Errno::EFAULT, "ioctl", "Invalid address");
+                }
+                if unsafe { *(arg as *const i32) } != 0 {
+                    self.flags.fetch_or(O_NONBLOCK, Ordering::Relaxed);
+                } else {
+                    self.flags.fetch_and(!O_NONBLOCK, Ordering::Relaxed);
+                }
+                0
+            }
+            _ => syscall_error(Errno::ENOTTY, "ioctl", "Inappropriate ioctl for device"),
+        }
+    }
+
+    /// `getdents64()`: fill `dirp` with `linux_dirent64` records, resuming at the current
+    /// offset (which counts entries, starting with "." and "..").
+    pub fn getdents(&self, dirp: *mut u8, count: usize) -> i32 {
+        let mut offset = self.offset.lock();
+        let inner = self.fs.inner.lock();
+        let (entries, parent) = match inner.dir_entries(self.ino) {
+            Ok(res) => res,
+            Err(e) => return syscall_error(e, "getdents", "Not a directory"),
+        };
+
+        let listing = [(self.ino, "."), (parent, "..")]
+            .into_iter()
+            .chain(entries.iter().map(|(name, &ino)| (ino, name.as_str())));
+
+        let mut written = 0usize;
+        for (idx, (ino, name)) in listing.enumerate().skip(*offset as usize) {
+            if written + dirent64_reclen(name) > count {
+                if written == 0 {
+                    return syscall_error(Errno::EINVAL, "getdents", "Result buffer is too small");
+                }
+                break;
+            }
+            let d_type = match inner.inodes[&ino].data {
+                InodeData::File(_) => libc::DT_REG,
+                InodeData::Dir { .. } => libc::DT_DIR,
+                InodeData::Symlink(_) => libc::DT_LNK,
+            };
+            written += write_dirent64(
+                unsafe { dirp.add(written) },
+                ino,
+                (idx + 1) as i64,
+                d_type,
+                name,
+            );
+            *offset = (idx + 1) as u64;
+        }
+        written as i32
+    }
+
+    /// Copy the whole file into `dst` starting at `offset`, zero-filling past EOF. Used to
+    /// populate private mappings of tmpfs files.
+    pub fn snapshot_into(&self, dst: *mut u8, len: usize, offset: i64) -> i32 {
+        let dst = unsafe { std::slice::from_raw_parts_mut(dst, len) };
+        let mut inner = self.fs.inner.lock();
+        match inner.read_at(self.ino, offset.max(0) as usize, dst) {
+            Ok(n) => {
+                dst[n..].fill(0);
+                0
+            }
+            Err(e) => syscall_error(e, "mmap", "cannot map tmpfs object"),
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! AF_UNIX namespace
+//!
+//! Unix domain sockets bound to a path are private to a lind instance already: the path
+//! resolves inside the lindfs chroot like any other. Abstract sockets, whose `sun_path` starts
+//! with a NUL byte, live in a namespace the host shares between all of its processes instead.
+//! Passed through as they are, they would let a cage reach host daemons listening on one
+//! (D-Bus, X11, systemd) and make two lind instances clash over the same names.
+//!
+//! So every abstract name a cage passes to `bind`, `connect`, `sendto`, `sendmsg` or
+//! `sendmmsg` is moved under a prefix of this instance, `lind-unix.<pid>.`, and the names
+//! returned by `getsockname`, `getpeername`, `accept`, `recvfrom` and `recvmsg` lose it again.
+//! A name that does not fit behind the prefix is replaced by a digest of it, which is
+//! remembered so the name can be reported back. Binding an `AF_UNIX` socket to an empty address
+//! picks a free abstract name of five hex digits, as Linux does, but in the namespace of the
+//! instance.
+//!
+//! The host's abstract names, and those of other instances, are out of reach of the cages.
+//! A peer that is bound to one anyway, say a socket inherited from the host, is reported with
+//! its host name.
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::mem::{size_of, zeroed};
+use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use sysdefs::constants::err_const::{get_errno, handle_errno, syscall_error, Errno};
+use sysdefs::constants::net_const::AF_UNIX;
+use sysdefs::data::net_struct::SockAddr;
+use typemap::network_helpers::copy_out_sockaddr;
+
+/// Offset of `sun_path` in `sockaddr_un`
+const SUN_PATH_OFFSET: usize = size_of::<libc::sa_family_t>();
+
+/// Autobind names are five hex digits, as on Linux
+const AUTOBIND_NAMES: u32 = 1 << 20;
+
+static NEXT_AUTOBIND: AtomicU32 = AtomicU32::new(0);
+
+lazy_static! {
+    /// Prefix of the abstract names of this instance
+    static ref NAME_PREFIX: String = format!("lind-unix.{}.", std::process::id());
+    /// Guest names too long to carry behind the prefix, by their digest
+    static ref DIGESTS: DashMap<String, Vec<u8>> = DashMap::new();
+}
+
+/// An abstract `AF_UNIX` address moved into the namespace of this instance, ready to be
+/// passed to the host
+pub struct HostUnixAddr {
+    sun: libc::sockaddr_un,
+    len: libc::socklen_t,
+}
+
+impl HostUnixAddr {
+    pub fn as_ptr(&self) -> *const libc::sockaddr {
+        &self.sun as *const _ as *const libc::sockaddr
+    }
+
+    pub fn addrlen(&self) -> libc::socklen_t {
+        self.len
+    }
+}
+
+/// Abstract address with the name `name` (without the leading NUL byte)
+fn abstract_addr(name: &[u8]) -> (libc::sockaddr_un, libc::socklen_t) {
+    let mut sun: libc::sockaddr_un = unsafe { zeroed() };
+    sun.sun_family = AF_UNIX as libc::sa_family_t;
+    for (dst, src) in sun.sun_path[1..].iter_mut().zip(name) {
+        *dst = *src as libc::c_char;
+    }
+    let len = SUN_PATH_OFFSET + 1 + name.len().min(sun.sun_path.len() - 1);
+    (sun, len as libc::socklen_t)
+}
+
+/// Name of an abstract address of `len` bytes, or `None` if it is not one
+fn abstract_name(sun: &libc::sockaddr_un, len: usize) -> Option<Vec<u8>> {
+    let path_len = len.checked_sub(SUN_PATH_OFFSET)?.min(sun.sun_path.len());
+    if sun.sun_family as i32 != AF_UNIX || path_len < 1 || sun.sun_path[0] != 0 {
+        return None;
+    }
+    Some(sun.sun_path[1..path_len].iter().map(|c| *c as u8).collect())
+}
+
+/// Host name of the guest abstract name `name`
+fn host_name(name: &[u8]) -> Vec<u8> {
+    let room = size_of::<libc::sockaddr_un>() - SUN_PATH_OFFSET - 1 - NAME_PREFIX.len() - 1;
+    let mut host = NAME_PREFIX.as_bytes().to_vec();
+    if name.len() <= room {
+        host.push(b'=');
+        host.extend_from_slice(name);
+    } else {
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        let digest = format!("{:016x}", hasher.finish());
+        host.push(b'#');
+        host.extend_from_slice(digest.as_bytes());
+        DIGESTS.insert(digest, name.to_vec());
+    }
+    host
+}
+
+/// Guest name of the host abstract name `host`, which is `host` itself if it does not belong
+/// to this instance
+fn guest_name(host: &[u8]) -> Vec<u8> {
+    let Some(rest) = host.strip_prefix(NAME_PREFIX.as_bytes()) else {
+        return host.to_vec();
+    };
+    match rest.split_first() {
+        Some((b'=', name)) => name.to_vec(),
+        Some((b'#', digest)) => std::str::from_utf8(digest)
+            .ok()
+            .and_then(|digest| DIGESTS.get(digest))
+            .map_or_else(|| host.to_vec(), |name| name.value().clone()),
+        _ => host.to_vec(),
+    }
+}
+
+/// Translate a guest address for the host if it is an abstract `AF_UNIX` one. Other addresses
+/// need no translation and give `None`.
+///
+/// # Safety
+/// `addr` must be NULL or point to `addrlen` readable bytes.
+pub unsafe fn unixns_to_host(addr: *const u8, addrlen: u32) -> Option<HostUnixAddr> {
+    if addr.is_null() || (addrlen as usize) <= SUN_PATH_OFFSET {
+        return None;
+    }
+    let mut sun: libc::sockaddr_un = zeroed();
+    let len = (addrlen as usize).min(size_of::<libc::sockaddr_un>());
+    ptr::copy_nonoverlapping(addr, &mut sun as *mut _ as *mut u8, len);
+
+    let name = abstract_name(&sun, len)?;
+    let (sun, len) = abstract_addr(&host_name(&name));
+    Some(HostUnixAddr { sun, len })
+}
+
+/// Copy a host address of `len` bytes out to a guest buffer of `capacity` bytes, removing the
+/// prefix of this instance from abstract `AF_UNIX` names. Like the kernel, the copy is
+/// truncated to the buffer.
+///
+/// ## Returns:
+/// The full length of the guest address
+///
+/// # Safety
+/// `dst` must be NULL or valid for `capacity` bytes of writes.
+pub unsafe fn unixns_name_out(
+    storage: &libc::sockaddr_storage,
+    len: libc::socklen_t,
+    dst: *mut u8,
+    capacity: libc::socklen_t,
+) -> libc::socklen_t {
+    let sun = &*(storage as *const _ as *const libc::sockaddr_un);
+    let translated = abstract_name(sun, len as usize).map(|name| abstract_addr(&guest_name(&name)));
+    let (src, len) = match &translated {
+        Some((sun, len)) => (sun as *const _ as *const u8, *len),
+        None => (storage as *const _ as *const u8, len),
+    };
+    if !dst.is_null() {
+        ptr::copy_nonoverlapping(src, dst, len.min(capacity) as usize);
+    }
+    len
+}
+
+/// `copy_out_sockaddr` for addresses that may be `AF_UNIX` ones: a unix address of `len`
+/// bytes goes out under its guest name and with its exact length, anything else is left to
+/// `copy_out_sockaddr`.
+///
+/// # Safety
+/// `dst` and `lenp` must be NULL or valid, `dst` for `*lenp` bytes of writes.
+pub unsafe fn unixns_copy_out(
+    dst: *mut SockAddr,
+    lenp: *mut libc::socklen_t,
+    storage: &libc::sockaddr_storage,
+    len: libc::socklen_t,
+) {
+    if dst.is_null() || lenp.is_null() {
+        return;
+    }
+    if storage.ss_family as i32 != AF_UNIX {
+        copy_out_sockaddr(dst, lenp, storage);
+        return;
+    }
+    *lenp = unixns_name_out(storage, len, dst as *mut u8, *lenp);
+}
+
+/// Whether `addr` is the empty `AF_UNIX` address that asks `bind()` for an autobind name
+///
+/// # Safety
+/// `addr` must be NULL or point to `addrlen` readable bytes.
+pub unsafe fn unixns_is_autobind(addr: *const u8, addrlen: u32) -> bool {
+    !addr.is_null()
+        && addrlen as usize == SUN_PATH_OFFSET
+        && ptr::read_unaligned(addr as *const libc::sa_family_t) as i32 == AF_UNIX
+}
+
+/// `bind()` of a host `AF_UNIX` socket to an empty address: bind it to a free name of five hex
+/// digits in the namespace of this instance
+pub fn unixns_autobind(fd: i32) -> i32 {
+    for _ in 0..AUTOBIND_NAMES {
+        let n = NEXT_AUTOBIND.fetch_add(1, Ordering::Relaxed) % AUTOBIND_NAMES;
+        let (sun, len) = abstract_addr(&host_name(format!("{:05x}", n).as_bytes()));
+        if unsafe { libc::bind(fd, &sun as *const _ as *const libc::sockaddr, len) } == 0 {
+            return 0;
+        }
+        let errno = get_errno();
+        if errno != libc::EADDRINUSE {
+            return handle_errno(errno, "bind");
+        }
+    }
+    syscall_error(Errno::EADDRINUSE, "bind", "no free autobind name")
+}
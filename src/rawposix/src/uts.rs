@@ -0,0 +1,93 @@
+//! Virtual UTS names
+//!
+//! `uname()`, `gethostname()` and `/proc/sys/kernel/hostname` used to report the host, so a
+//! guest's idea of the machine it runs on changed with every machine the tests ran on. Each
+//! cage now carries a `UtsNameStruct` of its own (`Cage::uts`), and those syscalls read and
+//! write that record instead.
+//!
+//! The init cage's record is built from the `UtsConfig` lind-boot passes to `uts_init`
+//! (`--hostname`, `--domainname`, `--uts-release`, `--uts-version`, `--uts-machine`) and every
+//! other cage starts with a copy of its parent's, see `fork_syscall`. A cage calling
+//! `sethostname()` or `setdomainname()` changes its own record only.
+
+use std::sync::OnceLock;
+use sysdefs::constants::err_const::Errno;
+use sysdefs::constants::sys_const::{HOST_NAME_MAX, UTSNAME_LENGTH};
+use sysdefs::data::sys_struct::UtsNameStruct;
+
+/// The names the init cage starts with. Only `sysname` is fixed, as programs compare it
+/// against "Linux".
+#[derive(Clone, Debug)]
+pub struct UtsConfig {
+    pub nodename: String,
+    pub domainname: String,
+    pub release: String,
+    pub version: String,
+    pub machine: String,
+}
+
+impl Default for UtsConfig {
+    fn default() -> Self {
+        UtsConfig {
+            nodename: "lind".to_string(),
+            // What Linux reports when no domain name was ever set
+            domainname: "(none)".to_string(),
+            release: "6.16.0-lind".to_string(),
+            version: "#1 SMP PREEMPT_DYNAMIC lind".to_string(),
+            machine: "x86_64".to_string(),
+        }
+    }
+}
+
+static UTS_DEFAULT: OnceLock<UtsNameStruct> = OnceLock::new();
+
+/// Set the names of the init cage. Has to be called before `rawposix_start`, which falls back
+/// to `UtsConfig::default()` otherwise.
+///
+/// ## Returns:
+/// - `Ok(())` on success
+/// - `Err(EINVAL)` if a name is longer than `HOST_NAME_MAX` bytes, `Err(EBUSY)` if the names
+///   were already set
+pub fn uts_init(config: &UtsConfig) -> Result<(), Errno> {
+    let uts = build(config)?;
+    UTS_DEFAULT.set(uts).map_err(|_| Errno::EBUSY)
+}
+
+/// The record the init cage starts with
+pub fn uts_default() -> UtsNameStruct {
+    *UTS_DEFAULT.get_or_init(|| build(&UtsConfig::default()).unwrap())
+}
+
+fn build(config: &UtsConfig) -> Result<UtsNameStruct, Errno> {
+    let field = |name: &str| -> Result<[u8; UTSNAME_LENGTH], Errno> {
+        let mut field = [0; UTSNAME_LENGTH];
+        uts_set_field(&mut field, name.as_bytes())?;
+        Ok(field)
+    };
+
+    Ok(UtsNameStruct {
+        sysname: field("Linux")?,
+        nodename: field(&config.nodename)?,
+        release: field(&config.release)?,
+        version: field(&config.version)?,
+        machine: field(&config.machine)?,
+        domainname: field(&config.domainname)?,
+    })
+}
+
+/// The name stored in a field, without the terminating NUL
+pub fn uts_field(field: &[u8; UTSNAME_LENGTH]) -> &[u8] {
+    let len = field.iter().position(|c| *c == 0).unwrap_or(HOST_NAME_MAX);
+    &field[..len]
+}
+
+/// Store `name` into a field. Like Linux, a name is at most `HOST_NAME_MAX` bytes long, and
+/// one with a NUL byte in it ends there.
+pub fn uts_set_field(field: &mut [u8; UTSNAME_LENGTH], name: &[u8]) -> Result<(), Errno> {
+    if name.len() > HOST_NAME_MAX {
+        return Err(Errno::EINVAL);
+    }
+    field.fill(0);
+    field[..name.len()].copy_from_slice(name);
+    Ok(())
+}
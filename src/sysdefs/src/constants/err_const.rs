@@ -164,6 +164,7 @@ reversible_enum! {
         EKEYREJECTED = 129,	// Key was rejected by service  for robust mutexes
         EOWNERDEAD = 130,	// Owner died
         ENOTRECOVERABLE = 131, // State not recoverable
+        ERESTARTSYS = 512, // Kernel internal: restart the call once the signal is handled
     }
 }
 
@@ -496,6 +497,12 @@ pub fn handle_errno(e: i32, syscall: &str) -> i32 {
         130 => syscall_error(Errno::EOWNERDEAD, syscall, "Owner died"),
         // ENOTRECOVERABLE = 131, // State not recoverable
         131 => syscall_error(Errno::ENOTRECOVERABLE, syscall, "State not recoverable"),
+        // ERESTARTSYS = 512, // Kernel internal: restart the call once the signal is handled
+        512 => syscall_error(
+            Errno::ERESTARTSYS,
+            syscall,
+            "Interrupted system call, to be restarted",
+        ),
         _ => syscall_error(Errno::EINVAL, syscall, "Invalid error code"),
     }
 }
@@ -73,10 +73,17 @@ pub const F_SETOWN: i32 = 8;
 pub const F_GETOWN: i32 = 9;
 pub const F_SETSIG: i32 = 10;
 pub const F_GETSIG: i32 = 11;
+pub const F_SETOWN_EX: i32 = 15;
+pub const F_GETOWN_EX: i32 = 16;
 pub const F_SETLEASE: i32 = 1024;
 pub const F_GETLEASE: i32 = 1025;
 pub const F_NOTIFY: i32 = 1026;
 
+// Owner types of F_SETOWN_EX / F_GETOWN_EX
+pub const F_OWNER_TID: i32 = 0;
+pub const F_OWNER_PID: i32 = 1;
+pub const F_OWNER_PGRP: i32 = 2;
+
 //Commands for IOCTL
 pub const FIONBIO: u32 = 21537;
 pub const FIOASYNC: u32 = 21586;
@@ -156,6 +163,8 @@ pub const MAP_PRIVATE: u32 = 0x02; // Changes are private to this process
 pub const MAP_SHARING_MASK: u32 = 0x03; // Mask to isolate sharing bits
 pub const MAP_POPULATE: u32 = 0x8000; // Override lazy loading of pages
 pub const MAP_ANON: u32 = 0x20; // Don't use a file descriptor
+pub const MAP_GROWSDOWN: u32 = 0x0100; // Stack-like segment
+pub const MAP_NORESERVE: u32 = 0x4000; // Don't check for reservations
 
 // ===== Page Size Constants =====
 // Note: These values are architecture-dependent
@@ -171,7 +180,46 @@ pub const MAP_FAILED: *mut std::ffi::c_void = (-1isize) as *mut std::ffi::c_void
 // Source: include/uapi/asm-generic/mman-common.h
 pub const MREMAP_MAYMOVE: u32 = 0x01; // Can relocate mapping
 pub const MREMAP_FIXED: u32 = 0x02; // New address is specified exactly
+pub const MREMAP_DONTUNMAP: u32 = 0x04; // Leave the old mapping in place
+
+// ===== Memory Sync Flags =====
+// Source: include/uapi/asm-generic/mman-common.h
+pub const MS_ASYNC: i32 = 1; // Sync memory asynchronously
+pub const MS_INVALIDATE: i32 = 2; // Invalidate the caches
+pub const MS_SYNC: i32 = 4; // Synchronous memory sync
+
+// ===== Memory Advice Values =====
+// Source: include/uapi/asm-generic/mman-common.h
+pub const MADV_NORMAL: i32 = 0; // No further special treatment
+pub const MADV_RANDOM: i32 = 1; // Expect random page references
+pub const MADV_SEQUENTIAL: i32 = 2; // Expect sequential page references
+pub const MADV_WILLNEED: i32 = 3; // Will need these pages
+pub const MADV_DONTNEED: i32 = 4; // Don't need these pages
+pub const MADV_FREE: i32 = 8; // Free pages only if memory pressure
+pub const MADV_REMOVE: i32 = 9; // Remove these pages and resources
+pub const MADV_DONTFORK: i32 = 10; // Don't inherit across fork
+pub const MADV_DOFORK: i32 = 11; // Do inherit across fork
+pub const MADV_MERGEABLE: i32 = 12; // KSM may merge identical pages
+pub const MADV_UNMERGEABLE: i32 = 13; // KSM may not merge identical pages
+pub const MADV_HUGEPAGE: i32 = 14; // Worth backing with hugepages
+pub const MADV_NOHUGEPAGE: i32 = 15; // Not worth backing with hugepages
+pub const MADV_DONTDUMP: i32 = 16; // Exclude from core dump
+pub const MADV_DODUMP: i32 = 17; // Clear the MADV_DONTDUMP flag
+pub const MADV_WIPEONFORK: i32 = 18; // Zero memory on fork, child only
+pub const MADV_KEEPONFORK: i32 = 19; // Undo MADV_WIPEONFORK
+pub const MADV_COLD: i32 = 20; // Deactivate these pages
+pub const MADV_PAGEOUT: i32 = 21; // Reclaim these pages
 
 // ===== File Access Modes =====
 // Source: include/uapi/asm-generic/fcntl.h
 pub const O_ACCMODE: i32 = 0o003; // Mask for file access modes
+
+// ===== Vectored I/O Limit =====
+// Source: include/uapi/linux/uio.h
+pub const IOV_MAX: i32 = 1024; // Max iovecs per readv/writev (UIO_MAXIOV)
+
+// ===== Fallocate Modes =====
+// Source: include/uapi/linux/falloc.h
+pub const FALLOC_FL_KEEP_SIZE: i32 = 0x01; // Don't extend the file size
+pub const FALLOC_FL_PUNCH_HOLE: i32 = 0x02; // De-allocate a range
+pub const FALLOC_FL_ZERO_RANGE: i32 = 0x10; // Zero a range
@@ -15,6 +15,18 @@ pub const LINDFS_ROOT: &str = "/home/lind/lind-wasm/lindfs";
 /// in `fdtables`. Used to distinguish kernel-backed FDs from fully virtual ones
 /// (e.g., in-memory pipes).
 pub const FDKIND_KERNEL: u32 = 0;
+/// Represents a virtual FD that refers to one of the built-in device nodes
+/// (`/dev/null`, `/dev/zero`, ...). The `underfd` stores the device id and no
+/// kernel file descriptor is ever opened for it.
+pub const FDKIND_DEV: u32 = 1;
+/// Represents a virtual FD that refers to a file or directory on an in-memory
+/// tmpfs mount. The `underfd` is the id of the open file description kept by
+/// RawPOSIX, so `dup`ed fds share the same file offset.
+pub const FDKIND_TMPFS: u32 = 2;
+/// Represents a virtual FD that refers to a TCP or UDP socket of the virtual
+/// network namespace. The `underfd` is the host `AF_UNIX` socket that carries
+/// its traffic, so readiness and data transfer work as for a kernel fd.
+pub const FDKIND_NETNS: u32 = 3;
 /// Maximum allowed Cage ID.  
 /// This limit is inherited from earlier implementations and may be
 /// adjusted in the future.
@@ -256,6 +256,12 @@ pub const MSG_BATCH: i32 = 0x40000; // sendmmsg(): more messages coming
 pub const MSG_EOF: i32 = MSG_FIN; // Alias for MSG_FIN
 pub const MSG_NO_SHARED_FRAGS: i32 = 0x80000; // sendpage() internal: no shared frags
 pub const MSG_SENDPAGE_DECRYPTED: i32 = 0x100000; // sendpage() internal: page needs encryption
+pub const MSG_CMSG_CLOEXEC: i32 = 0x40000000; // Set close-on-exec on SCM_RIGHTS fds
+
+// ===== Control Message Types =====
+// Source: include/linux/socket.h
+pub const SCM_RIGHTS: i32 = 1; // Pass file descriptors
+pub const SCM_CREDENTIALS: i32 = 2; // Pass process credentials
 
 // ===== Shutdown Constants =====
 // Source: include/linux/socket.h
@@ -291,6 +297,9 @@ pub const SO_RCVTIMEO_OLD: i32 = 20; // Receive timeout (old)
 pub const SO_SNDTIMEO_OLD: i32 = 21; // Send timeout (old)
 pub const SO_PEERNAME: i32 = 28; // Name of connected peer
 pub const SO_ACCEPTCONN: i32 = 30; // Socket has had listen()
+pub const SO_PROTOCOL: i32 = 38; // Get socket protocol
+pub const SO_DOMAIN: i32 = 39; // Get socket domain
+pub const SO_LIND_NOSIGPIPE: i32 = 0x1022; // No SIGPIPE on writes (lind only, like BSD SO_NOSIGPIPE)
 
 // ===== TCP Options =====
 // Source: include/uapi/linux/tcp.h
@@ -329,6 +338,7 @@ pub const EPOLLMSG: i32 = 0x400; // Message ready
 pub const EPOLLERR: i32 = 0x008; // Error condition
 pub const EPOLLHUP: i32 = 0x010; // Hang up
 pub const EPOLLRDHUP: i32 = 0x2000; // Peer closed the connection
+pub const EPOLLEXCLUSIVE: i32 = 1 << 28; // Wake up one of the waiters only
 pub const EPOLLWAKEUP: i32 = 1 << 29; // Prevent system suspend
 pub const EPOLLONESHOT: i32 = 1 << 30; // One-shot edge trigger
 pub const EPOLLET: i32 = 1 << 31; // Edge-triggered
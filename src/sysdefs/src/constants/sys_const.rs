@@ -27,6 +27,11 @@ pub const STACK_MAX: u64 = 1 << 32; // Hard limit for stack size (4GB)
 pub const RLIMIT_STACK: u64 = 0; // Limit type for stack size
 pub const RLIMIT_NOFILE: u64 = 1; // Limit type for number of files
 
+// ===== UTS Name Limits =====
+// Source: include/uapi/linux/utsname.h
+pub const UTSNAME_LENGTH: usize = 65; // Size of every field of struct utsname
+pub const HOST_NAME_MAX: usize = 64; // Longest host or domain name, without the NUL
+
 // ===== Process Exit Status =====
 // Source: <stdlib.h> and POSIX standard
 pub const EXIT_SUCCESS: i32 = 0; // Successful termination
@@ -107,6 +112,21 @@ pub const SA_RESTART: u32 = 0x10000000; // Restart syscall on signal return
 pub const SA_NODEFER: u32 = 0x40000000; // Don't automatically block the signal when its handler is being executed
 pub const SA_RESETHAND: u32 = 0x80000000; // Reset to SIG_DFL on entry to handler
 
+// sigaltstack() flags and sizes (from src/glibc/sysdeps/unix/sysv/linux/bits/ss_flags.h and
+// bits/sigstack.h)
+pub const SS_ONSTACK: i32 = 1; // Running on the alternate signal stack
+pub const SS_DISABLE: i32 = 2; // No alternate signal stack
+pub const MINSIGSTKSZ: u32 = 2048; // Smallest alternate signal stack
+
+// siginfo_t si_code values (from src/glibc/sysdeps/unix/sysv/linux/bits/siginfo-consts.h)
+pub const SI_USER: i32 = 0; // Sent by kill()
+pub const ILL_ILLOPC: i32 = 1; // Illegal opcode
+pub const FPE_INTDIV: i32 = 1; // Integer divide by zero
+pub const FPE_INTOVF: i32 = 2; // Integer overflow
+pub const FPE_FLTINV: i32 = 7; // Invalid floating point operation
+pub const SEGV_MAPERR: i32 = 1; // Address not mapped to object
+pub const BUS_ADRALN: i32 = 1; // Invalid address alignment
+
 // Special Signal Handlers
 pub const SIG_ERR: i32 = -1; // Error return
 pub const SIG_DFL: i32 = 0; // Default action
@@ -163,6 +183,39 @@ pub fn signal_default_handler_dispatcher(signo: i32) -> SignalDefaultHandler {
 
 // Timer types
 pub const ITIMER_REAL: i32 = 0; // Real-time timer
+pub const ITIMER_VIRTUAL: i32 = 1; // User CPU time timer
+pub const ITIMER_PROF: i32 = 2; // User and system CPU time timer
+
+// Clocks
+pub const CLOCK_REALTIME: i32 = 0;
+pub const CLOCK_MONOTONIC: i32 = 1;
+pub const CLOCK_PROCESS_CPUTIME_ID: i32 = 2;
+pub const CLOCK_THREAD_CPUTIME_ID: i32 = 3;
+pub const CLOCK_MONOTONIC_RAW: i32 = 4;
+pub const CLOCK_REALTIME_COARSE: i32 = 5;
+pub const CLOCK_MONOTONIC_COARSE: i32 = 6;
+pub const CLOCK_BOOTTIME: i32 = 7;
+pub const CLOCK_REALTIME_ALARM: i32 = 8;
+pub const CLOCK_BOOTTIME_ALARM: i32 = 9;
+pub const CLOCK_TAI: i32 = 11;
+
+// POSIX timers
+pub const TIMER_ABSTIME: i32 = 1; // timer_settime() / clock_nanosleep() value is absolute
+pub const SIGEV_SIGNAL: i32 = 0; // Notify with a signal
+pub const SIGEV_NONE: i32 = 1; // No notification
+pub const SIGEV_THREAD: i32 = 2; // Notify in a new thread (glibc only)
+pub const SIGEV_THREAD_ID: i32 = 4; // Notify a given thread with a signal
+
+// getrusage() targets
+pub const RUSAGE_SELF: i32 = 0;
+pub const RUSAGE_CHILDREN: i32 = -1;
+pub const RUSAGE_THREAD: i32 = 1;
+
+// Clock ticks per second of times(), the USER_HZ that the guest's sysconf(_SC_CLK_TCK) assumes
+pub const CLK_TCK: u128 = 100;
+
+// Syscall numbers (from glibc/lind_syscall/lind_syscall_num.h)
+pub const CLOCK_SETTIME_SYSCALL: u64 = 227;
 
 // Futex operation constants (from glibc/target/include/linux/futex.h)
 pub const FUTEX_WAIT: i32 = 0;
@@ -180,6 +233,30 @@ pub const FUTEX_WAIT_REQUEUE_PI: i32 = 11;
 pub const FUTEX_CMP_REQUEUE_PI: i32 = 12;
 pub const FUTEX_LOCK_PI2: i32 = 13;
 
+pub const FUTEX_PRIVATE_FLAG: i32 = 128;
+pub const FUTEX_CLOCK_REALTIME: i32 = 256;
+pub const FUTEX_CMD_MASK: i32 = !(FUTEX_PRIVATE_FLAG | FUTEX_CLOCK_REALTIME);
+pub const FUTEX_BITSET_MATCH_ANY: u32 = 0xffffffff;
+
+// Bits of a priority-inheritance futex word
+pub const FUTEX_WAITERS: u32 = 0x80000000;
+pub const FUTEX_OWNER_DIED: u32 = 0x40000000;
+pub const FUTEX_TID_MASK: u32 = 0x3fffffff;
+
+// FUTEX_WAKE_OP operations and comparisons, encoded in its val3
+pub const FUTEX_OP_SET: u32 = 0;
+pub const FUTEX_OP_ADD: u32 = 1;
+pub const FUTEX_OP_OR: u32 = 2;
+pub const FUTEX_OP_ANDN: u32 = 3;
+pub const FUTEX_OP_XOR: u32 = 4;
+pub const FUTEX_OP_OPARG_SHIFT: u32 = 8;
+pub const FUTEX_OP_CMP_EQ: u32 = 0;
+pub const FUTEX_OP_CMP_NE: u32 = 1;
+pub const FUTEX_OP_CMP_LT: u32 = 2;
+pub const FUTEX_OP_CMP_LE: u32 = 3;
+pub const FUTEX_OP_CMP_GT: u32 = 4;
+pub const FUTEX_OP_CMP_GE: u32 = 5;
+
 /* Cloning flags.  */
 pub const CSIGNAL: u64 = 0x000000ff; /* Signal mask to be sent at exit.  */
 pub const CLONE_VM: u64 = 0x00000100; /* Set if VM shared between processes.  */
@@ -70,6 +70,14 @@ pub struct SockPair {
     pub sock2: i32,
 }
 
+/// `struct f_owner_ex` of `fcntl(F_GETOWN_EX)` / `fcntl(F_SETOWN_EX)`
+#[derive(Eq, PartialEq, Default, Copy, Clone, Debug)]
+#[repr(C)]
+pub struct FOwnerEx {
+    pub owner_type: i32,
+    pub pid: i32,
+}
+
 //EPOLL
 #[derive(Copy, Clone, Debug)]
 #[repr(C)]
@@ -91,12 +99,61 @@ pub struct ITimerVal {
     pub it_value: TimeVal,
 }
 
+/// Resource usage as reported by `wait4()` and `getrusage()`, in the x86_64 kernel layout
+/// (every `long` field is 64 bits wide)
+#[repr(C)]
+pub struct Rusage {
+    pub ru_utime: TimeVal,
+    pub ru_stime: TimeVal,
+    pub ru_maxrss: i64, // peak resident set size, in kilobytes
+    pub ru_ixrss: i64,
+    pub ru_idrss: i64,
+    pub ru_isrss: i64,
+    pub ru_minflt: i64,
+    pub ru_majflt: i64,
+    pub ru_nswap: i64,
+    pub ru_inblock: i64,
+    pub ru_oublock: i64,
+    pub ru_msgsnd: i64,
+    pub ru_msgrcv: i64,
+    pub ru_nsignals: i64,
+    pub ru_nvcsw: i64,
+    pub ru_nivcsw: i64,
+}
+
+/// Process times as reported by `times()`, in clock ticks, in the x86_64 kernel layout
+#[repr(C)]
+pub struct Tms {
+    pub tms_utime: i64,
+    pub tms_stime: i64,
+    pub tms_cutime: i64,
+    pub tms_cstime: i64,
+}
+
 #[repr(C)]
 pub struct TimeSpec {
     pub tv_sec: i64,
     pub tv_nsec: i64,
 }
 
+/// `struct itimerspec` of `timer_settime()` / `timer_gettime()`, in the host layout
+#[repr(C)]
+pub struct ITimerSpec {
+    pub it_interval: TimeSpec,
+    pub it_value: TimeSpec,
+}
+
+/// The fields of `struct sigevent` `timer_create()` uses, as glibc hands them over. The guest's
+/// `sigval` is narrower, and its value is never delivered, as signal handlers get no `siginfo_t`.
+#[derive(Eq, PartialEq, Default, Copy, Clone, Debug)]
+#[repr(C)]
+pub struct SigEvent {
+    pub sigev_signo: i32,
+    pub sigev_notify: i32,
+    /// The thread `SIGEV_THREAD_ID` targets
+    pub sigev_notify_thread_id: i32,
+}
+
 #[derive(Copy, Clone)]
 #[repr(C)]
 pub union IoctlPtrUnion {
@@ -145,6 +202,16 @@ pub struct SigactionStruct {
     pub sa_flags: i32,
 }
 
+/// An alternate signal stack as `sigaltstack()` takes it, the wasm32 `stack_t`. `ss_sp` is an
+/// address in the cage's linear memory.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct StackStruct {
+    pub ss_sp: u32,
+    pub ss_flags: i32,
+    pub ss_size: u32,
+}
+
 use std::mem::size_of;
 
 // Represents a Dirent struct without the string, as rust has no flexible array member support
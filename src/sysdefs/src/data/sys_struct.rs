@@ -1,3 +1,5 @@
+use crate::constants::sys_const::UTSNAME_LENGTH;
+
 #[derive(Copy, Clone, Default, Debug)]
 #[repr(C)]
 pub struct CloneArgStruct {
@@ -13,3 +15,15 @@ pub struct CloneArgStruct {
     pub set_tid_size: u64, // Number of TIDs in the `set_tid` array
     pub cgroup: u64, // File descriptor for the cgroup to which the child process should be attached
 }
+
+/// `struct utsname` as seen by the guest: six NUL-terminated fields of `UTSNAME_LENGTH` bytes
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+pub struct UtsNameStruct {
+    pub sysname: [u8; UTSNAME_LENGTH],
+    pub nodename: [u8; UTSNAME_LENGTH],
+    pub release: [u8; UTSNAME_LENGTH],
+    pub version: [u8; UTSNAME_LENGTH],
+    pub machine: [u8; UTSNAME_LENGTH],
+    pub domainname: [u8; UTSNAME_LENGTH],
+}
@@ -58,6 +58,29 @@ pub fn _check_cage_handler_exists(cageid: u64) -> bool {
     HANDLERTABLE.contains_key(&cageid)
 }
 
+/// Checks if the syscall `syscall_num` of cage `cageid` is routed to grate `grateid`, that is,
+/// if the grate interposes on that call for the cage. Unlike `_get_handler`, a miss is not an
+/// error.
+///
+/// ## Arguments:
+/// - cageid: The ID of the cage whose handler table is looked up.
+/// - syscall_num: The number of the syscall.
+/// - grateid: The ID of the grate expected to handle it.
+///
+/// ## Returns:
+/// true if the cage has a handler for the syscall in that grate.
+/// false otherwise.
+pub fn _check_grate_handles_syscall(cageid: u64, syscall_num: u64, grateid: u64) -> bool {
+    HANDLERTABLE
+        .get(&cageid)
+        .and_then(|call_map| {
+            call_map
+                .get(&syscall_num)
+                .map(|target_map| target_map.contains_key(&grateid))
+        })
+        .unwrap_or(false)
+}
+
 /// Lookup the interposed handler for a given (self_cageid, syscall_num, target_cageid).
 ///
 /// 1. The lookup path is:
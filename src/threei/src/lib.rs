@@ -1,4 +1,5 @@
 pub mod handler_table;
+pub mod record;
 pub mod threei;
 pub mod threei_const;
 
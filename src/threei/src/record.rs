@@ -0,0 +1,693 @@
+//! Syscall record and replay
+//!
+//! With `lind-boot --record FILE`, every syscall a cage makes through `make_syscall` is logged
+//! per cage and thread: its number, arguments and result, and the bytes it wrote into the memory
+//! of the cage (read buffers, stat structs, received messages, ...). The signals delivered to a
+//! cage are logged as well, at the epoch callback that runs their handlers.
+//!
+//! With `lind-boot --replay FILE`, the same binary is run against such a log. Its syscalls do not
+//! touch the host: each one takes the next record of its cage and thread, writes the logged bytes
+//! back into memory and returns the logged result. Only the calls that change the state of the
+//! runtime itself rather than of the host are run for real, see `runs_for_real`: the memory
+//! layout, signal dispositions, futexes and the cage lifecycle. A forked child may get another
+//! cage id than when it was recorded, so the real id is mapped onto the recorded one, which is
+//! what the parent is told. A logged signal is raised again right after the syscall it followed,
+//! so it is delivered at the same syscall boundary, but not necessarily at the same instruction.
+//! A replay that makes a different syscall than the log has next panics.
+//!
+//! The log starts with `LOG_MAGIC` and `LOG_VERSION`, followed by the records, see
+//! `Record::encode`. Every record is written with a single `write()`, so the log of a run that
+//! crashed is complete up to the crash.
+use crate::threei::make_syscall;
+use cage::get_cage;
+use cage::memory::check_addr_read;
+use cage::signal::lind_send_signal;
+use dashmap::DashMap;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use sysdefs::constants::err_const::Errno;
+use sysdefs::constants::fs_const::{F_GETLK, F_GETOWN_EX, TIOCGWINSZ};
+use sysdefs::data::fs_struct::{FOwnerEx, FSData, Rusage, StatData};
+use sysdefs::data::sys_struct::UtsNameStruct;
+use typemap::datatype_conversion::sc_convert_iovec;
+use typemap::network_helpers::{convert_guest_mmsghdr, GUEST_MMSGHDR_SIZE};
+
+/// First bytes of a log
+pub const LOG_MAGIC: &[u8; 8] = b"LINDREC\0";
+/// Version of the log format, stored after `LOG_MAGIC`
+pub const LOG_VERSION: u32 = 1;
+
+const TAG_SYSCALL: u8 = 0;
+const TAG_SIGNAL: u8 = 1;
+
+const CLONE_SYSCALL: u64 = 56;
+const FORK_SYSCALL: u64 = 57;
+const NANOSLEEP_SYSCALL: u64 = 35;
+const CLOCK_NANOSLEEP_SYSCALL: u64 = 230;
+const TIME_SYSCALL: u64 = 201;
+const TIMES_SYSCALL: u64 = 100;
+
+/// Bytes a syscall wrote at `offset` into the memory of the cage
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemWrite {
+    pub offset: u64,
+    pub bytes: Vec<u8>,
+}
+
+/// An entry of the log
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Record {
+    Syscall {
+        cageid: u64,
+        tid: u64,
+        num: u64,
+        args: [u64; 6],
+        ret: i32,
+        writes: Vec<MemWrite>,
+    },
+    Signal {
+        cageid: u64,
+        tid: u64,
+        signo: i32,
+    },
+}
+
+impl Record {
+    fn key(&self) -> (u64, u64) {
+        match *self {
+            Record::Syscall { cageid, tid, .. } | Record::Signal { cageid, tid, .. } => {
+                (cageid, tid)
+            }
+        }
+    }
+
+    /// Append the record to `out`: a tag byte, followed by the fields as LEB128 varints, with
+    /// the signed ones zigzag encoded first. A memory write is its offset and length, followed
+    /// by the bytes.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Record::Syscall {
+                cageid,
+                tid,
+                num,
+                args,
+                ret,
+                writes,
+            } => {
+                out.push(TAG_SYSCALL);
+                for value in [*cageid, *tid, *num].iter().chain(args) {
+                    put_varint(out, *value);
+                }
+                put_varint(out, zigzag(*ret));
+                put_varint(out, writes.len() as u64);
+                for write in writes {
+                    put_varint(out, write.offset);
+                    put_varint(out, write.bytes.len() as u64);
+                    out.extend_from_slice(&write.bytes);
+                }
+            }
+            Record::Signal { cageid, tid, signo } => {
+                out.push(TAG_SIGNAL);
+                put_varint(out, *cageid);
+                put_varint(out, *tid);
+                put_varint(out, zigzag(*signo));
+            }
+        }
+    }
+
+    /// Take the next record off the front of `input`.
+    ///
+    /// ## Returns:
+    /// - `None` if `input` doesn't start with a complete record
+    pub fn decode(input: &mut &[u8]) -> Option<Record> {
+        let (&tag, rest) = input.split_first()?;
+        *input = rest;
+        match tag {
+            TAG_SYSCALL => {
+                let cageid = get_varint(input)?;
+                let tid = get_varint(input)?;
+                let num = get_varint(input)?;
+                let mut args = [0; 6];
+                for arg in &mut args {
+                    *arg = get_varint(input)?;
+                }
+                let ret = unzigzag(get_varint(input)?);
+                let count = get_varint(input)?;
+                let mut writes = Vec::new();
+                for _ in 0..count {
+                    let offset = get_varint(input)?;
+                    let len = usize::try_from(get_varint(input)?).ok()?;
+                    if len > input.len() {
+                        return None;
+                    }
+                    let (bytes, rest) = input.split_at(len);
+                    *input = rest;
+                    writes.push(MemWrite {
+                        offset,
+                        bytes: bytes.to_vec(),
+                    });
+                }
+                Some(Record::Syscall {
+                    cageid,
+                    tid,
+                    num,
+                    args,
+                    ret,
+                    writes,
+                })
+            }
+            TAG_SIGNAL => Some(Record::Signal {
+                cageid: get_varint(input)?,
+                tid: get_varint(input)?,
+                signo: unzigzag(get_varint(input)?),
+            }),
+            _ => None,
+        }
+    }
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn get_varint(input: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for (i, &byte) in input.iter().enumerate().take(10) {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            *input = &input[i + 1..];
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn zigzag(value: i32) -> u64 {
+    ((value << 1) ^ (value >> 31)) as u32 as u64
+}
+
+fn unzigzag(value: u64) -> i32 {
+    let value = value as u32;
+    ((value >> 1) as i32) ^ -((value & 1) as i32)
+}
+
+/// Parse a log into its records. A record cut short at the end, by a crash while it was being
+/// written, is dropped.
+///
+/// ## Returns:
+/// - `Err(EINVAL)` if `log` doesn't start with the header of this version
+pub fn parse_log(log: &[u8]) -> Result<Vec<Record>, Errno> {
+    let header = LOG_MAGIC.len() + 4;
+    if log.len() < header
+        || &log[..LOG_MAGIC.len()] != LOG_MAGIC
+        || log[LOG_MAGIC.len()..header] != LOG_VERSION.to_le_bytes()
+    {
+        return Err(Errno::EINVAL);
+    }
+    let mut input = &log[header..];
+    let mut records = Vec::new();
+    while let Some(record) = Record::decode(&mut input) {
+        records.push(record);
+    }
+    Ok(records)
+}
+
+struct Replayer {
+    /// The records not replayed yet, per recorded cage id and thread
+    streams: DashMap<(u64, u64), VecDeque<Record>>,
+    /// The recorded id of each forked cage, by its real id
+    cages: DashMap<u64, u64>,
+    /// The recorded child id of a `clone()` in progress, by the real cage id and thread
+    clones: DashMap<(u64, u64), u64>,
+}
+
+enum Mode {
+    Record(Mutex<File>),
+    Replay(Replayer),
+}
+
+static MODE: OnceLock<Mode> = OnceLock::new();
+
+fn io_errno(e: std::io::Error) -> Errno {
+    e.raw_os_error()
+        .and_then(|errno| Errno::from_discriminant(errno).ok())
+        .unwrap_or(Errno::EIO)
+}
+
+/// Start recording the syscalls of all cages to the log at `path`. Has to be called before the
+/// first cage runs.
+///
+/// ## Returns:
+/// - `Ok(())` on success
+/// - `Err(EBUSY)` if recording or replaying was set up already
+/// - the error of creating the log otherwise
+pub fn record_init(path: &str) -> Result<(), Errno> {
+    if MODE.get().is_some() {
+        return Err(Errno::EBUSY);
+    }
+    let mut log = File::create(path).map_err(io_errno)?;
+    let mut header = LOG_MAGIC.to_vec();
+    header.extend_from_slice(&LOG_VERSION.to_le_bytes());
+    log.write_all(&header).map_err(io_errno)?;
+    MODE.set(Mode::Record(Mutex::new(log)))
+        .map_err(|_| Errno::EBUSY)
+}
+
+/// Replay the syscalls of all cages from the log at `path`. Has to be called before the first
+/// cage runs.
+///
+/// ## Returns:
+/// - `Ok(())` on success
+/// - `Err(EBUSY)` if recording or replaying was set up already
+/// - `Err(EINVAL)` if the file isn't a log
+/// - the error of reading the log otherwise
+pub fn replay_init(path: &str) -> Result<(), Errno> {
+    if MODE.get().is_some() {
+        return Err(Errno::EBUSY);
+    }
+    let streams: DashMap<(u64, u64), VecDeque<Record>> = DashMap::new();
+    for record in parse_log(&std::fs::read(path).map_err(io_errno)?)? {
+        streams.entry(record.key()).or_default().push_back(record);
+    }
+    MODE.set(Mode::Replay(Replayer {
+        streams,
+        cages: DashMap::new(),
+        clones: DashMap::new(),
+    }))
+    .map_err(|_| Errno::EBUSY)
+}
+
+pub fn record_active() -> bool {
+    matches!(MODE.get(), Some(Mode::Record(_)))
+}
+
+pub fn replay_active() -> bool {
+    matches!(MODE.get(), Some(Mode::Replay(_)))
+}
+
+fn append(log: &Mutex<File>, record: &Record) {
+    let mut bytes = Vec::new();
+    record.encode(&mut bytes);
+    if let Err(e) = log.lock().unwrap().write_all(&bytes) {
+        panic!("cannot write the syscall log: {}", e);
+    }
+}
+
+/// The syscalls that replay runs for real: they change the memory of the cage, its signal
+/// dispositions or the cages themselves, which are all part of the runtime rather than the host.
+/// `clone()` and `fork()` have their result handled by `clone_returned`.
+fn runs_for_real(num: u64) -> bool {
+    matches!(
+        num,
+        9 | 10 | 11 | 12 // mmap, mprotect, munmap, brk
+        | 13 | 14 // sigaction, sigprocmask
+        | 24 // sched_yield
+        | 25 | 26 | 27 | 28 // mremap, msync, mincore, madvise
+        | 29 | 30 | 31 | 67 // shmget, shmat, shmctl, shmdt
+        | CLONE_SYSCALL | FORK_SYSCALL
+        | 59 | 60 // exec, exit
+        | 202 // futex
+    )
+}
+
+/// Where a syscall writes into the memory of the cage, worked out before the call
+enum Output {
+    /// `len` bytes at `ptr`
+    Bytes(u64, usize),
+    /// Up to `cap` bytes at `ptr`, as many as the syscall result
+    UpToRet(u64, usize),
+    /// As many elements of `size` bytes at `ptr` as the syscall result
+    RetTimes(u64, usize),
+    /// The buffers of a scatter read, filled in order up to the length the syscall returned, or
+    /// the one it stored at `len_ptr` if there is one
+    Iovecs(Vec<libc::iovec>, Option<u64>),
+}
+
+/// The outputs of syscall `num` with arguments `args`, a host pointer for each buffer. Only
+/// what the guest can observe is listed: a syscall missing here returns nothing but its result.
+fn outputs(num: u64, args: &[u64; 6], cageid: u64, base: u64) -> Vec<Output> {
+    use Output::*;
+    let readable =
+        |ptr: u64, len: usize| ptr >= base && check_addr_read(cageid, ptr - base, len).is_ok();
+    let deref = |ptr: u64| {
+        if readable(ptr, 4) {
+            unsafe { (ptr as *const u32).read_unaligned() as usize }
+        } else {
+            0
+        }
+    };
+    // a socket address and the length stored after it
+    let sockaddr = |addr: u64, len: u64| vec![Bytes(addr, deref(len)), Bytes(len, 4)];
+    match num {
+        // read, pread, getdents, readlink, readlinkat, getrandom
+        0 | 17 | 78 => vec![UpToRet(args[1], args[2] as usize)],
+        89 => vec![UpToRet(args[1], args[2] as usize)],
+        267 => vec![UpToRet(args[2], args[3] as usize)],
+        318 => vec![UpToRet(args[0], args[1] as usize)],
+        // stat, fstat, statfs, fstatfs
+        4 | 5 => vec![Bytes(args[1], size_of::<StatData>())],
+        137 | 138 => vec![Bytes(args[1], size_of::<FSData>())],
+        // pipe, pipe2, socketpair
+        22 | 293 => vec![Bytes(args[0], 8)],
+        53 => vec![Bytes(args[3], 8)],
+        // select, pselect6
+        23 | 270 => (1..4).map(|i| Bytes(args[i], 128)).collect(),
+        // poll, ppoll
+        7 | 271 => vec![Bytes(args[0], 8 * args[1] as usize)],
+        // epoll_wait, epoll_pwait, epoll_pwait2
+        232 | 281 | 441 => vec![RetTimes(args[1], 8)],
+        // nanosleep, clock_nanosleep, getitimer, setitimer, timer_create, timer_settime,
+        // timer_gettime, clock_gettime, clock_getres
+        NANOSLEEP_SYSCALL | CLOCK_NANOSLEEP_SYSCALL => vec![Bytes(args[3], 16)],
+        36 => vec![Bytes(args[1], 32)],
+        38 => vec![Bytes(args[2], 32)],
+        222 => vec![Bytes(args[2], 4)],
+        223 => vec![Bytes(args[3], 32)],
+        224 => vec![Bytes(args[1], 32)],
+        228 | 229 => vec![Bytes(args[1], 16)],
+        // gettimeofday, time, times, getrusage
+        96 => vec![Bytes(args[0], 16), Bytes(args[1], 8)],
+        TIME_SYSCALL => vec![Bytes(args[0], 8)],
+        TIMES_SYSCALL => vec![Bytes(args[0], 32)],
+        98 => vec![Bytes(args[1], size_of::<Rusage>())],
+        // getcwd, uname, gethostname
+        79 | 170 => vec![Bytes(args[0], args[1] as usize)],
+        63 => vec![Bytes(args[0], size_of::<UtsNameStruct>())],
+        // waitpid
+        61 => vec![Bytes(args[1], 4), Bytes(args[3], size_of::<Rusage>())],
+        // accept, accept4, getsockname, getpeername
+        43 | 288 | 51 | 52 => sockaddr(args[1], args[2]),
+        // recvfrom, getsockopt
+        45 => {
+            let mut outs = vec![UpToRet(args[1], args[2] as usize)];
+            outs.extend(sockaddr(args[4], args[5]));
+            outs
+        }
+        55 => sockaddr(args[3], args[4]),
+        // fcntl(F_GETLK), fcntl(F_GETOWN_EX), ioctl(TIOCGWINSZ)
+        72 if args[1] as i32 == F_GETLK => vec![Bytes(args[3], size_of::<libc::flock>())],
+        72 if args[1] as i32 == F_GETOWN_EX => vec![Bytes(args[3], size_of::<FOwnerEx>())],
+        16 if args[1] == TIOCGWINSZ as u64 => vec![Bytes(args[2], size_of::<libc::winsize>())],
+        // readv, preadv
+        19 | 295 => match sc_convert_iovec(args[1], cageid, args[2] as i32, cageid) {
+            Ok(iov) => vec![Iovecs(iov, None)],
+            Err(_) => vec![],
+        },
+        // sendfile, splice, copy_file_range
+        40 => vec![Bytes(args[2], 8)],
+        275 | 326 => vec![Bytes(args[1], 8), Bytes(args[3], 8)],
+        // recvmsg: glibc already translated the msghdr into the host layout
+        47 if readable(args[1], size_of::<libc::msghdr>()) => {
+            let msg = unsafe { &*(args[1] as *const libc::msghdr) };
+            let iov = if msg.msg_iov.is_null() {
+                vec![]
+            } else {
+                unsafe { std::slice::from_raw_parts(msg.msg_iov, msg.msg_iovlen) }.to_vec()
+            };
+            vec![
+                Bytes(args[1], size_of::<libc::msghdr>()),
+                Bytes(msg.msg_name as u64, msg.msg_namelen as usize),
+                Bytes(msg.msg_control as u64, msg.msg_controllen),
+                Iovecs(iov, None),
+            ]
+        }
+        // recvmmsg, sendmmsg: the guest layout, the kernel fills in `msg_len` of each message
+        299 | 307 => {
+            let vlen = args[2] as usize;
+            let mut outs = vec![Bytes(args[1], vlen * GUEST_MMSGHDR_SIZE)];
+            if num == 299 {
+                for (i, msg) in convert_guest_mmsghdr(args[1], cageid, vlen, cageid)
+                    .unwrap_or_default()
+                    .iter()
+                    .enumerate()
+                {
+                    let hdr = &msg.hdr;
+                    let msg_len = args[1] + ((i + 1) * GUEST_MMSGHDR_SIZE - 4) as u64;
+                    let iov = unsafe { std::slice::from_raw_parts(hdr.msg_iov, hdr.msg_iovlen) };
+                    outs.push(Bytes(hdr.msg_name as u64, hdr.msg_namelen as usize));
+                    outs.push(Bytes(hdr.msg_control as u64, hdr.msg_controllen));
+                    outs.push(Iovecs(iov.to_vec(), Some(msg_len)));
+                }
+            }
+            outs
+        }
+        _ => vec![],
+    }
+}
+
+/// Copy the bytes syscall `num` wrote at `outputs` now that it returned `ret`
+fn capture(num: u64, outputs: Vec<Output>, ret: i32, base: u64) -> Vec<MemWrite> {
+    // A sleep interrupted by a signal still stores the time left, and time() and times()
+    // return clock readings, which may look negative
+    let failed = ret < 0
+        && match num {
+            NANOSLEEP_SYSCALL | CLOCK_NANOSLEEP_SYSCALL => ret != -(Errno::EINTR as i32),
+            TIME_SYSCALL | TIMES_SYSCALL => false,
+            _ => true,
+        };
+    if failed {
+        return vec![];
+    }
+    let ret = ret.max(0) as usize;
+    let mut writes = Vec::new();
+    let mut copy = |ptr: u64, len: usize| {
+        if ptr >= base && len > 0 {
+            writes.push(MemWrite {
+                offset: ptr - base,
+                bytes: unsafe { std::slice::from_raw_parts(ptr as *const u8, len) }.to_vec(),
+            });
+        }
+    };
+    for output in outputs {
+        match output {
+            Output::Bytes(ptr, len) => copy(ptr, len),
+            Output::UpToRet(ptr, cap) => copy(ptr, ret.min(cap)),
+            Output::RetTimes(ptr, size) => copy(ptr, ret * size),
+            Output::Iovecs(iov, len_ptr) => {
+                let mut left = match len_ptr {
+                    Some(ptr) => unsafe { (ptr as *const u32).read_unaligned() as usize },
+                    None => ret,
+                };
+                for buf in iov {
+                    let len = buf.iov_len.min(left);
+                    copy(buf.iov_base as u64, len);
+                    left -= len;
+                }
+            }
+        }
+    }
+    writes
+}
+
+fn memory_base(cageid: u64) -> u64 {
+    get_cage(cageid)
+        .and_then(|cage| cage.vmmap.read().base_address)
+        .unwrap_or(0) as u64
+}
+
+impl Replayer {
+    /// The recorded id of cage `cageid`
+    fn recorded_cage(&self, cageid: u64) -> u64 {
+        self.cages.get(&cageid).map_or(cageid, |id| *id)
+    }
+
+    /// Take the next record of a thread, which has to be syscall `num`
+    fn next_syscall(&self, cageid: u64, tid: u64, num: u64) -> Record {
+        let key = (self.recorded_cage(cageid), tid);
+        let record = self
+            .streams
+            .get_mut(&key)
+            .and_then(|mut stream| stream.pop_front());
+        match record {
+            Some(record @ Record::Syscall { num: logged, .. }) if logged == num => record,
+            record => panic!(
+                "replay of cage {} thread {} diverged: syscall {} made, {:?} logged",
+                key.0, key.1, num, record
+            ),
+        }
+    }
+
+    /// Remember the child id the log has for the `clone()` thread `tid` of cage `cageid` is
+    /// about to make, for `fork_child`
+    fn expect_clone(&self, cageid: u64, tid: u64) {
+        let key = (self.recorded_cage(cageid), tid);
+        let logged = self
+            .streams
+            .get(&key)
+            .and_then(|stream| match stream.front() {
+                Some(&Record::Syscall { ret, .. }) => Some(ret),
+                _ => None,
+            });
+        if let Some(ret) = logged {
+            self.clones.insert((cageid, tid), ret as u64);
+        }
+    }
+
+    /// Raise the signals logged right after the last syscall of a thread
+    fn raise_signals(&self, cageid: u64, tid: u64) {
+        let key = (self.recorded_cage(cageid), tid);
+        let Some(mut stream) = self.streams.get_mut(&key) else {
+            return;
+        };
+        while let Some(&Record::Signal { signo, .. }) = stream.front() {
+            stream.pop_front();
+            lind_send_signal(cageid, signo);
+        }
+    }
+}
+
+/// `make_syscall` for a syscall made by thread `tid` of cage `self_cageid`, which records or
+/// replays it in those modes
+#[allow(clippy::too_many_arguments)]
+pub fn traced_syscall(
+    tid: u64,
+    self_cageid: u64,
+    syscall_num: u64,
+    syscall_name: u64,
+    target_cageid: u64,
+    arg1: u64,
+    arg1_cageid: u64,
+    arg2: u64,
+    arg2_cageid: u64,
+    arg3: u64,
+    arg3_cageid: u64,
+    arg4: u64,
+    arg4_cageid: u64,
+    arg5: u64,
+    arg5_cageid: u64,
+    arg6: u64,
+    arg6_cageid: u64,
+) -> i32 {
+    let call = || {
+        make_syscall(
+            self_cageid,
+            syscall_num,
+            syscall_name,
+            target_cageid,
+            arg1,
+            arg1_cageid,
+            arg2,
+            arg2_cageid,
+            arg3,
+            arg3_cageid,
+            arg4,
+            arg4_cageid,
+            arg5,
+            arg5_cageid,
+            arg6,
+            arg6_cageid,
+        )
+    };
+    let args = [arg1, arg2, arg3, arg4, arg5, arg6];
+    let mode = MODE.get();
+    // the result of clone() is only known once it returns to both sides, see `clone_returned`
+    if matches!(syscall_num, CLONE_SYSCALL | FORK_SYSCALL) {
+        if let Some(Mode::Replay(replayer)) = mode {
+            replayer.expect_clone(self_cageid, tid);
+        }
+        return call();
+    }
+    match mode {
+        None => call(),
+        Some(Mode::Record(log)) => {
+            let base = memory_base(self_cageid);
+            let outputs = outputs(syscall_num, &args, self_cageid, base);
+            let ret = call();
+            let writes = capture(syscall_num, outputs, ret, base);
+            append(
+                log,
+                &Record::Syscall {
+                    cageid: self_cageid,
+                    tid,
+                    num: syscall_num,
+                    args,
+                    ret,
+                    writes,
+                },
+            );
+            ret
+        }
+        Some(Mode::Replay(replayer)) => {
+            replayer.raise_signals(self_cageid, tid);
+            let record = replayer.next_syscall(self_cageid, tid, syscall_num);
+            let ret = if runs_for_real(syscall_num) {
+                call()
+            } else {
+                let Record::Syscall { ret, writes, .. } = record else {
+                    unreachable!();
+                };
+                let base = memory_base(self_cageid);
+                for write in writes {
+                    unsafe {
+                        std::ptr::copy_nonoverlapping(
+                            write.bytes.as_ptr(),
+                            (base + write.offset) as *mut u8,
+                            write.bytes.len(),
+                        );
+                    }
+                }
+                ret
+            };
+            replayer.raise_signals(self_cageid, tid);
+            ret
+        }
+    }
+}
+
+/// Called by `fork_syscall` once it allocated `child_cageid` for a fork of thread `parent_tid`
+/// of `parent_cageid`, before the child runs. In replay mode, the child is mapped onto the cage
+/// id it had in the log.
+pub fn fork_child(parent_cageid: u64, parent_tid: u64, child_cageid: u64) {
+    if let Some(Mode::Replay(replayer)) = MODE.get() {
+        if let Some((_, recorded)) = replayer.clones.remove(&(parent_cageid, parent_tid)) {
+            replayer.cages.insert(child_cageid, recorded);
+        }
+    }
+}
+
+/// Called when `clone()` returns `ret` to thread `tid` of cage `cageid`, the parent as well as
+/// the child.
+///
+/// ## Returns:
+/// - the result to hand to the guest: the logged one in replay mode, `ret` otherwise
+pub fn clone_returned(cageid: u64, tid: u64, num: u64, ret: i32) -> i32 {
+    match MODE.get() {
+        None => ret,
+        Some(Mode::Record(log)) => {
+            append(
+                log,
+                &Record::Syscall {
+                    cageid,
+                    tid,
+                    num,
+                    args: [0; 6],
+                    ret,
+                    writes: vec![],
+                },
+            );
+            ret
+        }
+        Some(Mode::Replay(replayer)) => {
+            replayer.clones.remove(&(cageid, tid));
+            let Record::Syscall { ret, .. } = replayer.next_syscall(cageid, tid, num) else {
+                unreachable!();
+            };
+            replayer.raise_signals(cageid, tid);
+            ret
+        }
+    }
+}
+
+/// Called by the epoch callback of thread `tid` of cage `cageid` before it runs the handler of
+/// signal `signo`. Logs the delivery in record mode.
+pub fn record_signal(cageid: u64, tid: u64, signo: i32) {
+    if let Some(Mode::Record(log)) = MODE.get() {
+        append(log, &Record::Signal { cageid, tid, signo });
+    }
+}
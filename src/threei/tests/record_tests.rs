@@ -0,0 +1,80 @@
+use threei::record::{parse_log, MemWrite, Record, LOG_MAGIC, LOG_VERSION};
+
+fn header() -> Vec<u8> {
+    let mut log = LOG_MAGIC.to_vec();
+    log.extend_from_slice(&LOG_VERSION.to_le_bytes());
+    log
+}
+
+fn sample() -> Vec<Record> {
+    vec![
+        Record::Syscall {
+            cageid: 1,
+            tid: 1,
+            num: 0,
+            args: [3, 0x7f00_0000_1000, 4096, 0, u64::MAX, 0],
+            ret: 5,
+            writes: vec![MemWrite {
+                offset: 0x1000,
+                bytes: b"hello".to_vec(),
+            }],
+        },
+        Record::Signal {
+            cageid: 1,
+            tid: 1,
+            signo: 14,
+        },
+        Record::Syscall {
+            cageid: 2,
+            tid: 3,
+            num: 43,
+            args: [0; 6],
+            ret: -11,
+            writes: vec![],
+        },
+        Record::Syscall {
+            cageid: 2,
+            tid: 3,
+            num: 56,
+            args: [0; 6],
+            ret: i32::MIN,
+            writes: vec![
+                MemWrite {
+                    offset: 0,
+                    bytes: vec![],
+                },
+                MemWrite {
+                    offset: u32::MAX as u64,
+                    bytes: vec![0xff; 300],
+                },
+            ],
+        },
+    ]
+}
+
+#[test]
+fn records_survive_encoding() {
+    let mut log = header();
+    for record in sample() {
+        record.encode(&mut log);
+    }
+    assert_eq!(parse_log(&log).unwrap(), sample());
+}
+
+#[test]
+fn truncated_record_is_dropped() {
+    let mut log = header();
+    for record in sample() {
+        record.encode(&mut log);
+    }
+    log.pop();
+    let records = parse_log(&log).unwrap();
+    assert_eq!(records, sample()[..3]);
+}
+
+#[test]
+fn log_without_header_is_rejected() {
+    assert!(parse_log(b"").is_err());
+    assert!(parse_log(b"LINDREC\0\x02\0\0\0").is_err());
+    assert!(parse_log(&header()).unwrap().is_empty());
+}
@@ -10,11 +10,13 @@ use crate::cage_helpers::validate_cageid;
 use cage::get_cage;
 use std::error::Error;
 use std::os::raw::c_char;
+use sysdefs::constants::fs_const::IOV_MAX;
 use sysdefs::constants::lind_platform_const::{MAX_CAGEID, PATH_MAX};
 use sysdefs::constants::lind_platform_const::{UNUSED_ARG, UNUSED_ID, UNUSED_NAME};
 use sysdefs::constants::Errno;
 use sysdefs::data::fs_struct::{
-    FSData, ITimerVal, PipeArray, ShmidsStruct, SigactionStruct, SigsetType, StatData,
+    FSData, ITimerVal, PipeArray, Rusage, ShmidsStruct, SigactionStruct, SigsetType, StackStruct,
+    StatData,
 };
 
 /// `sc_unusedarg()` is the security check function used to validate all unused args. This
@@ -407,6 +409,66 @@ pub fn sc_convert_sigactionStruct_mut<'a>(
     unsafe { Some(&mut *ptr) }
 }
 
+/// Convert a user-provided pointer (u64) from a cage into a shared reference to
+/// a `StackStruct`, the `stack_t` of `sigaltstack()`.
+///
+/// # Arguments
+/// * `ss_arg` - The raw user pointer (u64). If `0`, this means "no struct".
+/// * `ss_arg_cageid` - The cage ID in which the pointer resides.
+/// * `cageid` - The caller’s cage ID (can be used for cross-cage checks).
+///
+/// # Returns
+/// * `Some(&StackStruct)` if the pointer is nonzero and translation succeeds.
+/// * `None` if `ss_arg == 0`.
+pub fn sc_convert_addr_to_stack<'a>(
+    ss_arg: u64,
+    ss_arg_cageid: u64,
+    cageid: u64,
+) -> Option<&'a StackStruct> {
+    #[cfg(feature = "secure")]
+    {
+        if !validate_cageid(ss_arg_cageid, cageid) {
+            return None;
+        }
+    }
+    if ss_arg == 0 {
+        return None;
+    }
+
+    let ptr = ss_arg as *const StackStruct;
+    unsafe { Some(&*ptr) }
+}
+
+/// Convert a user-provided pointer (u64) from a cage into a mutable reference to
+/// a `StackStruct`.
+///
+/// # Arguments
+/// * `ss_arg` - The raw user pointer (u64). If `0`, this means "no struct".
+/// * `ss_arg_cageid` - The cage ID in which the pointer resides.
+/// * `cageid` - The caller’s cage ID (can be used for cross-cage checks).
+///
+/// # Returns
+/// * `Some(&mut StackStruct)` if the pointer is nonzero and translation succeeds.
+/// * `None` if `ss_arg == 0`.
+pub fn sc_convert_addr_to_stack_mut<'a>(
+    ss_arg: u64,
+    ss_arg_cageid: u64,
+    cageid: u64,
+) -> Option<&'a mut StackStruct> {
+    #[cfg(feature = "secure")]
+    {
+        if !validate_cageid(ss_arg_cageid, cageid) {
+            return None;
+        }
+    }
+    if ss_arg == 0 {
+        return None;
+    }
+
+    let ptr = ss_arg as *mut StackStruct;
+    unsafe { Some(&mut *ptr) }
+}
+
 /// Convert a user-provided pointer (u64) from a cage into a mutable reference to
 /// a `SigsetType`.
 ///
@@ -578,6 +640,35 @@ pub fn sc_convert_itimerval_mut(
     }
 }
 
+/// Translates an optional user-provided `struct rusage` address into a mutable
+/// reference to a `Rusage`.
+///
+/// `wait4()` takes the rusage buffer as an optional argument, while plain
+/// `waitpid()` leaves the slot unused, so both `0` and `UNUSED_ARG` mean "not
+/// requested".
+///
+/// # Returns
+/// * `Some(&mut Rusage)` if a buffer was passed.
+/// * `None` if the argument is `0` or `UNUSED_ARG`.
+pub fn sc_convert_addr_to_rusage(
+    arg: u64,
+    arg_cageid: u64,
+    cageid: u64,
+) -> Option<&'static mut Rusage> {
+    #[cfg(feature = "secure")]
+    {
+        if !validate_cageid(arg_cageid, cageid) {
+            panic!("Invalid Cage ID");
+        }
+    }
+
+    if is_unused(arg, UNUSED_ARG) {
+        None
+    } else {
+        Some(unsafe { &mut *(arg as *mut Rusage) })
+    }
+}
+
 /// `sc_convert_addr_to_statdata` translates a user-provided address from the
 /// calling Cage's virtual memory into a mutable reference to a `StatData`
 /// structure.
@@ -707,3 +798,65 @@ pub fn sc_convert_arg_nullity(arg: u64, arg_cageid: u64, cageid: u64) -> bool {
 
     (arg as *const u8).is_null()
 }
+
+/// Size of a guest `struct iovec`. On wasm32 `iov_base` and `iov_len` are 32 bits
+/// wide, and each is followed by a padding word so that the struct has the host's
+/// size; the padding words are not written by guest code and are ignored.
+const GUEST_IOVEC_SIZE: usize = 16;
+
+/// Translates a guest `struct iovec` array into host iovecs.
+///
+/// `iov_arg` is the array itself (already a host pointer), but the `iov_base`
+/// fields in it are still guest addresses. They are rebased onto the linear
+/// memory of `iov_cageid`; a NULL base stays NULL.
+///
+/// ## Returns:
+///  - `Ok(iovecs)` with `iovcnt` entries on success.
+///  - `Err(EINVAL)` if `iovcnt` is negative or larger than `IOV_MAX`.
+///  - `Err(EFAULT)` if the array pointer is NULL while `iovcnt` is not 0.
+pub fn sc_convert_iovec(
+    iov_arg: u64,
+    iov_cageid: u64,
+    iovcnt: i32,
+    cageid: u64,
+) -> Result<Vec<libc::iovec>, Errno> {
+    #[cfg(feature = "secure")]
+    {
+        if !validate_cageid(iov_cageid, cageid) {
+            return Err(Errno::EFAULT);
+        }
+    }
+
+    if !(0..=IOV_MAX).contains(&iovcnt) {
+        return Err(Errno::EINVAL);
+    }
+    if iovcnt == 0 {
+        return Ok(Vec::new());
+    }
+    if iov_arg == 0 {
+        return Err(Errno::EFAULT);
+    }
+
+    let base_addr = match get_cage(iov_cageid) {
+        Some(cage) => cage.vmmap.read().base_address.unwrap() as u64,
+        None => return Err(Errno::EFAULT),
+    };
+
+    let guest = iov_arg as *const u8;
+    let iovs = (0..iovcnt as usize)
+        .map(|i| {
+            let entry = unsafe { guest.add(i * GUEST_IOVEC_SIZE) };
+            let base = unsafe { (entry as *const u32).read_unaligned() } as u64;
+            let len = unsafe { (entry.add(8) as *const u32).read_unaligned() } as usize;
+            libc::iovec {
+                iov_base: if base == 0 {
+                    std::ptr::null_mut()
+                } else {
+                    (base_addr + base) as *mut libc::c_void
+                },
+                iov_len: len,
+            }
+        })
+        .collect();
+    Ok(iovs)
+}
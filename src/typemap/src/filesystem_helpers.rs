@@ -43,3 +43,31 @@ pub fn convert_fstatdata_to_user(stat_ptr: &mut FSData, libc_databuf: libc::stat
     stat_ptr.f_frsize = 4096;
     stat_ptr.f_spare = [0; 32];
 }
+
+/// Size of the `linux_dirent64` record for `name`: the fixed header (`d_ino`, `d_off`,
+/// `d_reclen`, `d_type`) plus the NUL-terminated name, padded to 8 bytes.
+pub fn dirent64_reclen(name: &str) -> usize {
+    (19 + name.len() + 1 + 7) & !7
+}
+
+/// Writes one `linux_dirent64` record, as returned by `getdents64`, for directories that are
+/// listed by RawPOSIX itself rather than by the host kernel.
+///
+/// ## Arguments:
+/// - `dst`: Destination, with room for at least `dirent64_reclen(name)` bytes.
+/// - `d_ino`, `d_off`, `d_type`, `name`: Values of the record.
+///
+/// ## Returns:
+/// The record length.
+pub fn write_dirent64(dst: *mut u8, d_ino: u64, d_off: i64, d_type: u8, name: &str) -> usize {
+    let reclen = dirent64_reclen(name);
+    unsafe {
+        std::ptr::write_bytes(dst, 0, reclen);
+        std::ptr::write_unaligned(dst as *mut u64, d_ino);
+        std::ptr::write_unaligned(dst.add(8) as *mut i64, d_off);
+        std::ptr::write_unaligned(dst.add(16) as *mut u16, reclen as u16);
+        *dst.add(18) = d_type;
+        std::ptr::copy_nonoverlapping(name.as_ptr(), dst.add(19), name.len());
+    }
+    reclen
+}
@@ -4,6 +4,7 @@
 //! host-usable pointer and to compute the correct socklen_t for Linux. It is used by
 //! our socket-related syscalls to bridge from per-cage virtual memory to host libc calls.
 use crate::cage_helpers::validate_cageid;
+use crate::datatype_conversion::sc_convert_iovec;
 use cage::get_cage;
 use libc::{
     sa_family_t, sockaddr, sockaddr_in, sockaddr_in6, sockaddr_storage, sockaddr_un, socklen_t,
@@ -209,3 +210,93 @@ pub fn convert_sockpair<'a>(
         "input data not valid",
     ));
 }
+
+/// Size of a guest `struct msghdr`. glibc gives every field a host-sized slot (a
+/// 32-bit value followed by a padding word), but the guest only fills in the low
+/// words, and its pointers are guest addresses.
+const GUEST_MSGHDR_SIZE: usize = 56;
+/// Size of a guest `struct mmsghdr`: the msghdr followed by `msg_len`. The host
+/// struct is 64 bytes, as it is padded to the msghdr's 8 byte alignment.
+pub const GUEST_MMSGHDR_SIZE: usize = GUEST_MSGHDR_SIZE + 4;
+
+/// A guest `struct msghdr` translated for the host
+pub struct HostMsghdr {
+    pub hdr: libc::msghdr,
+    // `hdr.msg_iov` points into this
+    _iov: Vec<libc::iovec>,
+}
+
+/// `convert_guest_mmsghdr` translates the guest `struct mmsghdr` array of
+/// `sendmmsg`/`recvmmsg` into host msghdrs.
+///
+/// The name and control buffers are rebased onto the cage's linear memory and
+/// passed through unchanged: the control buffer is still in the guest layout, and
+/// converting it is up to the caller. The iovecs are translated with
+/// `sc_convert_iovec`.
+///
+/// ## Returns:
+/// - `Ok(msgs)` with `vlen` entries on success.
+/// - `Err(EFAULT)` if the array pointer is NULL, `Err(EINVAL)` for an invalid
+///   `msg_iovlen`.
+pub fn convert_guest_mmsghdr(
+    vec_arg: u64,
+    vec_cageid: u64,
+    vlen: usize,
+    cageid: u64,
+) -> Result<Vec<HostMsghdr>, Errno> {
+    #[cfg(feature = "secure")]
+    {
+        if !validate_cageid(vec_cageid, cageid) {
+            return Err(Errno::EFAULT);
+        }
+    }
+
+    if vec_arg == 0 {
+        return Err(Errno::EFAULT);
+    }
+    let base_addr = match get_cage(vec_cageid) {
+        Some(cage) => cage.vmmap.read().base_address.unwrap() as u64,
+        None => return Err(Errno::EFAULT),
+    };
+    let rebase = |addr: u32| -> *mut c_void {
+        if addr == 0 {
+            ptr::null_mut()
+        } else {
+            (base_addr + addr as u64) as *mut c_void
+        }
+    };
+
+    let mut msgs = Vec::with_capacity(vlen);
+    for i in 0..vlen {
+        let entry = unsafe { (vec_arg as *const u8).add(i * GUEST_MMSGHDR_SIZE) };
+        let word = |off: usize| unsafe { (entry.add(off) as *const u32).read_unaligned() };
+
+        let mut iov =
+            sc_convert_iovec(rebase(word(16)) as u64, vec_cageid, word(24) as i32, cageid)?;
+
+        let mut hdr: libc::msghdr = unsafe { std::mem::zeroed() };
+        hdr.msg_name = rebase(word(0));
+        hdr.msg_namelen = word(8);
+        hdr.msg_iov = iov.as_mut_ptr();
+        hdr.msg_iovlen = iov.len();
+        hdr.msg_control = rebase(word(32));
+        hdr.msg_controllen = word(40) as usize;
+        hdr.msg_flags = word(48) as i32;
+        msgs.push(HostMsghdr { hdr, _iov: iov });
+    }
+    Ok(msgs)
+}
+
+/// `copy_out_mmsghdr` writes the fields the kernel updates (`msg_namelen`,
+/// `msg_controllen`, `msg_flags` and `msg_len`) back into entry `idx` of the
+/// guest `struct mmsghdr` array.
+///
+/// # Safety
+/// `vec_arg` must point to a guest mmsghdr array with more than `idx` entries.
+pub unsafe fn copy_out_mmsghdr(vec_arg: u64, idx: usize, hdr: &libc::msghdr, msg_len: u32) {
+    let entry = (vec_arg as *mut u8).add(idx * GUEST_MMSGHDR_SIZE);
+    (entry.add(8) as *mut u32).write_unaligned(hdr.msg_namelen);
+    (entry.add(40) as *mut u32).write_unaligned(hdr.msg_controllen as u32);
+    (entry.add(48) as *mut i32).write_unaligned(hdr.msg_flags);
+    (entry.add(GUEST_MSGHDR_SIZE) as *mut u32).write_unaligned(msg_len);
+}
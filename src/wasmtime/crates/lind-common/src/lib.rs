@@ -5,9 +5,7 @@ use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use sysdefs::constants::lind_platform_const;
 use sysdefs::constants::lind_platform_const::{UNUSED_ARG, UNUSED_ID};
-use threei::threei::{
-    copy_data_between_cages, copy_handler_table_to_cage, make_syscall, register_handler,
-};
+use threei::threei::{copy_data_between_cages, copy_handler_table_to_cage, register_handler};
 use threei::threei_const;
 use typemap::path_conversion::get_cstr;
 use wasmtime::Caller;
@@ -71,10 +69,31 @@ pub fn add_to_linker<
             // check here to early-return when we are on a rewind replay path.
             if call_number as i32 == CLONE_SYSCALL {
                 if let Some(rewind_res) = wasmtime_lind_multi_process::catch_rewind(&mut caller) {
-                    return rewind_res;
+                    // This is where clone() returns to the parent and the child, so it is
+                    // where its result is recorded or replayed
+                    return threei::record::clone_returned(
+                        wasmtime_lind_multi_process::current_cageid(&mut caller) as u64,
+                        wasmtime_lind_multi_process::current_tid(&mut caller) as u64,
+                        call_number as u64,
+                        rewind_res,
+                    );
                 }
             }
 
+            // A thread of a killed cage can still make a syscall before its next epoch check,
+            // e.g. when the default action of a signal tore the cage down from another thread.
+            // Its cage is gone from 3i and RawPOSIX by then, so the thread ends here instead.
+            let tid = wasmtime_lind_multi_process::current_tid(&mut caller) as u64;
+            if cage::signal::thread_is_killed(
+                wasmtime_lind_multi_process::current_cageid(&mut caller) as u64,
+                tid,
+            ) {
+                wasmtime_lind_multi_process::signal::thread_suicide();
+            }
+
+            // In deterministic mode every syscall moves the virtual clock forward by a tick
+            cage::deterministic::syscall_tick(self_cageid);
+
             // Some thread-related operations must be executed against a specific thread's
             // VMContext (e.g., pthread_create/exit). Because syscalls may be interposed/routed
             // through 3i functionality and the effective thread instance cannot be reliably derived
@@ -88,12 +107,14 @@ pub fn add_to_linker<
             let final_arg2 = if target_cageid == self_cageid
                 && matches!(call_number as i32, CLONE_SYSCALL | EXIT_SYSCALL)
             {
-                wasmtime_lind_multi_process::current_tid(&mut caller) as u64
+                tid
             } else {
                 arg2
             };
 
-            make_syscall(
+            // With `--record` or `--replay`, the syscall is logged or taken from the log
+            threei::record::traced_syscall(
+                tid,
                 self_cageid,
                 call_number as u64,
                 call_name,
@@ -27,8 +27,8 @@ use wasmtime::{
     Linker, Module, OnCalledAction, SharedMemory, Store, StoreOpaque, Val,
 };
 
-use cage::alloc_cage_id;
-use cage::signal::{lind_signal_init, lind_thread_exit};
+use cage::signal::{lind_signal_init, lind_thread_exit, signal_altstack_get, signal_mask_of};
+use cage::{alloc_cage_id, ALTSTACK_DISABLED};
 use wasmtime_environ::MemoryIndex;
 
 pub mod signal;
@@ -241,8 +241,9 @@ impl<
         let address = defined_memory.base;
         let parent_addr_len = defined_memory.current_length();
 
-        // get the stack pointer global
-        let stack_pointer = caller.get_stack_pointer().unwrap();
+        // get the stack pointer global, or in a handler on the alternate signal stack the one of
+        // the code it interrupted, see `signal::unwind_stack_pointer`
+        let stack_pointer = signal::unwind_stack_pointer(caller);
 
         // get the wasm stack top address
         let stack_low_usr = caller.as_context().get_stack_top();
@@ -295,6 +296,11 @@ impl<
 
         let parent_cageid = self.cageid;
 
+        // the child's thread starts with the signal mask of the thread that forked it, and its
+        // alternate signal stack, which is at the same address in the child's memory
+        let sigset = signal_mask_of(parent_cageid as u64, self.tid);
+        let altstack = signal_altstack_get(parent_cageid as u64, self.tid);
+
         // use the same engine for parent and child
         let engine = self.module.engine().clone();
 
@@ -378,6 +384,8 @@ impl<
                         pointer,
                         THREAD_START_ID,
                         true, /* this is the main thread */
+                        sigset,
+                        altstack,
                     );
 
                     // new cage created, increment the cage counter
@@ -467,10 +475,20 @@ impl<
 
                         let invoke_res = child_start_func.call(&mut store, &values, &mut results);
 
-                        // print errors if any when running the child process
+                        // a fault of the guest code is raised as a signal, any other error
+                        // when running the child process is printed
                         if let Err(err) = invoke_res {
-                            let e = wasi_common::maybe_exit_on_error(err);
-                            eprintln!("Error: {:?}", e);
+                            if let Err(err) = signal::trap_signal(
+                                &mut store,
+                                &instance,
+                                child_cageid,
+                                THREAD_START_ID,
+                                &lind_manager,
+                                err,
+                            ) {
+                                let e = wasi_common::maybe_exit_on_error(err);
+                                eprintln!("Error: {:?}", e);
+                            }
                             return 0;
                         }
 
@@ -538,7 +556,7 @@ impl<
         let parent_unwind_data_start_sys = parent_address as u64 + parent_unwind_data_start_usr;
 
         // get the current stack pointer
-        let stack_pointer = caller.get_stack_pointer().unwrap();
+        let stack_pointer = signal::unwind_stack_pointer(caller);
 
         let asyncify_start_unwind_func = caller.get_asyncify_start_unwind().unwrap();
 
@@ -568,6 +586,9 @@ impl<
         let mut child_host = caller.data().clone();
         // get current cageid, child should have the same cageid
         let child_cageid = self.cageid;
+        // the thread starts with the signal mask of the one creating it, and without an
+        // alternate signal stack
+        let sigset = signal_mask_of(child_cageid as u64, self.tid);
 
         // use the same engine for parent and child
         let engine = self.module.engine().clone();
@@ -683,6 +704,8 @@ impl<
                         pointer,
                         next_tid as i32,
                         false, /* this is not the main thread */
+                        sigset,
+                        ALTSTACK_DISABLED,
                     );
 
                     // The main challenge in enabling dynamic syscall interposition between grates and 3i lies in Rust’s
@@ -740,10 +763,20 @@ impl<
 
                     let invoke_res = child_start_func.call(&mut store, &values, &mut results);
 
-                    // print errors if any when running the thread
+                    // a fault of the guest code is raised as a signal, any other error
+                    // when running the thread is printed
                     if let Err(err) = invoke_res {
-                        let e = wasi_common::maybe_exit_on_error(err);
-                        eprintln!("Error: {:?}", e);
+                        if let Err(err) = signal::trap_signal(
+                            &mut store,
+                            &instance,
+                            child_cageid as u64,
+                            next_tid as i32,
+                            &lind_manager,
+                            err,
+                        ) {
+                            let e = wasi_common::maybe_exit_on_error(err);
+                            eprintln!("Error: {:?}", e);
+                        }
                         return 0;
                     }
 
@@ -896,7 +929,7 @@ impl<
         }
 
         // get the current stack pointer
-        let stack_pointer = caller.get_stack_pointer().unwrap();
+        let stack_pointer = signal::unwind_stack_pointer(caller);
 
         // start unwind
         let asyncify_start_unwind_func = caller.get_asyncify_start_unwind().unwrap();
@@ -989,7 +1022,7 @@ impl<
         let parent_unwind_data_start_sys = address as u64 + parent_unwind_data_start_usr;
 
         // get the stack pointer global
-        let stack_pointer = caller.get_stack_pointer().unwrap();
+        let stack_pointer = signal::unwind_stack_pointer(caller);
 
         // start unwind
         let asyncify_start_unwind_func = caller.get_asyncify_start_unwind().unwrap();
@@ -1047,7 +1080,7 @@ impl<
         let unwind_data_start_sys = address as u64 + unwind_data_start_usr;
 
         // get the stack pointer global
-        let stack_pointer = caller.get_stack_pointer().unwrap();
+        let stack_pointer = signal::unwind_stack_pointer(caller);
 
         // start unwind
         let asyncify_start_unwind_func = caller.get_asyncify_start_unwind().unwrap();
@@ -1086,8 +1119,15 @@ impl<
             // store the unwind data
             let hash =
                 store.store_unwind_data(unwind_data_start_sys as *const u8, rewind_total_size);
+            // next to the hash goes the number of signal handlers setjmp is called in, which
+            // is how many of them are still running once longjmp gets back here
+            let signal_depth = store.as_context().signal_asyncify_depth() as u32;
             unsafe {
                 std::ptr::write_unaligned((cloned_address + jmp_buf as u64) as *mut u64, hash);
+                std::ptr::write_unaligned(
+                    (cloned_address + jmp_buf as u64 + 8) as *mut u32,
+                    signal_depth,
+                );
             }
 
             // mark the parent to rewind state
@@ -1128,7 +1168,7 @@ impl<
         let unwind_data_start_sys = address as u64 + unwind_data_start_usr;
 
         // get the stack pointer global
-        let stack_pointer = caller.get_stack_pointer().unwrap();
+        let stack_pointer = signal::unwind_stack_pointer(caller);
 
         // start unwind
         let asyncify_start_unwind_func = caller.get_asyncify_start_unwind().unwrap();
@@ -1152,6 +1192,13 @@ impl<
         // we want to send this address to the thread
         let cloned_address = address as u64;
 
+        // the stack pointer global, to take off the alternate signal stack of a handler that
+        // longjmp leaves
+        let stack_pointer_global = caller
+            .get_export("__stack_pointer")
+            .and_then(|export| export.into_global())
+            .unwrap();
+
         // set up unwind callback function
         let store = caller.as_context_mut().0;
         store.set_on_called(Box::new(move |mut store| {
@@ -1160,6 +1207,15 @@ impl<
 
             let hash =
                 unsafe { std::ptr::read_unaligned((cloned_address + jmp_buf as u64) as *mut u64) };
+            // the signal handlers longjmp leaves will not return, so they are finished with
+            // here, see `setjmp_call`
+            let signal_depth = unsafe {
+                std::ptr::read_unaligned((cloned_address + jmp_buf as u64 + 8) as *mut u32)
+            };
+            if let Some(stack_pointer) = store.truncate_signal_asyncify_data(signal_depth as usize)
+            {
+                let _ = stack_pointer_global.set(&mut store, Val::I32(stack_pointer as i32));
+            }
             // retrieve the unwind data
             let data = store.retrieve_unwind_data(hash);
 
@@ -1,5 +1,14 @@
-use sysdefs::constants::{SIG_DFL, SIG_IGN};
-use wasmtime::{raise_trap, AsContext, AsContextMut, AsyncifyState, Caller, Trap};
+use sysdefs::constants::{
+    BUS_ADRALN, FPE_FLTINV, FPE_INTDIV, FPE_INTOVF, ILL_ILLOPC, SEGV_MAPERR, SIGBUS, SIGFPE,
+    SIGILL, SIGSEGV, SIG_DFL, SIG_IGN, SI_USER,
+};
+use wasmtime::vm::WasmFault;
+use wasmtime::{
+    raise_trap, AsContext, AsContextMut, AsyncifyState, Caller, Instance, SignalAsyncifyData,
+    Store, Trap,
+};
+use wasmtime_lind_3i::rm_vmctx;
+use wasmtime_lind_utils::LindCageManager;
 
 use crate::LindHost;
 
@@ -11,10 +20,12 @@ use crate::LindHost;
 // 1. check if epoch is triggered due to `killed` action, if it is, perform a suicide
 // 2. otherwise, retrieve the signal one by one and its handler
 // 3. if it is a default handler, we looked up the table and execute the default handler
-//    a. in case of termination, we signal all other threads in the cage to `killed` state and perform a suicide
+//    a. in case of termination, we signal all other threads in the cage to `killed` state, exit the cage
+//       for its parent to reap, and perform a suicide
 //    b. in case of ignore, we simply ignore this signal and do not do anything
 //    c. in case of stop/continue, this is currently also ignored but would possibly be a TODO to implement in the future
-// 4. otherwise if it is a custom handler, just call into glibc's signal handler directly
+// 4. otherwise if it is a custom handler, just call into glibc's signal handler directly, on the
+//    alternate signal stack of the thread if the handler was installed with SA_ONSTACK
 pub fn signal_handler<
     T: LindHost<T, U> + Clone + Send + 'static + std::marker::Sync,
     U: Clone + Send + 'static + std::marker::Sync,
@@ -31,7 +42,28 @@ pub fn signal_handler<
             .as_context_mut()
             .get_current_signal_rewind_data()
             .unwrap();
-        let _ = signal_func.call(caller.as_context_mut(), (data.signal_handler, data.signo));
+        let _ = signal_func.call(
+            caller.as_context_mut(),
+            (
+                data.signal_handler,
+                data.signo,
+                data.sa_flags,
+                data.si_code,
+                data.si_addr,
+            ),
+        );
+        // the handler unwound once more
+        if caller.as_context().get_asyncify_state() == AsyncifyState::Unwind {
+            return 0;
+        }
+        // otherwise the handler that was unwound through fork or setjmp has returned now, and is
+        // finished with as in the normal path below
+        if let Some(stack_pointer) = data.saved_stack_pointer {
+            caller.set_stack_pointer(stack_pointer).unwrap();
+        }
+        caller
+            .as_context_mut()
+            .pop_signal_asyncify_data(data.signal_handler, data.signo);
         return 0;
     }
     // otherwise, we are in normal execution and we should handle signals appropriately
@@ -39,37 +71,40 @@ pub fn signal_handler<
     let host = caller.data().clone();
     let ctx = host.get_ctx();
     let cageid = ctx.cageid as u64;
+    let threadid = ctx.tid;
 
     // first let's check if the epoch state is in "killed" state
     if cage::signal::thread_check_killed(cageid, ctx.tid as u64) {
         // if we are already killed, then perform a suicide
         thread_suicide();
     }
-    // all non-main thread of the cage should not be able to reach the below routine
-    // as only main thread is responsible for handling the signals, and the only situation for
-    // other non-main thread entered the epoch callback is that they are killed
+    // otherwise the thread was picked to handle signals: the ones sent to it with tgkill(),
+    // and the ones sent to the cage that it does not block
 
     // we loop to retrieve pending signals one by one untill there isn't any unblocked pending signals
     loop {
-        let signal = cage::signal::lind_get_first_signal(cageid);
+        let signal = cage::signal::lind_get_first_signal(cageid, threadid);
         if signal.is_none() {
             break;
         }
 
         // if this is the last pending (unblocked) signal in list, we should reset epoch
-        if cage::signal::lind_check_no_pending_signal(cageid) {
-            cage::signal::signal_epoch_reset(cageid);
+        if cage::signal::lind_check_no_pending_signal(cageid, threadid) {
+            cage::signal::signal_epoch_reset(cageid, threadid);
         }
 
-        let (signo, signal_handler, restorer) = signal.unwrap();
+        let (signo, (signal_handler, sa_flags, restorer)) = signal.unwrap();
+        // with `--record`, log where the signal was delivered, so that a replay can raise it
+        // at the same point
+        threei::record::record_signal(cageid, ctx.tid as u64, signo);
         if signal_handler == SIG_DFL as u32 {
             // default handler
             // look up the signal's default handler
             match sysdefs::constants::signal_default_handler_dispatcher(signo) {
                 sysdefs::constants::SignalDefaultHandler::Terminate => {
                     // if we are supposed to be terminated, switch the epoch state of all other threads
-                    // to "killed" state and perform a suicide
-                    cage::signal::epoch_kill_all(cageid);
+                    // to "killed" state, have the parent reap the cage and perform a suicide
+                    terminate_cage(cageid, threadid, signo, &ctx.lind_manager);
                     thread_suicide();
                 }
                 sysdefs::constants::SignalDefaultHandler::Ignore => {
@@ -98,20 +133,37 @@ pub fn signal_handler<
         } else {
             // we should invoke user's custom signal handler
 
+            // signals delivered here were sent with kill() or alike, so SA_SIGINFO handlers get
+            // SI_USER and no fault address
+            let saved_stack_pointer = switch_to_altstack(caller, cageid, threadid, sa_flags);
+            let data = SignalAsyncifyData {
+                signal_handler: signal_handler as i32,
+                signo,
+                sa_flags,
+                si_code: SI_USER,
+                si_addr: 0,
+                saved_stack_pointer,
+            };
             // before invoke the function, let's record the signal callstack information in case user performed
             // any Asyncify-related operation in signal handler
-            caller
-                .as_context_mut()
-                .append_signal_asyncify_data(signal_handler as i32, signo);
+            caller.as_context_mut().append_signal_asyncify_data(data);
             // invoke the
-            let invoke_res =
-                signal_func.call(caller.as_context_mut(), (signal_handler as i32, signo));
+            let invoke_res = signal_func.call(
+                caller.as_context_mut(),
+                (
+                    data.signal_handler,
+                    data.signo,
+                    data.sa_flags,
+                    data.si_code,
+                    data.si_addr,
+                ),
+            );
             // print errors if any when running the signal handler
             if let Err(err) = invoke_res {
                 let e = wasi_common::maybe_exit_on_error(err);
                 eprintln!("Error: {:?}", e);
                 // if we encountered any error when executing the signal handler, we should terminate the cage
-                cage::signal::epoch_kill_all(cageid);
+                cage::signal::epoch_kill_all(cageid, threadid);
                 thread_suicide();
             }
 
@@ -124,6 +176,10 @@ pub fn signal_handler<
 
                 // restore signal mask
                 restorer(cageid);
+                // leave the alternate signal stack
+                if let Some(stack_pointer) = data.saved_stack_pointer {
+                    caller.set_stack_pointer(stack_pointer).unwrap();
+                }
                 // clean up the signal callstack information for Asyncify
                 caller
                     .as_context_mut()
@@ -135,6 +191,161 @@ pub fn signal_handler<
     0
 }
 
+// move the stack pointer of the thread to the top of its alternate signal stack, if the handler
+// of a signal with `sa_flags` is to run there
+// returns the stack pointer of the interrupted code, to restore once the handler returns
+fn switch_to_altstack<T>(
+    caller: &mut Caller<'_, T>,
+    cageid: u64,
+    threadid: i32,
+    sa_flags: i32,
+) -> Option<u32> {
+    let stack_pointer = caller.get_stack_pointer().unwrap();
+    let top = cage::signal::signal_altstack_top(cageid, threadid, sa_flags, stack_pointer)?;
+    caller.set_stack_pointer(top).unwrap();
+    Some(stack_pointer)
+}
+
+// the stack pointer an Asyncify unwind of the thread keeps its data below
+// the unwind data goes between the stack low of the thread and its stack pointer, which is only
+// free space when the stack pointer is on the thread's stack. In a handler on the alternate
+// signal stack, that is the stack pointer of the code the handler interrupted
+pub fn unwind_stack_pointer<T>(caller: &mut Caller<'_, T>) -> u32 {
+    match caller.as_context().signal_interrupted_stack_pointer() {
+        Some(stack_pointer) => stack_pointer,
+        None => caller.get_stack_pointer().unwrap(),
+    }
+}
+
+// map a trap that ended a call into the guest to the signal Linux raises for the same fault,
+// along with the si_code and si_addr of its siginfo_t
+// traps that are not caused by the guest code faulting (epoch interruption, running out of
+// fuel, errors of the host...) have no signal
+fn trap_signal_info(err: &anyhow::Error) -> Option<(i32, i32, u64)> {
+    let trap = err.downcast_ref::<Trap>()?;
+    // wasmtime only knows the address of faulting loads and stores, other faults report 0
+    let si_addr = err
+        .downcast_ref::<WasmFault>()
+        .map_or(0, |fault| fault.wasm_address);
+    let (signo, si_code) = match trap {
+        Trap::MemoryOutOfBounds
+        | Trap::StackOverflow
+        | Trap::TableOutOfBounds
+        | Trap::IndirectCallToNull
+        | Trap::NullReference => (SIGSEGV, SEGV_MAPERR),
+        Trap::HeapMisaligned => (SIGBUS, BUS_ADRALN),
+        Trap::IntegerDivisionByZero => (SIGFPE, FPE_INTDIV),
+        Trap::IntegerOverflow => (SIGFPE, FPE_INTOVF),
+        Trap::BadConversionToInteger => (SIGFPE, FPE_FLTINV),
+        Trap::UnreachableCodeReached | Trap::BadSignature => (SIGILL, ILL_ILLOPC),
+        _ => return None,
+    };
+    Some((signo, si_code, si_addr))
+}
+
+// turn a trap that ended the entry point of a cage or thread into a synchronous signal for
+// the thread that hit it
+// the Wasm frames that faulted are gone by the time the trap reaches us, so an installed
+// handler runs on a fresh call into glibc's signal_callback, and neither returning from it nor
+// siglongjmp() can resume the faulting code. When the handler returns the fault is fatal, which
+// is what re-running the faulting instruction amounts to on Linux. A handler that calls exit()
+// ends the thread through the exit syscall as usual
+// with no handler, or once the handler returned, the cage is terminated by the signal: its other
+// threads are killed and the parent reaps it with the signal as its wait status
+// the error is handed back if the trap is not a fault, for the caller to report it as before
+pub fn trap_signal<T>(
+    store: &mut Store<T>,
+    instance: &Instance,
+    cageid: u64,
+    threadid: i32,
+    lind_manager: &LindCageManager,
+    err: anyhow::Error,
+) -> anyhow::Result<()> {
+    let Some((mut signo, si_code, si_addr)) = trap_signal_info(&err) else {
+        return Err(err);
+    };
+
+    if let Some((signal_handler, sa_flags, restorer)) =
+        cage::signal::lind_get_fault_signal(cageid, threadid, signo)
+    {
+        let signal_func = instance
+            .get_typed_func::<(i32, i32, i32, i32, i32), ()>(&mut *store, "signal_callback")
+            .unwrap();
+        // a handler for a stack overflow can only run on the alternate signal stack, the stack
+        // pointer stays where the overflow left it otherwise
+        let stack_pointer = instance.get_stack_pointer(&mut *store).unwrap();
+        let altstack_top =
+            cage::signal::signal_altstack_top(cageid, threadid, sa_flags, stack_pointer);
+        if let Some(top) = altstack_top {
+            instance.set_stack_pointer(&mut *store, top).unwrap();
+        }
+        // recorded like any handler, so that an exit() in it unwinds off the alternate stack
+        let data = SignalAsyncifyData {
+            signal_handler: signal_handler as i32,
+            signo,
+            sa_flags,
+            si_code,
+            si_addr: si_addr as i32,
+            saved_stack_pointer: altstack_top.map(|_| stack_pointer),
+        };
+        store.as_context_mut().append_signal_asyncify_data(data);
+        let invoke_res = signal_func.call(
+            &mut *store,
+            (
+                data.signal_handler,
+                data.signo,
+                data.sa_flags,
+                data.si_code,
+                data.si_addr,
+            ),
+        );
+        match invoke_res {
+            // the handler called exit()
+            Ok(()) if store.as_context().get_asyncify_state() == AsyncifyState::Unwind => {
+                return Ok(());
+            }
+            Ok(()) => {
+                restorer(cageid);
+                if altstack_top.is_some() {
+                    instance
+                        .set_stack_pointer(&mut *store, stack_pointer)
+                        .unwrap();
+                }
+                store
+                    .as_context_mut()
+                    .pop_signal_asyncify_data(data.signal_handler, data.signo);
+            }
+            // the handler faulted in turn, and that fault is the one the cage dies of
+            Err(err) => {
+                if let Some((nested_signo, _, _)) = trap_signal_info(&err) {
+                    signo = nested_signo;
+                }
+            }
+        }
+    }
+
+    // the cage was killed while the handler ran, whoever killed it also reports its exit
+    if cage::signal::thread_check_killed(cageid, threadid as u64) {
+        return Ok(());
+    }
+
+    // default action: terminate the cage
+    terminate_cage(cageid, threadid, signo, lind_manager);
+
+    Ok(())
+}
+
+// terminate the cage with the default action of `signo`, from one of its threads
+// the other threads are switched to "killed" state and end at their next epoch check, and the
+// harsh exit leaves a zombie that the parent reaps with the signal as its wait status
+fn terminate_cage(cageid: u64, threadid: i32, signo: i32, lind_manager: &LindCageManager) {
+    cage::signal::epoch_kill_all(cageid, threadid);
+    threei::trigger_harsh_cage_exit(cageid, signo as u64);
+    // the cage will not go through exit_call, which is where a cage is normally accounted as gone
+    rm_vmctx(cageid);
+    lind_manager.decrement();
+}
+
 // raise a trap to the current thread
 // this is paired with catch_traps function in /crates/wasmtime/src/runtime/vm/traphandlers.rs
 // which will catch the trap raised here and perform the clean up
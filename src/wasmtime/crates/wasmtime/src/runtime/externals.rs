@@ -32,6 +32,12 @@ pub enum AsyncifyState {
 pub struct SignalAsyncifyData {
     pub signal_handler: i32,
     pub signo: i32,
+    pub sa_flags: i32,
+    pub si_code: i32,
+    pub si_addr: i32,
+    // the `__stack_pointer` of the code the handler interrupted, if the handler was switched to
+    // the alternate signal stack. Put back once the handler returns
+    pub saved_stack_pointer: Option<u32>,
 }
 
 // Externals
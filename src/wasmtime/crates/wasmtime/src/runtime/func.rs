@@ -2138,6 +2138,15 @@ impl<T> Caller<'_, T> {
             .get_stack_pointer(&mut self.store)
     }
 
+    pub fn set_stack_pointer(&mut self, val: u32) -> Result<(), ()> {
+        self.caller
+            .host_state()
+            .downcast_ref::<Instance>()
+            .ok_or(())
+            .unwrap()
+            .set_stack_pointer(&mut self.store, val)
+    }
+
     pub fn get_asyncify_start_unwind(&mut self) -> Result<TypedFunc<i32, ()>, ()> {
         if let Some(asyncify_start_unwind_extern) = self.get_export("asyncify_start_unwind") {
             match asyncify_start_unwind_extern {
@@ -2242,11 +2251,13 @@ impl<T> Caller<'_, T> {
     }
 
     // retrieve the exported signal_callback function from glibc
-    pub fn get_signal_callback(&mut self) -> Result<TypedFunc<(i32, i32), ()>, ()> {
+    // its arguments are the handler, the signal number, the handler's sa_flags, and the si_code
+    // and si_addr of the siginfo_t passed to SA_SIGINFO handlers
+    pub fn get_signal_callback(&mut self) -> Result<TypedFunc<(i32, i32, i32, i32, i32), ()>, ()> {
         if let Some(signal_callback_extern) = self.get_export("signal_callback") {
             match signal_callback_extern {
                 Extern::Func(signal_callback) => {
-                    match signal_callback.typed::<(i32, i32), ()>(&self) {
+                    match signal_callback.typed::<(i32, i32, i32, i32, i32), ()>(&self) {
                         Ok(func) => {
                             return Ok(func);
                         }
@@ -694,6 +694,25 @@ impl Instance {
         return Err(());
     }
 
+    pub fn set_stack_pointer(&self, mut store: impl AsContextMut, val: u32) -> Result<(), ()> {
+        if let Some(sp_extern) = self.get_export(store.as_context_mut(), "__stack_pointer") {
+            match sp_extern {
+                Extern::Global(sp) => {
+                    // fails if the stack pointer is not a mutable i32
+                    return sp
+                        .set(store.as_context_mut(), Val::I32(val as i32))
+                        .map_err(|_| ());
+                }
+                _ => {
+                    // unexpected stack pointer export type (not a Global type)
+                    return Err(());
+                }
+            }
+        }
+        // __stack_pointer export not found
+        return Err(());
+    }
+
     pub fn get_stack_low(&self, mut store: impl AsContextMut) -> Result<i32, ()> {
         if let Some(sp_extern) = self.get_export(store.as_context_mut(), "__stack_low") {
             match sp_extern {
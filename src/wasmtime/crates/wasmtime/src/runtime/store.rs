@@ -1255,6 +1255,20 @@ impl<'a, T> StoreContext<'a, T> {
     pub fn get_stack_base(&self) -> u64 {
         self.0.stack_base
     }
+
+    /// number of signal handlers the thread is in, setjmp keeps it to drop the ones longjmp leaves
+    pub fn signal_asyncify_depth(&self) -> usize {
+        self.0.signal_asyncify_data.len()
+    }
+
+    /// the `__stack_pointer` of the code the outermost handler on the alternate signal stack
+    /// interrupted, if the thread is in such a handler
+    pub fn signal_interrupted_stack_pointer(&self) -> Option<u32> {
+        self.0
+            .signal_asyncify_data
+            .iter()
+            .find_map(|data| data.saved_stack_pointer)
+    }
 }
 
 impl<'a, T> StoreContextMut<'a, T> {
@@ -1393,16 +1407,29 @@ impl<'a, T> StoreContextMut<'a, T> {
     }
 
     // append the signal callstack information
-    pub fn append_signal_asyncify_data(&mut self, signal_handler: i32, signo: i32) {
-        self.0.signal_asyncify_data.push(SignalAsyncifyData {
-            signal_handler,
-            signo,
-        });
+    // this is everything glibc's signal_callback was called with, so that a rewind can
+    // re-enter the handler the same way
+    pub fn append_signal_asyncify_data(&mut self, data: SignalAsyncifyData) {
+        self.0.signal_asyncify_data.push(data);
     }
 
     // pop the signal callstack information
     pub fn pop_signal_asyncify_data(&mut self, signal_handler: i32, signo: i32) {
         self.0.signal_asyncify_data.pop();
+        if self.0.signal_asyncify_counter as usize >= self.0.signal_asyncify_data.len() {
+            self.0.signal_asyncify_counter = 0;
+        }
+    }
+
+    // drop the signal callstack information of the handlers a longjmp leaves, keeping the
+    // `depth` outermost ones
+    // returns the stack pointer of the code interrupted by the outermost dropped handler that ran
+    // on the alternate signal stack, which is where the stack pointer goes back to
+    pub fn truncate_signal_asyncify_data(&mut self, depth: usize) -> Option<u32> {
+        let depth = depth.min(self.0.signal_asyncify_data.len());
+        let dropped = self.0.signal_asyncify_data.split_off(depth);
+        self.0.signal_asyncify_counter = 0;
+        dropped.iter().find_map(|data| data.saved_stack_pointer)
     }
 
     // get the current signal callstack information
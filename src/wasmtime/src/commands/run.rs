@@ -32,6 +32,7 @@ use wasmtime_lind_3i::{get_vmctx, init_vmctx_pool, rm_vmctx, set_vmctx, VmCtxWra
 use wasmtime_lind_utils::LindCageManager;
 
 use cage::signal::{lind_signal_init, lind_thread_exit, signal_may_trigger};
+use cage::ALTSTACK_DISABLED;
 use core::ffi::c_void;
 use rawposix::sys_calls::{rawposix_shutdown, rawposix_start};
 use std::ptr::NonNull;
@@ -836,12 +837,15 @@ impl RunCommand {
                     }
                 }
 
-                // initialize the signal for the main thread of the cage
+                // initialize the signal for the main thread of the cage, which starts (or after
+                // exec starts over) with an empty signal mask and no alternate signal stack
                 lind_signal_init(
                     cageid,
                     pointer as *mut u64,
                     THREAD_START_ID,
                     true, /* this is the main thread */
+                    0,
+                    ALTSTACK_DISABLED,
                 );
 
                 // see comments at signal_may_trigger for more details
@@ -0,0 +1,177 @@
+/*
+ * Deterministic: clock_nanosleep, nanosleep, clock_getres and gettimeofday. A relative sleep
+ * lasts at least as long as requested and an absolute one until the clock reads its deadline.
+ * A sleep cut short by a signal fails with EINTR and, if it was relative, writes back the time
+ * that was left; an absolute sleep leaves `rem` alone.
+ */
+
+#include <assert.h>
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/time.h>
+#include <time.h>
+
+#define MS 1000000L
+#define SEC 1000000000L
+
+static volatile sig_atomic_t alarms;
+
+static void on_alarm(int sig)
+{
+	(void)sig;
+	alarms++;
+}
+
+static long now_ns(clockid_t clock)
+{
+	struct timespec ts;
+
+	assert(clock_gettime(clock, &ts) == 0);
+	return ts.tv_sec * SEC + ts.tv_nsec;
+}
+
+static struct timespec ns_ts(long ns)
+{
+	struct timespec ts = { ns / SEC, ns % SEC };
+
+	return ts;
+}
+
+/* Raise SIGALRM once after `ms` milliseconds */
+static void alarm_in(long ms)
+{
+	struct itimerval itv = { .it_value = { ms / 1000, ms % 1000 * 1000 } };
+
+	assert(setitimer(ITIMER_REAL, &itv, NULL) == 0);
+}
+
+static void test_sleep(void)
+{
+	struct timespec req, rem;
+	long start;
+
+	/* relative sleeps */
+	start = now_ns(CLOCK_MONOTONIC);
+	req = ns_ts(50 * MS);
+	assert(clock_nanosleep(CLOCK_MONOTONIC, 0, &req, NULL) == 0);
+	assert(now_ns(CLOCK_MONOTONIC) - start >= 50 * MS);
+
+	start = now_ns(CLOCK_MONOTONIC);
+	assert(nanosleep(&req, NULL) == 0);
+	assert(now_ns(CLOCK_MONOTONIC) - start >= 50 * MS);
+
+	/* absolute sleeps end once the clock reads the deadline */
+	req = ns_ts(now_ns(CLOCK_MONOTONIC) + 50 * MS);
+	assert(clock_nanosleep(CLOCK_MONOTONIC, TIMER_ABSTIME, &req, NULL) == 0);
+	assert(now_ns(CLOCK_MONOTONIC) >= req.tv_sec * SEC + req.tv_nsec);
+
+	req = ns_ts(now_ns(CLOCK_REALTIME) + 50 * MS);
+	assert(clock_nanosleep(CLOCK_REALTIME, TIMER_ABSTIME, &req, NULL) == 0);
+	assert(now_ns(CLOCK_REALTIME) >= req.tv_sec * SEC + req.tv_nsec);
+
+	/* a deadline in the past returns at once */
+	start = now_ns(CLOCK_MONOTONIC);
+	req = ns_ts(start - SEC);
+	assert(clock_nanosleep(CLOCK_MONOTONIC, TIMER_ABSTIME, &req, NULL) == 0);
+	assert(now_ns(CLOCK_MONOTONIC) - start < SEC);
+
+	/* invalid requests, reported as the return value */
+	req.tv_nsec = SEC;
+	assert(clock_nanosleep(CLOCK_MONOTONIC, 0, &req, &rem) == EINVAL);
+	req.tv_nsec = -1;
+	assert(clock_nanosleep(CLOCK_MONOTONIC, 0, &req, &rem) == EINVAL);
+	req = ns_ts(MS);
+	assert(clock_nanosleep(CLOCK_THREAD_CPUTIME_ID, 0, &req, NULL) == EINVAL);
+	assert(clock_nanosleep(12345, 0, &req, NULL) == EINVAL);
+	puts("sleep: ok");
+}
+
+static void test_interrupted(void)
+{
+	struct sigaction sa = { .sa_handler = on_alarm };
+	struct timespec req, rem;
+	long left;
+
+	sigemptyset(&sa.sa_mask);
+	assert(sigaction(SIGALRM, &sa, NULL) == 0);
+
+	/* a relative sleep writes back what was left */
+	alarm_in(100);
+	req = ns_ts(2 * SEC);
+	memset(&rem, 0, sizeof(rem));
+	assert(clock_nanosleep(CLOCK_MONOTONIC, 0, &req, &rem) == EINTR);
+	assert(alarms == 1);
+	left = rem.tv_sec * SEC + rem.tv_nsec;
+	assert(left > SEC && left <= 2 * SEC - 100 * MS);
+
+	alarm_in(100);
+	errno = 0;
+	memset(&rem, 0, sizeof(rem));
+	assert(nanosleep(&req, &rem) == -1 && errno == EINTR);
+	assert(alarms == 2);
+	left = rem.tv_sec * SEC + rem.tv_nsec;
+	assert(left > SEC && left <= 2 * SEC - 100 * MS);
+
+	/* an absolute sleep does not */
+	alarm_in(100);
+	req = ns_ts(now_ns(CLOCK_MONOTONIC) + 2 * SEC);
+	rem.tv_sec = 77;
+	rem.tv_nsec = 77;
+	assert(clock_nanosleep(CLOCK_MONOTONIC, TIMER_ABSTIME, &req, &rem) == EINTR);
+	assert(alarms == 3);
+	assert(rem.tv_sec == 77 && rem.tv_nsec == 77);
+	puts("interrupted: ok");
+}
+
+static void test_getres(void)
+{
+	static const clockid_t fine[] = {
+		CLOCK_REALTIME, CLOCK_MONOTONIC, CLOCK_BOOTTIME,
+		CLOCK_PROCESS_CPUTIME_ID, CLOCK_THREAD_CPUTIME_ID,
+	};
+	struct timespec res;
+	unsigned int i;
+
+	for (i = 0; i < sizeof(fine) / sizeof(fine[0]); i++) {
+		assert(clock_getres(fine[i], &res) == 0);
+		assert(res.tv_sec == 0 && res.tv_nsec == 1);
+	}
+
+	/* the coarse clocks tick at the scheduler's rate */
+	assert(clock_getres(CLOCK_MONOTONIC_COARSE, &res) == 0);
+	assert(res.tv_sec == 0 && res.tv_nsec > 1 && res.tv_nsec <= 10 * MS);
+
+	assert(clock_getres(CLOCK_MONOTONIC, NULL) == 0);
+	errno = 0;
+	assert(clock_getres(12345, &res) == -1 && errno == EINVAL);
+	puts("getres: ok");
+}
+
+static void test_gettimeofday(void)
+{
+	struct timezone tz = { 1, 1 };
+	struct timeval tv, tv2;
+	long real;
+
+	assert(gettimeofday(&tv, NULL) == 0);
+	real = now_ns(CLOCK_REALTIME);
+	assert(tv.tv_usec >= 0 && tv.tv_usec < 1000000);
+	assert(tv.tv_sec * SEC + tv.tv_usec * 1000L <= real);
+	assert(real - (tv.tv_sec * SEC + tv.tv_usec * 1000L) < SEC);
+
+	assert(gettimeofday(&tv2, &tz) == 0);
+	assert(tv2.tv_sec > tv.tv_sec || (tv2.tv_sec == tv.tv_sec && tv2.tv_usec >= tv.tv_usec));
+	assert(tz.tz_minuteswest == 0 && tz.tz_dsttime == 0);
+	puts("gettimeofday: ok");
+}
+
+int main(void)
+{
+	test_sleep();
+	test_interrupted();
+	test_getres();
+	test_gettimeofday();
+	return 0;
+}
@@ -0,0 +1,171 @@
+/*
+ * Deterministic: read, write, poll, ioctl and dup on the /dev device nodes. Each open of a
+ * device is a file description of its own, with its own epoll registration.
+ */
+
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/epoll.h>
+#include <sys/ioctl.h>
+#include <sys/stat.h>
+#include <termios.h>
+#include <unistd.h>
+
+static void check_ready(int fd, short events)
+{
+	struct pollfd pfd = { .fd = fd, .events = events };
+
+	assert(poll(&pfd, 1, 0) == 1);
+	assert(pfd.revents == events);
+}
+
+static void check_nonblock(int fd)
+{
+	int on = 1, off = 0;
+
+	assert(ioctl(fd, FIONBIO, &on) == 0);
+	assert(fcntl(fd, F_GETFL) & O_NONBLOCK);
+	assert(ioctl(fd, FIONBIO, &off) == 0);
+	assert(!(fcntl(fd, F_GETFL) & O_NONBLOCK));
+}
+
+/*
+ * Every device is a character device that refuses terminal requests: the memory
+ * devices with ENOTTY, the random devices with EINVAL.
+ */
+static int open_dev(const char *path, int tty_errno)
+{
+	struct winsize ws;
+	struct termios t;
+	struct stat st;
+	int fd;
+
+	fd = open(path, O_RDWR);
+	assert(fd >= 0);
+	assert(fstat(fd, &st) == 0 && S_ISCHR(st.st_mode));
+	errno = 0;
+	assert(ioctl(fd, TIOCGWINSZ, &ws) == -1 && errno == tty_errno);
+	errno = 0;
+	assert(ioctl(fd, TCGETS, &t) == -1 && errno == tty_errno);
+	assert(!isatty(fd));
+	check_nonblock(fd);
+	return fd;
+}
+
+static void test_null(void)
+{
+	char buf[16] = "data";
+	int fd, fd2;
+
+	fd = open_dev("/dev/null", ENOTTY);
+	assert(write(fd, buf, sizeof(buf)) == sizeof(buf));
+	assert(read(fd, buf, sizeof(buf)) == 0);
+	check_ready(fd, POLLIN | POLLOUT);
+
+	fd2 = dup(fd);
+	assert(fd2 >= 0);
+	assert(close(fd) == 0);
+	assert(write(fd2, buf, 4) == 4);
+	assert(read(fd2, buf, sizeof(buf)) == 0);
+	assert(close(fd2) == 0);
+}
+
+static void test_zero(void)
+{
+	char buf[64];
+	int fd, fd2, i;
+
+	fd = open_dev("/dev/zero", ENOTTY);
+	memset(buf, 'x', sizeof(buf));
+	assert(read(fd, buf, sizeof(buf)) == sizeof(buf));
+	for (i = 0; i < (int)sizeof(buf); i++)
+		assert(buf[i] == 0);
+	assert(write(fd, buf, sizeof(buf)) == sizeof(buf));
+	check_ready(fd, POLLIN | POLLOUT);
+
+	fd2 = dup2(fd, 20);
+	assert(fd2 == 20);
+	memset(buf, 'x', sizeof(buf));
+	assert(read(fd2, buf, 8) == 8 && buf[0] == 0 && buf[7] == 0 && buf[8] == 'x');
+	assert(close(fd) == 0);
+	assert(close(fd2) == 0);
+}
+
+static void test_random(const char *path)
+{
+	unsigned char a[64], b[64];
+	int fd, fd2;
+
+	fd = open_dev(path, EINVAL);
+	assert(read(fd, a, sizeof(a)) == sizeof(a));
+	check_ready(fd, POLLIN);
+
+	fd2 = dup(fd);
+	assert(fd2 >= 0);
+	assert(read(fd2, b, sizeof(b)) == sizeof(b));
+	assert(memcmp(a, b, sizeof(a)) != 0);
+
+	/* writing adds entropy on Linux and is accepted */
+	assert(write(fd2, a, sizeof(a)) == sizeof(a));
+	assert(close(fd) == 0);
+	assert(close(fd2) == 0);
+}
+
+static void test_opens(void)
+{
+	struct epoll_event ev = { .events = EPOLLIN }, out[4];
+	int a, b, ep;
+
+	/* two opens of a device can be watched by the same epoll instance */
+	a = open("/dev/random", O_RDONLY);
+	b = open("/dev/random", O_RDONLY);
+	assert(a >= 0 && b >= 0);
+	ep = epoll_create1(0);
+	assert(ep >= 0);
+	ev.data.fd = a;
+	assert(epoll_ctl(ep, EPOLL_CTL_ADD, a, &ev) == 0);
+	ev.data.fd = b;
+	assert(epoll_ctl(ep, EPOLL_CTL_ADD, b, &ev) == 0);
+	errno = 0;
+	assert(epoll_ctl(ep, EPOLL_CTL_ADD, b, &ev) == -1 && errno == EEXIST);
+	assert(epoll_wait(ep, out, 4, 0) == 2);
+
+	/* closing one leaves the other registered */
+	assert(close(a) == 0);
+	assert(epoll_wait(ep, out, 4, 0) == 1 && out[0].data.fd == b);
+	assert(close(b) == 0 && close(ep) == 0);
+}
+
+/* A test run may not have a controlling terminal, so /dev/tty is optional */
+static void test_tty(void)
+{
+	int fd, fd2;
+
+	fd = open("/dev/tty", O_RDWR);
+	if (fd < 0)
+		return;
+	check_ready(fd, POLLOUT);
+	check_nonblock(fd);
+
+	fd2 = dup(fd);
+	assert(fd2 >= 0);
+	assert(close(fd2) == 0);
+	assert(close(fd) == 0);
+}
+
+int main(void)
+{
+	test_null();
+	test_zero();
+	test_random("/dev/random");
+	test_random("/dev/urandom");
+	test_tty();
+	test_opens();
+
+	puts("devfs: ok");
+	return 0;
+}
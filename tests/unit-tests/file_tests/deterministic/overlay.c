@@ -0,0 +1,153 @@
+/*
+ * Deterministic: copy-up, whiteouts and merged directory listings of the overlay mode
+ * with one upper directory per cage (see runflags/overlay.flags). A forked child writes
+ * into its own upper layer, so none of its changes may show up in the parent. Native
+ * processes share one filesystem, so the output is compared against expected/.
+ */
+
+#include <assert.h>
+#include <dirent.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define DIR_PATH "testfiles/overlay"
+#define KEEP "testfiles/overlay/keep.txt"
+#define EDIT "testfiles/overlay/edit.txt"
+#define GONE "testfiles/overlay/gone.txt"
+#define NEW DIR_PATH "/new.txt"
+#define NEWDIR DIR_PATH "/newdir"
+
+static int cmpstr(const void *a, const void *b)
+{
+	return strcmp(*(char *const *)a, *(char *const *)b);
+}
+
+/* Sorted, comma separated listing of DIR_PATH without "." and ".." */
+static void list_dir(char *out, size_t size)
+{
+	char *names[16];
+	struct dirent *de;
+	int i, n = 0;
+	DIR *dir;
+
+	dir = opendir(DIR_PATH);
+	assert(dir != NULL);
+	while ((de = readdir(dir)) != NULL) {
+		if (strcmp(de->d_name, ".") == 0 || strcmp(de->d_name, "..") == 0)
+			continue;
+		assert(n < 16);
+		names[n++] = strdup(de->d_name);
+	}
+	assert(closedir(dir) == 0);
+
+	qsort(names, n, sizeof(names[0]), cmpstr);
+	out[0] = '\0';
+	for (i = 0; i < n; i++) {
+		if (i > 0)
+			strncat(out, ",", size - strlen(out) - 1);
+		strncat(out, names[i], size - strlen(out) - 1);
+		free(names[i]);
+	}
+}
+
+static void check_contents(const char *path, const char *expected)
+{
+	char buf[64] = { 0 };
+	int fd;
+
+	fd = open(path, O_RDONLY);
+	assert(fd >= 0);
+	assert(read(fd, buf, sizeof(buf) - 1) == (ssize_t)strlen(expected));
+	assert(strcmp(buf, expected) == 0);
+	assert(close(fd) == 0);
+}
+
+static void check_missing(const char *path)
+{
+	struct stat st;
+
+	errno = 0;
+	assert(stat(path, &st) == -1 && errno == ENOENT);
+}
+
+static void append(const char *path, const char *data)
+{
+	int fd;
+
+	fd = open(path, O_WRONLY | O_APPEND);
+	assert(fd >= 0);
+	assert(write(fd, data, strlen(data)) == (ssize_t)strlen(data));
+	assert(close(fd) == 0);
+}
+
+static void child(void)
+{
+	char listing[256];
+	int fd;
+
+	/* copy-up on write: the child sees its own version of the file */
+	append(EDIT, "child\n");
+	check_contents(EDIT, "edit\nchild\n");
+
+	/* the whiteout hides the lower file, and is never listed itself */
+	assert(unlink(GONE) == 0);
+	check_missing(GONE);
+	fd = open(NEW, O_CREAT | O_EXCL | O_WRONLY, 0644);
+	assert(fd >= 0);
+	assert(close(fd) == 0);
+
+	list_dir(listing, sizeof(listing));
+	printf("child: %s\n", listing);
+	fflush(stdout);
+	_exit(0);
+}
+
+int main(void)
+{
+	char listing[256];
+	int status;
+	pid_t pid;
+
+	check_contents(EDIT, "edit\n");
+	list_dir(listing, sizeof(listing));
+	printf("lower: %s\n", listing);
+	fflush(stdout);
+
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0)
+		child();
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	/* nothing the child did reached the lower layer or the parent's upper layer */
+	check_contents(EDIT, "edit\n");
+	check_contents(GONE, "gone\n");
+	check_missing(NEW);
+	list_dir(listing, sizeof(listing));
+	printf("parent after child: %s\n", listing);
+
+	/* the parent's own changes merge with the untouched lower files */
+	append(EDIT, "parent\n");
+	check_contents(EDIT, "edit\nparent\n");
+	assert(unlink(GONE) == 0);
+	assert(mkdir(NEWDIR, 0755) == 0);
+	check_contents(KEEP, "keep\n");
+	list_dir(listing, sizeof(listing));
+	printf("parent: %s\n", listing);
+
+	/* a file recreated over a whiteout starts out empty */
+	assert(close(open(GONE, O_CREAT | O_WRONLY, 0644)) == 0);
+	check_contents(GONE, "");
+	list_dir(listing, sizeof(listing));
+	printf("recreated: %s\n", listing);
+
+	puts("overlay: ok");
+	return 0;
+}
@@ -0,0 +1,214 @@
+/* Deterministic: file operations on a tmpfs mounted at /tmp (see runflags/tmpfs.flags). */
+
+#include <assert.h>
+#include <dirent.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/ioctl.h>
+#include <sys/mman.h>
+#include <sys/stat.h>
+#include <sys/vfs.h>
+#include <unistd.h>
+
+#define DIR_PATH "/tmp/tmpfs_test.d"
+#define TMPFS_MAGIC 0x01021994
+
+static void test_create(void)
+{
+	char buf[16] = { 0 };
+	int fd, fd2;
+
+	fd = open(DIR_PATH "/a", O_CREAT | O_EXCL | O_RDWR, 0644);
+	assert(fd >= 0);
+	assert(write(fd, "hello world", 11) == 11);
+	errno = 0;
+	assert(open(DIR_PATH "/a", O_CREAT | O_EXCL | O_RDWR, 0644) == -1 && errno == EEXIST);
+
+	assert(lseek(fd, 6, SEEK_SET) == 6);
+	assert(read(fd, buf, sizeof(buf)) == 5 && memcmp(buf, "world", 5) == 0);
+	assert(read(fd, buf, sizeof(buf)) == 0);
+
+	/* dup'd fds share the file offset */
+	fd2 = dup(fd);
+	assert(lseek(fd, 0, SEEK_SET) == 0);
+	assert(read(fd2, buf, 5) == 5 && memcmp(buf, "hello", 5) == 0);
+	assert(lseek(fd, 0, SEEK_CUR) == 5);
+	assert(close(fd2) == 0);
+
+	assert(pwrite(fd, "W", 1, 6) == 1);
+	assert(pread(fd, buf, 5, 6) == 5 && memcmp(buf, "World", 5) == 0);
+	assert(close(fd) == 0);
+}
+
+static void test_truncate(void)
+{
+	struct stat st;
+	char buf[16];
+	int fd;
+
+	fd = open(DIR_PATH "/a", O_RDWR);
+	assert(fd >= 0);
+
+	/* growing fills with zeros */
+	assert(ftruncate(fd, 16) == 0);
+	assert(fstat(fd, &st) == 0 && st.st_size == 16);
+	assert(pread(fd, buf, sizeof(buf), 0) == 16);
+	assert(memcmp(buf, "hello World", 11) == 0 && buf[11] == 0 && buf[15] == 0);
+
+	assert(truncate(DIR_PATH "/a", 5) == 0);
+	assert(fstat(fd, &st) == 0 && st.st_size == 5);
+	assert(pread(fd, buf, sizeof(buf), 0) == 5);
+
+	errno = 0;
+	assert(ftruncate(fd, -1) == -1 && errno == EINVAL);
+	assert(close(fd) == 0);
+}
+
+static void test_rename(void)
+{
+	struct stat st;
+
+	assert(mkdir(DIR_PATH "/sub", 0755) == 0);
+	assert(rename(DIR_PATH "/a", DIR_PATH "/sub/b") == 0);
+	errno = 0;
+	assert(stat(DIR_PATH "/a", &st) == -1 && errno == ENOENT);
+	assert(stat(DIR_PATH "/sub/b", &st) == 0 && st.st_size == 5);
+
+	/* a rename replaces an existing file */
+	assert(close(open(DIR_PATH "/c", O_CREAT | O_WRONLY, 0644)) == 0);
+	assert(rename(DIR_PATH "/sub/b", DIR_PATH "/c") == 0);
+	assert(stat(DIR_PATH "/c", &st) == 0 && st.st_size == 5);
+
+	errno = 0;
+	assert(rmdir(DIR_PATH) == -1 && errno == ENOTEMPTY);
+	assert(rmdir(DIR_PATH "/sub") == 0);
+}
+
+static void test_unlink_open(void)
+{
+	struct stat st;
+	char buf[16];
+	int fd;
+
+	fd = open(DIR_PATH "/c", O_RDWR);
+	assert(fd >= 0);
+	assert(unlink(DIR_PATH "/c") == 0);
+	errno = 0;
+	assert(open(DIR_PATH "/c", O_RDONLY) == -1 && errno == ENOENT);
+
+	/* the open file lives on until the last fd is closed */
+	assert(fstat(fd, &st) == 0 && st.st_nlink == 0);
+	assert(pwrite(fd, "12345678", 8, 5) == 8);
+	assert(pread(fd, buf, sizeof(buf), 0) == 13);
+	assert(memcmp(buf, "hello12345678", 13) == 0);
+	assert(close(fd) == 0);
+}
+
+static int cmpstr(const void *a, const void *b)
+{
+	return strcmp(*(char *const *)a, *(char *const *)b);
+}
+
+static void test_readdir(void)
+{
+	const char *names[] = { "x", "y", "z" };
+	char *seen[8];
+	struct dirent *de;
+	int i, n = 0;
+	DIR *dir;
+
+	for (i = 0; i < 3; i++) {
+		char path[64];
+
+		snprintf(path, sizeof(path), DIR_PATH "/%s", names[i]);
+		assert(close(open(path, O_CREAT | O_WRONLY, 0644)) == 0);
+	}
+
+	dir = opendir(DIR_PATH);
+	assert(dir != NULL);
+	while ((de = readdir(dir)) != NULL) {
+		assert(n < 8);
+		seen[n++] = strdup(de->d_name);
+	}
+	assert(closedir(dir) == 0);
+
+	assert(n == 5);
+	qsort(seen, n, sizeof(seen[0]), cmpstr);
+	assert(strcmp(seen[0], ".") == 0 && strcmp(seen[1], "..") == 0);
+	for (i = 0; i < 3; i++)
+		assert(strcmp(seen[i + 2], names[i]) == 0);
+	for (i = 0; i < n; i++)
+		free(seen[i]);
+
+	for (i = 0; i < 3; i++) {
+		char path[64];
+
+		snprintf(path, sizeof(path), DIR_PATH "/%s", names[i]);
+		assert(unlink(path) == 0);
+	}
+}
+
+static void test_mmap(void)
+{
+	char buf[8];
+	char *p;
+	int fd;
+
+	fd = open(DIR_PATH "/m", O_CREAT | O_RDWR, 0644);
+	assert(fd >= 0);
+	assert(write(fd, "mapped!", 7) == 7);
+
+	p = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_PRIVATE, fd, 0);
+	assert(p != MAP_FAILED);
+	assert(memcmp(p, "mapped!", 7) == 0 && p[7] == 0);
+
+	/* private changes never reach the file */
+	p[0] = 'M';
+	assert(pread(fd, buf, 7, 0) == 7 && buf[0] == 'm');
+	assert(munmap(p, 4096) == 0);
+
+	assert(close(fd) == 0);
+	assert(unlink(DIR_PATH "/m") == 0);
+}
+
+static void test_ioctl(void)
+{
+	struct winsize ws;
+	int fd, on = 1;
+
+	fd = open(DIR_PATH "/i", O_CREAT | O_RDWR, 0644);
+	assert(fd >= 0);
+	assert(ioctl(fd, FIONBIO, &on) == 0);
+	assert(fcntl(fd, F_GETFL) & O_NONBLOCK);
+	errno = 0;
+	assert(ioctl(fd, TIOCGWINSZ, &ws) == -1 && errno == ENOTTY);
+	assert(!isatty(fd));
+	assert(close(fd) == 0);
+	assert(unlink(DIR_PATH "/i") == 0);
+}
+
+int main(void)
+{
+	struct statfs sfs;
+
+	assert(mkdir(DIR_PATH, 0755) == 0);
+	assert(statfs(DIR_PATH, &sfs) == 0);
+#ifdef __wasm__
+	assert(sfs.f_type == TMPFS_MAGIC);
+#endif
+
+	test_create();
+	test_truncate();
+	test_rename();
+	test_unlink_open();
+	test_readdir();
+	test_mmap();
+	test_ioctl();
+
+	assert(rmdir(DIR_PATH) == 0);
+	puts("tmpfs: ok");
+	return 0;
+}
@@ -0,0 +1,42 @@
+/*
+ * Deterministic: lind rejects mmap flags it doesn't implement with EINVAL instead of
+ * silently ignoring them like Linux does, so the output is compared against expected/.
+ */
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <stdio.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+static void check_einval(int flags)
+{
+	void *p;
+
+	errno = 0;
+	p = mmap(NULL, 4096, PROT_READ | PROT_WRITE, flags, -1, 0);
+	assert(p == MAP_FAILED && errno == EINVAL);
+}
+
+int main(void)
+{
+	void *p;
+
+	check_einval(MAP_PRIVATE | MAP_ANONYMOUS | MAP_LOCKED);
+	check_einval(MAP_PRIVATE | MAP_ANONYMOUS | MAP_HUGETLB);
+	check_einval(MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED_NOREPLACE);
+	check_einval(MAP_PRIVATE | MAP_ANONYMOUS | 0x40000000);
+	/* exactly one of MAP_SHARED and MAP_PRIVATE is required */
+	check_einval(MAP_ANONYMOUS);
+	check_einval(MAP_SHARED_VALIDATE | MAP_ANONYMOUS);
+
+	/* the supported hints are still accepted */
+	p = mmap(NULL, 4096, PROT_READ | PROT_WRITE,
+		 MAP_PRIVATE | MAP_ANONYMOUS | MAP_POPULATE | MAP_NORESERVE, -1, 0);
+	assert(p != MAP_FAILED);
+	assert(munmap(p, 4096) == 0);
+
+	puts("mmap_unknown_flags: ok");
+	return 0;
+}
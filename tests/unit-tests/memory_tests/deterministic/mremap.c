@@ -0,0 +1,152 @@
+/* Deterministic: mremap in place, with MREMAP_MAYMOVE and MREMAP_FIXED, checked with mincore. */
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+static long page;
+
+static char *map(size_t npages)
+{
+	char *p = mmap(NULL, npages * page, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+
+	assert(p != MAP_FAILED);
+	return p;
+}
+
+static int mapped(void *addr, size_t npages)
+{
+	unsigned char vec[16];
+
+	assert(npages <= sizeof(vec));
+	if (mincore(addr, npages * page, vec) == 0)
+		return 1;
+	assert(errno == ENOMEM);
+	return 0;
+}
+
+static void fill(char *p, size_t npages, char c)
+{
+	size_t i;
+
+	for (i = 0; i < npages; i++)
+		p[i * page] = c + i;
+}
+
+static void check(const char *p, size_t npages, char c)
+{
+	size_t i;
+
+	for (i = 0; i < npages; i++)
+		assert(p[i * page] == (char)(c + i));
+}
+
+static void test_shrink_grow(void)
+{
+	char *p, *q;
+
+	/* free the tail of a reservation so growing in place can't collide with anything */
+	p = map(4);
+	assert(munmap(p + 2 * page, 2 * page) == 0);
+	assert(!mapped(p + 2 * page, 2));
+	fill(p, 2, 'a');
+
+	q = mremap(p, 2 * page, 4 * page, 0);
+	assert(q == p);
+	assert(mapped(p, 4));
+	check(p, 2, 'a');
+	assert(p[3 * page] == 0);
+
+	q = mremap(p, 4 * page, page, 0);
+	assert(q == p);
+	assert(mapped(p, 1) && !mapped(p + page, 3));
+	check(p, 1, 'a');
+	assert(munmap(p, page) == 0);
+}
+
+static void test_maymove(void)
+{
+	char *p, *q;
+
+	/* the page after the first one is mapped, so growing needs to move */
+	p = map(3);
+	fill(p, 1, 'm');
+	errno = 0;
+	assert(mremap(p, page, 2 * page, 0) == MAP_FAILED && errno == ENOMEM);
+
+	q = mremap(p, page, 2 * page, MREMAP_MAYMOVE);
+	assert(q != MAP_FAILED && q != p);
+	check(q, 1, 'm');
+	assert(q[page] == 0);
+	assert(!mapped(p, 1));
+	assert(mapped(p + page, 2));
+
+	assert(munmap(q, 2 * page) == 0);
+	assert(munmap(p + page, 2 * page) == 0);
+}
+
+static void test_fixed(void)
+{
+	char *p, *dst, *q;
+
+	p = map(2);
+	dst = map(4);
+	fill(p, 2, 'f');
+
+	errno = 0;
+	assert(mremap(p, 2 * page, 2 * page, MREMAP_FIXED, dst) == MAP_FAILED && errno == EINVAL);
+	errno = 0;
+	assert(mremap(p, 2 * page, 2 * page, MREMAP_MAYMOVE | MREMAP_FIXED, p + page) == MAP_FAILED &&
+	       errno == EINVAL);
+
+	/* the destination is replaced, whatever was mapped there */
+	q = mremap(p, 2 * page, 3 * page, MREMAP_MAYMOVE | MREMAP_FIXED, dst + page);
+	assert(q == dst + page);
+	check(q, 2, 'f');
+	assert(q[2 * page] == 0);
+	assert(!mapped(p, 2));
+	assert(mapped(dst, 4));
+
+	assert(munmap(dst, 4 * page) == 0);
+}
+
+static void test_errors(void)
+{
+	unsigned char vec[4];
+	char *p;
+
+	p = map(2);
+	assert(munmap(p + page, page) == 0);
+
+	/* mincore fails on any range with unmapped pages in it */
+	assert(mincore(p, page, vec) == 0);
+	errno = 0;
+	assert(mincore(p, 2 * page, vec) == -1 && errno == ENOMEM);
+	errno = 0;
+	assert(mincore(p + 1, page, vec) == -1 && errno == EINVAL);
+
+	/* the old range has to be mapped */
+	errno = 0;
+	assert(mremap(p + page, page, 2 * page, MREMAP_MAYMOVE) == MAP_FAILED && errno == EFAULT);
+	errno = 0;
+	assert(mremap(p, page, 0, MREMAP_MAYMOVE) == MAP_FAILED && errno == EINVAL);
+
+	assert(munmap(p, page) == 0);
+}
+
+int main(void)
+{
+	page = sysconf(_SC_PAGESIZE);
+
+	test_shrink_grow();
+	test_maymove();
+	test_fixed();
+	test_errors();
+
+	puts("mremap: ok");
+	return 0;
+}
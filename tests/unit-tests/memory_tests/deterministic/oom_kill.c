@@ -0,0 +1,158 @@
+/*
+ * Deterministic: memory figures of /proc, and a child killed by the OOM killer for going
+ * over the per-cage memory budget (see runflags/oom_kill.flags). Natively there is no
+ * budget, so the child ends itself with SIGKILL once it mapped everything.
+ */
+
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/resource.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define CHUNK (4 << 20)
+#define NCHUNKS 64
+#define CAGE_LIMIT_KB (64 << 10)
+#define GLOBAL_LIMIT_KB (256 << 10)
+
+static long page;
+
+static void read_proc(const char *path, char *buf, size_t size)
+{
+	ssize_t n;
+	int fd;
+
+	fd = open(path, O_RDONLY);
+	assert(fd >= 0);
+	n = read(fd, buf, size - 1);
+	assert(n > 0);
+	buf[n] = '\0';
+	assert(close(fd) == 0);
+}
+
+/* The value of a "Name:   1234 kB" line */
+static long field_kb(const char *content, const char *name)
+{
+	const char *line = strstr(content, name);
+
+	assert(line != NULL);
+	return strtol(line + strlen(name), NULL, 10);
+}
+
+static void read_statm(long *size, long *resident)
+{
+	char buf[256];
+
+	read_proc("/proc/self/statm", buf, sizeof(buf));
+	assert(sscanf(buf, "%ld %ld", size, resident) == 2);
+	assert(*size > 0 && *resident > 0 && *resident <= *size);
+}
+
+static void test_procfs(void)
+{
+	long size, resident, size2, resident2;
+	char buf[4096];
+	long total, free;
+	char *p;
+
+	read_statm(&size, &resident);
+
+	/* a mapping counts towards the size right away, and towards the resident set once used */
+	p = mmap(NULL, CHUNK, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	assert(p != MAP_FAILED);
+	memset(p, 1, CHUNK);
+	read_statm(&size2, &resident2);
+	assert(size2 - size == CHUNK / page);
+	assert(resident2 >= CHUNK / page);
+
+	read_proc("/proc/self/status", buf, sizeof(buf));
+	assert(field_kb(buf, "VmSize:") == size2 * (page >> 10));
+	assert(field_kb(buf, "VmPeak:") >= field_kb(buf, "VmSize:"));
+	assert(field_kb(buf, "VmHWM:") >= field_kb(buf, "VmRSS:"));
+	assert(field_kb(buf, "VmRSS:") >= CHUNK >> 10);
+
+	read_proc("/proc/meminfo", buf, sizeof(buf));
+	total = field_kb(buf, "MemTotal:");
+	free = field_kb(buf, "MemFree:");
+	assert(free <= total && field_kb(buf, "MemAvailable:") <= total);
+#ifdef __wasm__
+	/* the total is the global budget, of which this cage is the only user */
+	assert(total == GLOBAL_LIMIT_KB);
+	assert(free == total - size2 * (page >> 10));
+#endif
+
+	assert(munmap(p, CHUNK) == 0);
+	read_statm(&size, &resident);
+	assert(size == size2 - CHUNK / page);
+}
+
+static void child(void)
+{
+	char *p;
+	int i;
+
+	for (i = 0; i < NCHUNKS; i++) {
+		p = mmap(NULL, CHUNK, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+		if (p == MAP_FAILED) {
+			/* the call that crossed the budget fails, and SIGKILL is already on its way */
+			assert(errno == ENOMEM);
+			for (;;)
+				;
+		}
+		memset(p, 1, CHUNK);
+	}
+
+#ifdef __wasm__
+	/* the budget is far below what was mapped */
+	_exit(1);
+#else
+	raise(SIGKILL);
+#endif
+}
+
+static void test_oom_kill(void)
+{
+	struct rusage ru;
+	int status;
+	pid_t pid;
+	char *p;
+
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0)
+		child();
+
+	assert(wait4(pid, &status, 0, &ru) == pid);
+	assert(WIFSIGNALED(status) && WTERMSIG(status) == SIGKILL);
+
+	/* the child got to use about as much as the budget */
+	assert(ru.ru_maxrss >= CAGE_LIMIT_KB / 2);
+#ifdef __wasm__
+	/* only the CPU time and the peak resident set size are tracked */
+	assert(ru.ru_minflt == 0 && ru.ru_majflt == 0 && ru.ru_inblock == 0 &&
+	       ru.ru_oublock == 0 && ru.ru_nvcsw == 0 && ru.ru_nivcsw == 0);
+#endif
+
+	/* the budget is per cage, the parent still has all of its own */
+	p = mmap(NULL, CHUNK, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	assert(p != MAP_FAILED);
+	memset(p, 1, CHUNK);
+	assert(munmap(p, CHUNK) == 0);
+}
+
+int main(void)
+{
+	page = sysconf(_SC_PAGESIZE);
+
+	test_procfs();
+	test_oom_kill();
+
+	puts("oom_kill: ok");
+	return 0;
+}
@@ -0,0 +1,235 @@
+/*
+ * Deterministic: level-triggered, edge-triggered and one-shot epoll registrations, the
+ * removal of a registration once the last fd of its file is closed, and an interest list
+ * shared with a forked cage. Pipes are waited for by the kernel. Under lind, /dev/zero is
+ * also registered, which Linux refuses (EPERM), so that part is lind-only.
+ */
+
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <sys/epoll.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+/* Wait up to `ms` for a single event, returning how many there were */
+static int wait_one(int epfd, struct epoll_event *ev, int ms)
+{
+	int n = epoll_wait(epfd, ev, 1, ms);
+
+	assert(n == 0 || n == 1);
+	return n;
+}
+
+static void add(int epfd, int fd, unsigned int events, int tag)
+{
+	struct epoll_event ev = { .events = events, .data.u32 = tag };
+
+	assert(epoll_ctl(epfd, EPOLL_CTL_ADD, fd, &ev) == 0);
+}
+
+static void test_level_edge(void)
+{
+	struct epoll_event ev;
+	int lt[2], et[2], epfd;
+	char buf[8];
+
+	assert(pipe(lt) == 0 && pipe(et) == 0);
+	epfd = epoll_create1(0);
+	assert(epfd >= 0);
+	add(epfd, lt[0], EPOLLIN, 1);
+	add(epfd, et[0], EPOLLIN | EPOLLET, 2);
+
+	/* level-triggered: reported for as long as there is data */
+	assert(write(lt[1], "ab", 2) == 2);
+	assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 1 && ev.events == EPOLLIN);
+	assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 1);
+	assert(read(lt[0], buf, 1) == 1);
+	assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 1);
+	assert(read(lt[0], buf, 1) == 1);
+	assert(wait_one(epfd, &ev, 0) == 0);
+
+	/* edge-triggered: reported once per write, even if not all was read */
+	assert(write(et[1], "ab", 2) == 2);
+	assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 2 && ev.events == EPOLLIN);
+	assert(wait_one(epfd, &ev, 0) == 0);
+	assert(read(et[0], buf, 1) == 1);
+	assert(wait_one(epfd, &ev, 0) == 0);
+	assert(write(et[1], "c", 1) == 1);
+	assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 2);
+	assert(wait_one(epfd, &ev, 0) == 0);
+	assert(read(et[0], buf, sizeof(buf)) == 2);
+
+#ifdef __wasm__
+	{
+		/* /dev/zero is always readable: reported on every wait, or once when edge-triggered */
+		int zero = open("/dev/zero", O_RDONLY);
+
+		assert(zero >= 0);
+		add(epfd, zero, EPOLLIN, 3);
+		assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 3 && ev.events == EPOLLIN);
+		assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 3);
+
+		ev.events = EPOLLIN | EPOLLET;
+		ev.data.u32 = 4;
+		assert(epoll_ctl(epfd, EPOLL_CTL_MOD, zero, &ev) == 0);
+		assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 4);
+		assert(wait_one(epfd, &ev, 0) == 0);
+
+		/* modifying the registration checks it anew, as on Linux */
+		ev.events = EPOLLIN | EPOLLOUT | EPOLLET;
+		ev.data.u32 = 5;
+		assert(epoll_ctl(epfd, EPOLL_CTL_MOD, zero, &ev) == 0);
+		assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 5);
+		assert(wait_one(epfd, &ev, 0) == 0);
+		assert(close(zero) == 0);
+	}
+#endif
+
+	assert(close(epfd) == 0);
+	assert(close(lt[0]) == 0 && close(lt[1]) == 0);
+	assert(close(et[0]) == 0 && close(et[1]) == 0);
+	puts("level/edge: ok");
+}
+
+static void test_oneshot(void)
+{
+	struct epoll_event ev;
+	int p[2], epfd;
+
+	assert(pipe(p) == 0);
+	epfd = epoll_create1(0);
+	assert(epfd >= 0);
+	add(epfd, p[0], EPOLLIN | EPOLLONESHOT, 1);
+
+	/* reported once, then disarmed although the data is still there */
+	assert(write(p[1], "a", 1) == 1);
+	assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 1);
+	assert(wait_one(epfd, &ev, 0) == 0);
+	assert(write(p[1], "b", 1) == 1);
+	assert(wait_one(epfd, &ev, 0) == 0);
+
+	/* still registered: adding it again fails, modifying it arms it again */
+	ev.events = EPOLLIN | EPOLLONESHOT;
+	ev.data.u32 = 2;
+	errno = 0;
+	assert(epoll_ctl(epfd, EPOLL_CTL_ADD, p[0], &ev) == -1 && errno == EEXIST);
+	assert(epoll_ctl(epfd, EPOLL_CTL_MOD, p[0], &ev) == 0);
+	assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 2);
+	assert(wait_one(epfd, &ev, 0) == 0);
+
+#ifdef __wasm__
+	{
+		int zero = open("/dev/zero", O_RDONLY);
+
+		assert(zero >= 0);
+		add(epfd, zero, EPOLLIN | EPOLLONESHOT, 3);
+		assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 3);
+		assert(wait_one(epfd, &ev, 0) == 0);
+		ev.events = EPOLLIN | EPOLLONESHOT;
+		ev.data.u32 = 4;
+		assert(epoll_ctl(epfd, EPOLL_CTL_MOD, zero, &ev) == 0);
+		assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 4);
+		assert(wait_one(epfd, &ev, 0) == 0);
+		assert(close(zero) == 0);
+	}
+#endif
+
+	assert(close(epfd) == 0);
+	assert(close(p[0]) == 0 && close(p[1]) == 0);
+	puts("oneshot: ok");
+}
+
+static void test_close(void)
+{
+	struct epoll_event ev;
+	int p[2], epfd, dupfd;
+
+	assert(pipe(p) == 0);
+	epfd = epoll_create1(0);
+	assert(epfd >= 0);
+	add(epfd, p[0], EPOLLIN, 1);
+	assert(write(p[1], "a", 1) == 1);
+
+	/* the registration is the file's, so a duplicate keeps it alive */
+	dupfd = dup(p[0]);
+	assert(dupfd >= 0);
+	assert(close(p[0]) == 0);
+	assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 1);
+
+	/* and it is gone with the last fd, so the number can be registered anew */
+	assert(close(dupfd) == 0);
+	assert(wait_one(epfd, &ev, 0) == 0);
+	assert(pipe(p) == 0);
+	assert(write(p[1], "b", 1) == 1);
+	add(epfd, p[0], EPOLLIN, 2);
+	assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 2);
+	assert(close(p[0]) == 0 && close(p[1]) == 0);
+
+#ifdef __wasm__
+	{
+		int zero = open("/dev/zero", O_RDONLY);
+
+		assert(zero >= 0);
+		add(epfd, zero, EPOLLIN, 3);
+		assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 3);
+		assert(close(zero) == 0);
+		assert(wait_one(epfd, &ev, 0) == 0);
+	}
+#endif
+
+	assert(close(epfd) == 0);
+	puts("close: ok");
+}
+
+static void test_fork(void)
+{
+	struct epoll_event ev;
+	int p[2], epfd, status;
+	pid_t pid;
+
+	assert(pipe(p) == 0);
+	epfd = epoll_create1(0);
+	assert(epfd >= 0);
+	add(epfd, p[0], EPOLLIN | EPOLLONESHOT, 1);
+	assert(write(p[1], "a", 1) == 1);
+
+	/* the child's epoll fd is the same instance: what it consumes is gone for the parent */
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		assert(wait_one(epfd, &ev, 0) == 1 && ev.data.u32 == 1);
+		_exit(0);
+	}
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	assert(wait_one(epfd, &ev, 0) == 0);
+
+	/* and what the parent rearms, the child sees */
+	ev.events = EPOLLIN | EPOLLONESHOT;
+	ev.data.u32 = 2;
+	assert(epoll_ctl(epfd, EPOLL_CTL_MOD, p[0], &ev) == 0);
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		assert(wait_one(epfd, &ev, 1000) == 1 && ev.data.u32 == 2);
+		_exit(0);
+	}
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	assert(wait_one(epfd, &ev, 0) == 0);
+
+	assert(close(epfd) == 0);
+	assert(close(p[0]) == 0 && close(p[1]) == 0);
+	puts("fork: ok");
+}
+
+int main(void)
+{
+	test_level_edge();
+	test_oneshot();
+	test_close();
+	test_fork();
+	return 0;
+}
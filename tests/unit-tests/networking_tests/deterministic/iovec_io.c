@@ -0,0 +1,183 @@
+/*
+ * Deterministic: scatter/gather I/O with readv, writev, preadv and pwritev on files and
+ * pipes, and sendmmsg/recvmmsg on a datagram socketpair, including short transfers and
+ * invalid iovec arrays.
+ */
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/uio.h>
+#include <unistd.h>
+
+#define FILE_PATH "iovec_io.tmp"
+
+static void test_file(void)
+{
+	char a[4], b[8], c[16];
+	struct iovec iov[3] = {
+		{ .iov_base = "abc", .iov_len = 3 },
+		{ .iov_base = "", .iov_len = 0 },
+		{ .iov_base = "defghij", .iov_len = 7 },
+	};
+	int fd;
+
+	fd = open(FILE_PATH, O_CREAT | O_TRUNC | O_RDWR, 0644);
+	assert(fd >= 0);
+	assert(writev(fd, iov, 3) == 10);
+	assert(lseek(fd, 0, SEEK_CUR) == 10);
+
+	/* pwritev leaves the file offset alone */
+	iov[0] = (struct iovec){ .iov_base = "XY", .iov_len = 2 };
+	assert(pwritev(fd, iov, 1, 4) == 2);
+	assert(lseek(fd, 0, SEEK_CUR) == 10);
+
+	/* the last buffer is only partly filled at end of file */
+	iov[0] = (struct iovec){ .iov_base = a, .iov_len = sizeof(a) };
+	iov[1] = (struct iovec){ .iov_base = b, .iov_len = sizeof(b) };
+	assert(preadv(fd, iov, 2, 0) == 10);
+	assert(memcmp(a, "abcd", 4) == 0 && memcmp(b, "XYghij", 6) == 0);
+	assert(preadv(fd, iov, 2, 10) == 0);
+
+	assert(lseek(fd, 7, SEEK_SET) == 7);
+	iov[0] = (struct iovec){ .iov_base = c, .iov_len = 2 };
+	iov[1] = (struct iovec){ .iov_base = c + 2, .iov_len = sizeof(c) - 2 };
+	assert(readv(fd, iov, 2) == 3);
+	assert(memcmp(c, "hij", 3) == 0);
+	assert(lseek(fd, 0, SEEK_CUR) == 10);
+
+	errno = 0;
+	assert(preadv(fd, iov, 2, -1) == -1 && errno == EINVAL);
+	assert(close(fd) == 0);
+	assert(unlink(FILE_PATH) == 0);
+}
+
+static void test_pipe(void)
+{
+	char big[4096], buf[8];
+	struct iovec iov[2];
+	int p[2], total;
+	ssize_t n;
+
+	assert(pipe(p) == 0);
+
+	/* a read returns what is there, spread over the buffers in order */
+	assert(write(p[1], "12345", 5) == 5);
+	iov[0] = (struct iovec){ .iov_base = buf, .iov_len = 3 };
+	iov[1] = (struct iovec){ .iov_base = buf + 3, .iov_len = 5 };
+	assert(readv(p[0], iov, 2) == 5);
+	assert(memcmp(buf, "12345", 5) == 0);
+
+	/* a non-blocking write into an almost full pipe is short */
+	assert(fcntl(p[1], F_SETFL, O_NONBLOCK) == 0);
+	memset(big, 'b', sizeof(big));
+	while (write(p[1], big, sizeof(big)) == sizeof(big))
+		;
+	assert(errno == EAGAIN);
+	assert(read(p[0], big, sizeof(big)) == sizeof(big));
+	iov[0] = (struct iovec){ .iov_base = big, .iov_len = 6 };
+	iov[1] = (struct iovec){ .iov_base = big, .iov_len = sizeof(big) };
+	n = writev(p[1], iov, 2);
+	assert(n > 0 && n < 6 + (ssize_t)sizeof(big));
+
+	assert(fcntl(p[0], F_SETFL, O_NONBLOCK) == 0);
+	total = 0;
+	while ((n = read(p[0], big, sizeof(big))) > 0)
+		total += n;
+	assert(n == -1 && errno == EAGAIN && total > 0);
+
+	assert(close(p[0]) == 0);
+	assert(close(p[1]) == 0);
+}
+
+static void test_invalid(void)
+{
+	/* volatile, so that the compiler does not warn about what the calls are meant to test */
+	volatile int negative = -1, too_many = IOV_MAX + 1;
+	struct iovec *volatile null_iov = NULL;
+	struct iovec iov = { .iov_base = NULL, .iov_len = 4 };
+	char buf[4];
+	int p[2];
+
+	assert(pipe(p) == 0);
+	assert(write(p[1], "data", 4) == 4);
+
+	errno = 0;
+	assert(readv(p[0], &iov, negative) == -1 && errno == EINVAL);
+	errno = 0;
+	assert(readv(p[0], &iov, too_many) == -1 && errno == EINVAL);
+	errno = 0;
+	assert(readv(p[0], null_iov, 1) == -1 && errno == EFAULT);
+	errno = 0;
+	assert(readv(p[0], &iov, 1) == -1 && errno == EFAULT);
+	errno = 0;
+	assert(readv(-1, &iov, 1) == -1 && errno == EBADF);
+
+	/* none of the failed calls consumed anything */
+	assert(readv(p[0], &iov, 0) == 0);
+	iov.iov_base = buf;
+	assert(readv(p[0], &iov, 1) == 4 && memcmp(buf, "data", 4) == 0);
+
+	assert(close(p[0]) == 0);
+	assert(close(p[1]) == 0);
+}
+
+static void test_mmsg(void)
+{
+	struct mmsghdr msgs[4];
+	struct iovec iov[4];
+	char bufs[4][8];
+	const char *data[3] = { "one", "two", "three" };
+	int sv[2], i;
+
+	assert(socketpair(AF_UNIX, SOCK_DGRAM, 0, sv) == 0);
+
+	memset(msgs, 0, sizeof(msgs));
+	for (i = 0; i < 3; i++) {
+		iov[i] = (struct iovec){ .iov_base = (void *)data[i], .iov_len = strlen(data[i]) };
+		msgs[i].msg_hdr.msg_iov = &iov[i];
+		msgs[i].msg_hdr.msg_iovlen = 1;
+	}
+	assert(sendmmsg(sv[0], msgs, 3, 0) == 3);
+	for (i = 0; i < 3; i++)
+		assert(msgs[i].msg_len == strlen(data[i]));
+
+	/* fewer slots than queued messages: the rest stays queued */
+	memset(msgs, 0, sizeof(msgs));
+	for (i = 0; i < 4; i++) {
+		iov[i] = (struct iovec){ .iov_base = bufs[i], .iov_len = 4 };
+		msgs[i].msg_hdr.msg_iov = &iov[i];
+		msgs[i].msg_hdr.msg_iovlen = 1;
+	}
+	assert(recvmmsg(sv[1], msgs, 2, 0, NULL) == 2);
+	assert(msgs[0].msg_len == 3 && memcmp(bufs[0], "one", 3) == 0);
+	assert(msgs[1].msg_len == 3 && memcmp(bufs[1], "two", 3) == 0);
+	assert(!(msgs[1].msg_hdr.msg_flags & MSG_TRUNC));
+
+	/* a datagram longer than its buffer is cut, and the call returns once nothing is left */
+	assert(recvmmsg(sv[1], msgs + 2, 2, MSG_DONTWAIT, NULL) == 1);
+	assert(msgs[2].msg_len == 4 && memcmp(bufs[2], "thre", 4) == 0);
+	assert(msgs[2].msg_hdr.msg_flags & MSG_TRUNC);
+
+	errno = 0;
+	assert(recvmmsg(sv[1], msgs, 4, MSG_DONTWAIT, NULL) == -1 && errno == EAGAIN);
+
+	assert(close(sv[0]) == 0);
+	assert(close(sv[1]) == 0);
+}
+
+int main(void)
+{
+	test_file();
+	test_pipe();
+	test_invalid();
+	test_mmsg();
+
+	puts("iovec_io: ok");
+	return 0;
+}
@@ -0,0 +1,192 @@
+/*
+ * Deterministic: TCP and UDP over the loopback network of the virtual network namespace
+ * (see runflags/netns.flags). Nothing outside of the namespace can be reached, so the
+ * output is compared against expected/ instead of a native run.
+ */
+
+#include <arpa/inet.h>
+#include <assert.h>
+#include <errno.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#define TCP_PORT 8080
+#define UDP_PORT 5353
+
+static struct sockaddr_in addr4(const char *ip, int port)
+{
+	struct sockaddr_in sin = { .sin_family = AF_INET, .sin_port = htons(port) };
+
+	assert(inet_pton(AF_INET, ip, &sin.sin_addr) == 1);
+	return sin;
+}
+
+static void check_loopback(const struct sockaddr_in *sin, int port)
+{
+	assert(sin->sin_family == AF_INET);
+	assert(sin->sin_addr.s_addr == htonl(INADDR_LOOPBACK));
+	if (port >= 0)
+		assert(ntohs(sin->sin_port) == port);
+}
+
+static void test_tcp(void)
+{
+	struct sockaddr_in sin, peer;
+	socklen_t len;
+	int lsock, csock, asock, other;
+	char buf[8];
+
+	lsock = socket(AF_INET, SOCK_STREAM, 0);
+	assert(lsock >= 0);
+	sin = addr4("127.0.0.1", TCP_PORT);
+	assert(bind(lsock, (struct sockaddr *)&sin, sizeof(sin)) == 0);
+	assert(listen(lsock, 4) == 0);
+
+	/* the port space is the namespace's, and it is taken now */
+	other = socket(AF_INET, SOCK_STREAM, 0);
+	assert(other >= 0);
+	errno = 0;
+	assert(bind(other, (struct sockaddr *)&sin, sizeof(sin)) == -1 && errno == EADDRINUSE);
+	assert(close(other) == 0);
+
+	/* any loopback address reaches the one host of the namespace */
+	csock = socket(AF_INET, SOCK_STREAM, 0);
+	assert(csock >= 0);
+	sin = addr4("127.0.0.2", TCP_PORT);
+	assert(connect(csock, (struct sockaddr *)&sin, sizeof(sin)) == 0);
+
+	len = sizeof(peer);
+	asock = accept(lsock, (struct sockaddr *)&peer, &len);
+	assert(asock >= 0);
+	check_loopback(&peer, -1);
+	assert(ntohs(peer.sin_port) >= 32768 && ntohs(peer.sin_port) <= 60999);
+
+	/* both ends agree on the ports */
+	len = sizeof(sin);
+	assert(getsockname(csock, (struct sockaddr *)&sin, &len) == 0);
+	assert(sin.sin_port == peer.sin_port);
+	len = sizeof(sin);
+	assert(getpeername(csock, (struct sockaddr *)&sin, &len) == 0);
+	check_loopback(&sin, TCP_PORT);
+	len = sizeof(sin);
+	assert(getsockname(asock, (struct sockaddr *)&sin, &len) == 0);
+	check_loopback(&sin, TCP_PORT);
+
+	assert(write(csock, "ping", 4) == 4);
+	assert(read(asock, buf, sizeof(buf)) == 4 && memcmp(buf, "ping", 4) == 0);
+	assert(write(asock, "pong", 4) == 4);
+	assert(read(csock, buf, sizeof(buf)) == 4 && memcmp(buf, "pong", 4) == 0);
+
+	assert(close(csock) == 0);
+	assert(read(asock, buf, sizeof(buf)) == 0);
+	assert(close(asock) == 0);
+	assert(close(lsock) == 0);
+
+	/* once closed, the port is free again and nobody answers on it */
+	csock = socket(AF_INET, SOCK_STREAM, 0);
+	assert(csock >= 0);
+	sin = addr4("127.0.0.1", TCP_PORT);
+	errno = 0;
+	assert(connect(csock, (struct sockaddr *)&sin, sizeof(sin)) == -1 && errno == ECONNREFUSED);
+	assert(close(csock) == 0);
+	puts("tcp: ok");
+}
+
+static void test_udp(void)
+{
+	struct sockaddr_in sin, from;
+	socklen_t len;
+	int a, b;
+	char buf[8];
+
+	a = socket(AF_INET, SOCK_DGRAM, 0);
+	b = socket(AF_INET, SOCK_DGRAM, 0);
+	assert(a >= 0 && b >= 0);
+	sin = addr4("127.0.0.1", UDP_PORT);
+	assert(bind(b, (struct sockaddr *)&sin, sizeof(sin)) == 0);
+
+	/* the sender gets an ephemeral port, which the receiver sees */
+	assert(sendto(a, "dgram", 5, 0, (struct sockaddr *)&sin, sizeof(sin)) == 5);
+	len = sizeof(from);
+	assert(recvfrom(b, buf, sizeof(buf), 0, (struct sockaddr *)&from, &len) == 5);
+	assert(memcmp(buf, "dgram", 5) == 0);
+	check_loopback(&from, -1);
+	len = sizeof(sin);
+	assert(getsockname(a, (struct sockaddr *)&sin, &len) == 0);
+	assert(sin.sin_port == from.sin_port);
+
+	assert(sendto(b, "back", 4, 0, (struct sockaddr *)&from, sizeof(from)) == 4);
+	assert(recv(a, buf, sizeof(buf), 0) == 4 && memcmp(buf, "back", 4) == 0);
+
+	assert(close(a) == 0);
+	assert(close(b) == 0);
+	puts("udp: ok");
+}
+
+static void test_ipv6(void)
+{
+	struct sockaddr_in6 sin6 = { .sin6_family = AF_INET6, .sin6_port = htons(TCP_PORT) };
+	struct sockaddr_in6 peer;
+	socklen_t len;
+	int lsock, csock, asock;
+
+	sin6.sin6_addr = in6addr_loopback;
+	lsock = socket(AF_INET6, SOCK_STREAM, 0);
+	assert(lsock >= 0);
+	assert(bind(lsock, (struct sockaddr *)&sin6, sizeof(sin6)) == 0);
+	assert(listen(lsock, 1) == 0);
+
+	csock = socket(AF_INET6, SOCK_STREAM, 0);
+	assert(csock >= 0);
+	assert(connect(csock, (struct sockaddr *)&sin6, sizeof(sin6)) == 0);
+	len = sizeof(peer);
+	asock = accept(lsock, (struct sockaddr *)&peer, &len);
+	assert(asock >= 0);
+	assert(peer.sin6_family == AF_INET6);
+	assert(memcmp(&peer.sin6_addr, &in6addr_loopback, sizeof(peer.sin6_addr)) == 0);
+
+	assert(close(asock) == 0);
+	assert(close(csock) == 0);
+	assert(close(lsock) == 0);
+	puts("ipv6: ok");
+}
+
+static void test_isolation(void)
+{
+	struct sockaddr_in sin;
+	int sock;
+
+	/* there is no route out of the namespace */
+	sock = socket(AF_INET, SOCK_STREAM, 0);
+	assert(sock >= 0);
+	sin = addr4("192.0.2.1", 80);
+	errno = 0;
+	assert(connect(sock, (struct sockaddr *)&sin, sizeof(sin)) == -1 && errno == ENETUNREACH);
+	assert(close(sock) == 0);
+
+	sock = socket(AF_INET, SOCK_DGRAM, 0);
+	assert(sock >= 0);
+	sin = addr4("8.8.8.8", 53);
+	errno = 0;
+	assert(sendto(sock, "q", 1, 0, (struct sockaddr *)&sin, sizeof(sin)) == -1 &&
+	       errno == ENETUNREACH);
+
+	/* and no address to bind but the loopback ones */
+	sin = addr4("192.0.2.1", 0);
+	errno = 0;
+	assert(bind(sock, (struct sockaddr *)&sin, sizeof(sin)) == -1 && errno == EADDRNOTAVAIL);
+	assert(close(sock) == 0);
+	puts("isolation: ok");
+}
+
+int main(void)
+{
+	test_tcp();
+	test_udp();
+	test_ipv6();
+	test_isolation();
+	return 0;
+}
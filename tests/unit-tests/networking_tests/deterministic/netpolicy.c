@@ -0,0 +1,163 @@
+/*
+ * Deterministic: the network policy of runflags/netpolicy.flags, on the private network of
+ * the namespace. Denied destinations fail with ENETUNREACH, denied binds, listens and socket
+ * kinds with EACCES. The output is compared against expected/.
+ */
+
+#include <arpa/inet.h>
+#include <assert.h>
+#include <errno.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <unistd.h>
+
+static struct sockaddr_in addr4(const char *ip, int port)
+{
+	struct sockaddr_in sin = { .sin_family = AF_INET, .sin_port = htons(port) };
+
+	assert(inet_pton(AF_INET, ip, &sin.sin_addr) == 1);
+	return sin;
+}
+
+static int bound_socket(int type, const char *ip, int port)
+{
+	struct sockaddr_in sin = addr4(ip, port);
+	int sock;
+
+	sock = socket(AF_INET, type, 0);
+	assert(sock >= 0);
+	if (bind(sock, (struct sockaddr *)&sin, sizeof(sin)) < 0) {
+		int err = errno;
+
+		assert(close(sock) == 0);
+		errno = err;
+		return -1;
+	}
+	return sock;
+}
+
+static int try_connect(const char *ip, int port)
+{
+	struct sockaddr_in sin = addr4(ip, port);
+	int sock, ret, err;
+
+	sock = socket(AF_INET, SOCK_STREAM, 0);
+	assert(sock >= 0);
+	ret = connect(sock, (struct sockaddr *)&sin, sizeof(sin));
+	err = errno;
+	assert(close(sock) == 0);
+	errno = err;
+	return ret;
+}
+
+static void test_socket(void)
+{
+	int sock, sv[2];
+
+	errno = 0;
+	assert(socket(AF_INET6, SOCK_STREAM, 0) == -1 && errno == EACCES);
+	errno = 0;
+	assert(socket(AF_INET, SOCK_RAW, IPPROTO_ICMP) == -1 && errno == EACCES);
+
+	/* what is not listed is still allowed */
+	sock = socket(AF_INET, SOCK_DGRAM, 0);
+	assert(sock >= 0);
+	assert(close(sock) == 0);
+	assert(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+	assert(close(sv[0]) == 0 && close(sv[1]) == 0);
+	puts("socket: ok");
+}
+
+static void test_bind_listen(void)
+{
+	int sock;
+
+	errno = 0;
+	assert(bound_socket(SOCK_STREAM, "127.0.0.1", 9050) == -1 && errno == EACCES);
+
+	/* the port can be bound, but not listened on */
+	sock = bound_socket(SOCK_STREAM, "127.0.0.1", 8200);
+	assert(sock >= 0);
+	errno = 0;
+	assert(listen(sock, 1) == -1 && errno == EACCES);
+	assert(close(sock) == 0);
+
+	sock = bound_socket(SOCK_STREAM, "127.0.0.1", 8000);
+	assert(sock >= 0);
+	assert(listen(sock, 1) == 0);
+	assert(close(sock) == 0);
+	puts("bind/listen: ok");
+}
+
+static void test_connect(void)
+{
+	struct sockaddr_in ok, denied;
+	int lsock, sock, conn;
+
+	/* allowed, and working */
+	lsock = bound_socket(SOCK_STREAM, "127.0.0.1", 8010);
+	assert(lsock >= 0);
+	assert(listen(lsock, 1) == 0);
+	assert(try_connect("127.0.0.1", 8010) == 0);
+	conn = accept(lsock, NULL, NULL);
+	assert(conn >= 0);
+	assert(close(conn) == 0);
+	assert(close(lsock) == 0);
+
+	/* denied even though a listener is there */
+	lsock = bound_socket(SOCK_STREAM, "127.0.0.1", 8050);
+	assert(lsock >= 0);
+	assert(listen(lsock, 1) == 0);
+	errno = 0;
+	assert(try_connect("127.0.0.1", 8050) == -1 && errno == ENETUNREACH);
+	assert(close(lsock) == 0);
+
+	/* outside of the allowed ports, the deny rule only covers 127.0.0.1 */
+	errno = 0;
+	assert(try_connect("127.0.0.1", 7999) == -1 && errno == ENETUNREACH);
+	errno = 0;
+	assert(try_connect("127.0.0.2", 8050) == -1 && errno == ECONNREFUSED);
+
+	/* datagrams are checked per destination */
+	sock = socket(AF_INET, SOCK_DGRAM, 0);
+	assert(sock >= 0);
+	ok = addr4("127.0.0.1", 8020);
+	denied = addr4("127.0.0.1", 8101);
+	assert(sendto(sock, "x", 1, 0, (struct sockaddr *)&ok, sizeof(ok)) == 1);
+	errno = 0;
+	assert(sendto(sock, "x", 1, 0, (struct sockaddr *)&denied, sizeof(denied)) == -1 &&
+	       errno == ENETUNREACH);
+	assert(close(sock) == 0);
+	puts("connect: ok");
+}
+
+static void test_unix(void)
+{
+	struct sockaddr_un sun = { .sun_family = AF_UNIX };
+	int lsock, sock;
+
+	/* unix domain addresses are not subject to the rules */
+	strcpy(sun.sun_path + 1, "netpolicy-test");
+	lsock = socket(AF_UNIX, SOCK_STREAM, 0);
+	assert(lsock >= 0);
+	assert(bind(lsock, (struct sockaddr *)&sun, sizeof(sun)) == 0);
+	assert(listen(lsock, 1) == 0);
+	sock = socket(AF_UNIX, SOCK_STREAM, 0);
+	assert(sock >= 0);
+	assert(connect(sock, (struct sockaddr *)&sun, sizeof(sun)) == 0);
+	assert(close(sock) == 0);
+	assert(close(lsock) == 0);
+	puts("unix: ok");
+}
+
+int main(void)
+{
+	test_socket();
+	test_bind_listen();
+	test_connect();
+	test_unix();
+	return 0;
+}
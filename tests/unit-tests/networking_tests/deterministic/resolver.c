@@ -0,0 +1,188 @@
+/*
+ * Deterministic: name resolution from the files lind-boot generates for the host table of
+ * runflags/resolver.flags, through glibc and through the DNS stub of the namespace. The
+ * host has other names, so the output is compared against expected/.
+ */
+
+#define _GNU_SOURCE
+#include <arpa/inet.h>
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <netdb.h>
+#include <netinet/in.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#define HOSTS \
+	"# Generated by lind-boot\n" \
+	"127.0.0.1\tlocalhost\n" \
+	"::1\tlocalhost\n" \
+	"10.0.0.5\tdb.test\n" \
+	"fd00::5\tv6.test\n" \
+	"127.0.1.1\tresolver-host\n"
+
+static void test_files(void)
+{
+	char buf[512];
+	ssize_t n;
+	int fd;
+
+	fd = open("/etc/hosts", O_RDONLY);
+	assert(fd >= 0);
+	n = read(fd, buf, sizeof(buf));
+	assert(n == (ssize_t)strlen(HOSTS) && memcmp(buf, HOSTS, n) == 0);
+	assert(close(fd) == 0);
+
+	fd = open("/etc/resolv.conf", O_RDONLY);
+	assert(fd >= 0);
+	n = read(fd, buf, sizeof(buf) - 1);
+	assert(n > 0);
+	buf[n] = '\0';
+	assert(strstr(buf, "nameserver 127.0.0.1\n") != NULL);
+	assert(close(fd) == 0);
+
+	fd = open("/etc/nsswitch.conf", O_RDONLY);
+	assert(fd >= 0);
+	n = read(fd, buf, sizeof(buf) - 1);
+	assert(n > 0);
+	buf[n] = '\0';
+	assert(strstr(buf, "hosts:\tfiles dns\n") != NULL);
+	assert(close(fd) == 0);
+
+	/* they are generated, not stored */
+	errno = 0;
+	assert(open("/etc/hosts", O_WRONLY) == -1 && errno == EACCES);
+	puts("files: ok");
+}
+
+/* The one address getaddrinfo finds for `name` in `family`, as text */
+static void lookup(const char *name, int family, const char *expected)
+{
+	struct addrinfo hints = { .ai_family = family, .ai_socktype = SOCK_STREAM };
+	struct addrinfo *res;
+	char text[INET6_ADDRSTRLEN];
+	const void *addr;
+
+	assert(getaddrinfo(name, "80", &hints, &res) == 0);
+	assert(res != NULL && res->ai_next == NULL && res->ai_family == family);
+	if (family == AF_INET)
+		addr = &((struct sockaddr_in *)res->ai_addr)->sin_addr;
+	else
+		addr = &((struct sockaddr_in6 *)res->ai_addr)->sin6_addr;
+	assert(inet_ntop(family, addr, text, sizeof(text)) != NULL);
+	assert(strcmp(text, expected) == 0);
+	freeaddrinfo(res);
+}
+
+static void test_getaddrinfo(void)
+{
+	struct addrinfo hints = { .ai_family = AF_UNSPEC, .ai_socktype = SOCK_STREAM };
+	struct sockaddr_in sin = { .sin_family = AF_INET };
+	struct addrinfo *res;
+	char host[NI_MAXHOST];
+	int ret;
+
+	lookup("localhost", AF_INET, "127.0.0.1");
+	lookup("localhost", AF_INET6, "::1");
+	lookup("db.test", AF_INET, "10.0.0.5");
+	lookup("DB.TEST", AF_INET, "10.0.0.5");
+	lookup("v6.test", AF_INET6, "fd00::5");
+	lookup("resolver-host", AF_INET, "127.0.1.1");
+
+	/* unknown to the table, and to the stub */
+	ret = getaddrinfo("nowhere.test", "80", &hints, &res);
+	assert(ret == EAI_NONAME);
+
+	/* and the other way around */
+	assert(inet_pton(AF_INET, "10.0.0.5", &sin.sin_addr) == 1);
+	assert(getnameinfo((struct sockaddr *)&sin, sizeof(sin), host, sizeof(host), NULL, 0,
+			   NI_NAMEREQD) == 0);
+	assert(strcmp(host, "db.test") == 0);
+	puts("getaddrinfo: ok");
+}
+
+/* Send a query for `name` (in wire format) and return the length of the reply */
+static int query(int sock, const char *name, size_t name_len, uint16_t qtype, unsigned char *reply,
+		 size_t reply_size)
+{
+	struct sockaddr_in sin = { .sin_family = AF_INET, .sin_port = htons(53) };
+	unsigned char msg[128] = { 0x12, 0x34, 0x01, 0x00, 0x00, 0x01 };
+	size_t len = 12;
+	int n;
+
+	memcpy(msg + len, name, name_len);
+	len += name_len;
+	msg[len++] = qtype >> 8;
+	msg[len++] = qtype & 0xff;
+	msg[len++] = 0;
+	msg[len++] = 1;
+
+	assert(inet_pton(AF_INET, "127.0.0.1", &sin.sin_addr) == 1);
+	assert(sendto(sock, msg, len, 0, (struct sockaddr *)&sin, sizeof(sin)) == (ssize_t)len);
+	n = recv(sock, reply, reply_size, 0);
+	assert(n >= 12 + (int)name_len + 4);
+
+	/* same id, a response, and the question echoed back */
+	assert(reply[0] == 0x12 && reply[1] == 0x34);
+	assert(reply[2] & 0x80);
+	assert(reply[4] == 0 && reply[5] == 1);
+	assert(memcmp(reply + 12, msg + 12, len - 12) == 0);
+	return n;
+}
+
+static void test_stub(void)
+{
+	static const char db[] = "\2db\4test";
+	static const char v6[] = "\2v6\4test";
+	static const char nowhere[] = "\7nowhere\4test";
+	static const unsigned char v6_addr[16] = { 0xfd, [15] = 5 };
+	unsigned char reply[512];
+	unsigned char *rr;
+	int sock, n;
+
+	sock = socket(AF_INET, SOCK_DGRAM, 0);
+	assert(sock >= 0);
+
+	/* one A record: the name as a pointer, type, class, ttl, length and the address */
+	n = query(sock, db, sizeof(db), 1, reply, sizeof(reply));
+	assert((reply[3] & 0x0f) == 0);
+	assert(reply[6] == 0 && reply[7] == 1);
+	rr = reply + 12 + sizeof(db) + 4;
+	assert(n == rr - reply + 16);
+	assert(rr[0] == 0xc0 && rr[1] == 12);
+	assert(rr[2] == 0 && rr[3] == 1 && rr[4] == 0 && rr[5] == 1);
+	assert(rr[10] == 0 && rr[11] == 4);
+	assert(rr[12] == 10 && rr[13] == 0 && rr[14] == 0 && rr[15] == 5);
+
+	n = query(sock, v6, sizeof(v6), 28, reply, sizeof(reply));
+	assert((reply[3] & 0x0f) == 0);
+	assert(reply[6] == 0 && reply[7] == 1);
+	rr = reply + 12 + sizeof(v6) + 4;
+	assert(n == rr - reply + 28);
+	assert(rr[10] == 0 && rr[11] == 16 && memcmp(rr + 12, v6_addr, 16) == 0);
+
+	/* a known name without records of the type asked for */
+	query(sock, db, sizeof(db), 28, reply, sizeof(reply));
+	assert((reply[3] & 0x0f) == 0);
+	assert(reply[6] == 0 && reply[7] == 0);
+
+	/* an unknown name is NXDOMAIN */
+	query(sock, nowhere, sizeof(nowhere), 1, reply, sizeof(reply));
+	assert((reply[3] & 0x0f) == 3);
+	assert(reply[6] == 0 && reply[7] == 0);
+
+	assert(close(sock) == 0);
+	puts("dns stub: ok");
+}
+
+int main(void)
+{
+	test_files();
+	test_getaddrinfo();
+	test_stub();
+	return 0;
+}
@@ -0,0 +1,146 @@
+/*
+ * Deterministic: fds passed from a forked child with SCM_RIGHTS over a socketpair, both
+ * kernel-backed (a pipe) and not (/dev/zero), with MSG_CMSG_CLOEXEC and with a control
+ * buffer too small for what was sent (MSG_CTRUNC).
+ */
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define MAX_FDS 4
+
+static void send_fds(int sock, const int *fds, int nfds)
+{
+	char control[CMSG_SPACE(MAX_FDS * sizeof(int))];
+	struct iovec iov = { .iov_base = "x", .iov_len = 1 };
+	struct msghdr msg = { .msg_iov = &iov, .msg_iovlen = 1 };
+	struct cmsghdr *cmsg;
+
+	memset(control, 0, sizeof(control));
+	msg.msg_control = control;
+	msg.msg_controllen = CMSG_SPACE(nfds * sizeof(int));
+	cmsg = CMSG_FIRSTHDR(&msg);
+	cmsg->cmsg_level = SOL_SOCKET;
+	cmsg->cmsg_type = SCM_RIGHTS;
+	cmsg->cmsg_len = CMSG_LEN(nfds * sizeof(int));
+	memcpy(CMSG_DATA(cmsg), fds, nfds * sizeof(int));
+	assert(sendmsg(sock, &msg, 0) == 1);
+}
+
+/*
+ * Receive one message with a control buffer sized for `room` fds, returning how many were
+ * received and whether the control data was truncated. The buffer is padded to the
+ * alignment of the platform, so it may hold one more fd than asked for.
+ */
+static int recv_fds(int sock, int *fds, int room, int flags, int *truncated)
+{
+	char control[CMSG_SPACE(MAX_FDS * sizeof(int))];
+	struct msghdr msg = { 0 };
+	struct cmsghdr *cmsg;
+	struct iovec iov;
+	char c;
+	int n;
+
+	iov.iov_base = &c;
+	iov.iov_len = 1;
+	msg.msg_iov = &iov;
+	msg.msg_iovlen = 1;
+	if (room > 0) {
+		msg.msg_control = control;
+		msg.msg_controllen = CMSG_SPACE(room * sizeof(int));
+	}
+	assert(recvmsg(sock, &msg, flags) == 1 && c == 'x');
+	*truncated = (msg.msg_flags & MSG_CTRUNC) != 0;
+
+	cmsg = CMSG_FIRSTHDR(&msg);
+	if (cmsg == NULL)
+		return 0;
+	assert(cmsg->cmsg_level == SOL_SOCKET && cmsg->cmsg_type == SCM_RIGHTS);
+	n = (cmsg->cmsg_len - CMSG_LEN(0)) / sizeof(int);
+	assert(n <= MAX_FDS);
+	memcpy(fds, CMSG_DATA(cmsg), n * sizeof(int));
+	assert(CMSG_NXTHDR(&msg, cmsg) == NULL);
+	return n;
+}
+
+static void child(int sock)
+{
+	int pipefd[2], fds[3], zero;
+	char c;
+
+	assert(pipe(pipefd) == 0);
+	assert(write(pipefd[1], "through the pipe", 16) == 16);
+	assert(close(pipefd[1]) == 0);
+	zero = open("/dev/zero", O_RDONLY);
+	assert(zero >= 0);
+
+	/* a kernel-backed fd and a device of lind's own in one message */
+	fds[0] = pipefd[0];
+	fds[1] = zero;
+	send_fds(sock, fds, 2);
+
+	/* more fds than the receiver has room for */
+	fds[0] = fds[1] = fds[2] = zero;
+	send_fds(sock, fds, 3);
+
+	/* and no room at all */
+	send_fds(sock, &zero, 1);
+
+	/* sending does not take the fds away from the sender */
+	assert(read(zero, &c, 1) == 1 && c == 0);
+	assert(close(pipefd[0]) == 0);
+	assert(close(zero) == 0);
+	_exit(0);
+}
+
+int main(void)
+{
+	int sv[2], fds[MAX_FDS], truncated, status, n, i;
+	char buf[32];
+	pid_t pid;
+
+	assert(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		assert(close(sv[0]) == 0);
+		child(sv[1]);
+	}
+	assert(close(sv[1]) == 0);
+
+	assert(recv_fds(sv[0], fds, MAX_FDS, MSG_CMSG_CLOEXEC, &truncated) == 2);
+	assert(!truncated);
+	assert(fds[0] >= 0 && fds[1] >= 0 && fds[0] != fds[1]);
+	assert(fcntl(fds[0], F_GETFD) & FD_CLOEXEC);
+	assert(fcntl(fds[1], F_GETFD) & FD_CLOEXEC);
+	assert(read(fds[0], buf, sizeof(buf)) == 16 && memcmp(buf, "through the pipe", 16) == 0);
+	memset(buf, 'x', sizeof(buf));
+	assert(read(fds[1], buf, 8) == 8 && buf[0] == 0 && buf[7] == 0);
+	assert(close(fds[0]) == 0 && close(fds[1]) == 0);
+
+	/* what does not fit is dropped, and reported */
+	n = recv_fds(sv[0], fds, 1, 0, &truncated);
+	assert(n >= 1 && n < 3);
+	assert(truncated);
+	for (i = 0; i < n; i++) {
+		assert(!(fcntl(fds[i], F_GETFD) & FD_CLOEXEC));
+		assert(read(fds[i], buf, 4) == 4 && buf[0] == 0);
+		assert(close(fds[i]) == 0);
+	}
+
+	assert(recv_fds(sv[0], fds, 0, 0, &truncated) == 0);
+	assert(truncated);
+
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	assert(close(sv[0]) == 0);
+
+	puts("scm_rights: ok");
+	return 0;
+}
@@ -0,0 +1,159 @@
+/*
+ * Deterministic: abstract AF_UNIX names live in a namespace of the lind instance. Cages of
+ * the instance reach each other under the names they bound, which are reported back as
+ * such, while the names of the host (here those of common daemons) are neither reachable
+ * nor taken. The host may use those names, so the output is compared against expected/.
+ */
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <ctype.h>
+#include <errno.h>
+#include <stddef.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+/* An abstract address for `name`, and its length */
+static socklen_t abstract(struct sockaddr_un *sun, const char *name)
+{
+	memset(sun, 0, sizeof(*sun));
+	sun->sun_family = AF_UNIX;
+	memcpy(sun->sun_path + 1, name, strlen(name));
+	return offsetof(struct sockaddr_un, sun_path) + 1 + strlen(name);
+}
+
+static void check_name(const struct sockaddr_un *sun, socklen_t len, const char *name)
+{
+	assert(len == offsetof(struct sockaddr_un, sun_path) + 1 + strlen(name));
+	assert(sun->sun_family == AF_UNIX && sun->sun_path[0] == '\0');
+	assert(memcmp(sun->sun_path + 1, name, strlen(name)) == 0);
+}
+
+static void test_stream(void)
+{
+	struct sockaddr_un sun, got;
+	socklen_t len, got_len;
+	int lsock, sock, conn, status;
+	pid_t pid;
+	char c;
+
+	lsock = socket(AF_UNIX, SOCK_STREAM, 0);
+	assert(lsock >= 0);
+	len = abstract(&sun, "unixns.server");
+	assert(bind(lsock, (struct sockaddr *)&sun, len) == 0);
+	assert(listen(lsock, 1) == 0);
+	got_len = sizeof(got);
+	assert(getsockname(lsock, (struct sockaddr *)&got, &got_len) == 0);
+	check_name(&got, got_len, "unixns.server");
+
+	/* another cage of the instance connects under the same name */
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		sock = socket(AF_UNIX, SOCK_STREAM, 0);
+		assert(sock >= 0);
+		assert(connect(sock, (struct sockaddr *)&sun, len) == 0);
+		got_len = sizeof(got);
+		assert(getpeername(sock, (struct sockaddr *)&got, &got_len) == 0);
+		check_name(&got, got_len, "unixns.server");
+		assert(write(sock, "c", 1) == 1);
+		assert(close(sock) == 0);
+		_exit(0);
+	}
+
+	/* the client is not bound, so it has no name */
+	got_len = sizeof(got);
+	conn = accept(lsock, (struct sockaddr *)&got, &got_len);
+	assert(conn >= 0);
+	assert(got_len == sizeof(sa_family_t));
+	assert(read(conn, &c, 1) == 1 && c == 'c');
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	assert(close(conn) == 0);
+	assert(close(lsock) == 0);
+	puts("stream: ok");
+}
+
+static void test_dgram(void)
+{
+	char name[sizeof(((struct sockaddr_un *)0)->sun_path)];
+	struct sockaddr_un sun, from;
+	socklen_t len, from_len;
+	int a, b, i;
+	char buf[4];
+
+	/* an autobind name is five hex digits */
+	a = socket(AF_UNIX, SOCK_DGRAM, 0);
+	assert(a >= 0);
+	sun.sun_family = AF_UNIX;
+	assert(bind(a, (struct sockaddr *)&sun, sizeof(sa_family_t)) == 0);
+	len = sizeof(sun);
+	assert(getsockname(a, (struct sockaddr *)&sun, &len) == 0);
+	assert(len == offsetof(struct sockaddr_un, sun_path) + 6 && sun.sun_path[0] == '\0');
+	for (i = 1; i < 6; i++)
+		assert(isxdigit((unsigned char)sun.sun_path[i]));
+
+	/* the longest name there is comes back whole */
+	memset(name, 'n', sizeof(name) - 1);
+	name[sizeof(name) - 1] = '\0';
+	b = socket(AF_UNIX, SOCK_DGRAM, 0);
+	assert(b >= 0);
+	len = abstract(&sun, name);
+	assert(len == sizeof(sun));
+	assert(bind(b, (struct sockaddr *)&sun, len) == 0);
+
+	assert(sendto(a, "dg", 2, 0, (struct sockaddr *)&sun, len) == 2);
+	from_len = sizeof(from);
+	assert(recvfrom(b, buf, sizeof(buf), 0, (struct sockaddr *)&from, &from_len) == 2);
+	assert(from_len == offsetof(struct sockaddr_un, sun_path) + 6 && from.sun_path[0] == '\0');
+
+	assert(sendto(b, "re", 2, 0, (struct sockaddr *)&from, from_len) == 2);
+	from_len = sizeof(from);
+	assert(recvfrom(a, buf, sizeof(buf), 0, (struct sockaddr *)&from, &from_len) == 2);
+	check_name(&from, from_len, name);
+
+	assert(close(a) == 0);
+	assert(close(b) == 0);
+	puts("dgram: ok");
+}
+
+static void test_host_names(void)
+{
+	static const char *const names[] = {
+		"/tmp/.X11-unix/X0",
+		"/org/kernel/linux/storage/multipathd",
+		"ISCSIADM_ABSTRACT_NAMESPACE",
+	};
+	struct sockaddr_un sun;
+	socklen_t len;
+	unsigned int i;
+	int sock;
+
+	for (i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+		len = abstract(&sun, names[i]);
+
+		/* nothing listens there in the namespace, whatever the host runs */
+		sock = socket(AF_UNIX, SOCK_STREAM, 0);
+		assert(sock >= 0);
+		errno = 0;
+		assert(connect(sock, (struct sockaddr *)&sun, len) == -1 && errno == ECONNREFUSED);
+
+		/* and the name is free to take */
+		assert(bind(sock, (struct sockaddr *)&sun, len) == 0);
+		assert(close(sock) == 0);
+	}
+	puts("host names: ok");
+}
+
+int main(void)
+{
+	test_stream();
+	test_dgram();
+	test_host_names();
+	return 0;
+}
@@ -0,0 +1,198 @@
+/*
+ * Deterministic: a run under lind-boot --deterministic --seed 42 --tick 1us. The clocks are
+ * virtual: they start at the same reading on every run, move forward by one tick per syscall
+ * and by the full length of a sleep, which returns at once. getrandom() and /dev/urandom
+ * hand out the ChaCha20 stream keyed by the seed and the cage id, so a new cage always sees
+ * the same bytes. Directories are listed in name order. Outside of lind only the calls
+ * themselves are checked.
+ */
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <dirent.h>
+#include <fcntl.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/random.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+#define SEED 42
+#define TICK_NS 1000L
+#define REALTIME_START 946684800L /* 2000-01-01 00:00:00 UTC */
+
+static const char *const names[] = { "m", "c", "x", "a", "k" };
+#define NNAMES (sizeof(names) / sizeof(names[0]))
+
+static long ts_ns(clockid_t clock)
+{
+	struct timespec ts;
+
+	assert(clock_gettime(clock, &ts) == 0);
+	return ts.tv_sec * 1000000000L + ts.tv_nsec;
+}
+
+static void test_clock(void)
+{
+	struct timespec hour = { 3600, 0 };
+	long before, after, real;
+
+	before = ts_ns(CLOCK_MONOTONIC);
+	after = ts_ns(CLOCK_MONOTONIC);
+	assert(after > before);
+#ifdef __wasm__
+	/* the clock starts at one second and a tick passes between two readings */
+	assert(before >= 1000000000L && before < 2000000000L);
+	assert(after - before == TICK_NS);
+
+	/* the realtime clock reads 2000-01-01 plus the monotonic clock */
+	before = ts_ns(CLOCK_MONOTONIC);
+	real = ts_ns(CLOCK_REALTIME);
+	after = ts_ns(CLOCK_MONOTONIC);
+	assert(real - REALTIME_START * 1000000000L > before);
+	assert(real - REALTIME_START * 1000000000L < after);
+
+	/* a sleep takes its full length on the virtual clock, and no time on the host */
+	before = ts_ns(CLOCK_MONOTONIC);
+	assert(nanosleep(&hour, NULL) == 0);
+	after = ts_ns(CLOCK_MONOTONIC);
+	assert(after - before >= 3600 * 1000000000L);
+	assert(after - before < 3600 * 1000000000L + 1000 * TICK_NS);
+#else
+	(void)hour;
+	(void)real;
+#endif
+	puts("clock: ok");
+}
+
+#ifdef __wasm__
+#define ROTL(v, n) (((v) << (n)) | ((v) >> (32 - (n))))
+#define QR(a, b, c, d)                                  \
+	(a += b, d = ROTL(d ^ a, 16), c += d, b = ROTL(b ^ c, 12), \
+	 a += b, d = ROTL(d ^ a, 8), c += d, b = ROTL(b ^ c, 7))
+
+/* The first block of the ChaCha20 keystream with `seed` as key and `nonce` */
+static void chacha20_block(uint64_t seed, uint64_t nonce, unsigned char out[64])
+{
+	uint32_t state[16] = { 0x61707865, 0x3320646e, 0x79622d32, 0x6b206574 }, x[16];
+	int i;
+
+	state[4] = (uint32_t)seed;
+	state[5] = (uint32_t)(seed >> 32);
+	state[14] = (uint32_t)nonce;
+	state[15] = (uint32_t)(nonce >> 32);
+	memcpy(x, state, sizeof(x));
+	for (i = 0; i < 10; i++) {
+		QR(x[0], x[4], x[8], x[12]);
+		QR(x[1], x[5], x[9], x[13]);
+		QR(x[2], x[6], x[10], x[14]);
+		QR(x[3], x[7], x[11], x[15]);
+		QR(x[0], x[5], x[10], x[15]);
+		QR(x[1], x[6], x[11], x[12]);
+		QR(x[2], x[7], x[8], x[13]);
+		QR(x[3], x[4], x[9], x[14]);
+	}
+	for (i = 0; i < 16; i++) {
+		uint32_t v = x[i] + state[i];
+
+		out[4 * i] = v;
+		out[4 * i + 1] = v >> 8;
+		out[4 * i + 2] = v >> 16;
+		out[4 * i + 3] = v >> 24;
+	}
+}
+#endif
+
+static void read_random(unsigned char buf[64])
+{
+	int fd;
+
+	assert(getrandom(buf, 32, 0) == 32);
+	fd = open("/dev/urandom", O_RDONLY);
+	assert(fd >= 0);
+	assert(read(fd, buf + 32, 32) == 32);
+	assert(close(fd) == 0);
+}
+
+static void test_random(void)
+{
+	unsigned char first[64], again[64];
+	int status;
+	pid_t pid;
+
+	read_random(first);
+	read_random(again);
+	assert(memcmp(first, again, sizeof(first)) != 0);
+
+	/* a new cage starts at the beginning of its own stream */
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+#ifdef __wasm__
+		unsigned char block[64];
+
+		chacha20_block(SEED, getpid(), block);
+		read_random(first);
+		if (memcmp(first, block, sizeof(block)) != 0)
+			_exit(1);
+#endif
+		_exit(0);
+	}
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	puts("random: ok");
+}
+
+static void test_listing(void)
+{
+	char path[64], last[256] = "";
+	struct dirent *de;
+	unsigned int i, seen = 0;
+	DIR *dir;
+	int fd;
+
+	assert(mkdir("deterministic_dir.tmp", 0755) == 0);
+	for (i = 0; i < NNAMES; i++) {
+		snprintf(path, sizeof(path), "deterministic_dir.tmp/%s", names[i]);
+		fd = open(path, O_CREAT | O_WRONLY, 0644);
+		assert(fd >= 0 && close(fd) == 0);
+	}
+
+	dir = opendir("deterministic_dir.tmp");
+	assert(dir);
+	while ((de = readdir(dir)) != NULL) {
+#ifdef __wasm__
+		assert(strcmp(last, de->d_name) < 0);
+#endif
+		snprintf(last, sizeof(last), "%s", de->d_name);
+		seen++;
+	}
+	assert(seen == NNAMES + 2);
+
+	/* reading it again lists it the same way */
+	rewinddir(dir);
+	de = readdir(dir);
+	assert(de);
+#ifdef __wasm__
+	assert(strcmp(de->d_name, ".") == 0);
+#endif
+	assert(closedir(dir) == 0);
+
+	for (i = 0; i < NNAMES; i++) {
+		snprintf(path, sizeof(path), "deterministic_dir.tmp/%s", names[i]);
+		assert(unlink(path) == 0);
+	}
+	assert(rmdir("deterministic_dir.tmp") == 0);
+	puts("listing: ok");
+}
+
+int main(void)
+{
+	test_clock();
+	test_random();
+	test_listing();
+	return 0;
+}
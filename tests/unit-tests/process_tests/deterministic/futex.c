@@ -0,0 +1,419 @@
+/*
+ * Deterministic: the futex operations themselves, FUTEX_WAIT and FUTEX_WAKE with and without
+ * bitsets, FUTEX_CMP_REQUEUE, FUTEX_WAKE_OP, the priority-inheritance lock ops, and a signal
+ * ending a FUTEX_WAIT. Then shared futexes in MAP_SHARED memory: process-shared pthread objects
+ * used by a parent and its forked child, and a file mapped twice.
+ */
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <limits.h>
+#include <linux/futex.h>
+#include <fcntl.h>
+#include <pthread.h>
+#include <semaphore.h>
+#include <signal.h>
+#include <stdatomic.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <sys/mman.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+#ifdef __wasm__
+#include <lind_syscall.h>
+
+#define FUTEX_SYSCALL 202
+
+extern uint64_t __lind_base;
+extern uint64_t __lind_cageid;
+
+static uint64_t host_ptr(const void *p)
+{
+	return p ? __lind_base + (uintptr_t)p : 0;
+}
+#endif
+
+/*
+ * The futex syscall. `arg4` is the timeout of the ops that wait, and a count for the others.
+ * lind's glibc has no syscall(), so this goes through 3i like glibc's own futex calls.
+ */
+static long futex(atomic_uint *uaddr, int op, uint32_t val, uintptr_t arg4, atomic_uint *uaddr2,
+		  uint32_t val3)
+{
+#ifdef __wasm__
+	int cmd = op & FUTEX_CMD_MASK;
+	int timed = cmd == FUTEX_WAIT || cmd == FUTEX_WAIT_BITSET || cmd == FUTEX_LOCK_PI ||
+		    cmd == FUTEX_LOCK_PI2 || cmd == FUTEX_WAIT_REQUEUE_PI;
+	uint64_t self = __lind_cageid;
+
+	return make_threei_call(FUTEX_SYSCALL, 0, self, self, host_ptr(uaddr), self, op, self,
+				val, self, timed ? host_ptr((void *)arg4) : arg4, self,
+				host_ptr(uaddr2), self, val3, self, 1);
+#else
+	return syscall(SYS_futex, uaddr, op, val, arg4, uaddr2, val3);
+#endif
+}
+
+/* How many wait on `uaddr`, counted by requeueing them onto the same word */
+static long queued(atomic_uint *uaddr)
+{
+	return futex(uaddr, FUTEX_CMP_REQUEUE, 0, INT_MAX, uaddr, atomic_load(uaddr));
+}
+
+static void wait_queued(atomic_uint *uaddr, long n)
+{
+	while (queued(uaddr) != n)
+		usleep(1000);
+}
+
+struct wait_args {
+	atomic_uint *uaddr;
+	uint32_t val;
+	uint32_t bitset;
+	const struct timespec *timeout;
+	long ret;
+	int err;
+};
+
+static void *waiter(void *arg)
+{
+	struct wait_args *args = arg;
+	int op = args->bitset ? FUTEX_WAIT_BITSET : FUTEX_WAIT;
+
+	errno = 0;
+	args->ret = futex(args->uaddr, op, args->val, (uintptr_t)args->timeout, NULL,
+			  args->bitset);
+	args->err = errno;
+	return NULL;
+}
+
+/* Start a thread waiting on `uaddr`, with FUTEX_WAIT_BITSET if `bitset` is not 0 */
+static void start_waiter(pthread_t *thread, struct wait_args *args, atomic_uint *uaddr,
+			 uint32_t bitset, const struct timespec *timeout)
+{
+	*args = (struct wait_args){
+		.uaddr = uaddr,
+		.val = atomic_load(uaddr),
+		.bitset = bitset,
+		.timeout = timeout,
+	};
+	assert(pthread_create(thread, NULL, waiter, args) == 0);
+}
+
+static void join_woken(pthread_t thread, struct wait_args *args)
+{
+	assert(pthread_join(thread, NULL) == 0);
+	assert(args->ret == 0);
+}
+
+static void test_wait_wake(void)
+{
+	struct timespec ts = { .tv_nsec = 20000000 };
+	struct wait_args args[3];
+	pthread_t threads[3];
+	atomic_uint word = 7;
+	int i;
+
+	/* the word has to hold the value the caller expects */
+	errno = 0;
+	assert(futex(&word, FUTEX_WAIT, 8, 0, NULL, 0) == -1 && errno == EAGAIN);
+	errno = 0;
+	assert(futex(&word, FUTEX_WAIT, 7, (uintptr_t)&ts, NULL, 0) == -1 && errno == ETIMEDOUT);
+	assert(futex(&word, FUTEX_WAKE, 1, 0, NULL, 0) == 0);
+
+	/* a wake wakes no more than it is asked to */
+	for (i = 0; i < 3; i++)
+		start_waiter(&threads[i], &args[i], &word, 0, NULL);
+	wait_queued(&word, 3);
+	assert(futex(&word, FUTEX_WAKE, 2, 0, NULL, 0) == 2);
+	assert(queued(&word) == 1);
+	assert(futex(&word, FUTEX_WAKE, INT_MAX, 0, NULL, 0) == 1);
+	for (i = 0; i < 3; i++)
+		join_woken(threads[i], &args[i]);
+
+	/* and only waiters sharing a bit with its bitset */
+	start_waiter(&threads[0], &args[0], &word, 1, NULL);
+	wait_queued(&word, 1);
+	assert(futex(&word, FUTEX_WAKE_BITSET, INT_MAX, 0, NULL, 2) == 0);
+	assert(futex(&word, FUTEX_WAKE_BITSET, INT_MAX, 0, NULL, 3) == 1);
+	join_woken(threads[0], &args[0]);
+
+	errno = 0;
+	assert(futex(&word, FUTEX_WAKE_BITSET, 1, 0, NULL, 0) == -1 && errno == EINVAL);
+	puts("wait/wake: ok");
+}
+
+static void test_cmp_requeue(void)
+{
+	struct wait_args args[3];
+	pthread_t threads[3];
+	atomic_uint a = 1, b = 2;
+	int i;
+
+	for (i = 0; i < 3; i++)
+		start_waiter(&threads[i], &args[i], &a, 0, NULL);
+	wait_queued(&a, 3);
+
+	/* the word has changed under the caller */
+	errno = 0;
+	assert(futex(&a, FUTEX_CMP_REQUEUE, 1, 1, &b, 0) == -1 && errno == EAGAIN);
+	assert(queued(&a) == 3);
+
+	/* one is woken, one moves over to b, one stays */
+	assert(futex(&a, FUTEX_CMP_REQUEUE, 1, 1, &b, 1) == 2);
+	assert(queued(&a) == 1);
+	assert(queued(&b) == 1);
+	assert(futex(&b, FUTEX_WAKE, INT_MAX, 0, NULL, 0) == 1);
+	assert(futex(&a, FUTEX_WAKE, INT_MAX, 0, NULL, 0) == 1);
+	for (i = 0; i < 3; i++)
+		join_woken(threads[i], &args[i]);
+	puts("cmp_requeue: ok");
+}
+
+static void test_wake_op(void)
+{
+	struct wait_args args[2];
+	pthread_t threads[2];
+	atomic_uint a = 1, b = 0;
+
+	start_waiter(&threads[0], &args[0], &a, 0, NULL);
+	start_waiter(&threads[1], &args[1], &b, 0, NULL);
+	wait_queued(&a, 1);
+	wait_queued(&b, 1);
+
+	/* b += 5, and its waiter is woken too as b was 0 */
+	assert(futex(&a, FUTEX_WAKE_OP, 1, 1, &b,
+		     FUTEX_OP(FUTEX_OP_ADD, 5, FUTEX_OP_CMP_EQ, 0)) == 2);
+	assert(atomic_load(&b) == 5);
+	join_woken(threads[0], &args[0]);
+	join_woken(threads[1], &args[1]);
+
+	/* b |= 1 << 4, and nobody on b is woken as b was not below 5 */
+	start_waiter(&threads[1], &args[1], &b, 0, NULL);
+	wait_queued(&b, 1);
+	assert(futex(&a, FUTEX_WAKE_OP, 1, 1, &b,
+		     FUTEX_OP((FUTEX_OP_OR | FUTEX_OP_OPARG_SHIFT), 4, FUTEX_OP_CMP_LT, 5)) == 0);
+	assert(atomic_load(&b) == 21);
+	assert(queued(&b) == 1);
+	assert(futex(&b, FUTEX_WAKE, 1, 0, NULL, 0) == 1);
+	join_woken(threads[1], &args[1]);
+	puts("wake_op: ok");
+}
+
+static atomic_uint pi_word;
+static atomic_uint pi_owner;
+
+static void *pi_locker(void *arg)
+{
+	uint32_t owner;
+
+	(void)arg;
+	errno = 0;
+	assert(futex(&pi_word, FUTEX_TRYLOCK_PI, 0, 0, NULL, 0) == -1 && errno == EAGAIN);
+
+	/* the lock is handed over on unlock */
+	assert(futex(&pi_word, FUTEX_LOCK_PI, 0, 0, NULL, 0) == 0);
+	owner = atomic_load(&pi_word) & FUTEX_TID_MASK;
+	assert(owner != 0 && owner != pi_owner);
+	assert(futex(&pi_word, FUTEX_UNLOCK_PI, 0, 0, NULL, 0) == 0);
+	return NULL;
+}
+
+static void test_pi(void)
+{
+	pthread_t thread;
+	uint32_t word;
+
+	/* a free lock is taken right away */
+	assert(futex(&pi_word, FUTEX_LOCK_PI, 0, 0, NULL, 0) == 0);
+	pi_owner = atomic_load(&pi_word) & FUTEX_TID_MASK;
+	assert(pi_owner != 0);
+	errno = 0;
+	assert(futex(&pi_word, FUTEX_LOCK_PI, 0, 0, NULL, 0) == -1 && errno == EDEADLK);
+
+	/* a contender marks the word and sleeps until the owner lets go */
+	assert(pthread_create(&thread, NULL, pi_locker, NULL) == 0);
+	do {
+		usleep(1000);
+		word = atomic_load(&pi_word);
+	} while (!(word & FUTEX_WAITERS));
+	assert((word & FUTEX_TID_MASK) == pi_owner);
+	assert(futex(&pi_word, FUTEX_UNLOCK_PI, 0, 0, NULL, 0) == 0);
+	assert(pthread_join(thread, NULL) == 0);
+	assert(atomic_load(&pi_word) == 0);
+
+	/* only the owner can unlock */
+	errno = 0;
+	assert(futex(&pi_word, FUTEX_UNLOCK_PI, 0, 0, NULL, 0) == -1 && errno == EPERM);
+	puts("pi: ok");
+}
+
+static volatile sig_atomic_t handled;
+
+static void handler(int sig)
+{
+	(void)sig;
+	handled++;
+}
+
+static void test_signal(void)
+{
+	struct sigaction sa = { .sa_handler = handler };
+	struct timespec ts = { .tv_sec = 5 };
+	struct wait_args args;
+	pthread_t thread;
+	atomic_uint word = 0;
+
+	/* without SA_RESTART, the handler ends the wait */
+	sigemptyset(&sa.sa_mask);
+	assert(sigaction(SIGUSR1, &sa, NULL) == 0);
+	start_waiter(&thread, &args, &word, 0, NULL);
+	wait_queued(&word, 1);
+	assert(pthread_kill(thread, SIGUSR1) == 0);
+	assert(pthread_join(thread, NULL) == 0);
+	assert(args.ret == -1 && args.err == EINTR);
+	assert(handled == 1);
+	assert(queued(&word) == 0);
+
+	/* and so it does for a timed wait, even with SA_RESTART */
+	sa.sa_flags = SA_RESTART;
+	assert(sigaction(SIGUSR1, &sa, NULL) == 0);
+	start_waiter(&thread, &args, &word, 0, &ts);
+	wait_queued(&word, 1);
+	assert(pthread_kill(thread, SIGUSR1) == 0);
+	assert(pthread_join(thread, NULL) == 0);
+	assert(args.ret == -1 && args.err == EINTR);
+	assert(handled == 2);
+
+	/* an untimed one is made again after the handler, and a wake ends it */
+	start_waiter(&thread, &args, &word, 0, NULL);
+	wait_queued(&word, 1);
+	assert(pthread_kill(thread, SIGUSR1) == 0);
+	while (handled != 3)
+		usleep(1000);
+	wait_queued(&word, 1);
+	assert(futex(&word, FUTEX_WAKE, 1, 0, NULL, 0) == 1);
+	join_woken(thread, &args);
+	puts("signal: ok");
+}
+
+struct shared {
+	pthread_mutex_t lock;
+	pthread_cond_t cond;
+	sem_t sem;
+	int turn;
+	atomic_uint word;
+};
+
+static void shared_child(struct shared *sh)
+{
+	/* take turns with the parent under a process-shared mutex and condition variable */
+	assert(pthread_mutex_lock(&sh->lock) == 0);
+	while (sh->turn != 1)
+		assert(pthread_cond_wait(&sh->cond, &sh->lock) == 0);
+	sh->turn = 2;
+	assert(pthread_cond_signal(&sh->cond) == 0);
+	assert(pthread_mutex_unlock(&sh->lock) == 0);
+
+	assert(sem_wait(&sh->sem) == 0);
+
+	/* and wait on a plain shared futex word */
+	while (atomic_load(&sh->word) == 0)
+		futex(&sh->word, FUTEX_WAIT, 0, 0, NULL, 0);
+	_exit(0);
+}
+
+static void test_process_shared(void)
+{
+	pthread_mutexattr_t mattr;
+	pthread_condattr_t cattr;
+	struct shared *sh;
+	int status;
+	pid_t pid;
+
+	sh = mmap(NULL, sizeof(*sh), PROT_READ | PROT_WRITE, MAP_SHARED | MAP_ANONYMOUS, -1, 0);
+	assert(sh != MAP_FAILED);
+	assert(pthread_mutexattr_init(&mattr) == 0);
+	assert(pthread_mutexattr_setpshared(&mattr, PTHREAD_PROCESS_SHARED) == 0);
+	assert(pthread_mutex_init(&sh->lock, &mattr) == 0);
+	assert(pthread_condattr_init(&cattr) == 0);
+	assert(pthread_condattr_setpshared(&cattr, PTHREAD_PROCESS_SHARED) == 0);
+	assert(pthread_cond_init(&sh->cond, &cattr) == 0);
+	assert(sem_init(&sh->sem, 1, 0) == 0);
+	sh->turn = 0;
+	atomic_store(&sh->word, 0);
+
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0)
+		shared_child(sh);
+
+	/* give the child the time to block on the condition variable first */
+	usleep(100000);
+	assert(pthread_mutex_lock(&sh->lock) == 0);
+	sh->turn = 1;
+	assert(pthread_cond_signal(&sh->cond) == 0);
+	while (sh->turn != 2)
+		assert(pthread_cond_wait(&sh->cond, &sh->lock) == 0);
+	assert(pthread_mutex_unlock(&sh->lock) == 0);
+
+	usleep(100000);
+	assert(sem_post(&sh->sem) == 0);
+
+	/* the parent sees the waiter of the child on the same word, and wakes it */
+	wait_queued(&sh->word, 1);
+	atomic_store(&sh->word, 1);
+	assert(futex(&sh->word, FUTEX_WAKE, 1, 0, NULL, 0) == 1);
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	assert(pthread_cond_destroy(&sh->cond) == 0);
+	assert(pthread_mutex_destroy(&sh->lock) == 0);
+	assert(sem_destroy(&sh->sem) == 0);
+	assert(munmap(sh, sizeof(*sh)) == 0);
+	puts("process shared: ok");
+}
+
+static void test_shared_file(void)
+{
+	long page = sysconf(_SC_PAGESIZE);
+	struct wait_args args;
+	pthread_t thread;
+	char *a, *b;
+	int fd;
+
+	fd = open("futex_shared.tmp", O_CREAT | O_RDWR | O_TRUNC, 0600);
+	assert(fd >= 0);
+	assert(ftruncate(fd, 2 * page) == 0);
+	a = mmap(NULL, 2 * page, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+	b = mmap(NULL, page, PROT_READ | PROT_WRITE, MAP_SHARED, fd, page);
+	assert(a != MAP_FAILED && b != MAP_FAILED);
+	assert(close(fd) == 0);
+	assert(unlink("futex_shared.tmp") == 0);
+
+	/* the same word of the file through two mappings, even with the fd closed */
+	start_waiter(&thread, &args, (atomic_uint *)(a + page), 0, NULL);
+	wait_queued((atomic_uint *)b, 1);
+	assert(futex((atomic_uint *)b, FUTEX_WAKE, 1, 0, NULL, 0) == 1);
+	join_woken(thread, &args);
+
+	assert(munmap(a, 2 * page) == 0);
+	assert(munmap(b, page) == 0);
+	puts("shared file: ok");
+}
+
+int main(void)
+{
+	test_wait_wake();
+	test_cmp_requeue();
+	test_wake_op();
+	test_pi();
+	test_signal();
+	test_process_shared();
+	test_shared_file();
+	return 0;
+}
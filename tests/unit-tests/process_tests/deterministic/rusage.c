@@ -0,0 +1,129 @@
+/*
+ * Deterministic: CPU time accounting of times() and getrusage(). A process is charged the CPU
+ * time it uses, and its parent the time of each child it waited for, never before. The return
+ * value of times() advances with the clock.
+ */
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <stdio.h>
+#include <sys/resource.h>
+#include <sys/times.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+#define MS 1000000L
+#define SEC 1000000000L
+
+static long tv_ns(const struct timeval *tv)
+{
+	return tv->tv_sec * SEC + tv->tv_usec * 1000L;
+}
+
+static long cpu_ns(void)
+{
+	struct timespec ts;
+
+	assert(clock_gettime(CLOCK_PROCESS_CPUTIME_ID, &ts) == 0);
+	return ts.tv_sec * SEC + ts.tv_nsec;
+}
+
+/* Keep the CPU busy until the process used `ns` more CPU time */
+static void spin(long ns)
+{
+	volatile unsigned long spins = 0;
+	long until = cpu_ns() + ns;
+
+	while (cpu_ns() < until)
+		spins++;
+}
+
+static void test_self(void)
+{
+	struct rusage self, thread;
+	struct tms tms;
+	long ticks = sysconf(_SC_CLK_TCK), before;
+
+	before = cpu_ns();
+	spin(200 * MS);
+	assert(getrusage(RUSAGE_SELF, &self) == 0);
+	assert(tv_ns(&self.ru_utime) + tv_ns(&self.ru_stime) >= 200 * MS);
+	assert(tv_ns(&self.ru_utime) + tv_ns(&self.ru_stime) <= cpu_ns() + 10 * MS);
+	assert(getrusage(RUSAGE_THREAD, &thread) == 0);
+	assert(tv_ns(&thread.ru_utime) + tv_ns(&thread.ru_stime) >= cpu_ns() - before - 10 * MS);
+
+	assert(times(&tms) != (clock_t)-1);
+	assert((tms.tms_utime + tms.tms_stime) * (SEC / ticks) >= 190 * MS);
+	assert(tms.tms_cutime == 0 && tms.tms_cstime == 0);
+
+	errno = 0;
+	assert(getrusage(12345, &self) == -1 && errno == EINVAL);
+	puts("self: ok");
+}
+
+static void test_children(void)
+{
+	struct rusage before, after, self;
+	struct tms tms;
+	long ticks = sysconf(_SC_CLK_TCK), self_ns;
+	int status, go[2];
+	pid_t pid;
+	char c;
+
+	assert(pipe(go) == 0);
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		assert(read(go[0], &c, 1) == 1);
+		spin(300 * MS);
+		_exit(0);
+	}
+
+	/* a running child is not accounted to its parent */
+	assert(getrusage(RUSAGE_SELF, &self) == 0);
+	self_ns = tv_ns(&self.ru_utime) + tv_ns(&self.ru_stime);
+	assert(getrusage(RUSAGE_CHILDREN, &before) == 0);
+	assert(tv_ns(&before.ru_utime) + tv_ns(&before.ru_stime) == 0);
+	assert(write(go[1], "x", 1) == 1);
+
+	/* nor one that exited but was not waited for */
+	usleep(100000);
+	assert(getrusage(RUSAGE_CHILDREN, &before) == 0);
+	assert(tv_ns(&before.ru_utime) + tv_ns(&before.ru_stime) == 0);
+
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	assert(getrusage(RUSAGE_CHILDREN, &after) == 0);
+	assert(tv_ns(&after.ru_utime) + tv_ns(&after.ru_stime) >= 290 * MS);
+
+	/* the parent's own time does not include its child's */
+	assert(getrusage(RUSAGE_SELF, &self) == 0);
+	assert(tv_ns(&self.ru_utime) + tv_ns(&self.ru_stime) - self_ns < 100 * MS);
+
+	assert(times(&tms) != (clock_t)-1);
+	assert((tms.tms_cutime + tms.tms_cstime) * (SEC / ticks) >= 280 * MS);
+	puts("children: ok");
+}
+
+static void test_elapsed(void)
+{
+	clock_t start, end;
+	long ticks = sysconf(_SC_CLK_TCK);
+
+	start = times(NULL);
+	usleep(200000);
+	end = times(NULL);
+	assert((end - start) * (SEC / ticks) >= 190 * MS);
+	assert((end - start) * (SEC / ticks) < 10 * SEC);
+	puts("elapsed: ok");
+}
+
+int main(void)
+{
+	test_self();
+	test_children();
+	test_elapsed();
+	return 0;
+}
@@ -0,0 +1,132 @@
+/*
+ * Deterministic: the UTS names of runflags/uts.flags. A cage changing its host or domain
+ * name changes its own names and those of the cages it forks afterwards, never its
+ * parent's. The host's names differ, so the output is compared against expected/.
+ */
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/utsname.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+/* The names uname, gethostname, getdomainname and /proc agree on */
+static void check_names(const char *hostname, const char *domainname)
+{
+	struct utsname uts;
+	char buf[128];
+	int fd;
+	ssize_t n;
+
+	assert(uname(&uts) == 0);
+	assert(strcmp(uts.nodename, hostname) == 0);
+	assert(strcmp(uts.domainname, domainname) == 0);
+
+	assert(gethostname(buf, sizeof(buf)) == 0);
+	assert(strcmp(buf, hostname) == 0);
+	assert(getdomainname(buf, sizeof(buf)) == 0);
+	assert(strcmp(buf, domainname) == 0);
+
+	fd = open("/proc/sys/kernel/hostname", O_RDONLY);
+	assert(fd >= 0);
+	n = read(fd, buf, sizeof(buf) - 1);
+	assert(n == (ssize_t)strlen(hostname) + 1);
+	buf[n] = '\0';
+	assert(strncmp(buf, hostname, n - 1) == 0 && buf[n - 1] == '\n');
+	assert(close(fd) == 0);
+}
+
+static void wait_ok(pid_t pid)
+{
+	int status;
+
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+
+static void test_config(void)
+{
+	struct utsname uts;
+	char small[4];
+
+	assert(uname(&uts) == 0);
+	assert(strcmp(uts.sysname, "Linux") == 0);
+	assert(strcmp(uts.release, "6.1.0-uts") == 0);
+	assert(strcmp(uts.machine, "x86_64") == 0);
+	check_names("lind-uts", "uts.test");
+
+	errno = 0;
+	assert(gethostname(small, sizeof(small)) == -1 && errno == ENAMETOOLONG);
+	puts("config: ok");
+}
+
+static void test_child(void)
+{
+	pid_t pid;
+
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		check_names("lind-uts", "uts.test");
+		assert(sethostname("child", 5) == 0);
+		assert(setdomainname("child.test", 10) == 0);
+		check_names("child", "child.test");
+
+		/* a grandchild starts out with the names its parent set */
+		pid = fork();
+		assert(pid >= 0);
+		if (pid == 0) {
+			check_names("child", "child.test");
+			assert(sethostname("grandchild", 10) == 0);
+			check_names("grandchild", "child.test");
+			_exit(0);
+		}
+		wait_ok(pid);
+		check_names("child", "child.test");
+		_exit(0);
+	}
+	wait_ok(pid);
+
+	/* none of it reached the parent */
+	check_names("lind-uts", "uts.test");
+	puts("child: ok");
+}
+
+static void test_parent(void)
+{
+	char name[66];
+	pid_t pid;
+
+	/* a name is at most HOST_NAME_MAX bytes */
+	memset(name, 'n', sizeof(name));
+	errno = 0;
+	assert(sethostname(name, 65) == -1 && errno == EINVAL);
+	errno = 0;
+	assert(setdomainname(name, 65) == -1 && errno == EINVAL);
+	assert(sethostname(name, 64) == 0);
+	name[64] = '\0';
+	check_names(name, "uts.test");
+
+	/* a change made before forking is inherited */
+	assert(sethostname("parent", 6) == 0);
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		check_names("parent", "uts.test");
+		_exit(0);
+	}
+	wait_ok(pid);
+	puts("parent: ok");
+}
+
+int main(void)
+{
+	test_config();
+	test_child();
+	test_parent();
+	return 0;
+}
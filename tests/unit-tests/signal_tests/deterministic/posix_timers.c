@@ -0,0 +1,305 @@
+/*
+ * Deterministic: POSIX timers and the interval timers, which the timer service of lind serves
+ * for all cages. Timers fire in the order of their deadlines, also when a nearer one is armed
+ * after farther ones, and interval timers are armed again after each expiry. An expiry while
+ * the timer's signal is still pending counts as an overrun. ITIMER_VIRTUAL, ITIMER_PROF and
+ * the timers on a CPU time clock count only the time the process spends running.
+ */
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/time.h>
+#include <time.h>
+#include <unistd.h>
+
+#define MS 1000000L
+
+static volatile sig_atomic_t fired[65];
+static volatile sig_atomic_t order[3];
+static volatile sig_atomic_t norder;
+static volatile int overrun_seen = -1;
+static timer_t overrun_timer;
+
+static void handler(int sig)
+{
+	if (norder < 3)
+		order[norder++] = sig;
+	fired[sig]++;
+}
+
+static void overrun_handler(int sig)
+{
+	if (overrun_seen < 0)
+		overrun_seen = timer_getoverrun(overrun_timer);
+	fired[sig]++;
+}
+
+static void on(int sig, void (*fn)(int))
+{
+	struct sigaction sa = { .sa_handler = fn, .sa_flags = SA_RESTART };
+
+	sigemptyset(&sa.sa_mask);
+	assert(sigaction(sig, &sa, NULL) == 0);
+}
+
+static long elapsed_ns(clockid_t clock, const struct timespec *since)
+{
+	struct timespec now;
+
+	assert(clock_gettime(clock, &now) == 0);
+	return (now.tv_sec - since->tv_sec) * 1000000000L + now.tv_nsec - since->tv_nsec;
+}
+
+static long ts_ns(const struct timespec *ts)
+{
+	return ts->tv_sec * 1000000000L + ts->tv_nsec;
+}
+
+/* Sleep until signal `sig` was handled `count` times, for at most 5 seconds */
+static void wait_fired(int sig, int count)
+{
+	struct timespec start;
+
+	clock_gettime(CLOCK_MONOTONIC, &start);
+	while (fired[sig] < count) {
+		usleep(1000);
+		assert(elapsed_ns(CLOCK_MONOTONIC, &start) < 5000 * MS);
+	}
+}
+
+/* Keep the CPU busy until signal `sig` was handled `count` times, for at most 10 seconds */
+static void spin_fired(int sig, int count)
+{
+	volatile unsigned long spins = 0;
+	struct timespec start;
+
+	clock_gettime(CLOCK_MONOTONIC, &start);
+	while (fired[sig] < count) {
+		if (++spins % 1000000 == 0)
+			assert(elapsed_ns(CLOCK_MONOTONIC, &start) < 10000 * MS);
+	}
+}
+
+static timer_t create(clockid_t clock, int notify, int sig)
+{
+	struct sigevent sev;
+	timer_t timer;
+
+	memset(&sev, 0, sizeof(sev));
+	sev.sigev_notify = notify;
+	sev.sigev_signo = sig;
+	assert(timer_create(clock, &sev, &timer) == 0);
+	return timer;
+}
+
+static void arm(timer_t timer, long value_ns, long interval_ns, int flags)
+{
+	struct itimerspec its = {
+		.it_value = { value_ns / 1000000000L, value_ns % 1000000000L },
+		.it_interval = { interval_ns / 1000000000L, interval_ns % 1000000000L },
+	};
+
+	assert(timer_settime(timer, flags, &its, NULL) == 0);
+}
+
+/* Timers expire in the order of their deadlines, whatever the order they were armed in */
+static void test_service(void)
+{
+	timer_t far = create(CLOCK_MONOTONIC, SIGEV_SIGNAL, SIGUSR1);
+	timer_t middle = create(CLOCK_MONOTONIC, SIGEV_SIGNAL, SIGUSR2);
+	timer_t near = create(CLOCK_MONOTONIC, SIGEV_SIGNAL, SIGALRM);
+
+	arm(far, 600 * MS, 0, 0);
+	arm(middle, 400 * MS, 0, 0);
+	arm(near, 100 * MS, 0, 0);
+	wait_fired(SIGUSR1, 1);
+	assert(norder == 3);
+	assert(order[0] == SIGALRM && order[1] == SIGUSR2 && order[2] == SIGUSR1);
+	assert(fired[SIGALRM] == 1 && fired[SIGUSR2] == 1);
+
+	assert(timer_delete(far) == 0);
+	assert(timer_delete(middle) == 0);
+	assert(timer_delete(near) == 0);
+	memset((void *)fired, 0, sizeof(fired));
+	puts("service: ok");
+}
+
+static void test_settime(void)
+{
+	struct itimerspec its, old;
+	struct timespec now;
+	timer_t timer = create(CLOCK_MONOTONIC, SIGEV_SIGNAL, SIGUSR1);
+
+	/* a new timer is disarmed */
+	assert(timer_gettime(timer, &its) == 0);
+	assert(ts_ns(&its.it_value) == 0 && ts_ns(&its.it_interval) == 0);
+
+	/* the time left counts down from the value it was armed with */
+	arm(timer, 10000 * MS, 2000 * MS, 0);
+	assert(timer_gettime(timer, &its) == 0);
+	assert(ts_ns(&its.it_value) > 9000 * MS && ts_ns(&its.it_value) <= 10000 * MS);
+	assert(ts_ns(&its.it_interval) == 2000 * MS);
+
+	/* setting it again returns the setting it replaces, a zero value disarms it */
+	memset(&its, 0, sizeof(its));
+	assert(timer_settime(timer, 0, &its, &old) == 0);
+	assert(ts_ns(&old.it_value) > 9000 * MS && ts_ns(&old.it_interval) == 2000 * MS);
+	assert(timer_gettime(timer, &its) == 0);
+	assert(ts_ns(&its.it_value) == 0);
+	usleep(50000);
+	assert(fired[SIGUSR1] == 0);
+
+	/* an absolute deadline on the timer's clock */
+	assert(clock_gettime(CLOCK_MONOTONIC, &now) == 0);
+	arm(timer, ts_ns(&now) + 50 * MS, 0, TIMER_ABSTIME);
+	wait_fired(SIGUSR1, 1);
+	assert(elapsed_ns(CLOCK_MONOTONIC, &now) >= 50 * MS);
+	assert(timer_gettime(timer, &its) == 0);
+	assert(ts_ns(&its.it_value) == 0);
+
+	/* a deadline that already passed expires right away */
+	arm(timer, ts_ns(&now), 0, TIMER_ABSTIME);
+	wait_fired(SIGUSR1, 2);
+
+	errno = 0;
+	its.it_value.tv_nsec = 1000000000L;
+	assert(timer_settime(timer, 0, &its, NULL) == -1 && errno == EINVAL);
+	assert(timer_delete(timer) == 0);
+	memset((void *)fired, 0, sizeof(fired));
+	puts("settime: ok");
+}
+
+/* An interval timer is armed again after each expiry */
+static void test_interval(void)
+{
+	struct itimerspec its;
+	struct timespec start;
+	timer_t timer = create(CLOCK_MONOTONIC, SIGEV_SIGNAL, SIGUSR1);
+	timer_t quiet = create(CLOCK_MONOTONIC, SIGEV_NONE, 0);
+
+	assert(clock_gettime(CLOCK_MONOTONIC, &start) == 0);
+	arm(timer, 20 * MS, 20 * MS, 0);
+	wait_fired(SIGUSR1, 5);
+	assert(elapsed_ns(CLOCK_MONOTONIC, &start) >= 100 * MS);
+	assert(timer_gettime(timer, &its) == 0);
+	assert(ts_ns(&its.it_value) > 0 && ts_ns(&its.it_value) <= 20 * MS);
+	assert(ts_ns(&its.it_interval) == 20 * MS);
+	arm(timer, 0, 0, 0);
+
+	/* a timer without notification runs all the same */
+	arm(quiet, 20 * MS, 0, 0);
+	assert(timer_gettime(quiet, &its) == 0 && ts_ns(&its.it_value) > 0);
+	usleep(100000);
+	assert(timer_gettime(quiet, &its) == 0 && ts_ns(&its.it_value) == 0);
+
+	assert(timer_delete(quiet) == 0);
+	assert(timer_delete(timer) == 0);
+	memset((void *)fired, 0, sizeof(fired));
+	puts("interval: ok");
+}
+
+/* Expiries while the signal is blocked are counted as overruns of the one signal raised */
+static void test_overrun(void)
+{
+	struct timespec pause = { 0, 200 * MS };
+	sigset_t usr2, pending;
+
+	sigemptyset(&usr2);
+	sigaddset(&usr2, SIGUSR2);
+	on(SIGUSR2, overrun_handler);
+	assert(sigprocmask(SIG_BLOCK, &usr2, NULL) == 0);
+
+	overrun_timer = create(CLOCK_MONOTONIC, SIGEV_SIGNAL, SIGUSR2);
+	arm(overrun_timer, 10 * MS, 10 * MS, 0);
+	while (nanosleep(&pause, &pause) == -1)
+		assert(errno == EINTR);
+	assert(sigpending(&pending) == 0 && sigismember(&pending, SIGUSR2));
+	/* no signal was delivered yet, so there is no overrun to report */
+	assert(timer_getoverrun(overrun_timer) == 0);
+
+	assert(sigprocmask(SIG_UNBLOCK, &usr2, NULL) == 0);
+	wait_fired(SIGUSR2, 1);
+	assert(overrun_seen >= 5);
+	assert(timer_delete(overrun_timer) == 0);
+
+	errno = 0;
+	assert(timer_getoverrun(overrun_timer) == -1 && errno == EINVAL);
+	memset((void *)fired, 0, sizeof(fired));
+	puts("overrun: ok");
+}
+
+/* The CPU time timers only advance while the process runs */
+static void test_cputime(void)
+{
+	struct itimerval itv = { .it_value = { 0, 100000 } }, old;
+	struct timespec cpu;
+	timer_t timer;
+
+	/* ITIMER_VIRTUAL does not count sleeping */
+	assert(clock_gettime(CLOCK_PROCESS_CPUTIME_ID, &cpu) == 0);
+	assert(setitimer(ITIMER_VIRTUAL, &itv, NULL) == 0);
+	usleep(200000);
+	assert(fired[SIGVTALRM] == 0);
+	assert(getitimer(ITIMER_VIRTUAL, &old) == 0);
+	assert(old.it_value.tv_sec == 0 && old.it_value.tv_usec > 50000);
+	spin_fired(SIGVTALRM, 1);
+	assert(elapsed_ns(CLOCK_PROCESS_CPUTIME_ID, &cpu) >= 100 * MS);
+	assert(getitimer(ITIMER_VIRTUAL, &old) == 0);
+	assert(old.it_value.tv_sec == 0 && old.it_value.tv_usec == 0);
+	puts("itimer virtual: ok");
+
+	/* ITIMER_PROF, re-armed by its interval */
+	itv.it_value.tv_usec = 20000;
+	itv.it_interval.tv_usec = 20000;
+	assert(clock_gettime(CLOCK_PROCESS_CPUTIME_ID, &cpu) == 0);
+	assert(setitimer(ITIMER_PROF, &itv, NULL) == 0);
+	spin_fired(SIGPROF, 3);
+	assert(elapsed_ns(CLOCK_PROCESS_CPUTIME_ID, &cpu) >= 60 * MS);
+	memset(&itv, 0, sizeof(itv));
+	assert(setitimer(ITIMER_PROF, &itv, &old) == 0);
+	assert(old.it_interval.tv_sec == 0 && old.it_interval.tv_usec == 20000);
+	assert(getitimer(ITIMER_PROF, &old) == 0);
+	assert(old.it_value.tv_sec == 0 && old.it_value.tv_usec == 0);
+	puts("itimer prof: ok");
+
+	/* POSIX timers on the CPU time clocks of the process and of the thread */
+	timer = create(CLOCK_PROCESS_CPUTIME_ID, SIGEV_SIGNAL, SIGUSR1);
+	assert(clock_gettime(CLOCK_PROCESS_CPUTIME_ID, &cpu) == 0);
+	arm(timer, 50 * MS, 0, 0);
+	spin_fired(SIGUSR1, 1);
+	assert(elapsed_ns(CLOCK_PROCESS_CPUTIME_ID, &cpu) >= 50 * MS);
+	assert(timer_delete(timer) == 0);
+
+	timer = create(CLOCK_THREAD_CPUTIME_ID, SIGEV_SIGNAL, SIGUSR1);
+	assert(clock_gettime(CLOCK_THREAD_CPUTIME_ID, &cpu) == 0);
+	arm(timer, 50 * MS, 0, 0);
+	spin_fired(SIGUSR1, 2);
+	assert(elapsed_ns(CLOCK_THREAD_CPUTIME_ID, &cpu) >= 50 * MS);
+	assert(timer_delete(timer) == 0);
+	puts("cputime timers: ok");
+}
+
+int main(void)
+{
+	timer_t timer;
+
+	on(SIGUSR1, handler);
+	on(SIGUSR2, handler);
+	on(SIGALRM, handler);
+	on(SIGVTALRM, handler);
+	on(SIGPROF, handler);
+
+	errno = 0;
+	assert(timer_create(12345, NULL, &timer) == -1 && errno == EINVAL);
+
+	test_service();
+	test_settime();
+	test_interval();
+	test_overrun();
+	test_cputime();
+	return 0;
+}
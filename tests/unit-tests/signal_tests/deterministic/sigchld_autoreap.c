@@ -0,0 +1,57 @@
+/* Deterministic: children of a parent ignoring SIGCHLD leave no zombie. */
+
+#include <assert.h>
+#include <errno.h>
+#include <signal.h>
+#include <sys/types.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+static void reap_check(void)
+{
+	pid_t pid = fork();
+	assert(pid >= 0);
+	if (pid == 0)
+		_exit(7);
+
+	/* the child is reaped on exit, there is nothing to wait for */
+	errno = 0;
+	assert(waitpid(pid, NULL, 0) == -1 && errno == ECHILD);
+	errno = 0;
+	assert(wait(NULL) == -1 && errno == ECHILD);
+}
+
+static void chld_handler(int sig)
+{
+	(void)sig;
+}
+
+int main(void)
+{
+	struct sigaction sa = { .sa_handler = SIG_IGN };
+	int status;
+	pid_t pid;
+
+	sigemptyset(&sa.sa_mask);
+	assert(sigaction(SIGCHLD, &sa, NULL) == 0);
+	reap_check();
+
+	/* SA_NOCLDWAIT does the same with a handler installed */
+	sa.sa_handler = chld_handler;
+	sa.sa_flags = SA_NOCLDWAIT;
+	assert(sigaction(SIGCHLD, &sa, NULL) == 0);
+	reap_check();
+
+	/* back to the default, the child becomes a zombie again */
+	sa.sa_handler = SIG_DFL;
+	sa.sa_flags = 0;
+	assert(sigaction(SIGCHLD, &sa, NULL) == 0);
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0)
+		_exit(7);
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 7);
+
+	return 0;
+}
@@ -0,0 +1,150 @@
+/*
+ * Deterministic: signal-driven I/O on a socketpair. With an owner and O_ASYNC, data arriving
+ * on a socket raises SIGIO, or the signal set with F_SETSIG, in the owner, and nothing once
+ * O_ASYNC is cleared. The owner can be another cage sharing the file.
+ */
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <sys/socket.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+static volatile sig_atomic_t sigios, sigusr1s;
+
+static void handler(int sig)
+{
+	if (sig == SIGIO)
+		sigios++;
+	else if (sig == SIGUSR1)
+		sigusr1s++;
+}
+
+/* Wait up to two seconds for `*count` to become positive */
+static int wait_for(volatile sig_atomic_t *count)
+{
+	int i;
+
+	for (i = 0; i < 200 && *count == 0; i++)
+		usleep(10000);
+	return *count > 0;
+}
+
+/*
+ * Let a signal for what was ready already go by: lind signals once when a file starts being
+ * watched, Linux does not.
+ */
+static void settle(void)
+{
+	usleep(100000);
+	sigios = 0;
+	sigusr1s = 0;
+}
+
+static void drain(int fd)
+{
+	char buf[16];
+
+	while (recv(fd, buf, sizeof(buf), MSG_DONTWAIT) > 0)
+		;
+}
+
+static void test_sigio(const int *sv)
+{
+	assert(fcntl(sv[0], F_SETOWN, getpid()) == 0);
+	assert(fcntl(sv[0], F_GETOWN) == getpid());
+	assert(fcntl(sv[0], F_SETFL, fcntl(sv[0], F_GETFL) | O_ASYNC) == 0);
+	assert(fcntl(sv[0], F_GETFL) & O_ASYNC);
+	settle();
+
+	assert(write(sv[1], "a", 1) == 1);
+	assert(wait_for(&sigios));
+	assert(sigusr1s == 0);
+	drain(sv[0]);
+	puts("sigio: ok");
+}
+
+static void test_setsig(const int *sv)
+{
+	assert(fcntl(sv[0], F_GETSIG) == 0);
+	assert(fcntl(sv[0], F_SETSIG, SIGUSR1) == 0);
+	assert(fcntl(sv[0], F_GETSIG) == SIGUSR1);
+	settle();
+
+	assert(write(sv[1], "b", 1) == 1);
+	assert(wait_for(&sigusr1s));
+	assert(sigios == 0);
+	drain(sv[0]);
+
+	assert(fcntl(sv[0], F_SETSIG, 0) == 0);
+	puts("setsig: ok");
+}
+
+static void test_off(const int *sv)
+{
+	assert(fcntl(sv[0], F_SETFL, fcntl(sv[0], F_GETFL) & ~O_ASYNC) == 0);
+	assert(!(fcntl(sv[0], F_GETFL) & O_ASYNC));
+	settle();
+
+	assert(write(sv[1], "c", 1) == 1);
+	usleep(200000);
+	assert(sigios == 0 && sigusr1s == 0);
+	drain(sv[0]);
+	puts("off: ok");
+}
+
+static void test_other_owner(const int *sv)
+{
+	int to_child[2], to_parent[2], status;
+	pid_t pid;
+	char c;
+
+	assert(pipe(to_child) == 0 && pipe(to_parent) == 0);
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		/* the parent makes this cage the owner; the handler came along with fork */
+		assert(read(to_child[0], &c, 1) == 1);
+		settle();
+		assert(write(to_parent[1], "w", 1) == 1);
+		_exit(wait_for(&sigios) ? 0 : 1);
+	}
+
+	assert(fcntl(sv[0], F_SETOWN, pid) == 0);
+	assert(fcntl(sv[0], F_SETFL, fcntl(sv[0], F_GETFL) | O_ASYNC) == 0);
+	assert(write(to_child[1], "o", 1) == 1);
+	assert(read(to_parent[0], &c, 1) == 1 && c == 'w');
+	settle();
+	assert(write(sv[1], "d", 1) == 1);
+
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	/* the parent was not the owner */
+	assert(sigios == 0);
+	drain(sv[0]);
+	assert(close(to_child[0]) == 0 && close(to_child[1]) == 0);
+	assert(close(to_parent[0]) == 0 && close(to_parent[1]) == 0);
+	puts("other owner: ok");
+}
+
+int main(void)
+{
+	struct sigaction sa = { .sa_handler = handler };
+	int sv[2];
+
+	sigemptyset(&sa.sa_mask);
+	assert(sigaction(SIGIO, &sa, NULL) == 0);
+	assert(sigaction(SIGUSR1, &sa, NULL) == 0);
+	assert(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+
+	test_sigio(sv);
+	test_setsig(sv);
+	test_off(sv);
+	test_other_owner(sv);
+
+	assert(close(sv[0]) == 0 && close(sv[1]) == 0);
+	return 0;
+}
@@ -0,0 +1,108 @@
+/* Deterministic: SA_ONSTACK handlers run on the alternate signal stack, and leave it. */
+
+#include <assert.h>
+#include <errno.h>
+#include <setjmp.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <unistd.h>
+
+#define ALTSTACK_SIZE 65536
+
+static char *altstack;
+static sigjmp_buf env;
+static volatile sig_atomic_t handled;
+
+static int on_altstack(const void *p)
+{
+	const char *c = p;
+
+	return c >= altstack && c < altstack + ALTSTACK_SIZE;
+}
+
+static void handler(int sig)
+{
+	char local = 0;
+	stack_t cur, other = { .ss_sp = altstack, .ss_size = ALTSTACK_SIZE };
+
+	assert(sig == SIGUSR1);
+	assert(on_altstack(&local));
+
+	/* the stack in use is reported, and cannot be changed */
+	assert(sigaltstack(NULL, &cur) == 0);
+	assert(cur.ss_flags == SS_ONSTACK);
+	errno = 0;
+	assert(sigaltstack(&other, NULL) == -1 && errno == EPERM);
+
+	handled++;
+	if (handled == 2)
+		siglongjmp(env, 1);
+}
+
+static void deliver(void)
+{
+	sigset_t wait_mask;
+
+	assert(kill(getpid(), SIGUSR1) == 0);
+	sigemptyset(&wait_mask);
+	sigsuspend(&wait_mask);
+}
+
+int main(void)
+{
+	struct sigaction sa = { .sa_handler = handler, .sa_flags = SA_ONSTACK };
+	sigset_t block_mask;
+	stack_t ss, old;
+	char local = 0;
+
+	/* no alternate stack to begin with */
+	assert(sigaltstack(NULL, &old) == 0);
+	assert(old.ss_flags == SS_DISABLE);
+
+	altstack = malloc(ALTSTACK_SIZE);
+	assert(altstack != NULL);
+
+	/* bad flags and a stack that is too small are refused */
+	ss = (stack_t){ .ss_sp = altstack, .ss_size = ALTSTACK_SIZE, .ss_flags = 0x100 };
+	errno = 0;
+	assert(sigaltstack(&ss, NULL) == -1 && errno == EINVAL);
+	ss = (stack_t){ .ss_sp = altstack, .ss_size = 1024 };
+	errno = 0;
+	assert(sigaltstack(&ss, NULL) == -1 && errno == ENOMEM);
+
+	ss = (stack_t){ .ss_sp = altstack, .ss_size = ALTSTACK_SIZE };
+	assert(sigaltstack(&ss, &old) == 0);
+	assert(old.ss_flags == SS_DISABLE);
+	assert(sigaltstack(NULL, &old) == 0);
+	assert(old.ss_sp == altstack && old.ss_size == ALTSTACK_SIZE && old.ss_flags == 0);
+
+	sigemptyset(&sa.sa_mask);
+	assert(sigaction(SIGUSR1, &sa, NULL) == 0);
+	sigemptyset(&block_mask);
+	sigaddset(&block_mask, SIGUSR1);
+	assert(sigprocmask(SIG_BLOCK, &block_mask, NULL) == 0);
+
+	/* the handler returns: back on the main stack */
+	deliver();
+	assert(handled == 1);
+	assert(!on_altstack(&local));
+	assert(sigaltstack(NULL, &old) == 0 && old.ss_flags == 0);
+
+	/* the handler jumps out: off the alternate stack as well */
+	if (sigsetjmp(env, 1) == 0) {
+		deliver();
+		assert(0);
+	}
+	assert(handled == 2);
+	assert(sigaltstack(NULL, &old) == 0 && old.ss_flags == 0);
+
+	/* once disabled, there is no alternate stack anymore */
+	ss.ss_flags = SS_DISABLE;
+	assert(sigaltstack(&ss, NULL) == 0);
+	assert(sigaltstack(NULL, &old) == 0 && old.ss_flags == SS_DISABLE);
+
+	free(altstack);
+	puts("signal_altstack: ok");
+	return 0;
+}
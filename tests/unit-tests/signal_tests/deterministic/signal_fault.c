@@ -0,0 +1,51 @@
+/* Deterministic: faults raise synchronous signals, handled or fatal. */
+
+#include <assert.h>
+#include <signal.h>
+#include <stdint.h>
+#include <sys/types.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define BAD_ADDR ((uintptr_t)0xfffff000u)
+
+static void segv_handler(int sig, siginfo_t *info, void *ucontext)
+{
+	(void)ucontext;
+	if (sig == SIGSEGV && info->si_signo == SIGSEGV &&
+	    info->si_code == SEGV_MAPERR &&
+	    (uintptr_t)info->si_addr == BAD_ADDR)
+		_exit(0);
+	_exit(1);
+}
+
+int main(void)
+{
+	pid_t pid;
+	int status;
+
+	/* a handler installed with SA_SIGINFO sees the faulting address */
+	pid = fork();
+	if (pid == 0) {
+		struct sigaction sa = { .sa_sigaction = segv_handler };
+		sigemptyset(&sa.sa_mask);
+		sa.sa_flags = SA_SIGINFO;
+		assert(sigaction(SIGSEGV, &sa, NULL) == 0);
+
+		(void)*(volatile int *)BAD_ADDR;
+		_exit(2);
+	}
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	/* with no handler the fault terminates the child */
+	pid = fork();
+	if (pid == 0) {
+		volatile int zero = 0;
+		_exit(1 / zero);
+	}
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFSIGNALED(status) && WTERMSIG(status) == SIGFPE);
+
+	return 0;
+}
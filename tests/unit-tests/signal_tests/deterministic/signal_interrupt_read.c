@@ -0,0 +1,59 @@
+/* Deterministic: a signal ends a read blocked on a pipe, SA_RESTART makes it again. */
+
+#include <assert.h>
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <sys/time.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+static volatile sig_atomic_t alarms;
+
+static void handler(int sig)
+{
+	if (sig == SIGALRM)
+		alarms++;
+}
+
+static void arm(int flags)
+{
+	struct sigaction sa = { .sa_handler = handler, .sa_flags = flags };
+	struct itimerval it = { .it_value = { .tv_usec = 100000 } };
+
+	sigemptyset(&sa.sa_mask);
+	assert(sigaction(SIGALRM, &sa, NULL) == 0);
+	assert(setitimer(ITIMER_REAL, &it, NULL) == 0);
+}
+
+int main(void)
+{
+	int fds[2], status;
+	char c;
+
+	assert(pipe(fds) == 0);
+
+	/* nobody writes: the handler runs and the read fails */
+	arm(0);
+	errno = 0;
+	assert(read(fds[0], &c, 1) == -1 && errno == EINTR);
+	assert(alarms == 1);
+
+	/* the read goes on after the handler and gets what comes later */
+	pid_t pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		usleep(400000);
+		assert(write(fds[1], "y", 1) == 1);
+		_exit(0);
+	}
+	arm(SA_RESTART);
+	assert(read(fds[0], &c, 1) == 1 && c == 'y');
+	assert(alarms == 2);
+
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	puts("signal_interrupt_read: ok");
+	return 0;
+}
@@ -0,0 +1,159 @@
+/*
+ * Deterministic: ppoll, pselect and epoll_pwait wait with the signal mask they are given
+ * and put the caller's back afterwards. A signal the mask unblocks, whether pending when
+ * the call starts or sent while it waits, runs its handler under that mask and fails the
+ * call with EINTR. One the mask keeps blocked stays pending.
+ */
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <poll.h>
+#include <signal.h>
+#include <stdio.h>
+#include <sys/epoll.h>
+#include <sys/select.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+enum call { PPOLL, PSELECT, EPOLL_PWAIT };
+
+static const char *const names[] = { "ppoll", "pselect", "epoll_pwait" };
+
+static volatile sig_atomic_t handled;
+/* whether SIGUSR2 was blocked while the handler ran */
+static volatile sig_atomic_t usr2_blocked;
+
+static void handler(int sig)
+{
+	sigset_t cur;
+
+	assert(sig == SIGUSR1);
+	assert(sigprocmask(SIG_BLOCK, NULL, &cur) == 0);
+	usr2_blocked = sigismember(&cur, SIGUSR2);
+	handled++;
+}
+
+/*
+ * Wait with `mask` for the read end of `fds` to become readable, for `ms` milliseconds or
+ * forever if negative.
+ */
+static int wait_on(enum call call, const int *fds, int epfd, const sigset_t *mask, int ms)
+{
+	struct timespec ts = { .tv_sec = ms / 1000, .tv_nsec = (ms % 1000) * 1000000L };
+	struct pollfd pfd = { .fd = fds[0], .events = POLLIN };
+	struct epoll_event ev;
+	fd_set rfds;
+
+	switch (call) {
+	case PPOLL:
+		return ppoll(&pfd, 1, ms < 0 ? NULL : &ts, mask);
+	case PSELECT:
+		FD_ZERO(&rfds);
+		FD_SET(fds[0], &rfds);
+		return pselect(fds[0] + 1, &rfds, NULL, NULL, ms < 0 ? NULL : &ts, mask);
+	case EPOLL_PWAIT:
+		return epoll_pwait(epfd, &ev, 1, ms, mask);
+	}
+	return -1;
+}
+
+static void check_blocked(int blocked_usr1, int blocked_usr2)
+{
+	sigset_t cur;
+
+	assert(sigprocmask(SIG_BLOCK, NULL, &cur) == 0);
+	assert(sigismember(&cur, SIGUSR1) == blocked_usr1);
+	assert(sigismember(&cur, SIGUSR2) == blocked_usr2);
+}
+
+static void test(enum call call, const int *fds, int epfd)
+{
+	sigset_t blocked, open, pending;
+	int status;
+	pid_t pid;
+
+	sigemptyset(&blocked);
+	sigaddset(&blocked, SIGUSR1);
+	sigaddset(&blocked, SIGUSR2);
+	assert(sigprocmask(SIG_SETMASK, &blocked, NULL) == 0);
+	sigemptyset(&open);
+
+	/* pending before the call: delivered as soon as the mask lets it through */
+	handled = 0;
+	assert(raise(SIGUSR1) == 0);
+	assert(handled == 0);
+	errno = 0;
+	assert(wait_on(call, fds, epfd, &open, 5000) == -1 && errno == EINTR);
+	assert(handled == 1);
+	/* the handler ran with the mask of the call, and the caller's is back */
+	assert(!usr2_blocked);
+	check_blocked(1, 1);
+
+	/* a mask that keeps it blocked: the call times out and the signal stays pending */
+	assert(raise(SIGUSR1) == 0);
+	assert(wait_on(call, fds, epfd, &blocked, 20) == 0);
+	assert(handled == 1);
+	assert(sigpending(&pending) == 0 && sigismember(&pending, SIGUSR1));
+	check_blocked(1, 1);
+	errno = 0;
+	assert(wait_on(call, fds, epfd, &open, 5000) == -1 && errno == EINTR);
+	assert(handled == 2);
+	assert(sigpending(&pending) == 0 && !sigismember(&pending, SIGUSR1));
+
+	/* sent by another cage while the call waits with no timeout */
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		usleep(100000);
+		assert(kill(getppid(), SIGUSR1) == 0);
+		_exit(0);
+	}
+	errno = 0;
+	assert(wait_on(call, fds, epfd, &open, -1) == -1 && errno == EINTR);
+	assert(handled == 3);
+	check_blocked(1, 1);
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	/* a ready fd is reported as usual, under either mask */
+	assert(write(fds[1], "r", 1) == 1);
+	assert(wait_on(call, fds, epfd, &blocked, 1000) == 1);
+	assert(wait_on(call, fds, epfd, NULL, 1000) == 1);
+	assert(read(fds[0], &(char){ 0 }, 1) == 1);
+	check_blocked(1, 1);
+
+	assert(sigprocmask(SIG_SETMASK, &open, NULL) == 0);
+	printf("%s: ok\n", names[call]);
+}
+
+int main(void)
+{
+	struct sigaction sa = { .sa_handler = handler };
+	struct epoll_event ev = { .events = EPOLLIN };
+	struct pollfd pfd;
+	struct timespec ts = { .tv_nsec = 1000000000L };
+	int fds[2], epfd;
+
+	sigemptyset(&sa.sa_mask);
+	assert(sigaction(SIGUSR1, &sa, NULL) == 0);
+	assert(pipe(fds) == 0);
+	epfd = epoll_create1(0);
+	assert(epfd >= 0);
+	ev.data.fd = fds[0];
+	assert(epoll_ctl(epfd, EPOLL_CTL_ADD, fds[0], &ev) == 0);
+
+	test(PPOLL, fds, epfd);
+	test(PSELECT, fds, epfd);
+	test(EPOLL_PWAIT, fds, epfd);
+
+	/* the nanoseconds of a timeout have to be below a second */
+	pfd.fd = fds[0];
+	pfd.events = POLLIN;
+	errno = 0;
+	assert(ppoll(&pfd, 1, &ts, NULL) == -1 && errno == EINVAL);
+
+	assert(close(epfd) == 0);
+	assert(close(fds[0]) == 0 && close(fds[1]) == 0);
+	return 0;
+}
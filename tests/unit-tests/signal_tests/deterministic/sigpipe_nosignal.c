@@ -0,0 +1,71 @@
+/* Deterministic: EPIPE raises SIGPIPE unless MSG_NOSIGNAL or SO_LIND_NOSIGPIPE says otherwise. */
+
+#include <assert.h>
+#include <errno.h>
+#include <signal.h>
+#include <sys/socket.h>
+#include <unistd.h>
+#ifdef __wasm__
+#include <lind_syscall.h>
+#endif
+
+static volatile sig_atomic_t pipe_signals;
+
+static void handler(int sig)
+{
+	if (sig == SIGPIPE)
+		pipe_signals++;
+}
+
+/* give a pending signal the chance to be handled */
+static void settle(void)
+{
+	for (volatile int i = 0; i < 100000; i++)
+		;
+}
+
+int main(void)
+{
+	struct sigaction sa = { .sa_handler = handler };
+	int sv[2];
+
+	sigemptyset(&sa.sa_mask);
+	assert(sigaction(SIGPIPE, &sa, NULL) == 0);
+
+	assert(socketpair(AF_UNIX, SOCK_STREAM, 0, sv) == 0);
+	assert(close(sv[1]) == 0);
+
+	/* MSG_NOSIGNAL: EPIPE only */
+	errno = 0;
+	assert(send(sv[0], "x", 1, MSG_NOSIGNAL) == -1 && errno == EPIPE);
+	settle();
+	assert(pipe_signals == 0);
+
+	/* without it the sender gets SIGPIPE */
+	errno = 0;
+	assert(send(sv[0], "x", 1, 0) == -1 && errno == EPIPE);
+	while (pipe_signals == 0)
+		;
+	assert(pipe_signals == 1);
+
+#ifdef __wasm__
+	int on = 1, val = -1;
+	socklen_t len = sizeof(val);
+
+	/* SO_LIND_NOSIGPIPE (lind only) silences every write to the socket */
+	assert(getsockopt(sv[0], SOL_SOCKET, SO_LIND_NOSIGPIPE, &val, &len) == 0);
+	assert(val == 0 && len == sizeof(val));
+	assert(setsockopt(sv[0], SOL_SOCKET, SO_LIND_NOSIGPIPE, &on, sizeof(on)) == 0);
+	assert(getsockopt(sv[0], SOL_SOCKET, SO_LIND_NOSIGPIPE, &val, &len) == 0);
+	assert(val == 1);
+	errno = 0;
+	assert(write(sv[0], "x", 1) == -1 && errno == EPIPE);
+	errno = 0;
+	assert(send(sv[0], "x", 1, 0) == -1 && errno == EPIPE);
+	settle();
+	assert(pipe_signals == 1);
+#endif
+
+	assert(close(sv[0]) == 0);
+	return 0;
+}
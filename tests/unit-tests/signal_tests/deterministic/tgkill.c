@@ -0,0 +1,133 @@
+/*
+ * Deterministic: thread-directed signals with tgkill and pthread_kill, and the signal mask of
+ * each thread. A thread starts with the mask of the thread that created it, a signal sent to a
+ * thread runs its handler on that thread, and one it blocks stays pending for that thread alone
+ * until it unblocks it. A signal sent to the process goes to a thread that does not block it.
+ */
+
+#define _GNU_SOURCE
+#include <assert.h>
+#include <errno.h>
+#include <pthread.h>
+#include <sched.h>
+#include <signal.h>
+#include <stdio.h>
+#include <unistd.h>
+
+static volatile sig_atomic_t handled;
+static volatile pid_t handled_by;
+static volatile pid_t worker_tid;
+static pthread_barrier_t barrier;
+
+static void handler(int sig)
+{
+	(void)sig;
+	handled_by = gettid();
+	handled++;
+}
+
+/* Wait for one signal to be handled, and return the thread that handled it */
+static pid_t wait_handled(void)
+{
+	pid_t tid;
+
+	while (handled == 0)
+		sched_yield();
+	assert(handled == 1);
+	tid = handled_by;
+	handled = 0;
+	return tid;
+}
+
+static sigset_t signal_set(int signo)
+{
+	sigset_t set;
+
+	sigemptyset(&set);
+	sigaddset(&set, signo);
+	return set;
+}
+
+static void *worker(void *arg)
+{
+	sigset_t usr1 = signal_set(SIGUSR1), usr2 = signal_set(SIGUSR2), set;
+
+	(void)arg;
+	worker_tid = gettid();
+
+	/* the mask of the creating thread, which the worker then changes for itself */
+	assert(pthread_sigmask(SIG_SETMASK, NULL, &set) == 0);
+	assert(sigismember(&set, SIGUSR1) && !sigismember(&set, SIGUSR2));
+	assert(pthread_sigmask(SIG_UNBLOCK, &usr1, NULL) == 0);
+	assert(pthread_sigmask(SIG_BLOCK, &usr2, NULL) == 0);
+	pthread_barrier_wait(&barrier);
+
+	/* SIGUSR2 waits for the worker, SIGUSR1 pending for the main thread is not its own */
+	pthread_barrier_wait(&barrier);
+	assert(sigpending(&set) == 0);
+	assert(sigismember(&set, SIGUSR2) && !sigismember(&set, SIGUSR1));
+	assert(handled == 0);
+	assert(pthread_sigmask(SIG_UNBLOCK, &usr2, NULL) == 0);
+	assert(wait_handled() == gettid());
+	assert(sigpending(&set) == 0 && !sigismember(&set, SIGUSR2));
+	pthread_barrier_wait(&barrier);
+	return NULL;
+}
+
+int main(void)
+{
+	struct sigaction sa = { .sa_handler = handler, .sa_flags = SA_RESTART };
+	sigset_t usr1 = signal_set(SIGUSR1), set;
+	pid_t pid = getpid(), main_tid = gettid(), tid;
+	pthread_t thread;
+
+	sigemptyset(&sa.sa_mask);
+	assert(sigaction(SIGUSR1, &sa, NULL) == 0);
+	assert(sigaction(SIGUSR2, &sa, NULL) == 0);
+	assert(pthread_sigmask(SIG_BLOCK, &usr1, NULL) == 0);
+
+	pthread_barrier_init(&barrier, NULL, 2);
+	assert(pthread_create(&thread, NULL, worker, NULL) == 0);
+	pthread_barrier_wait(&barrier);
+	tid = worker_tid;
+	assert(tid != main_tid);
+
+	/* the handler runs on the thread the signal was sent to */
+	assert(tgkill(pid, tid, SIGUSR1) == 0);
+	assert(wait_handled() == tid);
+	assert(pthread_kill(thread, SIGUSR1) == 0);
+	assert(wait_handled() == tid);
+	assert(tgkill(pid, tid, 0) == 0);
+	errno = 0;
+	assert(tgkill(pid, tid, 65) == -1 && errno == EINVAL);
+	puts("tgkill: ok");
+
+	/* the main thread blocks SIGUSR1, the worker takes it */
+	assert(kill(pid, SIGUSR1) == 0);
+	assert(wait_handled() == tid);
+	puts("process: ok");
+
+	/* each blocked signal stays pending for the thread it was sent to */
+	assert(tgkill(pid, tid, SIGUSR2) == 0);
+	assert(pthread_kill(pthread_self(), SIGUSR1) == 0);
+	assert(sigpending(&set) == 0);
+	assert(sigismember(&set, SIGUSR1) && !sigismember(&set, SIGUSR2));
+	usleep(100000);
+	assert(handled == 0);
+	pthread_barrier_wait(&barrier);
+
+	/* once the worker handled its own, the main thread unblocks and handles SIGUSR1 */
+	pthread_barrier_wait(&barrier);
+	assert(handled == 0);
+	assert(pthread_sigmask(SIG_UNBLOCK, &usr1, NULL) == 0);
+	assert(wait_handled() == main_tid);
+	assert(sigpending(&set) == 0 && !sigismember(&set, SIGUSR1));
+	puts("pending: ok");
+
+	/* a thread that is gone can not be signalled */
+	assert(pthread_join(thread, NULL) == 0);
+	errno = 0;
+	assert(tgkill(pid, tid, 0) == -1 && errno == ESRCH);
+	pthread_barrier_destroy(&barrier);
+	return 0;
+}